@@ -0,0 +1,48 @@
+//! Moves per second of every bot difficulty over the same random positions, and perft from the
+//! empty boards.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use tictactoe::{
+    benchmark::{perft, random_positions},
+    board::SquareBoard,
+    grid::Grid,
+    player::{BotPlayer, BotPlayerDifficulty, Player},
+};
+
+fn bots(c: &mut Criterion) {
+    let positions = random_positions(100, 0);
+    let mut group = c.benchmark_group("bots");
+    group.throughput(Throughput::Elements(positions.len() as u64));
+    for (name, difficulty) in [
+        ("easy", BotPlayerDifficulty::Easy),
+        ("normal", BotPlayerDifficulty::Normal),
+        ("impossible", BotPlayerDifficulty::Impossible),
+        ("strength 70", BotPlayerDifficulty::Strength(70)),
+    ] {
+        let bot = BotPlayer::from_difficulty(difficulty).with_seed(0);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for (grid, mark) in &positions {
+                    black_box(bot.get_move(grid, mark).unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn perfts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    group.bench_function("grid", |b| b.iter(|| perft(black_box(&Grid::default()), 9)));
+    group.bench_function("square board 3x3", |b| {
+        b.iter(|| perft(black_box(&SquareBoard::new(3, 3)), 9))
+    });
+    group.bench_function("square board 4x4, depth 4", |b| {
+        b.iter(|| perft(black_box(&SquareBoard::new(4, 3)), 4))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bots, perfts);
+criterion_main!(benches);
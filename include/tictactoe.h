@@ -0,0 +1,144 @@
+#ifndef TICTACTOE_H
+#define TICTACTOE_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of the functions playing or choosing a move.
+ */
+typedef enum TttStatus {
+  TTT_STATUS_OK = 0,
+  /**
+   * The game pointer, or an output pointer, is null.
+   */
+  TTT_STATUS_NULL_GAME,
+  /**
+   * The game already has a result.
+   */
+  TTT_STATUS_GAME_OVER,
+  TTT_STATUS_CELL_IN_USE,
+  TTT_STATUS_OUT_OF_BOUNDS,
+  /**
+   * The difficulty isn't one of the `TttDifficulty` values.
+   */
+  TTT_STATUS_UNKNOWN_DIFFICULTY,
+  /**
+   * The bot didn't choose a move to place.
+   */
+  TTT_STATUS_NO_MOVE,
+} TttStatus;
+
+/**
+ * Difficulty of the bot of [`ttt_bot_move`], which takes it as a plain integer so that values
+ * unknown to this version of the library are reported instead of being undefined behavior.
+ */
+typedef enum TttDifficulty {
+  TTT_DIFFICULTY_EASY = 0,
+  TTT_DIFFICULTY_NORMAL = 1,
+  TTT_DIFFICULTY_IMPOSSIBLE = 2,
+} TttDifficulty;
+
+/**
+ * Mark on a cell, or `TTT_MARK_NONE` for an empty cell or no winner.
+ */
+typedef enum TttMark {
+  TTT_MARK_NONE = 0,
+  TTT_MARK_X = 1,
+  TTT_MARK_O = 2,
+  TTT_MARK_Y = 3,
+} TttMark;
+
+/**
+ * Local game on a [`Grid`], only used through pointers returned by [`ttt_game_new`]. `X` plays
+ * first, and moves are placed for the mark whose turn it is.
+ */
+typedef struct TttGame TttGame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a game on an empty grid, with `X` to play. It must be freed with [`ttt_game_free`].
+ */
+struct TttGame *ttt_game_new(void);
+
+/**
+ * Frees a game created with [`ttt_game_new`]. Does nothing if `game` is null.
+ *
+ * # Safety
+ *
+ * `game` must be null or returned by [`ttt_game_new`], and not freed already.
+ */
+void ttt_game_free(struct TttGame *game);
+
+/**
+ * Places the mark whose turn it is on the cell, and hands the turn to the other mark. The game is
+ * left unchanged unless `TTT_STATUS_OK` is returned.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game returned by [`ttt_game_new`].
+ */
+enum TttStatus ttt_game_play(struct TttGame *game, uintptr_t row, uintptr_t col);
+
+/**
+ * Writes the move a bot of the given difficulty, one of the `TttDifficulty` values, would play
+ * for the mark whose turn it is to `row` and `col`, without playing it.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game returned by [`ttt_game_new`], and `row` and `col` null or
+ * valid for writes.
+ */
+enum TttStatus ttt_bot_move(const struct TttGame *game,
+                            uint32_t difficulty,
+                            uintptr_t *row,
+                            uintptr_t *col);
+
+/**
+ * Returns the mark on the cell, `TTT_MARK_NONE` if it is empty or out of bounds.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game returned by [`ttt_game_new`].
+ */
+enum TttMark ttt_game_cell(const struct TttGame *game, uintptr_t row, uintptr_t col);
+
+/**
+ * Returns the mark playing the next move.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game returned by [`ttt_game_new`].
+ */
+enum TttMark ttt_game_current_mark(const struct TttGame *game);
+
+/**
+ * Returns whether the game has a result, won or drawn.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game returned by [`ttt_game_new`].
+ */
+bool ttt_game_is_over(const struct TttGame *game);
+
+/**
+ * Returns the winning mark, `TTT_MARK_NONE` if the game is a draw or still in progress.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game returned by [`ttt_game_new`].
+ */
+enum TttMark ttt_game_winner(const struct TttGame *game);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TICTACTOE_H */
@@ -0,0 +1,273 @@
+//! Statistics on how players play, gathered over many simulated games: how they open, how long
+//! their games last, and how often they create forks or blunder. See [`Analysis`].
+
+use std::fmt::Display;
+
+use crate::{
+    game::{Finished, Game, GameResult, PlayedMove},
+    grid::{Grid, Mark},
+    hint::{creates_fork, review_move},
+    player::Move,
+    record::format_move,
+    tournament::{Score, Tournament, TournamentError},
+};
+
+/// How a player played over a set of games, see [`PlayerStats::add_game`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub score: Score,
+    /// Marks placed by the player
+    pub moves: usize,
+    /// Marks placed by both players, over every game
+    pub game_moves: usize,
+    /// How many times the player opened on each cell, `first_moves[row][col]`, in the games they
+    /// moved first
+    pub first_moves: [[usize; 3]; 3],
+    /// Moves leaving the player 2 ways to win at once, without winning yet
+    pub forks: usize,
+    /// Moves leading to a worse outcome than the best move would, see [`review_move`]
+    pub blunders: usize,
+}
+
+impl PlayerStats {
+    /// Adds a finished game, in which the player played `mark`.
+    pub fn add_game(&mut self, history: &[PlayedMove], result: GameResult, mark: Mark) {
+        self.score.add(result, mark);
+
+        let mut grid = Grid::default();
+        for played in history {
+            let Move::Place(row, col) = played.player_move else {
+                continue;
+            };
+            if played.mark == mark {
+                if grid.cell_count() == 0 {
+                    self.first_moves[row][col] += 1;
+                }
+                self.moves += 1;
+                if review_move(&grid, &mark, (row, col)).is_some() {
+                    self.blunders += 1;
+                }
+                // Every cell is a way to win after a winning move, which isn't a fork
+                let mut next = grid;
+                next.set_cell(row, col, mark);
+                if next.get_winning_mark().is_none() && creates_fork(&grid, (row, col), &mark) {
+                    self.forks += 1;
+                }
+            }
+            grid.set_cell(row, col, played.mark);
+            self.game_moves += 1;
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.score.games()
+    }
+
+    /// Returns the average number of marks placed in the player's games.
+    pub fn average_length(&self) -> f64 {
+        ratio(self.game_moves, self.games())
+    }
+
+    /// Returns the average number of forks created by the player per game.
+    pub fn fork_rate(&self) -> f64 {
+        ratio(self.forks, self.games())
+    }
+
+    /// Returns the share of the player's moves that were blunders, between 0 and 1.
+    pub fn blunder_rate(&self) -> f64 {
+        ratio(self.blunders, self.moves)
+    }
+
+    /// Returns the cell the player opened on most often, with the share of their openings played
+    /// there. Ties go to the first cell in reading order.
+    pub fn favourite_first_move(&self) -> Option<((usize, usize), f64)> {
+        let openings: usize = self.first_moves.iter().flatten().sum();
+        (0..3)
+            .flat_map(|row| (0..3).map(move |col| (row, col)))
+            .rev()
+            .max_by_key(|&(row, col)| self.first_moves[row][col])
+            .filter(|_| openings > 0)
+            .map(|(row, col)| {
+                let share = ratio(self.first_moves[row][col], openings);
+                ((row, col), share)
+            })
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// Statistics of every entrant of a [`Tournament`], gathered while it is played. Its `Display`
+/// implementation writes them as a table.
+///
+/// ```
+/// # use tictactoe::{analytics::Analysis, player::BotPlayer, tournament::{Tournament, TournamentFormat}};
+/// let tournament = Tournament::new(TournamentFormat::RoundRobin { games: 4 })
+///     .with_player("easy", || Box::new(BotPlayer::easy()))
+///     .with_player("impossible", || Box::new(BotPlayer::impossible()));
+/// let analysis = Analysis::run(&tournament).unwrap();
+/// assert_eq!(analysis.stats("impossible").unwrap().blunders, 0);
+/// println!("{}", analysis);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    players: Vec<(String, PlayerStats)>,
+}
+
+impl Analysis {
+    /// Creates empty statistics for the entrants of `tournament`, to be filled with
+    /// [`Analysis::add_game`] from [`Tournament::run_with`]. Entrants sharing a name share their
+    /// statistics.
+    pub fn for_tournament(tournament: &Tournament) -> Self {
+        let mut analysis = Self { players: vec![] };
+        for name in tournament.names() {
+            if analysis.stats(name).is_none() {
+                analysis
+                    .players
+                    .push((name.to_owned(), PlayerStats::default()));
+            }
+        }
+        analysis
+    }
+
+    /// Plays `tournament`, gathering the statistics of its entrants.
+    pub fn run(tournament: &Tournament) -> Result<Self, TournamentError> {
+        let mut analysis = Self::for_tournament(tournament);
+        tournament.run_with(|x, o, game| analysis.add_game(x, o, game))?;
+        Ok(analysis)
+    }
+
+    /// Adds a game between the entrants called `player_x` and `player_o`. Players who aren't
+    /// entrants are ignored.
+    pub fn add_game(&mut self, player_x: &str, player_o: &str, game: &Game<Finished>) {
+        for (name, mark) in [(player_x, Mark::X), (player_o, Mark::O)] {
+            if let Some((_, stats)) = self.players.iter_mut().find(|(n, _)| n == name) {
+                stats.add_game(game.history(), game.result(), mark);
+            }
+        }
+    }
+
+    /// Returns the statistics of the entrant called `name`.
+    pub fn stats(&self, name: &str) -> Option<&PlayerStats> {
+        self.players
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, stats)| stats)
+    }
+
+    /// Returns the name and statistics of every entrant, in the order they were added.
+    pub fn players(&self) -> impl Iterator<Item = (&str, &PlayerStats)> {
+        self.players
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .players
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .chain(["Player".len()])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:<width$}  {:>5}  {:>6}  {:>10}  {:>8}  {:>8}",
+            "Player", "Games", "Length", "Forks/game", "Blunders", "Opening"
+        )?;
+        for (name, stats) in &self.players {
+            let opening = match stats.favourite_first_move() {
+                Some(((row, col), share)) => format!(
+                    "{} {:>3.0}%",
+                    format_move(Move::Place(row, col)),
+                    share * 100.0
+                ),
+                None => "-".to_owned(),
+            };
+            writeln!(
+                f,
+                "{:<width$}  {:>5}  {:>6.1}  {:>10.2}  {:>7.1}%  {:>8}",
+                name,
+                stats.games(),
+                stats.average_length(),
+                stats.fork_rate(),
+                stats.blunder_rate() * 100.0,
+                opening
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::WinReason,
+        player::{tests::ResigningPlayer, BotPlayer},
+        tournament::TournamentFormat,
+    };
+
+    use super::*;
+
+    fn place(mark: Mark, row: usize, col: usize) -> PlayedMove {
+        PlayedMove {
+            mark,
+            player_move: Move::Place(row, col),
+        }
+    }
+
+    #[test]
+    fn games_are_reviewed_move_by_move() {
+        // O's edge reply to the corner opening loses: X takes the center, then forks
+        let history = [
+            place(Mark::X, 0, 0),
+            place(Mark::O, 0, 1),
+            place(Mark::X, 1, 1),
+            place(Mark::O, 2, 2),
+            place(Mark::X, 1, 0),
+            place(Mark::O, 2, 0),
+            place(Mark::X, 1, 2),
+        ];
+        let result = GameResult::Win {
+            winner: Mark::X,
+            reason: WinReason::Line,
+        };
+
+        let mut x = PlayerStats::default();
+        x.add_game(&history, result, Mark::X);
+        assert_eq!((x.score.wins, x.moves, x.game_moves), (1, 4, 7));
+        assert_eq!((x.forks, x.blunders), (1, 0));
+        assert_eq!(x.favourite_first_move(), Some(((0, 0), 1.0)));
+
+        let mut o = PlayerStats::default();
+        o.add_game(&history, result, Mark::O);
+        assert_eq!((o.score.losses, o.moves, o.forks, o.blunders), (1, 3, 0, 1));
+        assert_eq!(o.favourite_first_move(), None);
+        assert_eq!(o.blunder_rate(), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn tournament_entrants_are_analyzed() {
+        let tournament = Tournament::new(TournamentFormat::RoundRobin { games: 2 })
+            .with_player("resigns", || Box::new(ResigningPlayer))
+            .with_player("minimax", || Box::new(BotPlayer::impossible()));
+        let analysis = Analysis::run(&tournament).unwrap();
+
+        let resigns = analysis.stats("resigns").unwrap();
+        assert_eq!((resigns.games(), resigns.moves), (2, 0));
+        let minimax = analysis.stats("minimax").unwrap();
+        assert_eq!((minimax.score.wins, minimax.blunders), (2, 0));
+        // Only the game where minimax moved first has a mark on the board
+        assert_eq!(minimax.average_length(), 0.5);
+        assert_eq!(analysis.players().count(), 2);
+        assert!(analysis.to_string().starts_with("Player "));
+    }
+}
@@ -0,0 +1,90 @@
+//! Large rendering of boards, where each mark is a 5x5 ASCII-art glyph, readable from afar on
+//! demo or streaming setups.
+
+use std::fmt::Display;
+
+use crate::{board::Board, grid::Mark};
+
+/// Side of the square glyphs marks are drawn with.
+const GLYPH_SIZE: usize = 5;
+
+const X_GLYPH: [&str; GLYPH_SIZE] = ["X   X", " X X ", "  X  ", " X X ", "X   X"];
+const O_GLYPH: [&str; GLYPH_SIZE] = [" OOO ", "O   O", "O   O", "O   O", " OOO "];
+const Y_GLYPH: [&str; GLYPH_SIZE] = ["Y   Y", " Y Y ", "  Y  ", "  Y  ", "  Y  "];
+
+/// Draws a board with ASCII characters, each cell being a 5x5 glyph of its mark, padded by a space
+/// on both sides. Rows are separated by a line, so each row of cells takes 6 lines.
+///
+/// ```
+/// # use tictactoe::{art::LargeBoard, board::Board, grid::{Grid, Mark}};
+/// let mut grid = Grid::default();
+/// grid.place(1, 1, Mark::X).unwrap();
+/// print!("{}", LargeBoard(&grid));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct LargeBoard<'a, B>(pub &'a B);
+
+impl<B: Board> Display for LargeBoard<'_, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (rows, cols) = self.0.dimensions();
+        // Left separator + each cell's glyph, padding and right separator
+        let separator = format!(
+            "+{}",
+            format!("{}+", "-".repeat(GLYPH_SIZE + 2)).repeat(cols)
+        );
+
+        writeln!(f, "{}", separator)?;
+        for row in 0..rows {
+            for line in 0..GLYPH_SIZE {
+                write!(f, "|")?;
+                for col in 0..cols {
+                    let glyph = match self.0.mark(row, col) {
+                        Some(Mark::X) => X_GLYPH[line],
+                        Some(Mark::O) => O_GLYPH[line],
+                        Some(Mark::Y) => Y_GLYPH[line],
+                        None => "     ",
+                    };
+                    write!(f, " {} |", glyph)?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", separator)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::SquareBoard, grid::Grid};
+
+    use super::*;
+
+    #[test]
+    fn marks_are_drawn_as_glyphs() {
+        let mut grid = Grid::default();
+        grid.place(0, 0, Mark::X).unwrap();
+        grid.place(0, 2, Mark::O).unwrap();
+
+        let drawn = LargeBoard(&grid).to_string();
+        let lines: Vec<&str> = drawn.lines().collect();
+        assert_eq!(lines.len(), 3 * 6 + 1);
+        assert_eq!(lines[0], "+-------+-------+-------+");
+        assert_eq!(lines[1], "| X   X |       |  OOO  |");
+        assert_eq!(lines[3], "|   X   |       | O   O |");
+        assert_eq!(lines[6], lines[0]);
+        assert_eq!(lines[7], "|       |       |       |");
+    }
+
+    #[test]
+    fn any_board_size_is_drawn() {
+        let mut board = SquareBoard::new(5, 4);
+        board.place(4, 4, Mark::Y).unwrap();
+
+        let drawn = LargeBoard(&board).to_string();
+        let lines: Vec<&str> = drawn.lines().collect();
+        assert_eq!(lines.len(), 5 * 6 + 1);
+        assert!(lines.iter().all(|line| line.len() == 5 * 8 + 1));
+        assert_eq!(&lines[4 * 6 + 3][32..], "|   Y   |");
+    }
+}
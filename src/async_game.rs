@@ -0,0 +1,665 @@
+//! Async counterparts of [`RemoteGame`](crate::game::RemoteGame) and
+//! [`ServerGame`](crate::game::ServerGame), built on tokio and speaking the same protocol. Only
+//! available with the `async` feature.
+//!
+//! A single [`AsyncGameServer`] can host any number of games at once, by accepting a new
+//! connection for each game and playing them in separate tasks.
+
+use std::{
+    io,
+    net::SocketAddr,
+    panic,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream, ToSocketAddrs,
+    },
+};
+
+use crate::{
+    game::{
+        self, ChatCallback, ChatHandler, GameResult, GameSnapshot, NetworkedGameError,
+        ServerGameSettings, WinReason,
+    },
+    grid::{Grid, Mark},
+    player::{Move, Player},
+    protocol::{
+        self, ChatMessage, ClientHello, ClientResume, EndOfGame, Heartbeat, InvalidMove, MoveAck,
+        Packet, PlayerMove, Rematch, Resign, ServerHello,
+    },
+};
+
+/// Server accepting clients for new or resumed games.
+#[derive(Debug)]
+pub struct AsyncGameServer {
+    listener: TcpListener,
+}
+
+impl AsyncGameServer {
+    pub async fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr).await?,
+        })
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Waits for a client to start a new game, played with `settings`. Connections not starting
+    /// with a CLIENT_HELLO packet or an introduction accepted by `settings` are dropped.
+    pub async fn accept(&self, settings: &ServerGameSettings) -> io::Result<AsyncNetworkedGame> {
+        let host = GameSnapshot {
+            grid: Grid::default(),
+            is_local_turn: settings.host_plays_first,
+            local_mark: settings.host_mark,
+        };
+        self.accept_matching(host, None, settings).await
+    }
+
+    /// Waits for a client resuming the game saved as `snapshot` (see
+    /// [`AsyncNetworkedGame::resume`]). Other connections are dropped.
+    pub async fn accept_resumed(&self, snapshot: &GameSnapshot) -> io::Result<AsyncNetworkedGame> {
+        let settings = ServerGameSettings::default();
+        self.accept_matching(*snapshot, Some(snapshot), &settings)
+            .await
+    }
+
+    async fn accept_matching(
+        &self,
+        host: GameSnapshot,
+        resumed: Option<&GameSnapshot>,
+        settings: &ServerGameSettings,
+    ) -> io::Result<AsyncNetworkedGame> {
+        loop {
+            let (socket, _) = self.listener.accept().await?;
+            let (mut reader, mut writer) = split(socket);
+
+            // Expect CLIENT_HELLO, or CLIENT_RESUME when resuming
+            let mut buf = vec![];
+            reader.read_until(protocol::TERMINATOR, &mut buf).await?;
+            let Ok(pkt) = game::parse_packet(&buf) else {
+                continue;
+            };
+            if !game::accepts_hello(&pkt, resumed, settings) {
+                continue;
+            }
+            if let Packet::Introduction(intro) = &pkt {
+                writer
+                    .write_all(&settings.introduction(intro).to_bytes())
+                    .await?;
+            }
+
+            let pkt = ServerHello {
+                client_first: !host.is_local_turn,
+                client_mark: host.local_mark.opposite(),
+                token: None,
+            }
+            .to_bytes();
+            writer.write_all(&pkt).await?;
+            writer.flush().await?;
+
+            return Ok(AsyncNetworkedGame::from_snapshot(reader, writer, &host));
+        }
+    }
+}
+
+/// Networked game played over an async connection, either as the client or the host.
+///
+/// Unlike the blocking games, waiting for a move doesn't block the thread, so a client can keep its
+/// UI running in the meantime, and a server can play other games on the same runtime.
+#[derive(Debug)]
+pub struct AsyncNetworkedGame {
+    reader: BufReader<OwnedReadHalf>,
+    writer: BufWriter<OwnedWriteHalf>,
+    chat_handler: ChatHandler,
+    grid: Grid,
+    is_local_turn: bool,
+    local_mark: Mark,
+    move_time_limit: Option<Duration>,
+    result: Option<GameResult>,
+    unacked_move: Option<PlayerMove>,
+}
+
+impl AsyncNetworkedGame {
+    /// Connects to a server to start a new game.
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let (reader, writer, server_hello) =
+            client_handshake(addr, &ClientHello.to_bytes()).await?;
+
+        let snapshot = GameSnapshot {
+            grid: Grid::default(),
+            is_local_turn: server_hello.client_first,
+            local_mark: server_hello.client_mark,
+        };
+        Ok(Self::from_snapshot(reader, writer, &snapshot))
+    }
+
+    /// Connects to a server hosting a resumed game, and continues playing from `snapshot`.
+    pub async fn resume<A: ToSocketAddrs>(addr: A, snapshot: &GameSnapshot) -> io::Result<Self> {
+        let pkt = ClientResume {
+            grid: snapshot.grid,
+            client_turn: snapshot.is_local_turn,
+            client_mark: snapshot.local_mark,
+        }
+        .to_bytes();
+        let (reader, writer, server_hello) = client_handshake(addr, &pkt).await?;
+        game::check_resumed_hello(&server_hello, snapshot)?;
+
+        Ok(Self::from_snapshot(reader, writer, snapshot))
+    }
+
+    fn from_snapshot(
+        reader: BufReader<OwnedReadHalf>,
+        writer: BufWriter<OwnedWriteHalf>,
+        snapshot: &GameSnapshot,
+    ) -> Self {
+        Self {
+            reader,
+            writer,
+            chat_handler: ChatHandler::default(),
+            grid: snapshot.grid,
+            is_local_turn: snapshot.is_local_turn,
+            local_mark: snapshot.local_mark,
+            move_time_limit: None,
+            result: None,
+            unacked_move: None,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn is_local_turn(&self) -> bool {
+        self.is_local_turn
+    }
+
+    pub fn local_mark(&self) -> Mark {
+        self.local_mark
+    }
+
+    /// Returns the current state of the game, which can be used to resume it later.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            grid: self.grid,
+            is_local_turn: self.is_local_turn,
+            local_mark: self.local_mark,
+        }
+    }
+
+    /// Limits how long each player has to choose a move, or removes the limit with `None`. See
+    /// [`NetworkedGame::set_move_time_limit`](crate::game::NetworkedGame::set_move_time_limit).
+    pub fn set_move_time_limit(&mut self, limit: Option<Duration>) {
+        self.move_time_limit = limit;
+    }
+
+    /// Sends a chat message to the remote player. See
+    /// [`NetworkedGame::send_chat`](crate::game::NetworkedGame::send_chat).
+    pub async fn send_chat(&mut self, message: &str) -> io::Result<()> {
+        game::check_chat_len(message)?;
+        self.send(&ChatMessage(message.to_owned()).to_bytes()).await
+    }
+
+    /// Sets the function called with every chat message received from the remote player while
+    /// [`AsyncNetworkedGame::try_move`] waits for their move.
+    pub fn set_chat_handler(&mut self, handler: ChatCallback) {
+        self.chat_handler.set(handler);
+    }
+
+    /// Returns the result of the game once it is over. See
+    /// [`NetworkedGame::result`](crate::game::NetworkedGame::result).
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// Plays the next move: asks `local_player` for their move and sends it on the local turn, or
+    /// waits for the remote player's move otherwise. Either player can resign instead.
+    ///
+    /// Players block while choosing their move, so `local_player` is asked on tokio's blocking
+    /// thread pool, and the remote player is sent heartbeats meanwhile.
+    pub async fn try_move(
+        &mut self,
+        local_player: &Arc<dyn Player>,
+    ) -> Result<(), NetworkedGameError> {
+        if self.result.is_some() {
+            return Err(NetworkedGameError::GameOver);
+        }
+
+        let player_move = if self.is_local_turn {
+            self.local_move(local_player).await?
+        } else {
+            let remote_mark = self.local_mark.opposite();
+            let limit = self.move_time_limit;
+            let read = self.read_remote_packet();
+            let pkt = match limit {
+                Some(limit) => tokio::time::timeout(limit, read)
+                    .await
+                    .map_err(|_| NetworkedGameError::Timeout(remote_mark))??,
+                None => read.await?,
+            };
+            match pkt {
+                Packet::InvalidMove(rejection) => {
+                    return Err(NetworkedGameError::Rejected(rejection.reason))
+                }
+                Packet::EndOfGame(EndOfGame(
+                    result @ GameResult::Win {
+                        reason: WinReason::Timeout,
+                        ..
+                    },
+                )) => {
+                    self.unacked_move = None;
+                    self.result = Some(result);
+                    return Ok(());
+                }
+                Packet::PlayerMove(pkt) => Move::Place(pkt.row, pkt.col),
+                Packet::Resign => Move::Resign,
+                pkt => return Err(game::unexpected_remote_packet(&pkt).into()),
+            }
+        };
+
+        // Same as the blocking games, the grid is only updated once the move is sent
+        let mark = if self.is_local_turn {
+            self.local_mark
+        } else {
+            self.local_mark.opposite()
+        };
+        let (row, col) = match player_move {
+            Move::Place(row, col) => (row, col),
+            Move::Resign => {
+                if self.is_local_turn {
+                    self.send(&Resign.to_bytes()).await?;
+                }
+                self.result = Some(GameResult::Win {
+                    winner: mark.opposite(),
+                    reason: WinReason::Resignation,
+                });
+                return Ok(());
+            }
+            m => return Err(NetworkedGameError::UnsupportedMove(m)),
+        };
+        let mut grid = self.grid;
+        grid.try_set_cell(row, col, mark)?;
+
+        let seq = grid.cell_count() as u8;
+        if self.is_local_turn {
+            let pkt = PlayerMove { row, col, seq };
+            self.send(&pkt.to_bytes()).await?;
+            self.unacked_move = Some(pkt);
+        } else {
+            let _ = self.send(&MoveAck { seq }.to_bytes()).await;
+        }
+
+        self.grid = grid;
+        self.is_local_turn = !self.is_local_turn;
+
+        if let Some(result) = game::board_result(&grid) {
+            let local_turn = !self.is_local_turn;
+            if local_turn {
+                let _ = self.send(&EndOfGame(result).to_bytes()).await;
+            } else {
+                // Same as the blocking games, the END_OF_GAME packet is only checked if it's
+                // received
+                if let Ok(Packet::EndOfGame(pkt)) = self.read_remote_packet().await {
+                    if pkt.0 != result {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Remote player reported a different result",
+                        )
+                        .into());
+                    }
+                }
+            }
+            self.result = Some(result);
+        }
+        Ok(())
+    }
+
+    /// Tells the remote player whether the local player wants a rematch once the game is over,
+    /// and restarts the game if both accepted. See
+    /// [`NetworkedGame::rematch`](crate::game::NetworkedGame::rematch).
+    pub async fn rematch(&mut self, accept: bool) -> io::Result<bool> {
+        if self.result.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "A rematch can only be played once the game is over",
+            ));
+        }
+        let sent = self.send(&Rematch(accept).to_bytes()).await;
+        if !accept {
+            return Ok(false);
+        }
+        sent?;
+
+        let next = loop {
+            match self.read_remote_packet().await? {
+                Packet::EndOfGame(_) => {}
+                Packet::Rematch(Rematch(accepts)) => {
+                    break accepts.then(|| game::rematch_snapshot(&self.snapshot()))
+                }
+                Packet::NewGame(pkt) => break Some(game::new_game_snapshot(pkt)),
+                pkt => return Err(game::unexpected_remote_packet(&pkt)),
+            }
+        };
+        let Some(snapshot) = next else {
+            return Ok(false);
+        };
+        self.grid = snapshot.grid;
+        self.is_local_turn = snapshot.is_local_turn;
+        self.local_mark = snapshot.local_mark;
+        self.result = None;
+        Ok(true)
+    }
+
+    /// Sends the local grid and turn for the remote player to check. See
+    /// [`NetworkedGame::verify_board`](crate::game::NetworkedGame::verify_board).
+    pub async fn verify_board(&mut self) -> io::Result<()> {
+        if !self.is_local_turn {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The board can only be verified during the local player's turn",
+            ));
+        }
+        let pkt = game::board_sync(&self.snapshot()).to_bytes();
+        self.send(&pkt).await
+    }
+
+    /// Asks `local_player` for their move without blocking the runtime's other tasks, sending
+    /// heartbeats until they chose it.
+    async fn local_move(
+        &mut self,
+        local_player: &Arc<dyn Player>,
+    ) -> Result<Move, NetworkedGameError> {
+        let player = Arc::clone(local_player);
+        let (grid, mark, limit) = (self.grid, self.local_mark, self.move_time_limit);
+        let mut chosen = tokio::task::spawn_blocking(move || {
+            game::local_networked_move(&player, &grid, mark, limit)
+        });
+        loop {
+            match tokio::time::timeout(game::HEARTBEAT_INTERVAL, &mut chosen).await {
+                Ok(Ok(chosen)) => return chosen,
+                // The player panicked, which is forwarded like the blocking games do
+                Ok(Err(e)) => panic::resume_unwind(e.into_panic()),
+                // Errors are noticed when the move is sent
+                Err(_) => {
+                    let _ = self.send(&Heartbeat.to_bytes()).await;
+                }
+            }
+        }
+    }
+
+    async fn send(&mut self, pkt: &[u8]) -> io::Result<()> {
+        self.writer.write_all(pkt).await?;
+        self.writer.flush().await
+    }
+
+    /// Reads packets until the remote player's move or end of game, like the blocking games do:
+    /// chat messages are passed to the chat handler, board synchronizations checked against the
+    /// local state, and the local player's last move sent again until it is acknowledged. Invalid
+    /// remote moves are rejected, and a rejection of the local player's last move takes it back and
+    /// is returned. Fails if no packet, not even a heartbeat, is received for too long, or the move
+    /// is never acknowledged.
+    async fn read_remote_packet(&mut self) -> io::Result<Packet> {
+        let mut ack_deadline = Instant::now() + game::ACK_TIMEOUT;
+        let mut retries = 0;
+        // Kept across timeouts, since a cancelled read may have already read part of the packet
+        let mut buf = vec![];
+        loop {
+            let ack_remaining = self
+                .unacked_move
+                .map(|_| ack_deadline.saturating_duration_since(Instant::now()));
+            let waiting_for_ack = ack_remaining.is_some_and(|ack| ack < game::HEARTBEAT_TIMEOUT);
+            let timeout = ack_remaining
+                .filter(|_| waiting_for_ack)
+                .unwrap_or(game::HEARTBEAT_TIMEOUT);
+
+            let read = self.reader.read_until(protocol::TERMINATOR, &mut buf);
+            match tokio::time::timeout(timeout, read).await {
+                Ok(Ok(0)) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                Ok(read) => read?,
+                Err(_) if waiting_for_ack => {
+                    let Some(pkt) = self
+                        .unacked_move
+                        .filter(|_| retries < game::MAX_MOVE_RETRIES)
+                    else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "Remote player didn't acknowledge the last move",
+                        ));
+                    };
+                    retries += 1;
+                    ack_deadline = Instant::now() + game::ACK_TIMEOUT;
+                    self.send(&pkt.to_bytes()).await?;
+                    continue;
+                }
+                Err(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "Remote player stopped sending heartbeats",
+                    ))
+                }
+            };
+            let pkt = game::parse_packet(&std::mem::take(&mut buf))?;
+
+            match pkt {
+                Packet::Heartbeat => {}
+                Packet::BoardSync(sync) => {
+                    game::check_board_sync(sync, game::board_sync(&self.snapshot()))?
+                }
+                Packet::ChatMessage(ChatMessage(message)) => self.chat_handler.receive(&message),
+                Packet::ServerShutdown(pkt) => self.chat_handler.server_shutdown(pkt),
+                Packet::MoveAck(ack) => {
+                    if self.unacked_move.is_some_and(|sent| sent.seq == ack.seq) {
+                        self.unacked_move = None;
+                    }
+                }
+                Packet::InvalidMove(rejection) => {
+                    if let Some(sent) = self.unacked_move.filter(|sent| rejection.rejects(sent)) {
+                        self.unacked_move = None;
+                        self.grid.clear_cell(sent.row, sent.col);
+                        self.is_local_turn = true;
+                        return Ok(pkt);
+                    }
+                }
+                Packet::PlayerMove(remote_move) => {
+                    if usize::from(remote_move.seq) <= self.grid.cell_count() {
+                        let ack = MoveAck {
+                            seq: remote_move.seq,
+                        };
+                        let _ = self.send(&ack.to_bytes()).await;
+                        continue;
+                    }
+                    let mut grid = self.grid;
+                    let mark = self.local_mark.opposite();
+                    if let Err(reason) = grid.try_set_cell(remote_move.row, remote_move.col, mark) {
+                        let rejection = InvalidMove {
+                            row: remote_move.row,
+                            col: remote_move.col,
+                            seq: remote_move.seq,
+                            reason,
+                        };
+                        self.send(&rejection.to_bytes()).await?;
+                        continue;
+                    }
+                    self.unacked_move = None;
+                    return Ok(pkt);
+                }
+                pkt => {
+                    self.unacked_move = None;
+                    return Ok(pkt);
+                }
+            }
+        }
+    }
+}
+
+fn split(stream: TcpStream) -> (BufReader<OwnedReadHalf>, BufWriter<OwnedWriteHalf>) {
+    let (reader, writer) = stream.into_split();
+    (BufReader::new(reader), BufWriter::new(writer))
+}
+
+/// Connects to `addr`, sends the `hello` packet and waits for the SERVER_HELLO reply.
+async fn client_handshake<A: ToSocketAddrs>(
+    addr: A,
+    hello: &[u8],
+) -> io::Result<(
+    BufReader<OwnedReadHalf>,
+    BufWriter<OwnedWriteHalf>,
+    ServerHello,
+)> {
+    let (mut reader, mut writer) = split(TcpStream::connect(addr).await?);
+    writer.write_all(hello).await?;
+    writer.flush().await?;
+
+    let mut buf = vec![];
+    reader.read_until(protocol::TERMINATOR, &mut buf).await?;
+    let buf = game::unframe_packet(buf)?;
+
+    Ok((reader, writer, game::parse_server_hello(&buf)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Mutex};
+
+    use crate::player::{tests::MockPlayer, PlayerError, ScriptedPlayer};
+
+    use super::*;
+
+    fn players() -> (Arc<dyn Player>, Arc<dyn Player>) {
+        (Arc::new(MockPlayer(1, 1)), Arc::new(MockPlayer(0, 0)))
+    }
+
+    /// Player blocking until it is told to play its move, or giving up after a few seconds.
+    #[derive(Debug)]
+    struct GatedPlayer(Mutex<mpsc::Receiver<()>>);
+
+    impl Player for GatedPlayer {
+        fn get_move(&self, _: &Grid, _: &Mark) -> Result<Move, PlayerError> {
+            match self.0.lock().unwrap().recv_timeout(Duration::from_secs(5)) {
+                Ok(()) => Ok(Move::Place(1, 1)),
+                Err(_) => Err(PlayerError::EndOfInput),
+            }
+        }
+    }
+
+    async fn connect_pair() -> (AsyncNetworkedGame, AsyncNetworkedGame) {
+        let server = AsyncGameServer::bind("127.0.0.1:0")
+            .await
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+
+        let settings = ServerGameSettings::default();
+        let (host, client) =
+            tokio::join!(server.accept(&settings), AsyncNetworkedGame::connect(addr));
+        (
+            host.expect("Error accepting client"),
+            client.expect("Error connecting to server"),
+        )
+    }
+
+    #[tokio::test]
+    async fn async_games_exchange_moves() {
+        let (center, corner) = players();
+        let (mut host, mut client) = connect_pair().await;
+        assert!(host.is_local_turn());
+        assert_eq!(client.local_mark(), Mark::O);
+
+        let (host_move, client_move) =
+            tokio::join!(host.try_move(&center), client.try_move(&corner));
+        host_move.unwrap();
+        client_move.unwrap();
+
+        assert_eq!(host.grid(), client.grid());
+        assert_eq!(client.grid().get_cell(1, 1).try_get_mark(), Some(&Mark::X));
+        assert!(client.is_local_turn());
+    }
+
+    #[tokio::test]
+    async fn async_games_end_on_resignation() {
+        let (_, corner) = players();
+        let (mut host, mut client) = connect_pair().await;
+        let resigning: Arc<dyn Player> = Arc::new(ScriptedPlayer::new([Move::Resign]));
+        let (host_move, client_move) =
+            tokio::join!(host.try_move(&resigning), client.try_move(&corner));
+        host_move.unwrap();
+        client_move.unwrap();
+
+        let result = GameResult::Win {
+            winner: Mark::O,
+            reason: WinReason::Resignation,
+        };
+        assert_eq!(host.result(), Some(result));
+        assert_eq!(client.result(), Some(result));
+        assert!(matches!(
+            client.try_move(&corner).await,
+            Err(NetworkedGameError::GameOver)
+        ));
+    }
+
+    #[tokio::test]
+    async fn async_game_resumes_and_times_out() {
+        let (center, _) = players();
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        let host = GameSnapshot {
+            grid,
+            is_local_turn: false,
+            local_mark: Mark::X,
+        };
+        let client = GameSnapshot {
+            grid,
+            is_local_turn: true,
+            local_mark: Mark::O,
+        };
+
+        let server = AsyncGameServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let (mut host_game, client_game) = tokio::join!(
+            server.accept_resumed(&host),
+            AsyncNetworkedGame::resume(addr, &client)
+        );
+        let host_game = host_game.as_mut().expect("Error accepting client");
+        assert_eq!(host_game.snapshot(), host);
+        // The client stays connected without playing its move
+        let client_game = client_game.expect("Error resuming game");
+        assert_eq!(client_game.snapshot(), client);
+
+        host_game.set_move_time_limit(Some(Duration::from_millis(10)));
+        let err = host_game.try_move(&center).await.unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Timeout(Mark::O)));
+    }
+
+    #[tokio::test]
+    async fn local_moves_dont_block_other_games() {
+        // Tests run on a single threaded runtime, which the pending move must not hold
+        let (mut pending_host, mut pending_client) = connect_pair().await;
+        let (mut host, mut client) = connect_pair().await;
+        let (center, corner) = players();
+        let (play, gate) = mpsc::channel();
+        let gated: Arc<dyn Player> = Arc::new(GatedPlayer(Mutex::new(gate)));
+
+        let other_game = async {
+            let (host_move, client_move) =
+                tokio::join!(host.try_move(&center), client.try_move(&corner));
+            host_move.unwrap();
+            client_move.unwrap();
+            play.send(()).unwrap();
+        };
+        let (pending_move, client_move, ()) = tokio::join!(
+            pending_host.try_move(&gated),
+            pending_client.try_move(&corner),
+            other_game
+        );
+        pending_move.unwrap();
+        client_move.unwrap();
+        assert_eq!(client.grid().cell_count(), 1);
+        assert_eq!(pending_client.grid().cell_count(), 1);
+    }
+}
@@ -0,0 +1,208 @@
+//! Measures how fast players choose their moves, to catch performance regressions in search-based
+//! bots. See [`benchmark`], and the `bots` criterion benchmark running it for every difficulty.
+//!
+//! [`perft`] counts the positions and games reachable from a board, to validate the moves of new
+//! board variants against known totals.
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    board::Board,
+    grid::{Grid, Mark},
+    player::{Player, PlayerError},
+};
+
+/// Returns `count` positions of games in progress, with the mark to move, reached by playing random
+/// moves from the empty grid. The same `seed` always returns the same positions.
+pub fn random_positions(count: usize, seed: u64) -> Vec<(Grid, Mark)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut positions = Vec::with_capacity(count);
+    let mut grid = Grid::default();
+    let mut mark = Mark::X;
+    while positions.len() < count {
+        positions.push((grid, mark));
+        let &(row, col) = grid.legal_moves().choose(&mut rng).unwrap();
+        grid.set_cell(row, col, mark);
+        mark = mark.opposite();
+        // Start a new game once this one is over
+        if grid.get_winning_mark().is_some() || grid.is_full() {
+            grid = Grid::default();
+            mark = Mark::X;
+        }
+    }
+    positions
+}
+
+/// Positions and games reachable from a board, see [`perft`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerftResult {
+    /// Move sequences of exactly `depth` moves, including ones whose last move ends the game
+    pub positions: u64,
+    /// Move sequences ending the game within `depth` moves
+    pub games: u64,
+    /// Games ending with a full board and no winner, out of `games`
+    pub draws: u64,
+}
+
+/// Plays every sequence of up to `depth` legal moves from `board`, X and O taking turns, and counts
+/// where they lead. The mark to move is X if the board has as many X as O, and O otherwise.
+///
+/// From the empty classic grid, `perft(&Grid::default(), 9)` finds the 255168 possible games, 46080
+/// of them draws.
+///
+/// ```
+/// # use tictactoe::{benchmark::perft, grid::Grid};
+/// assert_eq!(perft(&Grid::default(), 2).positions, 72);
+/// ```
+pub fn perft<B: Board>(board: &B, depth: usize) -> PerftResult {
+    let (rows, cols) = board.dimensions();
+    let marks = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .filter_map(|(row, col)| board.mark(row, col));
+    let (x, o) = marks.fold((0, 0), |(x, o), mark| match mark {
+        Mark::X => (x + 1, o),
+        Mark::O => (x, o + 1),
+        Mark::Y => (x, o),
+    });
+    let mark = if x == o { Mark::X } else { Mark::O };
+
+    let mut result = PerftResult::default();
+    count_moves(board, mark, depth, &mut result);
+    result
+}
+
+fn count_moves<B: Board>(board: &B, mark: Mark, depth: usize, result: &mut PerftResult) {
+    if depth == 0 {
+        result.positions += 1;
+        return;
+    }
+    if board.winner().is_some() || board.is_full() {
+        return;
+    }
+
+    for (row, col) in board.legal_moves() {
+        let mut next = board.clone();
+        next.place(row, col, mark)
+            .expect("Legal moves can be played");
+        let winner = next.winner();
+        if winner.is_some() || next.is_full() {
+            result.games += 1;
+            if winner.is_none() {
+                result.draws += 1;
+            }
+        }
+        count_moves(&next, mark.opposite(), depth - 1, result);
+    }
+}
+
+/// Time taken by a player to choose its moves, see [`benchmark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchmarkResult {
+    pub moves: usize,
+    pub elapsed: Duration,
+}
+
+impl BenchmarkResult {
+    pub fn moves_per_second(&self) -> f64 {
+        self.moves as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl Display for BenchmarkResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} moves in {:.2?} ({:.0} moves/s)",
+            self.moves,
+            self.elapsed,
+            self.moves_per_second()
+        )
+    }
+}
+
+/// Asks `player` for its move in each of `positions`, such as the ones of [`random_positions`],
+/// and measures how long it took.
+///
+/// ```
+/// # use tictactoe::{benchmark::{benchmark, random_positions}, player::BotPlayer};
+/// let positions = random_positions(100, 0);
+/// let result = benchmark(&BotPlayer::normal(), &positions).unwrap();
+/// assert_eq!(result.moves, 100);
+/// println!("{}", result);
+/// ```
+pub fn benchmark(
+    player: &dyn Player,
+    positions: &[(Grid, Mark)],
+) -> Result<BenchmarkResult, PlayerError> {
+    let start = Instant::now();
+    for (grid, mark) in positions {
+        player.get_move(grid, mark)?;
+    }
+    Ok(BenchmarkResult {
+        moves: positions.len(),
+        elapsed: start.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::SquareBoard, player::tests::MockPlayer};
+
+    use super::*;
+
+    #[test]
+    fn positions_are_random_games_in_progress() {
+        let positions = random_positions(50, 7);
+        assert_eq!(positions, random_positions(50, 7));
+        assert_eq!(positions[0], (Grid::default(), Mark::X));
+        for (grid, mark) in &positions {
+            assert!(grid.get_winning_mark().is_none() && !grid.is_full());
+            let expected = if grid.cell_count() % 2 == 0 {
+                Mark::X
+            } else {
+                Mark::O
+            };
+            assert_eq!(*mark, expected);
+        }
+
+        let result = benchmark(&MockPlayer(0, 0), &positions).unwrap();
+        assert_eq!(result.moves, 50);
+    }
+
+    #[test]
+    fn perft_finds_every_game() {
+        let grid = Grid::default();
+        assert_eq!(perft(&grid, 0).positions, 1);
+        assert_eq!(perft(&grid, 4).positions, 3024);
+        assert_eq!(
+            perft(&grid, 5),
+            PerftResult {
+                positions: 15120,
+                games: 1440,
+                draws: 0
+            }
+        );
+        let full = perft(&grid, 9);
+        assert_eq!(
+            full,
+            PerftResult {
+                positions: 127872,
+                games: 255168,
+                draws: 46080
+            }
+        );
+
+        // Variant boards have to agree with the classic grid
+        assert_eq!(perft(&SquareBoard::new(3, 3), 9), full);
+        assert_eq!(perft(&SquareBoard::new(4, 3), 2).positions, 16 * 15);
+
+        // O moves after X's corner opening
+        let grid: Grid = "X../.../...".parse().unwrap();
+        assert_eq!(perft(&grid, 8).games, 27732);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    env,
+    io::{self, BufRead},
+    net::SocketAddr,
+    process, thread,
+    time::Duration,
+};
+
+use tictactoe::server::{DedicatedServer, ServerAdmin};
+
+const DEFAULT_ADDR: &str = "0.0.0.0:8905";
+
+const USAGE: &str =
+    "Usage: tictactoe-server [address] [status address] [--accounts <path>] [--state <path>]";
+
+/// Grace period of the shutdown command when none is given, in seconds.
+const DEFAULT_SHUTDOWN_SECS: u64 = 30;
+
+const HELP: &str = "Commands:
+  games             List the games being relayed
+  accounts          List the accounts created on the server
+  kick <address>    Close the connection of the client at <address>
+  notice <message>  Send a chat message to every player
+  drain             Stop starting games, and exit once the current ones end
+  shutdown [secs]   Warn the players, then save the games still going after [secs] (30 by
+                    default) to the state file and exit. Restart within 30 seconds for
+                    the players to get their games back
+  help              Show this message";
+
+/// Hosts games between remote players, and relays games between players joining the same room.
+/// Takes the address to bind on as its first argument, and optionally the address to serve the
+/// HTTP status endpoint on as its second. `--accounts <path>` saves the players' accounts to a
+/// file, instead of forgetting them when the server stops, and `--state <path>` saves the games
+/// interrupted by the shutdown command, resuming them on the next start. Admin commands are read
+/// from stdin.
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let accounts = take_option(&mut args, "--accounts");
+    let state = take_option(&mut args, "--state");
+    let mut args = args.into_iter();
+
+    let addr = args.next().unwrap_or_else(|| DEFAULT_ADDR.to_owned());
+    let mut server = DedicatedServer::bind(&addr).expect("Error binding to socket");
+    println!(
+        "Listening on {}",
+        server.local_addr().expect("Error getting server address")
+    );
+    if let Some(path) = accounts {
+        server = server
+            .with_accounts_file(&path)
+            .expect("Error loading the accounts");
+        println!("Saving accounts to {}", path);
+    }
+    if let Some(path) = state {
+        server = server
+            .with_state_file(&path)
+            .expect("Error loading the saved games");
+        let resumed = server.admin().games().len();
+        println!("Saving games to {}, resuming {} game(s)", path, resumed);
+    }
+
+    if let Some(status_addr) = args.next() {
+        let status = server
+            .status_server(&status_addr)
+            .expect("Error binding status endpoint");
+        println!(
+            "Serving status on http://{}",
+            status.local_addr().expect("Error getting status address")
+        );
+        thread::spawn(move || {
+            if let Err(e) = status.run() {
+                println!("Error serving status: {}", e);
+            }
+        });
+    }
+
+    let admin = server.admin();
+    let server = thread::spawn(move || {
+        if let Err(e) = server.run() {
+            println!("Error accepting connections: {}", e);
+        }
+    });
+
+    // The server keeps running if stdin is closed, when run as a service for instance
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        run_command(&admin, line.trim());
+    }
+    let _ = server.join();
+}
+
+/// Removes the option `name` and its value from `args`, and returns the value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == name)?;
+    let Some(value) = args.get(i + 1).cloned() else {
+        println!("{}", USAGE);
+        process::exit(1);
+    };
+    args.drain(i..=i + 1);
+    Some(value)
+}
+
+fn run_command(admin: &ServerAdmin, line: &str) {
+    let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+    match (command, arg.trim()) {
+        ("", _) => {}
+        ("games", _) => {
+            let games = admin.games();
+            if games.is_empty() {
+                println!("No game is being relayed");
+            }
+            for game in games {
+                let player = |addr: SocketAddr, account: Option<String>| match account {
+                    Some(name) => format!("{} ({})", name, addr),
+                    None => addr.to_string(),
+                };
+                println!(
+                    "#{}: {} as X vs {} as O, {} move(s), started {}s ago",
+                    game.id,
+                    player(game.player_x, game.account_x),
+                    player(game.player_o, game.account_o),
+                    game.moves,
+                    game.duration.as_secs()
+                );
+            }
+        }
+        ("accounts", _) => {
+            let accounts = admin.accounts();
+            if accounts.is_empty() {
+                println!("No account was created");
+            }
+            for name in accounts {
+                println!("{}", name);
+            }
+        }
+        ("kick", peer) => match peer.parse::<SocketAddr>() {
+            Ok(peer) if admin.kick(peer) => println!("Kicked {}", peer),
+            Ok(peer) => println!("No client is connected from {}", peer),
+            Err(_) => println!("Usage: kick <address>, such as kick 10.0.0.2:51234"),
+        },
+        ("notice", "") => println!("Usage: notice <message>"),
+        ("notice", message) => match admin.notice(message) {
+            Ok(count) => println!("Sent to {} player(s)", count),
+            Err(e) => println!("Error sending notice: {}", e),
+        },
+        ("drain", _) => {
+            admin.drain();
+            println!(
+                "Draining, waiting for {} game(s) to end",
+                admin.games().len()
+            );
+            let admin = admin.clone();
+            thread::spawn(move || {
+                admin.wait_drained();
+                println!("All games ended, exiting");
+                process::exit(0);
+            });
+        }
+        ("shutdown", secs) => {
+            let secs = match secs {
+                "" => Ok(DEFAULT_SHUTDOWN_SECS),
+                secs => secs.parse(),
+            };
+            let Ok(secs) = secs else {
+                println!("Usage: shutdown [seconds], such as shutdown 60");
+                return;
+            };
+            println!(
+                "Shutting down in {}s, {} game(s) being relayed",
+                secs,
+                admin.games().len()
+            );
+            let admin = admin.clone();
+            thread::spawn(move || match admin.shutdown(Duration::from_secs(secs)) {
+                Ok(saved) => {
+                    println!("Saved {} unfinished game(s), exiting", saved);
+                    process::exit(0);
+                }
+                Err(e) => {
+                    println!("Error saving the unfinished games: {}", e);
+                    process::exit(1);
+                }
+            });
+        }
+        ("help", _) => println!("{}", HELP),
+        (command, _) => println!("Unknown command {:?}, type help for a list", command),
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt::{Debug, Display};
+
+use crate::grid::{GridPlacementError, Mark};
+
+/// Surface a game is played on. Implemented by [`Grid`](crate::grid::Grid) for the classic 3x3
+/// game, and by variant boards so they can reuse [`Game`](crate::game::Game)'s turn management and
+/// player dispatch.
+///
+/// Cells are addressed by `(row, col)`. Boards are `Send`, so they can be handed to players
+/// choosing their move on another thread, see [`MoveTimeLimit`](crate::game::MoveTimeLimit).
+pub trait Board: Clone + Debug + Display + Send + 'static {
+    /// Places `mark` on the cell at `(row, col)`, if it is in bounds and empty.
+    fn place(&mut self, row: usize, col: usize, mark: Mark) -> Result<(), GridPlacementError>;
+
+    /// Returns every cell a mark can currently be placed on.
+    fn legal_moves(&self) -> Vec<(usize, usize)>;
+
+    /// Returns the mark that won the game on this board, if any.
+    fn winner(&self) -> Option<Mark>;
+
+    /// Returns true if no more marks can be placed.
+    fn is_full(&self) -> bool;
+
+    /// Returns the number of `(rows, columns)`.
+    fn dimensions(&self) -> (usize, usize);
+
+    /// Returns the mark on the cell at `(row, col)`, if any.
+    fn mark(&self, row: usize, col: usize) -> Option<Mark>;
+}
+
+/// Square board of any size, won by aligning `win_length` marks in a row, column or diagonal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquareBoard {
+    size: usize,
+    win_length: usize,
+    cells: Vec<Option<Mark>>,
+}
+
+impl SquareBoard {
+    /// Creates an empty `size`x`size` board.
+    ///
+    /// # Panics
+    /// Panics if `win_length` is 0 or larger than `size`.
+    pub fn new(size: usize, win_length: usize) -> Self {
+        assert!(
+            (1..=size).contains(&win_length),
+            "Win length must be between 1 and the board size"
+        );
+
+        Self {
+            size,
+            win_length,
+            cells: vec![None; size * size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    pub fn get_mark(&self, row: usize, col: usize) -> Option<Mark> {
+        self.cells[row * self.size + col]
+    }
+
+    /// Returns true if `win_length` cells starting at `(row, col)` going towards `(dr, dc)` all
+    /// contain `mark`.
+    fn is_line(&self, row: usize, col: usize, (dr, dc): (isize, isize), mark: Mark) -> bool {
+        (0..self.win_length as isize).all(|i| {
+            let r = row as isize + dr * i;
+            let c = col as isize + dc * i;
+            (0..self.size as isize).contains(&r)
+                && (0..self.size as isize).contains(&c)
+                && self.get_mark(r as usize, c as usize) == Some(mark)
+        })
+    }
+}
+
+impl Board for SquareBoard {
+    fn place(&mut self, row: usize, col: usize, mark: Mark) -> Result<(), GridPlacementError> {
+        if row >= self.size || col >= self.size {
+            return Err(GridPlacementError::OutOfBounds { row, col });
+        }
+
+        let cell = &mut self.cells[row * self.size + col];
+        if cell.is_some() {
+            return Err(GridPlacementError::CellInUse { row, col });
+        }
+        *cell = Some(mark);
+        Ok(())
+    }
+
+    fn legal_moves(&self) -> Vec<(usize, usize)> {
+        (0..self.cells.len())
+            .filter(|&i| self.cells[i].is_none())
+            .map(|i| (i / self.size, i % self.size))
+            .collect()
+    }
+
+    fn winner(&self) -> Option<Mark> {
+        const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let Some(mark) = self.get_mark(row, col) else {
+                    continue;
+                };
+                if DIRECTIONS
+                    .iter()
+                    .any(|&dir| self.is_line(row, col, dir, mark))
+                {
+                    return Some(mark);
+                }
+            }
+        }
+        None
+    }
+
+    fn is_full(&self) -> bool {
+        self.cells.iter().all(|c| c.is_some())
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.size, self.size)
+    }
+
+    fn mark(&self, row: usize, col: usize) -> Option<Mark> {
+        self.get_mark(row, col)
+    }
+}
+
+/// Draws the board like [`Grid`](crate::grid::Grid), including with ASCII characters in the alternate
+/// form (`{:#}`).
+impl Display for SquareBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if cfg!(feature = "unicode") && !f.alternate() {
+            self.fmt_unicode(f)
+        } else {
+            self.fmt_ascii(f)
+        }
+    }
+}
+
+impl SquareBoard {
+    fn fmt_ascii(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Same layout as `Grid`, with `size` cells per row
+        let side_string = "-".repeat(1 + self.size * 4);
+        writeln!(f, "{}", side_string)?;
+        for row in self.cells.chunks(self.size) {
+            let value_line = row.iter().fold("|".to_owned(), |acc, cell| match cell {
+                Some(m) => format!("{acc} {m} |"),
+                None => format!("{acc}   |"),
+            });
+            writeln!(f, "{}", value_line)?;
+            writeln!(f, "{}", side_string)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_unicode(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Same layout as `Grid`, with `size` cells per row
+        let line = |left: &str, middle: &str, right: &str| {
+            format!(" {left}")
+                + &format!("\u{2500}\u{2500}\u{2500}{middle}").repeat(self.size - 1)
+                + &format!("\u{2500}\u{2500}\u{2500}{right}")
+        };
+        let top_line = line("\u{250C}", "\u{252C}", "\u{2510}");
+        let middle_line = line("\u{251C}", "\u{253C}", "\u{2524}");
+        let bottom_line = line("\u{2514}", "\u{2534}", "\u{2518}");
+
+        writeln!(f, "{}", top_line)?;
+        for (n, row) in self.cells.chunks(self.size).enumerate() {
+            let value_line = row
+                .iter()
+                .fold(" \u{2502}".to_owned(), |acc, cell| match cell {
+                    Some(m) => format!("{acc} {m} \u{2502}"),
+                    None => format!("{acc}   \u{2502}"),
+                });
+            writeln!(f, "{}", value_line)?;
+            if n == self.size - 1 {
+                writeln!(f, "{}", bottom_line)?;
+            } else {
+                writeln!(f, "{}", middle_line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_board_finds_lines() {
+        let mut board = SquareBoard::new(5, 4);
+        for col in 1..4 {
+            board.place(2, col, Mark::Y).unwrap();
+        }
+        assert_eq!(board.winner(), None);
+        board.place(2, 4, Mark::Y).unwrap();
+        assert_eq!(board.winner(), Some(Mark::Y));
+
+        let mut board = SquareBoard::new(5, 4);
+        for x in 0..4 {
+            board.place(x + 1, 3 - x, Mark::O).unwrap();
+        }
+        assert_eq!(board.winner(), Some(Mark::O));
+    }
+
+    #[test]
+    fn square_board_rejects_invalid_placement() {
+        let mut board = SquareBoard::new(5, 4);
+        assert!(board.place(0, 0, Mark::X).is_ok());
+        assert!(matches!(
+            board.place(0, 0, Mark::O),
+            Err(GridPlacementError::CellInUse { row: 0, col: 0 })
+        ));
+        assert!(matches!(
+            board.place(5, 0, Mark::O),
+            Err(GridPlacementError::OutOfBounds { row: 5, col: 0 })
+        ));
+        assert_eq!(board.legal_moves().len(), 24);
+    }
+}
@@ -0,0 +1,271 @@
+//! Estimates how strong each bot difficulty plays as an Elo rating, so menus can show it next to
+//! the difficulty. See [`calibrate`].
+//!
+//! Difficulties play a round-robin against each other and against reference strategies: a random
+//! mover, a greedy player completing and blocking lines, and a perfect player. The random mover is
+//! rated [`RANDOM_RATING`], and the other ratings are the ones best explaining the results.
+
+use std::{
+    f64::consts::LN_10,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    board::Board,
+    grid::{Grid, Mark},
+    player::{BotPlayer, BotPlayerDifficulty, Move, Player, PlayerError},
+    tournament::{Score, Tournament, TournamentError, TournamentFormat, TournamentResults},
+};
+
+/// Rating of the random reference strategy, which the other ratings are relative to.
+pub const RANDOM_RATING: f64 = 800.0;
+
+/// Games played by each pair of entrants when calibrating [`DIFFICULTY_RATINGS`].
+pub const CALIBRATION_GAMES: usize = 200;
+
+/// Ratings of the fixed difficulties, found by [`calibrate`] with [`CALIBRATION_GAMES`] games and
+/// the seed 0, rounded to the nearest 10. `tictactoe calibrate` prints them again after a bot
+/// changes.
+pub const DIFFICULTY_RATINGS: [(BotPlayerDifficulty, u32); 4] = [
+    (BotPlayerDifficulty::Easy, 790),
+    (BotPlayerDifficulty::Normal, 1030),
+    (BotPlayerDifficulty::Noisy(10), 1180),
+    (BotPlayerDifficulty::Impossible, 1270),
+];
+
+/// Returns the rating of `difficulty` from [`DIFFICULTY_RATINGS`], or `None` for custom strengths.
+pub fn estimated_rating(difficulty: BotPlayerDifficulty) -> Option<u32> {
+    DIFFICULTY_RATINGS
+        .iter()
+        .find(|(d, _)| *d == difficulty)
+        .map(|&(_, rating)| rating)
+}
+
+/// Rates `difficulties` by playing `games` games between each pair of entrants, alternating who
+/// moves first. Bots are seeded from `seed`, so the same arguments always give the same ratings.
+/// Difficulties given more than once are only entered once.
+pub fn calibrate(
+    difficulties: &[BotPlayerDifficulty],
+    games: usize,
+    seed: u64,
+) -> Result<Calibration, TournamentError> {
+    // Every bot gets its own seed, in the order games are played
+    let seeds = Arc::new(AtomicU64::new(seed));
+    let next_seed = move || seeds.fetch_add(1, Ordering::Relaxed);
+
+    let mut tournament = Tournament::new(TournamentFormat::RoundRobin { games });
+    let random = next_seed.clone();
+    tournament.add_player("Random", move || {
+        Box::new(BotPlayer::easy().with_seed(random()))
+    });
+    let greedy = next_seed.clone();
+    tournament.add_player("Greedy", move || Box::new(GreedyPlayer::new(greedy())));
+    tournament.add_player("Perfect", || Box::new(BotPlayer::impossible()));
+    let mut entered = vec![];
+    for &difficulty in difficulties {
+        if entered.contains(&difficulty) {
+            continue;
+        }
+        entered.push(difficulty);
+        let next_seed = next_seed.clone();
+        tournament.add_player(difficulty_name(difficulty), move || {
+            Box::new(BotPlayer::from_difficulty(difficulty).with_seed(next_seed()))
+        });
+    }
+
+    let results = tournament.run()?;
+    let names: Vec<String> = tournament.names().map(str::to_owned).collect();
+    let scores: Vec<Vec<Score>> = names
+        .iter()
+        .map(|a| {
+            names
+                .iter()
+                .map(|b| results.head_to_head(a, b).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    let ratings = names.into_iter().zip(fit_ratings(&scores)).collect();
+    Ok(Calibration { ratings, results })
+}
+
+/// Name of `difficulty` in a [`Calibration`].
+pub fn difficulty_name(difficulty: BotPlayerDifficulty) -> String {
+    match difficulty {
+        BotPlayerDifficulty::Easy => "Easy".to_owned(),
+        BotPlayerDifficulty::Normal => "Normal".to_owned(),
+        BotPlayerDifficulty::Impossible => "Impossible".to_owned(),
+        BotPlayerDifficulty::Strength(strength) => format!("Strength {}%", strength),
+        BotPlayerDifficulty::Noisy(error_rate) => format!("Noisy {}%", error_rate),
+    }
+}
+
+/// Ratings found by [`calibrate`]. Its `Display` implementation writes the entrants from the
+/// highest rated, with their rounded rating.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    /// Rating of each entrant, the reference strategies first
+    ratings: Vec<(String, f64)>,
+    results: TournamentResults,
+}
+
+impl Calibration {
+    /// Returns the rating of the entrant called `name`, a difficulty named by [`difficulty_name`]
+    /// or one of the reference strategies: `Random`, `Greedy` and `Perfect`.
+    pub fn rating(&self, name: &str) -> Option<f64> {
+        self.ratings
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|&(_, rating)| rating)
+    }
+
+    pub fn difficulty_rating(&self, difficulty: BotPlayerDifficulty) -> Option<f64> {
+        self.rating(&difficulty_name(difficulty))
+    }
+
+    /// Returns the entrants with their rating, the reference strategies first.
+    pub fn ratings(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.ratings
+            .iter()
+            .map(|(name, rating)| (name.as_str(), *rating))
+    }
+
+    /// Returns the results of the games the ratings were found from.
+    pub fn results(&self) -> &TournamentResults {
+        &self.results
+    }
+}
+
+impl Display for Calibration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.ratings.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+        let mut ratings: Vec<_> = self.ratings().collect();
+        ratings.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        writeln!(f, "{:<width$}  Rating", "Player")?;
+        for (name, rating) in ratings {
+            writeln!(f, "{:<width$}  {:>6.0}", name, rating)?;
+        }
+        Ok(())
+    }
+}
+
+/// Most iterations spent fitting the ratings, which usually converge in a few dozen.
+const FIT_ITERATIONS: usize = 1000;
+
+/// Returns the ratings whose expected scores best match `scores`, `scores[a][b]` being `a`'s score
+/// against `b`. The first entrant is rated [`RANDOM_RATING`]. Each pairing counts one more draw than
+/// was played, so entrants who never lost still get a finite rating.
+fn fit_ratings(scores: &[Vec<Score>]) -> Vec<f64> {
+    let mut ratings = vec![RANDOM_RATING; scores.len()];
+    for _ in 0..FIT_ITERATIONS {
+        let mut largest_step: f64 = 0.0;
+        for a in 1..scores.len() {
+            let (mut points, mut expected, mut slope) = (0.0, 0.0, 0.0);
+            for b in (0..scores.len()).filter(|&b| b != a) {
+                let score = scores[a][b];
+                let games = (score.games() + 1) as f64;
+                let p = expected_score(ratings[a], ratings[b]);
+                points += score.points() + 0.5;
+                expected += games * p;
+                slope += games * p * (1.0 - p) * LN_10 / 400.0;
+            }
+            // Newton's method, with steps limited while far from the solution
+            let step = ((points - expected) / slope).clamp(-400.0, 400.0);
+            ratings[a] += step;
+            largest_step = largest_step.max(step.abs());
+        }
+        if largest_step < 0.01 {
+            break;
+        }
+    }
+    ratings
+}
+
+/// Returns the score a player rated `a` is expected to make per game against a player rated `b`.
+fn expected_score(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+/// Reference strategy completing its own lines, then blocking the opponent's, and playing randomly
+/// otherwise.
+#[derive(Debug)]
+struct GreedyPlayer {
+    rng: Mutex<StdRng>,
+}
+
+impl GreedyPlayer {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl Player for GreedyPlayer {
+    fn get_move(&self, grid: &Grid, mark: &Mark) -> Result<Move, PlayerError> {
+        let moves = grid.legal_moves();
+        let completing = |mark: Mark| {
+            moves.iter().copied().find(|&(row, col)| {
+                let mut after = *grid;
+                after.set_cell(row, col, mark);
+                after.get_winning_mark() == Some(mark)
+            })
+        };
+        let (row, col) = match completing(*mark).or_else(|| completing(mark.opposite())) {
+            Some(cell) => cell,
+            None => *moves
+                .choose(&mut *self.rng.lock().unwrap())
+                .ok_or(PlayerError::EndOfInput)?,
+        };
+        Ok(Move::Place(row, col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stronger_difficulties_are_rated_higher() {
+        let difficulties = [
+            BotPlayerDifficulty::Easy,
+            BotPlayerDifficulty::Normal,
+            BotPlayerDifficulty::Impossible,
+            BotPlayerDifficulty::Easy,
+        ];
+        let calibration = calibrate(&difficulties, 10, 0).unwrap();
+        assert_eq!(calibration.ratings().count(), 6);
+        assert_eq!(calibration.rating("Random"), Some(RANDOM_RATING));
+        let rating = |difficulty| calibration.difficulty_rating(difficulty).unwrap();
+        assert!(rating(BotPlayerDifficulty::Easy) < rating(BotPlayerDifficulty::Normal));
+        assert!(rating(BotPlayerDifficulty::Normal) < rating(BotPlayerDifficulty::Impossible));
+        assert!(calibration.rating("Greedy").unwrap() < calibration.rating("Perfect").unwrap());
+        assert_eq!(calibration, calibrate(&difficulties, 10, 0).unwrap());
+    }
+
+    #[test]
+    fn ratings_match_the_expected_scores() {
+        // The second entrant scores 3 out of 4 against the first, and the third never loses
+        let score = |wins, losses, draws| Score {
+            wins,
+            losses,
+            draws,
+        };
+        let scores = vec![
+            vec![score(0, 0, 0), score(1, 3, 0), score(0, 4, 0)],
+            vec![score(3, 1, 0), score(0, 0, 0), score(0, 4, 0)],
+            vec![score(4, 0, 0), score(4, 0, 0), score(0, 0, 0)],
+        ];
+        let ratings = fit_ratings(&scores);
+        assert_eq!(ratings[0], RANDOM_RATING);
+        assert!(ratings[0] < ratings[1] && ratings[1] < ratings[2]);
+        assert!(ratings[2].is_finite());
+        assert_eq!(estimated_rating(BotPlayerDifficulty::Easy), Some(790));
+        assert_eq!(estimated_rating(BotPlayerDifficulty::Strength(50)), None);
+    }
+}
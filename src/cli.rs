@@ -0,0 +1,508 @@
+//! Command-line arguments. Every setting left out is asked for interactively instead.
+
+use std::{fmt::Display, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+
+use tictactoe::{
+    calibration::CALIBRATION_GAMES,
+    game::RematchPolicy,
+    grid::{Grid, Mark},
+    lobby::RoomCode,
+    player::{BotPersonality, BotPlayerDifficulty},
+    training::ExportFormat,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Tic-tac-toe against local players, bots, or remote players"
+)]
+pub struct Cli {
+    /// Type of game to play, chosen interactively if left out
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Configuration file, instead of ~/.config/tictactoe/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Draw the board with ASCII characters
+    #[arg(long, global = true)]
+    pub ascii: bool,
+    /// Color the marks
+    #[arg(long, global = true, conflicts_with = "no_color")]
+    pub color: bool,
+    /// Don't color the marks
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    /// Play in a full-screen terminal UI, moving a cursor with the arrow keys
+    #[arg(long, global = true)]
+    pub tui: bool,
+    /// Number cells like a numpad, with 7-8-9 as the top row
+    #[arg(long, global = true)]
+    pub numpad: bool,
+    /// Draw each mark as a large ASCII-art glyph, easier to see from afar
+    #[arg(long, global = true)]
+    pub large: bool,
+    /// After a game, list its moves with the outcome each one left, flagging the blunders
+    #[arg(long, global = true)]
+    pub annotate: bool,
+    /// Characters X is drawn with, such as an emoji
+    #[arg(long, global = true, value_name = "SYMBOL")]
+    pub x_symbol: Option<String>,
+    /// Characters O is drawn with, such as an emoji
+    #[arg(long, global = true, value_name = "SYMBOL")]
+    pub o_symbol: Option<String>,
+    /// Report games as JSON lines on stdout, for scripts and bots driving the game
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Seed the bots and the random first player with, to replay the same games
+    #[arg(long, global = true, value_name = "N")]
+    pub seed: Option<u64>,
+    /// Append the record of each finished 2 player local game to this file, to replay it later
+    #[arg(long, global = true, value_name = "PATH")]
+    pub export: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Play on this machine, against another player or a bot
+    Local {
+        #[command(flatten)]
+        players: PlayerArgs,
+        /// Clear the screen between the turns of two human players sharing the terminal, and wait
+        /// for the next one to press Enter, so neither sees the other's hints
+        #[arg(long)]
+        pass_and_play: bool,
+    },
+    /// Play a 3 player game on a 5x5 board
+    ThreePlayer {
+        #[command(flatten)]
+        players: PlayerArgs,
+        /// Player for Y
+        #[arg(long, value_name = "PLAYER", value_parser = parse_player)]
+        y: Option<PlayerSpec>,
+    },
+    /// Play a 2v2 game, where team members alternate making their team's moves
+    Teams {
+        /// Both players of team X
+        #[arg(long = "x", value_name = "PLAYER", value_parser = parse_player, num_args = 2)]
+        team_x: Option<Vec<PlayerSpec>>,
+        /// Both players of team O
+        #[arg(long = "o", value_name = "PLAYER", value_parser = parse_player, num_args = 2)]
+        team_o: Option<Vec<PlayerSpec>>,
+        /// Mark playing first: x, o or random
+        #[arg(long, value_parser = parse_first)]
+        first: Option<First>,
+    },
+    /// Connect to a hosted game, looking for games on the local network if no address is given
+    Join {
+        address: Option<String>,
+        /// Password of the hosted game
+        #[arg(long)]
+        password: Option<String>,
+        /// Room code of the game, such as BLUE-FOX-42: looked up on the local network, or joined on
+        /// the dedicated server given as the address
+        #[arg(long)]
+        code: Option<RoomCode>,
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+    /// Host a game for a remote player to join
+    Host {
+        /// Address to listen on
+        #[arg(long, value_name = "ADDRESS")]
+        bind: Option<String>,
+        /// Mark played by the host
+        #[arg(long, value_parser = parse_mark, default_value = "X")]
+        mark: Mark,
+        /// Let the remote player move first
+        #[arg(long)]
+        second: bool,
+        /// How rematches are played: swap (the marks and first player), keep, alternate (the first
+        /// player only) or never
+        #[arg(long, value_parser = parse_rematch_policy, default_value = "swap")]
+        rematch: RematchPolicy,
+        /// Announce the game on the local network
+        #[arg(long, conflicts_with = "no_announce")]
+        announce: bool,
+        /// Don't announce the game on the local network
+        #[arg(long)]
+        no_announce: bool,
+        /// Minutes to wait for a player, 0 to wait until one connects
+        #[arg(long, value_name = "MINUTES")]
+        wait: Option<usize>,
+        /// Password the remote player must join with
+        #[arg(long)]
+        password: Option<String>,
+        /// Let the remote player find the game on the local network with a room code, generated
+        /// if left out
+        #[arg(long, value_name = "CODE", num_args = 0..=1)]
+        code: Option<Option<RoomCode>>,
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+    /// Resume a saved game, by hosting it or reconnecting to its host
+    Resume {
+        /// Saved game file
+        #[arg(long, value_name = "PATH")]
+        file: Option<String>,
+        /// Host the game, listening on this address
+        #[arg(long, value_name = "ADDRESS", conflicts_with = "join")]
+        host: Option<String>,
+        /// Reconnect to the game's host at this address
+        #[arg(long, value_name = "ADDRESS")]
+        join: Option<String>,
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+    /// Enter a dedicated server's lobby, to open a game or choose one to join
+    Lobby {
+        address: Option<String>,
+        /// Name shown to the other players
+        #[arg(long)]
+        name: Option<String>,
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+    /// Create an account on a dedicated server, which the lobby and room commands then log in to
+    Account {
+        address: Option<String>,
+        /// Name of the account: letters, digits, dashes or underscores
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Watch a hosted game
+    Spectate { address: Option<String> },
+    /// Join a room on a dedicated server, to play against the player joining it with the same code
+    Room {
+        address: Option<String>,
+        /// Room code, shared with your opponent
+        #[arg(long)]
+        code: Option<RoomCode>,
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+    /// Watch two bots play each other, waiting between their moves
+    Watch {
+        /// Bot playing X: mirror, tuned or bot:<difficulty>[:<personality>], such as bot:impossible
+        #[arg(long, value_name = "BOT", value_parser = parse_bot)]
+        x: Option<PlayerSpec>,
+        /// Bot playing O, in the same format as --x
+        #[arg(long, value_name = "BOT", value_parser = parse_bot)]
+        o: Option<PlayerSpec>,
+        /// Mark playing first: x, o or random
+        #[arg(long, value_parser = parse_first)]
+        first: Option<First>,
+        /// Milliseconds each bot waits before moving
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        delay: u64,
+    },
+    /// Learn by playing X against a bot, with the threats on the board explained after each of your
+    /// moves and a warning before mistakes
+    Tutorial {
+        /// Bot playing O: mirror, tuned or bot:<difficulty>[:<personality>], such as bot:normal
+        #[arg(long, value_name = "BOT", value_parser = parse_bot)]
+        bot: Option<PlayerSpec>,
+        /// Mark playing first: x, o or random
+        #[arg(long, value_parser = parse_first)]
+        first: Option<First>,
+    },
+    /// Play bots against each other and show their standings
+    Tournament {
+        /// Bot entering the tournament, given once per entrant: mirror, tuned or
+        /// bot:<difficulty>[:<personality>]
+        #[arg(long = "bot", value_name = "BOT", value_parser = parse_bot, required = true)]
+        bots: Vec<PlayerSpec>,
+        /// Games played by each pair of bots, alternating who plays first
+        #[arg(long, default_value_t = 10)]
+        games: usize,
+        /// Play a knockout bracket instead of a round-robin
+        #[arg(long)]
+        knockout: bool,
+        /// Also print how each bot played: its openings, game length, forks and blunders
+        #[arg(long)]
+        stats: bool,
+    },
+    /// Estimate each bot difficulty's rating by playing it against reference strategies
+    Calibrate {
+        /// Difficulty to rate, given once per difficulty: easy, normal, impossible, a percentage
+        /// of optimal moves, or noisy-<percentage of second-best moves>. Easy, normal, noisy-10
+        /// and impossible by default
+        #[arg(long = "difficulty", value_name = "DIFFICULTY", value_parser = parse_difficulty)]
+        difficulties: Vec<BotPlayerDifficulty>,
+        /// Games played by each pair of players, alternating who plays first
+        #[arg(long, default_value_t = CALIBRATION_GAMES)]
+        games: usize,
+    },
+    /// Tune the weights of the tuned bot by self-play, and save them for players given as tuned
+    Tune {
+        /// Rounds of candidate weights to try
+        #[arg(long, default_value_t = 20)]
+        generations: usize,
+        /// Candidate weights tried each generation
+        #[arg(long, default_value_t = 4)]
+        candidates: usize,
+        /// Games played by each pair of entrants each generation, alternating who plays first
+        #[arg(long, default_value_t = 10)]
+        games: usize,
+        /// File to start from and save the weights to, instead of the tictactoe data directory
+        #[arg(long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Export positions of self-play games with the perfect engine's best move and the outcome, to
+    /// train external models
+    TrainingData {
+        /// Games to play
+        #[arg(long, default_value_t = 1000)]
+        games: usize,
+        /// Percentage of moves played randomly instead of the best move, so games aren't all
+        /// draws
+        #[arg(long, value_name = "PERCENTAGE", default_value_t = 20)]
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        exploration: u8,
+        /// Format of the samples: csv or jsonl
+        #[arg(long, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// File to write the samples to, instead of the standard output
+        #[arg(long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Step through a game saved with --export, one move at a time
+    Replay {
+        /// File the game was exported to
+        file: PathBuf,
+        /// Number of the game in the file, starting from 1. The last game by default
+        #[arg(long, value_name = "N")]
+        game: Option<usize>,
+    },
+    /// Show the outcome of a position with perfect play, its best moves and the principal
+    /// variation
+    Analyze {
+        /// Rows separated by '/' with X, O and . for empty cells, then the mark to move, such as
+        /// "X.O/.X./..O O". The mark can be left out when the number of each mark tells it
+        #[arg(value_parser = parse_position)]
+        position: Position,
+    },
+    /// Show totals, win rates and streaks of the games played against bots or remote players
+    Stats,
+}
+
+/// Position given to the analyze command.
+#[derive(Debug, Clone, Copy)]
+pub struct Position {
+    pub grid: Grid,
+    /// Mark about to play
+    pub mark: Mark,
+}
+
+/// Players of a local game with X and O.
+#[derive(Debug, Clone, Default, Args)]
+pub struct PlayerArgs {
+    /// Player for X: human, mirror, tuned, or bot:<difficulty>[:<personality>] such as
+    /// bot:impossible or bot:70:corner-lover
+    #[arg(long, value_name = "PLAYER", value_parser = parse_player)]
+    pub x: Option<PlayerSpec>,
+    /// Player for O, in the same format as --x
+    #[arg(long, value_name = "PLAYER", value_parser = parse_player)]
+    pub o: Option<PlayerSpec>,
+    /// Mark playing first: x, o, y or random
+    #[arg(long, value_parser = parse_first)]
+    pub first: Option<First>,
+}
+
+/// Settings of networked games.
+#[derive(Debug, Clone, Copy, Default, Args)]
+pub struct NetworkArgs {
+    /// Minutes on each player's clock, 0 for untimed. Must match your opponent's
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u16).range(0..=120))]
+    pub clock: Option<u16>,
+}
+
+/// Player of a local game: human, or a bot of the given strength and style.
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerSpec {
+    Human,
+    /// Novelty bot playing the reflection of its opponent's last move, see
+    /// [`MirrorPlayer`](tictactoe::player::MirrorPlayer)
+    Mirror,
+    /// Bot playing with the weights saved by `tictactoe tune`, see
+    /// [`TunedBot`](tictactoe::tuning::TunedBot)
+    Tuned,
+    Bot {
+        difficulty: BotPlayerDifficulty,
+        personality: BotPersonality,
+    },
+}
+
+/// Written like players are given on the command line, leaving out the balanced personality.
+impl Display for PlayerSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Human => write!(f, "human"),
+            Self::Mirror => write!(f, "mirror"),
+            Self::Tuned => write!(f, "tuned"),
+            Self::Bot {
+                difficulty,
+                personality,
+            } => {
+                write!(f, "bot:{}", format_difficulty(*difficulty))?;
+                match personality {
+                    BotPersonality::Balanced => Ok(()),
+                    BotPersonality::CenterHugger => write!(f, ":center-hugger"),
+                    BotPersonality::CornerLover => write!(f, ":corner-lover"),
+                    BotPersonality::ForkSeeker => write!(f, ":fork-seeker"),
+                    BotPersonality::Blocker => write!(f, ":blocker"),
+                }
+            }
+        }
+    }
+}
+
+/// Player moving first in a local game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum First {
+    Mark(Mark),
+    Random,
+}
+
+const PLAYER_FORMAT: &str = "expected human, mirror, tuned or bot:<difficulty>[:<personality>]";
+
+fn parse_player(s: &str) -> Result<PlayerSpec, String> {
+    let mut parts = s.split(':');
+    match parts.next().unwrap_or_default() {
+        "human" if parts.next().is_none() => return Ok(PlayerSpec::Human),
+        "mirror" if parts.next().is_none() => return Ok(PlayerSpec::Mirror),
+        "tuned" if parts.next().is_none() => return Ok(PlayerSpec::Tuned),
+        "bot" => {}
+        _ => return Err(PLAYER_FORMAT.to_owned()),
+    }
+
+    let difficulty = match parts.next() {
+        Some(difficulty) => parse_difficulty(difficulty)?,
+        None => return Err("expected a bot difficulty, such as bot:normal".to_owned()),
+    };
+    let personality = match parts.next() {
+        None | Some("balanced") => BotPersonality::Balanced,
+        Some("center-hugger") => BotPersonality::CenterHugger,
+        Some("corner-lover") => BotPersonality::CornerLover,
+        Some("fork-seeker") => BotPersonality::ForkSeeker,
+        Some("blocker") => BotPersonality::Blocker,
+        Some(_) => {
+            return Err(
+                "bot personalities are balanced, center-hugger, corner-lover, \
+                 fork-seeker and blocker"
+                    .to_owned(),
+            )
+        }
+    };
+    if parts.next().is_some() {
+        return Err(PLAYER_FORMAT.to_owned());
+    }
+    Ok(PlayerSpec::Bot {
+        difficulty,
+        personality,
+    })
+}
+
+/// Parses a grid followed by the mark to move, inferring the mark from the number of each mark
+/// when it is left out. X moves first in a grid with as many X as O.
+fn parse_position(s: &str) -> Result<Position, String> {
+    let mut parts = s.split_whitespace();
+    let grid: Grid = parts
+        .next()
+        .ok_or("expected a grid such as X.O/.X./..O")?
+        .parse()
+        .map_err(|e: tictactoe::grid::GridParseError| e.to_string())?;
+    let mark = match parts.next().map(str::to_ascii_lowercase).as_deref() {
+        None => None,
+        Some("x") => Some(Mark::X),
+        Some("o") => Some(Mark::O),
+        Some(_) => return Err("the mark to move is either X or O".to_owned()),
+    };
+    if parts.next().is_some() {
+        return Err("expected a grid and the mark to move, such as \"X.O/.X./..O O\"".to_owned());
+    }
+
+    let count = |mark: Mark| {
+        (0..9)
+            .filter(|i| grid.get_cell(i / 3, i % 3).try_get_mark() == Some(&mark))
+            .count() as isize
+    };
+    let mark = match (count(Mark::X) - count(Mark::O), mark) {
+        (0, mark) => mark.unwrap_or(Mark::X),
+        (1, None | Some(Mark::O)) => Mark::O,
+        (-1, None | Some(Mark::X)) => Mark::X,
+        (-1..=1, Some(mark)) => return Err(format!("{} can't be about to play", mark)),
+        _ => return Err("players can't have more than 1 mark more than the other".to_owned()),
+    };
+    Ok(Position { grid, mark })
+}
+
+fn parse_bot(s: &str) -> Result<PlayerSpec, String> {
+    match parse_player(s)? {
+        PlayerSpec::Human => {
+            Err("expected mirror, tuned or bot:<difficulty>[:<personality>]".to_owned())
+        }
+        bot => Ok(bot),
+    }
+}
+
+/// Parses a bot difficulty: easy, normal, impossible, a percentage of optimal moves, or noisy-
+/// followed by a percentage of second-best moves.
+pub fn parse_difficulty(s: &str) -> Result<BotPlayerDifficulty, String> {
+    let percentage = |s: &str| match s.parse() {
+        Ok(percentage @ 0..=100) => Ok(percentage),
+        _ => Err(
+            "bot difficulties are easy, normal, impossible, a percentage, or noisy-<percentage>"
+                .to_owned(),
+        ),
+    };
+    match s {
+        "easy" => Ok(BotPlayerDifficulty::Easy),
+        "normal" => Ok(BotPlayerDifficulty::Normal),
+        "impossible" => Ok(BotPlayerDifficulty::Impossible),
+        noisy if noisy.starts_with("noisy-") => {
+            percentage(&noisy["noisy-".len()..]).map(BotPlayerDifficulty::Noisy)
+        }
+        strength => percentage(strength).map(BotPlayerDifficulty::Strength),
+    }
+}
+
+/// Writes a bot difficulty the way [`parse_difficulty`] reads it.
+pub fn format_difficulty(difficulty: BotPlayerDifficulty) -> String {
+    match difficulty {
+        BotPlayerDifficulty::Easy => "easy".to_owned(),
+        BotPlayerDifficulty::Normal => "normal".to_owned(),
+        BotPlayerDifficulty::Impossible => "impossible".to_owned(),
+        BotPlayerDifficulty::Strength(strength) => strength.to_string(),
+        BotPlayerDifficulty::Noisy(error_rate) => format!("noisy-{}", error_rate),
+    }
+}
+
+fn parse_mark(s: &str) -> Result<Mark, String> {
+    match s.to_lowercase().as_str() {
+        "x" => Ok(Mark::X),
+        "o" => Ok(Mark::O),
+        _ => Err("expected X or O".to_owned()),
+    }
+}
+
+fn parse_rematch_policy(s: &str) -> Result<RematchPolicy, String> {
+    match s.to_lowercase().as_str() {
+        "swap" => Ok(RematchPolicy::Swap),
+        "keep" => Ok(RematchPolicy::Keep),
+        "alternate" => Ok(RematchPolicy::Alternate),
+        "never" => Ok(RematchPolicy::Never),
+        _ => Err("expected swap, keep, alternate or never".to_owned()),
+    }
+}
+
+fn parse_first(s: &str) -> Result<First, String> {
+    match s.to_lowercase().as_str() {
+        "random" => Ok(First::Random),
+        "y" => Ok(First::Mark(Mark::Y)),
+        mark => parse_mark(mark)
+            .map(First::Mark)
+            .map_err(|_| "expected X, O, Y or random".to_owned()),
+    }
+}
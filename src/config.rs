@@ -0,0 +1,162 @@
+//! Defaults loaded from the configuration file, so frequent players don't have to answer the same
+//! prompts every game. Command-line arguments take precedence over the file.
+//!
+//! The file is `$XDG_CONFIG_HOME/tictactoe/config.toml`, or `~/.config/tictactoe/config.toml`,
+//! unless another path is given with `--config`. Every setting is optional:
+//!
+//! ```toml
+//! bind = "0.0.0.0:8905"         # address hosted games listen on
+//! bot_difficulty = "impossible" # easy, normal, impossible, a percentage, or noisy-<percentage>
+//! unicode = true                # draw the board with box drawing characters
+//! color = false                 # color the marks
+//! nickname = "Player"           # name shown in a dedicated server's lobby and to remote players
+//! tui = false                   # play in the full-screen UI, with the `tui` feature
+//! numpad = false                # number cells like a numpad, with 7-8-9 as the top row
+//! large = false                 # draw each mark as a large ASCII-art glyph
+//! annotate = false              # list the moves with their outcome after a game, flagging blunders
+//! x_symbol = "X"                # characters X is drawn with, which can be an emoji
+//! o_symbol = "O"                # characters O is drawn with
+//! ```
+
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use tictactoe::{
+    grid::Mark,
+    player::{BotPlayerDifficulty, CellNumbering},
+};
+
+use crate::{cli, symbols::Symbols};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    bind: Option<String>,
+    bot_difficulty: Option<String>,
+    unicode: Option<bool>,
+    color: Option<bool>,
+    nickname: Option<String>,
+    tui: Option<bool>,
+    numpad: Option<bool>,
+    large: Option<bool>,
+    annotate: Option<bool>,
+    x_symbol: Option<String>,
+    o_symbol: Option<String>,
+}
+
+/// Settings of the front-end. Settings left out of the file are asked for interactively.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub bind: Option<String>,
+    pub bot_difficulty: Option<BotPlayerDifficulty>,
+    pub unicode: bool,
+    pub color: bool,
+    pub nickname: Option<String>,
+    pub tui: bool,
+    pub numpad: bool,
+    pub large: bool,
+    pub annotate: bool,
+    pub symbols: Symbols,
+    /// Whether games are reported as JSON lines, only set from the command line
+    pub json: bool,
+    /// Seed of the bots' random choices, only set from the command line
+    pub seed: Option<u64>,
+    /// File finished games are appended to, only set from the command line
+    pub export: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads the file at `path`, or at the default path if `None`. A missing file at the default
+    /// path gives the default settings.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_owned(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound && !required => return Ok(Self::default()),
+            Err(e) => return Err(format!("Error reading {}: {}", path.display(), e)),
+        };
+        let file: ConfigFile = toml::from_str(&contents)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+        let bot_difficulty = file
+            .bot_difficulty
+            .as_deref()
+            .map(cli::parse_difficulty)
+            .transpose()
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+        let symbols = match (file.x_symbol, file.o_symbol) {
+            (None, None) => Symbols::default(),
+            (x, o) => Symbols::new(
+                x.unwrap_or_else(|| Mark::X.to_string()),
+                o.unwrap_or_else(|| Mark::O.to_string()),
+            )
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?,
+        };
+
+        let default = Self::default();
+        Ok(Self {
+            bind: file.bind,
+            bot_difficulty,
+            unicode: file.unicode.unwrap_or(default.unicode),
+            color: file.color.unwrap_or(default.color),
+            nickname: file.nickname,
+            tui: file.tui.unwrap_or(default.tui),
+            numpad: file.numpad.unwrap_or(default.numpad),
+            large: file.large.unwrap_or(default.large),
+            annotate: file.annotate.unwrap_or(default.annotate),
+            symbols,
+            json: default.json,
+            seed: default.seed,
+            export: default.export,
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind: None,
+            bot_difficulty: None,
+            unicode: true,
+            color: false,
+            nickname: None,
+            tui: false,
+            numpad: false,
+            large: false,
+            annotate: false,
+            symbols: Symbols::default(),
+            json: false,
+            seed: None,
+            export: None,
+        }
+    }
+}
+
+impl Config {
+    /// Returns how human players number cells.
+    pub fn numbering(&self) -> CellNumbering {
+        if self.numpad {
+            CellNumbering::Numpad
+        } else {
+            CellNumbering::Rows
+        }
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_dir.join("tictactoe").join("config.toml"))
+}
@@ -0,0 +1,189 @@
+//! Discovery of games hosted on the local network. Clients broadcast a probe over UDP, and hosts
+//! announcing their game reply with the port it is hosted on. Clients looking for a game by its
+//! room code broadcast a room probe instead, which only the host of that game replies to.
+
+use std::{
+    collections::BTreeSet,
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    lobby::RoomCode,
+    protocol::{self, DiscoveryProbe, HostAnnouncement, RoomProbe},
+};
+
+/// UDP port hosts listen on for discovery probes.
+pub const DISCOVERY_PORT: u16 = 8906;
+
+/// How often the announcing thread checks whether it should stop.
+const ANNOUNCER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Answers discovery probes on a background thread, until dropped.
+#[derive(Debug)]
+pub(crate) struct Announcer {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Announcer {
+    /// Answers the probes broadcast on the local network, announcing a game hosted on
+    /// `game_port`. Plain probes are only answered if the game is `listed`, and room probes if they
+    /// look for its room `code`.
+    pub(crate) fn on_local_network(
+        game_port: u16,
+        listed: bool,
+        code: Option<RoomCode>,
+    ) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))?;
+        Self::new(socket, game_port, listed, code)
+    }
+
+    /// Answers the probes received on `socket`.
+    fn new(
+        socket: UdpSocket,
+        game_port: u16,
+        listed: bool,
+        code: Option<RoomCode>,
+    ) -> io::Result<Self> {
+        socket.set_read_timeout(Some(ANNOUNCER_POLL_INTERVAL))?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread_stopped = Arc::clone(&stopped);
+        thread::spawn(move || {
+            let reply = HostAnnouncement { port: game_port }.to_bytes();
+            let mut buf = [0_u8; 64];
+            while !thread_stopped.load(Ordering::Relaxed) {
+                let Ok((len, from)) = socket.recv_from(&mut buf) else {
+                    continue;
+                };
+                let answered = if listed && parse(&buf[..len], DiscoveryProbe::try_from).is_some() {
+                    true
+                } else {
+                    // Codes are compared normalized, in case a probe holds one as it was typed
+                    parse(&buf[..len], RoomProbe::try_from)
+                        .and_then(|RoomProbe(probed)| probed.parse::<RoomCode>().ok())
+                        .is_some_and(|probed| Some(probed) == code)
+                };
+                if answered {
+                    // The client may already be gone, which doesn't matter to other clients
+                    let _ = socket.send_to(&reply, from);
+                }
+            }
+        });
+
+        Ok(Self { stopped })
+    }
+}
+
+impl Drop for Announcer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sends a probe to `target`, and returns the address of every game announced in reply within
+/// `timeout`, in order.
+pub(crate) fn discover(target: SocketAddr, timeout: Duration) -> io::Result<Vec<SocketAddr>> {
+    probe(target, &DiscoveryProbe.to_bytes(), timeout, usize::MAX)
+}
+
+/// Sends a room probe for `code` to `target`, and returns the address of the first game announced
+/// in reply within `timeout`.
+pub(crate) fn find_room(
+    target: SocketAddr,
+    code: &RoomCode,
+    timeout: Duration,
+) -> io::Result<Option<SocketAddr>> {
+    let probe = RoomProbe(code.to_string()).to_bytes();
+    Ok(self::probe(target, &probe, timeout, 1)?.pop())
+}
+
+/// Sends `probe` to `target`, and returns the address of the games announced in reply, until
+/// `limit` of them replied or `timeout` is over.
+fn probe(
+    target: SocketAddr,
+    probe: &[u8],
+    timeout: Duration,
+    limit: usize,
+) -> io::Result<Vec<SocketAddr>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+    socket.send_to(probe, target)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut hosts = BTreeSet::new();
+    let mut buf = [0_u8; 16];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || hosts.len() >= limit {
+            return Ok(hosts.into_iter().collect());
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                if let Some(pkt) = parse(&buf[..len], HostAnnouncement::try_from) {
+                    hosts.insert(SocketAddr::new(from.ip(), pkt.port));
+                }
+            }
+            // Timeouts are reported as either kind depending on the platform, and a probe sent
+            // directly to a host that stopped announcing may be refused
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::WouldBlock
+                        | ErrorKind::TimedOut
+                        | ErrorKind::ConnectionRefused
+                        | ErrorKind::ConnectionReset
+                ) => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Parses a datagram, which holds a single packet with its checksum and terminator.
+fn parse<'a, T, E>(datagram: &'a [u8], parse: impl Fn(&'a [u8]) -> Result<T, E>) -> Option<T> {
+    let pkt = protocol::unframe(datagram).ok()?;
+    parse(pkt).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announced_games_are_discovered() {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = socket.local_addr().unwrap();
+        let announcer = Announcer::new(socket, 8905, true, None).expect("Error announcing game");
+        let hosts = discover(addr, Duration::from_millis(100)).expect("Error discovering games");
+        assert_eq!(hosts, ["127.0.0.1:8905".parse().unwrap()]);
+
+        drop(announcer);
+        thread::sleep(ANNOUNCER_POLL_INTERVAL * 2);
+        let hosts = discover(addr, Duration::from_millis(100)).expect("Error discovering games");
+        assert!(hosts.is_empty());
+    }
+
+    #[test]
+    fn games_are_found_by_room_code() {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = socket.local_addr().unwrap();
+        let code: RoomCode = "BLUE-FOX-42".parse().unwrap();
+        let _announcer =
+            Announcer::new(socket, 8905, false, Some(code)).expect("Error announcing game");
+
+        let timeout = Duration::from_millis(100);
+        let found = find_room(addr, &"blue fox 42".parse().unwrap(), timeout).unwrap();
+        assert_eq!(found, Some("127.0.0.1:8905".parse().unwrap()));
+        let other = find_room(addr, &"RED-FOX-42".parse().unwrap(), timeout).unwrap();
+        assert_eq!(other, None);
+        // Unlisted games are only found by their code
+        assert!(discover(addr, timeout).unwrap().is_empty());
+    }
+}
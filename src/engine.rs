@@ -0,0 +1,178 @@
+//! Players backed by an external program, see [`EngineProcessPlayer`].
+//!
+//! # Protocol
+//! Engines read commands from stdin and answer on stdout, one line each. Positions are written as
+//! the 9 cells of the grid in reading order, using `X`, `O` and `.` for empty cells, followed by
+//! the mark the engine plays with.
+//!
+//! | Command                  | Expected answer                                    |
+//! |--------------------------|----------------------------------------------------|
+//! | `go <position> <mark>`   | `move <row> <col>` (0 indexed), `draw` or `resign` |
+//! | `draw <position> <mark>` | `accept` or `decline`                              |
+//! | `quit`                   | None, the engine should exit                       |
+//!
+//! For example, `go X...O.... X` asks X for a move after X played in the top left corner and O in
+//! the center, and `move 0 2` answers with the top right corner.
+
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::Mutex,
+};
+
+use crate::{
+    grid::{Grid, Mark},
+    player::{Move, Player, PlayerError},
+};
+
+/// Player delegating its moves to an external engine process, using the line protocol described in
+/// the [module documentation](self).
+#[derive(Debug)]
+pub struct EngineProcessPlayer {
+    child: Child,
+    pipes: Mutex<(BufReader<ChildStdout>, ChildStdin)>,
+}
+
+impl EngineProcessPlayer {
+    /// Spawns the engine. Its stdin and stdout are replaced by pipes, stderr is inherited.
+    pub fn spawn(mut command: Command) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdin = child.stdin.take().expect("Engine stdin is piped");
+        let stdout = child.stdout.take().expect("Engine stdout is piped");
+        Ok(Self {
+            child,
+            pipes: Mutex::new((BufReader::new(stdout), stdin)),
+        })
+    }
+
+    /// Sends `command` to the engine and returns its answer, without the trailing newline.
+    fn request(&self, command: &str) -> Result<String, PlayerError> {
+        let mut pipes = self.pipes.lock().unwrap();
+        let (reader, writer) = &mut *pipes;
+        writeln!(writer, "{}", command)?;
+        writer.flush()?;
+
+        let mut answer = String::new();
+        if reader.read_line(&mut answer)? == 0 {
+            return Err(PlayerError::EndOfInput);
+        }
+        Ok(answer.trim().to_owned())
+    }
+}
+
+impl Drop for EngineProcessPlayer {
+    fn drop(&mut self) {
+        if let Ok((_, writer)) = self.pipes.get_mut() {
+            let _ = writeln!(writer, "quit").and_then(|_| writer.flush());
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Player for EngineProcessPlayer {
+    fn get_move(&self, grid: &Grid, mark: &Mark) -> Result<Move, PlayerError> {
+        let answer = self.request(&format!("go {} {}", encode_position(grid), mark))?;
+        parse_move(&answer).ok_or_else(|| invalid_answer(&answer))
+    }
+
+    fn accept_draw(&self, grid: &Grid, mark: &Mark) -> Result<bool, PlayerError> {
+        let answer = self.request(&format!("draw {} {}", encode_position(grid), mark))?;
+        match answer.as_str() {
+            "accept" => Ok(true),
+            "decline" => Ok(false),
+            _ => Err(invalid_answer(&answer)),
+        }
+    }
+}
+
+fn encode_position(grid: &Grid) -> String {
+    grid.rows()
+        .flatten()
+        .map(|cell| match cell.try_get_mark() {
+            Some(mark) => mark.to_string(),
+            None => ".".to_owned(),
+        })
+        .collect()
+}
+
+fn parse_move(answer: &str) -> Option<Move> {
+    match answer.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["move", row, col] => Some(Move::Place(row.parse().ok()?, col.parse().ok()?)),
+        ["draw"] => Some(Move::OfferDraw),
+        ["resign"] => Some(Move::Resign),
+        _ => None,
+    }
+}
+
+fn invalid_answer(answer: &str) -> PlayerError {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("unexpected engine answer: {:?}", answer),
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_position_uses_reading_order() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+        grid.set_cell(2, 1, Mark::X);
+        assert_eq!(encode_position(&grid), "X...O..X.");
+    }
+
+    #[test]
+    fn parse_move_reads_answers() {
+        assert_eq!(parse_move("move 2 1"), Some(Move::Place(2, 1)));
+        assert_eq!(parse_move("resign"), Some(Move::Resign));
+        assert_eq!(parse_move("draw"), Some(Move::OfferDraw));
+        assert_eq!(parse_move("move 2"), None);
+        assert_eq!(parse_move("move a b"), None);
+    }
+
+    #[cfg(unix)]
+    fn shell_engine(script: &str) -> EngineProcessPlayer {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        EngineProcessPlayer::spawn(command).expect("Error spawning engine")
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn engine_process_plays_moves() {
+        let engine = shell_engine(
+            r#"while read cmd pos mark; do
+                case "$cmd" in
+                    go) echo "move 1 2" ;;
+                    draw) echo accept ;;
+                    quit) exit 0 ;;
+                esac
+            done"#,
+        );
+
+        let grid = Grid::default();
+        assert_eq!(engine.get_move(&grid, &Mark::X).unwrap(), Move::Place(1, 2));
+        assert!(engine.accept_draw(&grid, &Mark::O).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn engine_process_reports_invalid_answers() {
+        let engine = shell_engine("read line; echo nonsense; read line");
+        let err = engine.get_move(&Grid::default(), &Mark::X).unwrap_err();
+        assert!(matches!(err, PlayerError::Io(e) if e.kind() == ErrorKind::InvalidData));
+
+        let engine = shell_engine("exit 0");
+        let err = engine.get_move(&Grid::default(), &Mark::X).unwrap_err();
+        assert!(matches!(err, PlayerError::EndOfInput | PlayerError::Io(_)));
+    }
+}
@@ -0,0 +1,59 @@
+//! [`Error`], wrapping the errors of every module.
+
+use std::io;
+
+use thiserror::Error;
+
+#[cfg(feature = "network")]
+use crate::game::{NetworkedGameError, PacketParseError};
+use crate::{
+    board::Board,
+    game::{MoveError, TurnError},
+    grid::{GridParseError, GridPlacementError, Mark},
+    player::PlayerError,
+};
+
+/// Any error returned by the crate, for applications handling them in one place. The error of each
+/// module converts into it with `?`.
+///
+/// ```
+/// # use tictactoe::{grid::{Grid, Mark}, Error};
+/// fn play(position: &str) -> Result<Grid, Error> {
+///     let mut grid: Grid = position.parse()?;
+///     grid.try_set_cell(1, 1, Mark::X)?;
+///     Ok(grid)
+/// }
+/// assert!(play("X../.../...").is_ok());
+/// assert!(matches!(play("X../.O./..."), Err(Error::Placement(_))));
+/// assert!(matches!(play("X.."), Err(Error::GridParse(_))));
+/// ```
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Placement(#[from] GridPlacementError),
+    #[error(transparent)]
+    GridParse(#[from] GridParseError),
+    #[error(transparent)]
+    Player(#[from] PlayerError),
+    /// The turn of the player with this mark couldn't be played, see [`MoveError`].
+    #[error("Player {mark} couldn't play their turn: {source}")]
+    Turn { mark: Mark, source: TurnError },
+    #[cfg(feature = "network")]
+    #[error(transparent)]
+    Network(#[from] NetworkedGameError),
+    #[cfg(feature = "network")]
+    #[error(transparent)]
+    Packet(#[from] PacketParseError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Drops the game, which can't be recovered from the converted error.
+impl<B: Board> From<MoveError<B>> for Error {
+    fn from(value: MoveError<B>) -> Self {
+        Self::Turn {
+            mark: value.mark(),
+            source: value.into_error(),
+        }
+    }
+}
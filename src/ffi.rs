@@ -0,0 +1,280 @@
+//! C bindings, to embed the engine in C, C++ or Swift front-ends. Only available with the `ffi`
+//! feature. The functions are declared in `include/tictactoe.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/tictactoe.h`.
+//!
+//! Games are created with [`ttt_game_new`] and must be freed with [`ttt_game_free`]. Functions
+//! taking a game accept null pointers, reporting [`TttStatus::NullGame`] or returning a neutral
+//! value.
+
+use crate::{
+    game::{self, GameResult},
+    grid::{Grid, GridPlacementError, Mark},
+    player::{BotPlayer, BotPlayerDifficulty, Move, Player},
+};
+
+/// Local game on a [`Grid`], only used through pointers returned by [`ttt_game_new`]. `X` plays
+/// first, and moves are placed for the mark whose turn it is.
+#[derive(Debug)]
+pub struct TttGame {
+    grid: Grid,
+    turn: Mark,
+    result: Option<GameResult>,
+}
+
+/// Mark on a cell, or `TTT_MARK_NONE` for an empty cell or no winner.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TttMark {
+    None = 0,
+    X = 1,
+    O = 2,
+    Y = 3,
+}
+
+impl From<Option<Mark>> for TttMark {
+    fn from(value: Option<Mark>) -> Self {
+        match value {
+            None => Self::None,
+            Some(Mark::X) => Self::X,
+            Some(Mark::O) => Self::O,
+            Some(Mark::Y) => Self::Y,
+        }
+    }
+}
+
+/// Outcome of the functions playing or choosing a move.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TttStatus {
+    Ok = 0,
+    /// The game pointer, or an output pointer, is null.
+    NullGame,
+    /// The game already has a result.
+    GameOver,
+    CellInUse,
+    OutOfBounds,
+    /// The difficulty isn't one of the `TttDifficulty` values.
+    UnknownDifficulty,
+    /// The bot didn't choose a move to place.
+    NoMove,
+}
+
+impl From<GridPlacementError> for TttStatus {
+    fn from(value: GridPlacementError) -> Self {
+        match value {
+            GridPlacementError::CellInUse { .. } => Self::CellInUse,
+            GridPlacementError::OutOfBounds { .. } => Self::OutOfBounds,
+        }
+    }
+}
+
+/// Difficulty of the bot of [`ttt_bot_move`], which takes it as a plain integer so that values
+/// unknown to this version of the library are reported instead of being undefined behavior.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TttDifficulty {
+    Easy = 0,
+    Normal = 1,
+    Impossible = 2,
+}
+
+impl TryFrom<u32> for TttDifficulty {
+    type Error = TttStatus;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Easy),
+            1 => Ok(Self::Normal),
+            2 => Ok(Self::Impossible),
+            _ => Err(TttStatus::UnknownDifficulty),
+        }
+    }
+}
+
+impl From<TttDifficulty> for BotPlayerDifficulty {
+    fn from(value: TttDifficulty) -> Self {
+        match value {
+            TttDifficulty::Easy => Self::Easy,
+            TttDifficulty::Normal => Self::Normal,
+            TttDifficulty::Impossible => Self::Impossible,
+        }
+    }
+}
+
+/// Creates a game on an empty grid, with `X` to play. It must be freed with [`ttt_game_free`].
+#[no_mangle]
+pub extern "C" fn ttt_game_new() -> *mut TttGame {
+    Box::into_raw(Box::new(TttGame {
+        grid: Grid::default(),
+        turn: Mark::X,
+        result: None,
+    }))
+}
+
+/// Frees a game created with [`ttt_game_new`]. Does nothing if `game` is null.
+///
+/// # Safety
+///
+/// `game` must be null or returned by [`ttt_game_new`], and not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_free(game: *mut TttGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Places the mark whose turn it is on the cell, and hands the turn to the other mark. The game is
+/// left unchanged unless `TTT_STATUS_OK` is returned.
+///
+/// # Safety
+///
+/// `game` must be null or a live game returned by [`ttt_game_new`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_play(game: *mut TttGame, row: usize, col: usize) -> TttStatus {
+    let Some(game) = game.as_mut() else {
+        return TttStatus::NullGame;
+    };
+    if game.result.is_some() {
+        return TttStatus::GameOver;
+    }
+    if let Err(e) = game.grid.try_set_cell(row, col, game.turn) {
+        return e.into();
+    }
+    game.turn = game.turn.opposite();
+    game.result = game::board_result(&game.grid);
+    TttStatus::Ok
+}
+
+/// Writes the move a bot of the given difficulty, one of the `TttDifficulty` values, would play
+/// for the mark whose turn it is to `row` and `col`, without playing it.
+///
+/// # Safety
+///
+/// `game` must be null or a live game returned by [`ttt_game_new`], and `row` and `col` null or
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_bot_move(
+    game: *const TttGame,
+    difficulty: u32,
+    row: *mut usize,
+    col: *mut usize,
+) -> TttStatus {
+    let (Some(game), Some(row), Some(col)) = (game.as_ref(), row.as_mut(), col.as_mut()) else {
+        return TttStatus::NullGame;
+    };
+    if game.result.is_some() {
+        return TttStatus::GameOver;
+    }
+    let difficulty = match TttDifficulty::try_from(difficulty) {
+        Ok(difficulty) => difficulty,
+        Err(status) => return status,
+    };
+
+    let bot = BotPlayer::from_difficulty(difficulty.into());
+    match bot.get_move(&game.grid, &game.turn) {
+        Ok(Move::Place(r, c)) => {
+            (*row, *col) = (r, c);
+            TttStatus::Ok
+        }
+        // Bots place a mark on games in progress, but panicking here would abort the C caller
+        _ => TttStatus::NoMove,
+    }
+}
+
+/// Returns the mark on the cell, `TTT_MARK_NONE` if it is empty or out of bounds.
+///
+/// # Safety
+///
+/// `game` must be null or a live game returned by [`ttt_game_new`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_cell(game: *const TttGame, row: usize, col: usize) -> TttMark {
+    match game.as_ref() {
+        Some(game) if row < 3 && col < 3 => game.grid.get_cell(row, col).try_get_mark().copied(),
+        _ => None,
+    }
+    .into()
+}
+
+/// Returns the mark playing the next move.
+///
+/// # Safety
+///
+/// `game` must be null or a live game returned by [`ttt_game_new`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_current_mark(game: *const TttGame) -> TttMark {
+    game.as_ref().map(|game| game.turn).into()
+}
+
+/// Returns whether the game has a result, won or drawn.
+///
+/// # Safety
+///
+/// `game` must be null or a live game returned by [`ttt_game_new`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_is_over(game: *const TttGame) -> bool {
+    game.as_ref().is_some_and(|game| game.result.is_some())
+}
+
+/// Returns the winning mark, `TTT_MARK_NONE` if the game is a draw or still in progress.
+///
+/// # Safety
+///
+/// `game` must be null or a live game returned by [`ttt_game_new`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_winner(game: *const TttGame) -> TttMark {
+    game.as_ref()
+        .and_then(|game| game.result)
+        .and_then(|result| result.winner())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn games_are_played_through_pointers() {
+        unsafe {
+            let game = ttt_game_new();
+            assert_eq!(ttt_game_play(game, 1, 1), TttStatus::Ok);
+            assert_eq!(ttt_game_cell(game, 1, 1), TttMark::X);
+            assert_eq!(ttt_game_current_mark(game), TttMark::O);
+            assert_eq!(ttt_game_play(game, 1, 1), TttStatus::CellInUse);
+            assert_eq!(ttt_game_play(game, 3, 0), TttStatus::OutOfBounds);
+            assert_eq!(ttt_game_cell(game, 3, 0), TttMark::None);
+
+            for (row, col) in [(1, 0), (0, 0), (2, 0)] {
+                assert_eq!(ttt_game_play(game, row, col), TttStatus::Ok);
+            }
+            let (mut row, mut col) = (0, 0);
+            let status = ttt_bot_move(game, 7, &mut row, &mut col);
+            assert_eq!(status, TttStatus::UnknownDifficulty);
+            let difficulty = TttDifficulty::Impossible as u32;
+            let status = ttt_bot_move(game, difficulty, &mut row, &mut col);
+            assert_eq!((status, row, col), (TttStatus::Ok, 2, 2));
+            assert_eq!(ttt_game_play(game, row, col), TttStatus::Ok);
+
+            assert!(ttt_game_is_over(game));
+            assert_eq!(ttt_game_winner(game), TttMark::X);
+            assert_eq!(ttt_game_play(game, 0, 1), TttStatus::GameOver);
+            ttt_game_free(game);
+        }
+    }
+
+    #[test]
+    fn null_games_are_rejected() {
+        unsafe {
+            assert_eq!(ttt_game_play(ptr::null_mut(), 0, 0), TttStatus::NullGame);
+            assert_eq!(ttt_game_current_mark(ptr::null()), TttMark::None);
+            assert!(!ttt_game_is_over(ptr::null()));
+            let game = ttt_game_new();
+            let easy = TttDifficulty::Easy as u32;
+            let status = ttt_bot_move(game, easy, ptr::null_mut(), ptr::null_mut());
+            assert_eq!(status, TttStatus::NullGame);
+            ttt_game_free(game);
+            ttt_game_free(ptr::null_mut());
+        }
+    }
+}
@@ -1,412 +1,1401 @@
 use std::{
     error::Error,
-    fmt::Display,
-    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write},
-    net::{TcpListener, TcpStream, ToSocketAddrs},
+    fmt::{Debug, Display},
+    panic,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
 };
 
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::{
+    board::{Board, SquareBoard},
     grid::{Grid, GridPlacementError, Mark},
-    player::Player,
-    protocol::{self, ClientHello, EndOfGame, PlayerMove, ServerHello},
+    player::{Move, Player, PlayerError},
 };
 
-use self::seal::ServerGameState;
+#[cfg(feature = "network")]
+pub use self::events::{GameCommand, GameEvent, GameEvents};
+#[cfg(feature = "network")]
+pub use self::memory::MemoryStream;
+#[cfg(feature = "network")]
+pub use self::network::*;
+use self::seal::GameState;
+
+#[cfg(feature = "network")]
+mod events;
+#[cfg(feature = "network")]
+mod memory;
+#[cfg(feature = "network")]
+mod network;
 
 #[derive(Debug)]
-pub struct GamePlayer<'a> {
+pub struct GamePlayer<'a, B: Board = Grid> {
     pub mark: Mark,
-    pub player: &'a dyn Player,
+    pub player: &'a dyn Player<B>,
 }
 
-impl Display for GamePlayer<'_> {
+impl<B: Board> Display for GamePlayer<'_, B> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.mark)
     }
 }
 
-pub struct Game {
-    grid: Grid,
-    player_x: Box<dyn Player>,
-    player_o: Box<dyn Player>,
-    is_x_turn: bool,
+/// Why a game was won.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum WinReason {
+    /// The winner completed a line.
+    Line,
+    /// The opponent resigned.
+    Resignation,
+    /// The opponent exceeded the move time limit, see [`MoveTimeLimit`].
+    Timeout,
 }
 
-impl Game {
-    pub fn new(player_x: Box<dyn Player>, player_o: Box<dyn Player>) -> Self {
-        Self {
-            player_x,
-            player_o,
-            grid: Grid::default(),
-            is_x_turn: true,
+/// Why a game ended in a draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DrawReason {
+    /// The board was filled without anyone completing a line.
+    BoardFull,
+    /// A player offered a draw and their opponent accepted it.
+    Agreement,
+}
+
+/// Final result of a game. With the `serde` feature, it is serialized as
+/// `{"winner": "X", "reason": "line"}` or `{"draw": "board_full"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "ResultFields", into = "ResultFields")
+)]
+pub enum GameResult {
+    Win { winner: Mark, reason: WinReason },
+    Draw(DrawReason),
+}
+
+/// Serialized form of [`GameResult`], telling draws apart by their `draw` field.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ResultFields {
+    Win { winner: Mark, reason: WinReason },
+    Draw { draw: DrawReason },
+}
+
+#[cfg(feature = "serde")]
+impl From<GameResult> for ResultFields {
+    fn from(value: GameResult) -> Self {
+        match value {
+            GameResult::Win { winner, reason } => Self::Win { winner, reason },
+            GameResult::Draw(draw) => Self::Draw { draw },
         }
     }
+}
 
-    pub fn grid(&self) -> &Grid {
-        &self.grid
+#[cfg(feature = "serde")]
+impl From<ResultFields> for GameResult {
+    fn from(value: ResultFields) -> Self {
+        match value {
+            ResultFields::Win { winner, reason } => Self::Win { winner, reason },
+            ResultFields::Draw { draw } => Self::Draw(draw),
+        }
     }
+}
 
-    pub fn current_player(&self) -> GamePlayer {
-        if self.is_x_turn {
-            GamePlayer {
-                mark: Mark::X,
-                player: self.player_x.as_ref(),
-            }
-        } else {
-            GamePlayer {
-                mark: Mark::O,
-                player: self.player_o.as_ref(),
-            }
+impl GameResult {
+    /// Returns the winning mark, if the game wasn't a draw.
+    pub fn winner(&self) -> Option<Mark> {
+        match self {
+            Self::Win { winner, .. } => Some(*winner),
+            Self::Draw(_) => None,
         }
     }
+}
+
+/// A move played during a game, along with the mark of the player who played it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayedMove {
+    pub mark: Mark,
+    pub player_move: Move,
+}
+
+/// What happens to a player exceeding the move time limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPolicy {
+    /// The player loses the game, as if they had resigned.
+    Forfeit,
+    /// The player's move is replaced by a random legal move.
+    RandomMove,
+}
+
+/// Time each player has to choose a move in a [`Game`].
+///
+/// Players choose their move on a worker thread, and the [`TimeoutPolicy`] applies as soon as the
+/// limit is over. A player still thinking then keeps running in the background until they answer,
+/// and their late move is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveTimeLimit {
+    pub limit: Duration,
+    pub on_timeout: TimeoutPolicy,
+}
+
+/// State of a [`Game`] that is still being played.
+#[derive(Debug, Default)]
+pub struct InProgress {
+    /// Whether the current player already offered a draw this turn.
+    draw_offered: bool,
+}
+impl GameState for InProgress {}
 
-    pub fn try_move(&mut self) -> Result<(), GridPlacementError> {
-        let game_player = self.current_player();
-        let (row, col) = game_player.player.get_move(self.grid(), &game_player.mark);
+/// State of a [`Game`] that has ended.
+#[derive(Debug)]
+pub struct Finished(GameResult);
+impl GameState for Finished {}
 
-        let mark = if self.is_x_turn { Mark::X } else { Mark::O };
-        self.grid.try_set_cell(row, col, mark)?;
+/// A mark taking part in a game, played by a single player or by a team of players taking turns.
+#[derive(Debug)]
+struct Seat<B: Board> {
+    mark: Mark,
+    /// Players in rota order, never empty. They are shared with the thread asking for their move
+    /// when a move time limit is set.
+    players: Vec<Arc<dyn Player<B>>>,
+    /// Index of the player making the seat's next move
+    rota: usize,
+}
 
-        self.is_x_turn = !self.is_x_turn;
-        Ok(())
+impl<B: Board> Seat<B> {
+    fn new(mark: Mark, player: Box<dyn Player<B>>) -> Self {
+        Self::team(mark, vec![player])
     }
 
-    pub fn find_winner(&self) -> Option<GamePlayer> {
-        self.grid
-            .get_winning_mark()
-            .map(|m| self.mark_to_game_player(&m))
+    fn team(mark: Mark, players: Vec<Box<dyn Player<B>>>) -> Self {
+        Self {
+            mark,
+            players: players.into_iter().map(Arc::from).collect(),
+            rota: 0,
+        }
     }
 
-    fn mark_to_game_player(&self, mark: &Mark) -> GamePlayer {
-        match mark {
-            Mark::X => GamePlayer {
-                mark: *mark,
-                player: self.player_x.as_ref(),
-            },
-            Mark::O => GamePlayer {
-                mark: *mark,
-                player: self.player_o.as_ref(),
-            },
+    /// Returns the player making the seat's next move.
+    fn player(&self) -> &Arc<dyn Player<B>> {
+        &self.players[self.rota]
+    }
+
+    fn as_game_player(&self) -> GamePlayer<'_, B> {
+        GamePlayer {
+            mark: self.mark,
+            player: self.player().as_ref(),
         }
     }
+
+    /// Hands the seat's next move to the following player of the team.
+    fn advance_rota(&mut self) {
+        self.rota = (self.rota + 1) % self.players.len();
+    }
+}
+
+#[derive(Debug)]
+pub struct Game<S: GameState = InProgress, B: Board = Grid> {
+    state: S,
+    board: B,
+    /// Players in turn order
+    seats: Vec<Seat<B>>,
+    /// Index of the current player in `seats`
+    turn: usize,
+    history: Vec<PlayedMove>,
+    move_time_limit: Option<MoveTimeLimit>,
+}
+
+/// Game returned after playing a turn, depending on whether the turn ended the game.
+#[derive(Debug)]
+pub enum Turn<B: Board = Grid> {
+    InProgress(Game<InProgress, B>),
+    Finished(Game<Finished, B>),
 }
 
+/// Why a turn couldn't be played.
 #[derive(Debug)]
-pub enum NetworkedGameError {
-    PlayError(GridPlacementError),
-    Io(io::Error),
+pub enum TurnError {
+    /// The move chosen by the player can't be applied to the board.
+    IllegalMove(GridPlacementError),
+    /// The player failed to choose a move or to answer a draw offer.
+    Player(PlayerError),
 }
 
-impl Display for NetworkedGameError {
+impl Display for TurnError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::PlayError(e) => write!(f, "Error while trying a move: {}", e),
-            Self::Io(e) => write!(f, "IO error while playing: {}", e),
+            Self::IllegalMove(e) => write!(f, "{}", e),
+            Self::Player(e) => write!(f, "{}", e),
         }
     }
 }
-impl Error for NetworkedGameError {}
 
-impl From<GridPlacementError> for NetworkedGameError {
-    fn from(value: GridPlacementError) -> Self {
-        Self::PlayError(value)
+impl Error for TurnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::IllegalMove(e) => Some(e),
+            Self::Player(e) => Some(e),
+        }
     }
 }
 
-impl From<io::Error> for NetworkedGameError {
-    fn from(value: io::Error) -> Self {
-        Self::Io(value)
+impl From<GridPlacementError> for TurnError {
+    fn from(value: GridPlacementError) -> Self {
+        Self::IllegalMove(value)
     }
 }
 
-pub trait NetworkedGame {
-    fn grid(&self) -> &Grid;
+impl From<PlayerError> for TurnError {
+    fn from(value: PlayerError) -> Self {
+        Self::Player(value)
+    }
+}
 
-    fn grid_mut(&mut self) -> &mut Grid;
+/// Error returned when a turn couldn't be played. The game is left unchanged, and can be recovered
+/// with [`MoveError::into_game`].
+#[derive(Debug)]
+pub struct MoveError<B: Board = Grid> {
+    game: Game<InProgress, B>,
+    error: TurnError,
+}
 
-    fn set_next_turn(&mut self);
+impl<B: Board> MoveError<B> {
+    pub fn error(&self) -> &TurnError {
+        &self.error
+    }
 
-    fn is_local_turn(&self) -> bool;
+    /// Returns the mark of the player whose turn couldn't be played.
+    pub fn mark(&self) -> Mark {
+        self.game.current_player().mark
+    }
 
-    fn local_mark(&self) -> Mark;
+    pub fn into_error(self) -> TurnError {
+        self.error
+    }
 
-    fn try_move(&mut self, player: &dyn Player) -> Result<(), NetworkedGameError>;
+    pub fn into_game(self) -> Game<InProgress, B> {
+        self.game
+    }
 }
 
-trait InternalNetworkBufAccessor {
-    fn reader(&mut self) -> &mut BufReader<TcpStream>;
-    fn writer(&mut self) -> &mut BufWriter<TcpStream>;
+impl<B: Board> Display for MoveError<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let player = self.game.current_player();
+        match &self.error {
+            TurnError::IllegalMove(e) => write!(f, "Invalid move by {}: {}", player, e),
+            TurnError::Player(e) => write!(f, "{} couldn't play: {}", player, e),
+        }
+    }
 }
-
-#[derive(Debug)]
-pub struct RemoteGame {
-    reader: BufReader<TcpStream>,
-    writer: BufWriter<TcpStream>,
-    grid: Grid,
-    is_local_turn: bool,
-    local_mark: Mark,
+impl<B: Board> Error for MoveError<B> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
 }
 
-impl NetworkedGame for RemoteGame {
-    fn grid(&self) -> &Grid {
-        &self.grid
+impl<S: GameState, B: Board> Game<S, B> {
+    pub fn board(&self) -> &B {
+        &self.board
     }
 
-    fn grid_mut(&mut self) -> &mut Grid {
-        &mut self.grid
+    /// Returns every move played since the starting position, in order.
+    pub fn history(&self) -> &[PlayedMove] {
+        &self.history
     }
 
-    fn set_next_turn(&mut self) {
-        self.is_local_turn = !self.is_local_turn;
+    fn mark_to_game_player(&self, mark: &Mark) -> Option<GamePlayer<'_, B>> {
+        self.seats
+            .iter()
+            .find(|seat| seat.mark == *mark)
+            .map(Seat::as_game_player)
     }
 
-    fn is_local_turn(&self) -> bool {
-        self.is_local_turn
+    fn into_state<T: GameState>(self, state: T) -> Game<T, B> {
+        Game {
+            state,
+            board: self.board,
+            seats: self.seats,
+            turn: self.turn,
+            history: self.history,
+            move_time_limit: self.move_time_limit,
+        }
     }
+}
 
-    fn local_mark(&self) -> Mark {
-        self.local_mark
+impl Game {
+    /// Creates a game on an empty [`Grid`], with `X` playing first.
+    pub fn new(player_x: Box<dyn Player>, player_o: Box<dyn Player>) -> Self {
+        Self::with_board(Grid::default(), player_x, player_o)
     }
 
-    fn try_move(&mut self, player: &dyn Player) -> Result<(), NetworkedGameError> {
-        try_networked_move(self, player)
+    /// Returns a builder to create a game from a custom starting position.
+    pub fn builder() -> GameBuilder {
+        GameBuilder::default()
     }
-}
 
-impl InternalNetworkBufAccessor for RemoteGame {
-    fn reader(&mut self) -> &mut BufReader<TcpStream> {
-        &mut self.reader
+    /// Creates a game by replaying `moves` from an empty [`Grid`], with `X` playing first. See
+    /// [`Game::replay`].
+    pub fn from_moves(
+        player_x: Box<dyn Player>,
+        player_o: Box<dyn Player>,
+        moves: &[PlayedMove],
+    ) -> Result<Turn, ReplayError> {
+        Self::new(player_x, player_o).replay(moves)
     }
+}
 
-    fn writer(&mut self) -> &mut BufWriter<TcpStream> {
-        &mut self.writer
+impl Game<InProgress, SquareBoard> {
+    /// Creates a 3 player game on an empty 5x5 board, won by aligning 4 marks. `X` plays first,
+    /// followed by `O` then `Y`.
+    pub fn three_player(
+        player_x: Box<dyn Player<SquareBoard>>,
+        player_o: Box<dyn Player<SquareBoard>>,
+        player_y: Box<dyn Player<SquareBoard>>,
+    ) -> Self {
+        let mut game = Self::with_board(SquareBoard::new(5, 4), player_x, player_o);
+        game.seats.push(Seat::new(Mark::Y, player_y));
+        game
     }
 }
 
-impl RemoteGame {
-    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<RemoteGame> {
-        let stream = TcpStream::connect(addr)?;
+impl<B: Board> Game<InProgress, B> {
+    /// Creates a team game played on `board`, with `X` playing first. Members of a team take turns
+    /// making their team's moves, in the order they are given.
+    ///
+    /// # Panics
+    /// Panics if a team is empty.
+    pub fn with_teams(
+        board: B,
+        team_x: Vec<Box<dyn Player<B>>>,
+        team_o: Vec<Box<dyn Player<B>>>,
+    ) -> Self {
+        assert!(
+            !team_x.is_empty() && !team_o.is_empty(),
+            "Teams need at least one player"
+        );
 
-        let mut reader = BufReader::new(stream.try_clone()?);
-        let mut writer = BufWriter::new(stream);
-        writer.write_all(&ClientHello.to_bytes())?;
-        writer.flush()?;
+        Self {
+            state: InProgress::default(),
+            board,
+            seats: vec![Seat::team(Mark::X, team_x), Seat::team(Mark::O, team_o)],
+            turn: 0,
+            history: vec![],
+            move_time_limit: None,
+        }
+    }
 
-        let mut buf = vec![];
-        reader.read_until(protocol::TERMINATOR, &mut buf)?;
-        buf.pop();
+    /// Creates a game played on `board`, with `X` playing first.
+    pub fn with_board(
+        board: B,
+        player_x: Box<dyn Player<B>>,
+        player_o: Box<dyn Player<B>>,
+    ) -> Self {
+        let seats = vec![Seat::new(Mark::X, player_x), Seat::new(Mark::O, player_o)];
 
-        let server_hello = ServerHello::try_from(buf.as_slice()).map_err(|_| {
-            io::Error::new(
-                ErrorKind::InvalidData,
-                "Received malformed SERVER_HELLO packet",
-            )
-        })?;
-
-        Ok(Self {
-            reader,
-            writer,
-            grid: Grid::default(),
-            is_local_turn: server_hello.client_first,
-            local_mark: server_hello.client_mark,
-        })
+        Self {
+            state: InProgress::default(),
+            board,
+            seats,
+            turn: 0,
+            history: vec![],
+            move_time_limit: None,
+        }
     }
-}
 
-mod seal {
-    pub trait ServerGameState {}
-}
+    pub fn current_player(&self) -> GamePlayer<'_, B> {
+        self.seats[self.turn].as_game_player()
+    }
 
-pub struct NewState(TcpListener);
-impl ServerGameState for NewState {}
+    /// Sets how long players have to choose each move, or removes the limit with `None`.
+    pub fn set_move_time_limit(&mut self, limit: Option<MoveTimeLimit>) {
+        self.move_time_limit = limit;
+    }
 
-pub struct ConnectedState(BufReader<TcpStream>, BufWriter<TcpStream>);
-impl ServerGameState for ConnectedState {}
+    /// Asks the current player for their move and applies it.
+    ///
+    /// If the player offers a draw, every other mark is asked to accept it. In team games, the
+    /// answer is given by the team member due to make that team's next move. When declined, the
+    /// turn doesn't change and the same player must be asked for a move again. Only one offer per
+    /// turn is forwarded, further offers are ignored.
+    ///
+    /// A player resigning in a game of 2 marks hands the win to their opponent. With more marks,
+    /// they are removed from the game and the others keep playing until one remains. In team games,
+    /// a player resigns for their whole team.
+    ///
+    /// If a move time limit is set and the player doesn't choose their move in time, the
+    /// [`TimeoutPolicy`] applies once the limit is over, see [`MoveTimeLimit`]. A forfeit is
+    /// recorded as a resignation in the history.
+    pub fn try_move(mut self) -> Result<Turn<B>, MoveError<B>> {
+        let turns: Vec<_> = (0..self.seats.len())
+            .map(|i| self.seats[(self.turn + i) % self.seats.len()].mark)
+            .collect();
+        let seat = &self.seats[self.turn];
+        let mark = seat.mark;
+        let player_move = match self.move_time_limit {
+            Some(limit) => {
+                match move_within(seat.player(), self.board(), mark, &turns, limit.limit) {
+                    Some(player_move) => player_move,
+                    None => match limit.on_timeout {
+                        TimeoutPolicy::Forfeit => return Ok(self.forfeit()),
+                        TimeoutPolicy::RandomMove => {
+                            let legal_moves = self.board.legal_moves();
+                            let &(row, col) = legal_moves
+                                .choose(&mut rand::thread_rng())
+                                .expect("Game in progress has a free cell");
+                            Ok(Move::Place(row, col))
+                        }
+                    },
+                }
+            }
+            None => seat
+                .player()
+                .get_move_with_turns(self.board(), &mark, &turns),
+        };
+        let player_move = match player_move {
+            Ok(player_move) => player_move,
+            Err(e) => return Err(self.into_move_error(e)),
+        };
+
+        let draw_accepted = if player_move == Move::OfferDraw && !self.state.draw_offered {
+            // Stops at the first opponent declining or failing to answer
+            self.seats
+                .iter()
+                .filter(|seat| seat.mark != mark)
+                .map(|seat| {
+                    let opponent = seat.as_game_player();
+                    opponent.player.accept_draw(self.board(), &opponent.mark)
+                })
+                .find(|answer| !matches!(answer, Ok(true)))
+                .unwrap_or(Ok(true))
+        } else {
+            Ok(false)
+        };
+        let draw_accepted = match draw_accepted {
+            Ok(accepted) => accepted,
+            Err(e) => return Err(self.into_move_error(e)),
+        };
+
+        let result = match self.apply_move(player_move) {
+            Ok(_) if draw_accepted => Some(GameResult::Draw(DrawReason::Agreement)),
+            Ok(result) => result,
+            Err(e) => return Err(self.into_move_error(e)),
+        };
+        Ok(self.into_turn(result))
+    }
 
-#[derive(Debug)]
-pub struct ServerGame<S: ServerGameState> {
-    state: S,
-    grid: Grid,
-    is_local_turn: bool,
-    local_mark: Mark,
+    /// Replays `moves` from the current position, checking that each one is legal and played by
+    /// the right mark. Draw offers are kept in the history, but are treated as declined.
+    pub fn replay(mut self, moves: &[PlayedMove]) -> Result<Turn<B>, ReplayError> {
+        for (index, played) in moves.iter().enumerate() {
+            let expected = self.current_player().mark;
+            if played.mark != expected {
+                return Err(ReplayError::WrongTurn { index, expected });
+            }
+
+            let result = self
+                .apply_move(played.player_move)
+                .map_err(|error| ReplayError::IllegalMove { index, error })?;
+            if result.is_some() && index + 1 < moves.len() {
+                return Err(ReplayError::GameOver { index: index + 1 });
+            }
+            if result.is_some() {
+                return Ok(self.into_turn(result));
+            }
+        }
+        Ok(Turn::InProgress(self))
+    }
+
+    /// Applies a move for the current player without consulting anyone, and returns the game's
+    /// result if the move ended it.
+    fn apply_move(&mut self, player_move: Move) -> Result<Option<GameResult>, GridPlacementError> {
+        let mark = self.current_player().mark;
+        let result = match player_move {
+            Move::Place(row, col) => {
+                self.board.place(row, col, mark)?;
+                self.seats[self.turn].advance_rota();
+                self.turn = (self.turn + 1) % self.seats.len();
+                self.state.draw_offered = false;
+                board_result(&self.board)
+            }
+            Move::Resign => {
+                self.seats.remove(self.turn);
+                self.turn %= self.seats.len();
+                self.state.draw_offered = false;
+                match self.seats.as_slice() {
+                    [last] => Some(GameResult::Win {
+                        winner: last.mark,
+                        reason: WinReason::Resignation,
+                    }),
+                    _ => None,
+                }
+            }
+            Move::OfferDraw => {
+                self.state.draw_offered = true;
+                None
+            }
+        };
+        self.history.push(PlayedMove { mark, player_move });
+        Ok(result)
+    }
+
+    /// Makes the current player resign after running out of time.
+    fn forfeit(mut self) -> Turn<B> {
+        let result = self
+            .apply_move(Move::Resign)
+            .expect("Resigning is always legal")
+            .map(|result| match result {
+                GameResult::Win { winner, .. } => GameResult::Win {
+                    winner,
+                    reason: WinReason::Timeout,
+                },
+                result => result,
+            });
+        self.into_turn(result)
+    }
+
+    fn into_turn(self, result: Option<GameResult>) -> Turn<B> {
+        match result {
+            Some(result) => Turn::Finished(self.into_state(Finished(result))),
+            None => Turn::InProgress(self),
+        }
+    }
+
+    fn into_move_error(self, error: impl Into<TurnError>) -> MoveError<B> {
+        MoveError {
+            game: self,
+            error: error.into(),
+        }
+    }
+
+    /// Plays the game until it ends.
+    pub fn play_to_end(self) -> Result<Game<Finished, B>, MoveError<B>> {
+        self.play_to_end_with(|_| {})
+    }
+
+    /// Plays the game until it ends. `on_move` is called after every turn.
+    pub fn play_to_end_with(
+        self,
+        mut on_move: impl FnMut(&Turn<B>),
+    ) -> Result<Game<Finished, B>, MoveError<B>> {
+        let mut game = self;
+        loop {
+            let turn = game.try_move()?;
+            on_move(&turn);
+            match turn {
+                Turn::InProgress(g) => game = g,
+                Turn::Finished(g) => return Ok(g),
+            }
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
-/// Defaults: host playing first with the `X` mark
-pub struct ServerGameSettings {
-    pub host_plays_first: bool,
-    pub host_mark: Mark,
+impl<B: Board> Game<Finished, B> {
+    pub fn result(&self) -> GameResult {
+        self.state.0
+    }
+
+    pub fn find_winner(&self) -> Option<GamePlayer<'_, B>> {
+        self.result()
+            .winner()
+            .and_then(|m| self.mark_to_game_player(&m))
+    }
 }
 
-impl Default for ServerGameSettings {
-    fn default() -> Self {
-        Self {
-            host_plays_first: true,
-            host_mark: Mark::X,
+/// Returns the result of a game on this board, if it is won or full.
+/// Asks `player` for their move on a worker thread, waiting for it until `limit` is over. Returns
+/// `None` if the player didn't answer in time, leaving them to finish in the background.
+pub(crate) fn move_within<B: Board>(
+    player: &Arc<dyn Player<B>>,
+    board: &B,
+    mark: Mark,
+    turns: &[Mark],
+    limit: Duration,
+) -> Option<Result<Move, PlayerError>> {
+    let (player, board, turns) = (Arc::clone(player), board.clone(), turns.to_vec());
+    let (send, chosen) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        // Nobody is waiting for the move anymore if sending fails
+        let _ = send.send(player.get_move_with_turns(&board, &mark, &turns));
+    });
+    match chosen.recv_timeout(limit) {
+        Ok(player_move) => Some(player_move),
+        Err(RecvTimeoutError::Timeout) => None,
+        // The player panicked, which is forwarded as if they played on the current thread
+        Err(RecvTimeoutError::Disconnected) => {
+            panic::resume_unwind(worker.join().expect_err("Players answer unless they panic"))
         }
     }
 }
 
-impl ServerGame<NewState> {
-    pub fn bind<A: ToSocketAddrs>(addr: A, settings: &ServerGameSettings) -> io::Result<Self> {
-        let state = NewState(TcpListener::bind(addr)?);
-
-        Ok(Self {
-            state,
-            grid: Grid::default(),
-            is_local_turn: settings.host_plays_first,
-            local_mark: settings.host_mark,
+pub(crate) fn board_result(board: &impl Board) -> Option<GameResult> {
+    if let Some(winner) = board.winner() {
+        Some(GameResult::Win {
+            winner,
+            reason: WinReason::Line,
         })
+    } else if board.is_full() {
+        Some(GameResult::Draw(DrawReason::BoardFull))
+    } else {
+        None
     }
+}
 
-    pub fn listen(self) -> io::Result<ServerGame<ConnectedState>> {
-        let listener = self.state.0;
+/// Error returned when a list of moves can't be replayed. `index` is the position of the faulty move
+/// in the list.
+#[derive(Debug, Clone, Copy)]
+pub enum ReplayError {
+    WrongTurn {
+        index: usize,
+        expected: Mark,
+    },
+    IllegalMove {
+        index: usize,
+        error: GridPlacementError,
+    },
+    /// The game already ended before this move.
+    GameOver {
+        index: usize,
+    },
+}
 
-        let reader;
-        let writer;
-        loop {
-            let (socket, _) = listener.accept()?;
-
-            let mut r = BufReader::new(socket.try_clone()?);
-            let mut w = BufWriter::new(socket);
-
-            // Expect CLIENT_HELLO
-            let mut buf = vec![];
-            r.read_until(protocol::TERMINATOR, &mut buf)?;
-            buf.pop();
-            match ClientHello::try_from(buf.as_slice()) {
-                Ok(_) => {}
-                Err(_) => continue,
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongTurn { index, expected } => {
+                write!(f, "Move {} should have been played by {}", index, expected)
             }
+            Self::IllegalMove { index, error } => write!(f, "Move {} is illegal: {}", index, error),
+            Self::GameOver { index } => write!(f, "Move {} was played after the game ended", index),
+        }
+    }
+}
+impl Error for ReplayError {}
 
-            // Send SERVER_HELLO
-            let pkt = ServerHello {
-                client_first: !self.is_local_turn,
-                client_mark: self.local_mark.opposite(),
-            }
-            .to_bytes();
-            w.write_all(&pkt)?;
-            w.flush()?;
+#[derive(Debug, Clone, Copy)]
+pub enum GameBuilderError {
+    MissingPlayer(Mark),
+    /// The starting position is already won or full.
+    PositionFinished,
+}
 
-            reader = r;
-            writer = w;
-            break;
+impl Display for GameBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingPlayer(m) => write!(f, "No player was set for {}", m),
+            Self::PositionFinished => write!(f, "Starting position is already finished"),
         }
+    }
+}
+impl Error for GameBuilderError {}
+
+/// How the mark playing first is chosen when building a game.
+#[derive(Debug, Clone, Copy)]
+enum FirstMark {
+    Fixed(Mark),
+    /// Picked at random among the marks taking part in the game, from a generator seeded with the
+    /// given seed if any.
+    Random(Option<u64>),
+}
 
-        let state = ConnectedState(reader, writer);
-
-        Ok(ServerGame::<ConnectedState> {
-            state,
-            grid: self.grid,
-            is_local_turn: self.is_local_turn,
-            local_mark: self.local_mark,
-        })
+impl Default for FirstMark {
+    fn default() -> Self {
+        Self::Fixed(Mark::X)
     }
 }
 
-impl NetworkedGame for ServerGame<ConnectedState> {
-    fn grid(&self) -> &Grid {
-        &self.grid
+#[derive(Debug, Default)]
+/// Defaults: empty board with `X` playing first. Players must be set for at least `X` and `O`, and
+/// `Y` can be added for a 3 player game. Any mark can be played by a team instead of a single
+/// player.
+pub struct GameBuilder<B: Board = Grid> {
+    board: B,
+    first_mark: FirstMark,
+    seats: Vec<Seat<B>>,
+    move_time_limit: Option<MoveTimeLimit>,
+}
+
+impl<B: Board> GameBuilder<B> {
+    /// Returns a builder for a game played on `board`.
+    pub fn with_board(board: B) -> Self {
+        Self {
+            board,
+            first_mark: FirstMark::default(),
+            seats: vec![],
+            move_time_limit: None,
+        }
     }
 
-    fn grid_mut(&mut self) -> &mut Grid {
-        &mut self.grid
+    /// Sets the starting position of the game.
+    pub fn board(mut self, board: B) -> Self {
+        self.board = board;
+        self
     }
 
-    fn set_next_turn(&mut self) {
-        self.is_local_turn = !self.is_local_turn;
+    /// Sets which mark plays the first move from the starting position.
+    pub fn first_mark(mut self, mark: Mark) -> Self {
+        self.first_mark = FirstMark::Fixed(mark);
+        self
     }
 
-    fn is_local_turn(&self) -> bool {
-        self.is_local_turn
+    /// Lets a random mark, among those with a player, play the first move from the starting
+    /// position. The choice is made when the game is built.
+    pub fn random_first_mark(mut self) -> Self {
+        self.first_mark = FirstMark::Random(None);
+        self
     }
 
-    fn local_mark(&self) -> Mark {
-        self.local_mark
+    /// Same as [`GameBuilder::random_first_mark`], picking the mark from a generator seeded with
+    /// `seed`, so the same mark is picked again with the same seed.
+    pub fn seeded_random_first_mark(mut self, seed: u64) -> Self {
+        self.first_mark = FirstMark::Random(Some(seed));
+        self
     }
 
-    fn try_move(&mut self, player: &dyn Player) -> Result<(), NetworkedGameError> {
-        try_networked_move(self, player)
+    /// Limits how long players have to choose each move. See [`Game::set_move_time_limit`].
+    pub fn move_time_limit(mut self, limit: MoveTimeLimit) -> Self {
+        self.move_time_limit = Some(limit);
+        self
     }
-}
 
-impl InternalNetworkBufAccessor for ServerGame<ConnectedState> {
-    fn reader(&mut self) -> &mut BufReader<TcpStream> {
-        &mut self.state.0
+    /// Sets the player playing `mark`, replacing any player previously set for it.
+    pub fn player(self, mark: Mark, player: Box<dyn Player<B>>) -> Self {
+        self.team(mark, vec![player])
     }
 
-    fn writer(&mut self) -> &mut BufWriter<TcpStream> {
-        &mut self.state.1
+    /// Sets the team playing `mark`, replacing any player previously set for it. Members take turns
+    /// making the team's moves, in the order they are given. An empty team leaves `mark` without
+    /// any player.
+    pub fn team(mut self, mark: Mark, players: Vec<Box<dyn Player<B>>>) -> Self {
+        self.seats.retain(|seat| seat.mark != mark);
+        if !players.is_empty() {
+            self.seats.push(Seat::team(mark, players));
+        }
+        self
     }
-}
 
-fn try_networked_move<G: NetworkedGame + InternalNetworkBufAccessor>(
-    game: &mut G,
-    local_player: &dyn Player,
-) -> Result<(), NetworkedGameError> {
-    // Get move
-    let (row, col) = if game.is_local_turn() {
-        local_player.get_move(game.grid(), &game.local_mark())
-    } else {
-        let mut buf = vec![];
-        game.reader().read_until(protocol::TERMINATOR, &mut buf)?;
-
-        // Expect 1 data byte + terminator
-        if buf.len() != 2 {
-            if EndOfGame::try_from(buf.as_slice()).is_ok() {
-                return Err(io::Error::new(
-                    ErrorKind::UnexpectedEof,
-                    "received unexpected end of game packet",
-                )
-                .into());
+    pub fn player_x(self, player: Box<dyn Player<B>>) -> Self {
+        self.player(Mark::X, player)
+    }
+
+    pub fn player_o(self, player: Box<dyn Player<B>>) -> Self {
+        self.player(Mark::O, player)
+    }
+
+    pub fn player_y(self, player: Box<dyn Player<B>>) -> Self {
+        self.player(Mark::Y, player)
+    }
+
+    pub fn build(mut self) -> Result<Game<InProgress, B>, GameBuilderError> {
+        for mark in [Mark::X, Mark::O] {
+            if !self.seats.iter().any(|seat| seat.mark == mark) {
+                return Err(GameBuilderError::MissingPlayer(mark));
             }
-            return Err(
-                io::Error::new(ErrorKind::InvalidData, "PlayerMove packet too long").into(),
-            );
         }
 
-        PlayerMove::from(buf[0]).to_tuple()
-    };
-
-    // Try applying move
-    let mark = if game.is_local_turn() {
-        game.local_mark()
-    } else {
-        game.local_mark().opposite()
-    };
-    game.grid_mut().try_set_cell(row, col, mark)?;
+        if board_result(&self.board).is_some() {
+            return Err(GameBuilderError::PositionFinished);
+        }
 
-    if game.is_local_turn() {
-        // Send move to remote player
-        let pkt = PlayerMove(row, col);
-        game.writer().write_all(&pkt.to_bytes())?;
-        game.writer().flush()?;
+        // Turn order is always X, O, then Y
+        self.seats.sort_by_key(|seat| seat.mark);
+        let turn = match self.first_mark {
+            FirstMark::Fixed(mark) => self
+                .seats
+                .iter()
+                .position(|seat| seat.mark == mark)
+                .ok_or(GameBuilderError::MissingPlayer(mark))?,
+            FirstMark::Random(None) => rand::thread_rng().gen_range(0..self.seats.len()),
+            FirstMark::Random(Some(seed)) => {
+                StdRng::seed_from_u64(seed).gen_range(0..self.seats.len())
+            }
+        };
+
+        Ok(Game {
+            state: InProgress::default(),
+            board: self.board,
+            seats: self.seats,
+            turn,
+            history: vec![],
+            move_time_limit: self.move_time_limit,
+        })
     }
+}
 
-    game.set_next_turn();
-    Ok(())
+mod seal {
+    pub trait GameState {}
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::player::{self};
+    use std::{sync::Mutex, time::Instant};
+
+    use crate::player::{self, BotPlayer, ScriptedPlayer};
 
     use super::*;
 
+    fn expect_in_progress<B: Board, E: std::fmt::Debug>(
+        turn: Result<Turn<B>, E>,
+    ) -> Game<InProgress, B> {
+        match turn {
+            Ok(Turn::InProgress(game)) => game,
+            other => panic!("Expected game to be in progress, got {:?}", other),
+        }
+    }
+
+    fn expect_finished<B: Board, E: std::fmt::Debug>(
+        turn: Result<Turn<B>, E>,
+    ) -> Game<Finished, B> {
+        match turn {
+            Ok(Turn::Finished(game)) => game,
+            other => panic!("Expected game to be finished, got {:?}", other),
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn games_are_played_from_worker_threads() {
+        assert_send_sync::<Game<InProgress>>();
+        assert_send_sync::<Game<Finished, SquareBoard>>();
+        assert_send_sync::<Turn>();
+        assert_send_sync::<GameBuilder<SquareBoard>>();
+        assert_send_sync::<MoveError>();
+
+        let game = Game::new(
+            Box::new(BotPlayer::impossible()),
+            Box::new(BotPlayer::impossible()),
+        );
+        let result = thread::spawn(move || game.play_to_end().unwrap().result());
+        assert_eq!(
+            result.join().unwrap(),
+            GameResult::Draw(DrawReason::BoardFull)
+        );
+    }
+
     #[test]
     fn try_move_rotates_player() {
         let player_x = Box::new(player::tests::MockPlayer(0, 0));
         let player_o = Box::new(player::tests::MockPlayer(1, 1));
-        let mut game = Game::new(player_x, player_o);
+        let game = Game::new(player_x, player_o);
 
         let player = game.current_player();
         assert_eq!(player.mark, Mark::X);
-        assert!(game.try_move().is_ok());
-        assert_eq!(game.grid().get_cell(0, 0).try_get_mark(), Some(&Mark::X));
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.board().get_cell(0, 0).try_get_mark(), Some(&Mark::X));
 
         let player = game.current_player();
         assert_eq!(player.mark, Mark::O);
-        assert!(game.try_move().is_ok());
-        assert!(game.try_move().is_err())
+        let game = expect_in_progress(game.try_move());
+        let err = game.try_move().expect_err("Cell should already be in use");
+        assert!(matches!(
+            err.error(),
+            TurnError::IllegalMove(GridPlacementError::CellInUse { row: 0, col: 0 })
+        ));
+        assert_eq!(err.into_game().history().len(), 2);
+    }
+
+    #[test]
+    fn resign_gives_win_to_opponent() {
+        let player_x = Box::new(player::tests::MockPlayer(0, 0));
+        let player_o = Box::new(player::tests::ResigningPlayer);
+        let game = Game::new(player_x, player_o);
+
+        let game = expect_in_progress(game.try_move());
+        let game = expect_finished(game.try_move());
+        assert_eq!(
+            game.result(),
+            GameResult::Win {
+                winner: Mark::X,
+                reason: WinReason::Resignation
+            }
+        );
+        assert_eq!(game.find_winner().map(|p| p.mark), Some(Mark::X));
+        assert_eq!(
+            game.history().last(),
+            Some(&PlayedMove {
+                mark: Mark::O,
+                player_move: Move::Resign
+            })
+        );
+    }
+
+    #[test]
+    fn three_player_game_rotates_marks() {
+        let game = Game::three_player(
+            Box::new(player::tests::MockPlayer(0, 0)),
+            Box::new(player::tests::MockPlayer(0, 1)),
+            Box::new(player::tests::MockPlayer(0, 2)),
+        );
+
+        let game = expect_in_progress(game.try_move());
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.current_player().mark, Mark::Y);
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.current_player().mark, Mark::X);
+        assert_eq!(game.board().get_mark(0, 2), Some(Mark::Y));
+    }
+
+    #[test]
+    fn three_player_resign_removes_player() {
+        let game = Game::three_player(
+            Box::new(player::tests::ResigningPlayer),
+            Box::new(player::tests::MockPlayer(0, 1)),
+            Box::new(player::tests::ResigningPlayer),
+        );
+
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.current_player().mark, Mark::O);
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.current_player().mark, Mark::Y);
+        let game = expect_finished(game.try_move());
+        assert_eq!(
+            game.result(),
+            GameResult::Win {
+                winner: Mark::O,
+                reason: WinReason::Resignation
+            }
+        );
+    }
+
+    #[test]
+    fn players_are_given_the_turns_of_the_remaining_marks() {
+        let recorder = TurnsRecorder::default();
+        let turns = Arc::clone(&recorder.0);
+        let game = Game::three_player(
+            Box::new(player::tests::ResigningPlayer),
+            Box::new(recorder),
+            Box::new(FirstFreeCell),
+        );
+
+        let game = expect_in_progress(game.try_move());
+        let game = expect_in_progress(game.try_move());
+        let game = expect_in_progress(game.try_move());
+        expect_in_progress(game.try_move());
+        assert_eq!(
+            *turns.lock().unwrap(),
+            [[Mark::O, Mark::Y], [Mark::O, Mark::Y]]
+        );
+    }
+
+    #[test]
+    fn team_members_take_turns() {
+        // X's team alternates between the left and right columns
+        let game = Game::with_teams(
+            Grid::default(),
+            vec![
+                Box::new(player::tests::MockPlayer(0, 0)),
+                Box::new(player::tests::MockPlayer(0, 2)),
+            ],
+            vec![Box::new(FirstFreeCell)],
+        );
+
+        let game = expect_in_progress(game.try_move());
+        let game = expect_in_progress(game.try_move());
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.board().get_cell(0, 0).try_get_mark(), Some(&Mark::X));
+        assert_eq!(game.board().get_cell(0, 1).try_get_mark(), Some(&Mark::O));
+        assert_eq!(game.board().get_cell(0, 2).try_get_mark(), Some(&Mark::X));
+    }
+
+    #[test]
+    fn builder_rejects_empty_team() {
+        let error = Game::builder()
+            .player_x(Box::new(player::tests::MockPlayer(0, 0)))
+            .team(Mark::O, vec![])
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, GameBuilderError::MissingPlayer(Mark::O)));
+    }
+
+    #[test]
+    fn scripted_players_replay_history() {
+        let game = Game::new(
+            Box::new(ScriptedPlayer::new([
+                Move::Place(1, 1),
+                Move::Place(0, 0),
+                Move::Place(2, 2),
+            ])),
+            Box::new(ScriptedPlayer::new([Move::Place(0, 1), Move::Place(0, 2)])),
+        );
+        let game = game.play_to_end().expect("Error playing game");
+        assert_eq!(game.result().winner(), Some(Mark::X));
+
+        // Playing the recorded history again gives the same game
+        let replayed = Game::new(
+            Box::new(ScriptedPlayer::from_history(game.history(), Mark::X)),
+            Box::new(ScriptedPlayer::from_history(game.history(), Mark::O)),
+        )
+        .play_to_end()
+        .expect("Error replaying game");
+        assert_eq!(replayed.history(), game.history());
+        assert_eq!(replayed.board(), game.board());
+    }
+
+    #[test]
+    fn play_to_end_finishes_game() {
+        let player_x = Box::new(player::BotPlayer::easy());
+        let player_o = Box::new(player::BotPlayer::easy());
+        let game = Game::new(player_x, player_o);
+
+        let mut moves = 0;
+        let game = game
+            .play_to_end_with(|_| moves += 1)
+            .expect("Error while playing");
+        assert_eq!(game.board().cell_count(), moves);
+        assert_eq!(game.history().len(), moves);
+        assert!(game.result().winner().is_some() || game.board().is_full());
+    }
+
+    /// 1x3 board, won by filling it with a single mark
+    #[derive(Debug, Clone, Default)]
+    struct Strip([Option<Mark>; 3]);
+
+    impl Display for Strip {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+
+    impl Board for Strip {
+        fn place(&mut self, row: usize, col: usize, mark: Mark) -> Result<(), GridPlacementError> {
+            match self.0.get_mut(col) {
+                None => Err(GridPlacementError::OutOfBounds { row, col }),
+                Some(Some(_)) => Err(GridPlacementError::CellInUse { row, col }),
+                Some(cell) => {
+                    *cell = Some(mark);
+                    Ok(())
+                }
+            }
+        }
+
+        fn legal_moves(&self) -> Vec<(usize, usize)> {
+            (0..3)
+                .filter(|&c| self.0[c].is_none())
+                .map(|c| (0, c))
+                .collect()
+        }
+
+        fn winner(&self) -> Option<Mark> {
+            let first = self.0[0]?;
+            self.0.iter().all(|&c| c == Some(first)).then_some(first)
+        }
+
+        fn is_full(&self) -> bool {
+            self.0.iter().all(|c| c.is_some())
+        }
+
+        fn dimensions(&self) -> (usize, usize) {
+            (1, 3)
+        }
+
+        fn mark(&self, _: usize, col: usize) -> Option<Mark> {
+            self.0[col]
+        }
+    }
+
+    #[derive(Debug)]
+    struct FirstFreeCell;
+
+    impl<B: Board> Player<B> for FirstFreeCell {
+        fn get_move(&self, board: &B, _: &Mark) -> Result<Move, PlayerError> {
+            let (row, col) = board.legal_moves()[0];
+            Ok(Move::Place(row, col))
+        }
+    }
+
+    /// Plays the first free cell, recording the turns it was given.
+    #[derive(Debug, Default)]
+    struct TurnsRecorder(Arc<Mutex<Vec<Vec<Mark>>>>);
+
+    impl<B: Board> Player<B> for TurnsRecorder {
+        fn get_move(&self, board: &B, mark: &Mark) -> Result<Move, PlayerError> {
+            FirstFreeCell.get_move(board, mark)
+        }
+
+        fn get_move_with_turns(
+            &self,
+            board: &B,
+            mark: &Mark,
+            turns: &[Mark],
+        ) -> Result<Move, PlayerError> {
+            self.0.lock().unwrap().push(turns.to_vec());
+            self.get_move(board, mark)
+        }
+    }
+
+    #[derive(Debug)]
+    struct DisconnectedPlayer;
+
+    impl Player for DisconnectedPlayer {
+        fn get_move(&self, _: &Grid, _: &Mark) -> Result<Move, PlayerError> {
+            Err(PlayerError::EndOfInput)
+        }
+    }
+
+    #[test]
+    fn player_error_leaves_game_unchanged() {
+        let game = Game::new(
+            Box::new(DisconnectedPlayer),
+            Box::new(player::tests::MockPlayer(0, 0)),
+        );
+
+        let err = game.try_move().unwrap_err();
+        assert!(matches!(
+            err.error(),
+            TurnError::Player(PlayerError::EndOfInput)
+        ));
+        let game = err.into_game();
+        assert_eq!(game.current_player().mark, Mark::X);
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn game_plays_on_custom_board() {
+        let game = Game::with_board(
+            Strip::default(),
+            Box::new(FirstFreeCell),
+            Box::new(FirstFreeCell),
+        );
+
+        let game = game.play_to_end().expect("Error while playing");
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::BoardFull));
+        assert_eq!(game.history().len(), 3);
+    }
+
+    fn place(mark: Mark, row: usize, col: usize) -> PlayedMove {
+        PlayedMove {
+            mark,
+            player_move: Move::Place(row, col),
+        }
+    }
+
+    #[test]
+    fn from_moves_rebuilds_position() {
+        let moves = [
+            place(Mark::X, 0, 0),
+            place(Mark::O, 1, 1),
+            PlayedMove {
+                mark: Mark::X,
+                player_move: Move::OfferDraw,
+            },
+            place(Mark::X, 2, 2),
+        ];
+        let player_x = Box::new(player::tests::MockPlayer(0, 1));
+        let player_o = Box::new(player::tests::MockPlayer(0, 2));
+        let game = expect_in_progress(Game::from_moves(player_x, player_o, &moves));
+
+        assert_eq!(game.current_player().mark, Mark::O);
+        assert_eq!(game.board().cell_count(), 3);
+        assert_eq!(game.history(), &moves);
+    }
+
+    #[test]
+    fn from_moves_detects_finished_game() {
+        let moves = [
+            place(Mark::X, 0, 0),
+            place(Mark::O, 1, 0),
+            place(Mark::X, 0, 1),
+            place(Mark::O, 1, 1),
+            place(Mark::X, 0, 2),
+        ];
+        let player_x = Box::new(player::tests::MockPlayer(0, 0));
+        let player_o = Box::new(player::tests::MockPlayer(0, 0));
+        let game = expect_finished(Game::from_moves(player_x, player_o, &moves));
+        assert_eq!(game.result().winner(), Some(Mark::X));
+    }
+
+    #[test]
+    fn from_moves_rejects_invalid_moves() {
+        let new_game = || {
+            Game::new(
+                Box::new(player::tests::MockPlayer(0, 0)),
+                Box::new(player::tests::MockPlayer(0, 0)),
+            )
+        };
+
+        let res = new_game().replay(&[place(Mark::X, 0, 0), place(Mark::X, 1, 1)]);
+        assert!(matches!(
+            res,
+            Err(ReplayError::WrongTurn {
+                index: 1,
+                expected: Mark::O
+            })
+        ));
+
+        let res = new_game().replay(&[place(Mark::X, 0, 0), place(Mark::O, 0, 0)]);
+        assert!(matches!(
+            res,
+            Err(ReplayError::IllegalMove {
+                index: 1,
+                error: GridPlacementError::CellInUse { row: 0, col: 0 }
+            })
+        ));
+
+        let resign = PlayedMove {
+            mark: Mark::X,
+            player_move: Move::Resign,
+        };
+        let res = new_game().replay(&[resign, place(Mark::O, 0, 0)]);
+        assert!(matches!(res, Err(ReplayError::GameOver { index: 1 })));
+    }
+
+    #[test]
+    fn builder_uses_starting_position() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+
+        let game = Game::builder()
+            .board(grid)
+            .first_mark(Mark::O)
+            .player_x(Box::new(player::tests::MockPlayer(2, 2)))
+            .player_o(Box::new(player::tests::MockPlayer(0, 1)))
+            .build()
+            .expect("Error building game");
+
+        assert_eq!(game.current_player().mark, Mark::O);
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.board().get_cell(0, 1).try_get_mark(), Some(&Mark::O));
+        assert_eq!(game.board().cell_count(), 3);
+    }
+
+    #[test]
+    fn builder_picks_random_first_mark() {
+        let game = Game::builder()
+            .random_first_mark()
+            .player_x(Box::new(player::tests::MockPlayer(0, 0)))
+            .player_o(Box::new(player::tests::MockPlayer(0, 0)))
+            .build()
+            .expect("Error building game");
+
+        assert!([Mark::X, Mark::O].contains(&game.current_player().mark));
+    }
+
+    #[test]
+    fn builder_rejects_finished_position() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::O);
+        grid.set_cell(1, 1, Mark::O);
+        grid.set_cell(2, 2, Mark::O);
+
+        let res = Game::builder()
+            .board(grid)
+            .player_x(Box::new(player::tests::MockPlayer(0, 1)))
+            .player_o(Box::new(player::tests::MockPlayer(0, 2)))
+            .build();
+        assert!(matches!(res, Err(GameBuilderError::PositionFinished)));
+    }
+
+    #[test]
+    fn builder_requires_players() {
+        let res = Game::builder()
+            .player_x(Box::new(player::tests::MockPlayer(0, 0)))
+            .build();
+        assert!(matches!(res, Err(GameBuilderError::MissingPlayer(Mark::O))));
+    }
+
+    #[test]
+    fn accepted_draw_offer_ends_game() {
+        let player_x = Box::new(player::tests::DrawingPlayer(false));
+        let player_o = Box::new(player::tests::DrawingPlayer(true));
+        let game = Game::new(player_x, player_o);
+
+        let game = expect_finished(game.try_move());
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::Agreement));
+    }
+
+    #[test]
+    fn declined_draw_offer_keeps_turn() {
+        let player_x = Box::new(player::tests::DrawingPlayer(false));
+        let player_o = Box::new(player::tests::MockPlayer(1, 1));
+        let game = Game::new(player_x, player_o);
+
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.current_player().mark, Mark::X);
+        assert_eq!(game.board().cell_count(), 0);
+    }
+
+    #[derive(Debug)]
+    pub(super) struct SlowPlayer(pub(super) Duration);
+
+    impl Player for SlowPlayer {
+        fn get_move(&self, _: &Grid, _: &Mark) -> Result<Move, PlayerError> {
+            thread::sleep(self.0);
+            Ok(Move::Place(0, 0))
+        }
+    }
+
+    fn slow_game(on_timeout: TimeoutPolicy) -> Game {
+        Game::builder()
+            .player_x(Box::new(SlowPlayer(Duration::from_millis(20))))
+            .player_o(Box::new(player::tests::MockPlayer(1, 1)))
+            .move_time_limit(MoveTimeLimit {
+                limit: Duration::from_millis(1),
+                on_timeout,
+            })
+            .build()
+            .expect("Error building game")
+    }
+
+    #[test]
+    fn slow_player_forfeits() {
+        let game = expect_finished(slow_game(TimeoutPolicy::Forfeit).try_move());
+        assert_eq!(
+            game.result(),
+            GameResult::Win {
+                winner: Mark::O,
+                reason: WinReason::Timeout
+            }
+        );
+        assert_eq!(game.history()[0].player_move, Move::Resign);
+    }
+
+    #[test]
+    fn slow_player_move_is_replaced() {
+        let game = expect_in_progress(slow_game(TimeoutPolicy::RandomMove).try_move());
+        assert_eq!(game.board().cell_count(), 1);
+        assert_eq!(game.current_player().mark, Mark::O);
+
+        let mut game = slow_game(TimeoutPolicy::Forfeit);
+        game.set_move_time_limit(None);
+        let game = expect_in_progress(game.try_move());
+        assert_eq!(game.board().get_cell(0, 0).try_get_mark(), Some(&Mark::X));
+    }
+
+    #[test]
+    fn stuck_player_is_stopped_at_the_limit() {
+        let game = Game::builder()
+            .player_x(Box::new(SlowPlayer(Duration::from_secs(60))))
+            .player_o(Box::new(player::tests::MockPlayer(1, 1)))
+            .move_time_limit(MoveTimeLimit {
+                limit: Duration::from_millis(10),
+                on_timeout: TimeoutPolicy::RandomMove,
+            })
+            .build()
+            .expect("Error building game");
+
+        let start = Instant::now();
+        let game = expect_in_progress(game.try_move());
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(game.board().cell_count(), 1);
+        assert_eq!(game.current_player().mark, Mark::O);
     }
 }
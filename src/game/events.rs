@@ -0,0 +1,376 @@
+//! Networked games played on a worker thread, reporting what happens on a channel, see
+//! [`GameEvents`].
+
+use std::{
+    io::ErrorKind,
+    panic,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    grid::{Grid, Mark},
+    player::{Move, Player, PlayerError},
+};
+
+use super::{GameResult, MoveCanceller, NetworkedGame, NetworkedGameError, CANCEL_POLL_INTERVAL};
+
+/// What happened in a game played with [`GameEvents`].
+#[derive(Debug)]
+pub enum GameEvent {
+    /// Either player placed a mark.
+    MovePlayed {
+        mark: Mark,
+        row: usize,
+        col: usize,
+    },
+    /// It is now `mark`'s turn, played by the local player if `local`. Sent when the game starts
+    /// and after every move.
+    TurnChanged {
+        mark: Mark,
+        local: bool,
+    },
+    ChatReceived(String),
+    /// The local player's last move couldn't be played, because the cell is taken or the remote
+    /// player rejected it. It is still their turn.
+    MoveRefused(NetworkedGameError),
+    /// The game is over. It is the last event, and the game can be taken back with
+    /// [`GameEvents::join`].
+    GameOver(GameResult),
+    /// The game stopped on an error, like a lost connection, or with
+    /// [`NetworkedGameError::Cancelled`] once [`GameEvents::cancel`] was called. It is the last
+    /// event.
+    Failed(NetworkedGameError),
+}
+
+/// What the local player does in a game played with [`GameEvents`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameCommand {
+    /// Plays the local player's move. Moves sent before the [`GameEvent::TurnChanged`] event giving
+    /// them the turn are ignored.
+    Move(Move),
+    /// Sends a chat message to the remote player. Messages are sent between turns: once the local
+    /// player played their move, or once the remote player's move arrived. Messages longer than
+    /// [`MAX_CHAT_LEN`](super::MAX_CHAT_LEN) bytes are dropped.
+    Chat(String),
+}
+
+/// Networked game played on a worker thread, for GUI frameworks which can't block on
+/// [`NetworkedGame::try_move`]. The game reports what happens as [`GameEvent`]s, and the local
+/// player's moves are sent as [`GameCommand`]s. Both go through `mpsc` channels, so the UI can
+/// poll events with [`Receiver::try_recv`] from its own loop.
+///
+/// The game is played until it is over or fails, after which it can be taken back with
+/// [`GameEvents::join`], to offer a rematch for instance. Dropping the handle while the local
+/// player has to move stops the game, and [`GameEvents::cancel`] stops it at any time.
+#[derive(Debug)]
+pub struct GameEvents<G> {
+    events: Receiver<GameEvent>,
+    commands: Sender<GameCommand>,
+    canceller: MoveCanceller,
+    thread: JoinHandle<G>,
+}
+
+impl<G: NetworkedGame + Send + 'static> GameEvents<G> {
+    /// Starts playing `game` on a new thread. Its chat handler is replaced, since chat messages
+    /// are reported as [`GameEvent::ChatReceived`].
+    pub fn spawn(mut game: G) -> Self {
+        let (event_sender, events) = mpsc::channel();
+        let (commands, command_receiver) = mpsc::channel();
+        let canceller = game.canceller();
+        let player = Arc::new(CommandPlayer {
+            commands: Mutex::new(command_receiver),
+            outgoing: Mutex::default(),
+            canceller: canceller.clone(),
+        });
+        let thread = thread::spawn(move || {
+            play(&mut game, &player, &event_sender);
+            game
+        });
+        Self {
+            events,
+            commands,
+            canceller,
+            thread,
+        }
+    }
+}
+
+impl<G> GameEvents<G> {
+    /// Returns the channel the game's events are received on.
+    pub fn events(&self) -> &Receiver<GameEvent> {
+        &self.events
+    }
+
+    /// Sends `command` to the game. Returns false if the game already ended.
+    pub fn send(&self, command: GameCommand) -> bool {
+        self.commands.send(command).is_ok()
+    }
+
+    /// Stops the game, whoever's turn it is, to quit it from the UI. The game ends with
+    /// [`GameEvent::Failed`] within [`CANCEL_POLL_INTERVAL`], without playing a move. Its
+    /// [`canceller`](NetworkedGame::canceller) must be reset before playing it again after
+    /// [`GameEvents::join`].
+    pub fn cancel(&self) {
+        self.canceller.cancel();
+    }
+
+    /// Returns whether the game ended, after which [`GameEvents::join`] doesn't block.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the game to end, and returns it.
+    pub fn join(self) -> G {
+        self.thread
+            .join()
+            .unwrap_or_else(|payload| panic::resume_unwind(payload))
+    }
+}
+
+/// Local player choosing the moves sent as commands.
+#[derive(Debug)]
+struct CommandPlayer {
+    commands: Mutex<Receiver<GameCommand>>,
+    /// Chat messages sent while choosing a move, to send once it is played
+    outgoing: Mutex<Vec<String>>,
+    canceller: MoveCanceller,
+}
+
+impl CommandPlayer {
+    /// Returns the chat messages to send. After the remote player's turn, the commands sent during
+    /// it are also taken, dropping the moves sent out of turn.
+    fn take_outgoing(&self, after_remote_turn: bool) -> Vec<String> {
+        let mut outgoing = std::mem::take(&mut *self.outgoing.lock().unwrap());
+        if !after_remote_turn {
+            return outgoing;
+        }
+        let commands = self.commands.lock().unwrap();
+        loop {
+            match commands.try_recv() {
+                Ok(GameCommand::Chat(message)) => outgoing.push(message),
+                Ok(GameCommand::Move(_)) => {}
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => return outgoing,
+            }
+        }
+    }
+}
+
+impl Player for CommandPlayer {
+    fn get_move(&self, _grid: &Grid, _mark: &Mark) -> Result<Move, PlayerError> {
+        let commands = self.commands.lock().unwrap();
+        loop {
+            match commands.recv_timeout(CANCEL_POLL_INTERVAL) {
+                Ok(GameCommand::Move(player_move)) => return Ok(player_move),
+                Ok(GameCommand::Chat(message)) => self.outgoing.lock().unwrap().push(message),
+                // The game drops the move and reports the cancellation
+                Err(RecvTimeoutError::Timeout) if self.canceller.is_cancelled() => {
+                    return Err(PlayerError::EndOfInput)
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(PlayerError::EndOfInput),
+            }
+        }
+    }
+}
+
+fn play<G: NetworkedGame>(game: &mut G, player: &Arc<CommandPlayer>, events: &Sender<GameEvent>) {
+    let local_player: Arc<dyn Player> = player.clone();
+    let sender = events.clone();
+    game.set_chat_handler(Box::new(move |message| {
+        let _ = sender.send(GameEvent::ChatReceived(message.to_owned()));
+    }));
+
+    let mut turn_changed = true;
+    let event = 'game: loop {
+        if let Some(result) = game.result() {
+            break GameEvent::GameOver(result);
+        }
+        if turn_changed {
+            let local = game.is_local_turn();
+            let mark = if local {
+                game.local_mark()
+            } else {
+                game.local_mark().opposite()
+            };
+            // The game goes on even if nobody listens anymore
+            let _ = events.send(GameEvent::TurnChanged { mark, local });
+        }
+
+        let before = *game.grid();
+        let local = game.is_local_turn();
+        turn_changed = match game.try_move(&local_player) {
+            Ok(()) => true,
+            Err(
+                e @ (NetworkedGameError::PlayError(_)
+                | NetworkedGameError::Rejected(_)
+                | NetworkedGameError::UnsupportedMove(_)),
+            ) => {
+                let _ = events.send(GameEvent::MoveRefused(e));
+                false
+            }
+            Err(e) => break GameEvent::Failed(e),
+        };
+        if let Some((row, col, mark)) = placed_mark(&before, game.grid()) {
+            let _ = events.send(GameEvent::MovePlayed { mark, row, col });
+        }
+
+        for message in player.take_outgoing(!local) {
+            match game.send_chat(&message) {
+                Err(e) if e.kind() != ErrorKind::InvalidInput => {
+                    break 'game GameEvent::Failed(e.into())
+                }
+                _ => {}
+            }
+        }
+    };
+    let _ = events.send(event);
+}
+
+/// Returns the cell marked on `after` but not on `before`, with its mark.
+fn placed_mark(before: &Grid, after: &Grid) -> Option<(usize, usize, Mark)> {
+    (0..3)
+        .flat_map(|row| (0..3).map(move |col| (row, col)))
+        .find_map(|(row, col)| {
+            let mark = after.get_cell(row, col).try_get_mark()?;
+            let was_empty = before.get_cell(row, col).try_get_mark().is_none();
+            was_empty.then_some((row, col, *mark))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{
+        game::{MemoryStream, RemoteGame, ServerGame, ServerGameSettings, WinReason},
+        player::tests::{shared, MockPlayer},
+    };
+
+    use super::*;
+
+    #[test]
+    fn moves_are_commanded_and_reported() {
+        let (host, client) = MemoryStream::pair();
+        let remote = thread::spawn(move || {
+            let mut game = RemoteGame::connect_stream(client, None).unwrap();
+            game.send_chat("hi").unwrap();
+            game.try_move(&shared(MockPlayer(0, 0))).unwrap();
+            game.try_move(&shared(MockPlayer(0, 0))).unwrap();
+            game.try_move(&shared(MockPlayer(0, 0))).unwrap();
+            game
+        });
+        let game = ServerGame::accept_stream(host, &ServerGameSettings::default()).unwrap();
+        let events = GameEvents::spawn(game);
+        let next = || events.events().recv().unwrap();
+
+        assert!(matches!(
+            next(),
+            GameEvent::TurnChanged {
+                mark: Mark::X,
+                local: true
+            }
+        ));
+        // Illegal moves are refused, and the player keeps the turn
+        assert!(events.send(GameCommand::Move(Move::Place(3, 0))));
+        assert!(matches!(next(), GameEvent::MoveRefused(_)));
+        events.send(GameCommand::Chat("hello".to_owned()));
+        events.send(GameCommand::Move(Move::Place(1, 1)));
+        assert!(matches!(
+            next(),
+            GameEvent::MovePlayed {
+                mark: Mark::X,
+                row: 1,
+                col: 1
+            }
+        ));
+        assert!(matches!(
+            next(),
+            GameEvent::TurnChanged { local: false, .. }
+        ));
+        assert!(matches!(next(), GameEvent::ChatReceived(message) if message == "hi"));
+        assert!(matches!(
+            next(),
+            GameEvent::MovePlayed {
+                mark: Mark::O,
+                row: 0,
+                col: 0
+            }
+        ));
+        assert!(matches!(next(), GameEvent::TurnChanged { local: true, .. }));
+        events.send(GameCommand::Move(Move::Resign));
+        assert!(matches!(
+            next(),
+            GameEvent::GameOver(GameResult::Win {
+                winner: Mark::O,
+                reason: WinReason::Resignation
+            })
+        ));
+
+        let game = events.join();
+        let remote = remote.join().unwrap();
+        assert_eq!(game.result(), remote.result());
+        assert_eq!(game.grid(), remote.grid());
+    }
+
+    #[test]
+    fn lost_connections_end_the_game() {
+        let (host, client) = MemoryStream::pair();
+        let remote = thread::spawn(move || RemoteGame::connect_stream(client, None).unwrap());
+        let settings = ServerGameSettings::default().with_host_plays_first(false);
+        let game = ServerGame::accept_stream(host, &settings).unwrap();
+        drop(remote.join().unwrap());
+
+        let events = GameEvents::spawn(game);
+        // The chat handler keeps a sender alive, so the channel stays open
+        let last = events.events().iter().nth(1).unwrap();
+        assert!(matches!(last, GameEvent::Failed(NetworkedGameError::Io(_))));
+        assert!(events.join().result().is_none());
+    }
+
+    #[test]
+    fn cancelled_games_stop_on_either_turn() {
+        let (host, client) = MemoryStream::pair();
+        let remote = thread::spawn(move || RemoteGame::connect_stream(client, None).unwrap());
+        let game = ServerGame::accept_stream(host, &ServerGameSettings::default()).unwrap();
+        let mut remote = remote.join().unwrap();
+
+        // The host's player is waiting for a command
+        let events = GameEvents::spawn(game);
+        assert!(matches!(
+            events.events().recv().unwrap(),
+            GameEvent::TurnChanged { local: true, .. }
+        ));
+        events.cancel();
+        let last = events.events().recv().unwrap();
+        assert!(matches!(
+            last,
+            GameEvent::Failed(NetworkedGameError::Cancelled)
+        ));
+
+        // The host is waiting for the remote player's move
+        let game = events.join();
+        game.canceller().reset();
+        let events = GameEvents::spawn(game);
+        events.send(GameCommand::Move(Move::Place(1, 1)));
+        let remote_turn = events
+            .events()
+            .iter()
+            .find(|event| matches!(event, GameEvent::TurnChanged { local: false, .. }));
+        assert!(remote_turn.is_some());
+        events.cancel();
+        let last = events.events().recv().unwrap();
+        assert!(matches!(
+            last,
+            GameEvent::Failed(NetworkedGameError::Cancelled)
+        ));
+        let game = events.join();
+        remote.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        remote.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        assert_eq!(game.grid().cell_count(), 1);
+        assert_eq!(remote.grid().cell_count(), 2);
+    }
+}
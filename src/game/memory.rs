@@ -0,0 +1,244 @@
+//! In-memory connections for networked games, see [`MemoryStream`].
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use super::GameStream;
+
+/// One end of an in-memory connection, to play networked games within a process without sockets.
+/// Both ends are created with [`MemoryStream::pair`]: bytes written to one are read from the other.
+///
+/// Games are played over it with the same handshake and packets as over TCP, hosted with
+/// [`ServerGame::accept_stream`](super::ServerGame::accept_stream) and joined with
+/// [`RemoteGame::connect_stream`](super::RemoteGame::connect_stream). This makes the protocol
+/// deterministic to test: nothing binds a port, and a dropped end is noticed right away.
+///
+/// ```
+/// # use std::thread;
+/// # use tictactoe::game::{
+/// #     MemoryStream, NetworkedGame, RemoteGame, ServerGame, ServerGameSettings,
+/// # };
+/// let (host, client) = MemoryStream::pair();
+/// let server = thread::spawn(move || {
+///     ServerGame::accept_stream(host, &ServerGameSettings::default()).unwrap()
+/// });
+/// let client = RemoteGame::connect_stream(client, None).unwrap();
+/// let server = server.join().unwrap();
+/// assert_eq!(server.is_local_turn(), !client.is_local_turn());
+/// ```
+///
+/// Memory streams don't have an address, so [`GameStream::peer_addr`] always fails. Writes never
+/// block, and write timeouts are ignored.
+#[derive(Debug)]
+pub struct MemoryStream {
+    /// Sends written bytes to the other end, until the stream is shut down
+    sender: Mutex<Option<Sender<Vec<u8>>>>,
+    receiver: Mutex<Receiver<Vec<u8>>>,
+    /// Bytes received from the other end but not read yet
+    pending: Vec<u8>,
+    read_timeout: Mutex<Option<Duration>>,
+    shut_down: AtomicBool,
+}
+
+impl MemoryStream {
+    /// Returns both ends of a new connection.
+    pub fn pair() -> (Self, Self) {
+        let (a_sender, b_receiver) = mpsc::channel();
+        let (b_sender, a_receiver) = mpsc::channel();
+        (
+            Self::new(a_sender, a_receiver),
+            Self::new(b_sender, b_receiver),
+        )
+    }
+
+    fn new(sender: Sender<Vec<u8>>, receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            sender: Mutex::new(Some(sender)),
+            receiver: Mutex::new(receiver),
+            pending: vec![],
+            read_timeout: Mutex::new(None),
+            shut_down: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.shut_down.load(Ordering::Relaxed) {
+            return Ok(0);
+        }
+
+        if self.pending.is_empty() {
+            let timeout = *self.read_timeout.get_mut().unwrap();
+            let receiver = self.receiver.get_mut().unwrap();
+            let received = match timeout {
+                Some(timeout) => receiver.recv_timeout(timeout),
+                None => receiver.recv().map_err(RecvTimeoutError::from),
+            };
+            match received {
+                Ok(bytes) => self.pending = bytes,
+                Err(RecvTimeoutError::Timeout) => return Err(ErrorKind::TimedOut.into()),
+                // The other end was dropped or shut down
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.pending.len());
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending.drain(..len);
+        Ok(len)
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let sender = self.sender.get_mut().unwrap();
+        match sender {
+            Some(sender) if sender.send(buf.to_vec()).is_ok() => Ok(buf.len()),
+            _ => Err(ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl GameStream for MemoryStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Zero isn't a valid read timeout",
+            ));
+        }
+        *self.read_timeout.lock().unwrap() = timeout;
+        Ok(())
+    }
+
+    fn set_write_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Err(io::Error::new(
+            ErrorKind::NotConnected,
+            "Memory streams don't have an address",
+        ))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.sender.lock().unwrap().take();
+        self.shut_down.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufRead, io::BufReader, thread};
+
+    use crate::{
+        game::{
+            Introduction, NetworkedGame, NetworkedGameError, RemoteGame, ServerGame,
+            ServerGameSettings,
+        },
+        grid::Mark,
+        player::tests::{shared, MockPlayer},
+        protocol::ClientHello,
+    };
+
+    use super::*;
+
+    #[test]
+    fn bytes_go_both_ways() {
+        let (mut a, b) = MemoryStream::pair();
+        a.write_all(b"hello\nworld\n").unwrap();
+        let mut b = BufReader::new(b);
+        let mut line = String::new();
+        b.read_line(&mut line).unwrap();
+        assert_eq!(line, "hello\n");
+
+        b.get_mut().write_all(b"back").unwrap();
+        let mut buf = [0; 4];
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"back");
+
+        // Reads time out once no data is left
+        a.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+        assert_eq!(a.read(&mut buf).unwrap_err().kind(), ErrorKind::TimedOut);
+        assert!(a.peer_addr().is_err());
+
+        // The other end reads what was sent before the shutdown, then the end of the stream
+        a.shutdown().unwrap();
+        assert!(a.write(b"late").is_err());
+        line.clear();
+        b.read_line(&mut line).unwrap();
+        assert_eq!(line, "world\n");
+        assert_eq!(b.read_line(&mut line).unwrap(), 0);
+
+        drop(a);
+        assert!(b.get_mut().write(b"gone").is_err());
+    }
+
+    #[test]
+    fn networked_games_are_played_in_memory() {
+        let (host, client) = MemoryStream::pair();
+        let settings = ServerGameSettings::default()
+            .with_host_plays_first(true)
+            .with_host_mark(Mark::X)
+            .with_nickname("host");
+        let server = thread::spawn(move || {
+            let mut server = ServerGame::accept_stream(host, &settings).unwrap();
+            // X wins on the first row
+            for (row, col) in [(0, 0), (0, 1), (0, 2)] {
+                server.try_move(&shared(MockPlayer(row, col))).unwrap();
+                if server.result().is_none() {
+                    server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+                }
+            }
+            server
+        });
+
+        let mut client = RemoteGame::connect_stream(client, Some(&Introduction::new("guest")))
+            .expect("the host accepts the client");
+        assert_eq!(client.peer().nickname.as_deref(), Some("host"));
+        for row in 1..3 {
+            client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+            client.try_move(&shared(MockPlayer(row, 0))).unwrap();
+        }
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+
+        let server = server.join().unwrap();
+        assert_eq!(server.peer().nickname.as_deref(), Some("guest"));
+        assert_eq!(server.peer().addr, None);
+        assert_eq!(server.result().unwrap().winner(), Some(Mark::X));
+        assert_eq!(client.result(), server.result());
+        assert!(matches!(
+            client.try_move(&shared(MockPlayer(2, 2))),
+            Err(NetworkedGameError::GameOver)
+        ));
+    }
+
+    #[test]
+    fn refused_clients_are_reported() {
+        let (host, mut client) = MemoryStream::pair();
+        let settings = ServerGameSettings::default().with_password("secret");
+        client.write_all(&ClientHello.to_bytes()).unwrap();
+        let refused = ServerGame::accept_stream(host, &settings);
+        assert!(refused.is_err_and(|e| e.kind() == ErrorKind::InvalidData));
+
+        // Nobody is on the other end of a dropped stream
+        let (host, client) = MemoryStream::pair();
+        drop(client);
+        assert!(ServerGame::accept_stream(host, &ServerGameSettings::default()).is_err());
+    }
+}
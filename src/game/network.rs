@@ -0,0 +1,3669 @@
+//! Games against a remote player, hosted with [`ServerGame`] or joined with [`RemoteGame`], and
+//! the [`NetworkedGame`] trait they share. Only built with the `network` feature.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    ops::RangeInclusive,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+use rand::Rng;
+use thiserror::Error;
+
+pub use crate::{
+    discovery::DISCOVERY_PORT,
+    protocol::{Introduction, PacketParseError, PROTOCOL_VERSION},
+};
+
+#[cfg(feature = "json")]
+use crate::json::JsonStream;
+#[cfg(feature = "noise")]
+use crate::noise::{NoiseConfig, NoiseStream};
+#[cfg(feature = "websocket")]
+use crate::websocket::WebSocketStream;
+use crate::{
+    discovery::{self, Announcer},
+    grid::{Grid, GridPlacementError, Mark},
+    lobby::RoomCode,
+    player::{Move, Player, PlayerError},
+    protocol::{
+        self, BoardSync, ChatMessage, ClientHello, ClientResume, ClockUpdate, EndOfGame, Heartbeat,
+        InvalidMove, MoveAck, MoveBroadcast, NewGame, Packet, PlayerMove, Reconnect, Rematch,
+        Resign, ServerHello, ServerShutdown,
+    },
+    trace::{self, ConnectionSpan},
+};
+
+use self::seal::ServerGameState;
+use super::{board_result, move_within, GameResult, WinReason};
+
+#[derive(Debug, Error)]
+pub enum NetworkedGameError {
+    #[error("Error while trying a move: {0}")]
+    PlayError(#[from] GridPlacementError),
+    /// The local player failed to choose a move.
+    #[error("Error while choosing a move: {0}")]
+    Player(#[from] PlayerError),
+    /// The local player chose a move that can't be played over the network.
+    #[error("Move not supported in networked games: {0:?}")]
+    UnsupportedMove(Move),
+    /// The player with this mark exceeded the move time limit, see
+    /// [`NetworkedGame::set_move_time_limit`].
+    #[error("Player {0} ran out of time")]
+    Timeout(Mark),
+    /// The game already has a result, see [`NetworkedGame::result`].
+    #[error("The game is already over")]
+    GameOver,
+    /// The remote player couldn't play the local player's last move on their grid. The move was
+    /// taken back, and it is the local player's turn again.
+    #[error("The remote player rejected the move: {0}")]
+    Rejected(#[source] GridPlacementError),
+    /// The move was interrupted with the game's [`MoveCanceller`], and wasn't played.
+    #[error("The move was cancelled")]
+    Cancelled,
+    #[error("IO error while playing: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// State of a networked game, which can be saved to resume the game after the connection was lost.
+/// See [`RemoteGame::resume`] and [`ServerGame::resume`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameSnapshot {
+    pub grid: Grid,
+    pub is_local_turn: bool,
+    pub local_mark: Mark,
+}
+
+impl GameSnapshot {
+    pub fn to_bytes(&self) -> [u8; 10] {
+        let mut bytes = [0_u8; 10];
+        bytes[0..9].copy_from_slice(&protocol::grid_to_bytes(&self.grid));
+        bytes[9] = protocol::turn_flags(self.is_local_turn, self.local_mark);
+        bytes
+    }
+
+    pub fn from_bytes(value: &[u8]) -> io::Result<Self> {
+        let parse = || {
+            if value.len() != 10 {
+                return Err(PacketParseError::InvalidSize { packet: "snapshot" });
+            }
+            let grid = protocol::grid_from_bytes(&value[0..9], "snapshot")?;
+            let (is_local_turn, local_mark) = protocol::parse_turn_flags(value[9], "snapshot")?;
+            Ok(Self {
+                grid,
+                is_local_turn,
+                local_mark,
+            })
+        };
+
+        parse().map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Time left to each player of a timed networked game, see [`NetworkedGame::set_clock`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    /// Time both players started with
+    time: Duration,
+    x: Duration,
+    o: Duration,
+}
+
+impl Clock {
+    fn new(time: Duration) -> Self {
+        Self {
+            time,
+            x: time,
+            o: time,
+        }
+    }
+
+    /// Returns the time `mark` has left to play their moves.
+    pub fn remaining(&self, mark: Mark) -> Duration {
+        match mark {
+            Mark::X => self.x,
+            _ => self.o,
+        }
+    }
+
+    fn remaining_mut(&mut self, mark: Mark) -> &mut Duration {
+        match mark {
+            Mark::X => &mut self.x,
+            _ => &mut self.o,
+        }
+    }
+
+    fn to_update(self) -> ClockUpdate {
+        ClockUpdate {
+            x: self.x,
+            o: self.o,
+        }
+    }
+}
+
+/// Who the remote player of a networked game is, see [`NetworkedGame::peer`]. Its `Display`
+/// implementation writes the nickname, if any, and the address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerInfo {
+    /// Address of the current connection to the remote side, if it could be read
+    pub addr: Option<SocketAddr>,
+    /// Nickname the remote player introduced themselves with, see [`Introduction`]
+    pub nickname: Option<String>,
+    /// Version of the protocol spoken by both sides: the lower of the 2 when the remote side
+    /// introduced itself, and 1 otherwise
+    pub protocol_version: u8,
+    /// Whether the game is resumed over a new connection if this one drops, see
+    /// [`ServerGameSettings::reconnect_grace_period`]
+    pub reconnects: bool,
+}
+
+impl PeerInfo {
+    /// Returns what is known of a remote side which sent `intro`, or didn't introduce itself.
+    fn new(addr: Option<SocketAddr>, intro: Option<&Introduction>, reconnects: bool) -> Self {
+        Self {
+            addr,
+            nickname: intro
+                .filter(|intro| !intro.nickname.is_empty())
+                .map(|intro| intro.nickname.clone()),
+            protocol_version: intro.map_or(1, |intro| intro.version.min(PROTOCOL_VERSION)),
+            reconnects,
+        }
+    }
+}
+
+impl Display for PeerInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.nickname, self.addr) {
+            (Some(nickname), Some(addr)) => write!(f, "{} ({})", nickname, addr),
+            (Some(nickname), None) => write!(f, "{}", nickname),
+            (None, Some(addr)) => write!(f, "{}", addr),
+            (None, None) => write!(f, "an unknown player"),
+        }
+    }
+}
+
+/// Maximum length of a chat message, in bytes. See [`NetworkedGame::send_chat`].
+pub const MAX_CHAT_LEN: usize = 256;
+
+/// Function called with every chat message received from the remote player, see
+/// [`NetworkedGame::set_chat_handler`].
+pub type ChatCallback = Box<dyn FnMut(&str) + Send + Sync>;
+
+/// Chat handler of a networked game, if one was set.
+#[derive(Default)]
+pub(crate) struct ChatHandler(Option<ChatCallback>);
+
+impl ChatHandler {
+    pub(crate) fn set(&mut self, handler: ChatCallback) {
+        self.0 = Some(handler);
+    }
+
+    /// Passes `message` to the handler, or drops it if no handler is set.
+    pub(crate) fn receive(&mut self, message: &str) {
+        if let Some(handler) = &mut self.0 {
+            handler(message);
+        }
+    }
+
+    /// Passes a notice of the server's shutdown (see [`ServerShutdown`]) to the handler, as a
+    /// message from the server.
+    pub(crate) fn server_shutdown(&mut self, pkt: ServerShutdown) {
+        self.receive(&format!(
+            "The server is shutting down in {} second(s)",
+            pkt.grace_period.as_secs()
+        ));
+    }
+}
+
+impl Debug for ChatHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ChatHandler")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+pub trait NetworkedGame {
+    fn grid(&self) -> &Grid;
+
+    fn grid_mut(&mut self) -> &mut Grid;
+
+    fn set_next_turn(&mut self);
+
+    fn is_local_turn(&self) -> bool;
+
+    fn local_mark(&self) -> Mark;
+
+    /// Plays the next turn: the local player's move is sent to the remote player, or the remote
+    /// player's move is received. Either player can resign instead of placing a mark. Fails with
+    /// [`NetworkedGameError::GameOver`] once the game has a result.
+    ///
+    /// With a move time limit, the local player chooses their move on a worker thread, which is
+    /// why they are shared. The game stops waiting for them once the limit is over, and fails with
+    /// [`NetworkedGameError::Timeout`].
+    fn try_move(&mut self, player: &Arc<dyn Player>) -> Result<(), NetworkedGameError>;
+
+    /// Returns the result of the game once it is over, after the last mark was placed or a player
+    /// resigned.
+    fn result(&self) -> Option<GameResult>;
+
+    /// Once the game is over, tells the remote player whether the local player wants a rematch,
+    /// and waits for their answer when they do. Fails with [`ErrorKind::InvalidInput`] if the game
+    /// isn't over.
+    ///
+    /// Returns `true` if both players accepted, and the game then restarts over the same
+    /// connection with the marks and first player swapped. When the host offered a new game with
+    /// [`ServerGame::new_game`] instead, accepting starts it with the host's settings. The remote
+    /// player can take a while to answer, so the local player's answer should be chosen within
+    /// [`NetworkedGame::with_heartbeat`].
+    fn rematch(&mut self, accept: bool) -> io::Result<bool>;
+
+    /// Sends the local grid and turn to the remote player, who checks them against their own while
+    /// waiting for the local player's move. A mismatch makes the remote player's
+    /// [`NetworkedGame::try_move`] fail with [`ErrorKind::InvalidData`]. Fails with
+    /// [`ErrorKind::InvalidInput`] if it isn't the local player's turn, since the remote side only
+    /// checks the position while both sides agree it can't change.
+    fn verify_board(&mut self) -> io::Result<()>;
+
+    /// Limits how long each player has to choose a move, or removes the limit with `None`.
+    ///
+    /// When the remote player doesn't send their move in time, or the local player takes too long
+    /// to choose theirs, [`NetworkedGame::try_move`] fails with [`NetworkedGameError::Timeout`]
+    /// and the game should be considered lost by that player. A late local move isn't sent, so the
+    /// remote side also times out if it set a limit.
+    fn set_move_time_limit(&mut self, limit: Option<Duration>) -> io::Result<()>;
+
+    /// Gives each player `time` to play all their moves, like a chess clock, or removes the clock
+    /// with `None`. Both sides must set the same clock before the first move, and the clocks start
+    /// over on a rematch.
+    ///
+    /// Players send the time left on both clocks along with each move, so both sides agree on
+    /// them. A player running out of time loses the game: their side sends the result once they
+    /// chose their move, and the remote side ends the game on its own if the move still didn't
+    /// arrive shortly after. [`NetworkedGame::try_move`] then returns `Ok` with a
+    /// [`WinReason::Timeout`] result.
+    fn set_clock(&mut self, time: Option<Duration>);
+
+    /// Returns the time left to each player, if a clock was set.
+    fn clock(&self) -> Option<Clock>;
+
+    /// Sends a chat message to the remote player. Fails with [`ErrorKind::InvalidInput`] if the
+    /// message is longer than [`MAX_CHAT_LEN`] bytes.
+    fn send_chat(&mut self, message: &str) -> io::Result<()>;
+
+    /// Sets the function called with every chat message received from the remote player while
+    /// [`NetworkedGame::try_move`] waits for their move. Messages received without a handler are
+    /// dropped.
+    fn set_chat_handler(&mut self, handler: ChatCallback);
+
+    /// Calls `f`, sending heartbeats to the remote player until it returns.
+    ///
+    /// Heartbeats are sent while the local player chooses their move, so the remote side knows the
+    /// connection is still alive. A side waiting for a move without receiving any packet for a
+    /// while considers the connection lost, so anything else keeping the local side busy for long,
+    /// like waiting for user input, should be wrapped in this.
+    fn with_heartbeat<T>(&mut self, f: impl FnOnce() -> T) -> T
+    where
+        Self: Sized;
+
+    /// Returns who the remote player is: their address, nickname, and the protocol version spoken
+    /// with them.
+    fn peer(&self) -> PeerInfo;
+
+    /// Returns a handle interrupting [`NetworkedGame::try_move`] from another thread, to quit the
+    /// game or stop waiting for a move without closing the process. See [`MoveCanceller`].
+    fn canceller(&self) -> MoveCanceller;
+
+    /// Returns the current state of the game, which can be used to resume it later.
+    fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            grid: *self.grid(),
+            is_local_turn: self.is_local_turn(),
+            local_mark: self.local_mark(),
+        }
+    }
+}
+
+/// Transport a networked game is played over: a plain [`TcpStream`], a TLS, Noise or WebSocket
+/// stream wrapping one when the `tls`, `noise` or `websocket` feature is enabled, a Unix socket, or
+/// an in-memory [`MemoryStream`]. [`ServerGame`] and [`RemoteGame`] run the same game code over any
+/// of them, and implementing this trait for another connection is enough to play over it, with
+/// [`ServerGame::accept_stream`] and [`RemoteGame::connect_stream`].
+/// Streams are `Send` and `Sync`, so games can be played from worker threads.
+pub trait GameStream: Read + Write + Debug + Send + Sync {
+    /// Sets how long reads wait for data, after which they fail with [`ErrorKind::WouldBlock`] or
+    /// [`ErrorKind::TimedOut`]. `None` waits forever.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+
+    /// Sets how long writes wait for the remote side, like [`GameStream::set_read_timeout`].
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+
+    /// Returns the address of the remote side. Fails if the connection doesn't have one.
+    fn peer_addr(&self) -> io::Result<SocketAddr>;
+
+    /// Closes the connection both ways: the remote side reads the end of the stream.
+    fn shutdown(&self) -> io::Result<()>;
+}
+
+impl GameStream for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_write_timeout(self, timeout)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        TcpStream::peer_addr(self)
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+/// Unix sockets don't have a [`SocketAddr`], so [`GameStream::peer_addr`] always fails.
+#[cfg(unix)]
+impl GameStream for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_write_timeout(self, timeout)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "Unix sockets don't have a network address",
+        ))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        UnixStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+/// TLS connection to a game server, see [`RemoteGame::connect_tls`].
+#[cfg(feature = "tls")]
+pub type TlsClientStream = rustls::StreamOwned<rustls::ClientConnection, TcpStream>;
+
+/// TLS connection to a game client, see [`ServerGame::listen_tls`].
+#[cfg(feature = "tls")]
+pub type TlsServerStream = rustls::StreamOwned<rustls::ServerConnection, TcpStream>;
+
+#[cfg(feature = "tls")]
+impl<C> GameStream for rustls::StreamOwned<C, TcpStream>
+where
+    Self: Read + Write + Debug + Send + Sync,
+{
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.sock.set_read_timeout(timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.sock.set_write_timeout(timeout)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.sock.peer_addr()
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.sock.shutdown(Shutdown::Both)
+    }
+}
+
+trait InternalNetworkBufAccessor {
+    type Stream: GameStream;
+
+    /// Returns the connection, buffered for reading. Packets are written directly to the inner
+    /// stream.
+    fn stream(&mut self) -> &mut BufReader<Self::Stream>;
+
+    /// Span every operation on the game runs in.
+    fn span(&self) -> &ConnectionSpan;
+
+    fn chat_handler(&mut self) -> &mut ChatHandler;
+
+    fn move_time_limit(&mut self) -> &mut Option<Duration>;
+
+    fn clock_mut(&mut self) -> &mut Option<Clock>;
+
+    fn result_mut(&mut self) -> &mut Option<GameResult>;
+
+    /// Last move sent to the remote player, until they acknowledge it.
+    fn unacked_move(&mut self) -> &mut Option<PlayerMove>;
+
+    /// Starts a new game from `snapshot`, for a rematch.
+    fn restart(&mut self, snapshot: &GameSnapshot);
+
+    /// How long the remote player has to play each move before forfeiting the game.
+    fn idle_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Called once a move is committed to the grid.
+    fn on_move(&mut self, _mark: Mark, _row: usize, _col: usize) {}
+
+    /// Called once a move rejected by the remote player is taken back from the grid.
+    fn on_move_rejected(&mut self) {}
+
+    /// Called once the game ended, whether on the board, by resignation or by timeout.
+    fn on_game_over(&mut self, _result: GameResult) {}
+
+    /// Tries to restore the connection after it failed with `error`, which is returned if it can't
+    /// be restored. The game's state may be updated to match the remote side's.
+    fn reconnect(&mut self, error: io::Error) -> io::Result<()> {
+        Err(error)
+    }
+}
+
+/// Client side of a networked game.
+///
+/// If the connection to a [`ServerGame`] or a [`DedicatedServer`](crate::server::DedicatedServer)
+/// drops, the client reconnects to it (see [`ServerGameSettings::reconnect_grace_period`]) before
+/// failing the current move. This includes games started from a
+/// [`LobbyClient`](crate::lobby::LobbyClient) or in a room.
+#[derive(Debug)]
+pub struct RemoteGame<S: GameStream = TcpStream> {
+    stream: BufReader<S>,
+    span: ConnectionSpan,
+    chat_handler: ChatHandler,
+    reconnector: Reconnector<S>,
+    /// Introduction of the host, if it introduced itself
+    host_intro: Option<Introduction>,
+    canceller: MoveCanceller,
+    move_time_limit: Option<Duration>,
+    clock: Option<Clock>,
+    result: Option<GameResult>,
+    unacked_move: Option<PlayerMove>,
+    grid: Grid,
+    is_local_turn: bool,
+    local_mark: Mark,
+}
+
+impl<S: GameStream> NetworkedGame for RemoteGame<S> {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    fn set_next_turn(&mut self) {
+        self.is_local_turn = !self.is_local_turn;
+    }
+
+    fn is_local_turn(&self) -> bool {
+        self.is_local_turn
+    }
+
+    fn local_mark(&self) -> Mark {
+        self.local_mark
+    }
+
+    fn try_move(&mut self, player: &Arc<dyn Player>) -> Result<(), NetworkedGameError> {
+        try_networked_move(self, player)
+    }
+
+    fn rematch(&mut self, accept: bool) -> io::Result<bool> {
+        networked_rematch(self, accept)
+    }
+
+    fn verify_board(&mut self) -> io::Result<()> {
+        verify_board(self)
+    }
+
+    fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    fn set_move_time_limit(&mut self, limit: Option<Duration>) -> io::Result<()> {
+        *self.move_time_limit() = limit;
+        Ok(())
+    }
+
+    fn set_clock(&mut self, time: Option<Duration>) {
+        self.clock = time.map(Clock::new);
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.clock
+    }
+
+    fn send_chat(&mut self, message: &str) -> io::Result<()> {
+        send_chat(self.stream().get_mut(), message)
+    }
+
+    fn set_chat_handler(&mut self, handler: ChatCallback) {
+        self.chat_handler().set(handler);
+    }
+
+    fn with_heartbeat<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        with_heartbeat(self.stream().get_mut(), f)
+    }
+
+    fn canceller(&self) -> MoveCanceller {
+        self.canceller.clone()
+    }
+
+    fn peer(&self) -> PeerInfo {
+        let reconnector = &self.reconnector;
+        PeerInfo::new(
+            self.stream.get_ref().peer_addr().ok(),
+            self.host_intro.as_ref(),
+            reconnector.token.is_some() && reconnector.connect.is_some(),
+        )
+    }
+}
+
+impl<S: GameStream> InternalNetworkBufAccessor for RemoteGame<S> {
+    type Stream = S;
+
+    fn stream(&mut self) -> &mut BufReader<S> {
+        &mut self.stream
+    }
+
+    fn span(&self) -> &ConnectionSpan {
+        &self.span
+    }
+
+    fn chat_handler(&mut self) -> &mut ChatHandler {
+        &mut self.chat_handler
+    }
+
+    fn move_time_limit(&mut self) -> &mut Option<Duration> {
+        &mut self.move_time_limit
+    }
+
+    fn clock_mut(&mut self) -> &mut Option<Clock> {
+        &mut self.clock
+    }
+
+    fn result_mut(&mut self) -> &mut Option<GameResult> {
+        &mut self.result
+    }
+
+    fn unacked_move(&mut self) -> &mut Option<PlayerMove> {
+        &mut self.unacked_move
+    }
+
+    fn restart(&mut self, snapshot: &GameSnapshot) {
+        self.grid = snapshot.grid;
+        self.is_local_turn = snapshot.is_local_turn;
+        self.local_mark = snapshot.local_mark;
+        self.result = None;
+    }
+
+    /// Reconnects to the host with the game's token until it accepts, or the grace period is
+    /// over. The host's state replaces the local one, since the last move may have been lost.
+    fn reconnect(&mut self, error: io::Error) -> io::Result<()> {
+        let (Some(token), Some(connect)) = (self.reconnector.token, &self.reconnector.connect)
+        else {
+            return Err(error);
+        };
+        trace::event!(warn, %error, "connection lost, reconnecting");
+        let deadline = Instant::now() + RECONNECT_GRACE_PERIOD;
+        let (stream, sync) = loop {
+            match connect().and_then(|stream| client_reconnect(stream, token)) {
+                Ok(reconnected) => break reconnected,
+                Err(_) if Instant::now() < deadline => thread::sleep(RECONNECT_POLL_INTERVAL),
+                Err(_) => {
+                    trace::event!(warn, "couldn't reconnect to host");
+                    return Err(error);
+                }
+            }
+        };
+
+        trace::event!(info, "reconnected to host");
+        self.stream = stream;
+        self.grid = sync.grid;
+        self.is_local_turn = sync.turn == self.local_mark;
+        Ok(())
+    }
+}
+
+/// Opens a new connection to the host of a game.
+type Connector<S> = Box<dyn Fn() -> io::Result<S> + Send + Sync>;
+
+/// What a client needs to reconnect to the host of its game.
+struct Reconnector<S> {
+    /// Token sent by the host, if it accepts reconnections
+    token: Option<u64>,
+    connect: Option<Connector<S>>,
+}
+
+impl<S> Debug for Reconnector<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reconnector")
+            .field("token", &self.token.is_some())
+            .field("connect", &self.connect.is_some())
+            .finish()
+    }
+}
+
+impl RemoteGame {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<RemoteGame> {
+        Self::start(tcp_connector(addr)?)
+    }
+
+    /// Same as [`RemoteGame::connect`], introducing the client with `intro`: its nickname, and the
+    /// password of games requiring one (see [`ServerGameSettings::password`]). The host closes the
+    /// connection if the password is wrong.
+    pub fn join<A: ToSocketAddrs>(addr: A, intro: &Introduction) -> io::Result<RemoteGame> {
+        Self::start_with(tcp_connector(addr)?, &intro.to_bytes())
+    }
+
+    /// Returns the address of every game announced on the local network (see
+    /// [`ServerGameSettings::announce`]), waiting `timeout` for hosts to reply.
+    pub fn discover(timeout: Duration) -> io::Result<Vec<SocketAddr>> {
+        discovery::discover((Ipv4Addr::BROADCAST, DISCOVERY_PORT).into(), timeout)
+    }
+
+    /// Returns the address of the game hosted on the local network with the room `code` (see
+    /// [`ServerGameSettings::room_code`]), waiting `timeout` for its host to reply.
+    pub fn find_room(code: &RoomCode, timeout: Duration) -> io::Result<Option<SocketAddr>> {
+        discovery::find_room((Ipv4Addr::BROADCAST, DISCOVERY_PORT).into(), code, timeout)
+    }
+
+    /// Connects to a server hosting a resumed game (see [`ServerGame::resume`]), and continues
+    /// playing from `snapshot`.
+    pub fn resume<A: ToSocketAddrs>(addr: A, snapshot: &GameSnapshot) -> io::Result<RemoteGame> {
+        Self::start_resumed(tcp_connector(addr)?, snapshot)
+    }
+
+    /// Lets a game started over an open connection, such as one from a lobby, reconnect to the
+    /// same address if the server sent a token.
+    pub(crate) fn reconnecting(mut self) -> Self {
+        if let Ok(addr) = self.stream.get_ref().peer_addr() {
+            self.reconnector.connect = Some(Box::new(move || TcpStream::connect(addr)));
+        }
+        self
+    }
+}
+
+/// Returns a function connecting to `addr`, which is resolved once.
+fn tcp_connector<A: ToSocketAddrs>(addr: A) -> io::Result<Connector<TcpStream>> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+    Ok(Box::new(move || TcpStream::connect(addrs.as_slice())))
+}
+
+#[cfg(feature = "json")]
+impl RemoteGame<JsonStream<TcpStream>> {
+    /// Same as [`RemoteGame::connect`], speaking the JSON encoding of the protocol (see
+    /// [`json`](crate::json)). The server must listen with [`ServerGame::listen_json`].
+    pub fn connect_json<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        Self::start(Box::new(move || {
+            TcpStream::connect(addrs.as_slice()).map(JsonStream::client)
+        }))
+    }
+}
+
+#[cfg(feature = "tls")]
+impl RemoteGame<TlsClientStream> {
+    /// Same as [`RemoteGame::connect`], over a TLS connection. The server's certificate must be
+    /// valid for `server_name`, according to `config`.
+    pub fn connect_tls<A: ToSocketAddrs>(
+        addr: A,
+        config: std::sync::Arc<rustls::ClientConfig>,
+        server_name: rustls::pki_types::ServerName<'static>,
+    ) -> io::Result<Self> {
+        Self::start(tls_connector(addr, config, server_name)?)
+    }
+
+    /// Same as [`RemoteGame::resume`], over a TLS connection. See [`RemoteGame::connect_tls`].
+    pub fn resume_tls<A: ToSocketAddrs>(
+        addr: A,
+        config: std::sync::Arc<rustls::ClientConfig>,
+        server_name: rustls::pki_types::ServerName<'static>,
+        snapshot: &GameSnapshot,
+    ) -> io::Result<Self> {
+        Self::start_resumed(tls_connector(addr, config, server_name)?, snapshot)
+    }
+}
+
+#[cfg(unix)]
+impl RemoteGame<UnixStream> {
+    /// Same as [`RemoteGame::connect`], over the Unix socket at `path`. The host accepts the
+    /// connection with [`ServerGame::accept_stream`].
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        Self::start(Box::new(move || UnixStream::connect(&path)))
+    }
+}
+
+#[cfg(feature = "websocket")]
+impl RemoteGame<WebSocketStream<TcpStream>> {
+    /// Same as [`RemoteGame::connect`], over a WebSocket connection (see
+    /// [`websocket`](crate::websocket)). The server must listen with
+    /// [`ServerGame::listen_websocket`].
+    pub fn connect_websocket<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        Self::start(Box::new(move || {
+            let socket = TcpStream::connect(addrs.as_slice())?;
+            let url = format!("ws://{}/", socket.peer_addr()?);
+            WebSocketStream::client(socket, &url)
+        }))
+    }
+}
+
+#[cfg(feature = "noise")]
+impl RemoteGame<NoiseStream<TcpStream>> {
+    /// Same as [`RemoteGame::connect`], over a connection encrypted with Noise (see
+    /// [`noise`](crate::noise)). The server must listen with [`ServerGame::listen_noise`].
+    pub fn connect_noise<A: ToSocketAddrs>(addr: A, config: NoiseConfig) -> io::Result<Self> {
+        Self::start(noise_connector(addr, config)?)
+    }
+
+    /// Same as [`RemoteGame::resume`], over a connection encrypted with Noise. See
+    /// [`RemoteGame::connect_noise`].
+    pub fn resume_noise<A: ToSocketAddrs>(
+        addr: A,
+        config: NoiseConfig,
+        snapshot: &GameSnapshot,
+    ) -> io::Result<Self> {
+        Self::start_resumed(noise_connector(addr, config)?, snapshot)
+    }
+}
+
+impl<S: GameStream> RemoteGame<S> {
+    /// Joins the game hosted over `stream`, an open connection such as one end of
+    /// [`MemoryStream::pair`], introducing the client with `intro` if given (see
+    /// [`RemoteGame::join`]). The host must accept it with [`ServerGame::accept_stream`].
+    ///
+    /// The game can't reconnect, since it doesn't know how the connection was opened.
+    pub fn connect_stream(stream: S, intro: Option<&Introduction>) -> io::Result<Self> {
+        let hello = intro.map_or_else(|| ClientHello.to_bytes().to_vec(), Introduction::to_bytes);
+        let (stream, server_hello, host_intro) = client_handshake(stream, &hello)?;
+        let mut game = Self::from_server_hello(stream, &server_hello);
+        game.host_intro = host_intro;
+        Ok(game)
+    }
+
+    /// Starts a new game over a connection to the server, opened with `connect`.
+    fn start(connect: Connector<S>) -> io::Result<Self> {
+        Self::start_with(connect, &ClientHello.to_bytes())
+    }
+
+    /// Same as [`RemoteGame::start`], opening the connection with the `hello` packet.
+    fn start_with(connect: Connector<S>, hello: &[u8]) -> io::Result<Self> {
+        let (stream, server_hello, host_intro) = client_handshake(connect()?, hello)?;
+        let mut game = Self::from_server_hello(stream, &server_hello);
+        game.reconnector.connect = Some(connect);
+        game.host_intro = host_intro;
+        Ok(game)
+    }
+
+    /// Starts a new game over a connection which already received the SERVER_HELLO packet. The
+    /// game can't reconnect, since it doesn't know how the connection was opened.
+    pub(crate) fn from_server_hello(stream: BufReader<S>, server_hello: &ServerHello) -> Self {
+        Self {
+            span: client_span(&stream),
+            stream,
+            chat_handler: ChatHandler::default(),
+            reconnector: Reconnector {
+                token: server_hello.token,
+                connect: None,
+            },
+            host_intro: None,
+            canceller: MoveCanceller::default(),
+            move_time_limit: None,
+            clock: None,
+            result: None,
+            unacked_move: None,
+            grid: Grid::default(),
+            is_local_turn: server_hello.client_first,
+            local_mark: server_hello.client_mark,
+        }
+    }
+
+    /// Resumes the game saved as `snapshot` over a connection to the server, opened with `connect`.
+    fn start_resumed(connect: Connector<S>, snapshot: &GameSnapshot) -> io::Result<Self> {
+        let pkt = ClientResume {
+            grid: snapshot.grid,
+            client_turn: snapshot.is_local_turn,
+            client_mark: snapshot.local_mark,
+        }
+        .to_bytes();
+        let (stream, server_hello, _) = client_handshake(connect()?, &pkt)?;
+        check_resumed_hello(&server_hello, snapshot)?;
+
+        Ok(Self {
+            span: client_span(&stream),
+            stream,
+            chat_handler: ChatHandler::default(),
+            reconnector: Reconnector {
+                token: server_hello.token,
+                connect: Some(connect),
+            },
+            host_intro: None,
+            canceller: MoveCanceller::default(),
+            move_time_limit: None,
+            clock: None,
+            result: None,
+            unacked_move: None,
+            grid: snapshot.grid,
+            is_local_turn: snapshot.is_local_turn,
+            local_mark: snapshot.local_mark,
+        })
+    }
+}
+
+/// Span of a client's connection to the host.
+fn client_span<S: GameStream>(stream: &BufReader<S>) -> ConnectionSpan {
+    let span = ConnectionSpan::new("client", stream.get_ref().peer_addr().ok());
+    let _span = span.enter();
+    trace::event!(info, "connected to host");
+    span
+}
+
+/// Same as [`tcp_connector`], wrapping the connections in TLS.
+#[cfg(feature = "tls")]
+fn tls_connector<A: ToSocketAddrs>(
+    addr: A,
+    config: std::sync::Arc<rustls::ClientConfig>,
+    server_name: rustls::pki_types::ServerName<'static>,
+) -> io::Result<Connector<TlsClientStream>> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+    Ok(Box::new(move || {
+        tls_client_stream(addrs.as_slice(), config.clone(), server_name.clone())
+    }))
+}
+
+/// Same as [`tcp_connector`], performing a Noise handshake over the connections.
+#[cfg(feature = "noise")]
+fn noise_connector<A: ToSocketAddrs>(
+    addr: A,
+    config: NoiseConfig,
+) -> io::Result<Connector<NoiseStream<TcpStream>>> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+    Ok(Box::new(move || {
+        noise_client_stream(addrs.as_slice(), &config)
+    }))
+}
+
+#[cfg(feature = "noise")]
+pub(crate) fn noise_client_stream<A: ToSocketAddrs>(
+    addr: A,
+    config: &NoiseConfig,
+) -> io::Result<NoiseStream<TcpStream>> {
+    let socket = TcpStream::connect(addr)?;
+    socket.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    NoiseStream::client(socket, config)
+}
+
+#[cfg(feature = "tls")]
+pub(crate) fn tls_client_stream<A: ToSocketAddrs>(
+    addr: A,
+    config: std::sync::Arc<rustls::ClientConfig>,
+    server_name: rustls::pki_types::ServerName<'static>,
+) -> io::Result<TlsClientStream> {
+    let connection =
+        rustls::ClientConnection::new(config, server_name).map_err(io::Error::other)?;
+    Ok(rustls::StreamOwned::new(
+        connection,
+        TcpStream::connect(addr)?,
+    ))
+}
+
+/// Sends the `hello` packet over `stream` and waits for the SERVER_HELLO reply, which follows the
+/// host's introduction if `hello` introduced the client.
+fn client_handshake<S: GameStream>(
+    stream: S,
+    hello: &[u8],
+) -> io::Result<(BufReader<S>, ServerHello, Option<Introduction>)> {
+    let mut stream = BufReader::new(stream);
+    stream.get_mut().write_all(hello)?;
+    stream.get_mut().flush()?;
+
+    let mut read_packet = || {
+        let mut buf = vec![];
+        stream.read_until(protocol::TERMINATOR, &mut buf)?;
+        unframe_packet(buf)
+    };
+    let mut buf = read_packet()?;
+    // Dedicated servers and older hosts don't introduce themselves
+    let host_intro = Introduction::try_from(buf.as_slice()).ok();
+    if let Some(_host) = &host_intro {
+        trace::event!(info, host = %_host.nickname, version = _host.version, "host introduced");
+        buf = read_packet()?;
+    }
+
+    Ok((stream, parse_server_hello(&buf)?, host_intro))
+}
+
+/// Sends the RECONNECT packet with `token` over `stream` and waits for the BOARD_SYNC reply.
+fn client_reconnect<S: GameStream>(stream: S, token: u64) -> io::Result<(BufReader<S>, BoardSync)> {
+    let mut stream = BufReader::new(stream);
+    send_packet(stream.get_mut(), &Reconnect { token }.to_bytes())?;
+
+    let mut buf = vec![];
+    stream.read_until(protocol::TERMINATOR, &mut buf)?;
+    let buf = unframe_packet(buf)?;
+    let sync = BoardSync::try_from(buf.as_slice())
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    Ok((stream, sync))
+}
+
+/// Strips the checksum and terminator of a packet read into `buf`, failing if it was corrupted.
+pub(crate) fn unframe_packet(mut buf: Vec<u8>) -> io::Result<Vec<u8>> {
+    let len = protocol::unframe(&buf)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?
+        .len();
+    buf.truncate(len);
+    Ok(buf)
+}
+
+pub(crate) fn parse_server_hello(pkt: &[u8]) -> io::Result<ServerHello> {
+    ServerHello::try_from(pkt).map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            "Received malformed SERVER_HELLO packet",
+        )
+    })
+}
+
+/// Checks that the server agrees with the client on the state of a resumed game.
+pub(crate) fn check_resumed_hello(
+    server_hello: &ServerHello,
+    snapshot: &GameSnapshot,
+) -> io::Result<()> {
+    if server_hello.client_first != snapshot.is_local_turn
+        || server_hello.client_mark != snapshot.local_mark
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Server doesn't agree on the resumed game state",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the first packet sent by a client matches how the game is started: a new game
+/// joined as `settings` allow, or the game the host resumes from `resumed`.
+pub(crate) fn accepts_hello(
+    pkt: &Packet,
+    resumed: Option<&GameSnapshot>,
+    settings: &ServerGameSettings,
+) -> bool {
+    match (pkt, resumed) {
+        (Packet::ClientHello | Packet::Introduction(_), None) => settings.accepts_client(pkt),
+        (Packet::ClientResume(resume), Some(host)) => {
+            resume.grid == host.grid
+                && resume.client_turn != host.is_local_turn
+                && resume.client_mark == host.local_mark.opposite()
+        }
+        _ => false,
+    }
+}
+
+mod seal {
+    pub trait ServerGameState {}
+}
+
+pub struct NewState {
+    listener: TcpListener,
+    /// Whether the game is resumed from a snapshot, in which case the client must resume it too.
+    resuming: bool,
+    settings: ServerGameSettings,
+    /// Announces the game on the local network until a client connects
+    announcer: Option<Announcer>,
+    canceller: ListenCanceller,
+}
+impl ServerGameState for NewState {}
+
+pub struct ConnectedState<S: GameStream = TcpStream> {
+    stream: BufReader<S>,
+    span: ConnectionSpan,
+    chat_handler: ChatHandler,
+    move_time_limit: Option<Duration>,
+    clock: Option<Clock>,
+    result: Option<GameResult>,
+    /// Last move sent to the client, until it acknowledges it
+    unacked_move: Option<PlayerMove>,
+    /// Listener accepting spectators and reconnections, unless the game is hosted over a single
+    /// stream (see [`ServerGame::accept_stream`])
+    listener: Option<GameListener<S>>,
+    /// Spectators receiving every move played
+    spectators: Vec<S>,
+    /// Token the client reconnects with, if reconnections are allowed
+    token: Option<u64>,
+    /// Introduction of the client, without its password, if it introduced itself
+    client_intro: Option<Introduction>,
+    canceller: MoveCanceller,
+    settings: ServerGameSettings,
+}
+impl<S: GameStream> ServerGameState for ConnectedState<S> {}
+
+/// How long a connection accepted during the game has to send its first packet by default (see
+/// [`ServerGameSettings::handshake_timeout`]), or a spectator to receive a packet, before it is
+/// dropped. This keeps a stalled peer from holding up the game.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long the host waits for a client to reconnect by default, see
+/// [`ServerGameSettings::reconnect_grace_period`]. Clients keep trying to reconnect for as long.
+pub const RECONNECT_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Delay between two attempts to accept or open a connection while reconnecting.
+pub(crate) const RECONNECT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Delay between two attempts to accept a connection while waiting for a client.
+const LISTEN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Stops a host waiting for a client, see [`ServerGame::canceller`]. Cancelling once the client
+/// connected has no effect.
+#[derive(Debug, Clone, Default)]
+pub struct ListenCanceller(Arc<AtomicBool>);
+
+impl ListenCanceller {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Interrupts the moves of a networked game, see [`NetworkedGame::canceller`].
+///
+/// Once cancelled, [`NetworkedGame::try_move`] fails with [`NetworkedGameError::Cancelled`] until
+/// the canceller is reset. Waiting for the remote player's move stops within
+/// [`CANCEL_POLL_INTERVAL`]. The local player's [`Player::get_move`] can't be interrupted by the
+/// game, but players waiting on something else than the terminal can return early once
+/// [`MoveCanceller::is_cancelled`], and the move they chose is dropped either way. Since cancelled
+/// moves aren't played, the game can go on after [`MoveCanceller::reset`].
+#[derive(Debug, Clone, Default)]
+pub struct MoveCanceller(Arc<AtomicBool>);
+
+impl MoveCanceller {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Lets moves be played again after a cancellation.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// How often a game waiting for the remote player's move checks whether it was cancelled, see
+/// [`MoveCanceller`].
+pub const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Listener of a hosted game. It keeps accepting spectators and reconnecting clients once the game
+/// started.
+struct GameListener<S> {
+    listener: TcpListener,
+    /// Turns accepted sockets into the game's stream
+    wrap: Box<dyn Fn(TcpStream) -> io::Result<S> + Send + Sync>,
+    limiter: ConnectionLimiter,
+    handshake_timeout: Duration,
+}
+
+impl<S: GameStream> GameListener<S> {
+    /// Accepts a connection and reads its first packet, waiting at most the handshake timeout for
+    /// it. Returns `None` if the connection was refused by the [`ConnectionLimits`], or if its
+    /// first packet couldn't be read. Fails with [`ErrorKind::WouldBlock`] when no connection is
+    /// pending once the game started, as the listener is then non-blocking.
+    fn accept(&mut self) -> io::Result<Option<Handshake<S>>> {
+        let (socket, peer) = self.listener.accept()?;
+        if !self.limiter.allow(peer.ip()) {
+            trace::event!(warn, %peer, "refused connection over the connection limits");
+            return Ok(None);
+        }
+
+        let handshake = (|| {
+            socket.set_nonblocking(false)?;
+            socket.set_read_timeout(Some(self.handshake_timeout))?;
+            let mut stream = BufReader::new((self.wrap)(socket)?);
+
+            let mut buf = vec![];
+            if stream.read_until(protocol::TERMINATOR, &mut buf)? == 0 {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+            Ok((stream, parse_packet(&buf)?))
+        })();
+        match handshake {
+            Ok((stream, hello)) => Ok(Some(Handshake {
+                peer: peer.ip(),
+                stream,
+                hello,
+            })),
+            Err(_error) => {
+                trace::event!(warn, %peer, error = %_error, "dropped connection without handshake");
+                self.limiter.record_failure(peer.ip());
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Connection accepted by a [`GameListener`], with its first packet.
+struct Handshake<S> {
+    peer: IpAddr,
+    stream: BufReader<S>,
+    hello: Packet,
+}
+
+/// Limits on the connections a host accepts from each IP address, so a hosted port can't be
+/// spammed with connections or invalid handshakes. Connections over the limits are closed right
+/// away.
+///
+/// Defaults: 10 connections per address every 10 seconds, and 5 failed handshakes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    /// Most connections accepted from an address within `window`
+    pub max_connections: u32,
+    pub window: Duration,
+    /// Failed handshakes (an invalid first packet, or none at all) after which an address is
+    /// refused for the rest of the game
+    pub max_failed_handshakes: u32,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            window: Duration::from_secs(10),
+            max_failed_handshakes: 5,
+        }
+    }
+}
+
+/// Applies [`ConnectionLimits`] to the connections accepted by a host.
+#[derive(Debug, Default)]
+struct ConnectionLimiter {
+    limits: Option<ConnectionLimits>,
+    peers: HashMap<IpAddr, PeerRecord>,
+}
+
+#[derive(Debug)]
+struct PeerRecord {
+    window_start: Instant,
+    connections: u32,
+    failed_handshakes: u32,
+}
+
+impl ConnectionLimiter {
+    fn new(limits: Option<ConnectionLimits>) -> Self {
+        Self {
+            limits,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Records a connection from `ip`, and returns whether it is within the limits.
+    fn allow(&mut self, ip: IpAddr) -> bool {
+        let Some(limits) = self.limits else {
+            return true;
+        };
+        // Only addresses which failed handshakes are remembered past their window
+        self.peers.retain(|_, peer| {
+            peer.failed_handshakes > 0 || peer.window_start.elapsed() < limits.window
+        });
+
+        let peer = self.peers.entry(ip).or_insert(PeerRecord {
+            window_start: Instant::now(),
+            connections: 0,
+            failed_handshakes: 0,
+        });
+        if peer.window_start.elapsed() >= limits.window {
+            peer.window_start = Instant::now();
+            peer.connections = 0;
+        }
+        peer.connections += 1;
+        peer.connections <= limits.max_connections
+            && peer.failed_handshakes < limits.max_failed_handshakes
+    }
+
+    /// Records a failed handshake from `ip`.
+    fn record_failure(&mut self, ip: IpAddr) {
+        if let Some(peer) = self.peers.get_mut(&ip) {
+            peer.failed_handshakes += 1;
+        }
+    }
+}
+
+/// Adds a spectator whose first packet `hello` was already received, then sends it the current
+/// state of the game.
+fn add_spectator<S: GameStream>(
+    spectators: &mut Vec<S>,
+    mut stream: S,
+    hello: &Packet,
+    sync: BoardSync,
+) -> io::Result<()> {
+    if !matches!(hello, Packet::SpectatorHello) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Unexpected first packet: {:?}", hello),
+        ));
+    }
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    send_packet(&mut stream, &sync.to_bytes())?;
+    spectators.push(stream);
+    Ok(())
+}
+
+fn send_packet(stream: &mut impl Write, pkt: &[u8]) -> io::Result<()> {
+    trace::event!(trace, packet = ?Packet::parse(pkt), "sending packet");
+    stream.write_all(pkt)?;
+    stream.flush()
+}
+
+#[derive(Debug)]
+pub struct ServerGame<S: ServerGameState> {
+    state: S,
+    grid: Grid,
+    is_local_turn: bool,
+    local_mark: Mark,
+}
+
+/// How a hosted game is played, built from the defaults with the `with_*` methods:
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use tictactoe::game::{RematchPolicy, ServerGame, ServerGameSettings};
+/// let settings = ServerGameSettings::default()
+///     .with_nickname("Alice")
+///     .with_password("secret")
+///     .with_move_time_limit(Some(Duration::from_secs(30)))
+///     .with_rematch_policy(RematchPolicy::Never);
+/// let game = ServerGame::bind("0.0.0.0:8905", &settings)?.listen()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// Defaults: host playing first with the `X` mark, waiting [`RECONNECT_GRACE_PERIOD`] for a client
+/// to reconnect, not announced on the local network nor given a room code, with the default
+/// [`ConnectionLimits`], a [`HANDSHAKE_TIMEOUT`], no idle timeout nor move time limit, no nickname
+/// nor password, accepting every protocol version and swapping marks on rematches.
+#[derive(Clone, Debug)]
+pub struct ServerGameSettings {
+    pub host_plays_first: bool,
+    pub host_mark: Mark,
+    /// How long the host waits for the client to reconnect after the connection dropped, or `None`
+    /// to end the game with an error right away
+    pub reconnect_grace_period: Option<Duration>,
+    /// Whether clients on the local network can find the game with [`RemoteGame::discover`] until
+    /// one connects. Only one game per machine can be announced, since it uses [`DISCOVERY_PORT`].
+    pub announce: bool,
+    /// Code clients on the local network can find the game with until one connects, see
+    /// [`RemoteGame::find_room`]. The game is announced to them even if [`Self::announce`] isn't
+    /// set, but isn't listed by [`RemoteGame::discover`] then.
+    pub room_code: Option<RoomCode>,
+    /// Limits on the connections accepted from each address, or `None` to accept every connection
+    pub connection_limits: Option<ConnectionLimits>,
+    /// How long the client has to play each move, or `None` to wait for as long as it sends
+    /// heartbeats. An idle client forfeits the game: it is sent an END_OF_GAME packet with a
+    /// [`WinReason::Timeout`] result, and the connection is closed.
+    pub idle_timeout: Option<Duration>,
+    /// How long a new connection has to send its first packet before it is dropped
+    pub handshake_timeout: Duration,
+    /// Move time limit the game starts with, see [`NetworkedGame::set_move_time_limit`]
+    pub move_time_limit: Option<Duration>,
+    /// Nickname introduced to clients joining with an [`Introduction`]
+    pub nickname: Option<String>,
+    /// Password clients must introduce themselves with, see [`RemoteGame::join`]. Clients opening
+    /// with a plain CLIENT_HELLO packet are refused when set.
+    pub password: Option<String>,
+    /// Versions of the protocol accepted from clients, see [`PROTOCOL_VERSION`]. Clients opening
+    /// with a plain CLIENT_HELLO packet speak version 1.
+    pub protocol_versions: RangeInclusive<u8>,
+    pub rematch_policy: RematchPolicy,
+}
+
+impl ServerGameSettings {
+    pub fn with_host_plays_first(mut self, host_plays_first: bool) -> Self {
+        self.host_plays_first = host_plays_first;
+        self
+    }
+
+    pub fn with_host_mark(mut self, host_mark: Mark) -> Self {
+        self.host_mark = host_mark;
+        self
+    }
+
+    pub fn with_reconnect_grace_period(mut self, grace_period: Option<Duration>) -> Self {
+        self.reconnect_grace_period = grace_period;
+        self
+    }
+
+    pub fn with_announce(mut self, announce: bool) -> Self {
+        self.announce = announce;
+        self
+    }
+
+    pub fn with_room_code(mut self, code: Option<RoomCode>) -> Self {
+        self.room_code = code;
+        self
+    }
+
+    pub fn with_connection_limits(mut self, limits: Option<ConnectionLimits>) -> Self {
+        self.connection_limits = limits;
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    pub fn with_move_time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.move_time_limit = limit;
+        self
+    }
+
+    pub fn with_nickname(mut self, nickname: impl Into<String>) -> Self {
+        self.nickname = Some(nickname.into());
+        self
+    }
+
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn with_protocol_versions(mut self, versions: RangeInclusive<u8>) -> Self {
+        self.protocol_versions = versions;
+        self
+    }
+
+    pub fn with_rematch_policy(mut self, policy: RematchPolicy) -> Self {
+        self.rematch_policy = policy;
+        self
+    }
+
+    /// Returns whether a client opening with `hello`, a CLIENT_HELLO packet or an introduction,
+    /// may join the game.
+    fn accepts_client(&self, hello: &Packet) -> bool {
+        let (version, password) = match hello {
+            Packet::ClientHello => (1, None),
+            Packet::Introduction(intro) => (intro.version, Some(intro.password.as_str())),
+            _ => return false,
+        };
+        self.protocol_versions.contains(&version)
+            && self.password.as_deref().is_none_or(|p| Some(p) == password)
+    }
+
+    /// Returns the host's reply to a client's introduction `intro`.
+    pub(crate) fn introduction(&self, intro: &Introduction) -> Introduction {
+        Introduction {
+            version: intro.version.min(PROTOCOL_VERSION),
+            nickname: self.nickname.clone().unwrap_or_default(),
+            password: String::new(),
+        }
+    }
+}
+
+impl Default for ServerGameSettings {
+    fn default() -> Self {
+        Self {
+            host_plays_first: true,
+            host_mark: Mark::X,
+            reconnect_grace_period: Some(RECONNECT_GRACE_PERIOD),
+            announce: false,
+            room_code: None,
+            connection_limits: Some(ConnectionLimits::default()),
+            idle_timeout: None,
+            handshake_timeout: HANDSHAKE_TIMEOUT,
+            move_time_limit: None,
+            nickname: None,
+            password: None,
+            protocol_versions: 1..=PROTOCOL_VERSION,
+            rematch_policy: RematchPolicy::default(),
+        }
+    }
+}
+
+/// How the host of a [`ServerGame`] answers rematches, see [`NetworkedGame::rematch`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RematchPolicy {
+    /// Rematches swap the marks and the first player
+    #[default]
+    Swap,
+    /// Rematches start like the first game, with the host's mark and first player from the
+    /// settings. They are offered as new games, see [`ServerGame::new_game`].
+    Keep,
+    /// Rematches keep the marks, and the first player alternates between games. They are offered as
+    /// new games, see [`ServerGame::new_game`].
+    Alternate,
+    /// The host declines every rematch
+    Never,
+}
+
+impl ServerGame<NewState> {
+    pub fn bind<A: ToSocketAddrs>(addr: A, settings: &ServerGameSettings) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let announcer = if settings.announce || settings.room_code.is_some() {
+            let port = listener.local_addr()?.port();
+            let code = settings.room_code.clone();
+            Some(Announcer::on_local_network(port, settings.announce, code)?)
+        } else {
+            None
+        };
+        let state = NewState {
+            listener,
+            resuming: false,
+            settings: settings.clone(),
+            announcer,
+            canceller: ListenCanceller::default(),
+        };
+
+        Ok(Self {
+            state,
+            grid: Grid::default(),
+            is_local_turn: settings.host_plays_first,
+            local_mark: settings.host_mark,
+        })
+    }
+
+    /// Binds to `addr` to host a game resumed from `snapshot`. Only a client resuming the same game
+    /// (see [`RemoteGame::resume`]) will be accepted.
+    pub fn resume<A: ToSocketAddrs>(addr: A, snapshot: &GameSnapshot) -> io::Result<Self> {
+        let state = NewState {
+            listener: TcpListener::bind(addr)?,
+            resuming: true,
+            settings: ServerGameSettings::default(),
+            announcer: None,
+            canceller: ListenCanceller::default(),
+        };
+
+        Ok(Self {
+            state,
+            grid: snapshot.grid,
+            is_local_turn: snapshot.is_local_turn,
+            local_mark: snapshot.local_mark,
+        })
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.state.listener.local_addr()
+    }
+
+    /// Returns a handle stopping [`ServerGame::listen`] from another thread, to stop hosting the
+    /// game before a client connects.
+    pub fn canceller(&self) -> ListenCanceller {
+        self.state.canceller.clone()
+    }
+
+    /// Waits for a client to play the game against. Fails with [`ErrorKind::Interrupted`] if
+    /// cancelled before a client connects, see [`ServerGame::canceller`].
+    ///
+    /// The server keeps accepting spectators (see [`Spectator`](crate::spectator::Spectator))
+    /// until the game is dropped, and sends them every move played. Spectators connecting after
+    /// the game started receive the moves they missed first.
+    ///
+    /// If the connection to the client drops, the server waits for the client to reconnect (see
+    /// [`ServerGameSettings::reconnect_grace_period`]) before failing the current move.
+    ///
+    /// Connections with an invalid handshake are dropped without ending the game, and addresses
+    /// opening too many connections or failing too many handshakes are refused (see
+    /// [`ServerGameSettings::connection_limits`]). Clients giving the wrong password or speaking a
+    /// protocol version the host doesn't accept fail the handshake.
+    pub fn listen(self) -> io::Result<ServerGame<ConnectedState>> {
+        self.listen_with(Ok)
+    }
+
+    /// Same as [`ServerGame::listen`], wrapping every accepted connection in TLS using `config`.
+    #[cfg(feature = "tls")]
+    pub fn listen_tls(
+        self,
+        config: std::sync::Arc<rustls::ServerConfig>,
+    ) -> io::Result<ServerGame<ConnectedState<TlsServerStream>>> {
+        self.listen_with(move |socket| {
+            let connection =
+                rustls::ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+            Ok(rustls::StreamOwned::new(connection, socket))
+        })
+    }
+
+    /// Same as [`ServerGame::listen`], performing the WebSocket opening handshake with every
+    /// accepted connection (see [`websocket`](crate::websocket)).
+    #[cfg(feature = "websocket")]
+    pub fn listen_websocket(
+        self,
+    ) -> io::Result<ServerGame<ConnectedState<WebSocketStream<TcpStream>>>> {
+        self.listen_with(WebSocketStream::host)
+    }
+
+    /// Same as [`ServerGame::listen`], performing a Noise handshake with every accepted connection
+    /// (see [`noise`](crate::noise)). Spectators and reconnecting clients must also authenticate
+    /// with a key accepted by `config`.
+    #[cfg(feature = "noise")]
+    pub fn listen_noise(
+        self,
+        config: NoiseConfig,
+    ) -> io::Result<ServerGame<ConnectedState<NoiseStream<TcpStream>>>> {
+        self.listen_with(move |socket| NoiseStream::host(socket, &config))
+    }
+
+    /// Same as [`ServerGame::listen`], accepting clients speaking the JSON encoding of the protocol
+    /// (see [`json`](crate::json)) as well as binary clients.
+    #[cfg(feature = "json")]
+    pub fn listen_json(self) -> io::Result<ServerGame<ConnectedState<JsonStream<TcpStream>>>> {
+        self.listen_with(|socket| Ok(JsonStream::host(socket)))
+    }
+
+    /// Waits for a client, using `wrap` to turn accepted sockets into the game's stream.
+    fn listen_with<S: GameStream>(
+        self,
+        wrap: impl Fn(TcpStream) -> io::Result<S> + Send + Sync + 'static,
+    ) -> io::Result<ServerGame<ConnectedState<S>>> {
+        let host = GameSnapshot {
+            grid: self.grid,
+            is_local_turn: self.is_local_turn,
+            local_mark: self.local_mark,
+        };
+        let mut listener = GameListener {
+            listener: self.state.listener,
+            wrap: Box::new(wrap),
+            limiter: ConnectionLimiter::new(self.state.settings.connection_limits),
+            handshake_timeout: self.state.settings.handshake_timeout,
+        };
+        let token = self
+            .state
+            .settings
+            .reconnect_grace_period
+            .map(|_| rand::thread_rng().gen());
+
+        // Connections are polled, to notice when listening is cancelled
+        listener.listener.set_nonblocking(true)?;
+        let mut spectators = vec![];
+        let (stream, hello) = loop {
+            if self.state.canceller.is_cancelled() {
+                return Err(io::Error::new(
+                    ErrorKind::Interrupted,
+                    "Stopped waiting for a client",
+                ));
+            }
+            let Some(Handshake {
+                peer,
+                mut stream,
+                hello,
+            }) = (match listener.accept() {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(LISTEN_POLL_INTERVAL);
+                    continue;
+                }
+                accepted => accepted?,
+            })
+            else {
+                continue;
+            };
+
+            // Expect CLIENT_HELLO or INTRODUCTION, or CLIENT_RESUME when resuming
+            let resumed = self.state.resuming.then_some(&host);
+            if !accepts_hello(&hello, resumed, &self.state.settings) {
+                // Spectators can also join before the game starts
+                let added = add_spectator(
+                    &mut spectators,
+                    stream.into_inner(),
+                    &hello,
+                    board_sync(&host),
+                );
+                match added {
+                    Ok(()) => trace::event!(info, %peer, "spectator joined"),
+                    Err(_) => listener.limiter.record_failure(peer),
+                }
+                continue;
+            }
+
+            greet_client(stream.get_mut(), &hello, &host, &self.state.settings, token)?;
+            break (stream, hello);
+        };
+        // Other clients can't join anymore
+        drop(self.state.announcer);
+        Ok(ServerGame::connected(
+            stream,
+            hello,
+            &host,
+            self.state.settings,
+            self.state.resuming,
+            Some(listener),
+            spectators,
+            token,
+        ))
+    }
+}
+
+/// Introduces the host to clients who introduced themselves, then sends SERVER_HELLO.
+fn greet_client<S: GameStream>(
+    stream: &mut S,
+    hello: &Packet,
+    host: &GameSnapshot,
+    settings: &ServerGameSettings,
+    token: Option<u64>,
+) -> io::Result<()> {
+    if let Packet::Introduction(intro) = hello {
+        send_packet(stream, &settings.introduction(intro).to_bytes())?;
+    }
+    let pkt = ServerHello {
+        client_first: !host.is_local_turn,
+        client_mark: host.local_mark.opposite(),
+        token,
+    }
+    .to_bytes();
+    send_packet(stream, &pkt)
+}
+
+impl<S: GameStream> ServerGame<ConnectedState<S>> {
+    /// Hosts a new game over `stream`, an open connection such as one end of
+    /// [`MemoryStream::pair`], once the client on the other end sent its CLIENT_HELLO or
+    /// INTRODUCTION packet. Fails with [`ErrorKind::InvalidData`] if `settings` don't accept the
+    /// client.
+    ///
+    /// The game has no listener: spectators can't join, and the client can't reconnect.
+    pub fn accept_stream(stream: S, settings: &ServerGameSettings) -> io::Result<Self> {
+        let host = GameSnapshot {
+            grid: Grid::default(),
+            is_local_turn: settings.host_plays_first,
+            local_mark: settings.host_mark,
+        };
+        stream.set_read_timeout(Some(settings.handshake_timeout))?;
+        let mut stream = BufReader::new(stream);
+        let mut buf = vec![];
+        if stream.read_until(protocol::TERMINATOR, &mut buf)? == 0 {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+        let hello = parse_packet(&buf)?;
+        if !accepts_hello(&hello, None, settings) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Refused the client"));
+        }
+
+        greet_client(stream.get_mut(), &hello, &host, settings, None)?;
+        Ok(Self::connected(
+            stream,
+            hello,
+            &host,
+            settings.clone(),
+            false,
+            None,
+            vec![],
+            None,
+        ))
+    }
+
+    /// Starts the game with the client who sent `hello` over `stream`, once it was greeted.
+    #[allow(clippy::too_many_arguments)]
+    fn connected(
+        stream: BufReader<S>,
+        hello: Packet,
+        host: &GameSnapshot,
+        settings: ServerGameSettings,
+        _resumed: bool,
+        listener: Option<GameListener<S>>,
+        spectators: Vec<S>,
+        token: Option<u64>,
+    ) -> Self {
+        let span = ConnectionSpan::new("host", stream.get_ref().peer_addr().ok());
+        let client_intro = match hello {
+            Packet::Introduction(intro) => Some(Introduction {
+                password: String::new(),
+                ..intro
+            }),
+            _ => None,
+        };
+        {
+            let _span = span.enter();
+            let _nickname = client_intro.as_ref().map(|intro| intro.nickname.as_str());
+            trace::event!(info, resumed = _resumed, nickname = ?_nickname, "client joined");
+        }
+
+        ServerGame {
+            state: ConnectedState {
+                stream,
+                span,
+                chat_handler: ChatHandler::default(),
+                move_time_limit: settings.move_time_limit,
+                clock: None,
+                result: None,
+                unacked_move: None,
+                listener,
+                spectators,
+                token,
+                client_intro,
+                canceller: MoveCanceller::default(),
+                settings,
+            },
+            grid: host.grid,
+            is_local_turn: host.is_local_turn,
+            local_mark: host.local_mark,
+        }
+    }
+}
+
+impl<S: GameStream> ServerGame<ConnectedState<S>> {
+    /// Once the game is over, offers the client a new game over the same connection instead of a
+    /// rematch, where the host plays `host_mark` and moves first if `host_plays_first`, and waits
+    /// for the client's answer. Fails with [`ErrorKind::InvalidInput`] if the game isn't over.
+    ///
+    /// Returns `true` if the client accepted, and the new game then starts. Clients accept through
+    /// [`NetworkedGame::rematch`], so the client's answer should also be chosen within
+    /// [`NetworkedGame::with_heartbeat`].
+    pub fn new_game(&mut self, host_plays_first: bool, host_mark: Mark) -> io::Result<bool> {
+        let pkt = NewGame {
+            client_first: !host_plays_first,
+            client_mark: host_mark.opposite(),
+        };
+        let next = GameSnapshot {
+            grid: Grid::default(),
+            is_local_turn: host_plays_first,
+            local_mark: host_mark,
+        };
+        next_game(self, &pkt.to_bytes(), Some(next))
+    }
+
+    /// Sends `pkt` to every spectator, dropping the ones whose connection failed.
+    fn broadcast(&mut self, pkt: &[u8]) {
+        self.state
+            .spectators
+            .retain_mut(|stream| send_packet(stream, pkt).is_ok());
+    }
+
+    /// Accepts the connections pending on the game's listener: spectators, or the client
+    /// reconnecting. Returns true if the client reconnected.
+    fn accept_pending(&mut self) -> bool {
+        let mut reconnected = false;
+        loop {
+            let Some(listener) = &mut self.state.listener else {
+                return false;
+            };
+            match listener.accept() {
+                Ok(Some(Handshake {
+                    peer,
+                    stream,
+                    hello,
+                })) => {
+                    // Errors only affect this connection, which is dropped
+                    match self.handle_connection(stream, &hello) {
+                        Ok(true) => {
+                            trace::event!(info, %peer, "client reconnected");
+                            reconnected = true;
+                        }
+                        Ok(false) => trace::event!(info, %peer, "spectator joined"),
+                        Err(_) => {
+                            if let Some(listener) = &mut self.state.listener {
+                                listener.limiter.record_failure(peer);
+                            }
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return reconnected,
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Handles a connection accepted during the game, whose first packet is `hello`. Returns true
+    /// if it is the client reconnecting, in which case it replaces the game's connection.
+    fn handle_connection(&mut self, mut stream: BufReader<S>, hello: &Packet) -> io::Result<bool> {
+        let is_client =
+            matches!(hello, Packet::Reconnect(pkt) if Some(pkt.token) == self.state.token);
+        if !is_client {
+            let sync = board_sync(&self.snapshot());
+            add_spectator(&mut self.state.spectators, stream.into_inner(), hello, sync)?;
+            return Ok(false);
+        }
+
+        send_packet(stream.get_mut(), &board_sync(&self.snapshot()).to_bytes())?;
+        self.state.stream = stream;
+        Ok(true)
+    }
+}
+
+impl<S: GameStream> NetworkedGame for ServerGame<ConnectedState<S>> {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    fn set_next_turn(&mut self) {
+        self.is_local_turn = !self.is_local_turn;
+    }
+
+    fn is_local_turn(&self) -> bool {
+        self.is_local_turn
+    }
+
+    fn local_mark(&self) -> Mark {
+        self.local_mark
+    }
+
+    fn try_move(&mut self, player: &Arc<dyn Player>) -> Result<(), NetworkedGameError> {
+        try_networked_move(self, player)
+    }
+
+    fn rematch(&mut self, accept: bool) -> io::Result<bool> {
+        let settings = &self.state.settings;
+        let (host_plays_first, host_mark) = (settings.host_plays_first, settings.host_mark);
+        match settings.rematch_policy {
+            RematchPolicy::Swap => networked_rematch(self, accept),
+            RematchPolicy::Keep if accept => self.new_game(host_plays_first, host_mark),
+            RematchPolicy::Alternate if accept => {
+                let next = rematch_snapshot(&self.snapshot());
+                self.new_game(next.is_local_turn, self.local_mark)
+            }
+            RematchPolicy::Keep | RematchPolicy::Alternate | RematchPolicy::Never => {
+                networked_rematch(self, false)
+            }
+        }
+    }
+
+    fn verify_board(&mut self) -> io::Result<()> {
+        verify_board(self)
+    }
+
+    fn result(&self) -> Option<GameResult> {
+        self.state.result
+    }
+
+    fn set_move_time_limit(&mut self, limit: Option<Duration>) -> io::Result<()> {
+        *self.move_time_limit() = limit;
+        Ok(())
+    }
+
+    fn set_clock(&mut self, time: Option<Duration>) {
+        self.state.clock = time.map(Clock::new);
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.state.clock
+    }
+
+    fn send_chat(&mut self, message: &str) -> io::Result<()> {
+        send_chat(self.stream().get_mut(), message)
+    }
+
+    fn set_chat_handler(&mut self, handler: ChatCallback) {
+        self.chat_handler().set(handler);
+    }
+
+    fn with_heartbeat<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        with_heartbeat(self.stream().get_mut(), f)
+    }
+
+    fn canceller(&self) -> MoveCanceller {
+        self.state.canceller.clone()
+    }
+
+    fn peer(&self) -> PeerInfo {
+        PeerInfo::new(
+            self.state.stream.get_ref().peer_addr().ok(),
+            self.state.client_intro.as_ref(),
+            self.state.token.is_some(),
+        )
+    }
+}
+
+impl<S: GameStream> InternalNetworkBufAccessor for ServerGame<ConnectedState<S>> {
+    type Stream = S;
+
+    fn stream(&mut self) -> &mut BufReader<S> {
+        &mut self.state.stream
+    }
+
+    fn span(&self) -> &ConnectionSpan {
+        &self.state.span
+    }
+
+    fn chat_handler(&mut self) -> &mut ChatHandler {
+        &mut self.state.chat_handler
+    }
+
+    fn move_time_limit(&mut self) -> &mut Option<Duration> {
+        &mut self.state.move_time_limit
+    }
+
+    fn clock_mut(&mut self) -> &mut Option<Clock> {
+        &mut self.state.clock
+    }
+
+    fn result_mut(&mut self) -> &mut Option<GameResult> {
+        &mut self.state.result
+    }
+
+    fn unacked_move(&mut self) -> &mut Option<PlayerMove> {
+        &mut self.state.unacked_move
+    }
+
+    /// Spectators are sent the new game's state, replacing their grid.
+    fn restart(&mut self, snapshot: &GameSnapshot) {
+        self.grid = snapshot.grid;
+        self.is_local_turn = snapshot.is_local_turn;
+        self.local_mark = snapshot.local_mark;
+        self.state.result = None;
+
+        self.broadcast(&board_sync(snapshot).to_bytes());
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        self.state.settings.idle_timeout
+    }
+
+    fn on_move(&mut self, mark: Mark, row: usize, col: usize) {
+        self.broadcast(&MoveBroadcast { mark, row, col }.to_bytes());
+        self.accept_pending();
+    }
+
+    /// Spectators already received the move, so they are sent the grid without it.
+    fn on_move_rejected(&mut self) {
+        self.broadcast(&board_sync(&self.snapshot()).to_bytes());
+    }
+
+    /// Spectators are sent the result, since resignations and timeouts don't show on the grid.
+    fn on_game_over(&mut self, result: GameResult) {
+        self.broadcast(&EndOfGame(result).to_bytes());
+    }
+
+    /// Waits for the client to reconnect with the game's token, during the grace period.
+    fn reconnect(&mut self, error: io::Error) -> io::Result<()> {
+        // Games hosted over a single stream have no listener for the client to reconnect to
+        let (Some(grace_period), Some(_)) = (
+            self.state.settings.reconnect_grace_period,
+            &self.state.listener,
+        ) else {
+            return Err(error);
+        };
+
+        trace::event!(warn, %error, "connection lost, waiting for the client to reconnect");
+        let deadline = Instant::now() + grace_period;
+        while Instant::now() < deadline {
+            if self.accept_pending() {
+                return Ok(());
+            }
+            thread::sleep(RECONNECT_POLL_INTERVAL);
+        }
+        trace::event!(warn, "client didn't reconnect");
+        Err(error)
+    }
+}
+
+fn try_networked_move<G: NetworkedGame + InternalNetworkBufAccessor>(
+    game: &mut G,
+    local_player: &Arc<dyn Player>,
+) -> Result<(), NetworkedGameError> {
+    let _span = game.span().enter();
+    if game.result().is_some() {
+        return Err(NetworkedGameError::GameOver);
+    }
+    let canceller = game.canceller();
+    if canceller.is_cancelled() {
+        return Err(NetworkedGameError::Cancelled);
+    }
+    let limit = *game.move_time_limit();
+    let local_turn = game.is_local_turn();
+
+    // Get move
+    let player_move = if local_turn {
+        let (grid, mark) = (*game.grid(), game.local_mark());
+        let start = Instant::now();
+        let player_move = with_heartbeat(game.stream().get_mut(), || {
+            local_networked_move(local_player, &grid, mark, limit)
+        });
+        if canceller.is_cancelled() {
+            trace::event!(info, "local move cancelled");
+            return Err(NetworkedGameError::Cancelled);
+        }
+        let player_move = player_move?;
+        if let Some(clock) = game.clock_mut() {
+            let Some(left) = clock.remaining(mark).checked_sub(start.elapsed()) else {
+                forfeit_on_time(game, mark);
+                return Ok(());
+            };
+            // Clocks are kept to the millisecond, as sent to the remote side
+            *clock.remaining_mut(mark) = Duration::from_millis(left.as_millis() as u64);
+            // The remote side measures the move's time on its own if this packet is lost
+            let pkt = clock.to_update().to_bytes();
+            let _ = send_packet(game.stream().get_mut(), &pkt);
+        }
+        player_move
+    } else {
+        let start = Instant::now();
+        let mut ack_deadline = start + ACK_TIMEOUT;
+        let mut retries = 0;
+        let mut clock_update = None;
+        let remote_mark = game.local_mark().opposite();
+        let mut last_packet = start;
+        // Packet being read, kept across read timeouts since part of it may already be read
+        let mut incoming = vec![];
+        let player_move = loop {
+            // Cancelling between packets leaves the connection ready for the next move
+            if canceller.is_cancelled() && incoming.is_empty() {
+                trace::event!(info, "wait for remote move cancelled");
+                return Err(NetworkedGameError::Cancelled);
+            }
+
+            // Wake up when the remote player runs out of time, stops sending heartbeats, or doesn't
+            // acknowledge the local player's last move in time
+            let remaining = limit.map(|limit| limit.saturating_sub(start.elapsed()));
+            let heartbeat = HEARTBEAT_TIMEOUT.saturating_sub(last_packet.elapsed());
+            let mut timeout = remaining.map_or(heartbeat, |r| r.min(heartbeat));
+            if remaining.is_some_and(|r| r.is_zero()) {
+                return Err(NetworkedGameError::Timeout(game.local_mark().opposite()));
+            }
+            let idle_remaining = game
+                .idle_timeout()
+                .map(|idle| idle.saturating_sub(start.elapsed()));
+            if let Some(idle) = idle_remaining {
+                if idle.is_zero() {
+                    forfeit_idle_peer(game);
+                    return Ok(());
+                }
+                timeout = timeout.min(idle);
+            }
+            let clock_remaining = game.clock().map(|clock| {
+                (clock.remaining(remote_mark) + CLOCK_GRACE_PERIOD).saturating_sub(start.elapsed())
+            });
+            if let Some(clock) = clock_remaining {
+                if clock.is_zero() {
+                    forfeit_on_time(game, remote_mark);
+                    return Ok(());
+                }
+                timeout = timeout.min(clock);
+            }
+            let ack_remaining = game
+                .unacked_move()
+                .map(|_| ack_deadline.saturating_duration_since(Instant::now()));
+            let waiting_for_ack = ack_remaining.is_some_and(|ack| ack <= timeout);
+            if let Some(ack) = ack_remaining.filter(|_| waiting_for_ack) {
+                timeout = ack;
+            }
+            let polling = timeout > CANCEL_POLL_INTERVAL;
+            if polling {
+                timeout = CANCEL_POLL_INTERVAL;
+            }
+            // Zero isn't a valid read timeout
+            let timeout = timeout.max(Duration::from_millis(1));
+            game.stream().get_ref().set_read_timeout(Some(timeout))?;
+
+            let read = match game
+                .stream()
+                .read_until(protocol::TERMINATOR, &mut incoming)
+            {
+                Ok(0) => Err(io::Error::from(ErrorKind::UnexpectedEof)),
+                Ok(_) => Ok(()),
+                // Timeouts are reported as either kind depending on the platform
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    if polling {
+                        continue;
+                    } else if waiting_for_ack {
+                        match *game.unacked_move() {
+                            Some(pkt) if retries < MAX_MOVE_RETRIES => {
+                                retries += 1;
+                                ack_deadline = Instant::now() + ACK_TIMEOUT;
+                                match send_packet(game.stream().get_mut(), &pkt.to_bytes()) {
+                                    Ok(()) => continue,
+                                    Err(e) => Err(e),
+                                }
+                            }
+                            _ => Err(io::Error::new(
+                                ErrorKind::TimedOut,
+                                "Remote player didn't acknowledge the last move",
+                            )),
+                        }
+                    } else if remaining.is_some_and(|r| r <= timeout) {
+                        return Err(NetworkedGameError::Timeout(game.local_mark().opposite()));
+                    } else if idle_remaining.is_some_and(|r| r <= timeout)
+                        || clock_remaining.is_some_and(|r| r <= timeout)
+                    {
+                        // The remote player forfeits once the loop starts again
+                        continue;
+                    } else {
+                        Err(io::Error::new(
+                            ErrorKind::TimedOut,
+                            "Remote player stopped sending heartbeats",
+                        ))
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(error) = read {
+                // The move is read again once reconnected, unless the remote side's state shows
+                // it was already played. The host's state also shows if the last local move was
+                // received.
+                incoming.clear();
+                game.reconnect(error)?;
+                last_packet = Instant::now();
+                *game.unacked_move() = None;
+                if game.is_local_turn() != local_turn {
+                    return Ok(());
+                }
+                continue;
+            }
+
+            last_packet = Instant::now();
+            let buf = std::mem::take(&mut incoming);
+            // The remote player can chat while choosing their move
+            match parse_packet(&buf)? {
+                Packet::Heartbeat => {}
+                Packet::BoardSync(sync) => check_board_sync(sync, board_sync(&game.snapshot()))?,
+                Packet::ChatMessage(ChatMessage(message)) => game.chat_handler().receive(&message),
+                Packet::ServerShutdown(pkt) => game.chat_handler().server_shutdown(pkt),
+                Packet::MoveAck(ack) => {
+                    if game.unacked_move().is_some_and(|pkt| pkt.seq == ack.seq) {
+                        *game.unacked_move() = None;
+                    }
+                }
+                Packet::ClockUpdate(update) => clock_update = Some(update),
+                Packet::EndOfGame(EndOfGame(
+                    result @ GameResult::Win {
+                        reason: WinReason::Timeout,
+                        ..
+                    },
+                )) => {
+                    // The remote side ended the game since a player ran out of time, or the host
+                    // since the local player was idle for too long
+                    *game.unacked_move() = None;
+                    end_game(game, result);
+                    return Ok(());
+                }
+                Packet::InvalidMove(rejection) => {
+                    // Rejections of moves that were since acknowledged are stale
+                    if let Some(pkt) = game.unacked_move().filter(|pkt| rejection.rejects(pkt)) {
+                        *game.unacked_move() = None;
+                        game.grid_mut().clear_cell(pkt.row, pkt.col);
+                        game.set_next_turn();
+                        trace::event!(info, ?rejection, "move rejected");
+                        game.on_move_rejected();
+                        return Err(NetworkedGameError::Rejected(rejection.reason));
+                    }
+                }
+                Packet::PlayerMove(pkt) => {
+                    if usize::from(pkt.seq) <= game.grid().cell_count() {
+                        // The remote player sent the move again, since the acknowledgment was late
+                        let _ = send_packet(
+                            game.stream().get_mut(),
+                            &MoveAck { seq: pkt.seq }.to_bytes(),
+                        );
+                        continue;
+                    }
+                    // Ask the remote player for another move rather than letting the grids diverge
+                    let (mut grid, mark) = (*game.grid(), game.local_mark().opposite());
+                    if let Err(reason) = grid.try_set_cell(pkt.row, pkt.col, mark) {
+                        let rejection = InvalidMove {
+                            row: pkt.row,
+                            col: pkt.col,
+                            seq: pkt.seq,
+                            reason,
+                        };
+                        send_packet(game.stream().get_mut(), &rejection.to_bytes())?;
+                        continue;
+                    }
+                    // The remote player's move shows they received the local player's last one
+                    *game.unacked_move() = None;
+                    break Move::Place(pkt.row, pkt.col);
+                }
+                Packet::Resign => {
+                    *game.unacked_move() = None;
+                    break Move::Resign;
+                }
+                pkt => return Err(unexpected_remote_packet(&pkt).into()),
+            }
+        };
+        if let Some(clock) = game.clock_mut() {
+            // Both sides use the time measured by the remote player, which doesn't include the
+            // time the move took to arrive. It can't give them time back.
+            let measured = clock.remaining(remote_mark).saturating_sub(start.elapsed());
+            let left = clock_update.map_or(measured, |update: ClockUpdate| {
+                let reported = if remote_mark == Mark::X {
+                    update.x
+                } else {
+                    update.o
+                };
+                reported.min(clock.remaining(remote_mark))
+            });
+            *clock.remaining_mut(remote_mark) = left;
+        }
+        player_move
+    };
+
+    let mark = if local_turn {
+        game.local_mark()
+    } else {
+        game.local_mark().opposite()
+    };
+    let (row, col) = match player_move {
+        Move::Place(row, col) => (row, col),
+        Move::Resign => {
+            if local_turn && !send_local_turn(game, &Resign.to_bytes())? {
+                return Ok(());
+            }
+            end_game(
+                game,
+                GameResult::Win {
+                    winner: mark.opposite(),
+                    reason: WinReason::Resignation,
+                },
+            );
+            return Ok(());
+        }
+        m => return Err(NetworkedGameError::UnsupportedMove(m)),
+    };
+
+    // Try applying move on a copy of the grid, which is only committed once the move is sent. This
+    // keeps the game's snapshot consistent with the remote player's if the connection drops.
+    let mut grid = *game.grid();
+    grid.try_set_cell(row, col, mark)?;
+
+    let seq = grid.cell_count() as u8;
+    if local_turn {
+        let pkt = PlayerMove { row, col, seq };
+        if !send_local_turn(game, &pkt.to_bytes())? {
+            return Ok(());
+        }
+        *game.unacked_move() = Some(pkt);
+    } else {
+        // A lost acknowledgment is detected by the remote player, who sends the move again
+        let _ = send_packet(game.stream().get_mut(), &MoveAck { seq }.to_bytes());
+    }
+
+    *game.grid_mut() = grid;
+    game.set_next_turn();
+    game.on_move(mark, row, col);
+
+    if let Some(result) = board_result(&grid) {
+        if local_turn {
+            // The remote player knows the result from their own grid, so it doesn't matter if they
+            // don't receive the packet
+            let _ = send_packet(game.stream().get_mut(), &EndOfGame(result).to_bytes());
+        } else if let Ok(EndOfGame(remote_result)) = receive_end_of_game(game) {
+            if remote_result != result {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Remote player reported a different result",
+                )
+                .into());
+            }
+        }
+        end_game(game, result);
+    }
+    Ok(())
+}
+
+/// Records the game's result, and lets the game notify anyone watching it.
+fn end_game<G: InternalNetworkBufAccessor>(game: &mut G, result: GameResult) {
+    trace::event!(info, ?result, "game over");
+    *game.result_mut() = Some(result);
+    game.on_game_over(result);
+}
+
+/// Ends the game after the remote player didn't play their move within the idle timeout: the
+/// remote player is sent the result, and the connection is closed.
+fn forfeit_idle_peer<G: NetworkedGame + InternalNetworkBufAccessor>(game: &mut G) {
+    trace::event!(info, "remote player idle for too long");
+    let result = GameResult::Win {
+        winner: game.local_mark(),
+        reason: WinReason::Timeout,
+    };
+    let _ = send_packet(game.stream().get_mut(), &EndOfGame(result).to_bytes());
+    let _ = game.stream().get_ref().shutdown();
+    end_game(game, result);
+}
+
+/// Ends the game after `mark`'s player ran out of time on their clock, telling the remote side.
+fn forfeit_on_time<G: NetworkedGame + InternalNetworkBufAccessor>(game: &mut G, mark: Mark) {
+    trace::event!(info, ?mark, "player ran out of time");
+    let result = GameResult::Win {
+        winner: mark.opposite(),
+        reason: WinReason::Timeout,
+    };
+    // The remote side ends the game on its own if it doesn't receive the packet
+    let _ = send_packet(game.stream().get_mut(), &EndOfGame(result).to_bytes());
+    *game.unacked_move() = None;
+    end_game(game, result);
+}
+
+/// Sends a packet for the local player's turn, again once reconnected if the remote side didn't
+/// receive it. Returns `false` if the remote side's state shows the turn was already played.
+fn send_local_turn<G: NetworkedGame + InternalNetworkBufAccessor>(
+    game: &mut G,
+    pkt: &[u8],
+) -> io::Result<bool> {
+    while let Err(error) = send_packet(game.stream().get_mut(), pkt) {
+        game.reconnect(error)?;
+        if !game.is_local_turn() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Reads the END_OF_GAME packet the remote player sends after playing the last move.
+fn receive_end_of_game<G: NetworkedGame + InternalNetworkBufAccessor>(
+    game: &mut G,
+) -> io::Result<EndOfGame> {
+    match receive_packet(game)? {
+        Packet::EndOfGame(pkt) => Ok(pkt),
+        pkt => Err(unexpected_remote_packet(&pkt)),
+    }
+}
+
+/// Reads the remote player's next packet outside of a move. Chat messages are still handled, and
+/// heartbeats skipped.
+fn receive_packet<G: NetworkedGame + InternalNetworkBufAccessor>(
+    game: &mut G,
+) -> io::Result<Packet> {
+    game.stream()
+        .get_ref()
+        .set_read_timeout(Some(HEARTBEAT_TIMEOUT))?;
+    loop {
+        let mut buf = vec![];
+        if game.stream().read_until(protocol::TERMINATOR, &mut buf)? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        match parse_packet(&buf)? {
+            // Acknowledgments of the last move are no longer needed
+            Packet::Heartbeat
+            | Packet::MoveAck(_)
+            | Packet::InvalidMove(_)
+            | Packet::ClockUpdate(_) => {}
+            Packet::BoardSync(sync) => check_board_sync(sync, board_sync(&game.snapshot()))?,
+            Packet::ChatMessage(ChatMessage(message)) => game.chat_handler().receive(&message),
+            Packet::ServerShutdown(pkt) => game.chat_handler().server_shutdown(pkt),
+            pkt => return Ok(pkt),
+        }
+    }
+}
+
+fn networked_rematch<G: NetworkedGame + InternalNetworkBufAccessor>(
+    game: &mut G,
+    accept: bool,
+) -> io::Result<bool> {
+    let next = rematch_snapshot(&game.snapshot());
+    next_game(game, &Rematch(accept).to_bytes(), accept.then_some(next))
+}
+
+/// Sends the local player's answer `pkt` once the game is over, and restarts the game as `next` if
+/// the remote player accepts. The game restarts as the remote player's settings instead if they
+/// offered a new game. Nothing is waited for when `next` is `None`, since the local player declined.
+fn next_game<G: NetworkedGame + InternalNetworkBufAccessor>(
+    game: &mut G,
+    pkt: &[u8],
+    next: Option<GameSnapshot>,
+) -> io::Result<bool> {
+    let _span = game.span().enter();
+    if game.result().is_none() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "A rematch can only be played once the game is over",
+        ));
+    }
+    let sent = send_packet(game.stream().get_mut(), pkt);
+    let Some(next) = next else {
+        // The remote player may have already left, which doesn't matter when declining
+        return Ok(false);
+    };
+    sent?;
+
+    let next = loop {
+        match receive_packet(game)? {
+            // The END_OF_GAME packet may not have been read yet, if it arrived late
+            Packet::EndOfGame(_) => {}
+            Packet::Rematch(Rematch(accepts)) => break accepts.then_some(next),
+            Packet::NewGame(pkt) => break Some(new_game_snapshot(pkt)),
+            pkt => return Err(unexpected_remote_packet(&pkt)),
+        }
+    };
+    trace::event!(info, remote_accepts = next.is_some(), "rematch answered");
+    let Some(next) = next else {
+        return Ok(false);
+    };
+    game.restart(&next);
+    if let Some(clock) = game.clock_mut() {
+        *clock = Clock::new(clock.time);
+    }
+    Ok(true)
+}
+
+fn verify_board<G: NetworkedGame + InternalNetworkBufAccessor>(game: &mut G) -> io::Result<()> {
+    let _span = game.span().enter();
+    if !game.is_local_turn() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The board can only be verified during the local player's turn",
+        ));
+    }
+    let pkt = board_sync(&game.snapshot()).to_bytes();
+    send_packet(game.stream().get_mut(), &pkt)
+}
+
+/// Returns the BOARD_SYNC packet describing `game`.
+pub(crate) fn board_sync(game: &GameSnapshot) -> BoardSync {
+    BoardSync {
+        grid: game.grid,
+        turn: if game.is_local_turn {
+            game.local_mark
+        } else {
+            game.local_mark.opposite()
+        },
+    }
+}
+
+/// Checks a BOARD_SYNC packet sent by the remote player against the local state `expected`.
+pub(crate) fn check_board_sync(sync: BoardSync, expected: BoardSync) -> io::Result<()> {
+    if sync != expected {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Remote player's board doesn't match the local one",
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the starting state of a rematch of `game`, where the marks and first player are swapped.
+pub(crate) fn rematch_snapshot(game: &GameSnapshot) -> GameSnapshot {
+    // The first player's turn comes back whenever an even number of marks were placed, including
+    // after a resignation, since the turn isn't handed over when a player resigns
+    let local_played_first = game.is_local_turn == game.grid.cell_count().is_multiple_of(2);
+    GameSnapshot {
+        grid: Grid::default(),
+        is_local_turn: !local_played_first,
+        local_mark: game.local_mark.opposite(),
+    }
+}
+
+/// Returns the starting state of the new game a NEW_GAME packet offers the client.
+pub(crate) fn new_game_snapshot(pkt: NewGame) -> GameSnapshot {
+    GameSnapshot {
+        grid: Grid::default(),
+        is_local_turn: pkt.client_first,
+        local_mark: pkt.client_mark,
+    }
+}
+
+/// Interval between two heartbeats sent while the local player chooses their move.
+pub(crate) const HEARTBEAT_INTERVAL: Duration = if cfg!(test) {
+    Duration::from_millis(20)
+} else {
+    Duration::from_secs(5)
+};
+
+/// How long to wait for the remote player's move without receiving any packet, before considering
+/// the connection lost. Covers a few missed heartbeats.
+pub(crate) const HEARTBEAT_TIMEOUT: Duration = if cfg!(test) {
+    Duration::from_millis(200)
+} else {
+    Duration::from_secs(20)
+};
+
+/// How long to wait for the remote player to acknowledge the local player's move, before sending it
+/// again. The remote player acknowledges it as soon as it is received, so this is much shorter than
+/// [`HEARTBEAT_TIMEOUT`].
+pub(crate) const ACK_TIMEOUT: Duration = if cfg!(test) {
+    Duration::from_millis(50)
+} else {
+    Duration::from_secs(2)
+};
+
+/// How long to keep waiting for the move of a remote player who ran out of time on their clock,
+/// before ending the game. Covers the time the move takes to arrive.
+pub(crate) const CLOCK_GRACE_PERIOD: Duration = if cfg!(test) {
+    Duration::from_millis(100)
+} else {
+    Duration::from_secs(1)
+};
+
+/// How many times a move is sent again without being acknowledged, before considering the
+/// connection lost.
+pub(crate) const MAX_MOVE_RETRIES: u32 = 2;
+
+/// Calls `f`, sending heartbeats over `stream` from another thread until it returns.
+pub(crate) fn with_heartbeat<T>(stream: &mut (impl Write + Send), f: impl FnOnce() -> T) -> T {
+    let (stop, stopped) = mpsc::channel::<()>();
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(HEARTBEAT_INTERVAL) {
+                match send_packet(stream, &Heartbeat.to_bytes()) {
+                    // The connection isn't ready, try again with the next heartbeat
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                    // Other errors are noticed when the move is sent
+                    Err(_) => return,
+                    Ok(_) => {}
+                }
+            }
+        });
+
+        let result = f();
+        drop(stop);
+        result
+    })
+}
+
+/// Sends a CHAT packet with `message`, checking its length first.
+fn send_chat(stream: &mut impl Write, message: &str) -> io::Result<()> {
+    check_chat_len(message)?;
+    stream.write_all(&ChatMessage(message.to_owned()).to_bytes())?;
+    stream.flush()
+}
+
+pub(crate) fn check_chat_len(message: &str) -> io::Result<()> {
+    if message.len() > MAX_CHAT_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Chat messages are limited to {} bytes", MAX_CHAT_LEN),
+        ));
+    }
+    Ok(())
+}
+
+/// Asks the local player of a networked game for their move, giving up once `limit` is over, and
+/// checks it can be sent to the remote player: placing a mark or resigning.
+pub(crate) fn local_networked_move(
+    player: &Arc<dyn Player>,
+    grid: &Grid,
+    mark: Mark,
+    limit: Option<Duration>,
+) -> Result<Move, NetworkedGameError> {
+    let player_move = match limit {
+        Some(limit) => move_within(player, grid, mark, &[mark, mark.opposite()], limit)
+            .ok_or(NetworkedGameError::Timeout(mark))??,
+        None => player.get_move(grid, &mark)?,
+    };
+    match player_move {
+        Move::Place(..) | Move::Resign => Ok(player_move),
+        m => Err(NetworkedGameError::UnsupportedMove(m)),
+    }
+}
+
+/// Parses a packet read up to its terminator. Fails with [`ErrorKind::UnexpectedEof`] if the
+/// connection closed before the terminator.
+pub(crate) fn parse_packet(buf: &[u8]) -> io::Result<Packet> {
+    if buf.last() != Some(&protocol::TERMINATOR) {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Packet::parse(buf)
+        .inspect(|_pkt| trace::event!(trace, packet = ?_pkt, "received packet"))
+        .map_err(|e| {
+            trace::event!(warn, error = %e, "received invalid packet");
+            io::Error::new(ErrorKind::InvalidData, e)
+        })
+}
+
+/// Error for a packet the remote player isn't expected to send at this point of the game.
+pub(crate) fn unexpected_remote_packet(pkt: &Packet) -> io::Error {
+    if let Packet::EndOfGame(_) = pkt {
+        return io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "received unexpected end of game packet",
+        );
+    }
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Unexpected packet from remote player: {:?}", pkt),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{
+        game::{tests::SlowPlayer, MemoryStream},
+        player::{
+            tests::{shared, MockPlayer},
+            ScriptedPlayer,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn snapshot_ser_de() {
+        let mut grid = Grid::default();
+        grid.set_cell(1, 2, Mark::O);
+        let snapshot = GameSnapshot {
+            grid,
+            is_local_turn: true,
+            local_mark: Mark::X,
+        };
+
+        let deserialized = GameSnapshot::from_bytes(&snapshot.to_bytes())
+            .expect("Error deserializing the byte value");
+        assert_eq!(deserialized, snapshot);
+        assert!(GameSnapshot::from_bytes(&[0; 9]).is_err());
+    }
+
+    #[test]
+    fn resume_restores_networked_game() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        let host = GameSnapshot {
+            grid,
+            is_local_turn: false,
+            local_mark: Mark::X,
+        };
+        let client = GameSnapshot {
+            grid,
+            is_local_turn: true,
+            local_mark: Mark::O,
+        };
+
+        let server = ServerGame::resume("127.0.0.1:0", &host).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen().map(|game| game.snapshot()));
+
+        let remote = RemoteGame::resume(addr, &client).expect("Error resuming game");
+        assert_eq!(remote.snapshot(), client);
+        let server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        assert_eq!(server, host);
+    }
+
+    #[test]
+    fn resume_rejects_mismatched_game() {
+        let host = GameSnapshot {
+            grid: Grid::default(),
+            is_local_turn: true,
+            local_mark: Mark::X,
+        };
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        let client = GameSnapshot {
+            grid,
+            is_local_turn: false,
+            local_mark: Mark::O,
+        };
+
+        let server = ServerGame::resume("127.0.0.1:0", &host).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        // Server keeps waiting for a matching client, so the thread is never joined
+        thread::spawn(move || server.listen().map(|game| game.snapshot()));
+
+        assert!(RemoteGame::resume(addr, &client).is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn tls_game_exchanges_moves() {
+        use std::sync::Arc;
+
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+
+        // Certificate for localhost, signed by a test CA
+        let ca = CertificateDer::from(&include_bytes!("../../tests/data/ca.der")[..]);
+        let cert = CertificateDer::from(&include_bytes!("../../tests/data/localhost.der")[..]);
+        let key =
+            PrivatePkcs8KeyDer::from(&include_bytes!("../../tests/data/localhost.key.der")[..]);
+
+        let server_config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], PrivateKeyDer::Pkcs8(key.clone_key()))
+            .expect("Error loading test certificate");
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(ca).expect("Error loading test CA");
+        let client_config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || {
+            let mut game = server.listen_tls(Arc::new(server_config))?;
+            game.try_move(&shared(MockPlayer(0, 0)))
+                .map_err(io::Error::other)?;
+            io::Result::Ok(game.snapshot())
+        });
+
+        let server_name = ServerName::try_from("localhost").unwrap();
+        let mut client = RemoteGame::connect_tls(addr, Arc::new(client_config), server_name)
+            .expect("Error connecting to server");
+        client
+            .try_move(&shared(MockPlayer(1, 1)))
+            .expect("Error receiving move");
+
+        let host = handle.join().unwrap().expect("Error playing as host");
+        assert_eq!(client.grid(), &host.grid);
+        assert_eq!(client.grid().get_cell(0, 0).try_get_mark(), Some(&Mark::X));
+    }
+
+    #[cfg(feature = "noise")]
+    #[test]
+    fn noise_game_exchanges_moves() {
+        use crate::noise::{NoiseConfig, NoiseKeypair};
+
+        let host_keys = NoiseKeypair::generate().unwrap();
+        let client_keys = NoiseKeypair::generate().unwrap();
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let host_config = NoiseConfig {
+            private_key: host_keys.private_key,
+            remote_public_key: Some(client_keys.public_key),
+        };
+        let handle = thread::spawn(move || {
+            let mut game = server.listen_noise(host_config)?;
+            game.try_move(&shared(MockPlayer(0, 0)))
+                .map_err(io::Error::other)?;
+            io::Result::Ok(game.snapshot())
+        });
+
+        let config = NoiseConfig {
+            private_key: client_keys.private_key,
+            remote_public_key: Some(host_keys.public_key),
+        };
+        let mut client =
+            RemoteGame::connect_noise(addr, config).expect("Error connecting to server");
+        client
+            .try_move(&shared(MockPlayer(1, 1)))
+            .expect("Error receiving move");
+
+        let host = handle.join().unwrap().expect("Error playing as host");
+        assert_eq!(client.grid(), &host.grid);
+        assert_eq!(client.grid().get_cell(0, 0).try_get_mark(), Some(&Mark::X));
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn websocket_game_exchanges_moves() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || {
+            let mut game = server.listen_websocket()?;
+            game.try_move(&shared(MockPlayer(0, 0)))
+                .map_err(io::Error::other)?;
+            io::Result::Ok(game)
+        });
+
+        let mut client = RemoteGame::connect_websocket(addr).expect("Error connecting to server");
+        client
+            .try_move(&shared(MockPlayer(1, 1)))
+            .expect("Error receiving move");
+
+        // The host is kept alive, since closed connections don't have a peer address anymore
+        let host = handle.join().unwrap().expect("Error playing as host");
+        assert_eq!(client.grid(), host.grid());
+        assert_eq!(client.peer().addr, Some(addr));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_game_exchanges_moves() {
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("tictactoe-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).expect("Error binding to socket");
+        let handle = thread::spawn(move || {
+            let (socket, _) = listener.accept()?;
+            let mut game = ServerGame::accept_stream(socket, &ServerGameSettings::default())?;
+            game.try_move(&shared(MockPlayer(0, 0)))
+                .map_err(io::Error::other)?;
+            io::Result::Ok(game.snapshot())
+        });
+
+        let mut client = RemoteGame::connect_unix(&path).expect("Error connecting to server");
+        client
+            .try_move(&shared(MockPlayer(1, 1)))
+            .expect("Error receiving move");
+
+        let host = handle.join().unwrap().expect("Error playing as host");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(client.grid(), &host.grid);
+        assert_eq!(client.peer().addr, None);
+    }
+
+    #[test]
+    fn networked_move_times_out() {
+        let settings = ServerGameSettings {
+            host_plays_first: false,
+            host_mark: Mark::X,
+            ..Default::default()
+        };
+        let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+
+        // The client never plays its move
+        let _client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        server
+            .set_move_time_limit(Some(Duration::from_millis(10)))
+            .unwrap();
+        let err = server.try_move(&shared(MockPlayer(0, 0))).unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Timeout(Mark::O)));
+        assert_eq!(server.grid().cell_count(), 0);
+    }
+
+    #[test]
+    fn stuck_local_player_times_out() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let _client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        server
+            .set_move_time_limit(Some(Duration::from_millis(10)))
+            .unwrap();
+
+        let start = Instant::now();
+        let err = server
+            .try_move(&shared(SlowPlayer(Duration::from_secs(60))))
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(matches!(err, NetworkedGameError::Timeout(Mark::X)));
+        assert_eq!(server.grid().cell_count(), 0);
+    }
+
+    #[test]
+    fn clocks_are_shared_and_enforced() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        let time = Duration::from_millis(200);
+        server.set_clock(Some(time));
+        client.set_clock(Some(time));
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client
+            .try_move(&shared(SlowPlayer(Duration::from_millis(50))))
+            .unwrap();
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        let clock = server.clock().unwrap();
+        assert!(clock.remaining(Mark::O) <= time - Duration::from_millis(50));
+        assert_eq!(client.clock(), Some(clock));
+
+        // The host runs out of time while choosing its move
+        server.try_move(&shared(SlowPlayer(time))).unwrap();
+        client.try_move(&shared(MockPlayer(2, 2))).unwrap();
+        let result = GameResult::Win {
+            winner: Mark::O,
+            reason: WinReason::Timeout,
+        };
+        assert_eq!(server.result(), Some(result));
+        assert_eq!(client.result(), Some(result));
+        assert_eq!(server.grid().cell_count(), 2);
+
+        // The host ends the game once the client's time is up, even without hearing from it
+        let rematch = thread::spawn(move || client.rematch(true).map(|_| client));
+        assert!(server.rematch(true).unwrap());
+        let client = rematch.join().unwrap().unwrap();
+        assert_eq!(server.clock().unwrap().remaining(Mark::O), time);
+        // Short enough to run out before the heartbeat timeout
+        server.set_clock(Some(Duration::from_millis(20)));
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        assert_eq!(
+            server.result(),
+            Some(GameResult::Win {
+                winner: Mark::O,
+                reason: WinReason::Timeout,
+            })
+        );
+        drop(client);
+    }
+
+    #[test]
+    fn chat_is_received_while_waiting_for_move() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        client.set_chat_handler(Box::new(move |message| {
+            sender.send(message.to_owned()).unwrap()
+        }));
+        server.send_chat("Good luck!").unwrap();
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+
+        assert_eq!(receiver.try_recv().as_deref(), Ok("Good luck!"));
+        assert_eq!(client.grid(), server.grid());
+        let err = client.send_chat(&"a".repeat(MAX_CHAT_LEN + 1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn networked_game_ends_with_result() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        let server_player = shared(ScriptedPlayer::new(
+            [(0, 0), (0, 1), (0, 2)].map(|(r, c)| Move::Place(r, c)),
+        ));
+        let client_player = shared(ScriptedPlayer::new(
+            [(1, 0), (1, 1)].map(|(r, c)| Move::Place(r, c)),
+        ));
+        let handle = thread::spawn(move || {
+            while client.result().is_none() {
+                client.try_move(&client_player).unwrap();
+            }
+            (client, client_player)
+        });
+        while server.result().is_none() {
+            server.try_move(&server_player).unwrap();
+        }
+        let (mut client, client_player) = handle.join().unwrap();
+
+        let result = GameResult::Win {
+            winner: Mark::X,
+            reason: WinReason::Line,
+        };
+        assert_eq!(server.result(), Some(result));
+        assert_eq!(client.result(), Some(result));
+        assert!(matches!(
+            client.try_move(&client_player),
+            Err(NetworkedGameError::GameOver)
+        ));
+    }
+
+    #[test]
+    fn players_resign_over_the_network() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client
+            .try_move(&shared(ScriptedPlayer::new([Move::Resign])))
+            .unwrap();
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+
+        let result = GameResult::Win {
+            winner: Mark::X,
+            reason: WinReason::Resignation,
+        };
+        assert_eq!(client.result(), Some(result));
+        assert_eq!(server.result(), Some(result));
+        assert_eq!(server.grid().cell_count(), 1);
+    }
+
+    #[test]
+    fn rematch_swaps_marks_and_first_player() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        assert_eq!(
+            server.rematch(true).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client
+            .try_move(&shared(ScriptedPlayer::new([Move::Resign])))
+            .unwrap();
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+
+        let handle = thread::spawn(move || client.rematch(true).map(|accepted| (client, accepted)));
+        assert!(server.rematch(true).unwrap());
+        let (mut client, accepted) = handle.join().unwrap().unwrap();
+        assert!(accepted);
+
+        assert_eq!(server.result(), None);
+        assert_eq!(server.grid().cell_count(), 0);
+        assert_eq!(
+            (server.local_mark(), client.local_mark()),
+            (Mark::O, Mark::X)
+        );
+        assert!(client.is_local_turn() && !server.is_local_turn());
+        client.try_move(&shared(MockPlayer(2, 2))).unwrap();
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        assert_eq!(server.grid(), client.grid());
+    }
+
+    #[test]
+    fn host_offers_new_game_with_its_settings() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        let resign = shared(ScriptedPlayer::new([Move::Resign, Move::Resign]));
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&resign).unwrap();
+        client.try_move(&resign).unwrap();
+        server.try_move(&resign).unwrap();
+        let handle = thread::spawn(move || client.rematch(true).map(|accepted| (client, accepted)));
+        // Unlike a rematch, the host keeps moving first
+        assert!(server.new_game(true, Mark::O).unwrap());
+        let (mut client, accepted) = handle.join().unwrap().unwrap();
+        assert!(accepted);
+
+        assert_eq!(
+            (server.local_mark(), client.local_mark()),
+            (Mark::O, Mark::X)
+        );
+        assert!(server.is_local_turn() && !client.is_local_turn());
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        client.try_move(&resign).unwrap();
+        client.try_move(&resign).unwrap();
+        server.try_move(&resign).unwrap();
+        assert_eq!(server.grid(), client.grid());
+
+        let handle = thread::spawn(move || client.rematch(false));
+        assert!(!server.new_game(true, Mark::X).unwrap());
+        assert!(!handle.join().unwrap().unwrap());
+        assert!(server.result().is_some());
+    }
+
+    #[test]
+    fn declined_rematch_is_not_played() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        server
+            .try_move(&shared(ScriptedPlayer::new([Move::Resign])))
+            .unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+
+        // Declining doesn't wait for the other answer
+        assert!(!client.rematch(false).unwrap());
+        assert!(!server.rematch(true).unwrap());
+        assert_eq!(server.local_mark(), Mark::X);
+        assert!(server.result().is_some());
+    }
+
+    #[test]
+    fn networked_games_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RemoteGame>();
+        assert_send_sync::<ServerGame<NewState>>();
+        assert_send_sync::<ServerGame<ConnectedState>>();
+        assert_send_sync::<NetworkedGameError>();
+        assert_send_sync::<crate::spectator::Spectator>();
+        #[cfg(feature = "tls")]
+        assert_send_sync::<RemoteGame<TlsClientStream>>();
+        #[cfg(feature = "noise")]
+        assert_send_sync::<ServerGame<ConnectedState<NoiseStream<TcpStream>>>>();
+    }
+
+    #[test]
+    fn host_rematch_policy_is_applied() {
+        for (policy, expected, host_first) in [
+            (RematchPolicy::Keep, true, true),
+            (RematchPolicy::Alternate, true, false),
+            (RematchPolicy::Never, false, true),
+        ] {
+            let settings = ServerGameSettings::default().with_rematch_policy(policy);
+            let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding");
+            let addr = server.local_addr().expect("Error getting server address");
+            let handle = thread::spawn(move || server.listen());
+            let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+            let mut server = handle
+                .join()
+                .unwrap()
+                .expect("Error listening to connections");
+
+            server
+                .try_move(&shared(ScriptedPlayer::new([Move::Resign])))
+                .unwrap();
+            client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+            let handle =
+                thread::spawn(move || client.rematch(true).map(|accepted| (client, accepted)));
+            assert_eq!(server.rematch(true).unwrap(), expected);
+            let (client, accepted) = handle.join().unwrap().unwrap();
+            assert_eq!(accepted, expected);
+
+            // Only swapped rematches swap the marks
+            assert_eq!(server.local_mark(), Mark::X);
+            assert_eq!(client.local_mark(), Mark::O);
+            assert_eq!(server.is_local_turn(), host_first);
+            assert_eq!(client.is_local_turn(), !host_first);
+            assert_eq!(server.result().is_none(), expected);
+        }
+    }
+
+    #[test]
+    fn board_verification_detects_mismatch() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        assert_eq!(
+            client.verify_board().unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        server.verify_board().unwrap();
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+
+        client.grid_mut().set_cell(0, 0, Mark::X);
+        client.verify_board().unwrap();
+        match server.try_move(&shared(MockPlayer(0, 0))) {
+            Err(NetworkedGameError::Io(e)) => assert_eq!(e.kind(), ErrorKind::InvalidData),
+            other => panic!("Expected the boards to mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn client_reconnects_after_connection_drops() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+
+        // The server notices the drop while waiting for the client's move
+        let handle =
+            thread::spawn(move || server.try_move(&shared(MockPlayer(0, 0))).map(|_| server));
+        client
+            .stream
+            .get_ref()
+            .shutdown(std::net::Shutdown::Both)
+            .unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        let server = handle.join().unwrap().expect("Error receiving move");
+
+        assert_eq!(client.grid(), server.grid());
+        assert_eq!(server.grid().cell_count(), 2);
+        assert!(server.is_local_turn());
+    }
+
+    #[test]
+    fn server_without_grace_period_fails_on_drop() {
+        let settings = ServerGameSettings {
+            reconnect_grace_period: None,
+            ..Default::default()
+        };
+        let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        assert!(client.reconnector.token.is_none());
+
+        drop(client);
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        let err = server.try_move(&shared(MockPlayer(0, 0))).unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Io(_)));
+    }
+
+    #[test]
+    fn heartbeats_keep_slow_moves_alive() {
+        #[derive(Debug)]
+        struct ThinkingPlayer;
+
+        impl Player for ThinkingPlayer {
+            fn get_move(&self, _: &Grid, _: &Mark) -> Result<Move, PlayerError> {
+                thread::sleep(HEARTBEAT_TIMEOUT * 3);
+                Ok(Move::Place(1, 1))
+            }
+        }
+
+        let settings = ServerGameSettings {
+            reconnect_grace_period: None,
+            ..Default::default()
+        };
+        let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        let handle =
+            thread::spawn(move || client.try_move(&shared(ThinkingPlayer)).map(|_| client));
+        server.try_move(&shared(ThinkingPlayer)).unwrap();
+        let client = handle.join().unwrap().expect("Error receiving move");
+        assert_eq!(client.grid(), server.grid());
+    }
+
+    #[test]
+    fn silent_peer_is_detected() {
+        let settings = ServerGameSettings {
+            host_plays_first: false,
+            reconnect_grace_period: None,
+            ..Default::default()
+        };
+        let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+
+        // The client stays connected, but neither plays nor sends heartbeats
+        let mut client = TcpStream::connect(addr).expect("Error connecting to server");
+        client.write_all(&ClientHello.to_bytes()).unwrap();
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        let err = server.try_move(&shared(MockPlayer(0, 0))).unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Io(e) if e.kind() == ErrorKind::TimedOut));
+    }
+
+    #[test]
+    fn lost_move_is_sent_again_then_detected() {
+        let settings = ServerGameSettings {
+            reconnect_grace_period: None,
+            ..Default::default()
+        };
+        let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+
+        // The client receives moves, but never acknowledges them
+        let mut client = BufReader::new(TcpStream::connect(addr).expect("Error connecting"));
+        client.get_mut().write_all(&ClientHello.to_bytes()).unwrap();
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        let mut buf = vec![];
+        client.read_until(protocol::TERMINATOR, &mut buf).unwrap();
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        let start = Instant::now();
+        let err = server.try_move(&shared(MockPlayer(0, 0))).unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Io(e) if e.kind() == ErrorKind::TimedOut));
+        assert!(start.elapsed() < HEARTBEAT_TIMEOUT);
+
+        let sent = PlayerMove {
+            row: 1,
+            col: 1,
+            seq: 1,
+        };
+        for _ in 0..=MAX_MOVE_RETRIES {
+            buf.clear();
+            client.read_until(protocol::TERMINATOR, &mut buf).unwrap();
+            assert_eq!(buf, sent.to_bytes());
+        }
+    }
+
+    #[test]
+    fn corrupted_move_is_detected() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+
+        let mut client = BufReader::new(TcpStream::connect(addr).expect("Error connecting"));
+        client.get_mut().write_all(&ClientHello.to_bytes()).unwrap();
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        let mut buf = vec![];
+        client.read_until(protocol::TERMINATOR, &mut buf).unwrap();
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        buf.clear();
+        client.read_until(protocol::TERMINATOR, &mut buf).unwrap();
+        client
+            .get_mut()
+            .write_all(&MoveAck { seq: 1 }.to_bytes())
+            .unwrap();
+
+        // A bit flipped in transit turns the move into another cell
+        let mut pkt = PlayerMove {
+            row: 0,
+            col: 0,
+            seq: 2,
+        }
+        .to_bytes();
+        pkt[0] ^= 0b1;
+        client.get_mut().write_all(&pkt).unwrap();
+        let err = server.try_move(&shared(MockPlayer(0, 0))).unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Io(e) if e.kind() == ErrorKind::InvalidData));
+        assert!(server.grid().get_cell(0, 1).try_get_mark().is_none());
+    }
+
+    #[test]
+    fn repeated_move_is_acknowledged_once_played() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        // As if the acknowledgment was late, and the move sent again
+        let sent = server.state.unacked_move.unwrap();
+        server
+            .state
+            .stream
+            .get_mut()
+            .write_all(&sent.to_bytes())
+            .unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+
+        assert_eq!(client.grid(), server.grid());
+        assert_eq!(client.grid().cell_count(), 2);
+        assert_eq!(server.state.unacked_move, None);
+    }
+
+    #[test]
+    fn rejected_move_is_taken_back_and_played_again() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        // The client's grid no longer matches, so it plays on the server's mark
+        client.grid_mut().clear_cell(1, 1);
+        client.grid_mut().set_cell(2, 2, Mark::X);
+        let handle = thread::spawn(move || {
+            server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+            server
+        });
+
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        let err = client.try_move(&shared(MockPlayer(0, 0))).unwrap_err();
+        assert!(matches!(
+            err,
+            NetworkedGameError::Rejected(GridPlacementError::CellInUse { row: 1, col: 1 })
+        ));
+        assert!(client.is_local_turn());
+        assert!(client.grid().get_cell(1, 1).is_empty());
+
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        let server = handle.join().unwrap();
+        assert_eq!(server.grid().get_cell(0, 0).try_get_mark(), Some(&Mark::O));
+        assert_eq!(server.grid().cell_count(), 2);
+    }
+
+    #[test]
+    fn listen_skips_invalid_handshakes() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+
+        let mut garbage = TcpStream::connect(addr).expect("Error connecting");
+        garbage.write_all(&[1, 2, 3, protocol::TERMINATOR]).unwrap();
+        drop(TcpStream::connect(addr).expect("Error connecting"));
+        let client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        assert_eq!(client.local_mark(), server.local_mark().opposite());
+        // Clients opening with CLIENT_HELLO speak the first version of the protocol
+        assert_eq!(
+            (server.peer().nickname, server.peer().protocol_version),
+            (None, 1)
+        );
+        assert_eq!(client.peer().addr, Some(addr));
+        let peer = garbage.local_addr().unwrap().ip();
+        assert_eq!(
+            server.state.listener.as_ref().unwrap().limiter.peers[&peer].failed_handshakes,
+            2
+        );
+    }
+
+    #[test]
+    fn clients_join_with_the_password_and_a_supported_version() {
+        let settings = ServerGameSettings::default()
+            .with_nickname("Host")
+            .with_password("secret")
+            .with_protocol_versions(PROTOCOL_VERSION..=PROTOCOL_VERSION)
+            .with_move_time_limit(Some(Duration::from_secs(5)));
+        let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+
+        // Plain CLIENT_HELLO packets are version 1, and don't have a password
+        assert!(RemoteGame::connect(addr).is_err());
+        let wrong = Introduction::new("Guest").with_password("guess");
+        assert!(RemoteGame::join(addr, &wrong).is_err());
+        let intro = Introduction::new("Guest").with_password("secret");
+        let mut client = RemoteGame::join(addr, &intro).expect("Error joining server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        let peer = client.stream.get_ref().local_addr().unwrap().ip();
+        assert_eq!(
+            server.state.listener.as_ref().unwrap().limiter.peers[&peer].failed_handshakes,
+            2
+        );
+        assert_eq!(server.state.move_time_limit, Some(Duration::from_secs(5)));
+        let guest = server.peer();
+        assert_eq!(guest.nickname.as_deref(), Some("Guest"));
+        assert_eq!(guest.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(guest.addr, client.stream.get_ref().local_addr().ok());
+        assert!(guest.reconnects);
+        assert_eq!(client.peer().to_string(), format!("Host ({})", addr));
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        assert_eq!(server.grid(), client.grid());
+
+        let host = settings.introduction(&Introduction {
+            version: 1,
+            ..intro
+        });
+        assert_eq!((host.version, host.nickname.as_str()), (1, "Host"));
+        assert!(host.password.is_empty());
+    }
+
+    #[test]
+    fn connection_limiter_refuses_spammers() {
+        let window = Duration::from_millis(50);
+        let mut limiter = ConnectionLimiter::new(Some(ConnectionLimits {
+            max_connections: 2,
+            window,
+            max_failed_handshakes: 1,
+        }));
+        let (spammer, other) = ([10, 0, 0, 1].into(), [10, 0, 0, 2].into());
+
+        assert!(limiter.allow(spammer));
+        assert!(limiter.allow(spammer));
+        assert!(!limiter.allow(spammer));
+        assert!(limiter.allow(other));
+
+        thread::sleep(window);
+        assert!(limiter.allow(spammer));
+        limiter.record_failure(spammer);
+        thread::sleep(window);
+        assert!(!limiter.allow(spammer));
+        assert!(limiter.allow(other));
+
+        let mut unlimited = ConnectionLimiter::new(None);
+        assert!((0..100).all(|_| unlimited.allow(spammer)));
+    }
+
+    #[test]
+    fn idle_client_forfeits_the_game() {
+        let settings = ServerGameSettings {
+            host_plays_first: false,
+            idle_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let server = ServerGame::bind("127.0.0.1:0", &settings).expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        let handle = thread::spawn(move || {
+            server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+            server.result()
+        });
+        thread::sleep(Duration::from_millis(200));
+        // The late move is ignored, and the client learns the result once it waits for the host
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+
+        let result = GameResult::Win {
+            winner: Mark::X,
+            reason: WinReason::Timeout,
+        };
+        assert_eq!(handle.join().unwrap(), Some(result));
+        assert_eq!(client.result(), Some(result));
+    }
+
+    #[test]
+    fn cancelled_listen_stops_waiting() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let canceller = server.canceller();
+        let handle = thread::spawn(move || server.listen());
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(!handle.is_finished());
+        canceller.cancel();
+        let err = handle
+            .join()
+            .unwrap()
+            .err()
+            .expect("Listening wasn't cancelled");
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn cancelled_moves_are_not_played() {
+        let (host, client) = MemoryStream::pair();
+        let handle = thread::spawn(move || {
+            ServerGame::accept_stream(host, &ServerGameSettings::default()).unwrap()
+        });
+        let mut client = RemoteGame::connect_stream(client, None).unwrap();
+        let mut server = handle.join().unwrap();
+        let cancel_later = |canceller: MoveCanceller| {
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                canceller.cancel();
+            })
+        };
+
+        // The client stops waiting for the host's move before its heartbeats are missed
+        let cancel = cancel_later(client.canceller());
+        let err = client.try_move(&shared(MockPlayer(0, 0))).unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Cancelled));
+        cancel.join().unwrap();
+        assert!(matches!(
+            client.try_move(&shared(MockPlayer(0, 0))),
+            Err(NetworkedGameError::Cancelled)
+        ));
+
+        // The move is received once the canceller is reset
+        client.canceller().reset();
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+
+        // A local move chosen after the cancellation isn't sent
+        let cancel = cancel_later(client.canceller());
+        let err = client
+            .try_move(&shared(SlowPlayer(Duration::from_millis(50))))
+            .unwrap_err();
+        assert!(matches!(err, NetworkedGameError::Cancelled));
+        cancel.join().unwrap();
+        assert_eq!(client.grid().cell_count(), 1);
+        client.canceller().reset();
+        client.try_move(&shared(MockPlayer(2, 2))).unwrap();
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        assert_eq!(client.grid(), server.grid());
+        assert_eq!(server.grid().cell_count(), 2);
+    }
+}
@@ -1,37 +1,99 @@
-use std::{error::Error, fmt::Display};
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Copy, Clone, Debug)]
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::board::Board;
+
+/// Error returned when a mark can't be placed on the cell at `(row, col)`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Error)]
 pub enum GridPlacementError {
-    CellInUse,
-    OutOfBounds,
+    #[error("Cell ({row}, {col}) is not empty")]
+    CellInUse { row: usize, col: usize },
+    #[error("Cell ({row}, {col}) is out of bounds")]
+    OutOfBounds { row: usize, col: usize },
 }
 
-impl Display for GridPlacementError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::CellInUse => write!(f, "Cell is not empty"),
-            Self::OutOfBounds => write!(f, "Cell is out of bounds"),
+impl GridPlacementError {
+    /// Returns the `(row, col)` of the cell the mark couldn't be placed on.
+    pub fn cell(&self) -> (usize, usize) {
+        match *self {
+            Self::CellInUse { row, col } | Self::OutOfBounds { row, col } => (row, col),
         }
     }
 }
-impl Error for GridPlacementError {}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Error returned when parsing a [`Grid`] from a string.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GridParseError {
+    /// The grid doesn't have 3 rows separated by `/`.
+    #[error("Expected 3 rows separated by '/', found {0}")]
+    RowCount(usize),
+    /// The row (counted from 1) doesn't have 3 cells.
+    #[error("Row {0} doesn't have 3 cells")]
+    RowLength(usize),
+    /// The character isn't `X`, `O` or `.` for an empty cell.
+    #[error("Invalid cell '{0}', expected X, O or .")]
+    InvalidCell(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Mark {
     X,
     O,
+    /// Third mark, only used in 3 player games.
+    Y,
 }
 
 impl Mark {
-    /// Returns the opposite mark:
+    /// Every mark, in the order they play: `X` first, then `O`, then `Y` in 3 player games.
+    pub const TURN_ORDER: [Mark; 3] = [Mark::X, Mark::O, Mark::Y];
+
+    /// Returns the opposite mark in a 2 player game:
     /// - `Mark::X` returns `Mark::O`
     /// - `Mark::O` returns `Mark::X`
+    ///
+    /// `Mark::Y` only plays in 3 player games, where [`Mark::next`] gives the mark playing next.
+    ///
+    /// # Panics
+    /// Panics if called on `Mark::Y`.
     pub fn opposite(&self) -> Self {
         match self {
             Self::X => Self::O,
             Self::O => Self::X,
+            Self::Y => panic!("Mark::Y has no opposite mark, see Mark::next"),
         }
     }
+
+    /// Returns the mark playing after this one in a game of `players` marks, see
+    /// [`Mark::TURN_ORDER`].
+    ///
+    /// # Panics
+    /// Panics if `players` isn't 2 or 3, or if this mark doesn't play in such a game.
+    pub fn next(&self, players: usize) -> Self {
+        self.rotation(players)[1]
+    }
+
+    /// Returns the marks of a game of `players` marks in the order they play, starting with this
+    /// one.
+    ///
+    /// # Panics
+    /// Panics if `players` isn't 2 or 3, or if this mark doesn't play in such a game.
+    pub fn rotation(&self, players: usize) -> Vec<Mark> {
+        assert!(
+            (2..=Self::TURN_ORDER.len()).contains(&players),
+            "Games are played by 2 or 3 marks"
+        );
+        let mut turns = Self::TURN_ORDER[..players].to_vec();
+        let first = turns
+            .iter()
+            .position(|mark| mark == self)
+            .unwrap_or_else(|| panic!("{} doesn't play in {} player games", self, players));
+        turns.rotate_left(first);
+        turns
+    }
 }
 
 impl Display for Mark {
@@ -39,6 +101,7 @@ impl Display for Mark {
         match self {
             Mark::X => write!(f, "X"),
             Mark::O => write!(f, "O"),
+            Mark::Y => write!(f, "Y"),
         }
     }
 }
@@ -65,14 +128,32 @@ impl CellState {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+/// Every line (rows, columns and diagonals) a player can win with.
+const LINES: [[(usize, usize); 3]; 8] = [
+    [(0, 0), (0, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2)],
+    [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 0), (2, 0)],
+    [(0, 1), (1, 1), (2, 1)],
+    [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)],
+    [(0, 2), (1, 1), (2, 0)],
+];
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Grid {
     inner: [CellState; 9],
 }
 
+/// Draws the grid with box drawing characters when the `unicode` feature is enabled, and with ASCII
+/// characters otherwise. The alternate form (`{:#}`) always uses ASCII characters.
 impl Display for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.fmt_inner(f)
+        if cfg!(feature = "unicode") && !f.alternate() {
+            self.fmt_unicode(f)
+        } else {
+            self.fmt_ascii(f)
+        }
     }
 }
 
@@ -85,6 +166,24 @@ impl Grid {
         self.inner[row * 3 + col] = CellState(Some(mark));
     }
 
+    /// Writes the grid the way it is parsed, like `X.O/.X./..O`.
+    pub fn notation(&self) -> String {
+        let rows = self.rows().map(|row| {
+            row.iter()
+                .map(|cell| {
+                    cell.try_get_mark()
+                        .map_or('.', |mark| mark.to_string().remove(0))
+                })
+                .collect::<String>()
+        });
+        rows.collect::<Vec<_>>().join("/")
+    }
+
+    /// Removes the mark on the cell, if any.
+    pub fn clear_cell(&mut self, row: usize, col: usize) {
+        self.inner[row * 3 + col] = CellState(None);
+    }
+
     pub fn try_set_cell(
         &mut self,
         row: usize,
@@ -92,11 +191,11 @@ impl Grid {
         mark: Mark,
     ) -> Result<(), GridPlacementError> {
         if !(0..=2).contains(&row) || !(0..=2).contains(&col) {
-            return Err(GridPlacementError::OutOfBounds);
+            return Err(GridPlacementError::OutOfBounds { row, col });
         }
 
         if !self.get_cell(row, col).is_empty() {
-            return Err(GridPlacementError::CellInUse);
+            return Err(GridPlacementError::CellInUse { row, col });
         }
 
         self.inner[row * 3 + col] = CellState(Some(mark));
@@ -155,8 +254,16 @@ impl Grid {
         None
     }
 
-    #[cfg(not(feature = "unicode"))]
-    fn fmt_inner(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    /// Returns true if neither player can complete a line anymore, meaning the game is guaranteed
+    /// to end in a draw no matter how it is played out.
+    pub fn is_draw_certain(&self) -> bool {
+        LINES.iter().all(|line| {
+            let marks = line.map(|(r, c)| self.get_cell(r, c).try_get_mark().copied());
+            marks.contains(&Some(Mark::X)) && marks.contains(&Some(Mark::O))
+        })
+    }
+
+    fn fmt_ascii(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Horizontal len = Left serparator + 3 * (left pad + cell value + pad + right separator)
         let side_string = "-".repeat(1 + 3 * 4);
         // Top
@@ -171,8 +278,7 @@ impl Grid {
         Ok(())
     }
 
-    #[cfg(feature = "unicode")]
-    fn fmt_inner(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt_unicode(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Horizontal top line: left corner + 2 * (2x line (padding) + line (value) + down part) +
         // (3 lines + right corner)
         let top_line = " \u{250C}".to_owned()
@@ -202,10 +308,116 @@ impl Grid {
     }
 }
 
+/// Serialized as 3 rows of 3 cells, each a mark or `None` when empty.
+#[cfg(feature = "serde")]
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<Vec<Option<Mark>>> = self
+            .rows()
+            .map(|row| row.iter().map(|cell| cell.0).collect())
+            .collect();
+        rows.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = <[[Option<Mark>; 3]; 3]>::deserialize(deserializer)?;
+        let mut grid = Self::default();
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                grid.inner[row * 3 + col] = CellState(*cell);
+            }
+        }
+        Ok(grid)
+    }
+}
+
+/// Parses rows separated by `/`, from top to bottom, with `X`, `O` and `.` for an empty cell, such
+/// as `X.O/.X./..O`. Marks are case insensitive.
+impl FromStr for Grid {
+    type Err = GridParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = s.trim().split('/').collect();
+        if rows.len() != 3 {
+            return Err(GridParseError::RowCount(rows.len()));
+        }
+
+        let mut grid = Grid::default();
+        for (row, cells) in rows.into_iter().enumerate() {
+            if cells.chars().count() != 3 {
+                return Err(GridParseError::RowLength(row + 1));
+            }
+            for (col, c) in cells.chars().enumerate() {
+                match c {
+                    'X' | 'x' => grid.set_cell(row, col, Mark::X),
+                    'O' | 'o' => grid.set_cell(row, col, Mark::O),
+                    '.' => {}
+                    c => return Err(GridParseError::InvalidCell(c)),
+                }
+            }
+        }
+        Ok(grid)
+    }
+}
+
+impl Board for Grid {
+    fn place(&mut self, row: usize, col: usize, mark: Mark) -> Result<(), GridPlacementError> {
+        self.try_set_cell(row, col, mark)
+    }
+
+    fn legal_moves(&self) -> Vec<(usize, usize)> {
+        (0..9)
+            .map(|i| (i / 3, i % 3))
+            .filter(|&(r, c)| self.get_cell(r, c).is_empty())
+            .collect()
+    }
+
+    fn winner(&self) -> Option<Mark> {
+        self.get_winning_mark()
+    }
+
+    fn is_full(&self) -> bool {
+        Grid::is_full(self)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (3, 3)
+    }
+
+    fn mark(&self, row: usize, col: usize) -> Option<Mark> {
+        self.get_cell(row, col).try_get_mark().copied()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn marks_rotate_in_turn_order() {
+        assert_eq!(Mark::X.opposite(), Mark::O);
+        assert_eq!(Mark::O.next(2), Mark::X);
+        assert_eq!(Mark::O.next(3), Mark::Y);
+        assert_eq!(Mark::Y.next(3), Mark::X);
+        assert_eq!(Mark::O.rotation(3), [Mark::O, Mark::Y, Mark::X]);
+        assert_eq!(Mark::X.rotation(2), [Mark::X, Mark::O]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn y_has_no_place_in_two_player_games() {
+        Mark::Y.next(2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn y_has_no_opposite() {
+        Mark::Y.opposite();
+    }
+
     #[test]
     fn is_full_detects_full_grid() {
         let mut grid = Grid::default();
@@ -218,6 +430,19 @@ mod tests {
         assert!(grid.is_full())
     }
 
+    #[test]
+    fn legal_moves_lists_empty_cells() {
+        let mut grid = Grid::default();
+        assert_eq!(grid.legal_moves().len(), 9);
+
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(2, 1, Mark::O);
+        let moves = grid.legal_moves();
+        assert_eq!(moves.len(), 7);
+        assert!(!moves.contains(&(0, 0)));
+        assert!(!moves.contains(&(2, 1)));
+    }
+
     #[test]
     fn find_winner_finds_horizontal_win() {
         for row in 0..=2 {
@@ -266,4 +491,63 @@ mod tests {
 
         assert!(grid.get_winning_mark().is_none())
     }
+
+    #[test]
+    fn is_draw_certain_detects_blocked_lines() {
+        // |X|O|X|
+        // |X|O|O|
+        // |O|X| |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::O);
+        grid.set_cell(0, 2, Mark::X);
+        grid.set_cell(1, 0, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+        grid.set_cell(1, 2, Mark::O);
+        grid.set_cell(2, 0, Mark::O);
+        grid.set_cell(2, 1, Mark::X);
+        assert!(grid.is_draw_certain());
+
+        // Diagonal (\) is still open for X
+        let mut grid = Grid::default();
+        grid.set_cell(0, 1, Mark::O);
+        grid.set_cell(1, 1, Mark::X);
+        assert!(!grid.is_draw_certain());
+    }
+
+    #[test]
+    fn grids_are_parsed_row_by_row() {
+        let grid: Grid = "X.O/.x./..O".parse().unwrap();
+        assert_eq!(grid.get_cell(0, 0).try_get_mark(), Some(&Mark::X));
+        assert_eq!(grid.get_cell(0, 2).try_get_mark(), Some(&Mark::O));
+        assert_eq!(grid.get_cell(1, 1).try_get_mark(), Some(&Mark::X));
+        assert_eq!(grid.cell_count(), 4);
+        assert_eq!(grid.notation(), "X.O/.X./..O");
+
+        assert_eq!("X.O/.X.".parse::<Grid>(), Err(GridParseError::RowCount(2)));
+        assert_eq!(
+            "X.O/.X/..O".parse::<Grid>(),
+            Err(GridParseError::RowLength(2))
+        );
+        assert_eq!(
+            "X.O/.Y./..O".parse::<Grid>(),
+            Err(GridParseError::InvalidCell('Y'))
+        );
+    }
+
+    #[test]
+    fn alternate_form_draws_ascii() {
+        let mut grid = Grid::default();
+        grid.set_cell(1, 1, Mark::X);
+        assert_eq!(
+            format!("{:#}", grid),
+            "-------------\n\
+             |   |   |   |\n\
+             -------------\n\
+             |   | X |   |\n\
+             -------------\n\
+             |   |   |   |\n\
+             -------------\n"
+        );
+    }
 }
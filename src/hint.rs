@@ -0,0 +1,580 @@
+use std::fmt::Display;
+
+use crate::{
+    board::Board,
+    game::PlayedMove,
+    grid::{Grid, Mark},
+    player::{BotPlayer, Move},
+    record::format_move,
+};
+
+/// Why a move is recommended, from the most to the least urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintReason {
+    /// The move completes a line.
+    Win,
+    /// The opponent would complete a line on this cell next turn.
+    Block,
+    /// The move creates 2 winning threats, only one of which can be blocked.
+    Fork,
+    /// The opponent would create a fork on this cell next turn.
+    BlockFork,
+    /// No immediate tactic, the move keeps the best outcome reachable with perfect play.
+    BestOutcome,
+}
+
+impl Display for HintReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Win => write!(f, "completes a line and wins the game"),
+            Self::Block => write!(f, "blocks your opponent's line"),
+            Self::Fork => write!(f, "creates two threats at once"),
+            Self::BlockFork => write!(f, "stops your opponent from creating two threats"),
+            Self::BestOutcome => write!(f, "keeps the best possible outcome"),
+        }
+    }
+}
+
+/// A recommended move, see [`suggest_move`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    pub row: usize,
+    pub col: usize,
+    pub reason: HintReason,
+}
+
+impl Display for Hint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Play row {}, column {}: it {}",
+            self.row + 1,
+            self.col + 1,
+            self.reason
+        )
+    }
+}
+
+/// Suggests the optimal move for `mark`, along with the reason it is recommended. Returns `None` if
+/// the game is already over.
+pub fn suggest_move(grid: &Grid, mark: &Mark) -> Option<Hint> {
+    if grid.winner().is_some() || grid.is_full() {
+        return None;
+    }
+
+    let (row, col) = BotPlayer::minimax_move(grid, mark);
+    let opponent = mark.opposite();
+    let reason = if winning_moves(grid, mark).contains(&(row, col)) {
+        HintReason::Win
+    } else if winning_moves(grid, &opponent).contains(&(row, col)) {
+        HintReason::Block
+    } else if creates_fork(grid, (row, col), mark) {
+        HintReason::Fork
+    } else if creates_fork(grid, (row, col), &opponent) {
+        HintReason::BlockFork
+    } else {
+        HintReason::BestOutcome
+    };
+
+    Some(Hint { row, col, reason })
+}
+
+/// Outcome of a position for the player about to move, if both players play perfectly from there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Evaluation {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Evaluation {
+    /// Returns the same outcome from the opponent's side.
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Loss => Self::Win,
+            Self::Draw => Self::Draw,
+            Self::Win => Self::Loss,
+        }
+    }
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loss => write!(f, "a loss"),
+            Self::Draw => write!(f, "a draw"),
+            Self::Win => write!(f, "a win"),
+        }
+    }
+}
+
+/// Evaluates `grid` for `mark`, who is about to play. A finished game is evaluated as it ended.
+pub fn evaluate(grid: &Grid, mark: &Mark) -> Evaluation {
+    match BotPlayer::minimax_score(grid, mark) {
+        score if score > 0 => Evaluation::Win,
+        0 => Evaluation::Draw,
+        _ => Evaluation::Loss,
+    }
+}
+
+/// Outcome and best moves of a position with perfect play, see [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionAnalysis {
+    /// Outcome for the player about to move
+    pub evaluation: Evaluation,
+    /// Every move reaching that outcome, winning as fast or losing as slowly as possible
+    pub best_moves: Vec<(usize, usize)>,
+    /// Moves played until the end of the game if both players keep playing their first best move,
+    /// starting with the player about to move
+    pub principal_variation: Vec<(usize, usize)>,
+}
+
+/// Analyzes `grid` for `mark`, who is about to play. Returns `None` if the game is already over.
+///
+/// ```
+/// # use tictactoe::{grid::{Grid, Mark}, hint::{analyze, Evaluation}};
+/// let grid: Grid = "XX./OO./...".parse().unwrap();
+/// let analysis = analyze(&grid, &Mark::X).unwrap();
+/// assert_eq!(analysis.evaluation, Evaluation::Win);
+/// assert_eq!(analysis.best_moves, [(0, 2)]);
+/// ```
+pub fn analyze(grid: &Grid, mark: &Mark) -> Option<PositionAnalysis> {
+    if grid.winner().is_some() || grid.is_full() {
+        return None;
+    }
+
+    let mut principal_variation = vec![];
+    let mut next = *grid;
+    let mut to_move = *mark;
+    while next.winner().is_none() && !next.is_full() {
+        let (row, col) = best_moves(&next, &to_move)[0];
+        next.set_cell(row, col, to_move);
+        principal_variation.push((row, col));
+        to_move = to_move.opposite();
+    }
+
+    Some(PositionAnalysis {
+        evaluation: evaluate(grid, mark),
+        best_moves: best_moves(grid, mark),
+        principal_variation,
+    })
+}
+
+/// Tactic available on the board to one of the players.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threat {
+    /// `mark` completes a line by playing on the cell, so their opponent has to block it.
+    Line { mark: Mark, row: usize, col: usize },
+    /// `mark` creates 2 winning threats at once by playing on the cell.
+    Fork { mark: Mark, row: usize, col: usize },
+}
+
+impl Display for Threat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Line { mark, row, col } => write!(
+                f,
+                "{} can complete a line on row {}, column {}",
+                mark,
+                row + 1,
+                col + 1
+            ),
+            Self::Fork { mark, row, col } => write!(
+                f,
+                "{} can create two threats at once on row {}, column {}",
+                mark,
+                row + 1,
+                col + 1
+            ),
+        }
+    }
+}
+
+/// Lists the threats of both X and O on `grid`, lines before forks. Forks are only listed for a
+/// mark without a line to complete, since any move would then leave them with two threats. Returns
+/// nothing if the game is over.
+pub fn threats(grid: &Grid) -> Vec<Threat> {
+    if grid.winner().is_some() || grid.is_full() {
+        return vec![];
+    }
+
+    let mut threats = vec![];
+    for mark in [Mark::X, Mark::O] {
+        threats.extend(
+            winning_moves(grid, &mark)
+                .into_iter()
+                .map(|(row, col)| Threat::Line { mark, row, col }),
+        );
+    }
+    for mark in [Mark::X, Mark::O] {
+        if !winning_moves(grid, &mark).is_empty() {
+            continue;
+        }
+        threats.extend(
+            grid.legal_moves()
+                .into_iter()
+                .filter(|&cell| creates_fork(grid, cell, &mark))
+                .map(|(row, col)| Threat::Fork { mark, row, col }),
+        );
+    }
+    threats
+}
+
+/// What a blundered move gives up, from the most to the least obvious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlunderReason {
+    /// A line could have been completed instead.
+    MissedWin,
+    /// The opponent is left a line to complete.
+    MissedBlock,
+    /// The opponent is left a cell creating two threats at once.
+    AllowsFork,
+    /// The opponent can force a better outcome some other way.
+    Other,
+    /// The player resigned before the game was lost.
+    Resignation,
+}
+
+impl Display for BlunderReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissedWin => write!(f, "misses a line you could complete"),
+            Self::MissedBlock => write!(f, "leaves your opponent a line to complete"),
+            Self::AllowsFork => write!(f, "lets your opponent create two threats at once"),
+            Self::Other => write!(f, "lets your opponent force a better outcome"),
+            Self::Resignation => write!(f, "gives up a game that wasn't lost"),
+        }
+    }
+}
+
+/// A move turning the outcome reachable with perfect play into a worse one, see [`review_move`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Blunder {
+    pub reason: BlunderReason,
+    /// Outcome reachable before the move
+    pub before: Evaluation,
+    /// Outcome reachable after the move
+    pub after: Evaluation,
+}
+
+impl Display for Blunder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "this move {}, turning {} into {}",
+            self.reason, self.before, self.after
+        )
+    }
+}
+
+/// Checks `mark` playing on `cell`, returning the blunder it makes if it leads to a worse outcome
+/// than the best move would.
+pub fn review_move(grid: &Grid, mark: &Mark, (row, col): (usize, usize)) -> Option<Blunder> {
+    let before = evaluate(grid, mark);
+    let mut next = *grid;
+    next.set_cell(row, col, *mark);
+    let after = evaluate(&next, &mark.opposite()).opposite();
+    if after >= before {
+        return None;
+    }
+
+    let opponent = mark.opposite();
+    let reason = if !winning_moves(grid, mark).is_empty() {
+        BlunderReason::MissedWin
+    } else if !winning_moves(&next, &opponent).is_empty() {
+        BlunderReason::MissedBlock
+    } else if next
+        .legal_moves()
+        .into_iter()
+        .any(|cell| creates_fork(&next, cell, &opponent))
+    {
+        BlunderReason::AllowsFork
+    } else {
+        BlunderReason::Other
+    };
+    Some(Blunder {
+        reason,
+        before,
+        after,
+    })
+}
+
+/// A move of a finished game with the outcome it left to its player, see [`annotate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Annotation {
+    pub played: PlayedMove,
+    /// Outcome reachable with perfect play by the player who moved, after the move
+    pub evaluation: Evaluation,
+    /// How the move made that outcome worse, if it did
+    pub blunder: Option<Blunder>,
+}
+
+/// Writes the move followed by its outcome, or by `??` and the blunder, such as `O b1?? this move
+/// lets your opponent create two threats at once, turning a draw into a loss`.
+impl Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let played = format_move(self.played.player_move);
+        match &self.blunder {
+            Some(blunder) => write!(f, "{} {}?? {}", self.played.mark, played, blunder),
+            None => write!(f, "{} {} ({})", self.played.mark, played, self.evaluation),
+        }
+    }
+}
+
+/// Annotates every move of a game played on the classic grid from the empty grid, such as the
+/// history of a finished [`Game`](crate::game::Game), flagging the moves throwing away a win or a
+/// draw. A resignation leaves a loss, and a draw offer leaves the outcome as it was.
+pub fn annotate(history: &[PlayedMove]) -> Vec<Annotation> {
+    let mut grid = Grid::default();
+    history
+        .iter()
+        .map(|&played| {
+            let mark = played.mark;
+            let before = evaluate(&grid, &mark);
+            let (evaluation, blunder) = match played.player_move {
+                Move::Place(row, col) => {
+                    let blunder = review_move(&grid, &mark, (row, col));
+                    grid.set_cell(row, col, mark);
+                    (evaluate(&grid, &mark.opposite()).opposite(), blunder)
+                }
+                Move::Resign => {
+                    let blunder = (before > Evaluation::Loss).then_some(Blunder {
+                        reason: BlunderReason::Resignation,
+                        before,
+                        after: Evaluation::Loss,
+                    });
+                    (Evaluation::Loss, blunder)
+                }
+                Move::OfferDraw => (before, None),
+            };
+            Annotation {
+                played,
+                evaluation,
+                blunder,
+            }
+        })
+        .collect()
+}
+
+/// Returns every cell where `mark` would complete a line.
+fn winning_moves(grid: &Grid, mark: &Mark) -> Vec<(usize, usize)> {
+    grid.legal_moves()
+        .into_iter()
+        .filter(|&(row, col)| {
+            let mut grid = *grid;
+            grid.set_cell(row, col, *mark);
+            grid.winner() == Some(*mark)
+        })
+        .collect()
+}
+
+/// Returns every move of `mark` with the best score, which accounts for how fast the game ends.
+fn best_moves(grid: &Grid, mark: &Mark) -> Vec<(usize, usize)> {
+    let scores: Vec<_> = grid
+        .legal_moves()
+        .into_iter()
+        .map(|(row, col)| {
+            let mut next = *grid;
+            next.set_cell(row, col, *mark);
+            (
+                (row, col),
+                -BotPlayer::minimax_score(&next, &mark.opposite()),
+            )
+        })
+        .collect();
+    let best = scores.iter().map(|&(_, score)| score).max().unwrap_or(0);
+    scores
+        .into_iter()
+        .filter(|&(_, score)| score == best)
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+/// Returns true if `mark` playing on `cell` would leave them with 2 or more ways to win.
+pub(crate) fn creates_fork(grid: &Grid, (row, col): (usize, usize), mark: &Mark) -> bool {
+    let mut grid = *grid;
+    grid.set_cell(row, col, *mark);
+    winning_moves(&grid, mark).len() >= 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggest_move_explains_win_and_block() {
+        // |X|X| |
+        // |O|O| |
+        // | | | |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 0, Mark::O);
+        grid.set_cell(1, 1, Mark::O);
+
+        let hint = suggest_move(&grid, &Mark::X).unwrap();
+        assert_eq!((hint.row, hint.col, hint.reason), (0, 2, HintReason::Win));
+
+        grid.set_cell(2, 2, Mark::X);
+        let hint = suggest_move(&grid, &Mark::O).unwrap();
+        assert_eq!((hint.row, hint.col, hint.reason), (1, 2, HintReason::Win));
+
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+        let hint = suggest_move(&grid, &Mark::O).unwrap();
+        assert_eq!((hint.row, hint.col, hint.reason), (0, 2, HintReason::Block));
+    }
+
+    #[test]
+    fn suggest_move_detects_fork() {
+        // |X|O| |
+        // | |X| |
+        // | | |O|
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::O);
+        grid.set_cell(1, 1, Mark::X);
+        grid.set_cell(2, 2, Mark::O);
+
+        let hint = suggest_move(&grid, &Mark::X).unwrap();
+        assert_eq!(hint.reason, HintReason::Fork);
+        assert!(creates_fork(&grid, (hint.row, hint.col), &Mark::X));
+    }
+
+    #[test]
+    fn threats_list_lines_and_forks() {
+        // |X| | |
+        // | |O| |
+        // | | |X|
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+        grid.set_cell(2, 2, Mark::X);
+        let found = threats(&grid);
+        assert!(found.contains(&Threat::Fork {
+            mark: Mark::X,
+            row: 0,
+            col: 2
+        }));
+        assert!(!found.iter().any(|t| matches!(t, Threat::Line { .. })));
+
+        grid.set_cell(0, 1, Mark::O);
+        assert_eq!(
+            threats(&grid)[0],
+            Threat::Line {
+                mark: Mark::O,
+                row: 2,
+                col: 1
+            }
+        );
+    }
+
+    #[test]
+    fn review_move_detects_blunders() {
+        // |X|X| |
+        // | |O| |
+        // | | | |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+        assert_eq!(review_move(&grid, &Mark::O, (0, 2)), None);
+        let blunder = review_move(&grid, &Mark::O, (2, 2)).unwrap();
+        assert_eq!(blunder.reason, BlunderReason::MissedBlock);
+        assert_eq!(
+            (blunder.before, blunder.after),
+            (Evaluation::Draw, Evaluation::Loss)
+        );
+
+        // Answering a corner with an edge lets X fork
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        let blunder = review_move(&grid, &Mark::O, (0, 1)).unwrap();
+        assert_eq!(blunder.reason, BlunderReason::Other);
+        assert_eq!(review_move(&grid, &Mark::O, (1, 1)), None);
+    }
+
+    #[test]
+    fn evaluate_scores_perfect_play() {
+        assert_eq!(evaluate(&Grid::default(), &Mark::X), Evaluation::Draw);
+
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::O);
+        assert_eq!(evaluate(&grid, &Mark::X), Evaluation::Win);
+        grid.set_cell(1, 1, Mark::X);
+        grid.set_cell(2, 2, Mark::X);
+        assert_eq!(evaluate(&grid, &Mark::O), Evaluation::Loss);
+    }
+
+    #[test]
+    fn analyze_finds_best_moves_and_principal_variation() {
+        // The center is X's only reply to a corner opening that doesn't lose
+        let grid: Grid = "O../.../...".parse().unwrap();
+        let analysis = analyze(&grid, &Mark::X).unwrap();
+        assert_eq!(analysis.evaluation, Evaluation::Draw);
+        assert_eq!(analysis.best_moves, [(1, 1)]);
+        assert_eq!(analysis.principal_variation.len(), 8);
+        assert_eq!(analysis.principal_variation[0], (1, 1));
+
+        // Winning right away beats blocking O's line and winning later
+        let grid: Grid = "XX./OO./X..".parse().unwrap();
+        let analysis = analyze(&grid, &Mark::X).unwrap();
+        assert_eq!(analysis.best_moves, [(0, 2)]);
+        assert_eq!(analysis.principal_variation, [(0, 2)]);
+
+        assert_eq!(analyze(&"XXX/OO./...".parse().unwrap(), &Mark::O), None);
+    }
+
+    #[test]
+    fn annotate_flags_the_moves_throwing_the_game_away() {
+        let place = |mark, row, col| PlayedMove {
+            mark,
+            player_move: Move::Place(row, col),
+        };
+        // O's edge reply to the corner opening loses, then X misses the win and resigns
+        let history = [
+            place(Mark::X, 0, 0),
+            place(Mark::O, 0, 1),
+            place(Mark::X, 2, 1),
+            PlayedMove {
+                mark: Mark::O,
+                player_move: Move::OfferDraw,
+            },
+            PlayedMove {
+                mark: Mark::X,
+                player_move: Move::Resign,
+            },
+        ];
+        let annotations = annotate(&history);
+        assert_eq!(annotations.len(), 5);
+
+        assert_eq!(annotations[0].evaluation, Evaluation::Draw);
+        assert_eq!(annotations[0].blunder, None);
+        assert_eq!(annotations[0].to_string(), "X a1 (a draw)");
+        let blunder = annotations[1].blunder.unwrap();
+        assert_eq!(
+            (blunder.before, blunder.after),
+            (Evaluation::Draw, Evaluation::Loss)
+        );
+        assert_eq!(annotations[2].blunder.unwrap().after, Evaluation::Draw);
+        assert_eq!(annotations[3].evaluation, Evaluation::Draw);
+        assert_eq!(annotations[3].blunder, None);
+        assert_eq!(
+            annotations[4].blunder.unwrap().reason,
+            BlunderReason::Resignation
+        );
+        assert!(annotations[4].to_string().starts_with("X resign?? "));
+    }
+
+    #[test]
+    fn suggest_move_returns_none_when_game_is_over() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(0, 2, Mark::X);
+        assert!(suggest_move(&grid, &Mark::O).is_none());
+    }
+}
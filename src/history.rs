@@ -0,0 +1,380 @@
+//! Finished games saved in a local SQLite database, with their players, moves, result and
+//! timestamps. See [`GameHistory`].
+
+use std::{
+    error::Error,
+    fmt::Display,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::{
+    game::{DrawReason, GameResult, PlayedMove, WinReason},
+    grid::Mark,
+    player::Move,
+    record::GameRecord,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        player_x TEXT NOT NULL,
+        player_o TEXT NOT NULL,
+        winner TEXT,
+        reason TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS moves (
+        game_id INTEGER NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+        ply INTEGER NOT NULL,
+        mark TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        row INTEGER,
+        col INTEGER,
+        PRIMARY KEY (game_id, ply)
+    );
+";
+
+/// Finished game of a [`GameHistory`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedGame {
+    /// Who played X, such as a name or a bot difficulty
+    pub player_x: String,
+    /// Who played O
+    pub player_o: String,
+    /// Every move of the game, empty if they weren't known
+    pub moves: Vec<PlayedMove>,
+    pub result: GameResult,
+    /// Saved to the second, like `ended_at`
+    pub started_at: SystemTime,
+    pub ended_at: SystemTime,
+}
+
+/// Keeps the players as the `X` and `O` tags.
+impl From<RecordedGame> for GameRecord {
+    fn from(game: RecordedGame) -> Self {
+        GameRecord::new(game.moves, game.result)
+            .with_tag("X", game.player_x)
+            .with_tag("O", game.player_o)
+    }
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    Sqlite(rusqlite::Error),
+    /// A saved value can't be read back, the database was changed by another program.
+    InvalidData(String),
+}
+
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sqlite(e) => write!(f, "Database error: {}", e),
+            Self::InvalidData(value) => write!(f, "Invalid value in the database: {}", value),
+        }
+    }
+}
+
+impl Error for HistoryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Sqlite(e) => Some(e),
+            Self::InvalidData(_) => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for HistoryError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sqlite(value)
+    }
+}
+
+/// Database of finished games, which are given an id when recorded. Ids increase with each game,
+/// so they list games in the order they were recorded.
+///
+/// ```
+/// # use std::time::SystemTime;
+/// # use tictactoe::{game::{DrawReason, GameResult}, history::{GameHistory, RecordedGame}};
+/// let mut history = GameHistory::open_in_memory().unwrap();
+/// let id = history
+///     .record(&RecordedGame {
+///         player_x: "Alice".to_owned(),
+///         player_o: "bot:impossible".to_owned(),
+///         moves: vec![],
+///         result: GameResult::Draw(DrawReason::Agreement),
+///         started_at: SystemTime::now(),
+///         ended_at: SystemTime::now(),
+///     })
+///     .unwrap();
+/// assert_eq!(history.game(id).unwrap().unwrap().player_x, "Alice");
+/// ```
+#[derive(Debug)]
+pub struct GameHistory {
+    connection: Connection,
+}
+
+impl GameHistory {
+    /// Opens the database at `path`, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Opens an empty database kept in memory, lost when dropped.
+    pub fn open_in_memory() -> Result<Self, HistoryError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Self, HistoryError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Saves `game`, and returns its id.
+    pub fn record(&mut self, game: &RecordedGame) -> Result<i64, HistoryError> {
+        let (winner, reason) = match game.result {
+            GameResult::Win { winner, reason } => {
+                let reason = match reason {
+                    WinReason::Line => "line",
+                    WinReason::Resignation => "resignation",
+                    WinReason::Timeout => "timeout",
+                };
+                (Some(winner.to_string()), reason)
+            }
+            GameResult::Draw(DrawReason::BoardFull) => (None, "board_full"),
+            GameResult::Draw(DrawReason::Agreement) => (None, "agreement"),
+        };
+
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO games (player_x, player_o, winner, reason, started_at, ended_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                game.player_x,
+                game.player_o,
+                winner,
+                reason,
+                to_secs(game.started_at),
+                to_secs(game.ended_at),
+            ],
+        )?;
+        let id = transaction.last_insert_rowid();
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO moves (game_id, ply, mark, kind, row, col)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (ply, played) in game.moves.iter().enumerate() {
+                let (kind, cell) = match played.player_move {
+                    Move::Place(row, col) => ("place", Some((row as i64, col as i64))),
+                    Move::Resign => ("resign", None),
+                    Move::OfferDraw => ("offer_draw", None),
+                };
+                insert.execute(params![
+                    id,
+                    ply as i64,
+                    played.mark.to_string(),
+                    kind,
+                    cell.map(|(row, _)| row),
+                    cell.map(|(_, col)| col),
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    /// Returns the game recorded with `id`, or `None` if there is none.
+    pub fn game(&self, id: i64) -> Result<Option<RecordedGame>, HistoryError> {
+        let columns = self
+            .connection
+            .query_row(
+                "SELECT player_x, player_o, winner, reason, started_at, ended_at
+                 FROM games WHERE id = ?1",
+                [id],
+                GameColumns::read,
+            )
+            .optional()?;
+        columns.map(|columns| self.load(id, columns)).transpose()
+    }
+
+    /// Returns every game along with its id, oldest first.
+    pub fn games(&self) -> Result<Vec<(i64, RecordedGame)>, HistoryError> {
+        let mut select = self.connection.prepare(
+            "SELECT id, player_x, player_o, winner, reason, started_at, ended_at
+             FROM games ORDER BY id",
+        )?;
+        let rows = select
+            .query_map([], |row| Ok((row.get(0)?, GameColumns::read_from(row, 1)?)))?
+            .collect::<Result<Vec<(i64, GameColumns)>, _>>()?;
+        rows.into_iter()
+            .map(|(id, columns)| Ok((id, self.load(id, columns)?)))
+            .collect()
+    }
+
+    /// Builds the game `id` from its `columns` of the games table, and its moves.
+    fn load(&self, id: i64, columns: GameColumns) -> Result<RecordedGame, HistoryError> {
+        let mut select = self
+            .connection
+            .prepare("SELECT mark, kind, row, col FROM moves WHERE game_id = ?1 ORDER BY ply")?;
+        let moves = select
+            .query_map([id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                ))
+            })?
+            .map(|row| {
+                let (mark, kind, row, col) = row?;
+                let player_move = match (kind.as_str(), row, col) {
+                    ("place", Some(row), Some(col)) if row >= 0 && col >= 0 => {
+                        Move::Place(row as usize, col as usize)
+                    }
+                    ("resign", None, None) => Move::Resign,
+                    ("offer_draw", None, None) => Move::OfferDraw,
+                    _ => return Err(HistoryError::InvalidData(format!("move {}", kind))),
+                };
+                Ok(PlayedMove {
+                    mark: parse_mark(&mark)?,
+                    player_move,
+                })
+            })
+            .collect::<Result<_, HistoryError>>()?;
+
+        let result = match (columns.winner.as_deref(), columns.reason.as_str()) {
+            (Some(winner), reason) => GameResult::Win {
+                winner: parse_mark(winner)?,
+                reason: match reason {
+                    "line" => WinReason::Line,
+                    "resignation" => WinReason::Resignation,
+                    "timeout" => WinReason::Timeout,
+                    _ => return Err(HistoryError::InvalidData(format!("reason {}", reason))),
+                },
+            },
+            (None, "board_full") => GameResult::Draw(DrawReason::BoardFull),
+            (None, "agreement") => GameResult::Draw(DrawReason::Agreement),
+            (None, reason) => return Err(HistoryError::InvalidData(format!("reason {}", reason))),
+        };
+
+        Ok(RecordedGame {
+            player_x: columns.player_x,
+            player_o: columns.player_o,
+            moves,
+            result,
+            started_at: from_secs(columns.started_at),
+            ended_at: from_secs(columns.ended_at),
+        })
+    }
+}
+
+/// Row of the games table, before its values are parsed.
+struct GameColumns {
+    player_x: String,
+    player_o: String,
+    winner: Option<String>,
+    reason: String,
+    started_at: i64,
+    ended_at: i64,
+}
+
+impl GameColumns {
+    fn read(row: &Row) -> rusqlite::Result<Self> {
+        Self::read_from(row, 0)
+    }
+
+    /// Reads the columns starting at index `first`.
+    fn read_from(row: &Row, first: usize) -> rusqlite::Result<Self> {
+        Ok(Self {
+            player_x: row.get(first)?,
+            player_o: row.get(first + 1)?,
+            winner: row.get(first + 2)?,
+            reason: row.get(first + 3)?,
+            started_at: row.get(first + 4)?,
+            ended_at: row.get(first + 5)?,
+        })
+    }
+}
+
+fn parse_mark(s: &str) -> Result<Mark, HistoryError> {
+    match s {
+        "X" => Ok(Mark::X),
+        "O" => Ok(Mark::O),
+        "Y" => Ok(Mark::Y),
+        _ => Err(HistoryError::InvalidData(format!("mark {}", s))),
+    }
+}
+
+fn to_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as i64)
+}
+
+fn from_secs(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(player_x: &str, moves: Vec<PlayedMove>, result: GameResult) -> RecordedGame {
+        RecordedGame {
+            player_x: player_x.to_owned(),
+            player_o: "bot:easy".to_owned(),
+            moves,
+            result,
+            started_at: from_secs(1_700_000_000),
+            ended_at: from_secs(1_700_000_060),
+        }
+    }
+
+    #[test]
+    fn games_are_read_back_in_order() {
+        let mut history = GameHistory::open_in_memory().unwrap();
+        let won = game(
+            "Alice",
+            vec![
+                PlayedMove {
+                    mark: Mark::X,
+                    player_move: Move::Place(1, 1),
+                },
+                PlayedMove {
+                    mark: Mark::O,
+                    player_move: Move::Resign,
+                },
+            ],
+            GameResult::Win {
+                winner: Mark::X,
+                reason: WinReason::Resignation,
+            },
+        );
+        let drawn = game("Bob", vec![], GameResult::Draw(DrawReason::Agreement));
+
+        let first = history.record(&won).unwrap();
+        let second = history.record(&drawn).unwrap();
+        assert!(first < second);
+        assert_eq!(history.game(first).unwrap(), Some(won.clone()));
+        assert_eq!(history.game(second + 1).unwrap(), None);
+        assert_eq!(
+            history.games().unwrap(),
+            vec![(first, won), (second, drawn)]
+        );
+    }
+
+    #[test]
+    fn games_are_kept_in_the_database_file() {
+        let path = std::env::temp_dir().join(format!("tictactoe-history-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let recorded = game("Alice", vec![], GameResult::Draw(DrawReason::BoardFull));
+        let id = GameHistory::open(&path).unwrap().record(&recorded).unwrap();
+
+        let games = GameHistory::open(&path).unwrap().games().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(games, vec![(id, recorded)]);
+    }
+}
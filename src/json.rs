@@ -0,0 +1,374 @@
+//! Line-delimited JSON encoding of the networked game protocol, for clients that would rather not
+//! implement the binary format: scripts, debugging tools, or programs written in other languages.
+//! Only available with the `json` feature.
+//!
+//! Every packet is a JSON object on its own line, with its kind in the `type` field:
+//!
+//! ```text
+//! {"type":"client_hello"}
+//! {"type":"server_hello","client_first":true,"client_mark":"X","token":"00ab12cd34ef5678"}
+//! {"type":"move","row":1,"col":1,"seq":1}
+//! {"type":"ack","seq":1}
+//! {"type":"heartbeat"}
+//! {"type":"end_of_game","result":{"winner":"X","reason":"line"}}
+//! ```
+//!
+//! The encoding is negotiated by the client's first packet: hosts listening with
+//! [`ServerGame::listen_json`](crate::game::ServerGame::listen_json) answer in JSON to clients
+//! opening with a JSON object, and in binary to the others. Clients speak JSON with
+//! [`RemoteGame::connect_json`](crate::game::RemoteGame::connect_json).
+
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use crate::{
+    game::GameStream,
+    grid::Mark,
+    protocol::{self, Packet, ServerHello},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Binary,
+    Json,
+}
+
+/// Connection speaking the JSON encoding of the protocol. The game reads and writes binary packets
+/// as usual, which are translated to and from JSON lines.
+///
+/// On the host side, the encoding is picked from the client's first packet, and binary packets
+/// are passed through unchanged when the client doesn't speak JSON.
+#[derive(Debug)]
+pub struct JsonStream<S> {
+    inner: BufReader<S>,
+    /// Encoding spoken by the peer, unknown until the host reads the client's first packet
+    encoding: Option<Encoding>,
+    /// Whether this is the host's side of the connection, which sends SERVER_HELLO packets
+    host: bool,
+    /// Line being read, kept across timeouts since part of it may already be read
+    line: Vec<u8>,
+    /// Binary packet decoded from the last line, not read yet
+    decoded: Vec<u8>,
+    /// Binary packet being written, until its terminator is written
+    encoded: Vec<u8>,
+}
+
+impl<S: Read + Write> JsonStream<S> {
+    /// Wraps the client's side of a connection, speaking JSON.
+    pub fn client(inner: S) -> Self {
+        Self::new(inner, Some(Encoding::Json), false)
+    }
+
+    /// Wraps the host's side of a connection, speaking the encoding of the client's first packet.
+    pub fn host(inner: S) -> Self {
+        Self::new(inner, None, true)
+    }
+
+    fn new(inner: S, encoding: Option<Encoding>, host: bool) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            encoding,
+            host,
+            line: vec![],
+            decoded: vec![],
+            encoded: vec![],
+        }
+    }
+
+    /// Returns whether the peer speaks JSON. Always `false` on the host side until the client's
+    /// first packet is read.
+    pub fn is_json(&self) -> bool {
+        self.encoding == Some(Encoding::Json)
+    }
+}
+
+impl<S: Read + Write> Read for JsonStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.encoding.is_none() {
+            // Binary packets never start with an opening brace
+            let Some(&first) = self.inner.fill_buf()?.first() else {
+                return Ok(0);
+            };
+            self.encoding = Some(if first == b'{' {
+                Encoding::Json
+            } else {
+                Encoding::Binary
+            });
+        }
+        if self.encoding == Some(Encoding::Binary) {
+            return self.inner.read(buf);
+        }
+
+        while self.decoded.is_empty() {
+            if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                return Ok(0);
+            }
+            if self.line.last() != Some(&b'\n') {
+                // The connection closed in the middle of a line
+                return Ok(0);
+            }
+            let line = std::mem::take(&mut self.line);
+            let line = std::str::from_utf8(&line)
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?
+                .trim();
+            if !line.is_empty() {
+                self.decoded = packet_from_json(line)?;
+            }
+        }
+
+        let len = buf.len().min(self.decoded.len());
+        buf[..len].copy_from_slice(&self.decoded[..len]);
+        self.decoded.drain(..len);
+        Ok(len)
+    }
+}
+
+impl<S: Read + Write> Write for JsonStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.is_json() {
+            return self.inner.get_mut().write(buf);
+        }
+
+        self.encoded.extend_from_slice(buf);
+        while let Some(end) = self.encoded.iter().position(|&b| b == protocol::TERMINATOR) {
+            let pkt: Vec<u8> = self.encoded.drain(..=end).collect();
+            let mut line = packet_to_json(&pkt, self.host)?.to_string();
+            line.push('\n');
+            self.inner.get_mut().write_all(line.as_bytes())?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.get_mut().flush()
+    }
+}
+
+impl<S: GameStream> GameStream for JsonStream<S> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.get_ref().set_read_timeout(timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.get_ref().set_write_timeout(timeout)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.get_ref().peer_addr()
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.inner.get_ref().shutdown()
+    }
+}
+
+/// Encodes a binary packet, including its checksum and terminator, as a JSON object. Hosts and
+/// clients both send HELLO packets, which `host` tells apart.
+pub fn packet_to_json(buf: &[u8], host: bool) -> io::Result<Value> {
+    let pkt = match Packet::parse(buf).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))? {
+        // A SERVER_HELLO without any flag set has the same bytes as CLIENT_HELLO
+        Packet::ClientHello if host => Packet::ServerHello(ServerHello {
+            client_first: false,
+            client_mark: Mark::O,
+            token: None,
+        }),
+        pkt => pkt,
+    };
+    serde_json::to_value(pkt).map_err(|_| invalid_data("Packet can't be encoded as JSON"))
+}
+
+/// Decodes a JSON object into a binary packet, including its checksum and terminator.
+pub fn packet_from_json(line: &str) -> io::Result<Vec<u8>> {
+    let pkt: Packet =
+        serde_json::from_str(line).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    Ok(pkt.to_bytes())
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    use serde_json::json;
+
+    use crate::{
+        game::{DrawReason, GameResult, NetworkedGame, RemoteGame, ServerGame, ServerGameSettings},
+        grid::{Grid, GridPlacementError},
+        player::tests::{shared, MockPlayer},
+        protocol::{
+            BoardSync, ChatMessage, ClockUpdate, EndOfGame, InvalidMove, MoveAck, NewGame,
+            PlayerMove, Reconnect,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn packets_round_trip_through_json() {
+        let mut grid = Grid::default();
+        grid.set_cell(1, 1, Mark::X);
+        let packets = [
+            PlayerMove {
+                row: 2,
+                col: 0,
+                seq: 3,
+            }
+            .to_bytes()
+            .to_vec(),
+            MoveAck { seq: 3 }.to_bytes().to_vec(),
+            ChatMessage("Hi \"there\"".to_owned()).to_bytes(),
+            EndOfGame(GameResult::Draw(DrawReason::BoardFull))
+                .to_bytes()
+                .to_vec(),
+            BoardSync {
+                grid,
+                turn: Mark::O,
+            }
+            .to_bytes()
+            .to_vec(),
+            Reconnect { token: u64::MAX }.to_bytes().to_vec(),
+            ClockUpdate {
+                x: Duration::from_millis(4_500),
+                o: Duration::from_secs(60),
+            }
+            .to_bytes()
+            .to_vec(),
+            NewGame {
+                client_first: true,
+                client_mark: Mark::X,
+            }
+            .to_bytes()
+            .to_vec(),
+            Packet::ClientHello.to_bytes(),
+            InvalidMove {
+                row: 0,
+                col: 2,
+                seq: 4,
+                reason: GridPlacementError::CellInUse { row: 0, col: 2 },
+            }
+            .to_bytes()
+            .to_vec(),
+        ];
+        for pkt in packets {
+            let json = packet_to_json(&pkt, false).unwrap();
+            assert_eq!(packet_from_json(&json.to_string()).unwrap(), pkt);
+        }
+
+        let hello = ServerHello {
+            client_first: false,
+            client_mark: Mark::O,
+            token: None,
+        }
+        .to_bytes();
+        let json = packet_to_json(&hello, true).unwrap();
+        assert_eq!(json["type"], "server_hello");
+        assert_eq!(packet_from_json(&json.to_string()).unwrap(), hello);
+
+        assert!(packet_from_json(r#"{"type":"move","row":-1,"col":0,"seq":1}"#).is_err());
+        assert!(packet_from_json(r#"{"type":"unknown"}"#).is_err());
+        assert!(packet_from_json("not json").is_err());
+    }
+
+    #[test]
+    fn packets_keep_their_json_fields() {
+        let fixtures = [
+            (
+                Packet::ServerHello(ServerHello {
+                    client_first: true,
+                    client_mark: Mark::X,
+                    token: Some(0xab),
+                }),
+                json!({"type": "server_hello", "client_first": true, "client_mark": "X", "token": "00000000000000ab"}),
+            ),
+            (
+                Packet::EndOfGame(EndOfGame(GameResult::Draw(DrawReason::Agreement))),
+                json!({"type": "end_of_game", "result": {"draw": "agreement"}}),
+            ),
+            (
+                Packet::ClockUpdate(ClockUpdate {
+                    x: Duration::from_millis(1500),
+                    o: Duration::ZERO,
+                }),
+                json!({"type": "clock", "x_ms": 1500, "o_ms": 0}),
+            ),
+            (
+                Packet::InvalidMove(InvalidMove {
+                    row: 3,
+                    col: 0,
+                    seq: 1,
+                    reason: GridPlacementError::OutOfBounds { row: 3, col: 0 },
+                }),
+                json!({"type": "invalid_move", "row": 3, "col": 0, "seq": 1, "reason": "out_of_bounds"}),
+            ),
+        ];
+        for (pkt, expected) in fixtures {
+            assert_eq!(serde_json::to_value(&pkt).unwrap(), expected);
+            let parsed: Packet = serde_json::from_value(expected).unwrap();
+            assert_eq!(parsed.to_bytes(), pkt.to_bytes());
+        }
+        assert!(serde_json::to_value(Packet::DiscoveryProbe).is_err());
+    }
+
+    #[test]
+    fn host_negotiates_encoding_with_clients() {
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen_json());
+        let mut client = RemoteGame::connect_json(addr).expect("Error connecting to server");
+        let mut server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        server.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        server.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        assert_eq!(client.grid(), server.grid());
+
+        // Binary clients are still accepted
+        let server = ServerGame::bind("127.0.0.1:0", &ServerGameSettings::default())
+            .expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let handle = thread::spawn(move || server.listen_json());
+        let client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let server = handle
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+        assert_eq!(client.local_mark(), server.local_mark().opposite());
+    }
+
+    #[test]
+    fn scripts_can_play_over_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let mut script = BufReader::new(socket);
+            let mut line = String::new();
+            script.read_line(&mut line).unwrap();
+            assert_eq!(line, "{\"type\":\"client_hello\"}\n");
+            let hello = "{\"type\":\"server_hello\",\"client_first\":true,\"client_mark\":\"X\",\"token\":null}\n";
+            script.get_mut().write_all(hello.as_bytes()).unwrap();
+
+            line.clear();
+            script.read_line(&mut line).unwrap();
+            let value: Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(value, json!({"type": "move", "row": 2, "col": 1, "seq": 1}));
+        });
+
+        let mut client = RemoteGame::connect_json(addr).expect("Error connecting to script");
+        client.try_move(&shared(MockPlayer(2, 1))).unwrap();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Machine-readable output of `--json`, for scripts and bots driving the binary. Only built with the
+//! `json` feature.
+//!
+//! Games are reported as JSON objects on stdout, one per line, with their kind in the `event`
+//! field:
+//!
+//! ```text
+//! {"event":"game_started","rows":3,"cols":3,"first":"X"}
+//! {"event":"turn","mark":"X"}
+//! {"event":"move_played","mark":"X","move":"place","row":1,"col":1}
+//! {"event":"game_over","result":{"winner":"X","reason":"line"}}
+//! ```
+//!
+//! Human players still enter their moves on stdin after their `turn` event, and the prompts they
+//! would otherwise see are written to stderr.
+
+use std::{io, sync::Arc};
+
+use serde_json::{json, Value};
+use tictactoe::{
+    board::Board,
+    game::{
+        Finished, Game, GameResult, InProgress, NetworkedGame, NetworkedGameError, Turn, WinReason,
+    },
+    grid::Mark,
+    player::{LocalPlayer, Move, Player, StdinReader},
+};
+
+use crate::config::Config;
+
+/// Creates a human player reading their moves from stdin, with their prompts on stderr so stdout
+/// only holds events.
+pub fn local_player(config: &Config) -> LocalPlayer<StdinReader, io::Stderr> {
+    LocalPlayer::new(StdinReader::default(), io::stderr()).with_numbering(config.numbering())
+}
+
+/// Plays a local game until it ends, reporting it as events, and returns its result.
+pub fn local_game_loop<B: Board>(game: Game<InProgress, B>) -> Option<Game<Finished, B>> {
+    let (rows, cols) = game.board().dimensions();
+    let first = game.current_player().mark;
+    emit(json!({"event": "game_started", "rows": rows, "cols": cols, "first": first.to_string()}));
+    emit_turn(first);
+
+    let result = game.play_to_end_with(|turn| {
+        let history = match turn {
+            Turn::InProgress(game) => game.history(),
+            Turn::Finished(game) => game.history(),
+        };
+        if let Some(played) = history.last() {
+            emit_move(played.mark, played.player_move);
+        }
+        if let Turn::InProgress(game) = turn {
+            emit_turn(game.current_player().mark);
+        }
+    });
+    match result {
+        Ok(game) => {
+            emit_result(game.result());
+            Some(game)
+        }
+        Err(e) => {
+            emit_error(e);
+            None
+        }
+    }
+}
+
+/// Plays a networked game until it ends, reporting it as events, and declines the rematch.
+pub fn networked_game_loop(
+    game: &mut impl NetworkedGame,
+    player: &Arc<dyn Player>,
+) -> Result<GameResult, NetworkedGameError> {
+    game.set_chat_handler(Box::new(|message| {
+        emit(json!({"event": "chat", "message": message}))
+    }));
+    let local = game.local_mark();
+    let first = if game.is_local_turn() {
+        local
+    } else {
+        local.opposite()
+    };
+    let peer = game.peer();
+    emit(json!({
+        "event": "game_started",
+        "rows": 3,
+        "cols": 3,
+        "first": first.to_string(),
+        "local_mark": local.to_string(),
+        "opponent": {
+            "nickname": peer.nickname,
+            "address": peer.addr.map(|addr| addr.to_string()),
+            "protocol_version": peer.protocol_version,
+        },
+    }));
+
+    let result = loop {
+        if let Some(result) = game.result() {
+            break result;
+        }
+
+        let mark = if game.is_local_turn() {
+            local
+        } else {
+            local.opposite()
+        };
+        emit_turn(mark);
+        let before = *game.grid();
+        match game.try_move(player) {
+            Ok(()) => {}
+            Err(NetworkedGameError::Rejected(e)) => emit_error(e),
+            Err(e) => return Err(e),
+        }
+        // Moves aren't reported by networked games, so they are found on the grid
+        let placed = (0..9)
+            .map(|i| (i / 3, i % 3))
+            .find(|&(row, col)| before.mark(row, col) != game.grid().mark(row, col));
+        match (placed, game.result()) {
+            (Some((row, col)), _) => emit_move(mark, Move::Place(row, col)),
+            (
+                None,
+                Some(GameResult::Win {
+                    winner,
+                    reason: WinReason::Resignation,
+                }),
+            ) => emit_move(winner.opposite(), Move::Resign),
+            _ => {}
+        }
+    };
+
+    emit_result(result);
+    game.rematch(false)?;
+    Ok(result)
+}
+
+/// Reports an error that ended the game.
+pub fn emit_error(error: impl ToString) {
+    emit(json!({"event": "error", "message": error.to_string()}));
+}
+
+fn emit_turn(mark: Mark) {
+    emit(json!({"event": "turn", "mark": mark.to_string()}));
+}
+
+fn emit_move(mark: Mark, player_move: Move) {
+    let mut event = json!({"event": "move_played", "mark": mark.to_string()});
+    match player_move {
+        Move::Place(row, col) => {
+            event["move"] = json!("place");
+            event["row"] = json!(row);
+            event["col"] = json!(col);
+        }
+        Move::Resign => event["move"] = json!("resign"),
+        Move::OfferDraw => event["move"] = json!("offer_draw"),
+    }
+    emit(event);
+}
+
+/// Reports a result as `{"winner": <mark>, "reason": <reason>}`, or `{"draw": <reason>}`, like
+/// the JSON encoding of the network protocol.
+fn emit_result(result: GameResult) {
+    emit(json!({"event": "game_over", "result": result}));
+}
+
+fn emit(event: Value) {
+    println!("{}", event);
+}
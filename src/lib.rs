@@ -1,5 +1,52 @@
+pub mod analytics;
+pub mod art;
+#[cfg(feature = "async")]
+pub mod async_game;
+pub mod benchmark;
+pub mod board;
+pub mod calibration;
+pub mod engine;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod game;
 pub mod grid;
+pub mod hint;
+#[cfg(feature = "sqlite")]
+pub mod history;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "network")]
+pub mod lobby;
+#[cfg(feature = "noise")]
+pub mod noise;
+#[cfg(feature = "onnx")]
+pub mod onnx;
 pub mod player;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod qlearning;
+pub mod record;
+pub mod registry;
+pub mod search;
+#[cfg(feature = "network")]
+pub mod server;
+#[cfg(feature = "network")]
+pub mod spectator;
+pub mod tournament;
+pub mod training;
+pub mod tuning;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
+#[cfg(feature = "network")]
+mod discovery;
+mod error;
+#[cfg(feature = "network")]
 mod protocol;
+#[cfg(feature = "network")]
+mod trace;
+
+pub use self::error::Error;
@@ -0,0 +1,535 @@
+//! Client side of a [`DedicatedServer`](crate::server::DedicatedServer)'s lobby, where players can
+//! open games and choose which one to join, or join a room by its code. Players can also create an
+//! account on the server, see [`create_account`], to tie their games to it.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{TcpStream, ToSocketAddrs},
+    str::FromStr,
+};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    game::{self, RemoteGame},
+    protocol::{self, LobbyPacket},
+    server::{LeaderboardEntry, MAX_ROOM_CODE_LEN},
+};
+
+/// Game waiting for an opponent in a lobby.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenGame {
+    pub id: u32,
+    /// Name of the player who opened the game.
+    pub host: String,
+}
+
+#[derive(Debug)]
+pub enum LobbyError {
+    /// The server refused the request, with the reason it gave.
+    Refused(String),
+    Io(io::Error),
+}
+
+impl Display for LobbyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Refused(reason) => write!(f, "Server refused the request: {}", reason),
+            Self::Io(e) => write!(f, "IO error in lobby: {}", e),
+        }
+    }
+}
+
+impl Error for LobbyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Refused(_) => None,
+            Self::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for LobbyError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Name and token of an account on a dedicated server, see [`create_account`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub name: String,
+    pub token: String,
+}
+
+/// Creates the account `name` on the server at `addr`, and returns the credentials to log in to it
+/// with. Names are 1 to [`MAX_ACCOUNT_NAME_LEN`](crate::server::MAX_ACCOUNT_NAME_LEN) letters,
+/// digits, dashes or underscores, and the server refuses names that are invalid or taken.
+pub fn create_account<A: ToSocketAddrs>(addr: A, name: &str) -> Result<Credentials, LobbyError> {
+    let mut stream = BufReader::new(TcpStream::connect(addr)?);
+    send(&mut stream, &LobbyPacket::CreateAccount(name.to_owned()))?;
+    match receive(&mut stream)? {
+        LobbyPacket::AccountCreated(token) => Ok(Credentials {
+            name: name.to_owned(),
+            token,
+        }),
+        pkt => Err(unexpected_packet(pkt)),
+    }
+}
+
+/// Logs in to the account of `credentials` on a new connection to a server.
+fn log_in(stream: &mut BufReader<TcpStream>, credentials: &Credentials) -> Result<(), LobbyError> {
+    let login = LobbyPacket::Login {
+        name: credentials.name.clone(),
+        token: credentials.token.clone(),
+    };
+    send(stream, &login)?;
+    match receive(stream)? {
+        LobbyPacket::LoggedIn => Ok(()),
+        pkt => Err(unexpected_packet(pkt)),
+    }
+}
+
+/// Connection to a server's lobby. It ends once a game is created or joined, and the connection is
+/// then used to play that game.
+#[derive(Debug)]
+pub struct LobbyClient {
+    stream: BufReader<TcpStream>,
+}
+
+impl LobbyClient {
+    /// Connects to the server and enters its lobby as `name`.
+    pub fn connect<A: ToSocketAddrs>(addr: A, name: &str) -> io::Result<Self> {
+        let mut client = Self {
+            stream: BufReader::new(TcpStream::connect(addr)?),
+        };
+        client.send(&LobbyPacket::Register(name.to_owned()))?;
+        Ok(client)
+    }
+
+    /// Same as [`LobbyClient::connect`], logged in to the account of `credentials`: the lobby shows
+    /// the account's name, and the games played are tied to it.
+    pub fn login<A: ToSocketAddrs>(addr: A, credentials: &Credentials) -> Result<Self, LobbyError> {
+        let mut stream = BufReader::new(TcpStream::connect(addr)?);
+        log_in(&mut stream, credentials)?;
+        let mut client = Self { stream };
+        client.send(&LobbyPacket::Register(credentials.name.clone()))?;
+        Ok(client)
+    }
+
+    /// Returns the games currently waiting for an opponent.
+    pub fn list_games(&mut self) -> Result<Vec<OpenGame>, LobbyError> {
+        self.send(&LobbyPacket::ListGames)?;
+        match self.receive()? {
+            LobbyPacket::GameList(games) => Ok(games
+                .into_iter()
+                .map(|(id, host)| OpenGame { id, host })
+                .collect()),
+            pkt => Err(unexpected_packet(pkt)),
+        }
+    }
+
+    /// Returns the results of the server's accounts, best first. Only games played by players
+    /// logged in to an account are counted, see [`LobbyClient::login`].
+    pub fn leaderboard(&mut self) -> Result<Vec<LeaderboardEntry>, LobbyError> {
+        self.send(&LobbyPacket::GetLeaderboard)?;
+        match self.receive()? {
+            LobbyPacket::Leaderboard(entries) => Ok(entries),
+            pkt => Err(unexpected_packet(pkt)),
+        }
+    }
+
+    /// Opens a new game, which other players can see and join.
+    pub fn create_game(mut self) -> Result<PendingGame, LobbyError> {
+        self.send(&LobbyPacket::CreateGame)?;
+        match self.receive()? {
+            LobbyPacket::GameCreated(id) => Ok(PendingGame {
+                id,
+                stream: self.stream,
+            }),
+            pkt => Err(unexpected_packet(pkt)),
+        }
+    }
+
+    /// Joins the open game `id`. On failure, the client is returned along with the error, so
+    /// another game can be chosen.
+    pub fn join_game(mut self, id: u32) -> Result<RemoteGame, (Self, LobbyError)> {
+        if let Err(e) = self.send(&LobbyPacket::JoinGame(id)) {
+            return Err((self, e.into()));
+        }
+
+        let mut buf = vec![];
+        if let Err(e) = self.stream.read_until(protocol::TERMINATOR, &mut buf) {
+            return Err((self, e.into()));
+        }
+        let buf = match game::unframe_packet(buf) {
+            Ok(buf) => buf,
+            Err(e) => return Err((self, e.into())),
+        };
+        if let Ok(server_hello) = game::parse_server_hello(&buf) {
+            return Ok(RemoteGame::from_server_hello(self.stream, &server_hello).reconnecting());
+        }
+
+        let error = match parse_packet(&buf) {
+            Ok(pkt) => unexpected_packet(pkt),
+            Err(e) => e.into(),
+        };
+        Err((self, error))
+    }
+
+    fn send(&mut self, pkt: &LobbyPacket) -> io::Result<()> {
+        send(&mut self.stream, pkt)
+    }
+
+    fn receive(&mut self) -> io::Result<LobbyPacket> {
+        receive(&mut self.stream)
+    }
+}
+
+/// Game opened in a lobby, waiting for another player to join it.
+#[derive(Debug)]
+pub struct PendingGame {
+    id: u32,
+    stream: BufReader<TcpStream>,
+}
+
+impl PendingGame {
+    /// Identifier other players use to join the game.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Waits for another player to join, and starts the game.
+    pub fn wait(mut self) -> io::Result<RemoteGame> {
+        let mut buf = vec![];
+        self.stream.read_until(protocol::TERMINATOR, &mut buf)?;
+        let buf = game::unframe_packet(buf)?;
+        let server_hello = game::parse_server_hello(&buf)?;
+        Ok(RemoteGame::from_server_hello(self.stream, &server_hello).reconnecting())
+    }
+}
+
+const CODE_COLORS: [&str; 16] = [
+    "AMBER", "BLACK", "BLUE", "BRONZE", "CORAL", "CYAN", "GOLD", "GRAY", "GREEN", "IVORY", "LIME",
+    "PINK", "PLUM", "RED", "SILVER", "WHITE",
+];
+const CODE_ANIMALS: [&str; 16] = [
+    "BEAR", "CRAB", "CROW", "DEER", "DUCK", "EEL", "FOX", "FROG", "HAWK", "LION", "MOLE", "MOTH",
+    "OTTER", "SEAL", "TOAD", "WOLF",
+];
+
+/// Short code players share to meet, like `BLUE-FOX-42`: the room on a dedicated server (see
+/// [`join_room`]), or a game hosted on the local network (see
+/// [`ServerGameSettings::room_code`](crate::game::ServerGameSettings::room_code)).
+///
+/// Codes are parsed ignoring case, with spaces and underscores read as dashes, so `blue fox 42` is
+/// the same code. They are made of ASCII letters, digits and dashes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RoomCode(String);
+
+impl RoomCode {
+    /// Generates a code from a color, an animal and a number from 10 to 99.
+    pub fn random() -> Self {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    /// Same as [`RoomCode::random`], drawing the code from `rng`.
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        let color = CODE_COLORS.choose(rng).unwrap();
+        let animal = CODE_ANIMALS.choose(rng).unwrap();
+        Self(format!("{}-{}-{}", color, animal, rng.gen_range(10..100)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RoomCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for RoomCode {
+    type Err = RoomCodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s
+            .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+            .filter(|word| !word.is_empty())
+            .collect();
+        let code = words.join("-").to_ascii_uppercase();
+        if let Some(c) = code
+            .chars()
+            .find(|&c| !c.is_ascii_alphanumeric() && c != '-')
+        {
+            return Err(RoomCodeError::InvalidCharacter(c));
+        }
+        match code.len() {
+            0 => Err(RoomCodeError::Empty),
+            len if len > MAX_ROOM_CODE_LEN => Err(RoomCodeError::TooLong),
+            _ => Ok(Self(code)),
+        }
+    }
+}
+
+/// Error returned when parsing a [`RoomCode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomCodeError {
+    Empty,
+    /// The code is longer than the server accepts, see [`MAX_ROOM_CODE_LEN`].
+    TooLong,
+    /// The code contains something else than letters, digits, dashes, underscores and spaces.
+    InvalidCharacter(char),
+}
+
+impl Display for RoomCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Room codes can't be empty"),
+            Self::TooLong => write!(
+                f,
+                "Room codes are limited to {} characters",
+                MAX_ROOM_CODE_LEN
+            ),
+            Self::InvalidCharacter(c) => write!(
+                f,
+                "Invalid character '{}' in room code, expected letters, digits and dashes",
+                c
+            ),
+        }
+    }
+}
+
+impl Error for RoomCodeError {}
+
+/// Joins the room `code` on the server, and waits for another player to join it with the same code
+/// to start the game. The first player in the room plays `X` and moves first.
+///
+/// Both players connect out to the server, so neither has to accept connections: the server can
+/// relay games between players behind NAT.
+pub fn join_room<A: ToSocketAddrs>(addr: A, code: &str) -> Result<RemoteGame, LobbyError> {
+    enter_room(BufReader::new(TcpStream::connect(addr)?), code)
+}
+
+/// Same as [`join_room`], logged in to the account of `credentials`, so the game is tied to it.
+pub fn join_room_as<A: ToSocketAddrs>(
+    addr: A,
+    code: &str,
+    credentials: &Credentials,
+) -> Result<RemoteGame, LobbyError> {
+    let mut stream = BufReader::new(TcpStream::connect(addr)?);
+    log_in(&mut stream, credentials)?;
+    enter_room(stream, code)
+}
+
+fn enter_room(mut stream: BufReader<TcpStream>, code: &str) -> Result<RemoteGame, LobbyError> {
+    send(&mut stream, &LobbyPacket::JoinRoom(code.to_owned()))?;
+
+    let mut buf = vec![];
+    stream.read_until(protocol::TERMINATOR, &mut buf)?;
+    let buf = game::unframe_packet(buf)?;
+    if let Ok(server_hello) = game::parse_server_hello(&buf) {
+        return Ok(RemoteGame::from_server_hello(stream, &server_hello).reconnecting());
+    }
+    Err(unexpected_packet(parse_packet(&buf)?))
+}
+
+fn send(stream: &mut BufReader<TcpStream>, pkt: &LobbyPacket) -> io::Result<()> {
+    stream.get_mut().write_all(&pkt.to_bytes())?;
+    stream.get_mut().flush()
+}
+
+fn receive(stream: &mut BufReader<TcpStream>) -> io::Result<LobbyPacket> {
+    let mut buf = vec![];
+    stream.read_until(protocol::TERMINATOR, &mut buf)?;
+    parse_packet(&game::unframe_packet(buf)?)
+}
+
+fn parse_packet(buf: &[u8]) -> io::Result<LobbyPacket> {
+    LobbyPacket::try_from(buf).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+fn unexpected_packet(pkt: LobbyPacket) -> LobbyError {
+    match pkt {
+        LobbyPacket::Error(reason) => LobbyError::Refused(reason),
+        pkt => io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Unexpected lobby packet: {:?}", pkt),
+        )
+        .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::{
+        game::NetworkedGame,
+        grid::Mark,
+        player::{tests::shared, Move, ScriptedPlayer},
+        server::DedicatedServer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn lobby_clients_join_chosen_game() {
+        let server = DedicatedServer::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        thread::spawn(move || server.run());
+
+        let alice = LobbyClient::connect(addr, "Alice").expect("Error entering lobby");
+        let pending = alice.create_game().expect("Error creating game");
+        let id = pending.id();
+        let host = thread::spawn(move || pending.wait());
+
+        let mut bob = LobbyClient::connect(addr, "Bob").expect("Error entering lobby");
+        let games = bob.list_games().expect("Error listing games");
+        assert_eq!(
+            games,
+            [OpenGame {
+                id,
+                host: "Alice".to_owned()
+            }]
+        );
+
+        let (bob, err) = bob.join_game(id + 1).unwrap_err();
+        assert!(matches!(err, LobbyError::Refused(_)));
+        let bob = bob.join_game(id).expect("Error joining game");
+        let alice = host.join().unwrap().expect("Error starting game");
+
+        assert!(alice.is_local_turn());
+        assert_eq!(alice.local_mark(), Mark::X);
+        assert!(!bob.is_local_turn());
+        assert_eq!(bob.local_mark(), Mark::O);
+    }
+
+    #[test]
+    fn clients_in_same_room_play_together() {
+        let server = DedicatedServer::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        thread::spawn(move || server.run());
+
+        let alice = thread::spawn(move || join_room(addr, "blue-otter"));
+        thread::sleep(Duration::from_millis(50));
+        // Clients in other rooms are not paired with Alice
+        let carol = thread::spawn(move || join_room(addr, "red-fox"));
+        thread::sleep(Duration::from_millis(50));
+        let bob = join_room(addr, "blue-otter").expect("Error joining room");
+        let alice = alice.join().unwrap().expect("Error joining room");
+
+        assert!(alice.is_local_turn());
+        assert_eq!(alice.local_mark(), Mark::X);
+        assert!(!bob.is_local_turn());
+        assert_eq!(bob.local_mark(), Mark::O);
+        assert!(!carol.is_finished());
+
+        let err = join_room(addr, "").unwrap_err();
+        assert!(matches!(err, LobbyError::Refused(_)));
+    }
+
+    #[test]
+    fn accounts_identify_clients() {
+        let server = DedicatedServer::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let admin = server.admin();
+        thread::spawn(move || server.run());
+
+        let alice = create_account(addr, "alice").expect("Error creating account");
+        let err = create_account(addr, "alice").unwrap_err();
+        assert!(matches!(err, LobbyError::Refused(_)));
+        assert_eq!(admin.accounts(), ["alice"]);
+
+        // The lobby lists the account's name, whatever name is registered
+        let pending = LobbyClient::login(addr, &alice)
+            .and_then(LobbyClient::create_game)
+            .expect("Error creating game");
+        let mut bob = LobbyClient::connect(addr, "Bob").expect("Error entering lobby");
+        let games = bob.list_games().expect("Error listing games");
+        assert_eq!(games[0].host, "alice");
+        drop(pending);
+
+        // Only clients logged in to the account can use its name
+        let mut impostor = LobbyClient::connect(addr, "alice").expect("Error entering lobby");
+        let err = impostor.list_games().unwrap_err();
+        assert!(matches!(err, LobbyError::Refused(_)));
+
+        let wrong = Credentials {
+            name: "alice".to_owned(),
+            token: "wrong".to_owned(),
+        };
+        let err = join_room_as(addr, "blue-otter", &wrong).unwrap_err();
+        assert!(matches!(err, LobbyError::Refused(_)));
+
+        let room = thread::spawn(move || join_room_as(addr, "blue-otter", &alice));
+        thread::sleep(Duration::from_millis(50));
+        let _bob = join_room(addr, "blue-otter").expect("Error joining room");
+        let _alice = room.join().unwrap().expect("Error joining room");
+        let games = admin.games();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].account_x.as_deref(), Some("alice"));
+        assert_eq!(games[0].account_o, None);
+    }
+
+    #[test]
+    fn leaderboard_counts_account_games() {
+        let server = DedicatedServer::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        thread::spawn(move || server.run());
+
+        let alice = create_account(addr, "alice").expect("Error creating account");
+        let bob = create_account(addr, "bob").expect("Error creating account");
+        let pending = LobbyClient::login(addr, &alice)
+            .and_then(LobbyClient::create_game)
+            .expect("Error creating game");
+        let id = pending.id();
+        let host = thread::spawn(move || pending.wait());
+        let mut bob = LobbyClient::login(addr, &bob)
+            .expect("Error entering lobby")
+            .join_game(id)
+            .expect("Error joining game");
+        let mut alice = host.join().unwrap().expect("Error starting game");
+
+        alice
+            .try_move(&shared(ScriptedPlayer::new([Move::Place(1, 1)])))
+            .expect("Error playing move");
+        // Bob receives Alice's move, then resigns
+        let resign = shared(ScriptedPlayer::new([Move::Resign]));
+        while bob.result().is_none() {
+            bob.try_move(&resign).expect("Error resigning");
+        }
+        thread::sleep(Duration::from_millis(50));
+
+        let mut carol = LobbyClient::connect(addr, "Carol").expect("Error entering lobby");
+        let leaderboard = carol.leaderboard().expect("Error getting leaderboard");
+        let results: Vec<_> = leaderboard
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.wins, entry.draws, entry.losses))
+            .collect();
+        assert_eq!(results, [("alice", 1, 0, 0), ("bob", 0, 0, 1)]);
+    }
+
+    #[test]
+    fn room_codes_are_normalized() {
+        let code: RoomCode = " blue fox_42 ".parse().unwrap();
+        assert_eq!(code.as_str(), "BLUE-FOX-42");
+        assert_eq!("BLUE--FOX-42".parse(), Ok(code));
+        assert_eq!("  ".parse::<RoomCode>(), Err(RoomCodeError::Empty));
+        assert_eq!(
+            "blue.fox".parse::<RoomCode>(),
+            Err(RoomCodeError::InvalidCharacter('.'))
+        );
+        assert_eq!(
+            "A".repeat(33).parse::<RoomCode>(),
+            Err(RoomCodeError::TooLong)
+        );
+
+        let generated = RoomCode::random();
+        assert_eq!(generated.to_string().parse(), Ok(generated));
+    }
+}
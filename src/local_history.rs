@@ -0,0 +1,314 @@
+//! Games played on this machine, read by `tictactoe stats`. Each game against a bot or a remote
+//! player is appended as a line of `$XDG_DATA_HOME/tictactoe/history`, or
+//! `~/.local/share/tictactoe/history`. With the `sqlite` feature, games are saved with their moves
+//! in the `history.db` database of the same directory instead, see [`tictactoe::history`]. Games
+//! without exactly one human playing on this machine aren't recorded, since they have no side to
+//! take.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+#[cfg(not(feature = "sqlite"))]
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    time::UNIX_EPOCH,
+};
+
+#[cfg(feature = "sqlite")]
+use tictactoe::history::{GameHistory, RecordedGame};
+
+use tictactoe::{
+    game::{GameResult, PlayedMove},
+    grid::Mark,
+    player::BotPlayerDifficulty,
+};
+
+use crate::cli::{self, PlayerSpec};
+
+/// Who the local player played against.
+#[derive(Debug, Clone, Copy)]
+pub enum Opponent {
+    Remote,
+    Bot(BotPlayerDifficulty),
+}
+
+impl Opponent {
+    /// Returns the mark of the only human and their opponent in a local game between `x` and `o`,
+    /// or `None` if both or neither are human.
+    pub fn of_local_game(x: PlayerSpec, o: PlayerSpec) -> Option<(Mark, Self)> {
+        match (x, o) {
+            (PlayerSpec::Human, PlayerSpec::Bot { difficulty, .. }) => {
+                Some((Mark::X, Self::Bot(difficulty)))
+            }
+            (PlayerSpec::Bot { difficulty, .. }, PlayerSpec::Human) => {
+                Some((Mark::O, Self::Bot(difficulty)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses an opponent written by its `Display` implementation.
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "remote" => Some(Self::Remote),
+            bot => Some(Self::Bot(
+                cli::parse_difficulty(bot.strip_prefix("bot:")?).ok()?,
+            )),
+        }
+    }
+}
+
+/// Written like bot players on the command line, such as `bot:impossible` or `bot:70`.
+impl Display for Opponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Remote => write!(f, "remote"),
+            Self::Bot(difficulty) => write!(f, "bot:{}", cli::format_difficulty(*difficulty)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Outcome {
+    /// Returns the outcome of `result` for the player of `mark`.
+    pub fn of(result: GameResult, mark: Mark) -> Self {
+        match result {
+            GameResult::Win { winner, .. } if winner == mark => Self::Win,
+            GameResult::Win { .. } => Self::Loss,
+            GameResult::Draw(_) => Self::Draw,
+        }
+    }
+}
+
+/// Game of the history, from the local player's side.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub opponent: Opponent,
+    pub outcome: Outcome,
+}
+
+#[cfg(not(feature = "sqlite"))]
+impl Entry {
+    /// Parses a line written by [`record`]: the time the game ended at in seconds since the Unix
+    /// epoch, the local player's mark, the outcome and the opponent.
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split(' ');
+        fields.next()?.parse::<u64>().ok()?;
+        if !matches!(fields.next()?, "X" | "O") {
+            return None;
+        }
+        let outcome = match fields.next()? {
+            "win" => Outcome::Win,
+            "loss" => Outcome::Loss,
+            "draw" => Outcome::Draw,
+            _ => return None,
+        };
+        let opponent = Opponent::parse(fields.next()?)?;
+        fields
+            .next()
+            .is_none()
+            .then_some(Self { opponent, outcome })
+    }
+}
+
+/// Adds a game that just ended to the history. Its `moves` and start time are only kept in the
+/// database of the `sqlite` feature.
+#[cfg(not(feature = "sqlite"))]
+pub fn record(
+    mark: Mark,
+    opponent: Opponent,
+    result: GameResult,
+    _moves: &[PlayedMove],
+    _started_at: SystemTime,
+) -> Result<(), String> {
+    let path = create_path("history")?;
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let outcome = match Outcome::of(result, mark) {
+        Outcome::Win => "win",
+        Outcome::Loss => "loss",
+        Outcome::Draw => "draw",
+    };
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{} {} {} {}", time, mark, outcome, opponent))
+        .map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
+
+/// Reads every game of the history, oldest first. A missing history has no games.
+#[cfg(not(feature = "sqlite"))]
+pub fn load() -> Result<Vec<Entry>, String> {
+    let Some(path) = path("history") else {
+        return Ok(vec![]);
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("Error reading {}: {}", path.display(), e)),
+    };
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            Entry::parse(line)
+                .ok_or_else(|| format!("Invalid game on line {} of {}", i + 1, path.display()))
+        })
+        .collect()
+}
+
+/// Name of the player of this machine in the database, the other player being an [`Opponent`].
+#[cfg(feature = "sqlite")]
+const LOCAL_PLAYER: &str = "local";
+
+/// Adds a game that just ended to the history, along with its `moves` if they are known.
+#[cfg(feature = "sqlite")]
+pub fn record(
+    mark: Mark,
+    opponent: Opponent,
+    result: GameResult,
+    moves: &[PlayedMove],
+    started_at: SystemTime,
+) -> Result<(), String> {
+    let path = create_path("history.db")?;
+    let opponent = opponent.to_string();
+    let (player_x, player_o) = match mark {
+        Mark::X => (LOCAL_PLAYER.to_owned(), opponent),
+        _ => (opponent, LOCAL_PLAYER.to_owned()),
+    };
+    let game = RecordedGame {
+        player_x,
+        player_o,
+        moves: moves.to_vec(),
+        result,
+        started_at,
+        ended_at: SystemTime::now(),
+    };
+    GameHistory::open(&path)
+        .and_then(|mut history| history.record(&game))
+        .map(drop)
+        .map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
+
+/// Reads every game of the history, oldest first. A missing history has no games.
+#[cfg(feature = "sqlite")]
+pub fn load() -> Result<Vec<Entry>, String> {
+    let Some(path) = path("history.db").filter(|path| path.exists()) else {
+        return Ok(vec![]);
+    };
+    let games = GameHistory::open(&path)
+        .and_then(|history| history.games())
+        .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+    games
+        .into_iter()
+        .map(|(id, game)| {
+            let sides = match (game.player_x.as_str(), game.player_o.as_str()) {
+                (LOCAL_PLAYER, opponent) => Some((Mark::X, opponent)),
+                (opponent, LOCAL_PLAYER) => Some((Mark::O, opponent)),
+                _ => None,
+            };
+            let (mark, opponent) = sides
+                .and_then(|(mark, opponent)| Some((mark, Opponent::parse(opponent)?)))
+                .ok_or_else(|| format!("Invalid game {} in {}", id, path.display()))?;
+            Ok(Entry {
+                opponent,
+                outcome: Outcome::of(game.result, mark),
+            })
+        })
+        .collect()
+}
+
+/// Totals of a set of games.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Totals {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl Totals {
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Loss => self.losses += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Returns the percentage of games won.
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 * 100.0 / self.games().max(1) as f64
+    }
+}
+
+/// Statistics of the history.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub totals: Totals,
+    /// Totals against each opponent, keyed by how the opponent is written
+    pub by_opponent: BTreeMap<String, Totals>,
+    pub longest_win_streak: usize,
+    pub longest_loss_streak: usize,
+}
+
+impl Stats {
+    pub fn new(entries: &[Entry]) -> Self {
+        let mut stats = Self::default();
+        let mut streak = (Outcome::Draw, 0);
+        for entry in entries {
+            stats.totals.add(entry.outcome);
+            stats
+                .by_opponent
+                .entry(entry.opponent.to_string())
+                .or_default()
+                .add(entry.outcome);
+
+            streak = match streak {
+                (outcome, n) if outcome == entry.outcome => (outcome, n + 1),
+                _ => (entry.outcome, 1),
+            };
+            match streak {
+                (Outcome::Win, n) => stats.longest_win_streak = stats.longest_win_streak.max(n),
+                (Outcome::Loss, n) => stats.longest_loss_streak = stats.longest_loss_streak.max(n),
+                (Outcome::Draw, _) => {}
+            }
+        }
+        stats
+    }
+}
+
+/// Returns the path of the data file `name`, such as the history.
+pub fn path(name: &str) -> Option<PathBuf> {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(data_dir.join("tictactoe").join(name))
+}
+
+/// Returns the path of the data file `name`, creating its directory if needed.
+pub fn create_path(name: &str) -> Result<PathBuf, String> {
+    let path = path(name).ok_or_else(|| "No home directory".to_owned())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Error creating {}: {}", dir.display(), e))?;
+    }
+    Ok(path)
+}
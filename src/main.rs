@@ -1,21 +1,259 @@
+use std::{
+    fs,
+    io::{self, ErrorKind, Write},
+    path::Path,
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use clap::Parser;
+
 use tictactoe::{
-    game::{Game, NetworkedGame, RemoteGame, ServerGame},
-    player::{self, BotPlayerDifficulty, LocalPlayer, Player},
+    analytics::Analysis,
+    art::LargeBoard,
+    board::{Board, SquareBoard},
+    calibration::{self, difficulty_name, DIFFICULTY_RATINGS},
+    game::{
+        DrawReason, Finished, Game, GameBuilder, GameResult, GameSnapshot, InProgress,
+        Introduction, NetworkedGame, NetworkedGameError, PlayedMove, RematchPolicy, RemoteGame,
+        ServerGame, ServerGameSettings, Turn, WinReason,
+    },
+    grid::{Grid, Mark},
+    hint,
+    lobby::{self, Credentials, LobbyClient, RoomCode},
+    player::{
+        BotPersonality, BotPlayer, BotPlayerDifficulty, CellNumbering, LocalPlayer, MirrorPlayer,
+        Move, Player, PlayerError, StdinReader,
+    },
+    record::GameRecord,
+    server::LeaderboardEntry,
+    spectator::{Spectator, SpectatorEvent},
+    tournament::{Tournament, TournamentFormat},
+    training::{self, ExportFormat, SelfPlay},
+    tuning::{HeuristicWeights, TunedBot, Tuner},
 };
 
+mod cli;
+mod config;
+#[cfg(feature = "json")]
+mod json_output;
+mod local_history;
+mod symbols;
+#[cfg(feature = "tui")]
+mod tui;
 mod utils;
 
+use cli::{Cli, Command, First, NetworkArgs, PlayerArgs, PlayerSpec, Position};
+use config::Config;
+use local_history::{Opponent, Stats};
+use symbols::Symbols;
+
+/// Number of bots created so far, whose seeds are derived from `--seed`
+static BOTS_CREATED: AtomicU64 = AtomicU64::new(0);
+
+/// Data file holding the weights of tuned bots
+const TUNED_WEIGHTS: &str = "tuned-weights";
+
+/// Default path used to save interrupted networked games
+const SAVE_FILE: &str = "tictactoe.save";
+
 fn main() {
-    let game_type = prompt_game_type("What type of game do you wish to play?");
+    let cli = Cli::parse();
+    let mut config = Config::load(cli.config.as_deref()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    if cli.ascii {
+        config.unicode = false;
+    }
+    if cli.color || cli.no_color {
+        config.color = cli.color;
+    }
+    config.tui |= cli.tui;
+    config.numpad |= cli.numpad;
+    config.large |= cli.large;
+    config.annotate |= cli.annotate;
+    config.seed = cli.seed;
+    config.export = cli.export;
+    if cli.x_symbol.is_some() || cli.o_symbol.is_some() {
+        let x = cli
+            .x_symbol
+            .unwrap_or_else(|| config.symbols.get(Mark::X).to_owned());
+        let o = cli
+            .o_symbol
+            .unwrap_or_else(|| config.symbols.get(Mark::O).to_owned());
+        config.symbols = Symbols::new(x, o).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+    }
+    config.json = cli.json;
+    config.tui &= !config.json;
+    if config.tui && !cfg!(feature = "tui") {
+        eprintln!("This build doesn't include the full-screen UI, build it with --features tui");
+        process::exit(1);
+    }
+    if config.json && !cfg!(feature = "json") {
+        eprintln!("This build doesn't include the JSON output, build it with --features json");
+        process::exit(1);
+    }
+    if config.json && cli.command.is_none() {
+        eprintln!("--json needs the type of game, such as `tictactoe --json local`");
+        process::exit(1);
+    }
+    let command = cli
+        .command
+        .unwrap_or_else(|| prompt_command("What type of game do you wish to play?"));
+    if let Command::Stats = command {
+        return show_stats();
+    }
+    // Samples written to the standard output aren't followed by anything else
+    if let Command::TrainingData {
+        games,
+        exploration,
+        format,
+        output,
+    } = &command
+    {
+        return export_training_data(*games, *exploration, *format, output.as_deref(), &config);
+    }
+    if config.json && matches!(command, Command::Tutorial { .. }) {
+        eprintln!("The tutorial explains moves as text, it can't be played with --json");
+        process::exit(1);
+    }
+    if config.tui && matches!(command, Command::Tutorial { .. }) {
+        println!("The full-screen UI doesn't explain moves, showing the tutorial as text instead.");
+    }
+    if config.tui
+        && matches!(
+            command,
+            Command::ThreePlayer { .. } | Command::Teams { .. } | Command::Spectate { .. }
+        )
+    {
+        println!("The full-screen UI only plays 2 player games, showing this one as text instead.");
+    }
 
     loop {
-        match game_type {
-            GameType::Local => play_local_game(),
-            GameType::Remote => play_remote_game(),
-            GameType::Host => play_hosted_game(),
+        match &command {
+            Command::Local {
+                players,
+                pass_and_play,
+            } => play_local_game(players, *pass_and_play, &config),
+            Command::ThreePlayer { players, y } => play_three_player_game(players, *y, &config),
+            Command::Teams {
+                team_x,
+                team_o,
+                first,
+            } => play_team_game(team_x.as_deref(), team_o.as_deref(), *first, &config),
+            Command::Join {
+                address,
+                password,
+                code,
+                network,
+            } => play_remote_game(
+                address.clone(),
+                password.clone(),
+                code.clone(),
+                *network,
+                &config,
+            ),
+            Command::Host {
+                bind,
+                mark,
+                second,
+                rematch,
+                announce,
+                no_announce,
+                wait,
+                password,
+                code,
+                network,
+            } => {
+                let code = code
+                    .clone()
+                    .map(|code| code.unwrap_or_else(RoomCode::random));
+                let mut settings = ServerGameSettings::default()
+                    .with_host_plays_first(!second)
+                    .with_host_mark(*mark)
+                    .with_rematch_policy(*rematch)
+                    .with_room_code(code);
+                settings.nickname = config.nickname.clone();
+                settings.password = password.clone();
+                // Players given a room code don't need the game listed
+                let announce = (*announce || *no_announce || settings.room_code.is_some())
+                    .then_some(*announce);
+                let bind = bind.clone().or_else(|| config.bind.clone());
+                play_hosted_game(bind, settings, announce, *wait, *network, &config)
+            }
+            Command::Resume {
+                file,
+                host,
+                join,
+                network,
+            } => play_resumed_game(file.clone(), host.clone(), join.clone(), *network, &config),
+            Command::Lobby {
+                address,
+                name,
+                network,
+            } => {
+                let name = name.clone().or_else(|| config.nickname.clone());
+                play_lobby_game(address.clone(), name, *network, &config)
+            }
+            Command::Account { address, name } => create_account(address.clone(), name.clone()),
+            Command::Spectate { address } => spectate_game(address.clone(), &config),
+            Command::Room {
+                address,
+                code,
+                network,
+            } => play_room_game(address.clone(), code.clone(), *network, &config),
+            Command::Watch { x, o, first, delay } => {
+                watch_bot_game(*x, *o, *first, Duration::from_millis(*delay), &config)
+            }
+            Command::Tutorial { bot, first } => play_tutorial(*bot, *first, &config),
+            Command::Replay { file, game } => replay_game(file, *game, &config),
+            Command::Analyze { position } => analyze_position(position, &config),
+            Command::Tournament {
+                bots,
+                games,
+                knockout,
+                stats,
+            } => play_tournament(bots, *games, *knockout, *stats, &config),
+            Command::Calibrate {
+                difficulties,
+                games,
+            } => calibrate_bots(difficulties, *games, &config),
+            Command::Tune {
+                generations,
+                candidates,
+                games,
+                output,
+            } => tune_bot(
+                *generations,
+                *candidates,
+                *games,
+                output.as_deref(),
+                &config,
+            ),
+            Command::Stats | Command::TrainingData { .. } => unreachable!(),
         }
 
-        if matches!(game_type, GameType::Local) {
+        // Scripts driving a JSON game start another one by running the binary again
+        if config.json {
+            return;
+        }
+        if matches!(
+            command,
+            Command::Local { .. }
+                | Command::ThreePlayer { .. }
+                | Command::Teams { .. }
+                | Command::Watch { .. }
+                | Command::Tutorial { .. }
+        ) {
             if !utils::read_bool("Do you want to play again?", false) {
                 println!("Goodbye!");
                 return;
@@ -27,129 +265,1483 @@ fn main() {
     }
 }
 
-enum GameType {
-    Local,
-    Remote,
-    Host,
+/// Sets up a 2 player game on the classic grid. With `pass_and_play`, the screen is cleared between
+/// the turns of two human players.
+fn play_local_game(players: &PlayerArgs, pass_and_play: bool, config: &Config) {
+    #[cfg(feature = "tui")]
+    if config.tui {
+        return play_tui_local_game(players, config);
+    }
+
+    let spec_x = select_player_spec(players.x, "Select the player type for X", config);
+    let spec_o = select_player_spec(players.o, "Select the player type for O", config);
+    let builder = Game::builder()
+        .player_x(create_player(spec_x, config))
+        .player_o(create_player(spec_o, config));
+    let builder = select_first_mark(builder, &[Mark::X, Mark::O], players.first, config);
+    let pass_and_play =
+        pass_and_play && matches!((spec_x, spec_o), (PlayerSpec::Human, PlayerSpec::Human));
+    let started_at = SystemTime::now();
+    let game = local_game_loop(
+        builder.build().expect("Error creating game"),
+        pass_and_play,
+        config,
+    );
+    if let Some(game) = &game {
+        print_annotations(game.history(), config);
+        export_game(game, spec_x, spec_o, config);
+    }
+    if let (Some(game), Some((mark, opponent))) = (game, Opponent::of_local_game(spec_x, spec_o)) {
+        record_game(mark, opponent, game.result(), game.history(), started_at);
+    }
+}
+
+/// Sets up a 2 player game on the classic grid, played in the full-screen UI
+#[cfg(feature = "tui")]
+fn play_tui_local_game(players: &PlayerArgs, config: &Config) {
+    let spec_x = select_player_spec(players.x, "Select the player type for X", config);
+    let spec_o = select_player_spec(players.o, "Select the player type for O", config);
+    let builder = Game::builder();
+    let builder = select_first_mark(builder, &[Mark::X, Mark::O], players.first, config);
+
+    let started_at = SystemTime::now();
+    let result = tui::Tui::new(false, config.numbering(), config.symbols.clone()).and_then(|tui| {
+        let player = |spec| -> Box<dyn Player> {
+            match spec {
+                PlayerSpec::Human => Box::new(tui::TuiPlayer::new(tui.clone(), true)),
+                spec => create_player(spec, config),
+            }
+        };
+        let game = builder
+            .player_x(player(spec_x))
+            .player_o(player(spec_o))
+            .build()
+            .expect("Error creating game");
+        match tui::local_game_loop(game, &tui) {
+            Ok(game) => {
+                tui.lock().unwrap().wait_key("Press any key to continue.")?;
+                Ok(game)
+            }
+            Err(e) => Err(std::io::Error::other(e)),
+        }
+    });
+    let game = match result {
+        Ok(game) => game,
+        Err(e) => return println!("Error while playing: {}", e),
+    };
+    print_annotations(game.history(), config);
+    export_game(&game, spec_x, spec_o, config);
+    if let Some((mark, opponent)) = Opponent::of_local_game(spec_x, spec_o) {
+        record_game(mark, opponent, game.result(), game.history(), started_at)
+    }
+}
+
+/// Sets up a game against a bot on the classic grid, where the human player is warned about their
+/// mistakes and told about the threats on the board
+fn play_tutorial(bot: Option<PlayerSpec>, first: Option<First>, config: &Config) {
+    let bot = bot.unwrap_or_else(|| PlayerSpec::Bot {
+        difficulty: config
+            .bot_difficulty
+            .unwrap_or_else(prompt_bot_difficulty_selection),
+        personality: prompt_bot_personality_selection(),
+    });
+    println!("You play X. Type \"hint\" for the best move, or enter a cell to play it.");
+    println!("Moves making your outcome worse are explained before you confirm them.");
+    let builder = Game::builder()
+        .player_x(Box::new(local_player(config).with_tutor(true)))
+        .player_o(create_player(bot, config));
+    let builder = select_first_mark(builder, &[Mark::X, Mark::O], first, config);
+    let started_at = SystemTime::now();
+    let game = local_game_loop(builder.build().expect("Error creating game"), false, config);
+    if let Some(game) = &game {
+        print_annotations(game.history(), config);
+        export_game(game, PlayerSpec::Human, bot, config);
+    }
+    if let (Some(game), Some((mark, opponent))) =
+        (game, Opponent::of_local_game(PlayerSpec::Human, bot))
+    {
+        record_game(mark, opponent, game.result(), game.history(), started_at);
+    }
+}
+
+/// Sets up a game between two bots on the classic grid, each waiting `delay` before moving so the
+/// game can be followed
+fn watch_bot_game(
+    x: Option<PlayerSpec>,
+    o: Option<PlayerSpec>,
+    first: Option<First>,
+    delay: Duration,
+    config: &Config,
+) {
+    let select_bot = |spec: Option<PlayerSpec>, mark: Mark| {
+        spec.unwrap_or_else(|| {
+            println!("Select the bot playing {}", mark);
+            PlayerSpec::Bot {
+                difficulty: prompt_bot_difficulty_selection(),
+                personality: prompt_bot_personality_selection(),
+            }
+        })
+    };
+    let delayed = |spec| -> Box<dyn Player> {
+        Box::new(DelayedPlayer {
+            player: create_player(spec, config),
+            delay,
+        })
+    };
+    let spec_x = select_bot(x, Mark::X);
+    let spec_o = select_bot(o, Mark::O);
+    let builder = Game::builder()
+        .player_x(delayed(spec_x))
+        .player_o(delayed(spec_o));
+    let builder = select_first_mark(builder, &[Mark::X, Mark::O], first, config);
+    let game = builder.build().expect("Error creating game");
+
+    #[cfg(feature = "tui")]
+    if config.tui {
+        let result =
+            tui::Tui::new(false, config.numbering(), config.symbols.clone()).and_then(|tui| {
+                let game = tui::local_game_loop(game, &tui).map_err(std::io::Error::other)?;
+                tui.lock().unwrap().wait_key("Press any key to continue.")?;
+                Ok(game)
+            });
+        match result {
+            Ok(game) => {
+                print_annotations(game.history(), config);
+                export_game(&game, spec_x, spec_o, config);
+            }
+            Err(e) => println!("Error while playing: {}", e),
+        }
+        return;
+    }
+
+    if let Some(game) = local_game_loop(game, false, config) {
+        print_annotations(game.history(), config);
+        export_game(&game, spec_x, spec_o, config);
+    }
+}
+
+/// Plays a tournament between `bots`, each pair playing `games` games, and prints the standings,
+/// followed by the statistics of each bot if `stats` is set
+fn play_tournament(
+    bots: &[PlayerSpec],
+    games: usize,
+    knockout: bool,
+    stats: bool,
+    config: &Config,
+) {
+    let format = if knockout {
+        TournamentFormat::Knockout { games }
+    } else {
+        TournamentFormat::RoundRobin { games }
+    };
+    let mut tournament = Tournament::new(format);
+    let mut entered: Vec<String> = Vec::new();
+    for &spec in bots {
+        // The same bot can enter more than once, so repeated names get a number
+        let base = spec.to_string();
+        let copies = entered.iter().filter(|&n| *n == base).count();
+        let name = match copies {
+            0 => base.clone(),
+            n => format!("{} #{}", base, n + 1),
+        };
+        entered.push(base);
+
+        let seed = config.seed;
+        tournament.add_player(name, move || match spec {
+            PlayerSpec::Bot {
+                difficulty,
+                personality,
+            } => Box::new(create_bot(difficulty, personality, seed)),
+            PlayerSpec::Mirror => Box::new(create_mirror(seed)),
+            PlayerSpec::Tuned => Box::new(create_tuned(seed)),
+            PlayerSpec::Human => unreachable!("Tournament entrants are parsed as bots"),
+        });
+    }
+
+    let mut analysis = Analysis::for_tournament(&tournament);
+    let results = tournament.run_with(|x, o, game| {
+        if stats {
+            analysis.add_game(x, o, game);
+        }
+    });
+    match results {
+        Ok(results) => {
+            print!("{}", results);
+            if stats {
+                print!("\nStatistics:\n{}", analysis);
+            }
+        }
+        Err(e) => println!("Error while playing the tournament: {}", e),
+    }
 }
 
-/// Game loop: Plays a game until there's a winner or there's a draw
-fn play_local_game() {
-    let player_x = prompt_player_selection("Select the player type for X");
-    let player_y = prompt_player_selection("Select the player type for O");
-    let mut game = Game::new(player_x, player_y);
+/// Rates `difficulties`, or the fixed difficulties if empty, and prints their ratings.
+fn calibrate_bots(difficulties: &[BotPlayerDifficulty], games: usize, config: &Config) {
+    let difficulties = if difficulties.is_empty() {
+        DIFFICULTY_RATINGS
+            .map(|(difficulty, _)| difficulty)
+            .to_vec()
+    } else {
+        difficulties.to_vec()
+    };
+    println!("Playing {} games between each pair of players...", games);
+    match calibration::calibrate(&difficulties, games, config.seed.unwrap_or(0)) {
+        Ok(calibration) => print!("{}", calibration),
+        Err(e) => println!("Error while calibrating the bots: {}", e),
+    }
+}
 
-    while !game.grid().is_full() {
+/// Tunes the weights of tuned bots, starting from the weights saved in `output` or the data
+/// directory, and saves the best ones there.
+fn tune_bot(
+    generations: usize,
+    candidates: usize,
+    games: usize,
+    output: Option<&Path>,
+    config: &Config,
+) {
+    let path = match output {
+        Some(path) => path.to_owned(),
+        None => match local_history::create_path(TUNED_WEIGHTS) {
+            Ok(path) => path,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        },
+    };
+    let start = match fs::read_to_string(&path) {
+        Ok(saved) => match saved.parse() {
+            Ok(weights) => weights,
+            Err(e) => {
+                println!("Invalid weights in {}: {}", path.display(), e);
+                return;
+            }
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => HeuristicWeights::default(),
+        Err(e) => {
+            println!("Error reading {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    let tuner = Tuner::new()
+        .with_generations(generations)
+        .with_candidates(candidates)
+        .with_games(games)
+        .with_seed(config.seed.unwrap_or(0));
+    let tuned = tuner.tune_with(start, |generation, _, share| {
+        println!(
+            "Generation {}/{}: {:.0}% of the points",
+            generation,
+            generations,
+            share * 100.0
+        );
+    });
+    match tuned {
+        Ok(weights) => match fs::write(&path, weights.to_string()) {
+            Ok(()) => print!("Saved to {}:\n{}", path.display(), weights),
+            Err(e) => println!("Error saving the weights to {}: {}", path.display(), e),
+        },
+        Err(e) => println!("Error while tuning the weights: {}", e),
+    }
+}
+
+/// Writes the samples of `games` self-play games to `output`, or to the standard output. Errors
+/// are printed to stderr, so they don't end up among the samples.
+fn export_training_data(
+    games: usize,
+    exploration: u8,
+    format: ExportFormat,
+    output: Option<&Path>,
+    config: &Config,
+) {
+    let self_play = SelfPlay::new(games)
+        .with_exploration(exploration)
+        .with_seed(config.seed.unwrap_or_else(rand::random));
+    let written = match output {
+        Some(path) => fs::File::create(path)
+            .map(io::BufWriter::new)
+            .and_then(|mut file| {
+                let written = training::write_samples(&mut file, self_play.samples(), format)?;
+                file.flush().map(|()| written)
+            }),
+        None => {
+            let mut stdout = io::BufWriter::new(io::stdout().lock());
+            training::write_samples(&mut stdout, self_play.samples(), format)
+                .and_then(|written| stdout.flush().map(|()| written))
+        }
+    };
+    match (written, output) {
+        (Ok(written), Some(path)) => println!("Wrote {} samples to {}", written, path.display()),
+        (Ok(_), None) => {}
+        (Err(e), _) => eprintln!("Error writing the samples: {}", e),
+    }
+}
+
+/// Player waiting before each of its moves, so games between bots can be followed.
+#[derive(Debug)]
+struct DelayedPlayer {
+    player: Box<dyn Player>,
+    delay: Duration,
+}
+
+impl Player for DelayedPlayer {
+    fn get_move(&self, board: &Grid, mark: &Mark) -> Result<Move, PlayerError> {
+        thread::sleep(self.delay);
+        self.player.get_move(board, mark)
+    }
+
+    fn accept_draw(&self, board: &Grid, mark: &Mark) -> Result<bool, PlayerError> {
+        self.player.accept_draw(board, mark)
+    }
+}
+
+/// Sets up a 3 player game on a 5x5 board
+fn play_three_player_game(players: &PlayerArgs, y: Option<PlayerSpec>, config: &Config) {
+    let player_x = select_player(players.x, "Select the player type for X", config);
+    let player_o = select_player(players.o, "Select the player type for O", config);
+    let player_y = select_player(y, "Select the player type for Y", config);
+    let builder = GameBuilder::with_board(SquareBoard::new(5, 4))
+        .player_x(player_x)
+        .player_o(player_o)
+        .player_y(player_y);
+    let builder = select_first_mark(builder, &Mark::TURN_ORDER, players.first, config);
+    local_game_loop(builder.build().expect("Error creating game"), false, config);
+}
+
+/// Sets up a 2v2 game on the classic grid, where team members alternate making their team's moves
+fn play_team_game(
+    team_x: Option<&[PlayerSpec]>,
+    team_o: Option<&[PlayerSpec]>,
+    first: Option<First>,
+    config: &Config,
+) {
+    let select_team = |mark: Mark, team: Option<&[PlayerSpec]>| {
+        (0..2)
+            .map(|i| {
+                select_player(
+                    team.map(|team| team[i]),
+                    format!("Select the player type for {}'s player {}", mark, i + 1),
+                    config,
+                )
+            })
+            .collect()
+    };
+    let team_x = select_team(Mark::X, team_x);
+    let team_o = select_team(Mark::O, team_o);
+    let builder = Game::builder().team(Mark::X, team_x).team(Mark::O, team_o);
+    let builder = select_first_mark(builder, &[Mark::X, Mark::O], first, config);
+    local_game_loop(builder.build().expect("Error creating game"), false, config);
+}
+
+/// Game loop: Plays a game until there's a winner or there's a draw, and returns the finished game.
+/// With `pass_and_play`, the screen is cleared after every move until the next player is ready.
+fn local_game_loop<B: Board>(
+    game: Game<InProgress, B>,
+    pass_and_play: bool,
+    config: &Config,
+) -> Option<Game<Finished, B>> {
+    #[cfg(feature = "json")]
+    if config.json {
+        return json_output::local_game_loop(game);
+    }
+
+    // Show the cell numbers before the first move
+    if config.numpad {
+        print_board(game.board(), config);
+    }
+    println!("--- {}'s turn ---", game.current_player());
+    let result = game.play_to_end_with(|turn| {
+        let Turn::InProgress(game) = turn else {
+            return;
+        };
+
+        if pass_and_play {
+            pass_device(game.current_player().mark);
+        }
+        match game.history().last() {
+            Some(m) if m.player_move == Move::OfferDraw => println!("Draw offer declined."),
+            Some(m) if m.player_move == Move::Resign => println!("Player {} resigned.", m.mark),
+            _ => print_board(game.board(), config),
+        }
         println!("--- {}'s turn ---", game.current_player());
-        if let Err(e) = game.try_move() {
-            panic!("Error while executing move: {}", e);
+    });
+    let game = match result {
+        Ok(game) => game,
+        Err(e) => {
+            println!("Error while executing move: {}", e);
+            return None;
         }
+    };
 
-        println!("{}", game.grid());
+    match game.result() {
+        GameResult::Win {
+            winner,
+            reason: WinReason::Resignation,
+        } => {
+            let resigned = game.history().last().map(|m| m.mark).unwrap();
+            println!(
+                "Player {} resigned. Player {} won the game!",
+                resigned, winner
+            )
+        }
+        GameResult::Win {
+            winner,
+            reason: WinReason::Timeout,
+        } => {
+            let timed_out = game.history().last().map(|m| m.mark).unwrap();
+            println!(
+                "Player {} ran out of time. Player {} won the game!",
+                timed_out, winner
+            )
+        }
+        GameResult::Win { winner, .. } => {
+            print_board(game.board(), config);
+            println!("Player {} won the game!", winner);
+        }
+        GameResult::Draw(DrawReason::Agreement) => println!("Players agreed to a draw."),
+        GameResult::Draw(DrawReason::BoardFull) => {
+            print_board(game.board(), config);
+            println!("Draw!");
+        }
+    }
+    Some(game)
+}
 
-        if let Some(p) = game.find_winner() {
-            println!("Player {} won the game!", p);
+/// Clears the terminal and its scrollback, hiding the last player's hints, and waits for `mark`'s
+/// player to take the device and press Enter.
+fn pass_device(mark: Mark) {
+    const CLEAR_SCREEN: &str = "\x1b[2J\x1b[3J\x1b[H";
+    print!("{}", CLEAR_SCREEN);
+    utils::wait_for_enter(format!("Pass the device to {}, then press Enter.", mark));
+    print!("{}", CLEAR_SCREEN);
+}
+
+/// Appends the record of a finished game to the file given with `--export`, if any, with its
+/// players as the `X` and `O` tags. Only fails with a warning on stderr.
+fn export_game(game: &Game<Finished>, spec_x: PlayerSpec, spec_o: PlayerSpec, config: &Config) {
+    let Some(path) = &config.export else {
+        return;
+    };
+    let record = GameRecord::from_game(game)
+        .with_tag("X", spec_x.to_string())
+        .with_tag("O", spec_o.to_string());
+    // Records are separated by a blank line
+    let separator = match fs::metadata(path) {
+        Ok(metadata) if metadata.len() > 0 => "\n",
+        _ => "",
+    };
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| write!(file, "{}{}", separator, record));
+    if let Err(e) = written {
+        eprintln!("Couldn't export the game to {}: {}", path.display(), e);
+    }
+}
+
+/// Steps through game `number` of the records in `file`, or its last one, waiting for Enter
+/// before each move
+fn replay_game(file: &Path, number: Option<usize>, config: &Config) {
+    let records = fs::read_to_string(file)
+        .map_err(|e| format!("Error reading {}: {}", file.display(), e))
+        .and_then(|contents| {
+            GameRecord::parse_all(&contents)
+                .map_err(|e| format!("Invalid game record in {}: {}", file.display(), e))
+        });
+    let records = match records {
+        Ok(records) => records,
+        Err(e) => return println!("{}", e),
+    };
+    let record = match number {
+        Some(number) => number.checked_sub(1).and_then(|i| records.get(i)),
+        None => records.last(),
+    };
+    let Some(record) = record else {
+        return println!("{} doesn't have this game.", file.display());
+    };
+
+    for (name, value) in &record.tags {
+        println!("{}: {}", name, value);
+    }
+    let mut grid = Grid::default();
+    print_board(&grid, config);
+    for (i, played) in record.moves.iter().enumerate() {
+        let prompt = format!("Press Enter for move {} of {}.", i + 1, record.moves.len());
+        if !utils::wait_for_enter(prompt) {
             return;
         }
+        match played.player_move {
+            Move::Place(row, col) => {
+                if let Err(e) = grid.place(row, col, played.mark) {
+                    return println!("Move {} is invalid: {}", i + 1, e);
+                }
+                print_board(&grid, config);
+            }
+            Move::Resign => println!("Player {} resigned.", played.mark),
+            Move::OfferDraw => println!("Player {} offered a draw.", played.mark),
+        }
+    }
+
+    match record.result {
+        GameResult::Win {
+            winner,
+            reason: WinReason::Timeout,
+        } => {
+            // Running out of time is recorded as resigning, even in 3 player games
+            match record.moves.iter().rfind(|m| m.player_move == Move::Resign) {
+                Some(timed_out) => println!(
+                    "Player {} ran out of time. Player {} won the game!",
+                    timed_out.mark, winner
+                ),
+                None => println!("Player {} won the game on time!", winner),
+            }
+        }
+        GameResult::Win { winner, .. } => println!("Player {} won the game!", winner),
+        GameResult::Draw(DrawReason::Agreement) => println!("Players agreed to a draw."),
+        GameResult::Draw(DrawReason::BoardFull) => println!("Draw!"),
     }
+    print_annotations(&record.moves, config);
+}
 
-    println!("Draw!");
+/// Lists the moves of a finished game with the outcome each one left to its player, flagging the
+/// blunders, if enabled with `--annotate`. Nothing is printed in JSON mode.
+fn print_annotations(history: &[PlayedMove], config: &Config) {
+    if !config.annotate || config.json {
+        return;
+    }
+    println!("Moves, with the outcome each one left:");
+    for (i, annotation) in hint::annotate(history).iter().enumerate() {
+        println!("{:>2}. {}", i + 1, annotation);
+    }
+}
+
+/// Prints the outcome of `position` with perfect play, its best moves and the principal variation.
+/// Cells are named like in exported games, from a1 at the top left to c3 at the bottom right.
+fn analyze_position(position: &Position, config: &Config) {
+    let cell = |(row, col): (usize, usize)| format!("{}{}", char::from(b'a' + col as u8), row + 1);
+
+    print_board(&position.grid, config);
+    let Some(analysis) = hint::analyze(&position.grid, &position.mark) else {
+        return println!("The game is already over.");
+    };
+    println!(
+        "{} to move, {} with perfect play.",
+        position.mark, analysis.evaluation
+    );
+    let best_moves: Vec<_> = analysis.best_moves.into_iter().map(cell).collect();
+    println!("Best moves: {}", best_moves.join(", "));
+    let mut mark = position.mark;
+    let variation: Vec<_> = analysis
+        .principal_variation
+        .into_iter()
+        .map(|played| {
+            let played = format!("{} {}", mark, cell(played));
+            mark = mark.opposite();
+            played
+        })
+        .collect();
+    println!("Principal variation: {}", variation.join(", "));
+}
+
+/// Adds a game to the history, which only fails with a warning on stderr. `moves` can be empty if
+/// they aren't known.
+fn record_game(
+    mark: Mark,
+    opponent: Opponent,
+    result: GameResult,
+    moves: &[PlayedMove],
+    started_at: SystemTime,
+) {
+    if let Err(e) = local_history::record(mark, opponent, result, moves, started_at) {
+        eprintln!("Couldn't add the game to the history: {}", e);
+    }
+}
+
+/// Prints the totals, win rates and streaks of the history.
+fn show_stats() {
+    let entries = local_history::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    if entries.is_empty() {
+        return println!("No games played against bots or remote players yet.");
+    }
+
+    let stats = Stats::new(&entries);
+    let totals = stats.totals;
+    println!(
+        "{} games played: {} won, {} lost, {} drawn ({:.0}% won)",
+        totals.games(),
+        totals.wins,
+        totals.losses,
+        totals.draws,
+        totals.win_rate()
+    );
+    println!();
+    println!(
+        "{:<16} {:>6} {:>6} {:>6} {:>6} {:>9}",
+        "Opponent", "Games", "Won", "Lost", "Drawn", "Win rate"
+    );
+    for (opponent, totals) in &stats.by_opponent {
+        println!(
+            "{:<16} {:>6} {:>6} {:>6} {:>6} {:>8.0}%",
+            opponent,
+            totals.games(),
+            totals.wins,
+            totals.losses,
+            totals.draws,
+            totals.win_rate()
+        );
+    }
+    println!();
+    println!("Longest winning streak: {}", stats.longest_win_streak);
+    println!("Longest losing streak: {}", stats.longest_loss_streak);
 }
 
 /// Connect to remote server + game loop
-fn play_remote_game() {
-    let addr = utils::read_string_default("Server address", "127.0.0.1:8905");
-    let mut game = RemoteGame::connect(addr).expect("Error while connecting to remote server.");
-    let player = LocalPlayer;
-    networked_game_loop(&mut game, &player)
+fn play_remote_game(
+    addr: Option<String>,
+    password: Option<String>,
+    code: Option<RoomCode>,
+    network: NetworkArgs,
+    config: &Config,
+) {
+    let addr = match (addr, code) {
+        // The address is the dedicated server relaying the room
+        (Some(addr), Some(code)) => return play_room_game(Some(addr), Some(code), network, config),
+        (None, Some(code)) => match find_room(&code) {
+            Some(addr) => addr,
+            None => return,
+        },
+        (addr, None) => addr.unwrap_or_else(prompt_server_address),
+    };
+    let game = match (&config.nickname, password) {
+        (None, None) => RemoteGame::connect(addr),
+        (nickname, password) => {
+            let intro = Introduction::new(nickname.clone().unwrap_or_default())
+                .with_password(password.unwrap_or_default());
+            RemoteGame::join(addr, &intro)
+        }
+    };
+    let mut game = game.expect("Error while connecting to remote server.");
+    let player: Arc<dyn Player> = Arc::new(local_player(config));
+    networked_game_loop(&mut game, &player, network, config)
 }
 
 /// Host a game + game loop
-fn play_hosted_game() {
-    let player = LocalPlayer;
+fn play_hosted_game(
+    addr: Option<String>,
+    settings: ServerGameSettings,
+    announce: Option<bool>,
+    wait: Option<usize>,
+    network: NetworkArgs,
+    config: &Config,
+) {
+    let player: Arc<dyn Player> = Arc::new(local_player(config));
 
-    let addr = utils::read_string_default("Bind on address", "0.0.0.0:8905");
-    let game = ServerGame::bind(addr, &Default::default()).expect("Error binding to socket");
+    let addr =
+        addr.unwrap_or_else(|| utils::read_string_default("Bind on address", "0.0.0.0:8905"));
+    let settings = settings.with_announce(
+        announce
+            .unwrap_or_else(|| utils::read_bool("Announce the game on the local network?", true)),
+    );
+    let game = ServerGame::bind(addr, &settings).expect("Error binding to socket");
+    if let Some(code) = &settings.room_code {
+        println!(
+            "Room code: {}. Your opponent joins with `tictactoe join --code {}`.",
+            code, code
+        );
+    }
+    let wait = wait.unwrap_or_else(|| {
+        utils::read_number_default(
+            "Minutes to wait for a player (0 to wait until one connects)",
+            0..=1440,
+            0,
+        )
+    });
+    if wait > 0 {
+        let canceller = game.canceller();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(wait as u64 * 60));
+            canceller.cancel();
+        });
+    }
 
     println!("Waiting for a player to connect.");
-    let mut game = game.listen().expect("Error listening to connections");
-    networked_game_loop(&mut game, &player);
+    let mut game = match game.listen() {
+        Ok(game) => game,
+        Err(e) if e.kind() == ErrorKind::Interrupted => {
+            return println!("No player connected, stopped hosting.")
+        }
+        Err(e) => panic!("Error listening to connections: {}", e),
+    };
+    networked_game_loop(&mut game, &player, network, config);
 }
 
-fn networked_game_loop(game: &mut impl NetworkedGame, local_player: &dyn Player) {
-    while !game.grid().is_full() {
-        if game.is_local_turn() {
-            println!("--- {}'s turn ---", game.local_mark());
-            if let Err(e) = game.try_move(local_player) {
-                panic!("Error while executing move: {}", e)
+/// Looks for games announced on the local network, and lets the user pick one, or enter a room code
+/// or an address
+fn prompt_server_address() -> String {
+    println!("Looking for games on the local network...");
+    let hosts = RemoteGame::discover(Duration::from_secs(1)).unwrap_or_default();
+
+    let mut options: Vec<String> = hosts
+        .iter()
+        .map(|addr| format!("Join the game hosted on {}", addr))
+        .collect();
+    options.push("Enter a room code".to_owned());
+    options.push("Enter an address".to_owned());
+    loop {
+        match utils::read_list("Choose a game", &options) {
+            i if i < hosts.len() => return hosts[i].to_string(),
+            i if i == hosts.len() => {
+                let code = utils::read_string("Room code");
+                match code.parse().map(|code| find_room(&code)) {
+                    Ok(Some(addr)) => return addr,
+                    Ok(None) => {}
+                    Err(e) => println!("{}", e),
+                }
             }
-        } else {
-            println!("Waiting for remote player to play...");
-            if let Err(e) = game.try_move(local_player) {
-                panic!("Error while receiving remote move: {}", e)
+            _ => return prompt_dedicated_server_address(),
+        }
+    }
+}
+
+/// Looks for the game hosted on the local network with the room `code`, and returns its address.
+/// Prints why if it isn't found.
+fn find_room(code: &RoomCode) -> Option<String> {
+    println!("Looking for room {} on the local network...", code);
+    match RemoteGame::find_room(code, Duration::from_secs(2)) {
+        Ok(Some(addr)) => Some(addr.to_string()),
+        Ok(None) => {
+            println!("No game with the room code {} on the local network.", code);
+            None
+        }
+        Err(e) => {
+            println!("Error looking for the room: {}", e);
+            None
+        }
+    }
+}
+
+/// Load a saved game, then host it or reconnect to its host + game loop
+fn play_resumed_game(
+    path: Option<String>,
+    host: Option<String>,
+    join: Option<String>,
+    network: NetworkArgs,
+    config: &Config,
+) {
+    let path = path.unwrap_or_else(|| utils::read_string_default("Saved game file", SAVE_FILE));
+    let bytes = fs::read(path).expect("Error reading saved game");
+    let snapshot = GameSnapshot::from_bytes(&bytes).expect("Invalid saved game");
+    print_board(&snapshot.grid, config);
+
+    let player: Arc<dyn Player> = Arc::new(local_player(config));
+    let host = match (host, join) {
+        (Some(addr), _) => Ok(addr),
+        (_, Some(addr)) => Err(addr),
+        _ if utils::read_bool("Host the resumed game?", true) => Ok(config
+            .bind
+            .clone()
+            .unwrap_or_else(|| utils::read_string_default("Bind on address", "0.0.0.0:8905"))),
+        _ => Err(utils::read_string_default(
+            "Server address",
+            "127.0.0.1:8905",
+        )),
+    };
+    match host {
+        Ok(addr) => {
+            let game = ServerGame::resume(addr, &snapshot).expect("Error binding to socket");
+
+            println!("Waiting for the other player to resume the game.");
+            let mut game = game.listen().expect("Error listening to connections");
+            networked_game_loop(&mut game, &player, network, config);
+        }
+        Err(addr) => {
+            let mut game =
+                RemoteGame::resume(addr, &snapshot).expect("Error while resuming remote game.");
+            networked_game_loop(&mut game, &player, network, config);
+        }
+    }
+}
+
+/// Data file of the accounts created on dedicated servers, one line each:
+/// `<address> <name> <token>`.
+const ACCOUNTS: &str = "accounts";
+
+/// Creates an account on a dedicated server, and saves it for the next games played there
+fn create_account(addr: Option<String>, name: Option<String>) {
+    let addr = addr.unwrap_or_else(prompt_dedicated_server_address);
+    let name = name.unwrap_or_else(|| utils::read_string("Account name"));
+    let credentials = match lobby::create_account(&addr, &name) {
+        Ok(credentials) => credentials,
+        Err(e) => return println!("Couldn't create the account: {}", e),
+    };
+    let saved = local_history::create_path(ACCOUNTS).and_then(|path| {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{} {} {}", addr, name, credentials.token))
+            .map_err(|e| format!("Error saving to {}: {}", path.display(), e))
+    });
+    match saved {
+        Ok(()) => println!(
+            "Created the account {}: the lobby and room commands log in to it on {}.",
+            name, addr
+        ),
+        Err(e) => println!(
+            "Created the account {}, with the token {}. {}",
+            name, credentials.token, e
+        ),
+    }
+}
+
+/// Returns the last account created on the dedicated server at `addr`, if any.
+fn saved_account(addr: &str) -> Option<Credentials> {
+    let saved = fs::read_to_string(local_history::path(ACCOUNTS)?).ok()?;
+    saved.lines().rev().find_map(
+        |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [address, name, token] if address == addr => Some(Credentials {
+                name: name.to_owned(),
+                token: token.to_owned(),
+            }),
+            _ => None,
+        },
+    )
+}
+
+/// Enter a dedicated server's lobby, create or join a game + game loop
+fn play_lobby_game(
+    addr: Option<String>,
+    name: Option<String>,
+    network: NetworkArgs,
+    config: &Config,
+) {
+    let addr = addr.unwrap_or_else(prompt_dedicated_server_address);
+    let lobby = match saved_account(&addr) {
+        Some(credentials) => {
+            println!("Logging in as {}.", credentials.name);
+            LobbyClient::login(&addr, &credentials).map_err(|e| e.to_string())
+        }
+        None => {
+            let name = name.unwrap_or_else(|| utils::read_string_default("Your name", "Player"));
+            LobbyClient::connect(&addr, &name).map_err(|e| e.to_string())
+        }
+    };
+    let mut lobby = lobby.expect("Error while connecting to remote server.");
+    let player: Arc<dyn Player> = Arc::new(local_player(config));
+
+    loop {
+        let games = lobby.list_games().expect("Error listing open games");
+        let mut options: Vec<String> = games
+            .iter()
+            .map(|game| format!("Join {}'s game", game.host))
+            .collect();
+        options.push("Create a game".to_owned());
+        options.push("Show the leaderboard".to_owned());
+        options.push("Refresh".to_owned());
+
+        match utils::read_list("Choose a game", &options) {
+            i if i < games.len() => match lobby.join_game(games[i].id) {
+                Ok(mut game) => return networked_game_loop(&mut game, &player, network, config),
+                Err((client, e)) => {
+                    println!("Couldn't join the game: {}", e);
+                    lobby = client;
+                }
+            },
+            i if i == games.len() => {
+                let pending = lobby.create_game().expect("Error creating game");
+                println!("Waiting for a player to join.");
+                let mut game = pending.wait().expect("Error waiting for a player");
+                return networked_game_loop(&mut game, &player, network, config);
             }
+            i if i == games.len() + 1 => match lobby.leaderboard() {
+                Ok(entries) => print_leaderboard(&entries),
+                Err(e) => println!("Couldn't get the leaderboard: {}", e),
+            },
+            _ => {}
         }
+    }
+}
 
-        println!("{}", game.grid());
+/// Print the results of a dedicated server's accounts, as ranked by the server
+fn print_leaderboard(entries: &[LeaderboardEntry]) {
+    if entries.is_empty() {
+        return println!("No account finished a game yet.");
+    }
+    println!(
+        "{:>3} {:<24} {:>6} {:>6} {:>6}",
+        "#", "Account", "Won", "Drawn", "Lost"
+    );
+    for (rank, entry) in entries.iter().enumerate() {
+        println!(
+            "{:>3} {:<24} {:>6} {:>6} {:>6}",
+            rank + 1,
+            entry.name,
+            entry.wins,
+            entry.draws,
+            entry.losses
+        );
+    }
+}
+
+/// Join a room on a dedicated server, and wait for the other player to join it + game loop
+fn play_room_game(
+    addr: Option<String>,
+    code: Option<RoomCode>,
+    network: NetworkArgs,
+    config: &Config,
+) {
+    let addr = addr.unwrap_or_else(prompt_dedicated_server_address);
+    let code = code.unwrap_or_else(|| loop {
+        let code = utils::read_string_default(
+            "Room code (share it with your opponent)",
+            RoomCode::random().to_string(),
+        );
+        match code.parse() {
+            Ok(code) => break code,
+            Err(e) => println!("{}", e),
+        }
+    });
+    println!("Waiting for your opponent to join the room {}.", code);
+    let game = match saved_account(&addr) {
+        Some(credentials) => lobby::join_room_as(&addr, code.as_str(), &credentials),
+        None => lobby::join_room(&addr, code.as_str()),
+    };
+    let mut game = game.expect("Error joining the room");
+    let player: Arc<dyn Player> = Arc::new(local_player(config));
+    networked_game_loop(&mut game, &player, network, config);
+}
+
+/// Connect to a hosted game as a spectator + print moves and results until the host closes the game
+fn spectate_game(addr: Option<String>, config: &Config) {
+    let addr = addr.unwrap_or_else(prompt_dedicated_server_address);
+    let mut spectator = Spectator::connect(addr).expect("Error while connecting to remote server.");
+
+    println!("Waiting for moves...");
+    loop {
+        match spectator.next_event() {
+            Ok(Some(SpectatorEvent::Move(played))) => {
+                println!("--- {} played ---", played.mark);
+                print_board(spectator.grid(), config);
+            }
+            Ok(Some(SpectatorEvent::GameOver(result))) => match result {
+                GameResult::Win {
+                    winner,
+                    reason: WinReason::Resignation,
+                } => println!(
+                    "Player {} resigned. Player {} won the game!",
+                    winner.opposite(),
+                    winner
+                ),
+                GameResult::Win {
+                    winner,
+                    reason: WinReason::Timeout,
+                } => println!(
+                    "Player {} took too long to move. Player {} won the game!",
+                    winner.opposite(),
+                    winner
+                ),
+                GameResult::Win { winner, .. } => println!("Player {} won the game!", winner),
+                GameResult::Draw(_) => println!("Draw!"),
+            },
+            Ok(None) => break,
+            Err(e) => return println!("Error while receiving moves: {}", e),
+        }
+    }
 
-        if let Some(p) = game.grid().get_winning_mark() {
-            if p == game.local_mark() {
-                println!("You won the game!");
+    if spectator.result().is_none() {
+        println!("The host closed the game.");
+    }
+}
+
+fn networked_game_loop(
+    game: &mut impl NetworkedGame,
+    local_player: &Arc<dyn Player>,
+    network: NetworkArgs,
+    config: &Config,
+) {
+    let peer = game.peer();
+    if !config.json {
+        println!("Playing against {}.", peer);
+    }
+    let name = peer.nickname.unwrap_or_else(|| "Opponent".to_owned());
+    game.set_chat_handler(Box::new(move |message| println!("[{}] {}", name, message)));
+    let minutes = network.clock.map(usize::from).unwrap_or_else(|| {
+        game.with_heartbeat(|| {
+            utils::read_number_default(
+                "Minutes on each player's clock (0 for untimed, must match your opponent's)",
+                0..=120,
+                0,
+            )
+        })
+    });
+    if minutes > 0 {
+        game.set_clock(Some(Duration::from_secs(minutes as u64 * 60)));
+    }
+
+    #[cfg(feature = "tui")]
+    if config.tui {
+        // The UI is closed before the error is shown
+        let result = tui::Tui::new(true, config.numbering(), config.symbols.clone())
+            .map_err(NetworkedGameError::Io)
+            .and_then(|tui| tui::networked_game_loop(game, &tui));
+        if let Err(e) = result {
+            println!("Error while playing: {}", e);
+            offer_save(game, e);
+        }
+        return;
+    }
+
+    #[cfg(feature = "json")]
+    if config.json {
+        let player: Arc<dyn Player> = Arc::new(json_output::local_player(config));
+        let started_at = SystemTime::now();
+        match json_output::networked_game_loop(game, &player) {
+            Ok(result) => record_game(game.local_mark(), Opponent::Remote, result, &[], started_at),
+            Err(e) => json_output::emit_error(e),
+        }
+        return;
+    }
+
+    loop {
+        let started_at = SystemTime::now();
+        let result = loop {
+            if let Some(result) = game.result() {
+                break result;
+            }
+
+            if game.is_local_turn() {
+                println!("--- {}'s turn ---", game.local_mark());
+                send_chat_messages(game);
+                if let Err(e) = game.try_move(local_player) {
+                    println!("Error while executing move: {}", e);
+                    return offer_save(game, e);
+                }
             } else {
-                println!("Your opponent won the game.");
+                println!("Waiting for remote player to play...");
+                match game.try_move(local_player) {
+                    Ok(()) => {}
+                    Err(NetworkedGameError::Rejected(e)) => {
+                        println!("Your opponent rejected your last move ({}), play again.", e)
+                    }
+                    Err(e) => {
+                        println!("Error while receiving remote move: {}", e);
+                        return offer_save(game, e);
+                    }
+                }
+            }
+
+            print_board(game.grid(), config);
+            if let Some(clock) = game.clock() {
+                println!(
+                    "Time left: X {:.1}s, O {:.1}s",
+                    clock.remaining(Mark::X).as_secs_f32(),
+                    clock.remaining(Mark::O).as_secs_f32()
+                );
+            }
+        };
+
+        record_game(game.local_mark(), Opponent::Remote, result, &[], started_at);
+        match result {
+            GameResult::Win {
+                winner,
+                reason: WinReason::Resignation,
+            } if winner == game.local_mark() => {
+                println!("Your opponent resigned. You won the game!")
             }
+            GameResult::Win {
+                reason: WinReason::Resignation,
+                ..
+            } => println!("You resigned."),
+            GameResult::Win {
+                winner,
+                reason: WinReason::Timeout,
+            } if game.clock().is_some() => {
+                if winner == game.local_mark() {
+                    println!("Your opponent ran out of time. You won the game!")
+                } else {
+                    println!("You ran out of time, and lost the game.")
+                }
+            }
+            // Idle players are disconnected, so no rematch can be played
+            GameResult::Win {
+                winner,
+                reason: WinReason::Timeout,
+            } if winner == game.local_mark() => {
+                return println!("Your opponent took too long to move. You won the game!")
+            }
+            GameResult::Win {
+                reason: WinReason::Timeout,
+                ..
+            } => return println!("You took too long to move, and forfeited the game."),
+            GameResult::Win { winner, .. } if winner == game.local_mark() => {
+                println!("You won the game!")
+            }
+            GameResult::Win { .. } => println!("Your opponent won the game."),
+            GameResult::Draw(_) => println!("Draw!"),
+        }
+
+        let accept = game.with_heartbeat(|| utils::read_bool("Play a rematch?", false));
+        if accept {
+            println!("Waiting for your opponent's answer...");
+        }
+        match game.rematch(accept) {
+            Ok(true) => println!("Rematch accepted! You now play {}.", game.local_mark()),
+            Ok(false) if accept => return println!("Your opponent declined the rematch."),
+            Ok(false) => return,
+            Err(e) => return println!("Error while setting up the rematch: {}", e),
+        }
+    }
+}
+
+/// Lets the local player send chat messages before playing their move
+fn send_chat_messages(game: &mut impl NetworkedGame) {
+    loop {
+        let message = game.with_heartbeat(|| {
+            utils::read_string_default("Message to your opponent (empty to play)", "")
+        });
+        if message.is_empty() {
             return;
         }
+        if let Err(e) = game.send_chat(&message) {
+            println!("Couldn't send the message: {}", e);
+        }
+    }
+}
+
+/// Offers to save a networked game interrupted by a connection error or quit mid-game, so it can be
+/// resumed later.
+fn offer_save(game: &impl NetworkedGame, error: NetworkedGameError) {
+    if !matches!(
+        error,
+        NetworkedGameError::Io(_) | NetworkedGameError::Cancelled
+    ) || !utils::read_bool("Save the game to resume it later?", true)
+    {
+        return;
+    }
+
+    let path = utils::read_string_default("Save file", SAVE_FILE);
+    match fs::write(&path, game.snapshot().to_bytes()) {
+        Ok(_) => println!("Game saved to {}", path),
+        Err(e) => println!("Error saving game: {}", e),
+    }
+}
+
+/// Prints a board as configured: as ASCII-art glyphs if `large` is on, otherwise with the
+/// configured symbols and ASCII characters if `unicode` is off. Marks are colored if `color` is on,
+/// and a legend of the cell numbers is added if `numpad` is on.
+fn print_board(board: &impl Board, config: &Config) {
+    // Lines taken by each row of cells, including the separator line above it
+    let (mut board, row_height) = if config.large {
+        let glyphs = LargeBoard(board).to_string();
+        if !config.color {
+            (glyphs, 6)
+        } else {
+            // Glyphs are only made of separators and marks, so every letter is a mark
+            let colored = glyphs
+                .chars()
+                .map(|c| match c {
+                    'X' => symbols::paint(Mark::X, "X"),
+                    'O' => symbols::paint(Mark::O, "O"),
+                    'Y' => symbols::paint(Mark::Y, "Y"),
+                    c => c.to_string(),
+                })
+                .collect();
+            (colored, 6)
+        }
+    } else {
+        let unicode = config.unicode && cfg!(feature = "unicode");
+        (config.symbols.draw(board, unicode, config.color), 2)
+    };
+    if config.numpad {
+        board = with_legend(&board, row_height, config.numbering());
+    }
+    println!("{}", board);
+}
+
+/// Writes the number of each cell next to the middle of the row of `board` it is on, rows taking
+/// `row_height` lines.
+fn with_legend(board: &str, row_height: usize, numbering: CellNumbering) -> String {
+    // Boards are drawn with a separator line above and below each row
+    let lines: Vec<&str> = board.lines().collect();
+    let size = lines.len() / row_height;
+    let width = (size * size).to_string().len();
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        out.push_str(line);
+        if i % row_height == row_height / 2 {
+            let row = i / row_height;
+            let numbers: Vec<String> = (0..size)
+                .map(|col| format!("{:>width$}", numbering.number(row, col, size)))
+                .collect();
+            out.push_str("    ");
+            out.push_str(&numbers.join(" "));
+        }
+        out.push('\n');
     }
-    println!("Draw!")
+    out
+}
+
+/// Asks for the address of a dedicated server or hosted game
+fn prompt_dedicated_server_address() -> String {
+    utils::read_string_default("Server address", "127.0.0.1:8905")
 }
 
-fn prompt_game_type(prompt: impl AsRef<str>) -> GameType {
+/// Asks for the type of game, whose settings are then all asked for
+fn prompt_command(prompt: impl AsRef<str>) -> Command {
     let options = vec![
         "Local only",               // 0
         "Connect to a remote game", // 1
         "Host a game",              // 2
+        "Resume a saved game",      // 3
+        "Local 3 player game",      // 4
+        "Local 2v2 team game",      // 5
+        "Join a server lobby",      // 6
+        "Spectate a hosted game",   // 7
+        "Join a room on a server",  // 8
+        "Watch two bots play",      // 9
+        "Tutorial against a bot",   // 10
     ];
 
+    let network = NetworkArgs::default();
     match utils::read_list(prompt, &options) {
-        0 => GameType::Local,
-        1 => GameType::Remote,
-        2 => GameType::Host,
+        0 => Command::Local {
+            players: PlayerArgs::default(),
+            pass_and_play: false,
+        },
+        1 => Command::Join {
+            address: None,
+            password: None,
+            code: None,
+            network,
+        },
+        2 => Command::Host {
+            bind: None,
+            mark: Mark::X,
+            second: false,
+            rematch: RematchPolicy::Swap,
+            announce: false,
+            no_announce: false,
+            wait: None,
+            password: None,
+            code: None,
+            network,
+        },
+        3 => Command::Resume {
+            file: None,
+            host: None,
+            join: None,
+            network,
+        },
+        4 => Command::ThreePlayer {
+            players: PlayerArgs::default(),
+            y: None,
+        },
+        5 => Command::Teams {
+            team_x: None,
+            team_o: None,
+            first: None,
+        },
+        6 => Command::Lobby {
+            address: None,
+            name: None,
+            network,
+        },
+        7 => Command::Spectate { address: None },
+        9 => Command::Watch {
+            x: None,
+            o: None,
+            first: None,
+            delay: 1000,
+        },
+        10 => Command::Tutorial {
+            bot: None,
+            first: None,
+        },
+        8 => Command::Room {
+            address: None,
+            code: None,
+            network,
+        },
         _ => unreachable!(),
     }
 }
 
-fn prompt_player_selection(prompt: impl AsRef<str>) -> Box<dyn Player> {
+/// Uses the first player given on the command line, or asks which of `marks` plays first, or
+/// whether to pick it randomly.
+fn select_first_mark<B: Board>(
+    builder: GameBuilder<B>,
+    marks: &[Mark],
+    first: Option<First>,
+    config: &Config,
+) -> GameBuilder<B> {
+    let random = |builder: GameBuilder<B>| match config.seed {
+        Some(seed) => builder.seeded_random_first_mark(seed),
+        None => builder.random_first_mark(),
+    };
+    match first {
+        Some(First::Mark(mark)) if marks.contains(&mark) => return builder.first_mark(mark),
+        Some(First::Mark(mark)) => println!("{} isn't playing in this game.", mark),
+        Some(First::Random) => return random(builder),
+        None => {}
+    }
+
+    let mut options: Vec<String> = marks.iter().map(|m| m.to_string()).collect();
+    options.push("Random".to_owned());
+
+    match utils::read_list("Who plays first?", &options) {
+        i if i < marks.len() => builder.first_mark(marks[i]),
+        _ => random(builder),
+    }
+}
+
+/// Creates the player given on the command line, or the one chosen interactively.
+fn select_player<B: Board>(
+    spec: Option<PlayerSpec>,
+    prompt: impl AsRef<str>,
+    config: &Config,
+) -> Box<dyn Player<B>>
+where
+    LocalPlayer: Player<B>,
+    LocalPlayer<StdinReader, io::Stderr>: Player<B>,
+    BotPlayer: Player<B>,
+{
+    create_player(select_player_spec(spec, prompt, config), config)
+}
+
+/// Returns the player given on the command line, or asks for one.
+fn select_player_spec(
+    spec: Option<PlayerSpec>,
+    prompt: impl AsRef<str>,
+    config: &Config,
+) -> PlayerSpec {
+    spec.unwrap_or_else(|| prompt_player_selection(prompt, config))
+}
+
+fn create_player<B: Board>(spec: PlayerSpec, config: &Config) -> Box<dyn Player<B>>
+where
+    LocalPlayer: Player<B>,
+    LocalPlayer<StdinReader, io::Stderr>: Player<B>,
+    BotPlayer: Player<B>,
+{
+    match spec {
+        #[cfg(feature = "json")]
+        PlayerSpec::Human if config.json => Box::new(json_output::local_player(config)),
+        PlayerSpec::Human => Box::new(local_player(config)),
+        PlayerSpec::Bot {
+            difficulty,
+            personality,
+        } => Box::new(create_bot(difficulty, personality, config.seed)),
+        PlayerSpec::Mirror => Box::new(create_mirror(config.seed)),
+        PlayerSpec::Tuned => Box::new(create_tuned(config.seed)),
+    }
+}
+
+fn create_bot(
+    difficulty: BotPlayerDifficulty,
+    personality: BotPersonality,
+    seed: Option<u64>,
+) -> BotPlayer {
+    let bot = BotPlayer::from_difficulty(difficulty).with_personality(personality);
+    match next_bot_seed(seed) {
+        Some(seed) => bot.with_seed(seed),
+        None => bot,
+    }
+}
+
+fn create_mirror(seed: Option<u64>) -> MirrorPlayer {
+    match next_bot_seed(seed) {
+        Some(seed) => MirrorPlayer::new().with_seed(seed),
+        None => MirrorPlayer::new(),
+    }
+}
+
+/// Creates a tuned bot with the weights saved by `tictactoe tune`, or the default weights if none
+/// were saved.
+fn create_tuned(seed: Option<u64>) -> TunedBot {
+    let weights = match local_history::path(TUNED_WEIGHTS).map(fs::read_to_string) {
+        Some(Ok(saved)) => saved.parse().unwrap_or_else(|e| {
+            eprintln!(
+                "Invalid tuned weights, playing with the default ones: {}",
+                e
+            );
+            HeuristicWeights::default()
+        }),
+        _ => HeuristicWeights::default(),
+    };
+    let bot = TunedBot::new(weights);
+    match next_bot_seed(seed) {
+        Some(seed) => bot.with_seed(seed),
+        None => bot,
+    }
+}
+
+/// Bots are seeded in the order they are created from `seed`, so each gets its own seed.
+fn next_bot_seed(seed: Option<u64>) -> Option<u64> {
+    let n = BOTS_CREATED.fetch_add(1, Ordering::Relaxed);
+    seed.map(|seed| seed.wrapping_add(n + 1))
+}
+
+/// Creates a human player entering moves in the terminal, numbering cells as configured.
+fn local_player(config: &Config) -> LocalPlayer {
+    LocalPlayer::default().with_numbering(config.numbering())
+}
+
+fn prompt_player_selection(prompt: impl AsRef<str>, config: &Config) -> PlayerSpec {
     let player_options = vec![
         "Local Player", // 0
         "Local Bot",    // 1
     ];
 
     match utils::read_list(prompt, &player_options) {
-        0 => {
-            // Local Player
-            Box::new(player::LocalPlayer)
-        }
-        1 => {
-            // Local Bot
-            let diff = prompt_bot_difficulty_selection();
-            Box::new(player::BotPlayer::from_difficulty(diff))
-        }
+        0 => PlayerSpec::Human,
+        1 => PlayerSpec::Bot {
+            difficulty: config
+                .bot_difficulty
+                .unwrap_or_else(prompt_bot_difficulty_selection),
+            personality: prompt_bot_personality_selection(),
+        },
         _ => unreachable!(),
     }
 }
 
 fn prompt_bot_difficulty_selection() -> BotPlayerDifficulty {
-    let diff_options = vec![
-        "Easy",       // 0
-        "Normal",     // 1
-        "Impossible", // 2
-    ];
+    // Fixed difficulties show their estimated rating
+    let mut diff_options: Vec<String> = DIFFICULTY_RATINGS
+        .iter()
+        .map(|&(difficulty, rating)| format!("{} ≈ {}", difficulty_name(difficulty), rating))
+        .collect();
+    diff_options.push("Custom".to_owned());
 
     match utils::read_list("Choose a bot difficulty", &diff_options) {
-        0 => BotPlayerDifficulty::Easy,
-        1 => BotPlayerDifficulty::Normal,
-        2 => BotPlayerDifficulty::Impossible,
+        i if i < DIFFICULTY_RATINGS.len() => DIFFICULTY_RATINGS[i].0,
+        _ => {
+            let strength = utils::read_number_default("Percentage of optimal moves", 0..=100, 50);
+            BotPlayerDifficulty::Strength(strength as u8)
+        }
+    }
+}
+
+fn prompt_bot_personality_selection() -> BotPersonality {
+    let personality_options = vec![
+        "Balanced",      // 0
+        "Center hugger", // 1
+        "Corner lover",  // 2
+        "Fork seeker",   // 3
+        "Blocker",       // 4
+    ];
+
+    match utils::read_list("Choose a bot personality", &personality_options) {
+        0 => BotPersonality::Balanced,
+        1 => BotPersonality::CenterHugger,
+        2 => BotPersonality::CornerLover,
+        3 => BotPersonality::ForkSeeker,
+        4 => BotPersonality::Blocker,
         _ => unreachable!(),
     }
 }
@@ -0,0 +1,314 @@
+//! Encrypted and mutually authenticated connections using the Noise protocol framework, for games
+//! between players who can't easily get TLS certificates. Only available with the `noise` feature.
+//!
+//! Each player has a static key pair (see [`NoiseKeypair::generate`]), and players exchange their
+//! public keys beforehand, over chat for instance. Connections start with a
+//! `Noise_XX_25519_ChaChaPoly_BLAKE2s` handshake, after which each side checks that the other side
+//! holds the private key of the public key it expects. Packets are then sent as Noise transport
+//! messages, each preceded by its length as 2 big-endian bytes.
+
+use std::{
+    fmt::Debug,
+    io::{self, ErrorKind, Read, Write},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use snow::{Builder, HandshakeState, TransportState};
+
+use crate::game::GameStream;
+
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+/// Length of the private and public keys.
+pub const NOISE_KEY_LEN: usize = 32;
+
+/// Longest Noise message, including its authentication tag.
+const MAX_MESSAGE_LEN: usize = 65535;
+
+const TAG_LEN: usize = 16;
+
+/// Static key pair identifying a player in Noise handshakes.
+#[derive(Clone)]
+pub struct NoiseKeypair {
+    pub private_key: [u8; NOISE_KEY_LEN],
+    pub public_key: [u8; NOISE_KEY_LEN],
+}
+
+impl NoiseKeypair {
+    /// Generates a new random key pair.
+    pub fn generate() -> io::Result<Self> {
+        let keypair = builder()?.generate_keypair().map_err(io::Error::other)?;
+        Ok(Self {
+            private_key: key(&keypair.private)?,
+            public_key: key(&keypair.public)?,
+        })
+    }
+}
+
+impl Debug for NoiseKeypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NoiseKeypair")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keys used by one side of a Noise connection.
+#[derive(Clone)]
+pub struct NoiseConfig {
+    /// Private key of the local player.
+    pub private_key: [u8; NOISE_KEY_LEN],
+    /// Public key the remote side must authenticate with, or `None` to accept any key, which only
+    /// encrypts the connection. The key used can then be checked with
+    /// [`NoiseStream::remote_public_key`].
+    pub remote_public_key: Option<[u8; NOISE_KEY_LEN]>,
+}
+
+impl Debug for NoiseConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NoiseConfig")
+            .field("remote_public_key", &self.remote_public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Connection encrypted with Noise, see the [module documentation](self).
+#[derive(Debug)]
+pub struct NoiseStream<S> {
+    inner: S,
+    transport: TransportState,
+    remote_public_key: [u8; NOISE_KEY_LEN],
+    /// Message being read, kept across timeouts since part of it may already be read
+    incoming: Vec<u8>,
+    /// Plaintext decrypted from the last message, not read yet
+    decrypted: Vec<u8>,
+}
+
+impl<S: Read + Write> NoiseStream<S> {
+    /// Performs the handshake as the client, which sends the first message.
+    pub fn client(inner: S, config: &NoiseConfig) -> io::Result<Self> {
+        let handshake = builder()?
+            .local_private_key(&config.private_key)
+            .build_initiator()
+            .map_err(io::Error::other)?;
+        Self::handshake(inner, handshake, config, true)
+    }
+
+    /// Performs the handshake as the host, which answers the client's first message.
+    pub fn host(inner: S, config: &NoiseConfig) -> io::Result<Self> {
+        let handshake = builder()?
+            .local_private_key(&config.private_key)
+            .build_responder()
+            .map_err(io::Error::other)?;
+        Self::handshake(inner, handshake, config, false)
+    }
+
+    /// Returns the public key the remote side authenticated with.
+    pub fn remote_public_key(&self) -> [u8; NOISE_KEY_LEN] {
+        self.remote_public_key
+    }
+
+    fn handshake(
+        mut inner: S,
+        mut handshake: HandshakeState,
+        config: &NoiseConfig,
+        mut writing: bool,
+    ) -> io::Result<Self> {
+        let mut message = vec![0; MAX_MESSAGE_LEN];
+        let mut payload = vec![0; MAX_MESSAGE_LEN];
+        // Bytes read past the last handshake message are the start of the first transport message
+        let mut incoming = vec![];
+        while !handshake.is_handshake_finished() {
+            if writing {
+                let len = handshake
+                    .write_message(&[], &mut message)
+                    .map_err(invalid_data)?;
+                write_message(&mut inner, &message[..len])?;
+            } else {
+                let message = read_message(&mut inner, &mut incoming)?;
+                handshake
+                    .read_message(&message, &mut payload)
+                    .map_err(invalid_data)?;
+            }
+            writing = !writing;
+        }
+
+        let remote_public_key = key(handshake.get_remote_static().unwrap_or_default())?;
+        if config
+            .remote_public_key
+            .is_some_and(|expected| expected != remote_public_key)
+        {
+            return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                "Remote side authenticated with an unexpected public key",
+            ));
+        }
+        Ok(Self {
+            inner,
+            transport: handshake.into_transport_mode().map_err(invalid_data)?,
+            remote_public_key,
+            incoming,
+            decrypted: vec![],
+        })
+    }
+}
+
+impl<S: Read + Write> Read for NoiseStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.decrypted.is_empty() {
+            let message = match read_message(&mut self.inner, &mut self.incoming) {
+                Ok(message) => message,
+                // The connection closed between two messages
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof && self.incoming.is_empty() => {
+                    return Ok(0)
+                }
+                Err(e) => return Err(e),
+            };
+            let mut payload = vec![0; message.len()];
+            let len = self
+                .transport
+                .read_message(&message, &mut payload)
+                .map_err(invalid_data)?;
+            payload.truncate(len);
+            self.decrypted = payload;
+        }
+
+        let len = buf.len().min(self.decrypted.len());
+        buf[..len].copy_from_slice(&self.decrypted[..len]);
+        self.decrypted.drain(..len);
+        Ok(len)
+    }
+}
+
+impl<S: Read + Write> Write for NoiseStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(MAX_MESSAGE_LEN - TAG_LEN);
+        let mut message = vec![0; len + TAG_LEN];
+        let written = self
+            .transport
+            .write_message(&buf[..len], &mut message)
+            .map_err(io::Error::other)?;
+        write_message(&mut self.inner, &message[..written])?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: GameStream> GameStream for NoiseStream<S> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_write_timeout(timeout)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.inner.shutdown()
+    }
+}
+
+fn builder() -> io::Result<Builder<'static>> {
+    let params = NOISE_PARAMS.parse().map_err(io::Error::other)?;
+    Ok(Builder::new(params))
+}
+
+fn key(bytes: &[u8]) -> io::Result<[u8; NOISE_KEY_LEN]> {
+    bytes
+        .try_into()
+        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid Noise key length"))
+}
+
+fn invalid_data(e: snow::Error) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+fn write_message(stream: &mut impl Write, message: &[u8]) -> io::Result<()> {
+    // Messages are at most 65535 bytes long, so their length fits
+    let len = message.len() as u16;
+    let mut framed = len.to_be_bytes().to_vec();
+    framed.extend_from_slice(message);
+    stream.write_all(&framed)
+}
+
+/// Reads the next message into `incoming`, which keeps the part already read if reading fails,
+/// and returns it.
+fn read_message(stream: &mut impl Read, incoming: &mut Vec<u8>) -> io::Result<Vec<u8>> {
+    loop {
+        if let Some(&[a, b]) = incoming.get(0..2) {
+            let len = usize::from(u16::from_be_bytes([a, b]));
+            if incoming.len() >= 2 + len {
+                let message = incoming[2..2 + len].to_vec();
+                incoming.drain(..2 + len);
+                return Ok(message);
+            }
+        }
+
+        let mut buf = [0; 1024];
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        incoming.extend_from_slice(&buf[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::BufReader,
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn peers_authenticate_with_expected_keys() {
+        let host_keys = NoiseKeypair::generate().unwrap();
+        let client_keys = NoiseKeypair::generate().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let host_config = NoiseConfig {
+            private_key: host_keys.private_key,
+            remote_public_key: Some(client_keys.public_key),
+        };
+        let host = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let mut stream = NoiseStream::host(socket, &host_config).unwrap();
+            stream.write_all(b"hello").unwrap();
+            // A client with an unexpected key is refused
+            let (socket, _) = listener.accept().unwrap();
+            NoiseStream::host(socket, &host_config).unwrap_err().kind()
+        });
+
+        let config = NoiseConfig {
+            private_key: client_keys.private_key,
+            remote_public_key: Some(host_keys.public_key),
+        };
+        let stream = NoiseStream::client(TcpStream::connect(addr).unwrap(), &config).unwrap();
+        assert_eq!(stream.remote_public_key(), host_keys.public_key);
+        let mut line = String::new();
+        BufReader::new(stream)
+            .take(5)
+            .read_to_string(&mut line)
+            .unwrap();
+        assert_eq!(line, "hello");
+
+        let other = NoiseConfig {
+            private_key: NoiseKeypair::generate().unwrap().private_key,
+            remote_public_key: None,
+        };
+        let _ = NoiseStream::client(TcpStream::connect(addr).unwrap(), &other);
+        assert_eq!(host.join().unwrap(), ErrorKind::PermissionDenied);
+    }
+}
@@ -0,0 +1,166 @@
+//! Bots playing with a policy network in the ONNX format, see [`OnnxPlayer`]. Only available with
+//! the `onnx` feature.
+//!
+//! # Network
+//! Networks take a `[1, cells]` tensor of `f32`: the board in reading order, with 1 for the bot's
+//! marks, -1 for any other mark and 0 for empty cells. They return a `[1, cells]` tensor scoring
+//! each cell, and the bot plays the legal cell scoring the most. A network is made for one board
+//! size, so a network trained on a 5x5 board plays 5x5 variants, whatever their win length.
+//!
+//! Networks are run with [tract](https://github.com/sonos/tract), which supports most operators
+//! exported by PyTorch and TensorFlow, without any native library.
+
+use std::{
+    fmt::Debug,
+    io::{self, ErrorKind, Read},
+    path::Path,
+};
+
+use tract_onnx::prelude::{
+    tract_ndarray::Array2, tvec, Framework, InferenceModelExt, Tensor, TypedModel,
+    TypedRunnableModel,
+};
+
+use crate::{
+    board::Board,
+    grid::Mark,
+    player::{Move, Player, PlayerError},
+};
+
+/// Bot choosing its moves with a policy network, see the [module documentation](self). Plays on
+/// any board with as many cells as the network's input.
+pub struct OnnxPlayer {
+    model: TypedRunnableModel<TypedModel>,
+    cells: usize,
+}
+
+impl Debug for OnnxPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnnxPlayer")
+            .field("cells", &self.cells)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OnnxPlayer {
+    /// Loads the network saved at `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(&mut std::fs::File::open(path)?)
+    }
+
+    /// Loads a network from the bytes of an ONNX file.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_reader(&mut &*bytes)
+    }
+
+    /// Loads a network, failing with [`ErrorKind::InvalidData`] if it can't be run or its input
+    /// isn't a `[1, cells]` tensor.
+    fn from_reader(reader: &mut dyn Read) -> io::Result<Self> {
+        let model = tract_onnx::onnx()
+            .model_for_read(reader)
+            .and_then(|model| model.into_optimized())
+            .map_err(invalid_network)?;
+        let cells = match model.input_fact(0).map(|fact| fact.shape.as_concrete()) {
+            Ok(Some(&[1, cells])) => cells,
+            _ => return Err(invalid_network("Expected a [1, cells] input")),
+        };
+        let model = model.into_runnable().map_err(invalid_network)?;
+        Ok(Self { model, cells })
+    }
+
+    /// Number of cells of the boards the network plays on.
+    pub fn cells(&self) -> usize {
+        self.cells
+    }
+
+    /// Returns the network's score of each cell of `board`, for `mark` to play.
+    fn scores(&self, board: &impl Board, mark: &Mark) -> io::Result<Vec<f32>> {
+        let (rows, cols) = board.dimensions();
+        if rows * cols != self.cells {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The network plays on boards of {} cells, not {}x{}",
+                    self.cells, rows, cols
+                ),
+            ));
+        }
+
+        let input: Vec<f32> = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| board.mark(row, col)))
+            .map(|cell| match cell {
+                Some(m) if m == *mark => 1.0,
+                Some(_) => -1.0,
+                None => 0.0,
+            })
+            .collect();
+        let input = Array2::from_shape_vec((1, self.cells), input).map_err(invalid_network)?;
+        let output = self
+            .model
+            .run(tvec!(Tensor::from(input).into()))
+            .map_err(invalid_network)?;
+        let scores = output[0].to_array_view::<f32>().map_err(invalid_network)?;
+        if scores.len() != self.cells {
+            return Err(invalid_network("Expected a [1, cells] output"));
+        }
+        Ok(scores.iter().copied().collect())
+    }
+}
+
+impl<B: Board> Player<B> for OnnxPlayer {
+    fn get_move(&self, board: &B, mark: &Mark) -> Result<Move, PlayerError> {
+        let scores = self.scores(board, mark)?;
+        let (_, cols) = board.dimensions();
+        let (row, col) = board
+            .legal_moves()
+            .into_iter()
+            .max_by(|&(a_row, a_col), &(b_row, b_col)| {
+                let a = scores[a_row * cols + a_col];
+                let b = scores[b_row * cols + b_col];
+                // The first cell in reading order wins ties
+                a.total_cmp(&b).then((b_row, b_col).cmp(&(a_row, a_col)))
+            })
+            .ok_or(PlayerError::EndOfInput)?;
+        Ok(Move::Place(row, col))
+    }
+}
+
+fn invalid_network(error: impl ToString) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::SquareBoard, grid::Grid};
+
+    use super::*;
+
+    /// Written by `tests/data/make_policy.py`: prefers the center, then the corners, and cells next
+    /// to the bot's marks.
+    const POLICY: &[u8] = include_bytes!("../tests/data/policy.onnx");
+
+    #[test]
+    fn networks_choose_the_moves() {
+        let player = OnnxPlayer::from_bytes(POLICY).unwrap();
+        assert_eq!(player.cells(), 9);
+        let mut grid = Grid::default();
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(1, 1));
+
+        // Corners score higher, and the free one next to O's mark the most
+        grid.set_cell(1, 1, Mark::X);
+        grid.set_cell(0, 1, Mark::O);
+        grid.set_cell(0, 0, Mark::X);
+        assert_eq!(player.get_move(&grid, &Mark::O).unwrap(), Move::Place(0, 2));
+    }
+
+    #[test]
+    fn networks_only_play_their_board_size() {
+        let player = OnnxPlayer::from_bytes(POLICY).unwrap();
+        let board = SquareBoard::new(4, 3);
+        let err = player.get_move(&board, &Mark::X).unwrap_err();
+        assert!(matches!(err, PlayerError::Io(e) if e.kind() == ErrorKind::InvalidInput));
+
+        let err = OnnxPlayer::from_bytes(b"not a network").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
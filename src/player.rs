@@ -1,89 +1,304 @@
 use std::{
-    fmt::Debug,
-    io::{self, BufRead, Write},
+    error::Error,
+    fmt::{Debug, Display},
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
 };
 
-use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
 
-use crate::grid::{Grid, Mark};
+use crate::{
+    board::{Board, SquareBoard},
+    game::PlayedMove,
+    grid::{Grid, Mark},
+    search,
+};
 
-pub trait Player: Debug {
-    // Gets the player's next move. Strategy dependent on player implementation.
-    fn get_move(&self, grid: &Grid, mark: &Mark) -> (usize, usize);
+#[cfg(feature = "cli")]
+pub use self::local::*;
+pub use self::mirror::*;
+
+#[cfg(feature = "cli")]
+mod local;
+mod mirror;
+
+/// A move chosen by a player on their turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    /// Place the player's mark on the cell at `(row, col)`.
+    Place(usize, usize),
+    /// Give up the game, handing the win to the opponent.
+    Resign,
+    /// Propose to end the game in a draw. If the opponent declines, the player still has to move.
+    OfferDraw,
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct LocalPlayer;
-
-impl LocalPlayer {
-    /// Reads from stdin until we receive a number between 1 and 3
-    fn stdin_read_valid_number(&self, prompt: impl AsRef<str>) -> usize {
-        let mut stdin = io::stdin().lock();
-        let mut buffer = String::new();
-        loop {
-            println!("{}", prompt.as_ref());
-            print!("Enter a number [1-3]: ");
-            io::stdout().flush().unwrap();
-            stdin
-                .read_line(&mut buffer)
-                .expect("Error reading from stdin");
-
-            if let Ok(i) = buffer.trim().parse::<usize>() {
-                if (1..=3).contains(&i) {
-                    return i;
-                }
-            }
+/// Error returned by a player unable to choose a move or answer a draw offer.
+#[derive(Debug)]
+pub enum PlayerError {
+    /// The player's input source ran out before they could answer.
+    EndOfInput,
+    Io(io::Error),
+}
 
-            println!("Invalid value");
-            buffer = String::new();
+impl Display for PlayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EndOfInput => write!(f, "Player input ended"),
+            Self::Io(e) => write!(f, "IO error while reading player input: {}", e),
         }
     }
 }
 
-impl Player for LocalPlayer {
-    /// Asks the player to enter their next move.
-    fn get_move(&self, grid: &Grid, _: &Mark) -> (usize, usize) {
-        loop {
-            let row = self.stdin_read_valid_number("Select a row") - 1;
-            let col = self.stdin_read_valid_number("Select a column") - 1;
-
-            if !grid.get_cell(row, col).is_empty() {
-                println!("Invalid cell, already in use");
-            } else {
-                return (row, col);
-            }
+impl Error for PlayerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::EndOfInput => None,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl From<io::Error> for PlayerError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// A player able to play on boards of type `B`, the classic [`Grid`] by default. Players are `Send`
+/// and `Sync`, so games can be played from worker threads, and a player can be shared between
+/// several games.
+pub trait Player<B: Board = Grid>: Debug + Send + Sync {
+    // Gets the player's next move. Strategy dependent on player implementation.
+    fn get_move(&self, board: &B, mark: &Mark) -> Result<Move, PlayerError>;
+
+    /// Gets the player's next move, knowing the marks still playing: `turns` lists them in the
+    /// order they play, starting with `mark`. Games ask their players through this method, which
+    /// falls back to [`Player::get_move`] for players who don't look ahead.
+    fn get_move_with_turns(
+        &self,
+        board: &B,
+        mark: &Mark,
+        _turns: &[Mark],
+    ) -> Result<Move, PlayerError> {
+        self.get_move(board, mark)
+    }
+
+    /// Called when the opponent offers a draw, returns true if the player accepts it. Declines by
+    /// default.
+    fn accept_draw(&self, _board: &B, _mark: &Mark) -> Result<bool, PlayerError> {
+        Ok(false)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BotPlayerDifficulty {
     Easy,
     Normal,
     Impossible,
+    /// Plays the optimal move the given percentage of the time, and a random move otherwise.
+    Strength(u8),
+    /// Plays the optimal move, except the given percentage of the time where it plays the
+    /// second-best move instead. Between `Normal` and `Impossible` for low percentages.
+    Noisy(u8),
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct BotPlayer(BotPlayerDifficulty);
+/// Style of a bot, deciding which cells it favors whenever its difficulty leaves it a choice: moves
+/// it would otherwise pick randomly, and equally good moves at the `Impossible` difficulty.
+///
+/// Personalities only apply to the classic [`Grid`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BotPersonality {
+    /// No preference, picks randomly.
+    #[default]
+    Balanced,
+    /// Favors the center, then edges.
+    CenterHugger,
+    /// Favors corners, then the center.
+    CornerLover,
+    /// Favors moves creating threats, ideally 2 at once.
+    ForkSeeker,
+    /// Favors the cells the opponent would benefit the most from.
+    Blocker,
+}
+
+impl BotPersonality {
+    /// Scores how much a bot playing `mark` likes playing on `(row, col)`, higher is better.
+    fn score(&self, grid: &Grid, mark: &Mark, (row, col): (usize, usize)) -> usize {
+        let is_center = (row, col) == (1, 1);
+        let is_corner = row != 1 && col != 1;
+        match self {
+            Self::Balanced => 0,
+            Self::CenterHugger if is_center => 2,
+            Self::CenterHugger if is_corner => 0,
+            Self::CenterHugger => 1,
+            Self::CornerLover if is_corner => 2,
+            Self::CornerLover if is_center => 1,
+            Self::CornerLover => 0,
+            Self::ForkSeeker => threats_after(grid, mark, (row, col)),
+            Self::Blocker => {
+                let opponent = mark.opposite();
+                let mut next = *grid;
+                next.set_cell(row, col, opponent);
+                if next.get_winning_mark() == Some(opponent) {
+                    // Blocking a line beats denying any number of threats
+                    9
+                } else {
+                    threats_after(grid, &opponent, (row, col))
+                }
+            }
+        }
+    }
+
+    /// Picks the move this personality likes the most among `moves`, breaking ties randomly.
+    fn pick(
+        &self,
+        grid: &Grid,
+        mark: &Mark,
+        moves: &[(usize, usize)],
+        rng: &mut dyn RngCore,
+    ) -> (usize, usize) {
+        let best = moves
+            .iter()
+            .map(|&m| self.score(grid, mark, m))
+            .max()
+            .expect("No move to pick from");
+        let favorites: Vec<_> = moves
+            .iter()
+            .filter(|&&m| self.score(grid, mark, m) == best)
+            .collect();
+        **favorites.choose(rng).unwrap()
+    }
+}
+
+/// Number of cells where `mark` could complete a line after playing on `(row, col)`.
+fn threats_after(grid: &Grid, mark: &Mark, (row, col): (usize, usize)) -> usize {
+    let mut next = *grid;
+    next.set_cell(row, col, *mark);
+    next.legal_moves()
+        .into_iter()
+        .filter(|&(r, c)| {
+            let mut after = next;
+            after.set_cell(r, c, *mark);
+            after.get_winning_mark() == Some(*mark)
+        })
+        .count()
+}
+
+#[derive(Debug)]
+pub struct BotPlayer {
+    difficulty: BotPlayerDifficulty,
+    personality: BotPersonality,
+    /// Generator of the bot's random choices once seeded, the thread's generator is used otherwise
+    rng: Option<Mutex<StdRng>>,
+}
+
+/// Clones start from the same state of the generator, so they make the same choices.
+impl Clone for BotPlayer {
+    fn clone(&self) -> Self {
+        Self {
+            difficulty: self.difficulty,
+            personality: self.personality,
+            rng: self
+                .rng
+                .as_ref()
+                .map(|rng| Mutex::new(rng.lock().unwrap().clone())),
+        }
+    }
+}
 
 impl BotPlayer {
     pub fn easy() -> Self {
-        Self(BotPlayerDifficulty::Easy)
+        Self::from_difficulty(BotPlayerDifficulty::Easy)
     }
 
     pub fn normal() -> Self {
-        Self(BotPlayerDifficulty::Normal)
+        Self::from_difficulty(BotPlayerDifficulty::Normal)
     }
     pub fn impossible() -> Self {
-        Self(BotPlayerDifficulty::Impossible)
+        Self::from_difficulty(BotPlayerDifficulty::Impossible)
+    }
+
+    /// Creates a bot playing the optimal move `strength`% of the time, and a random move otherwise.
+    /// A strength of 0 plays like [`BotPlayer::easy`], and 100 like [`BotPlayer::impossible`].
+    ///
+    /// # Panics
+    /// Panics if `strength` is greater than 100.
+    pub fn with_strength(strength: u8) -> Self {
+        assert!(strength <= 100, "Strength must be between 0 and 100");
+        Self::from_difficulty(BotPlayerDifficulty::Strength(strength))
+    }
+
+    /// Creates a bot playing the second-best move `error_rate`% of the time, and the optimal move
+    /// otherwise. An error rate of 0 plays like [`BotPlayer::impossible`].
+    ///
+    /// # Panics
+    /// Panics if `error_rate` is greater than 100.
+    pub fn with_error_rate(error_rate: u8) -> Self {
+        assert!(error_rate <= 100, "Error rate must be between 0 and 100");
+        Self::from_difficulty(BotPlayerDifficulty::Noisy(error_rate))
     }
 
     pub fn from_difficulty(diff: BotPlayerDifficulty) -> Self {
-        Self(diff)
+        Self {
+            difficulty: diff,
+            personality: BotPersonality::default(),
+            rng: None,
+        }
+    }
+
+    /// Draws the bot's random choices from a generator seeded with `seed`, so it plays the same
+    /// moves again when given the same seed and the same positions.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Some(Mutex::new(StdRng::seed_from_u64(seed)));
+        self
+    }
+
+    /// Sets the bot's personality, which doesn't change its difficulty.
+    pub fn with_personality(mut self, personality: BotPersonality) -> Self {
+        self.personality = personality;
+        self
+    }
+
+    pub fn personality(&self) -> BotPersonality {
+        self.personality
+    }
+
+    /// Move played when the difficulty doesn't dictate one: random for balanced bots, or following
+    /// the bot's personality.
+    fn casual_move(&self, grid: &Grid, mark: &Mark, rng: &mut dyn RngCore) -> (usize, usize) {
+        match self.personality {
+            BotPersonality::Balanced => BotPlayer::random_move(grid, rng),
+            personality => personality.pick(grid, mark, &grid.legal_moves(), rng),
+        }
+    }
+
+    /// Optimal move, picked according to the bot's personality when several are equally good.
+    fn best_move(&self, grid: &Grid, mark: &Mark, rng: &mut dyn RngCore) -> (usize, usize) {
+        match self.personality {
+            BotPersonality::Balanced => BotPlayer::minimax_move(grid, mark),
+            personality => personality.pick(grid, mark, &BotPlayer::minimax_moves(grid, mark), rng),
+        }
+    }
+
+    /// Calls `f` with the bot's generator if it was seeded, or with the thread's generator.
+    fn with_rng<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        match &self.rng {
+            Some(rng) => f(&mut *rng.lock().unwrap()),
+            None => f(&mut rand::thread_rng()),
+        }
+    }
+
+    /// Returns true if a bot of the given strength should play its best move this turn.
+    fn plays_best_move(strength: u8, rng: &mut dyn RngCore) -> bool {
+        rng.gen_ratio(strength.min(100).into(), 100)
     }
 
     /// Chooses a random free cell in the game's grid.
-    fn random_move(grid: &Grid) -> (usize, usize) {
+    fn random_move(grid: &Grid, rng: &mut dyn RngCore) -> (usize, usize) {
         // Strategy: randomly choose a free cell
         let mut indexes: Vec<(usize, usize)> = Vec::with_capacity(3 * 3);
         for r in 0..3 {
@@ -92,7 +307,7 @@ impl BotPlayer {
             }
         }
         let indexes: &mut [(usize, usize)] = &mut indexes;
-        indexes.shuffle(&mut rand::thread_rng());
+        indexes.shuffle(rng);
 
         for (row, col) in indexes {
             if grid.get_cell(*row, *col).is_empty() {
@@ -102,6 +317,41 @@ impl BotPlayer {
         panic!("Grid did not have any empty cells.");
     }
 
+    /// Chooses a random legal move on any board.
+    fn random_legal_move(board: &impl Board, rng: &mut dyn RngCore) -> (usize, usize) {
+        *board
+            .legal_moves()
+            .choose(rng)
+            .expect("Board did not have any empty cells.")
+    }
+
+    /// Board agnostic version of [`BotPlayer::detect_near_win`]: tries every legal move for `mark`
+    /// and returns the first one completing a line.
+    fn find_winning_move<B: Board>(board: &B, mark: &Mark) -> Option<(usize, usize)> {
+        board.legal_moves().into_iter().find(|&(row, col)| {
+            let mut board = board.clone();
+            board.place(row, col, *mark).is_ok() && board.winner() == Some(*mark)
+        })
+    }
+
+    /// Empty cells left from which two-player games on larger boards are searched to the end.
+    const SOLVE_LIMIT: usize = 9;
+
+    /// Random playouts of the tree search choosing moves on larger boards.
+    const SEARCH_ITERATIONS: usize = 1000;
+
+    /// Searches the game tree of `board` for the best move of the first mark of `turns`, to the end
+    /// once few cells are left in a two-player game, and with a Monte Carlo tree search otherwise.
+    fn searched_move(board: &SquareBoard, turns: &[Mark], rng: &mut dyn RngCore) -> (usize, usize) {
+        let searched = match *turns {
+            [mark, opponent] if board.legal_moves().len() <= Self::SOLVE_LIMIT => {
+                search::best_move(board, mark, opponent)
+            }
+            _ => search::mcts_move(board, turns, Self::SEARCH_ITERATIONS, rng),
+        };
+        searched.unwrap_or_else(|| BotPlayer::random_legal_move(board, rng))
+    }
+
     /// Detects if the player playing with `mark` can win in 1 move. If so, returns the position of
     /// their next winning move.
     fn detect_near_win(grid: &Grid, mark: &Mark) -> Option<(usize, usize)> {
@@ -206,135 +456,197 @@ impl BotPlayer {
         None
     }
 
-    /// Plays the optimal move every time
-    ///
-    /// # Playing first
-    /// 1.  Play a corner.
-    /// 2.  Opponent doesn't play in the middle cell:
-    ///     1. Play the other corner of the unblocked edge.
-    ///     2. Win, or play in the corner that sees your 2 other cells.
-    ///     3. Play the remaining winning move.
-    /// 3.  Opponent plays in the middle cell:
-    ///     1. Play the opposite corner from the 1st move.
-    ///     2. Try to win or block the opponent's move.
-    ///     3. Repeat until draw.
-    ///
-    /// # Playing second
-    /// 1. Opponent starts in a corner.
-    ///     1. Play the center cell.
-    ///     2. Block the move, or choose an edge cell (NOT a corner)
-    ///     3. Try to win, otherwise block.
-    /// 2. Opponent starts in the center.
-    ///     1. Play a corner.
-    ///     2. Try to win, otherwise block.
-    /// 3. Opponent starts on an edge
-    ///     1. Play the center cell.
-    ///     2. If they block opposite to the center (row or col == XOX), play a corner, otherwise
-    ///        block.
-    ///     3. Try to win, otherwise block.
-    fn perfect_move(grid: &Grid, mark: &Mark) -> (usize, usize) {
-        match grid.cell_count() {
-            0 => {
-                // We have the first move
-                (0, 0)
-            }
-            1 => {
-                // We have the second move; play center if free, corner otherwise
-                if grid.get_cell(1, 1).is_empty() {
-                    (1, 1)
-                } else {
-                    (0, 0)
+    /// Plays one of the best moves among those worse than optimal, preferring them like
+    /// [`BotPlayer::best_move`] does. Plays an optimal move when every move is as good.
+    fn second_best_move(&self, grid: &Grid, mark: &Mark, rng: &mut dyn RngCore) -> (usize, usize) {
+        let scored = search::scored_moves(grid, *mark, mark.opposite());
+        let best = scored.iter().map(|&(_, score)| score).max();
+        let second = scored
+            .iter()
+            .map(|&(_, score)| score)
+            .filter(|&score| Some(score) < best)
+            .max();
+        let Some(second) = second else {
+            return self.best_move(grid, mark, rng);
+        };
+
+        let moves: Vec<_> = scored
+            .into_iter()
+            .filter(|&(_, score)| score == second)
+            .map(|(cell, _)| cell)
+            .collect();
+        match self.personality {
+            BotPersonality::Balanced => moves[0],
+            personality => personality.pick(grid, mark, &moves, rng),
+        }
+    }
+
+    /// Returns every optimal move for `mark`, see [`BotPlayer::minimax_move`].
+    fn minimax_moves(grid: &Grid, mark: &Mark) -> Vec<(usize, usize)> {
+        search::best_moves(grid, *mark, mark.opposite())
+    }
+
+    /// Plays the optimal move every time, by searching the whole game tree from the current
+    /// position. Among equally good moves, the fastest win (or slowest loss) is preferred, then the
+    /// first cell in reading order.
+    pub(crate) fn minimax_move(grid: &Grid, mark: &Mark) -> (usize, usize) {
+        search::best_move(grid, *mark, mark.opposite()).expect("Grid did not have any empty cells.")
+    }
+
+    /// Scores `grid` for `mark`, who is about to play: positive if they can force a win, negative
+    /// if their opponent can, and 0 if perfect play leads to a draw.
+    pub(crate) fn minimax_score(grid: &Grid, mark: &Mark) -> i32 {
+        search::solve(grid, *mark, mark.opposite())
+    }
+}
+
+impl Player for BotPlayer {
+    fn get_move(&self, grid: &Grid, mark: &Mark) -> Result<Move, PlayerError> {
+        let (row, col) = self.with_rng(|rng| match self.difficulty {
+            // Strategy: randomly choose a free cell
+            BotPlayerDifficulty::Easy => self.casual_move(grid, mark, rng),
+            // Strategy: block winning move if found, otherwise revert to random
+            BotPlayerDifficulty::Normal => {
+                match BotPlayer::detect_near_win(grid, &mark.opposite()) {
+                    Some(pos) => pos,
+                    None => self.casual_move(grid, mark, rng),
                 }
             }
-            2 => {
-                // 2nd move (we played first)
-                if grid.get_cell(1, 1).is_empty() {
-                    // 1. Play the other corner of the unblocked edge
-                    if grid.get_cell(0, 1).is_empty() && grid.get_cell(0, 2).is_empty() {
-                        (0, 2)
-                    } else {
-                        (2, 0)
-                    }
+            BotPlayerDifficulty::Impossible => self.best_move(grid, mark, rng),
+            BotPlayerDifficulty::Strength(strength) => {
+                if BotPlayer::plays_best_move(strength, rng) {
+                    self.best_move(grid, mark, rng)
                 } else {
-                    // 1. Play the opposite corner from the 1st move.
-                    (2, 2)
+                    self.casual_move(grid, mark, rng)
                 }
             }
-            3 => {
-                // 2nd move (we played 2nd)
-                if let Some(block) = Self::detect_near_win(grid, &mark.opposite()) {
-                    block
-                } else if grid.get_cell(1, 1).try_get_mark() == Some(mark)
-                    && ((grid.get_cell(0, 1).try_get_mark() == Some(&mark.opposite())
-                        && grid.get_cell(2, 1).try_get_mark() == Some(&mark.opposite()))
-                        || (grid.get_cell(1, 0).try_get_mark() == Some(&mark.opposite())
-                            && grid.get_cell(1, 2).try_get_mark() == Some(&mark.opposite())))
-                {
-                    // XOX edgecase: we have center, they have 2 cells opposite of the center; play
-                    // a corner
-                    (0, 0)
+            BotPlayerDifficulty::Noisy(error_rate) => {
+                if BotPlayer::plays_best_move(100 - error_rate.min(100), rng) {
+                    self.best_move(grid, mark, rng)
                 } else {
-                    // Play a non-corner cell
-                    if grid.get_cell(0, 1).is_empty() {
-                        (0, 1)
-                    } else if (grid.get_cell(1, 0)).is_empty() {
-                        (1, 0)
-                    } else {
-                        (1, 2)
-                    }
+                    self.second_best_move(grid, mark, rng)
                 }
             }
-            4 => {
-                // 3rd move (we played first)
-                if grid.get_cell(1, 1).is_empty() {
-                    // 2. Win, or play in the corner that sees your 2 other cells.
-                    if let Some(win) = Self::detect_near_win(grid, mark) {
-                        win
-                    } else {
-                        // Figure out which of the free corner sees our 2 other corners
-                        // Either the diagonal (2, 2), or if not empty, then only 1 corner should remain
-                        if grid.get_cell(2, 2).is_empty() {
-                            (2, 2)
-                        } else if grid.get_cell(0, 2).is_empty() {
-                            (0, 2)
-                        } else {
-                            (2, 0)
-                        }
-                    }
+        });
+        Ok(Move::Place(row, col))
+    }
+
+    /// Bots only accept a draw once neither side can win anymore.
+    fn accept_draw(&self, grid: &Grid, _: &Mark) -> Result<bool, PlayerError> {
+        Ok(grid.is_draw_certain())
+    }
+}
+
+impl Player<SquareBoard> for BotPlayer {
+    /// Without knowing the game, `Y` is assumed to play in a three-player game and the other marks
+    /// in a two-player game.
+    fn get_move(&self, board: &SquareBoard, mark: &Mark) -> Result<Move, PlayerError> {
+        let players = if *mark == Mark::Y { 3 } else { 2 };
+        self.get_move_with_turns(board, mark, &mark.rotation(players))
+    }
+
+    /// `Impossible` completes its own lines and blocks its opponents' first, then picks its move
+    /// with [`search`], see [`BotPlayer::searched_move`]. Of several opponents about to complete a
+    /// line, the one playing next in `turns` is blocked.
+    fn get_move_with_turns(
+        &self,
+        board: &SquareBoard,
+        mark: &Mark,
+        turns: &[Mark],
+    ) -> Result<Move, PlayerError> {
+        let block = turns
+            .iter()
+            .skip(1)
+            .find_map(|m| BotPlayer::find_winning_move(board, m));
+
+        let best = |rng: &mut dyn RngCore| {
+            BotPlayer::find_winning_move(board, mark)
+                .or(block)
+                .unwrap_or_else(|| BotPlayer::searched_move(board, turns, rng))
+        };
+        let (row, col) = self.with_rng(|rng| match self.difficulty {
+            BotPlayerDifficulty::Easy => BotPlayer::random_legal_move(board, rng),
+            BotPlayerDifficulty::Normal => {
+                block.unwrap_or_else(|| BotPlayer::random_legal_move(board, rng))
+            }
+            BotPlayerDifficulty::Impossible => best(rng),
+            BotPlayerDifficulty::Strength(strength) => {
+                if BotPlayer::plays_best_move(strength, rng) {
+                    best(rng)
                 } else {
-                    Self::detect_near_win(grid, &mark.opposite()).unwrap()
+                    BotPlayer::random_legal_move(board, rng)
                 }
             }
-            x if x > 4 => {
-                // Win or block
-                if let Some(win) = Self::detect_near_win(grid, mark) {
-                    win
-                } else if let Some(block) = Self::detect_near_win(grid, &mark.opposite()) {
-                    block
+            // Moves aren't ranked on larger boards, mistakes are random moves instead
+            BotPlayerDifficulty::Noisy(error_rate) => {
+                if BotPlayer::plays_best_move(100 - error_rate.min(100), rng) {
+                    best(rng)
                 } else {
-                    Self::random_move(grid)
+                    BotPlayer::random_legal_move(board, rng)
                 }
             }
-            _ => unreachable!(),
-        }
+        });
+        Ok(Move::Place(row, col))
     }
 }
 
-impl Player for BotPlayer {
-    fn get_move(&self, grid: &Grid, mark: &Mark) -> (usize, usize) {
-        match self.0 {
-            // Strategy: randomly choose a free cell
-            BotPlayerDifficulty::Easy => BotPlayer::random_move(grid),
-            // Strategy: block winning move if found, otherwise revert to random
-            BotPlayerDifficulty::Normal => {
-                match BotPlayer::detect_near_win(grid, &mark.opposite()) {
-                    Some(pos) => pos,
-                    None => BotPlayer::random_move(grid),
-                }
-            }
-            BotPlayerDifficulty::Impossible => BotPlayer::perfect_move(grid, mark),
+/// Player playing a fixed sequence of moves, on any board. Useful for tests and to play back
+/// recorded games.
+///
+/// Once every move has been played, asking for another one fails with
+/// [`PlayerError::EndOfInput`].
+#[derive(Debug)]
+pub struct ScriptedPlayer {
+    moves: Vec<Move>,
+    next: AtomicUsize,
+    accepts_draws: bool,
+}
+
+impl ScriptedPlayer {
+    /// Creates a player playing `moves` in order. Draw offers are declined.
+    pub fn new(moves: impl IntoIterator<Item = Move>) -> Self {
+        Self {
+            moves: moves.into_iter().collect(),
+            next: AtomicUsize::new(0),
+            accepts_draws: false,
         }
     }
+
+    /// Creates a player replaying the moves `mark` played in `history`, such as
+    /// [`Game::history`](crate::game::Game::history).
+    pub fn from_history(history: &[PlayedMove], mark: Mark) -> Self {
+        Self::new(
+            history
+                .iter()
+                .filter(|played| played.mark == mark)
+                .map(|played| played.player_move),
+        )
+    }
+
+    /// Sets whether the player accepts draw offers.
+    pub fn accepts_draws(mut self, accepts: bool) -> Self {
+        self.accepts_draws = accepts;
+        self
+    }
+
+    /// Number of moves left to play.
+    pub fn remaining(&self) -> usize {
+        self.moves.len() - self.next.load(Ordering::Relaxed).min(self.moves.len())
+    }
+}
+
+impl<B: Board> Player<B> for ScriptedPlayer {
+    fn get_move(&self, _: &B, _: &Mark) -> Result<Move, PlayerError> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        self.moves
+            .get(index)
+            .copied()
+            .ok_or(PlayerError::EndOfInput)
+    }
+
+    fn accept_draw(&self, _: &B, _: &Mark) -> Result<bool, PlayerError> {
+        Ok(self.accepts_draws)
+    }
 }
 
 #[cfg(test)]
@@ -352,9 +664,37 @@ pub mod tests {
         }
     }
 
-    impl Player for MockPlayer {
-        fn get_move(&self, _: &Grid, _: &Mark) -> (usize, usize) {
-            (self.0, self.1)
+    impl<B: Board> Player<B> for MockPlayer {
+        fn get_move(&self, _: &B, _: &Mark) -> Result<Move, PlayerError> {
+            Ok(Move::Place(self.0, self.1))
+        }
+    }
+
+    /// Shares `player` like networked games take their local player.
+    pub fn shared(player: impl Player + 'static) -> std::sync::Arc<dyn Player> {
+        std::sync::Arc::new(player)
+    }
+
+    #[derive(Debug)]
+    pub struct ResigningPlayer;
+
+    impl<B: Board> Player<B> for ResigningPlayer {
+        fn get_move(&self, _: &B, _: &Mark) -> Result<Move, PlayerError> {
+            Ok(Move::Resign)
+        }
+    }
+
+    /// Always offers a draw, and accepts draws offered to it if `self.0` is true.
+    #[derive(Debug)]
+    pub struct DrawingPlayer(pub bool);
+
+    impl<B: Board> Player<B> for DrawingPlayer {
+        fn get_move(&self, _: &B, _: &Mark) -> Result<Move, PlayerError> {
+            Ok(Move::OfferDraw)
+        }
+
+        fn accept_draw(&self, _: &B, _: &Mark) -> Result<bool, PlayerError> {
+            Ok(self.0)
         }
     }
 
@@ -442,18 +782,18 @@ pub mod tests {
     }
 
     #[test]
-    fn perfect_move_x_correct_first_move() {
+    fn minimax_move_x_correct_first_move() {
         // |!| | |
         // | | | |
         // | | | |
         let grid = Grid::default();
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::X);
         assert!(position_is_corner(pos))
     }
 
     #[test]
-    fn perfect_move_x_correct_second_move_o_middle() {
+    fn minimax_move_x_correct_second_move_o_middle() {
         // |X| | |
         // | |O| |
         // | | |!|
@@ -461,12 +801,13 @@ pub mod tests {
         grid.set_cell(0, 0, Mark::X);
         grid.set_cell(1, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
-        assert_eq!(pos, (2, 2))
+        // The search may prefer other moves, as long as they are just as good
+        let moves = BotPlayer::minimax_moves(&grid, &Mark::X);
+        assert!(moves.contains(&(2, 2)))
     }
 
     #[test]
-    fn perfect_move_x_correct_third_move_o_middle() {
+    fn minimax_move_x_correct_third_move_o_middle() {
         // |X|!| |
         // | |O| |
         // | |O|X|
@@ -476,12 +817,12 @@ pub mod tests {
         grid.set_cell(2, 1, Mark::O);
         grid.set_cell(2, 2, Mark::X);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::X);
         assert_eq!(pos, (0, 1))
     }
 
     #[test]
-    fn perfect_move_x_correct_second_move_o_other_1() {
+    fn minimax_move_x_correct_second_move_o_other_1() {
         // |X|O| |
         // | | | |
         // |!| | |
@@ -489,12 +830,12 @@ pub mod tests {
         grid.set_cell(0, 0, Mark::X);
         grid.set_cell(0, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
-        assert_eq!(pos, (2, 0))
+        let moves = BotPlayer::minimax_moves(&grid, &Mark::X);
+        assert!(moves.contains(&(2, 0)))
     }
 
     #[test]
-    fn perfect_move_x_correct_second_move_o_other_2() {
+    fn minimax_move_x_correct_second_move_o_other_2() {
         // |X| |!|
         // |O| | |
         // | | | |
@@ -502,12 +843,12 @@ pub mod tests {
         grid.set_cell(0, 0, Mark::X);
         grid.set_cell(1, 0, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
-        assert_eq!(pos, (0, 2))
+        let moves = BotPlayer::minimax_moves(&grid, &Mark::X);
+        assert!(moves.contains(&(0, 2)))
     }
 
     #[test]
-    fn perfect_move_x_correct_second_move_o_other_3() {
+    fn minimax_move_x_correct_second_move_o_other_3() {
         // |X| |O|
         // | | | |
         // |!| | |
@@ -515,12 +856,12 @@ pub mod tests {
         grid.set_cell(0, 0, Mark::X);
         grid.set_cell(0, 2, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
-        assert_eq!(pos, (2, 0))
+        let moves = BotPlayer::minimax_moves(&grid, &Mark::X);
+        assert!(moves.contains(&(2, 0)))
     }
 
     #[test]
-    fn perfect_move_x_correct_third_move_o_other_1() {
+    fn minimax_move_x_correct_third_move_o_other_1() {
         // |X| |O|
         // |O| | |
         // |X| |!|
@@ -530,12 +871,12 @@ pub mod tests {
         grid.set_cell(0, 2, Mark::O);
         grid.set_cell(1, 0, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::X);
         assert_eq!(pos, (2, 2))
     }
 
     #[test]
-    fn perfect_move_x_correct_third_move_o_other_2() {
+    fn minimax_move_x_correct_third_move_o_other_2() {
         // |X|O|X|
         // |O| | |
         // | | |!|
@@ -545,12 +886,12 @@ pub mod tests {
         grid.set_cell(0, 1, Mark::O);
         grid.set_cell(1, 0, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
-        assert_eq!(pos, (2, 2))
+        let moves = BotPlayer::minimax_moves(&grid, &Mark::X);
+        assert!(moves.contains(&(2, 2)))
     }
 
     #[test]
-    fn perfect_move_x_correct_third_move_o_other_3() {
+    fn minimax_move_x_correct_third_move_o_other_3() {
         // |X|O|X|
         // | | | |
         // |!| |O|
@@ -560,12 +901,12 @@ pub mod tests {
         grid.set_cell(0, 1, Mark::O);
         grid.set_cell(2, 2, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::X);
         assert_eq!(pos, (2, 0))
     }
 
     #[test]
-    fn perfect_move_x_correct_last_move_o_other() {
+    fn minimax_move_x_correct_last_move_o_other() {
         // |X| |O|
         // |O|!| |
         // |X|O|X|
@@ -577,24 +918,24 @@ pub mod tests {
         grid.set_cell(1, 0, Mark::O);
         grid.set_cell(2, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::X);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::X);
         assert_eq!(pos, (1, 1))
     }
 
     #[test]
-    fn perfect_move_o_correct_first_move_x_corner() {
+    fn minimax_move_o_correct_first_move_x_corner() {
         // |X| | |
         // | |!| |
         // | | | |
         let mut grid = Grid::default();
         grid.set_cell(0, 0, Mark::X);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::O);
         assert_eq!(pos, (1, 1))
     }
 
     #[test]
-    fn perfect_move_o_correct_second_move_x_corner_1() {
+    fn minimax_move_o_correct_second_move_x_corner_1() {
         // |X|!| |
         // |!|O|!|
         // | |!|X|
@@ -603,12 +944,12 @@ pub mod tests {
         grid.set_cell(2, 2, Mark::X);
         grid.set_cell(1, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::O);
         assert!(!position_is_corner(pos))
     }
 
     #[test]
-    fn perfect_move_o_correct_second_move_x_corner_2() {
+    fn minimax_move_o_correct_second_move_x_corner_2() {
         // |X|!|X|
         // | |O| |
         // | | | |
@@ -617,12 +958,12 @@ pub mod tests {
         grid.set_cell(0, 2, Mark::X);
         grid.set_cell(1, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::O);
         assert_eq!(pos, (0, 1))
     }
 
     #[test]
-    fn perfect_move_o_correct_second_move_x_corner_3() {
+    fn minimax_move_o_correct_second_move_x_corner_3() {
         // |X| | |
         // |X|O| |
         // |!| | |
@@ -631,36 +972,36 @@ pub mod tests {
         grid.set_cell(1, 0, Mark::X);
         grid.set_cell(1, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::O);
         assert_eq!(pos, (2, 0))
     }
 
     #[test]
-    fn perfect_move_detects_xox_start_row() {
+    fn minimax_move_detects_xox_start_row() {
         // | | | |
         // |X|!| |
         // | | | |
         let mut grid = Grid::default();
         grid.set_cell(1, 0, Mark::X);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
-        assert_eq!(pos, (1, 1))
+        let moves = BotPlayer::minimax_moves(&grid, &Mark::O);
+        assert!(moves.contains(&(1, 1)))
     }
 
     #[test]
-    fn perfect_move_detects_xox_start_col() {
+    fn minimax_move_detects_xox_start_col() {
         // | |X| |
         // | |!| |
         // | | | |
         let mut grid = Grid::default();
         grid.set_cell(0, 1, Mark::X);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
-        assert_eq!(pos, (1, 1))
+        let moves = BotPlayer::minimax_moves(&grid, &Mark::O);
+        assert!(moves.contains(&(1, 1)))
     }
 
     #[test]
-    fn perfect_move_detects_xox_row() {
+    fn minimax_move_detects_xox_row() {
         // |!| |!|
         // |X|O|X|
         // |!| |!|
@@ -669,11 +1010,11 @@ pub mod tests {
         grid.set_cell(1, 2, Mark::X);
         grid.set_cell(1, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::O);
         assert!(position_is_corner(pos))
     }
     #[test]
-    fn perfect_move_detects_xox_col() {
+    fn minimax_move_detects_xox_col() {
         // |!|X|!|
         // | |O| |
         // |!|X|!|
@@ -682,7 +1023,252 @@ pub mod tests {
         grid.set_cell(2, 1, Mark::X);
         grid.set_cell(1, 1, Mark::O);
 
-        let pos = BotPlayer::perfect_move(&grid, &Mark::O);
+        let pos = BotPlayer::minimax_move(&grid, &Mark::O);
         assert!(position_is_corner(pos))
     }
+
+    #[test]
+    fn square_board_bot_blocks_any_opponent() {
+        // Y is one move away from completing the top row of a 5x5 board
+        let mut board = SquareBoard::new(5, 4);
+        for col in 0..3 {
+            board.place(0, col, Mark::Y).unwrap();
+        }
+        board.place(4, 4, Mark::O).unwrap();
+
+        let player = BotPlayer::normal();
+        let turns = [Mark::X, Mark::O, Mark::Y];
+        let m = player
+            .get_move_with_turns(&board, &Mark::X, &turns)
+            .unwrap();
+        assert_eq!(m, Move::Place(0, 3));
+
+        // O is one move away from completing the bottom row too, and is blocked since it plays next
+        for col in 2..4 {
+            board.place(4, col, Mark::O).unwrap();
+        }
+        let m = player
+            .get_move_with_turns(&board, &Mark::X, &turns)
+            .unwrap();
+        assert_eq!(m, Move::Place(4, 1));
+        let turns = [Mark::X, Mark::Y, Mark::O];
+        let m = player
+            .get_move_with_turns(&board, &Mark::X, &turns)
+            .unwrap();
+        assert_eq!(m, Move::Place(0, 3));
+    }
+
+    #[test]
+    fn square_board_impossible_bot_searches_the_game_tree() {
+        // Small boards are solved like the classic grid
+        let player = BotPlayer::impossible().with_seed(0);
+        let board = SquareBoard::new(3, 3);
+        let m = Player::<SquareBoard>::get_move(&player, &board, &Mark::X).unwrap();
+        let (row, col) = BotPlayer::minimax_move(&Grid::default(), &Mark::X);
+        assert_eq!(m, Move::Place(row, col));
+
+        // Y plays after O in three-player games, before it has placed any mark
+        let board = SquareBoard::new(5, 4);
+        let turns = [Mark::X, Mark::O, Mark::Y];
+        let m = player
+            .get_move_with_turns(&board, &Mark::X, &turns)
+            .unwrap();
+        assert!(matches!(m, Move::Place(row, col) if board.mark(row, col).is_none()));
+
+        // Once Y resigned, X and O are solved like a two-player game
+        let mut board = SquareBoard::new(3, 3);
+        board.place(0, 0, Mark::X).unwrap();
+        board.place(1, 1, Mark::Y).unwrap();
+        board.place(2, 2, Mark::O).unwrap();
+        let m = player.get_move_with_turns(&board, &Mark::O, &[Mark::O, Mark::X]);
+        assert!(matches!(m, Ok(Move::Place(row, col)) if board.mark(row, col).is_none()));
+    }
+
+    /// Plays every possible opponent reply against the bot, and checks it never loses a game.
+    fn assert_minimax_never_loses(grid: Grid, to_move: Mark, bot: Mark) {
+        if grid.get_winning_mark().is_some() || grid.is_full() {
+            assert_ne!(grid.get_winning_mark(), Some(bot.opposite()), "{grid}");
+            return;
+        }
+
+        if to_move == bot {
+            let (row, col) = BotPlayer::minimax_move(&grid, &bot);
+            let mut next = grid;
+            next.set_cell(row, col, bot);
+            assert_minimax_never_loses(next, to_move.opposite(), bot);
+        } else {
+            for (row, col) in grid.legal_moves() {
+                let mut next = grid;
+                next.set_cell(row, col, to_move);
+                assert_minimax_never_loses(next, to_move.opposite(), bot);
+            }
+        }
+    }
+
+    #[test]
+    fn minimax_move_never_loses() {
+        assert_minimax_never_loses(Grid::default(), Mark::X, Mark::X);
+        assert_minimax_never_loses(Grid::default(), Mark::X, Mark::O);
+    }
+
+    #[test]
+    fn minimax_move_prefers_winning_to_blocking() {
+        // |X|X|!|
+        // |O|O| |
+        // | | | |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 0, Mark::O);
+        grid.set_cell(1, 1, Mark::O);
+
+        assert_eq!(BotPlayer::minimax_move(&grid, &Mark::X), (0, 2));
+    }
+
+    #[test]
+    fn full_strength_plays_optimal_moves() {
+        // |X|X|!|
+        // |O|O| |
+        // | | | |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 0, Mark::O);
+        grid.set_cell(1, 1, Mark::O);
+
+        let player = BotPlayer::with_strength(100);
+        for _ in 0..20 {
+            assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(0, 2));
+        }
+    }
+
+    #[test]
+    fn noisy_bots_play_the_second_best_move() {
+        // |X|X|!|
+        // |O|O|?|
+        // | | | |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 0, Mark::O);
+        grid.set_cell(1, 1, Mark::O);
+
+        // Blocking O is second best to winning, any other move loses
+        let player = BotPlayer::with_error_rate(100);
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(1, 2));
+        let player = BotPlayer::with_error_rate(0);
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(0, 2));
+
+        // Only one move is left, it is played even though it isn't a mistake
+        grid.set_cell(1, 2, Mark::X);
+        grid.set_cell(0, 2, Mark::O);
+        grid.set_cell(2, 0, Mark::X);
+        grid.set_cell(2, 1, Mark::O);
+        let player = BotPlayer::with_error_rate(100);
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(2, 2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn strength_above_100_panics() {
+        BotPlayer::with_strength(101);
+    }
+
+    #[test]
+    fn scripted_player_plays_moves_in_order() {
+        let player = ScriptedPlayer::new([Move::Place(0, 0), Move::OfferDraw, Move::Resign]);
+        let grid = Grid::default();
+
+        assert_eq!(player.remaining(), 3);
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(0, 0));
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::OfferDraw);
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Resign);
+        assert_eq!(player.remaining(), 0);
+        assert!(matches!(
+            player.get_move(&grid, &Mark::X),
+            Err(PlayerError::EndOfInput)
+        ));
+    }
+
+    #[test]
+    fn seeded_bots_repeat_their_moves() {
+        let play = |bot: BotPlayer| {
+            let mut grid = Grid::default();
+            let mut moves = vec![];
+            while !grid.is_full() {
+                let Ok(Move::Place(row, col)) = bot.get_move(&grid, &Mark::X) else {
+                    panic!("Bot didn't place a mark");
+                };
+                grid.set_cell(row, col, Mark::X);
+                moves.push((row, col));
+            }
+            moves
+        };
+
+        let moves = play(BotPlayer::easy().with_seed(42));
+        assert_eq!(play(BotPlayer::easy().with_seed(42)), moves);
+        assert_ne!(play(BotPlayer::easy().with_seed(43)), moves);
+        // Clones start from the same state
+        let bot = BotPlayer::easy().with_seed(7);
+        bot.get_move(&Grid::default(), &Mark::X).unwrap();
+        assert_eq!(play(bot.clone()), play(bot));
+    }
+
+    #[test]
+    fn personalities_favor_their_cells() {
+        let grid = Grid::default();
+        let center = BotPlayer::easy().with_personality(BotPersonality::CenterHugger);
+        let corner = BotPlayer::easy().with_personality(BotPersonality::CornerLover);
+        for _ in 0..10 {
+            assert_eq!(center.get_move(&grid, &Mark::X).unwrap(), Move::Place(1, 1));
+            let Move::Place(row, col) = corner.get_move(&grid, &Mark::X).unwrap() else {
+                panic!("Bot didn't place a mark");
+            };
+            assert!(position_is_corner((row, col)));
+        }
+    }
+
+    #[test]
+    fn fork_seeker_and_blocker_personalities() {
+        // |X|O| |
+        // | |X| |
+        // | | |O|
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::O);
+        grid.set_cell(1, 1, Mark::X);
+        grid.set_cell(2, 2, Mark::O);
+
+        // Both (1, 0) and (2, 0) give X 2 ways to win
+        let bot = BotPlayer::easy().with_personality(BotPersonality::ForkSeeker);
+        let Move::Place(row, col) = bot.get_move(&grid, &Mark::X).unwrap() else {
+            panic!("Bot didn't place a mark");
+        };
+        assert_eq!(threats_after(&grid, &Mark::X, (row, col)), 2);
+
+        // O must deny X its forks
+        let bot = BotPlayer::easy().with_personality(BotPersonality::Blocker);
+        let Move::Place(row, col) = bot.get_move(&grid, &Mark::O).unwrap() else {
+            panic!("Bot didn't place a mark");
+        };
+        assert_eq!(threats_after(&grid, &Mark::X, (row, col)), 2);
+    }
+
+    #[test]
+    fn impossible_personalities_only_pick_optimal_moves() {
+        let bot = BotPlayer::impossible().with_personality(BotPersonality::CornerLover);
+        let mut grid = Grid::default();
+        grid.set_cell(1, 1, Mark::X);
+        // Only corners draw against a center opening
+        let Move::Place(row, col) = bot.get_move(&grid, &Mark::O).unwrap() else {
+            panic!("Bot didn't place a mark");
+        };
+        assert!(position_is_corner((row, col)));
+
+        let bot = BotPlayer::impossible().with_personality(BotPersonality::CenterHugger);
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        // Only the center draws against a corner opening
+        assert_eq!(bot.get_move(&grid, &Mark::O).unwrap(), Move::Place(1, 1));
+    }
 }
@@ -0,0 +1,440 @@
+//! Players typing their moves in a terminal, see [`LocalPlayer`].
+
+use std::{
+    fmt::Debug,
+    io::{self, BufRead, Write},
+    sync::Mutex,
+};
+
+use crate::{
+    board::{Board, SquareBoard},
+    grid::{Grid, Mark},
+    hint::{self, Hint},
+};
+
+use super::{Move, Player, PlayerError};
+
+/// Value entered by a local player when asked for their move.
+enum Input {
+    /// `(row, col)` of the cell the player entered.
+    Cell(usize, usize),
+    /// The player entered a command instead of a number.
+    Command(Move),
+    /// The player asked for a hint.
+    Hint,
+}
+
+/// Reads stdin one line at a time, only locking it while reading. Unlike [`io::StdinLock`], other
+/// code can keep reading from stdin while it exists, and it never buffers past the current line.
+#[derive(Debug, Default)]
+pub struct StdinReader {
+    line: String,
+    pos: usize,
+}
+
+impl io::Read for StdinReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for StdinReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.line.len() {
+            self.line.clear();
+            self.pos = 0;
+            io::stdin().read_line(&mut self.line)?;
+        }
+        Ok(&self.line.as_bytes()[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+/// How cells entered as a single number are numbered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CellNumbering {
+    /// Row by row from the top left cell, so 1-2-3 is the top row of the classic grid.
+    #[default]
+    Rows,
+    /// Like a numeric keypad, row by row from the bottom left cell, so 7-8-9 is the top row of the
+    /// classic grid.
+    Numpad,
+}
+
+impl CellNumbering {
+    /// Returns the `(row, col)` of the cell numbered `n` on a `size` x `size` board, if any.
+    pub fn cell(self, n: usize, size: usize) -> Option<(usize, usize)> {
+        let index = n.checked_sub(1).filter(|&i| i < size * size)?;
+        let (row, col) = (index / size, index % size);
+        match self {
+            Self::Rows => Some((row, col)),
+            Self::Numpad => Some((size - 1 - row, col)),
+        }
+    }
+
+    /// Returns the number of the cell at `(row, col)` on a `size` x `size` board.
+    pub fn number(self, row: usize, col: usize, size: usize) -> usize {
+        match self {
+            Self::Rows => row * size + col + 1,
+            Self::Numpad => (size - 1 - row) * size + col + 1,
+        }
+    }
+}
+
+/// Human player entering their moves as text. Reads from stdin and writes prompts to stdout by
+/// default, but any reader and writer can be used with [`LocalPlayer::new`].
+pub struct LocalPlayer<R: BufRead = StdinReader, W: Write = io::Stdout> {
+    io: Mutex<(R, W)>,
+    numbering: CellNumbering,
+    tutor: bool,
+}
+
+impl Default for LocalPlayer {
+    fn default() -> Self {
+        Self::new(StdinReader::default(), io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> Debug for LocalPlayer<R, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalPlayer").finish_non_exhaustive()
+    }
+}
+
+impl<R: BufRead, W: Write> LocalPlayer<R, W> {
+    /// Creates a player reading their input from `reader`, and writing prompts to `writer`.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            io: Mutex::new((reader, writer)),
+            numbering: CellNumbering::default(),
+            tutor: false,
+        }
+    }
+
+    /// Sets how cells entered as a single number are numbered.
+    pub fn with_numbering(mut self, numbering: CellNumbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    /// Turns on tutorial mode on the classic grid: the player is warned before a move making the
+    /// outcome worse, and the threats left on the board are explained after each of their moves.
+    pub fn with_tutor(mut self, tutor: bool) -> Self {
+        self.tutor = tutor;
+        self
+    }
+
+    /// Flushes the prompt, then reads a line into `buffer`, failing if the input ended.
+    fn read_line(reader: &mut R, writer: &mut W, buffer: &mut String) -> Result<(), PlayerError> {
+        writer.flush()?;
+        buffer.clear();
+        match reader.read_line(buffer)? {
+            0 => Err(PlayerError::EndOfInput),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads until we receive a cell of a `size` x `size` board, or a command (`resign`, `draw`,
+    /// or `hint` if `hints` is true). The accepted formats are listed whenever the input is
+    /// invalid.
+    fn read_valid_input(
+        (reader, writer): &mut (R, W),
+        size: usize,
+        numbering: CellNumbering,
+        hints: bool,
+    ) -> Result<Input, PlayerError> {
+        let mut buffer = String::new();
+        loop {
+            if hints {
+                write!(writer, "Enter a cell, \"draw\", \"resign\" or \"hint\": ")?;
+            } else {
+                write!(writer, "Enter a cell, \"draw\" or \"resign\": ")?;
+            }
+            Self::read_line(reader, writer, &mut buffer)?;
+
+            match buffer.trim().to_lowercase().as_ref() {
+                "resign" => return Ok(Input::Command(Move::Resign)),
+                "draw" => return Ok(Input::Command(Move::OfferDraw)),
+                "hint" if hints => return Ok(Input::Hint),
+                input => {
+                    if let Some((row, col)) = parse_cell(input, size, numbering) {
+                        return Ok(Input::Cell(row, col));
+                    }
+                }
+            }
+
+            let last_column = char::from(b'a' + (size.min(26) - 1) as u8);
+            writeln!(writer, "Invalid cell, enter one of:")?;
+            match numbering {
+                CellNumbering::Rows => writeln!(
+                    writer,
+                    "- its number [1-{}], counting from the top left cell",
+                    size * size
+                )?,
+                CellNumbering::Numpad => writeln!(
+                    writer,
+                    "- its number [1-{}], laid out like a numpad",
+                    size * size
+                )?,
+            }
+            writeln!(writer, "- its row and column, like \"2,3\"")?;
+            writeln!(
+                writer,
+                "- its column [a-{}] and row, like \"b2\"",
+                last_column
+            )?;
+        }
+    }
+
+    /// Same as [`LocalPlayer::read_valid_input`], but shows `hint` whenever the player asks for
+    /// it, so [`Input::Hint`] is never returned.
+    fn read_input_or_hint(
+        io: &mut (R, W),
+        size: usize,
+        numbering: CellNumbering,
+        hint: Option<&dyn Fn() -> Option<Hint>>,
+    ) -> Result<Input, PlayerError> {
+        loop {
+            match Self::read_valid_input(io, size, numbering, hint.is_some())? {
+                Input::Hint => match hint.and_then(|hint| hint()) {
+                    Some(hint) => writeln!(io.1, "Hint: {}", hint)?,
+                    None => writeln!(io.1, "No hint available")?,
+                },
+                input => return Ok(input),
+            }
+        }
+    }
+
+    /// Asks the player for a cell until they select a free one on a `size` x `size` board. When
+    /// `hint` is set, the player can ask for it instead of entering a cell.
+    fn read_move(
+        &self,
+        board: &impl Board,
+        size: usize,
+        hint: Option<&dyn Fn() -> Option<Hint>>,
+    ) -> Result<Move, PlayerError> {
+        let mut io = self.io.lock().unwrap();
+        loop {
+            let (row, col) = match Self::read_input_or_hint(&mut io, size, self.numbering, hint)? {
+                Input::Cell(row, col) => (row, col),
+                Input::Command(m) => return Ok(m),
+                Input::Hint => unreachable!(),
+            };
+
+            if !board.legal_moves().contains(&(row, col)) {
+                writeln!(io.1, "Invalid cell, already in use")?;
+            } else {
+                return Ok(Move::Place(row, col));
+            }
+        }
+    }
+
+    /// Asks the player playing `mark` whether they accept a draw offer.
+    fn read_draw_answer(&self, mark: &Mark) -> Result<bool, PlayerError> {
+        self.read_confirmation(&format!("{}'s opponent offers a draw. Accept?", mark))
+    }
+
+    /// Asks the player a yes or no `question`, defaulting to no.
+    fn read_confirmation(&self, question: &str) -> Result<bool, PlayerError> {
+        let mut io = self.io.lock().unwrap();
+        let (reader, writer) = &mut *io;
+        let mut buffer = String::new();
+        loop {
+            write!(writer, "{} [y/N]: ", question)?;
+            Self::read_line(reader, writer, &mut buffer)?;
+
+            match buffer.trim().to_lowercase().as_ref() {
+                "" | "no" | "n" => return Ok(false),
+                "yes" | "y" => return Ok(true),
+                _ => {}
+            }
+
+            writeln!(writer, "Invalid value")?;
+        }
+    }
+}
+
+impl<R: BufRead + Send, W: Write + Send> Player for LocalPlayer<R, W> {
+    /// Asks the player to enter their next move. The player can ask for a hint first.
+    fn get_move(&self, grid: &Grid, mark: &Mark) -> Result<Move, PlayerError> {
+        loop {
+            let m = self.read_move(grid, 3, Some(&|| hint::suggest_move(grid, mark)))?;
+            let Move::Place(row, col) = m else {
+                return Ok(m);
+            };
+            if !self.tutor {
+                return Ok(m);
+            }
+
+            if let Some(blunder) = hint::review_move(grid, mark, (row, col)) {
+                let question = format!("Careful, {}. Play it anyway?", blunder);
+                if !self.read_confirmation(&question)? {
+                    continue;
+                }
+            }
+            let mut next = *grid;
+            next.set_cell(row, col, *mark);
+            let writer = &mut self.io.lock().unwrap().1;
+            for threat in hint::threats(&next) {
+                writeln!(writer, "Threat: {}", threat)?;
+            }
+            return Ok(m);
+        }
+    }
+
+    /// Asks the player whether they accept the draw offered by their opponent.
+    fn accept_draw(&self, _: &Grid, mark: &Mark) -> Result<bool, PlayerError> {
+        self.read_draw_answer(mark)
+    }
+}
+
+impl<R: BufRead + Send, W: Write + Send> Player<SquareBoard> for LocalPlayer<R, W> {
+    fn get_move(&self, board: &SquareBoard, _: &Mark) -> Result<Move, PlayerError> {
+        self.read_move(board, board.size(), None)
+    }
+
+    fn accept_draw(&self, _: &SquareBoard, mark: &Mark) -> Result<bool, PlayerError> {
+        self.read_draw_answer(mark)
+    }
+}
+
+/// Parses a cell of a `size` x `size` board entered as a single token, returning its `(row, col)`.
+/// Cells can be entered as their number in the given `numbering` (`5`), as their row and column
+/// (`2,3`), or as their column letter and row (`b2`).
+fn parse_cell(input: &str, size: usize, numbering: CellNumbering) -> Option<(usize, usize)> {
+    let in_bounds = |n: usize| n.checked_sub(1).filter(|&i| i < size);
+
+    if let Some((row, col)) = input.split_once(',') {
+        let row = in_bounds(row.trim().parse().ok()?)?;
+        let col = in_bounds(col.trim().parse().ok()?)?;
+        return Some((row, col));
+    }
+    if let Ok(n) = input.parse::<usize>() {
+        return numbering.cell(n, size);
+    }
+
+    let mut chars = input.chars();
+    let letter = chars.next().filter(char::is_ascii_lowercase)?;
+    let col = in_bounds(usize::from(letter as u8 - b'a') + 1)?;
+    let row = in_bounds(chars.as_str().parse().ok()?)?;
+    Some((row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_player(input: &str) -> LocalPlayer<&[u8], Vec<u8>> {
+        LocalPlayer::new(input.as_bytes(), vec![])
+    }
+
+    #[test]
+    fn local_player_reads_moves() {
+        let grid = Grid::default();
+        let player = local_player("2,3\n");
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(1, 2));
+
+        let player = local_player("0\nabc\nresign\n");
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Resign);
+        let output = String::from_utf8(player.io.into_inner().unwrap().1).unwrap();
+        assert!(output.contains("like \"b2\""));
+
+        let player = local_player("10\n");
+        assert!(matches!(
+            player.get_move(&grid, &Mark::X),
+            Err(PlayerError::EndOfInput)
+        ));
+    }
+
+    #[test]
+    fn local_player_rejects_used_cell() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::O);
+        let player = local_player("1\n2\n");
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(0, 1));
+
+        let output = String::from_utf8(player.io.into_inner().unwrap().1).unwrap();
+        assert!(output.contains("Invalid cell, already in use"));
+    }
+
+    #[test]
+    fn cells_are_parsed_in_every_format() {
+        assert_eq!(parse_cell("5", 3, CellNumbering::Rows), Some((1, 1)));
+        assert_eq!(parse_cell("9", 3, CellNumbering::Rows), Some((2, 2)));
+        assert_eq!(parse_cell("2,3", 3, CellNumbering::Rows), Some((1, 2)));
+        assert_eq!(parse_cell("2, 3", 3, CellNumbering::Rows), Some((1, 2)));
+        assert_eq!(parse_cell("b2", 3, CellNumbering::Rows), Some((1, 1)));
+        assert_eq!(parse_cell("c1", 3, CellNumbering::Rows), Some((0, 2)));
+        assert_eq!(parse_cell("e5", 5, CellNumbering::Rows), Some((4, 4)));
+        assert_eq!(parse_cell("13", 5, CellNumbering::Rows), Some((2, 2)));
+
+        for invalid in ["0", "10", "4,1", "1,", "d1", "a0", "a", "2b", ""] {
+            assert_eq!(
+                parse_cell(invalid, 3, CellNumbering::Rows),
+                None,
+                "{:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn numpad_numbering_starts_from_the_bottom_row() {
+        let numpad = CellNumbering::Numpad;
+        assert_eq!(numpad.cell(7, 3), Some((0, 0)));
+        assert_eq!(numpad.cell(5, 3), Some((1, 1)));
+        assert_eq!(numpad.cell(3, 3), Some((2, 2)));
+        assert_eq!(numpad.cell(10, 3), None);
+        for n in 1..=9 {
+            let (row, col) = numpad.cell(n, 3).unwrap();
+            assert_eq!(numpad.number(row, col, 3), n);
+        }
+
+        // Only numbers follow the numpad layout
+        assert_eq!(parse_cell("1", 3, numpad), Some((2, 0)));
+        assert_eq!(parse_cell("1,1", 3, numpad), Some((0, 0)));
+        let player = local_player("9\n").with_numbering(numpad);
+        assert_eq!(
+            player.get_move(&Grid::default(), &Mark::X).unwrap(),
+            Move::Place(0, 2)
+        );
+    }
+
+    #[test]
+    fn tutor_warns_before_blunders() {
+        // |X|X| |
+        // | |O| |
+        // | | | |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+
+        let player = local_player("9\nn\n3\n").with_tutor(true);
+        assert_eq!(player.get_move(&grid, &Mark::O).unwrap(), Move::Place(0, 2));
+        let output = String::from_utf8(player.io.into_inner().unwrap().1).unwrap();
+        assert!(output.contains("leaves your opponent a line to complete"));
+        assert!(output.contains("Threat: O can complete a line on row 3, column 1"));
+
+        let player = local_player("9\ny\n").with_tutor(true);
+        assert_eq!(player.get_move(&grid, &Mark::O).unwrap(), Move::Place(2, 2));
+        let output = String::from_utf8(player.io.into_inner().unwrap().1).unwrap();
+        assert!(output.contains("Threat: X can complete a line on row 1, column 3"));
+    }
+
+    #[test]
+    fn local_player_answers_draw_offers() {
+        let grid = Grid::default();
+        assert!(local_player("maybe\ny\n")
+            .accept_draw(&grid, &Mark::X)
+            .unwrap());
+        assert!(!local_player("\n").accept_draw(&grid, &Mark::X).unwrap());
+    }
+}
@@ -0,0 +1,153 @@
+//! Novelty bot copying its opponent, see [`MirrorPlayer`].
+
+use std::sync::Mutex;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{board::Board, grid::Mark};
+
+use super::{BotPlayer, Move, Player, PlayerError};
+
+/// Bot playing the reflection of its opponent's last move through the center of the board, the
+/// cell across from it once the board is rotated by 180°. Mirroring is a classic talking point of
+/// tic-tac-toe strategy: it keeps the position symmetrical, but never stops a line going through
+/// the center.
+///
+/// When the reflected cell is taken, or the opponent's last move isn't known, the bot completes its
+/// own lines, blocks its opponents', takes the center, or else plays randomly.
+///
+/// The bot remembers the board after each of its moves to find the opponent's last move, so it
+/// shouldn't play several games at once. It notices a new game once a cell it saw taken is empty.
+#[derive(Debug)]
+pub struct MirrorPlayer {
+    /// Cells taken after the bot's last move
+    taken: Mutex<Vec<(usize, usize)>>,
+    rng: Mutex<StdRng>,
+}
+
+impl Default for MirrorPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MirrorPlayer {
+    pub fn new() -> Self {
+        Self {
+            taken: Mutex::new(vec![]),
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Draws the bot's random moves from a generator seeded with `seed`, like
+    /// [`BotPlayer::with_seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Returns the last move of any other player than `mark`, if only one was played since the
+    /// bot's last move.
+    fn last_move(&self, board: &impl Board, mark: &Mark) -> Option<(usize, usize)> {
+        let mut taken = self.taken.lock().unwrap();
+        if taken
+            .iter()
+            .any(|&(row, col)| board.mark(row, col).is_none())
+        {
+            taken.clear();
+        }
+
+        let (rows, cols) = board.dimensions();
+        let mut played = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .filter(|&(row, col)| board.mark(row, col).is_some_and(|m| m != *mark))
+            .filter(|cell| !taken.contains(cell));
+        match (played.next(), played.next()) {
+            (Some(cell), None) => Some(cell),
+            _ => None,
+        }
+    }
+
+    fn fallback_move(board: &impl Board, mark: &Mark, rng: &mut StdRng) -> (usize, usize) {
+        let (rows, cols) = board.dimensions();
+        let center = (rows % 2 == 1 && cols % 2 == 1)
+            .then_some((rows / 2, cols / 2))
+            .filter(|center| board.legal_moves().contains(center));
+        BotPlayer::find_winning_move(board, mark)
+            .or_else(|| {
+                [Mark::X, Mark::O, Mark::Y]
+                    .iter()
+                    .filter(|m| *m != mark)
+                    .find_map(|m| BotPlayer::find_winning_move(board, m))
+            })
+            .or(center)
+            .unwrap_or_else(|| BotPlayer::random_legal_move(board, rng))
+    }
+}
+
+impl<B: Board> Player<B> for MirrorPlayer {
+    fn get_move(&self, board: &B, mark: &Mark) -> Result<Move, PlayerError> {
+        let (rows, cols) = board.dimensions();
+        let mirrored = self
+            .last_move(board, mark)
+            .map(|(row, col)| (rows - 1 - row, cols - 1 - col))
+            .filter(|cell| board.legal_moves().contains(cell));
+        let (row, col) = mirrored.unwrap_or_else(|| {
+            MirrorPlayer::fallback_move(board, mark, &mut self.rng.lock().unwrap())
+        });
+
+        let mut taken = self.taken.lock().unwrap();
+        *taken = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .filter(|&(r, c)| board.mark(r, c).is_some() || (r, c) == (row, col))
+            .collect();
+        Ok(Move::Place(row, col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::SquareBoard, grid::Grid};
+
+    use super::*;
+
+    fn play(player: &MirrorPlayer, board: &mut impl Board, mark: Mark) -> (usize, usize) {
+        let Move::Place(row, col) = player.get_move(board, &mark).unwrap() else {
+            panic!("Mirror bots only place marks");
+        };
+        board.place(row, col, mark).unwrap();
+        (row, col)
+    }
+
+    #[test]
+    fn mirrors_the_last_move() {
+        let player = MirrorPlayer::new().with_seed(0);
+        let mut grid = Grid::default();
+        // Without a move to mirror, the center is taken
+        assert_eq!(play(&player, &mut grid, Mark::X), (1, 1));
+        grid.place(0, 1, Mark::O).unwrap();
+        assert_eq!(play(&player, &mut grid, Mark::X), (2, 1));
+        grid.place(2, 0, Mark::O).unwrap();
+        assert_eq!(play(&player, &mut grid, Mark::X), (0, 2));
+
+        // A new game is noticed, and X's first move is mirrored
+        let mut board = SquareBoard::new(4, 3);
+        board.place(0, 1, Mark::X).unwrap();
+        assert_eq!(play(&player, &mut board, Mark::O), (3, 2));
+    }
+
+    #[test]
+    fn falls_back_without_a_move_to_mirror() {
+        // X's two marks don't tell which came last, so O blocks their line
+        let player = MirrorPlayer::new().with_seed(0);
+        let mut grid = Grid::default();
+        grid.place(0, 0, Mark::X).unwrap();
+        grid.place(1, 1, Mark::X).unwrap();
+        assert_eq!(play(&player, &mut grid, Mark::O), (2, 2));
+
+        // The center reflects onto itself
+        let mut grid = Grid::default();
+        grid.place(1, 1, Mark::X).unwrap();
+        assert_ne!(play(&player, &mut grid, Mark::O), (1, 1));
+    }
+}
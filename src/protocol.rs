@@ -1,152 +1,1625 @@
-use std::{error::Error, fmt::Display};
+use std::time::Duration;
 
-use crate::grid::Mark;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-pub const CLIENT_HELLO_PKT_LENGTH: usize = 5;
+use crate::{
+    game::{DrawReason, GameResult, WinReason, MAX_CHAT_LEN},
+    grid::{Grid, GridPlacementError, Mark},
+    server::{LeaderboardEntry, MAX_ROOM_CODE_LEN},
+};
+
+pub const CLIENT_HELLO_PKT_LENGTH: usize = 6;
+
+/// Version of the protocol spoken by this crate, sent in [`Introduction`]. Clients opening with
+/// [`ClientHello`] speak version 1, which had no introductions.
+pub const PROTOCOL_VERSION: u8 = 2;
 
 const HELLO_MAGIC: u32 = 0xFD36_0084;
 const EOG_MAGIC: u32 = 0x5CD9_0094;
+const RESUME_MAGIC: u32 = 0x7E51_0031;
+const LOBBY_MAGIC: u32 = 0x1B0B_0042;
+const SPECTATE_MAGIC: u32 = 0x5EC7_0057;
+const CHAT_MAGIC: u32 = 0xC4A7_0063;
+const RECONNECT_MAGIC: u32 = 0x4EC0_0077;
+const HEARTBEAT_MAGIC: u32 = 0x4EA7_0088;
+const RESIGN_MAGIC: u32 = 0x4E51_0099;
+const REMATCH_MAGIC: u32 = 0x4E3A_0105;
+const SYNC_MAGIC: u32 = 0x5B0A_0111;
+const ACK_MAGIC: u32 = 0xAC4B_0122;
+const INVALID_MOVE_MAGIC: u32 = 0x1B7A_0133;
+const DISCOVERY_MAGIC: u32 = 0xD15C_0144;
+const CLOCK_MAGIC: u32 = 0xC10C_0155;
+const NEW_GAME_MAGIC: u32 = 0x4E3A_0166;
+const INTRODUCTION_MAGIC: u32 = 0x1D7E_0177;
+const ROOM_PROBE_MAGIC: u32 = 0xD15C_0188;
+const SHUTDOWN_MAGIC: u32 = 0x5D07_0199;
 pub const TERMINATOR: u8 = 0xFF;
 
-#[derive(Debug, Clone)]
+/// Error returned when a packet can't be parsed. `packet` is the type of packet that was expected,
+/// or `"unknown"` when its type couldn't be told from its magic value.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum PacketParseError {
-    InvalidSize,
-    InvalidMagic,
+    #[error("Error parsing {packet} packet: Wrong packet size")]
+    InvalidSize { packet: &'static str },
+    #[error("Error parsing {packet} packet: Wrong magic value")]
+    InvalidMagic { packet: &'static str },
+    #[error("Error parsing {packet} packet: Invalid field value")]
+    UnexpectedValue { packet: &'static str },
+    /// The packet's checksum doesn't match its content, it was corrupted or truncated.
+    #[error("Error parsing packet: Checksum doesn't match the packet")]
+    BadChecksum,
 }
-impl Display for PacketParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Error parsing packet: ")?;
-        match self {
-            Self::InvalidSize => write!(f, "Wrong packet size"),
-            Self::InvalidMagic => write!(f, "Wrong magic value"),
+
+/// Checksum sent at the end of every packet, before the terminator: the payload's CRC-8, reduced
+/// so it is never the terminator.
+fn checksum(payload: &[u8]) -> u8 {
+    let crc = payload.iter().fold(0_u8, |mut crc, &b| {
+        crc ^= b;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    });
+    crc % TERMINATOR
+}
+
+/// Writes the checksum and terminator in the last 2 bytes of a fixed size packet.
+fn seal(pkt: &mut [u8]) {
+    let len = pkt.len();
+    pkt[len - 2] = checksum(&pkt[..len - 2]);
+    pkt[len - 1] = TERMINATOR;
+}
+
+/// Appends the checksum and terminator to a variable size packet.
+fn sealed(mut pkt: Vec<u8>) -> Vec<u8> {
+    pkt.push(checksum(&pkt));
+    pkt.push(TERMINATOR);
+    pkt
+}
+
+/// Returns the payload of a packet read up to its terminator, after checking its checksum. Packets
+/// are then parsed from their payload.
+pub fn unframe(buf: &[u8]) -> Result<&[u8], PacketParseError> {
+    let (&sum, payload) = buf
+        .strip_suffix(&[TERMINATOR])
+        .and_then(|pkt| pkt.split_last())
+        .ok_or(PacketParseError::InvalidSize { packet: "unknown" })?;
+    if checksum(payload) != sum {
+        return Err(PacketParseError::BadChecksum);
+    }
+    Ok(payload)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClientHello;
+impl TryFrom<&[u8]> for ClientHello {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != CLIENT_HELLO_PKT_LENGTH - 2 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "ClientHello",
+            });
+        }
+
+        if value != HELLO_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "ClientHello",
+            });
+        }
+
+        Ok(Self)
+    }
+}
+impl ClientHello {
+    pub fn to_bytes(self) -> [u8; 6] {
+        let mut pkt = [0_u8; 6];
+        pkt[0..4].copy_from_slice(&HELLO_MAGIC.to_be_bytes());
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by clients instead of [`ClientHello`] to join a game under a nickname, or with the game's
+/// password. Hosts reply with their own introduction, carrying the protocol version both sides
+/// speak and an empty password, before [`ServerHello`].
+///
+/// The payload is the version byte, then the nickname and the password separated by a newline.
+/// Newlines in the nickname are sent as spaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Introduction {
+    pub version: u8,
+    pub nickname: String,
+    pub password: String,
+}
+impl TryFrom<&[u8]> for Introduction {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() < 6 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "Introduction",
+            });
+        }
+
+        if value[0..4] != INTRODUCTION_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "Introduction",
+            });
+        }
+
+        let unexpected = PacketParseError::UnexpectedValue {
+            packet: "Introduction",
+        };
+        let (nickname, password) = std::str::from_utf8(&value[5..])
+            .ok()
+            .and_then(|text| text.split_once('\n'))
+            .ok_or(unexpected.clone())?;
+        if value[4] == 0 {
+            return Err(unexpected);
+        }
+        Ok(Self {
+            version: value[4],
+            nickname: nickname.to_owned(),
+            password: password.to_owned(),
+        })
+    }
+}
+impl Introduction {
+    /// Introduces a player speaking [`PROTOCOL_VERSION`] under `nickname`, without a password.
+    pub fn new(nickname: impl Into<String>) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            nickname: nickname.into(),
+            password: String::new(),
+        }
+    }
+
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Serializes the packet. Text never contains the terminator, since it is not valid UTF-8, and
+    /// neither does the version as long as it is below 255.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut pkt = INTRODUCTION_MAGIC.to_be_bytes().to_vec();
+        pkt.push(self.version);
+        pkt.extend_from_slice(self.nickname.replace('\n', " ").as_bytes());
+        pkt.push(b'\n');
+        pkt.extend_from_slice(self.password.as_bytes());
+        sealed(pkt)
+    }
+}
+
+/// Reply to [`ClientHello`] or [`ClientResume`]. Servers accepting reconnections (see
+/// [`Reconnect`]) append the game's token to the packet.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ServerHello {
+    pub client_first: bool,
+    pub client_mark: Mark,
+    #[cfg_attr(feature = "serde", serde(default, with = "fields::optional_token"))]
+    pub token: Option<u64>,
+}
+impl TryFrom<&[u8]> for ServerHello {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let token = match value.len() {
+            4 => None,
+            20 => Some(token_from_bytes(&value[4..20], "ServerHello")?),
+            _ => {
+                return Err(PacketParseError::InvalidSize {
+                    packet: "ServerHello",
+                })
+            }
+        };
+
+        // Set last 2 bits to 0
+        let mut x = [0_u8; 4];
+        x.clone_from_slice(&value[0..4]);
+        x[3] &= !0b11;
+        if x != HELLO_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "ServerHello",
+            });
+        }
+
+        let client_first = (value[3] & 0b10) != 0;
+        let client_mark = if (value[3] & 0b1) == 0 {
+            Mark::O
+        } else {
+            Mark::X
+        };
+
+        Ok(Self {
+            client_first,
+            client_mark,
+            token,
+        })
+    }
+}
+impl ServerHello {
+    pub fn to_bytes(self) -> Vec<u8> {
+        let magic_bytes = HELLO_MAGIC.to_be_bytes();
+        let mut pkt = magic_bytes.to_vec();
+
+        let mut b = magic_bytes[3];
+        if self.client_first {
+            b |= 0b10;
+        }
+        if self.client_mark == Mark::X {
+            b |= 1;
+        }
+        pkt[3] = b;
+        if let Some(token) = self.token {
+            pkt.extend_from_slice(&token_to_bytes(token));
+        }
+        sealed(pkt)
+    }
+}
+
+fn mark_to_byte(mark: Mark) -> u8 {
+    match mark {
+        Mark::X => 1,
+        Mark::O => 2,
+        Mark::Y => 3,
+    }
+}
+
+fn mark_from_byte(b: u8, packet: &'static str) -> Result<Mark, PacketParseError> {
+    match b {
+        1 => Ok(Mark::X),
+        2 => Ok(Mark::O),
+        3 => Ok(Mark::Y),
+        _ => Err(PacketParseError::UnexpectedValue { packet }),
+    }
+}
+
+/// Encodes a game token as hexadecimal text, which can't contain the terminator.
+fn token_to_bytes(token: u64) -> [u8; 16] {
+    let mut bytes = [0_u8; 16];
+    bytes.copy_from_slice(format!("{:016x}", token).as_bytes());
+    bytes
+}
+
+fn token_from_bytes(value: &[u8], packet: &'static str) -> Result<u64, PacketParseError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+        .ok_or(PacketParseError::UnexpectedValue { packet })
+}
+
+/// Mark placed by a player, with its sequence number: the number of marks on the grid once it is
+/// placed. The receiver acknowledges it with [`MoveAck`], and ignores moves it already received,
+/// which the sender may send again if it doesn't get the acknowledgment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerMove {
+    pub row: usize,
+    pub col: usize,
+    pub seq: u8,
+}
+impl TryFrom<&[u8]> for PlayerMove {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 2 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "PlayerMove",
+            });
+        }
+
+        let (row, col) = cell_from_byte(value[0]);
+        Ok(Self {
+            row,
+            col,
+            seq: value[1],
+        })
+    }
+}
+impl PlayerMove {
+    pub fn to_bytes(self) -> [u8; 4] {
+        let mut pkt = [cell_to_byte(self.row, self.col), self.seq, 0, 0];
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by a player once they received the [`PlayerMove`] with this sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MoveAck {
+    pub seq: u8,
+}
+impl TryFrom<&[u8]> for MoveAck {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 5 {
+            return Err(PacketParseError::InvalidSize { packet: "MoveAck" });
+        }
+
+        if value[0..4] != ACK_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic { packet: "MoveAck" });
+        }
+        Ok(Self { seq: value[4] })
+    }
+}
+impl MoveAck {
+    pub fn to_bytes(self) -> [u8; 7] {
+        let mut pkt = [0_u8; 7];
+        pkt[0..4].copy_from_slice(&ACK_MAGIC.to_be_bytes());
+        pkt[4] = self.seq;
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent instead of a [`MoveAck`] when a [`PlayerMove`] can't be played on the receiver's grid. The
+/// sender takes the move back and plays its turn again.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "fields::InvalidMove", into = "fields::InvalidMove")
+)]
+pub struct InvalidMove {
+    pub row: usize,
+    pub col: usize,
+    pub seq: u8,
+    pub reason: GridPlacementError,
+}
+impl TryFrom<&[u8]> for InvalidMove {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 7 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "InvalidMove",
+            });
+        }
+
+        if value[0..4] != INVALID_MOVE_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "InvalidMove",
+            });
+        }
+        let (row, col) = cell_from_byte(value[4]);
+        let reason = match value[6] {
+            0 => GridPlacementError::CellInUse { row, col },
+            1 => GridPlacementError::OutOfBounds { row, col },
+            _ => {
+                return Err(PacketParseError::UnexpectedValue {
+                    packet: "InvalidMove",
+                })
+            }
+        };
+        Ok(Self {
+            row,
+            col,
+            seq: value[5],
+            reason,
+        })
+    }
+}
+impl InvalidMove {
+    pub fn to_bytes(self) -> [u8; 9] {
+        let mut pkt = [0_u8; 9];
+        pkt[0..4].copy_from_slice(&INVALID_MOVE_MAGIC.to_be_bytes());
+        pkt[4] = cell_to_byte(self.row, self.col);
+        pkt[5] = self.seq;
+        pkt[6] = match self.reason {
+            GridPlacementError::CellInUse { .. } => 0,
+            GridPlacementError::OutOfBounds { .. } => 1,
+        };
+        seal(&mut pkt);
+        pkt
+    }
+
+    /// Returns whether this rejects `pkt`.
+    pub fn rejects(&self, pkt: &PlayerMove) -> bool {
+        (self.row, self.col, self.seq) == (pkt.row, pkt.col, pkt.seq)
+    }
+}
+
+/// Encodes a cell's position in a single byte, with the row in the high nibble.
+fn cell_to_byte(row: usize, col: usize) -> u8 {
+    (row << 4) as u8 + (col as u8 & 0b1111)
+}
+
+/// Decodes a byte encoded with [`cell_to_byte`].
+fn cell_from_byte(b: u8) -> (usize, usize) {
+    ((b >> 4) as usize, (b & 0b1111) as usize)
+}
+
+/// Encodes a grid using 1 byte per cell: 0 for empty, 1 for `X`, 2 for `O` and 3 for `Y`.
+pub fn grid_to_bytes(grid: &Grid) -> [u8; 9] {
+    let mut bytes = [0_u8; 9];
+    for (i, cell) in grid.rows().flatten().enumerate() {
+        bytes[i] = match cell.try_get_mark() {
+            None => 0,
+            Some(Mark::X) => 1,
+            Some(Mark::O) => 2,
+            Some(Mark::Y) => 3,
+        };
+    }
+    bytes
+}
+
+/// Decodes a grid encoded with [`grid_to_bytes`].
+pub fn grid_from_bytes(value: &[u8], packet: &'static str) -> Result<Grid, PacketParseError> {
+    if value.len() != 9 {
+        return Err(PacketParseError::InvalidSize { packet });
+    }
+
+    let mut grid = Grid::default();
+    for (i, b) in value.iter().enumerate() {
+        match b {
+            0 => {}
+            1 => grid.set_cell(i / 3, i % 3, Mark::X),
+            2 => grid.set_cell(i / 3, i % 3, Mark::O),
+            3 => grid.set_cell(i / 3, i % 3, Mark::Y),
+            _ => return Err(PacketParseError::UnexpectedValue { packet }),
+        }
+    }
+    Ok(grid)
+}
+
+/// Encodes whose turn it is (`0b10`) and which mark is played (`0b1` if `X`) in a single byte.
+pub fn turn_flags(turn: bool, mark: Mark) -> u8 {
+    let mut b = 0;
+    if turn {
+        b |= 0b10;
+    }
+    if mark == Mark::X {
+        b |= 1;
+    }
+    b
+}
+
+/// Decodes a byte encoded with [`turn_flags`].
+pub fn parse_turn_flags(b: u8, packet: &'static str) -> Result<(bool, Mark), PacketParseError> {
+    if b & !0b11 != 0 {
+        return Err(PacketParseError::UnexpectedValue { packet });
+    }
+
+    let mark = if (b & 0b1) == 0 { Mark::O } else { Mark::X };
+    Ok(((b & 0b10) != 0, mark))
+}
+
+/// Sent by the client instead of [`ClientHello`] to resume a previously interrupted game. Both
+/// sides must agree on the grid, turn and marks for the game to resume.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClientResume {
+    pub grid: Grid,
+    pub client_turn: bool,
+    pub client_mark: Mark,
+}
+impl TryFrom<&[u8]> for ClientResume {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 14 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "ClientResume",
+            });
+        }
+
+        if value[0..4] != RESUME_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "ClientResume",
+            });
+        }
+
+        let grid = grid_from_bytes(&value[4..13], "ClientResume")?;
+        let (client_turn, client_mark) = parse_turn_flags(value[13], "ClientResume")?;
+        Ok(Self {
+            grid,
+            client_turn,
+            client_mark,
+        })
+    }
+}
+impl ClientResume {
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut pkt = [0_u8; 16];
+        pkt[0..4].copy_from_slice(&RESUME_MAGIC.to_be_bytes());
+        pkt[4..13].copy_from_slice(&grid_to_bytes(&self.grid));
+        pkt[13] = turn_flags(self.client_turn, self.client_mark);
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by a client instead of [`ClientHello`] to get back into a game after its connection
+/// dropped, using the token received in [`ServerHello`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reconnect {
+    #[cfg_attr(feature = "serde", serde(with = "fields::token"))]
+    pub token: u64,
+}
+impl TryFrom<&[u8]> for Reconnect {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 20 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "Reconnect",
+            });
+        }
+
+        if value[0..4] != RECONNECT_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "Reconnect",
+            });
+        }
+        Ok(Self {
+            token: token_from_bytes(&value[4..20], "Reconnect")?,
+        })
+    }
+}
+impl Reconnect {
+    pub fn to_bytes(self) -> [u8; 22] {
+        let mut pkt = [0_u8; 22];
+        pkt[0..4].copy_from_slice(&RECONNECT_MAGIC.to_be_bytes());
+        pkt[4..20].copy_from_slice(&token_to_bytes(self.token));
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Whole state of a game: its grid and the mark playing next. It is sent by the host in reply to
+/// [`Reconnect`] to resynchronize the client, since moves sent right before the connection dropped
+/// may have been lost, and to spectators when they join or a rematch starts. Players can also send
+/// it during their turn, for the other player to check they agree on the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BoardSync {
+    pub grid: Grid,
+    pub turn: Mark,
+}
+impl TryFrom<&[u8]> for BoardSync {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 14 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "BoardSync",
+            });
+        }
+
+        if value[0..4] != SYNC_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "BoardSync",
+            });
+        }
+
+        Ok(Self {
+            grid: grid_from_bytes(&value[4..13], "BoardSync")?,
+            turn: mark_from_byte(value[13], "BoardSync")?,
+        })
+    }
+}
+impl BoardSync {
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut pkt = [0_u8; 16];
+        pkt[0..4].copy_from_slice(&SYNC_MAGIC.to_be_bytes());
+        pkt[4..13].copy_from_slice(&grid_to_bytes(&self.grid));
+        pkt[13] = mark_to_byte(self.turn);
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by the player making the last move of a game, right after it, with the game's result.
+///
+/// Hosts also send it to their spectators whenever a game ends, including by resignation or
+/// timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "fields::EndOfGame", into = "fields::EndOfGame")
+)]
+pub struct EndOfGame(pub GameResult);
+impl TryFrom<&[u8]> for EndOfGame {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 7 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "EndOfGame",
+            });
+        }
+
+        if value[0..4] != EOG_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "EndOfGame",
+            });
+        }
+
+        let result = match value[4..7] {
+            [0, mark, reason] => GameResult::Win {
+                winner: mark_from_byte(mark, "EndOfGame")?,
+                reason: match reason {
+                    0 => WinReason::Line,
+                    1 => WinReason::Resignation,
+                    2 => WinReason::Timeout,
+                    _ => {
+                        return Err(PacketParseError::UnexpectedValue {
+                            packet: "EndOfGame",
+                        })
+                    }
+                },
+            },
+            [1, 0, reason] => GameResult::Draw(match reason {
+                0 => DrawReason::BoardFull,
+                1 => DrawReason::Agreement,
+                _ => {
+                    return Err(PacketParseError::UnexpectedValue {
+                        packet: "EndOfGame",
+                    })
+                }
+            }),
+            _ => {
+                return Err(PacketParseError::UnexpectedValue {
+                    packet: "EndOfGame",
+                })
+            }
+        };
+        Ok(Self(result))
+    }
+}
+impl EndOfGame {
+    pub fn to_bytes(self) -> [u8; 9] {
+        let mut pkt = [0_u8; 9];
+        pkt[0..4].copy_from_slice(&EOG_MAGIC.to_be_bytes());
+        pkt[4..7].copy_from_slice(&match self.0 {
+            GameResult::Win { winner, reason } => [
+                0,
+                mark_to_byte(winner),
+                match reason {
+                    WinReason::Line => 0,
+                    WinReason::Resignation => 1,
+                    WinReason::Timeout => 2,
+                },
+            ],
+            GameResult::Draw(reason) => [
+                1,
+                0,
+                match reason {
+                    DrawReason::BoardFull => 0,
+                    DrawReason::Agreement => 1,
+                },
+            ],
+        });
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by a player instead of their move to resign, which ends the game.
+#[derive(Debug, Clone, Copy)]
+pub struct Resign;
+impl TryFrom<&[u8]> for Resign {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 4 {
+            return Err(PacketParseError::InvalidSize { packet: "Resign" });
+        }
+
+        if value != RESIGN_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic { packet: "Resign" });
+        }
+        Ok(Self)
+    }
+}
+impl Resign {
+    pub fn to_bytes(self) -> [u8; 6] {
+        let mut pkt = [0_u8; 6];
+        pkt[0..4].copy_from_slice(&RESIGN_MAGIC.to_be_bytes());
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Time left to each player of a timed game, sent by a player right before their move. Times are
+/// encoded in milliseconds, as hexadecimal text which can't contain the terminator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClockUpdate {
+    #[cfg_attr(feature = "serde", serde(rename = "x_ms", with = "fields::millis"))]
+    pub x: Duration,
+    #[cfg_attr(feature = "serde", serde(rename = "o_ms", with = "fields::millis"))]
+    pub o: Duration,
+}
+impl TryFrom<&[u8]> for ClockUpdate {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 20 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "ClockUpdate",
+            });
+        }
+
+        if value[0..4] != CLOCK_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "ClockUpdate",
+            });
+        }
+        let millis = |bytes: &[u8]| {
+            std::str::from_utf8(bytes)
+                .ok()
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .map(|millis| Duration::from_millis(millis.into()))
+                .ok_or(PacketParseError::UnexpectedValue {
+                    packet: "ClockUpdate",
+                })
+        };
+        Ok(Self {
+            x: millis(&value[4..12])?,
+            o: millis(&value[12..20])?,
+        })
+    }
+}
+impl ClockUpdate {
+    /// Serializes the packet. Times are truncated to the millisecond, and capped to about 49 days.
+    pub fn to_bytes(self) -> [u8; 22] {
+        let millis = |time: Duration| u32::try_from(time.as_millis()).unwrap_or(u32::MAX);
+        let mut pkt = [0_u8; 22];
+        pkt[0..4].copy_from_slice(&CLOCK_MAGIC.to_be_bytes());
+        pkt[4..20]
+            .copy_from_slice(format!("{:08x}{:08x}", millis(self.x), millis(self.o)).as_bytes());
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by both players once a game is over, telling whether they want to play a rematch. It
+/// starts once both players accepted.
+///
+/// The host can send a [`NewGame`] packet instead of accepting, to choose the new game's settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "fields::Rematch", into = "fields::Rematch")
+)]
+pub struct Rematch(pub bool);
+impl TryFrom<&[u8]> for Rematch {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 5 {
+            return Err(PacketParseError::InvalidSize { packet: "Rematch" });
+        }
+
+        if value[0..4] != REMATCH_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic { packet: "Rematch" });
+        }
+        match value[4] {
+            0 => Ok(Self(false)),
+            1 => Ok(Self(true)),
+            _ => Err(PacketParseError::UnexpectedValue { packet: "Rematch" }),
+        }
+    }
+}
+impl Rematch {
+    pub fn to_bytes(self) -> [u8; 7] {
+        let mut pkt = [0_u8; 7];
+        pkt[0..4].copy_from_slice(&REMATCH_MAGIC.to_be_bytes());
+        pkt[4] = self.0 as u8;
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by the host once a game is over instead of accepting a [`Rematch`], to offer a new game
+/// over the same connection with the given settings. The client answers with a [`Rematch`] packet,
+/// and the new game starts if it accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NewGame {
+    pub client_first: bool,
+    pub client_mark: Mark,
+}
+impl TryFrom<&[u8]> for NewGame {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 6 {
+            return Err(PacketParseError::InvalidSize { packet: "NewGame" });
+        }
+
+        if value[0..4] != NEW_GAME_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic { packet: "NewGame" });
+        }
+        let client_first = match value[4] {
+            0 => false,
+            1 => true,
+            _ => return Err(PacketParseError::UnexpectedValue { packet: "NewGame" }),
+        };
+        Ok(Self {
+            client_first,
+            client_mark: mark_from_byte(value[5], "NewGame")?,
+        })
+    }
+}
+impl NewGame {
+    pub fn to_bytes(self) -> [u8; 8] {
+        let mut pkt = [0_u8; 8];
+        pkt[0..4].copy_from_slice(&NEW_GAME_MAGIC.to_be_bytes());
+        pkt[4] = self.client_first as u8;
+        pkt[5] = mark_to_byte(self.client_mark);
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by a client to watch a hosted game instead of playing it.
+#[derive(Debug, Clone, Copy)]
+pub struct SpectatorHello;
+impl TryFrom<&[u8]> for SpectatorHello {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 4 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "SpectatorHello",
+            });
+        }
+
+        if value != SPECTATE_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "SpectatorHello",
+            });
+        }
+        Ok(Self)
+    }
+}
+impl SpectatorHello {
+    pub fn to_bytes(self) -> [u8; 6] {
+        let mut pkt = [0_u8; 6];
+        pkt[0..4].copy_from_slice(&SPECTATE_MAGIC.to_be_bytes());
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Sent by the host to spectators for every move played, using the [`PlayerMove`] encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MoveBroadcast {
+    pub mark: Mark,
+    pub row: usize,
+    pub col: usize,
+}
+impl TryFrom<&[u8]> for MoveBroadcast {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 6 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "MoveBroadcast",
+            });
+        }
+
+        if value[0..4] != SPECTATE_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "MoveBroadcast",
+            });
+        }
+
+        let mark = mark_from_byte(value[4], "MoveBroadcast")?;
+        let (row, col) = cell_from_byte(value[5]);
+        Ok(Self { mark, row, col })
+    }
+}
+impl MoveBroadcast {
+    pub fn to_bytes(self) -> [u8; 8] {
+        let mut pkt = [0_u8; 8];
+        pkt[0..4].copy_from_slice(&SPECTATE_MAGIC.to_be_bytes());
+        pkt[4] = mark_to_byte(self.mark);
+        pkt[5] = cell_to_byte(self.row, self.col);
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Text message sent by a player to their opponent, at any time during the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "fields::ChatMessage", into = "fields::ChatMessage")
+)]
+pub struct ChatMessage(pub String);
+impl TryFrom<&[u8]> for ChatMessage {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() < 4 || value.len() > 4 + MAX_CHAT_LEN {
+            return Err(PacketParseError::InvalidSize {
+                packet: "ChatMessage",
+            });
+        }
+
+        if value[0..4] != CHAT_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "ChatMessage",
+            });
+        }
+
+        let text =
+            std::str::from_utf8(&value[4..]).map_err(|_| PacketParseError::UnexpectedValue {
+                packet: "ChatMessage",
+            })?;
+        Ok(Self(text.to_owned()))
+    }
+}
+impl ChatMessage {
+    /// Serializes the packet. Text never contains the terminator, since it is not valid UTF-8.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut pkt = CHAT_MAGIC.to_be_bytes().to_vec();
+        pkt.extend_from_slice(self.0.as_bytes());
+        sealed(pkt)
+    }
+}
+
+/// Sent periodically by a player choosing their move, so the other side knows the connection is
+/// still alive while it waits.
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeat;
+impl TryFrom<&[u8]> for Heartbeat {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 4 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "Heartbeat",
+            });
+        }
+
+        if value != HEARTBEAT_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "Heartbeat",
+            });
+        }
+        Ok(Self)
+    }
+}
+impl Heartbeat {
+    pub fn to_bytes(self) -> [u8; 6] {
+        let mut pkt = [0_u8; 6];
+        pkt[0..4].copy_from_slice(&HEARTBEAT_MAGIC.to_be_bytes());
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Broadcast over UDP by clients looking for games hosted on the local network.
+#[derive(Debug, Clone, Copy)]
+pub struct DiscoveryProbe;
+impl TryFrom<&[u8]> for DiscoveryProbe {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 4 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "DiscoveryProbe",
+            });
+        }
+
+        if value != DISCOVERY_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "DiscoveryProbe",
+            });
+        }
+        Ok(Self)
+    }
+}
+impl DiscoveryProbe {
+    pub fn to_bytes(self) -> [u8; 6] {
+        let mut pkt = [0_u8; 6];
+        pkt[0..4].copy_from_slice(&DISCOVERY_MAGIC.to_be_bytes());
+        seal(&mut pkt);
+        pkt
+    }
+}
+
+/// Reply to [`DiscoveryProbe`], with the TCP port the game is hosted on. The port is written as
+/// decimal text, like tokens it can't contain the terminator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostAnnouncement {
+    pub port: u16,
+}
+impl TryFrom<&[u8]> for HostAnnouncement {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !(5..=9).contains(&value.len()) {
+            return Err(PacketParseError::InvalidSize {
+                packet: "HostAnnouncement",
+            });
+        }
+
+        if value[0..4] != DISCOVERY_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "HostAnnouncement",
+            });
         }
+        let port = std::str::from_utf8(&value[4..])
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .ok_or(PacketParseError::UnexpectedValue {
+                packet: "HostAnnouncement",
+            })?;
+        Ok(Self { port })
+    }
+}
+impl HostAnnouncement {
+    pub fn to_bytes(self) -> Vec<u8> {
+        let mut pkt = DISCOVERY_MAGIC.to_be_bytes().to_vec();
+        pkt.extend_from_slice(self.port.to_string().as_bytes());
+        sealed(pkt)
     }
 }
-impl Error for PacketParseError {}
 
-#[derive(Debug, Clone, Copy)]
-pub struct ClientHello;
-impl TryFrom<&[u8]> for ClientHello {
+/// Broadcast over UDP by clients looking for the game hosted on the local network with a room code,
+/// answered with a [`HostAnnouncement`] by that game's host only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomProbe(pub String);
+impl TryFrom<&[u8]> for RoomProbe {
     type Error = PacketParseError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        if value.len() != CLIENT_HELLO_PKT_LENGTH - 1 {
-            return Err(PacketParseError::InvalidSize);
+        if value.len() < 5 || value.len() > 4 + MAX_ROOM_CODE_LEN {
+            return Err(PacketParseError::InvalidSize {
+                packet: "RoomProbe",
+            });
         }
 
-        if value != HELLO_MAGIC.to_be_bytes() {
-            return Err(PacketParseError::InvalidMagic);
+        if value[0..4] != ROOM_PROBE_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "RoomProbe",
+            });
         }
-
-        Ok(Self)
+        let code =
+            std::str::from_utf8(&value[4..]).map_err(|_| PacketParseError::UnexpectedValue {
+                packet: "RoomProbe",
+            })?;
+        Ok(Self(code.to_owned()))
     }
 }
-impl ClientHello {
-    pub fn to_bytes(self) -> [u8; 5] {
-        let mut pkt = [0_u8; 5];
-        pkt[0..4].copy_from_slice(&HELLO_MAGIC.to_be_bytes());
-        pkt[4] = TERMINATOR;
-        pkt
+impl RoomProbe {
+    /// Serializes the packet. Like chat messages, the code never contains the terminator.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut pkt = ROOM_PROBE_MAGIC.to_be_bytes().to_vec();
+        pkt.extend_from_slice(self.0.as_bytes());
+        sealed(pkt)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct ServerHello {
-    pub client_first: bool,
-    pub client_mark: Mark,
+/// Sent by a dedicated server to the players of its games when it is about to stop. Games still
+/// going once `grace_period` is over are interrupted: clients can then reconnect to the game if the
+/// server saved it and starts again in time, like they would after losing their connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ServerShutdown {
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "grace_period_ms", with = "fields::millis")
+    )]
+    pub grace_period: Duration,
 }
-impl TryFrom<&[u8]> for ServerHello {
+impl TryFrom<&[u8]> for ServerShutdown {
     type Error = PacketParseError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        if value.len() != 4 {
-            return Err(PacketParseError::InvalidSize);
+        if value.len() != 12 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "ServerShutdown",
+            });
         }
 
-        // Set last 2 bits to 0
-        let mut x = [0_u8; 4];
-        x.clone_from_slice(value);
-        x[3] &= !0b11;
-        if x != HELLO_MAGIC.to_be_bytes() {
-            return Err(PacketParseError::InvalidMagic);
+        if value[0..4] != SHUTDOWN_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "ServerShutdown",
+            });
         }
-
-        let client_first = (value[3] & 0b10) != 0;
-        let client_mark = if (value[3] & 0b1) == 0 {
-            Mark::O
-        } else {
-            Mark::X
-        };
-
+        let millis = std::str::from_utf8(&value[4..12])
+            .ok()
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or(PacketParseError::UnexpectedValue {
+                packet: "ServerShutdown",
+            })?;
         Ok(Self {
-            client_first,
-            client_mark,
+            grace_period: Duration::from_millis(millis.into()),
         })
     }
 }
-impl ServerHello {
-    pub fn to_bytes(self) -> [u8; 5] {
-        let mut pkt = [0_u8; 5];
-        let magic_bytes = HELLO_MAGIC.to_be_bytes();
-        pkt[0..4].copy_from_slice(&magic_bytes);
+impl ServerShutdown {
+    /// Serializes the packet. The grace period is truncated to the millisecond, and capped to about
+    /// 49 days.
+    pub fn to_bytes(self) -> [u8; 14] {
+        let millis = u32::try_from(self.grace_period.as_millis()).unwrap_or(u32::MAX);
+        let mut pkt = [0_u8; 14];
+        pkt[0..4].copy_from_slice(&SHUTDOWN_MAGIC.to_be_bytes());
+        pkt[4..12].copy_from_slice(format!("{:08x}", millis).as_bytes());
+        seal(&mut pkt);
+        pkt
+    }
+}
 
-        let mut b = magic_bytes[3];
-        if self.client_first {
-            b |= 0b10;
+/// Packets exchanged with the lobby of a dedicated server, before a game starts. Every packet is
+/// made of the lobby magic value, a kind byte and a text payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LobbyPacket {
+    /// Sent by the client to enter the lobby under a name.
+    Register(String),
+    ListGames,
+    /// Open games, as (id, host name) pairs. Encoded as one `<id> <name>` line per game.
+    GameList(Vec<(u32, String)>),
+    /// Opens a game, waiting for another client to join it.
+    CreateGame,
+    GameCreated(u32),
+    /// Joins an open game, answered by [`ServerHello`] once the game starts.
+    JoinGame(u32),
+    Error(String),
+    /// Sent by the client instead of registering, to play against the other client joining the
+    /// room with the same code. Answered by [`ServerHello`] once both joined.
+    JoinRoom(String),
+    /// Creates an account with this name, answered by [`LobbyPacket::AccountCreated`]. The client
+    /// is then logged in to it.
+    CreateAccount(String),
+    /// Token the client logs in to its new account with.
+    AccountCreated(String),
+    /// Logs in to an account, before registering or joining a room. Answered by
+    /// [`LobbyPacket::LoggedIn`]. Encoded as `<name> <token>`.
+    Login {
+        name: String,
+        token: String,
+    },
+    LoggedIn,
+    /// Asks for the server's leaderboard, answered by [`LobbyPacket::Leaderboard`].
+    GetLeaderboard,
+    /// Results of the server's accounts, best first. Encoded as one
+    /// `<name> <wins> <draws> <losses>` line per account.
+    Leaderboard(Vec<LeaderboardEntry>),
+}
+
+impl TryFrom<&[u8]> for LobbyPacket {
+    type Error = PacketParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, PacketParseError> {
+        if value.len() < 5 {
+            return Err(PacketParseError::InvalidSize {
+                packet: "LobbyPacket",
+            });
         }
-        if self.client_mark == Mark::X {
-            b |= 1;
+
+        if value[0..4] != LOBBY_MAGIC.to_be_bytes() {
+            return Err(PacketParseError::InvalidMagic {
+                packet: "LobbyPacket",
+            });
         }
-        pkt[3] = b;
-        pkt[4] = TERMINATOR;
-        pkt
+
+        let payload =
+            std::str::from_utf8(&value[5..]).map_err(|_| PacketParseError::UnexpectedValue {
+                packet: "LobbyPacket",
+            })?;
+        let parse_number = |number: &str| {
+            number
+                .parse()
+                .map_err(|_| PacketParseError::UnexpectedValue {
+                    packet: "LobbyPacket",
+                })
+        };
+        Ok(match value[4] {
+            0 => Self::Register(payload.to_owned()),
+            1 => Self::ListGames,
+            2 => Self::GameList(
+                payload
+                    .lines()
+                    .map(|line| {
+                        let (id, name) =
+                            line.split_once(' ')
+                                .ok_or(PacketParseError::UnexpectedValue {
+                                    packet: "LobbyPacket",
+                                })?;
+                        Ok((parse_number(id)?, name.to_owned()))
+                    })
+                    .collect::<Result<_, PacketParseError>>()?,
+            ),
+            3 => Self::CreateGame,
+            4 => Self::GameCreated(parse_number(payload)?),
+            5 => Self::JoinGame(parse_number(payload)?),
+            6 => Self::Error(payload.to_owned()),
+            7 => Self::JoinRoom(payload.to_owned()),
+            8 => Self::CreateAccount(payload.to_owned()),
+            9 => Self::AccountCreated(payload.to_owned()),
+            10 => {
+                let (name, token) =
+                    payload
+                        .split_once(' ')
+                        .ok_or(PacketParseError::UnexpectedValue {
+                            packet: "LobbyPacket",
+                        })?;
+                Self::Login {
+                    name: name.to_owned(),
+                    token: token.to_owned(),
+                }
+            }
+            11 => Self::LoggedIn,
+            12 => Self::GetLeaderboard,
+            13 => Self::Leaderboard(
+                payload
+                    .lines()
+                    .map(|line| {
+                        let fields: Vec<&str> = line.split(' ').collect();
+                        let [name, wins, draws, losses] = fields[..] else {
+                            return Err(PacketParseError::UnexpectedValue {
+                                packet: "LobbyPacket",
+                            });
+                        };
+                        Ok(LeaderboardEntry {
+                            name: name.to_owned(),
+                            wins: parse_number(wins)?,
+                            draws: parse_number(draws)?,
+                            losses: parse_number(losses)?,
+                        })
+                    })
+                    .collect::<Result<_, PacketParseError>>()?,
+            ),
+            _ => {
+                return Err(PacketParseError::UnexpectedValue {
+                    packet: "LobbyPacket",
+                })
+            }
+        })
     }
 }
+impl LobbyPacket {
+    /// Serializes the packet. Text never contains the terminator, since it is not valid UTF-8.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (kind, payload) = match self {
+            Self::Register(name) => (0, name.clone()),
+            Self::ListGames => (1, String::new()),
+            Self::GameList(games) => (
+                2,
+                games
+                    .iter()
+                    .map(|(id, name)| format!("{} {}", id, name))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Self::CreateGame => (3, String::new()),
+            Self::GameCreated(id) => (4, id.to_string()),
+            Self::JoinGame(id) => (5, id.to_string()),
+            Self::Error(message) => (6, message.clone()),
+            Self::JoinRoom(code) => (7, code.clone()),
+            Self::CreateAccount(name) => (8, name.clone()),
+            Self::AccountCreated(token) => (9, token.clone()),
+            Self::Login { name, token } => (10, format!("{} {}", name, token)),
+            Self::LoggedIn => (11, String::new()),
+            Self::GetLeaderboard => (12, String::new()),
+            Self::Leaderboard(entries) => (
+                13,
+                entries
+                    .iter()
+                    .map(|entry| {
+                        format!(
+                            "{} {} {} {}",
+                            entry.name, entry.wins, entry.draws, entry.losses
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        };
 
-#[derive(Debug, Clone, Copy)]
-pub struct PlayerMove(pub usize, pub usize);
-impl From<u8> for PlayerMove {
-    fn from(value: u8) -> Self {
-        let row = value >> 4;
-        let col = value & 0b1111;
-        Self(row as usize, col as usize)
+        let mut pkt = LOBBY_MAGIC.to_be_bytes().to_vec();
+        pkt.push(kind);
+        pkt.extend_from_slice(payload.as_bytes());
+        sealed(pkt)
     }
 }
-impl PlayerMove {
-    pub fn to_tuple(self) -> (usize, usize) {
-        (self.0, self.1)
+
+/// Any packet of the protocol, for receivers accepting several kinds of packets. It is parsed from
+/// its magic value, or its size for [`PlayerMove`] which doesn't have one.
+///
+/// A [`ServerHello`] telling the client it plays `O` second, without a token, has the same bytes as
+/// [`ClientHello`] and is parsed as such: clients read the host's reply with
+/// [`ServerHello::try_from`] instead.
+///
+/// With the `serde` feature, packets are serialized as maps with their kind in the `type` field,
+/// the representation of the JSON encoding (see the `json` module). Discovery and lobby packets
+/// can't be serialized.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum Packet {
+    ClientHello,
+    Introduction(Introduction),
+    ServerHello(ServerHello),
+    ClientResume(ClientResume),
+    Reconnect(Reconnect),
+    #[cfg_attr(feature = "serde", serde(rename = "move"))]
+    PlayerMove(PlayerMove),
+    #[cfg_attr(feature = "serde", serde(rename = "ack"))]
+    MoveAck(MoveAck),
+    InvalidMove(InvalidMove),
+    EndOfGame(EndOfGame),
+    Resign,
+    #[cfg_attr(feature = "serde", serde(rename = "clock"))]
+    ClockUpdate(ClockUpdate),
+    Rematch(Rematch),
+    NewGame(NewGame),
+    BoardSync(BoardSync),
+    #[cfg_attr(feature = "serde", serde(rename = "spectate"))]
+    SpectatorHello,
+    MoveBroadcast(MoveBroadcast),
+    #[cfg_attr(feature = "serde", serde(rename = "chat"))]
+    ChatMessage(ChatMessage),
+    Heartbeat,
+    ServerShutdown(ServerShutdown),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    DiscoveryProbe,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    HostAnnouncement(HostAnnouncement),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    RoomProbe(RoomProbe),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Lobby(LobbyPacket),
+}
+impl Packet {
+    /// Parses a packet read from a connection, including its checksum and terminator.
+    pub fn parse(buf: &[u8]) -> Result<Self, PacketParseError> {
+        let pkt = unframe(buf)?;
+        if pkt.len() == 2 {
+            return Ok(Self::PlayerMove(PlayerMove::try_from(pkt)?));
+        }
+        let Some(&[a, b, c, d]) = pkt.get(0..4) else {
+            return Err(PacketParseError::InvalidSize { packet: "unknown" });
+        };
+
+        Ok(match u32::from_be_bytes([a, b, c, d]) {
+            HELLO_MAGIC if pkt.len() == 4 => Self::ClientHello,
+            // The last 2 bits of the SERVER_HELLO magic are flags
+            magic if magic & !0b11 == HELLO_MAGIC => Self::ServerHello(pkt.try_into()?),
+            INTRODUCTION_MAGIC => Self::Introduction(pkt.try_into()?),
+            RESUME_MAGIC => Self::ClientResume(pkt.try_into()?),
+            RECONNECT_MAGIC => Self::Reconnect(pkt.try_into()?),
+            ACK_MAGIC => Self::MoveAck(pkt.try_into()?),
+            INVALID_MOVE_MAGIC => Self::InvalidMove(pkt.try_into()?),
+            EOG_MAGIC => Self::EndOfGame(pkt.try_into()?),
+            RESIGN_MAGIC => {
+                Resign::try_from(pkt)?;
+                Self::Resign
+            }
+            CLOCK_MAGIC => Self::ClockUpdate(pkt.try_into()?),
+            REMATCH_MAGIC => Self::Rematch(pkt.try_into()?),
+            NEW_GAME_MAGIC => Self::NewGame(pkt.try_into()?),
+            SYNC_MAGIC => Self::BoardSync(pkt.try_into()?),
+            SPECTATE_MAGIC if pkt.len() == 4 => Self::SpectatorHello,
+            SPECTATE_MAGIC => Self::MoveBroadcast(pkt.try_into()?),
+            CHAT_MAGIC => Self::ChatMessage(pkt.try_into()?),
+            HEARTBEAT_MAGIC => {
+                Heartbeat::try_from(pkt)?;
+                Self::Heartbeat
+            }
+            SHUTDOWN_MAGIC => Self::ServerShutdown(pkt.try_into()?),
+            DISCOVERY_MAGIC if pkt.len() == 4 => Self::DiscoveryProbe,
+            DISCOVERY_MAGIC => Self::HostAnnouncement(pkt.try_into()?),
+            ROOM_PROBE_MAGIC => Self::RoomProbe(pkt.try_into()?),
+            LOBBY_MAGIC => Self::Lobby(pkt.try_into()?),
+            _ => return Err(PacketParseError::InvalidMagic { packet: "unknown" }),
+        })
     }
 
-    pub fn to_bytes(self) -> [u8; 2] {
-        let mut pkt = [0_u8; 2];
-        pkt[0] = (self.0 << 4) as u8 + (self.1 as u8 & 0b1111);
-        pkt[1] = TERMINATOR;
-        pkt
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::ClientHello => ClientHello.to_bytes().to_vec(),
+            Self::ServerHello(pkt) => pkt.to_bytes(),
+            Self::Introduction(pkt) => pkt.to_bytes(),
+            Self::ClientResume(pkt) => pkt.to_bytes().to_vec(),
+            Self::Reconnect(pkt) => pkt.to_bytes().to_vec(),
+            Self::PlayerMove(pkt) => pkt.to_bytes().to_vec(),
+            Self::MoveAck(pkt) => pkt.to_bytes().to_vec(),
+            Self::InvalidMove(pkt) => pkt.to_bytes().to_vec(),
+            Self::EndOfGame(pkt) => pkt.to_bytes().to_vec(),
+            Self::Resign => Resign.to_bytes().to_vec(),
+            Self::ClockUpdate(pkt) => pkt.to_bytes().to_vec(),
+            Self::Rematch(pkt) => pkt.to_bytes().to_vec(),
+            Self::NewGame(pkt) => pkt.to_bytes().to_vec(),
+            Self::BoardSync(pkt) => pkt.to_bytes().to_vec(),
+            Self::SpectatorHello => SpectatorHello.to_bytes().to_vec(),
+            Self::MoveBroadcast(pkt) => pkt.to_bytes().to_vec(),
+            Self::ChatMessage(pkt) => pkt.to_bytes(),
+            Self::Heartbeat => Heartbeat.to_bytes().to_vec(),
+            Self::ServerShutdown(pkt) => pkt.to_bytes().to_vec(),
+            Self::DiscoveryProbe => DiscoveryProbe.to_bytes().to_vec(),
+            Self::HostAnnouncement(pkt) => pkt.to_bytes(),
+            Self::RoomProbe(pkt) => pkt.to_bytes(),
+            Self::Lobby(pkt) => pkt.to_bytes(),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct EndOfGame;
-impl TryFrom<&[u8]> for EndOfGame {
-    type Error = PacketParseError;
+/// Serialized forms of the packets whose fields don't map directly to their serialized fields.
+#[cfg(feature = "serde")]
+mod fields {
+    use std::time::Duration;
 
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        if value.len() != 4 {
-            return Err(PacketParseError::InvalidSize);
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::{game::GameResult, grid::GridPlacementError};
+
+    #[derive(Serialize, Deserialize)]
+    pub struct EndOfGame {
+        result: GameResult,
+    }
+
+    impl From<super::EndOfGame> for EndOfGame {
+        fn from(value: super::EndOfGame) -> Self {
+            Self { result: value.0 }
         }
+    }
 
-        if value != EOG_MAGIC.to_be_bytes() {
-            return Err(PacketParseError::InvalidMagic);
+    impl From<EndOfGame> for super::EndOfGame {
+        fn from(value: EndOfGame) -> Self {
+            Self(value.result)
         }
-        Ok(Self)
     }
-}
-impl EndOfGame {
-    pub fn to_bytes(self) -> [u8; 5] {
-        let mut pkt = [0_u8; 5];
-        pkt[0..4].copy_from_slice(&EOG_MAGIC.to_be_bytes());
-        pkt[4] = TERMINATOR;
-        pkt
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Rematch {
+        accept: bool,
+    }
+
+    impl From<super::Rematch> for Rematch {
+        fn from(value: super::Rematch) -> Self {
+            Self { accept: value.0 }
+        }
+    }
+
+    impl From<Rematch> for super::Rematch {
+        fn from(value: Rematch) -> Self {
+            Self(value.accept)
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ChatMessage {
+        message: String,
+    }
+
+    impl From<super::ChatMessage> for ChatMessage {
+        fn from(value: super::ChatMessage) -> Self {
+            Self { message: value.0 }
+        }
+    }
+
+    impl From<ChatMessage> for super::ChatMessage {
+        fn from(value: ChatMessage) -> Self {
+            Self(value.message)
+        }
+    }
+
+    /// The rejected cell is only sent once, in `row` and `col`.
+    #[derive(Serialize, Deserialize)]
+    pub struct InvalidMove {
+        row: usize,
+        col: usize,
+        seq: u8,
+        reason: Reason,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Reason {
+        CellInUse,
+        OutOfBounds,
+    }
+
+    impl From<super::InvalidMove> for InvalidMove {
+        fn from(value: super::InvalidMove) -> Self {
+            let reason = match value.reason {
+                GridPlacementError::CellInUse { .. } => Reason::CellInUse,
+                GridPlacementError::OutOfBounds { .. } => Reason::OutOfBounds,
+            };
+            Self {
+                row: value.row,
+                col: value.col,
+                seq: value.seq,
+                reason,
+            }
+        }
+    }
+
+    impl From<InvalidMove> for super::InvalidMove {
+        fn from(value: InvalidMove) -> Self {
+            let InvalidMove { row, col, seq, .. } = value;
+            let reason = match value.reason {
+                Reason::CellInUse => GridPlacementError::CellInUse { row, col },
+                Reason::OutOfBounds => GridPlacementError::OutOfBounds { row, col },
+            };
+            Self {
+                row,
+                col,
+                seq,
+                reason,
+            }
+        }
+    }
+
+    /// Tokens are hexadecimal strings, since they don't fit in a JavaScript number.
+    pub mod token {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(token: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+            format!("{:016x}", token).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+            let hex = String::deserialize(deserializer)?;
+            u64::from_str_radix(&hex, 16).map_err(|_| D::Error::custom("invalid hexadecimal token"))
+        }
+    }
+
+    pub mod optional_token {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            token: &Option<u64>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            token
+                .map(|token| format!("{:016x}", token))
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<u64>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|hex| u64::from_str_radix(&hex, 16))
+                .transpose()
+                .map_err(|_| D::Error::custom("invalid hexadecimal token"))
+        }
+    }
+
+    pub mod millis {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            duration: &Duration,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            (duration.as_millis() as u64).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Duration, D::Error> {
+            u64::deserialize(deserializer).map(Duration::from_millis)
+        }
     }
 }
 
@@ -157,17 +1630,24 @@ mod tests {
     #[test]
     fn validate_client_hello_pkt_ser_de() {
         let bytes = ClientHello.to_bytes();
-        assert_eq!(bytes[4], TERMINATOR);
+        assert_eq!(bytes[5], TERMINATOR);
         assert!(ClientHello::try_from(&bytes[0..4]).is_ok())
     }
 
     #[test]
     fn fail_invalid_len_client_hello_pkt() {
         let bytes = [0; 6];
-        assert!(matches!(
-            ClientHello::try_from(bytes.as_slice()),
-            Err(PacketParseError::InvalidSize)
-        ))
+        let err = ClientHello::try_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(
+            err,
+            PacketParseError::InvalidSize {
+                packet: "ClientHello"
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Error parsing ClientHello packet: Wrong packet size"
+        );
     }
 
     #[test]
@@ -175,19 +1655,44 @@ mod tests {
         let bytes = [0; 4];
         assert!(matches!(
             ClientHello::try_from(bytes.as_slice()),
-            Err(PacketParseError::InvalidMagic)
+            Err(PacketParseError::InvalidMagic { .. })
         ))
     }
 
+    #[test]
+    fn validate_introduction_pkt_ser_de() {
+        let pkt = Introduction::new("Ali\nce").with_password("pass\nword");
+        let bytes = pkt.to_bytes();
+        let parsed = Introduction::try_from(unframe(&bytes).unwrap()).unwrap();
+        assert_eq!(parsed.version, PROTOCOL_VERSION);
+        assert_eq!(parsed.nickname, "Ali ce");
+        assert_eq!(parsed.password, "pass\nword");
+
+        let anonymous = Introduction::new("").to_bytes();
+        assert_eq!(
+            Introduction::try_from(unframe(&anonymous).unwrap()),
+            Ok(Introduction::new(""))
+        );
+        let mut no_version = anonymous;
+        no_version[4] = 0;
+        assert_eq!(
+            Introduction::try_from(&no_version[..no_version.len() - 2]),
+            Err(PacketParseError::UnexpectedValue {
+                packet: "Introduction"
+            })
+        );
+    }
+
     #[test]
     fn validate_server_hello_pkt_ser_de_1() {
         let pkt = ServerHello {
             client_first: true,
             client_mark: Mark::O,
+            token: None,
         };
         let bytes = pkt.to_bytes();
 
-        assert_eq!(bytes[4], TERMINATOR);
+        assert_eq!(bytes[5], TERMINATOR);
         let deserialized =
             ServerHello::try_from(&bytes[0..4]).expect("Error deserializing the byte value");
         assert_eq!(deserialized.client_mark, pkt.client_mark);
@@ -199,10 +1704,11 @@ mod tests {
         let pkt = ServerHello {
             client_first: false,
             client_mark: Mark::X,
+            token: None,
         };
         let bytes = pkt.to_bytes();
 
-        assert_eq!(bytes[4], TERMINATOR);
+        assert_eq!(bytes[5], TERMINATOR);
         let deserialized =
             ServerHello::try_from(&bytes[0..4]).expect("Error deserializing the byte value");
         assert_eq!(deserialized.client_mark, pkt.client_mark);
@@ -214,7 +1720,7 @@ mod tests {
         let bytes = [0; 6];
         assert!(matches!(
             ServerHello::try_from(bytes.as_slice()),
-            Err(PacketParseError::InvalidSize)
+            Err(PacketParseError::InvalidSize { .. })
         ))
     }
 
@@ -223,27 +1729,142 @@ mod tests {
         let bytes = [0; 4];
         assert!(matches!(
             ServerHello::try_from(bytes.as_slice()),
-            Err(PacketParseError::InvalidMagic)
+            Err(PacketParseError::InvalidMagic { .. })
         ))
     }
 
     #[test]
     fn validate_player_move_pkt_ser_de() {
-        let pkt = PlayerMove(15, 8);
+        let pkt = PlayerMove {
+            row: 15,
+            col: 8,
+            seq: 4,
+        };
+        let bytes = pkt.to_bytes();
+
+        assert_eq!(bytes[3], TERMINATOR);
+
+        let deserialized = PlayerMove::try_from(&bytes[0..2]).unwrap();
+        assert_eq!(pkt, deserialized);
+    }
+
+    #[test]
+    fn validate_move_ack_pkt_ser_de() {
+        let pkt = MoveAck { seq: 9 };
+        let bytes = pkt.to_bytes();
+        assert_eq!(bytes[6], TERMINATOR);
+        assert_eq!(MoveAck::try_from(&bytes[0..5]).unwrap(), pkt);
+        assert!(matches!(
+            MoveAck::try_from(&Rematch(true).to_bytes()[0..5]),
+            Err(PacketParseError::InvalidMagic { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_invalid_move_pkt_ser_de() {
+        let pkt = InvalidMove {
+            row: 4,
+            col: 1,
+            seq: 3,
+            reason: GridPlacementError::OutOfBounds { row: 4, col: 1 },
+        };
         let bytes = pkt.to_bytes();
+        assert_eq!(bytes[8], TERMINATOR);
+        let parsed = InvalidMove::try_from(&bytes[0..7]).unwrap();
+        assert!(parsed.rejects(&PlayerMove {
+            row: 4,
+            col: 1,
+            seq: 3
+        }));
+        assert_eq!(
+            parsed.reason,
+            GridPlacementError::OutOfBounds { row: 4, col: 1 }
+        );
 
-        assert_eq!(bytes[1], TERMINATOR);
+        let mut bytes = bytes;
+        bytes[6] = 2;
+        assert!(matches!(
+            InvalidMove::try_from(&bytes[0..7]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_discovery_pkt_ser_de() {
+        let bytes = DiscoveryProbe.to_bytes();
+        assert_eq!(bytes[5], TERMINATOR);
+        assert!(DiscoveryProbe::try_from(&bytes[0..4]).is_ok());
+        assert!(HostAnnouncement::try_from(&bytes[0..4]).is_err());
+
+        let pkt = HostAnnouncement { port: 65535 };
+        let bytes = pkt.to_bytes();
+        assert_eq!(bytes.last(), Some(&TERMINATOR));
+        assert!(!bytes[..bytes.len() - 1].contains(&TERMINATOR));
+        assert_eq!(
+            HostAnnouncement::try_from(&bytes[..bytes.len() - 2]).unwrap(),
+            pkt
+        );
+        assert!(matches!(
+            HostAnnouncement::try_from(&[&bytes[0..4], b"port"].concat()[..]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_client_resume_pkt_ser_de() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(2, 1, Mark::O);
+        let pkt = ClientResume {
+            grid,
+            client_turn: true,
+            client_mark: Mark::O,
+        };
+        let bytes = pkt.to_bytes();
+
+        assert_eq!(bytes[15], TERMINATOR);
+        let deserialized =
+            ClientResume::try_from(&bytes[0..14]).expect("Error deserializing the byte value");
+        assert_eq!(deserialized.grid, pkt.grid);
+        assert_eq!(deserialized.client_turn, pkt.client_turn);
+        assert_eq!(deserialized.client_mark, pkt.client_mark);
+    }
 
-        let deserialized = PlayerMove::from(bytes[0]);
-        assert_eq!(pkt.0, deserialized.0);
-        assert_eq!(pkt.1, deserialized.1);
+    #[test]
+    fn fail_invalid_grid_client_resume_pkt() {
+        let mut bytes = ClientResume {
+            grid: Grid::default(),
+            client_turn: false,
+            client_mark: Mark::X,
+        }
+        .to_bytes();
+        bytes[5] = 4;
+        assert!(matches!(
+            ClientResume::try_from(&bytes[0..14]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ))
     }
 
     #[test]
     fn validate_eog_pkt_ser_de() {
-        let bytes = EndOfGame.to_bytes();
-        assert_eq!(bytes[4], TERMINATOR);
-        assert!(EndOfGame::try_from(&bytes[0..4]).is_ok())
+        let results = [
+            GameResult::Win {
+                winner: Mark::O,
+                reason: WinReason::Resignation,
+            },
+            GameResult::Win {
+                winner: Mark::X,
+                reason: WinReason::Line,
+            },
+            GameResult::Draw(DrawReason::BoardFull),
+        ];
+        for result in results {
+            let bytes = EndOfGame(result).to_bytes();
+            assert_eq!(bytes[8], TERMINATOR);
+            let deserialized =
+                EndOfGame::try_from(&bytes[0..7]).expect("Error deserializing the byte value");
+            assert_eq!(deserialized.0, result);
+        }
     }
 
     #[test]
@@ -251,15 +1872,338 @@ mod tests {
         let bytes = [0; 6];
         assert!(matches!(
             EndOfGame::try_from(bytes.as_slice()),
-            Err(PacketParseError::InvalidSize)
+            Err(PacketParseError::InvalidSize { .. })
         ))
     }
     #[test]
     fn fail_invalid_magic_eog_pkt() {
-        let bytes = [0; 4];
+        let bytes = [0; 7];
         assert!(matches!(
             EndOfGame::try_from(bytes.as_slice()),
-            Err(PacketParseError::InvalidMagic)
+            Err(PacketParseError::InvalidMagic { .. })
         ))
     }
+
+    #[test]
+    fn validate_lobby_pkt_ser_de() {
+        let packets = [
+            LobbyPacket::Register("Alice".to_owned()),
+            LobbyPacket::ListGames,
+            LobbyPacket::GameList(vec![(1, "Alice".to_owned()), (12, "Bob B".to_owned())]),
+            LobbyPacket::GameList(vec![]),
+            LobbyPacket::CreateGame,
+            LobbyPacket::GameCreated(3),
+            LobbyPacket::JoinGame(3),
+            LobbyPacket::Error("No such game".to_owned()),
+            LobbyPacket::JoinRoom("blue-otter".to_owned()),
+            LobbyPacket::CreateAccount("alice".to_owned()),
+            LobbyPacket::AccountCreated("0123abcd".to_owned()),
+            LobbyPacket::Login {
+                name: "alice".to_owned(),
+                token: "0123abcd".to_owned(),
+            },
+            LobbyPacket::LoggedIn,
+            LobbyPacket::GetLeaderboard,
+            LobbyPacket::Leaderboard(vec![
+                LeaderboardEntry {
+                    name: "alice".to_owned(),
+                    wins: 3,
+                    draws: 1,
+                    losses: 0,
+                },
+                LeaderboardEntry {
+                    name: "bob".to_owned(),
+                    wins: 0,
+                    draws: 1,
+                    losses: 3,
+                },
+            ]),
+            LobbyPacket::Leaderboard(vec![]),
+        ];
+        for pkt in packets {
+            let bytes = pkt.to_bytes();
+            assert_eq!(
+                bytes.iter().position(|&b| b == TERMINATOR),
+                Some(bytes.len() - 1)
+            );
+            let deserialized = LobbyPacket::try_from(&bytes[..bytes.len() - 2])
+                .expect("Error deserializing the byte value");
+            assert_eq!(deserialized, pkt);
+        }
+    }
+
+    #[test]
+    fn fail_invalid_lobby_pkt() {
+        let mut bytes = LobbyPacket::JoinGame(3).to_bytes();
+        bytes[5] = b'x';
+        assert!(matches!(
+            LobbyPacket::try_from(&bytes[..bytes.len() - 2]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+        assert!(matches!(
+            LobbyPacket::try_from(&ClientHello.to_bytes()[0..4]),
+            Err(PacketParseError::InvalidSize { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_spectator_pkt_ser_de() {
+        let bytes = SpectatorHello.to_bytes();
+        assert_eq!(bytes[5], TERMINATOR);
+        assert!(SpectatorHello::try_from(&bytes[0..4]).is_ok());
+        assert!(SpectatorHello::try_from(&ClientHello.to_bytes()[0..4]).is_err());
+
+        let pkt = MoveBroadcast {
+            mark: Mark::O,
+            row: 2,
+            col: 1,
+        };
+        let bytes = pkt.to_bytes();
+        assert_eq!(bytes[7], TERMINATOR);
+        let deserialized =
+            MoveBroadcast::try_from(&bytes[0..6]).expect("Error deserializing the byte value");
+        assert_eq!(deserialized, pkt);
+    }
+
+    #[test]
+    fn validate_chat_pkt_ser_de() {
+        let pkt = ChatMessage("Good luck, have fun!".to_owned());
+        let bytes = pkt.to_bytes();
+        assert_eq!(bytes.last(), Some(&TERMINATOR));
+        let deserialized = ChatMessage::try_from(&bytes[..bytes.len() - 2])
+            .expect("Error deserializing the byte value");
+        assert_eq!(deserialized, pkt);
+
+        let bytes = ChatMessage("a".repeat(MAX_CHAT_LEN + 1)).to_bytes();
+        assert!(matches!(
+            ChatMessage::try_from(&bytes[..bytes.len() - 2]),
+            Err(PacketParseError::InvalidSize { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_reconnect_pkt_ser_de() {
+        let pkt = ServerHello {
+            client_first: false,
+            client_mark: Mark::O,
+            token: Some(u64::MAX),
+        };
+        let bytes = pkt.to_bytes();
+        assert!(!bytes[..bytes.len() - 1].contains(&TERMINATOR));
+        let deserialized = ServerHello::try_from(&bytes[..bytes.len() - 2])
+            .expect("Error deserializing the byte value");
+        assert_eq!(deserialized.token, pkt.token);
+
+        let pkt = Reconnect { token: 0x0123_4567 };
+        let bytes = pkt.to_bytes();
+        assert_eq!(Reconnect::try_from(&bytes[0..20]).unwrap(), pkt);
+    }
+
+    #[test]
+    fn validate_board_sync_pkt_ser_de() {
+        let mut grid = Grid::default();
+        grid.set_cell(2, 0, Mark::X);
+        let pkt = BoardSync {
+            grid,
+            turn: Mark::O,
+        };
+        let bytes = pkt.to_bytes();
+        assert_eq!(bytes[15], TERMINATOR);
+        assert_eq!(BoardSync::try_from(&bytes[0..14]).unwrap(), pkt);
+
+        let mut bytes = pkt.to_bytes();
+        bytes[13] = 0;
+        assert!(matches!(
+            BoardSync::try_from(&bytes[0..14]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_heartbeat_pkt_ser_de() {
+        let bytes = Heartbeat.to_bytes();
+        assert_eq!(bytes[5], TERMINATOR);
+        assert!(Heartbeat::try_from(&bytes[0..4]).is_ok());
+        assert!(matches!(
+            Heartbeat::try_from(&Resign.to_bytes()[0..4]),
+            Err(PacketParseError::InvalidMagic { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_new_game_pkt_ser_de() {
+        let pkt = NewGame {
+            client_first: false,
+            client_mark: Mark::X,
+        };
+        let bytes = pkt.to_bytes();
+        assert_eq!(bytes[7], TERMINATOR);
+        assert_eq!(NewGame::try_from(&bytes[0..6]).unwrap(), pkt);
+
+        let mut bytes = pkt.to_bytes();
+        bytes[4] = 2;
+        assert!(matches!(
+            NewGame::try_from(&bytes[0..6]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_rematch_pkt_ser_de() {
+        for accepted in [true, false] {
+            let bytes = Rematch(accepted).to_bytes();
+            assert_eq!(bytes[6], TERMINATOR);
+            assert_eq!(Rematch::try_from(&bytes[0..5]).unwrap(), Rematch(accepted));
+        }
+
+        let mut bytes = Rematch(true).to_bytes();
+        bytes[4] = 2;
+        assert!(matches!(
+            Rematch::try_from(&bytes[0..5]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_clock_update_pkt_ser_de() {
+        let pkt = ClockUpdate {
+            x: Duration::from_millis(90_250),
+            o: Duration::from_secs(u64::MAX),
+        };
+        let bytes = pkt.to_bytes();
+        assert!(!bytes[..bytes.len() - 1].contains(&TERMINATOR));
+        let deserialized = ClockUpdate::try_from(&bytes[0..20]).unwrap();
+        assert_eq!(deserialized.x, pkt.x);
+        assert_eq!(deserialized.o, Duration::from_millis(u32::MAX.into()));
+
+        let mut bytes = pkt.to_bytes();
+        bytes[4] = b'g';
+        assert!(matches!(
+            ClockUpdate::try_from(&bytes[0..20]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_server_shutdown_pkt_ser_de() {
+        let pkt = ServerShutdown {
+            grace_period: Duration::from_millis(12_345),
+        };
+        let bytes = pkt.to_bytes();
+        assert!(!bytes[..bytes.len() - 1].contains(&TERMINATOR));
+        assert_eq!(ServerShutdown::try_from(&bytes[0..12]), Ok(pkt));
+
+        let mut bytes = pkt.to_bytes();
+        bytes[11] = b'z';
+        assert!(matches!(
+            ServerShutdown::try_from(&bytes[0..12]),
+            Err(PacketParseError::UnexpectedValue { .. })
+        ));
+    }
+
+    #[test]
+    fn corrupted_pkts_fail_checksum() {
+        let bytes = PlayerMove {
+            row: 1,
+            col: 2,
+            seq: 3,
+        }
+        .to_bytes();
+        assert!(unframe(&bytes).is_ok());
+
+        let mut corrupted = bytes;
+        corrupted[0] = cell_to_byte(2, 1);
+        assert!(matches!(
+            unframe(&corrupted),
+            Err(PacketParseError::BadChecksum)
+        ));
+        assert!(matches!(
+            unframe(&bytes[1..]),
+            Err(PacketParseError::BadChecksum)
+        ));
+        assert!(matches!(
+            unframe(&[TERMINATOR]),
+            Err(PacketParseError::InvalidSize { .. })
+        ));
+
+        // The checksum can't end a packet early
+        for b in 0..=u8::MAX {
+            assert_ne!(checksum(&[b]), TERMINATOR);
+        }
+    }
+
+    #[test]
+    fn pkts_are_parsed_by_kind() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 2, Mark::O);
+        let packets = [
+            Packet::ClientHello,
+            Packet::Introduction(Introduction::new("Alice").with_password("secret")),
+            Packet::ServerHello(ServerHello {
+                client_first: true,
+                client_mark: Mark::X,
+                token: Some(42),
+            }),
+            Packet::ClientResume(ClientResume {
+                grid,
+                client_turn: false,
+                client_mark: Mark::X,
+            }),
+            Packet::Reconnect(Reconnect { token: 42 }),
+            Packet::PlayerMove(PlayerMove {
+                row: 2,
+                col: 2,
+                seq: 5,
+            }),
+            Packet::MoveAck(MoveAck { seq: 5 }),
+            Packet::InvalidMove(InvalidMove {
+                row: 3,
+                col: 0,
+                seq: 1,
+                reason: GridPlacementError::OutOfBounds { row: 3, col: 0 },
+            }),
+            Packet::EndOfGame(EndOfGame(GameResult::Draw(DrawReason::Agreement))),
+            Packet::Resign,
+            Packet::ClockUpdate(ClockUpdate {
+                x: Duration::from_millis(61_500),
+                o: Duration::ZERO,
+            }),
+            Packet::Rematch(Rematch(false)),
+            Packet::NewGame(NewGame {
+                client_first: true,
+                client_mark: Mark::O,
+            }),
+            Packet::BoardSync(BoardSync {
+                grid,
+                turn: Mark::X,
+            }),
+            Packet::SpectatorHello,
+            Packet::MoveBroadcast(MoveBroadcast {
+                mark: Mark::O,
+                row: 0,
+                col: 2,
+            }),
+            Packet::ChatMessage(ChatMessage(String::new())),
+            Packet::Heartbeat,
+            Packet::ServerShutdown(ServerShutdown {
+                grace_period: Duration::from_secs(30),
+            }),
+            Packet::DiscoveryProbe,
+            Packet::HostAnnouncement(HostAnnouncement { port: 8905 }),
+            Packet::RoomProbe(RoomProbe("BLUE-FOX-42".to_owned())),
+            Packet::Lobby(LobbyPacket::ListGames),
+        ];
+        for pkt in packets {
+            let bytes = pkt.to_bytes();
+            let parsed = Packet::parse(&bytes).expect("Error parsing packet");
+            assert_eq!(format!("{:?}", parsed), format!("{:?}", pkt));
+            assert_eq!(parsed.to_bytes(), bytes);
+        }
+
+        let bytes = sealed(0x1234_5678_u32.to_be_bytes().to_vec());
+        assert!(matches!(
+            Packet::parse(&bytes),
+            Err(PacketParseError::InvalidMagic { .. })
+        ));
+    }
 }
@@ -0,0 +1,264 @@
+//! Python bindings, built with PyO3. Only available with the `python` feature. `maturin develop`
+//! builds and installs the `tictactoe` Python module, see `pyproject.toml`.
+//!
+//! The module exposes [`Grid`] as `Grid`, a game between 2 marks on a grid as `Game`, and
+//! [`BotPlayer`] as `Bot`. Marks are the strings `"X"` and `"O"`, and cells `(row, col)` tuples.
+//!
+//! ```python
+//! from tictactoe import Bot, Game
+//!
+//! game, bot = Game(), Bot("impossible")
+//! while not game.is_over:
+//!     game.play(*bot.choose_move(game.grid, game.current_mark))
+//! assert game.winner is None
+//! ```
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+};
+
+use crate::{
+    board::Board,
+    game::{self, GameResult},
+    grid::{Grid, Mark},
+    player::{BotPlayer, BotPlayerDifficulty, Move, Player},
+};
+
+/// [`Grid`] exposed to Python as `Grid`.
+#[pyclass(name = "Grid", module = "tictactoe", eq, from_py_object)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PyGrid(Grid);
+
+#[pymethods]
+impl PyGrid {
+    /// Creates an empty grid, or the grid of a position like `"X.O/.X./..O"`.
+    #[new]
+    #[pyo3(signature = (position = None))]
+    fn new(position: Option<&str>) -> PyResult<Self> {
+        match position {
+            Some(position) => position.parse().map(Self).map_err(value_error),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Returns the mark on the cell, or `None` if it is empty.
+    fn cell(&self, row: usize, col: usize) -> PyResult<Option<String>> {
+        check_bounds(row, col)?;
+        Ok(self
+            .0
+            .get_cell(row, col)
+            .try_get_mark()
+            .map(Mark::to_string))
+    }
+
+    /// Places `mark` on the cell, raising `ValueError` if it isn't empty.
+    fn place(&mut self, row: usize, col: usize, mark: &str) -> PyResult<()> {
+        let mark = parse_mark(mark)?;
+        self.0.try_set_cell(row, col, mark).map_err(value_error)
+    }
+
+    /// Returns the empty cells, in reading order.
+    fn legal_moves(&self) -> Vec<(usize, usize)> {
+        self.0.legal_moves()
+    }
+
+    /// Returns the mark with 3 in a row, if any.
+    fn winner(&self) -> Option<String> {
+        self.0.get_winning_mark().map(|mark| mark.to_string())
+    }
+
+    fn is_full(&self) -> bool {
+        self.0.is_full()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Grid('{}')", self.0.notation())
+    }
+}
+
+/// Game between `X` and `O` on a grid, exposed to Python as `Game`: moves are played one at a time
+/// for the mark whose turn it is. `X` plays first.
+#[pyclass(name = "Game", module = "tictactoe", skip_from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyGame {
+    grid: Grid,
+    turn: Mark,
+    result: Option<GameResult>,
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    fn new() -> Self {
+        Self {
+            grid: Grid::default(),
+            turn: Mark::X,
+            result: None,
+        }
+    }
+
+    /// Places the current mark on the cell, and hands the turn to the other mark. Raises
+    /// `ValueError` if the move is illegal or the game is over.
+    fn play(&mut self, row: usize, col: usize) -> PyResult<()> {
+        if self.result.is_some() {
+            return Err(PyValueError::new_err("The game is already over"));
+        }
+
+        self.grid
+            .try_set_cell(row, col, self.turn)
+            .map_err(value_error)?;
+        self.turn = self.turn.opposite();
+        self.result = game::board_result(&self.grid);
+        Ok(())
+    }
+
+    /// Lets `bot` play the current turn.
+    fn play_bot(&mut self, bot: &PyBot) -> PyResult<()> {
+        let (row, col) = bot.choose_move(&PyGrid(self.grid), &self.turn.to_string())?;
+        self.play(row, col)
+    }
+
+    /// Returns a copy of the grid.
+    #[getter]
+    fn grid(&self) -> PyGrid {
+        PyGrid(self.grid)
+    }
+
+    /// Returns the mark playing the next move.
+    #[getter]
+    fn current_mark(&self) -> String {
+        self.turn.to_string()
+    }
+
+    #[getter]
+    fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Returns the winning mark, or `None` if the game is a draw or still in progress.
+    #[getter]
+    fn winner(&self) -> Option<String> {
+        self.result
+            .and_then(|result| result.winner())
+            .map(|mark| mark.to_string())
+    }
+
+    fn legal_moves(&self) -> Vec<(usize, usize)> {
+        if self.result.is_some() {
+            vec![]
+        } else {
+            self.grid.legal_moves()
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.grid.to_string()
+    }
+}
+
+/// [`BotPlayer`] exposed to Python as `Bot`.
+#[pyclass(name = "Bot", module = "tictactoe", frozen)]
+#[derive(Debug)]
+pub struct PyBot(BotPlayer);
+
+#[pymethods]
+impl PyBot {
+    /// Creates a bot of the given difficulty: `"easy"`, `"normal"`, `"impossible"`, or a strength
+    /// from 0 to 100, the percentage of optimal moves. Bots created with the same `seed` make the
+    /// same choices.
+    #[new]
+    #[pyo3(signature = (difficulty = "impossible", seed = None))]
+    fn new(difficulty: &str, seed: Option<u64>) -> PyResult<Self> {
+        let difficulty = match difficulty {
+            "easy" => BotPlayerDifficulty::Easy,
+            "normal" => BotPlayerDifficulty::Normal,
+            "impossible" => BotPlayerDifficulty::Impossible,
+            strength => match strength.parse() {
+                Ok(strength @ 0..=100) => BotPlayerDifficulty::Strength(strength),
+                _ => return Err(PyValueError::new_err("Unknown bot difficulty")),
+            },
+        };
+        let bot = BotPlayer::from_difficulty(difficulty);
+        Ok(Self(match seed {
+            Some(seed) => bot.with_seed(seed),
+            None => bot,
+        }))
+    }
+
+    /// Returns the cell the bot would play for `mark` on `grid`. Raises `ValueError` if the game on
+    /// the grid is over, or if the bot doesn't place a mark, and `RuntimeError` if it fails to
+    /// choose a move.
+    fn choose_move(&self, grid: &PyGrid, mark: &str) -> PyResult<(usize, usize)> {
+        let mark = parse_mark(mark)?;
+        if game::board_result(&grid.0).is_some() {
+            return Err(PyValueError::new_err("The game is already over"));
+        }
+        match self.0.get_move(&grid.0, &mark) {
+            Ok(Move::Place(row, col)) => Ok((row, col)),
+            Ok(other) => Err(PyValueError::new_err(format!(
+                "The bot didn't place a mark: {:?}",
+                other
+            ))),
+            Err(e) => Err(PyRuntimeError::new_err(e.to_string())),
+        }
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "tictactoe")]
+fn tictactoe_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGrid>()?;
+    m.add_class::<PyGame>()?;
+    m.add_class::<PyBot>()?;
+    Ok(())
+}
+
+fn parse_mark(mark: &str) -> PyResult<Mark> {
+    match mark {
+        "X" | "x" => Ok(Mark::X),
+        "O" | "o" => Ok(Mark::O),
+        _ => Err(PyValueError::new_err(format!("Invalid mark {:?}", mark))),
+    }
+}
+
+fn check_bounds(row: usize, col: usize) -> PyResult<()> {
+    if row < 3 && col < 3 {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(format!(
+            "Cell ({}, {}) is out of bounds",
+            row, col
+        )))
+    }
+}
+
+fn value_error(error: impl std::error::Error) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn games_are_played_against_bots() {
+        let bot = PyBot::new("impossible", Some(0)).unwrap();
+        let mut game = PyGame::new();
+        while !game.is_over() {
+            game.play_bot(&bot).unwrap();
+        }
+        assert_eq!(game.winner(), None);
+        assert!(game.play(0, 0).is_err());
+        assert!(game.legal_moves().is_empty());
+
+        let grid = PyGrid::new(Some("XX./OO./...")).unwrap();
+        assert_eq!(bot.choose_move(&grid, "X").unwrap(), (0, 2));
+        assert_eq!(grid.__repr__(), "Grid('XX./OO./...')");
+        assert!(PyBot::new("101", None).is_err());
+    }
+}
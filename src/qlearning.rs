@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+};
+
+use rand::{seq::SliceRandom, Rng, RngCore};
+
+use crate::{
+    board::Board,
+    grid::{Grid, Mark},
+    player::{Move, Player, PlayerError},
+};
+
+/// Size in bytes of a serialized Q-table entry: state (u32), action (u8) and value (f64).
+const ENTRY_SIZE: usize = 13;
+
+/// Bot learning to play through self-play, using tabular Q-learning.
+///
+/// The Q-table scores every (position, cell) pair seen during training, from the point of view of
+/// the player about to move. Both marks share the same table, since positions are encoded relative
+/// to the player's own mark.
+///
+/// [`QLearningPlayer::train`] plays games against itself while exploring, whereas playing as a
+/// [`Player`] always picks the best known move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QLearningPlayer {
+    table: HashMap<(u32, u8), f64>,
+    learning_rate: f64,
+    discount: f64,
+    exploration: f64,
+}
+
+impl Default for QLearningPlayer {
+    fn default() -> Self {
+        Self::new(0.5, 0.9, 0.2)
+    }
+}
+
+impl QLearningPlayer {
+    /// Creates an untrained player.
+    /// - `learning_rate`: how much a single game updates the table, between 0 and 1
+    /// - `discount`: how much future rewards are worth compared to immediate ones, between 0 and 1
+    /// - `exploration`: probability of playing a random move while training, between 0 and 1
+    pub fn new(learning_rate: f64, discount: f64, exploration: f64) -> Self {
+        Self {
+            table: HashMap::new(),
+            learning_rate,
+            discount,
+            exploration,
+        }
+    }
+
+    /// Trains the player by making it play `episodes` games against itself.
+    pub fn train(&mut self, episodes: usize) {
+        self.train_with_rng(episodes, &mut rand::thread_rng());
+    }
+
+    /// Same as [`QLearningPlayer::train`], drawing random numbers from `rng` so training can be
+    /// reproduced.
+    pub fn train_with_rng(&mut self, episodes: usize, rng: &mut impl Rng) {
+        for _ in 0..episodes {
+            self.play_episode(rng);
+        }
+    }
+
+    /// Number of (position, cell) pairs learned so far.
+    pub fn table_size(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Serializes the Q-table, so a trained player can be saved and loaded later with
+    /// [`QLearningPlayer::from_bytes`]. Training parameters are not included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.table.len() * ENTRY_SIZE);
+        for (&(state, action), value) in &self.table {
+            bytes.extend_from_slice(&state.to_le_bytes());
+            bytes.push(action);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Loads a Q-table serialized with [`QLearningPlayer::to_bytes`], using the default training
+    /// parameters.
+    pub fn from_bytes(value: &[u8]) -> io::Result<Self> {
+        if !value.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "Invalid Q-table size",
+            ));
+        }
+
+        let mut player = Self::default();
+        for entry in value.chunks_exact(ENTRY_SIZE) {
+            let state = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+            let action = entry[4];
+            let value = f64::from_le_bytes(entry[5..13].try_into().unwrap());
+            player.table.insert((state, action), value);
+        }
+        Ok(player)
+    }
+
+    /// Plays a full game against itself, updating the table after every move.
+    fn play_episode(&mut self, rng: &mut impl Rng) {
+        let mut grid = Grid::default();
+        let mut mark = Mark::X;
+        loop {
+            let state = encode(&grid, &mark);
+            let action = if rng.gen_bool(self.exploration) {
+                *grid.legal_moves().choose(rng).unwrap()
+            } else {
+                self.best_move(&grid, &mark, Some(&mut *rng))
+            };
+            grid.set_cell(action.0, action.1, mark);
+
+            // Rewards are given to the player who just moved, and the opponent's best outcome
+            // from the next position counts against them.
+            let target = if grid.get_winning_mark().is_some() {
+                1.0
+            } else if grid.is_full() {
+                0.0
+            } else {
+                let next = encode(&grid, &mark.opposite());
+                -self.discount * self.max_value(next, &grid)
+            };
+
+            let value = self.table.entry((state, to_action(action))).or_default();
+            *value += self.learning_rate * (target - *value);
+
+            if grid.get_winning_mark().is_some() || grid.is_full() {
+                return;
+            }
+            mark = mark.opposite();
+        }
+    }
+
+    /// Returns the legal move with the highest value for `mark`. Ties are broken randomly when
+    /// `rng` is set, or by picking the first cell in reading order otherwise.
+    fn best_move(&self, grid: &Grid, mark: &Mark, rng: Option<&mut dyn RngCore>) -> (usize, usize) {
+        let state = encode(grid, mark);
+        let moves = grid.legal_moves();
+        let best = moves
+            .iter()
+            .map(|&m| self.value(state, m))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let best_moves: Vec<_> = moves
+            .into_iter()
+            .filter(|&m| self.value(state, m) == best)
+            .collect();
+
+        match rng {
+            Some(rng) => *best_moves.choose(rng).unwrap(),
+            None => best_moves[0],
+        }
+    }
+
+    fn value(&self, state: u32, action: (usize, usize)) -> f64 {
+        self.table
+            .get(&(state, to_action(action)))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Highest value among the legal moves of `grid`, encoded as `state`.
+    fn max_value(&self, state: u32, grid: &Grid) -> f64 {
+        grid.legal_moves()
+            .into_iter()
+            .map(|m| self.value(state, m))
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+impl Player for QLearningPlayer {
+    fn get_move(&self, grid: &Grid, mark: &Mark) -> Result<Move, PlayerError> {
+        let (row, col) = self.best_move(grid, mark, None);
+        Ok(Move::Place(row, col))
+    }
+
+    /// Like other bots, only accepts a draw once neither side can win anymore.
+    fn accept_draw(&self, grid: &Grid, _: &Mark) -> Result<bool, PlayerError> {
+        Ok(grid.is_draw_certain())
+    }
+}
+
+/// Encodes `grid` as a base 3 number, from the point of view of `mark`: each cell is 0 if empty,
+/// 1 if it contains `mark` and 2 otherwise.
+fn encode(grid: &Grid, mark: &Mark) -> u32 {
+    (0..9).rev().fold(0, |acc, i| {
+        let digit = match grid.get_cell(i / 3, i % 3).try_get_mark() {
+            None => 0,
+            Some(m) if m == mark => 1,
+            Some(_) => 2,
+        };
+        acc * 3 + digit
+    })
+}
+
+fn to_action((row, col): (usize, usize)) -> u8 {
+    (row * 3 + col) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn trained_player() -> QLearningPlayer {
+        let mut player = QLearningPlayer::default();
+        player.train_with_rng(5_000, &mut StdRng::seed_from_u64(0));
+        player
+    }
+
+    #[test]
+    fn encode_is_relative_to_mark() {
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::O);
+
+        assert_eq!(encode(&grid, &Mark::X), 1 + 2 * 3);
+        assert_eq!(encode(&grid, &Mark::O), 2 + 3);
+    }
+
+    #[test]
+    fn trained_player_completes_line() {
+        // |X|X|!|
+        // |O|O| |
+        // | | | |
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        grid.set_cell(1, 0, Mark::O);
+        grid.set_cell(1, 1, Mark::O);
+
+        let player = trained_player();
+        assert_eq!(player.get_move(&grid, &Mark::X).unwrap(), Move::Place(0, 2));
+    }
+
+    #[test]
+    fn q_table_roundtrip() {
+        let player = trained_player();
+        let loaded = QLearningPlayer::from_bytes(&player.to_bytes()).unwrap();
+        assert_eq!(loaded, player);
+        assert!(QLearningPlayer::from_bytes(&[0; ENTRY_SIZE + 1]).is_err());
+    }
+}
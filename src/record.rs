@@ -0,0 +1,390 @@
+//! Text notation of complete games, inspired by chess' PGN. See [`GameRecord`].
+//!
+//! # Notation
+//! A record starts with a header of tags, one per line, written as `[Name "value"]`. The `Result`
+//! tag is required, the others describe the game freely, such as `X` and `O` for the players. The
+//! header is followed by a blank line and the moves, each written as the mark playing it and either
+//! a cell (its column letter and row number, like `b2`), `resign` or `draw` for a draw offer.
+//! Moves are numbered every 2 moves, numbers being ignored when parsing:
+//!
+//! ```text
+//! [X "human"]
+//! [O "bot:impossible"]
+//! [Result "O line"]
+//!
+//! 1. X a1 O b2 2. X c3 O b1 3. X a3 O b3
+//! ```
+//!
+//! Results are written as the winner and the reason (`line`, `resignation` or `timeout`), or as
+//! `draw` and the reason (`board-full` or `agreement`).
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::{
+    board::Board,
+    game::{DrawReason, Finished, Game, GameResult, PlayedMove, WinReason},
+    grid::Mark,
+    player::Move,
+};
+
+/// Width the move list is wrapped at.
+const LINE_WIDTH: usize = 80;
+
+/// Complete game with a header of tags, which can be written and read back with its `Display` and
+/// `FromStr` implementations. See the [module documentation](self) for the notation.
+///
+/// ```
+/// # use tictactoe::{game::{GameResult, PlayedMove, WinReason}, grid::Mark, player::Move,
+/// #     record::GameRecord};
+/// let record = GameRecord::new(
+///     vec![PlayedMove { mark: Mark::X, player_move: Move::Resign }],
+///     GameResult::Win { winner: Mark::O, reason: WinReason::Resignation },
+/// )
+/// .with_tag("X", "Alice");
+/// let text = record.to_string();
+/// assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    /// Tags of the header other than `Result`, in the order they are written
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PlayedMove>,
+    pub result: GameResult,
+}
+
+impl GameRecord {
+    pub fn new(moves: Vec<PlayedMove>, result: GameResult) -> Self {
+        Self {
+            tags: vec![],
+            moves,
+            result,
+        }
+    }
+
+    /// Records the moves and result of a finished game.
+    pub fn from_game<B: Board>(game: &Game<Finished, B>) -> Self {
+        Self::new(game.history().to_vec(), game.result())
+    }
+
+    /// Adds a tag to the header, replacing the value of any tag with the same name.
+    pub fn with_tag(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let (name, value) = (name.into(), value.into());
+        match self.tags.iter_mut().find(|(tag, _)| *tag == name) {
+            Some((_, old)) => *old = value,
+            None => self.tags.push((name, value)),
+        }
+        self
+    }
+
+    /// Returns the value of the tag `name`, if the header has it.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses every record of `s`, written one after the other like [`GameRecord::to_string`]
+    /// writes them, with blank lines in between.
+    pub fn parse_all(s: &str) -> Result<Vec<Self>, RecordParseError> {
+        let mut records = vec![];
+        let mut current = String::new();
+        let mut in_moves = false;
+        for line in s.lines() {
+            let header = line.trim_start().starts_with('[');
+            if header && in_moves {
+                records.push(current.parse()?);
+                current.clear();
+                in_moves = false;
+            }
+            in_moves |= !header && !line.trim().is_empty();
+            current.push_str(line);
+            current.push('\n');
+        }
+        if !current.trim().is_empty() {
+            records.push(current.parse()?);
+        }
+        Ok(records)
+    }
+}
+
+/// Writes the header, a blank line and the numbered moves, wrapped to 80 columns.
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(f, "[Result \"{}\"]", format_result(self.result))?;
+        writeln!(f)?;
+
+        let mut line = String::new();
+        for (i, played) in self.moves.iter().enumerate() {
+            let mut token = format!("{} {}", played.mark, format_move(played.player_move));
+            if i % 2 == 0 {
+                token = format!("{}. {}", i / 2 + 1, token);
+            }
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).skip_while(|line| line.is_empty());
+        let mut tags = vec![];
+        let mut result = None;
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) =
+                parse_tag(line).ok_or_else(|| RecordParseError::InvalidTag(line.to_owned()))?;
+            if name == "Result" {
+                result = Some(
+                    parse_result(&value).ok_or(RecordParseError::InvalidResult(value.clone()))?,
+                );
+            } else {
+                tags.push((name, value));
+            }
+        }
+        let result = result.ok_or(RecordParseError::MissingResult)?;
+
+        let mut tokens = lines
+            .flat_map(str::split_whitespace)
+            .filter(|token| !is_move_number(token));
+        let mut moves = vec![];
+        while let Some(mark) = tokens.next() {
+            let player_move = tokens
+                .next()
+                .ok_or_else(|| RecordParseError::InvalidMove(mark.to_owned()))?;
+            let invalid = || RecordParseError::InvalidMove(format!("{} {}", mark, player_move));
+            moves.push(PlayedMove {
+                mark: parse_mark(mark).ok_or_else(invalid)?,
+                player_move: parse_move(player_move).ok_or_else(invalid)?,
+            });
+        }
+
+        Ok(Self {
+            tags,
+            moves,
+            result,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordParseError {
+    /// A header line isn't written as `[Name "value"]`.
+    InvalidTag(String),
+    /// The header has no `Result` tag.
+    MissingResult,
+    InvalidResult(String),
+    InvalidMove(String),
+}
+
+impl Display for RecordParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidTag(line) => write!(f, "Invalid tag: {}", line),
+            Self::MissingResult => write!(f, "Missing Result tag"),
+            Self::InvalidResult(result) => write!(f, "Invalid result: {}", result),
+            Self::InvalidMove(m) => write!(f, "Invalid move: {}", m),
+        }
+    }
+}
+impl Error for RecordParseError {}
+
+fn format_result(result: GameResult) -> String {
+    match result {
+        GameResult::Win { winner, reason } => {
+            let reason = match reason {
+                WinReason::Line => "line",
+                WinReason::Resignation => "resignation",
+                WinReason::Timeout => "timeout",
+            };
+            format!("{} {}", winner, reason)
+        }
+        GameResult::Draw(DrawReason::BoardFull) => "draw board-full".to_owned(),
+        GameResult::Draw(DrawReason::Agreement) => "draw agreement".to_owned(),
+    }
+}
+
+fn parse_result(s: &str) -> Option<GameResult> {
+    let (winner, reason) = s.split_once(' ')?;
+    if winner == "draw" {
+        return match reason {
+            "board-full" => Some(GameResult::Draw(DrawReason::BoardFull)),
+            "agreement" => Some(GameResult::Draw(DrawReason::Agreement)),
+            _ => None,
+        };
+    }
+    let reason = match reason {
+        "line" => WinReason::Line,
+        "resignation" => WinReason::Resignation,
+        "timeout" => WinReason::Timeout,
+        _ => return None,
+    };
+    Some(GameResult::Win {
+        winner: parse_mark(winner)?,
+        reason,
+    })
+}
+
+pub(crate) fn format_move(player_move: Move) -> String {
+    match player_move {
+        Move::Place(row, col) => format!("{}{}", char::from(b'a' + col as u8), row + 1),
+        Move::Resign => "resign".to_owned(),
+        Move::OfferDraw => "draw".to_owned(),
+    }
+}
+
+fn parse_move(s: &str) -> Option<Move> {
+    match s {
+        "resign" => Some(Move::Resign),
+        "draw" => Some(Move::OfferDraw),
+        cell => {
+            let mut chars = cell.chars();
+            let col = chars.next().filter(char::is_ascii_lowercase)? as usize - 'a' as usize;
+            let row = chars.as_str().parse::<usize>().ok()?.checked_sub(1)?;
+            Some(Move::Place(row, col))
+        }
+    }
+}
+
+fn parse_mark(s: &str) -> Option<Mark> {
+    match s {
+        "X" => Some(Mark::X),
+        "O" => Some(Mark::O),
+        "Y" => Some(Mark::Y),
+        _ => None,
+    }
+}
+
+fn is_move_number(token: &str) -> bool {
+    token
+        .strip_suffix('.')
+        .is_some_and(|n| n.parse::<usize>().is_ok())
+}
+
+/// Parses a `[Name "value"]` tag, where quotes and backslashes of the value are escaped with a
+/// backslash.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let line = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = line.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next()?),
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    Some((name.to_owned(), unescaped))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(mark: Mark, row: usize, col: usize) -> PlayedMove {
+        PlayedMove {
+            mark,
+            player_move: Move::Place(row, col),
+        }
+    }
+
+    #[test]
+    fn records_are_written_in_the_notation() {
+        let record = GameRecord::new(
+            vec![
+                place(Mark::X, 0, 0),
+                place(Mark::O, 1, 1),
+                place(Mark::X, 2, 2),
+                PlayedMove {
+                    mark: Mark::O,
+                    player_move: Move::OfferDraw,
+                },
+                place(Mark::O, 0, 1),
+            ],
+            GameResult::Draw(DrawReason::Agreement),
+        )
+        .with_tag("X", "Alice \"Al\"")
+        .with_tag("O", "bot:normal");
+
+        assert_eq!(
+            record.to_string(),
+            "[X \"Alice \\\"Al\\\"\"]\n[O \"bot:normal\"]\n[Result \"draw agreement\"]\n\n\
+             1. X a1 O b2 2. X c3 O draw 3. O b1\n"
+        );
+        assert_eq!(record.to_string().parse::<GameRecord>().unwrap(), record);
+        assert_eq!(record.tag("X"), Some("Alice \"Al\""));
+    }
+
+    #[test]
+    fn long_games_are_wrapped_and_read_back() {
+        let moves: Vec<_> = (0..25)
+            .map(|i| place(if i % 2 == 0 { Mark::X } else { Mark::O }, i / 5, i % 5))
+            .collect();
+        let record = GameRecord::new(
+            moves,
+            GameResult::Win {
+                winner: Mark::X,
+                reason: WinReason::Line,
+            },
+        );
+        let text = record.to_string();
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH));
+        assert!(text.lines().count() > 3);
+        assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+
+        let both = format!("{}\n{}", text, record.clone().with_tag("Round", "2"));
+        let records = GameRecord::parse_all(&both).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].tag("Round"), Some("2"));
+    }
+
+    #[test]
+    fn invalid_records_are_rejected() {
+        let parse = |s: &str| s.parse::<GameRecord>();
+        assert_eq!(
+            parse("[X \"Alice\"]\n\n1. X a1"),
+            Err(RecordParseError::MissingResult)
+        );
+        assert_eq!(
+            parse("[Result \"X won\"]\n"),
+            Err(RecordParseError::InvalidResult("X won".to_owned()))
+        );
+        assert!(matches!(
+            parse("X \"Alice\"\n"),
+            Err(RecordParseError::InvalidTag(_))
+        ));
+        assert!(matches!(
+            parse("[Result \"X line\"]\n\n1. X a1 O"),
+            Err(RecordParseError::InvalidMove(_))
+        ));
+        assert!(matches!(
+            parse("[Result \"X line\"]\n\n1. X a0"),
+            Err(RecordParseError::InvalidMove(_))
+        ));
+    }
+}
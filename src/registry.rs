@@ -0,0 +1,161 @@
+use std::{collections::BTreeMap, error::Error, fmt::Display};
+
+use crate::{
+    board::Board,
+    grid::Grid,
+    player::{BotPlayer, Player},
+};
+
+#[cfg(feature = "cli")]
+use crate::player::LocalPlayer;
+
+type PlayerConstructor<B> = Box<dyn Fn() -> Box<dyn Player<B>> + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    UnknownPlayer(String),
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownPlayer(id) => write!(f, "No player is registered as {:?}", id),
+        }
+    }
+}
+impl Error for RegistryError {}
+
+/// Creates players from string identifiers, so they can be chosen from configuration data.
+///
+/// [`PlayerRegistry::default`] knows the built-in players:
+/// - `human`: a `LocalPlayer` using stdin and stdout, with the `cli` feature
+/// - `bot:easy`, `bot:normal` and `bot:impossible`: a [`BotPlayer`] of that difficulty
+/// - `bot:minimax`: same as `bot:impossible`, always playing the minimax move
+pub struct PlayerRegistry<B: Board = Grid> {
+    constructors: BTreeMap<String, PlayerConstructor<B>>,
+}
+
+#[cfg(feature = "cli")]
+impl<B: Board> Default for PlayerRegistry<B>
+where
+    LocalPlayer: Player<B>,
+    BotPlayer: Player<B>,
+{
+    fn default() -> Self {
+        Self::with_bots().with("human", || Box::new(LocalPlayer::default()))
+    }
+}
+
+#[cfg(not(feature = "cli"))]
+impl<B: Board> Default for PlayerRegistry<B>
+where
+    BotPlayer: Player<B>,
+{
+    fn default() -> Self {
+        Self::with_bots()
+    }
+}
+
+impl<B: Board> PlayerRegistry<B>
+where
+    BotPlayer: Player<B>,
+{
+    fn with_bots() -> Self {
+        Self::new()
+            .with("bot:easy", || Box::new(BotPlayer::easy()))
+            .with("bot:normal", || Box::new(BotPlayer::normal()))
+            .with("bot:impossible", || Box::new(BotPlayer::impossible()))
+            .with("bot:minimax", || Box::new(BotPlayer::impossible()))
+    }
+}
+
+impl<B: Board> std::fmt::Debug for PlayerRegistry<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlayerRegistry")
+            .field("ids", &self.constructors.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<B: Board> PlayerRegistry<B> {
+    /// Creates a registry without any player.
+    pub fn new() -> Self {
+        Self {
+            constructors: BTreeMap::new(),
+        }
+    }
+
+    /// Registers `constructor` as `id`, replacing any constructor previously registered with it.
+    pub fn register(
+        &mut self,
+        id: impl Into<String>,
+        constructor: impl Fn() -> Box<dyn Player<B>> + Send + Sync + 'static,
+    ) {
+        self.constructors.insert(id.into(), Box::new(constructor));
+    }
+
+    /// Same as [`PlayerRegistry::register`], returning the registry.
+    pub fn with(
+        mut self,
+        id: impl Into<String>,
+        constructor: impl Fn() -> Box<dyn Player<B>> + Send + Sync + 'static,
+    ) -> Self {
+        self.register(id, constructor);
+        self
+    }
+
+    /// Creates a new player registered as `id`.
+    pub fn create(&self, id: &str) -> Result<Box<dyn Player<B>>, RegistryError> {
+        self.constructors
+            .get(id)
+            .map(|constructor| constructor())
+            .ok_or_else(|| RegistryError::UnknownPlayer(id.to_owned()))
+    }
+
+    /// Returns every registered identifier, in alphabetical order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.constructors.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        grid::Mark,
+        player::{tests::MockPlayer, Move},
+    };
+
+    use super::*;
+
+    #[test]
+    fn default_registry_creates_builtin_players() {
+        let registry = PlayerRegistry::<Grid>::default();
+        let mut ids = vec!["bot:easy", "bot:impossible", "bot:minimax", "bot:normal"];
+        if cfg!(feature = "cli") {
+            ids.push("human");
+        }
+        assert_eq!(registry.ids().collect::<Vec<_>>(), ids);
+
+        let bot = registry.create("bot:minimax").unwrap();
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(0, 1, Mark::X);
+        assert_eq!(bot.get_move(&grid, &Mark::X).unwrap(), Move::Place(0, 2));
+    }
+
+    #[test]
+    fn registry_creates_registered_players() {
+        let mut registry = PlayerRegistry::<Grid>::new();
+        assert_eq!(
+            registry.create("mock").unwrap_err(),
+            RegistryError::UnknownPlayer("mock".to_owned())
+        );
+
+        registry.register("mock", || Box::new(MockPlayer(2, 1)));
+        let player = registry.create("mock").unwrap();
+        assert_eq!(
+            player.get_move(&Grid::default(), &Mark::O).unwrap(),
+            Move::Place(2, 1)
+        );
+    }
+}
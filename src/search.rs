@@ -0,0 +1,300 @@
+//! Game tree searches working on any [`Board`], so bots play every variant without a strategy
+//! written for it. They only rely on the board's legal moves and winner: a board where aligning
+//! marks loses reports the other mark as the winner, and a board where marks fall to the bottom of
+//! their column only returns the reachable cells as legal moves.
+//!
+//! [`solve`] and [`best_moves`] search two-player positions to the end of the game, which is only
+//! practical with a dozen or so empty cells. [`mcts_move`] estimates the best move of larger
+//! positions with random playouts, with any number of players.
+
+use rand::{seq::SliceRandom, RngCore};
+
+use crate::{board::Board, grid::Mark};
+
+/// Scores `board` for `mark`, about to play against `opponent`: positive if `mark` can force a win,
+/// negative if `opponent` can, and 0 if perfect play leads to a draw. Faster wins and slower losses
+/// score further from 0. A finished game is scored as it ended.
+pub fn solve<B: Board>(board: &B, mark: Mark, opponent: Mark) -> i32 {
+    let win = win_score(board);
+    negamax(board, mark, opponent, 0, -win, win)
+}
+
+/// Returns every legal move of `mark` with the best score, in the order of [`Board::legal_moves`].
+/// Returns no moves if the game is over.
+pub fn best_moves<B: Board>(board: &B, mark: Mark, opponent: Mark) -> Vec<(usize, usize)> {
+    let scored = scored_moves(board, mark, opponent);
+    let best = scored.iter().map(|&(_, score)| score).max();
+    scored
+        .into_iter()
+        .filter(|&(_, score)| Some(score) == best)
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+/// Returns every legal move of `mark` with its score for `mark`, scored like [`solve`] does, in the
+/// order of [`Board::legal_moves`]. Returns no moves if the game is over.
+pub fn scored_moves<B: Board>(board: &B, mark: Mark, opponent: Mark) -> Vec<((usize, usize), i32)> {
+    if is_over(board) {
+        return vec![];
+    }
+    let win = win_score(board);
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|(row, col)| {
+            let next = after_move(board, (row, col), mark);
+            ((row, col), -negamax(&next, opponent, mark, 1, -win, win))
+        })
+        .collect()
+}
+
+/// Same as the first of [`best_moves`], pruning more of the search since the other best moves
+/// aren't needed. Returns `None` if the game is over.
+pub fn best_move<B: Board>(board: &B, mark: Mark, opponent: Mark) -> Option<(usize, usize)> {
+    if is_over(board) {
+        return None;
+    }
+    let win = win_score(board);
+    let mut best = None;
+    let mut alpha = -win;
+    for (row, col) in board.legal_moves() {
+        let next = after_move(board, (row, col), mark);
+        let score = -negamax(&next, opponent, mark, 1, -win, -alpha);
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some((row, col));
+        }
+    }
+    best
+}
+
+/// Score of a game won right away, higher than any game lasting longer.
+fn win_score(board: &impl Board) -> i32 {
+    let (rows, cols) = board.dimensions();
+    (rows * cols) as i32 + 1
+}
+
+/// Scores `board` from the point of view of `mark`, who is about to play, using an alpha-beta
+/// pruned negamax search. `depth` is the number of moves played since the search started.
+fn negamax<B: Board>(
+    board: &B,
+    mark: Mark,
+    opponent: Mark,
+    depth: i32,
+    mut alpha: i32,
+    beta: i32,
+) -> i32 {
+    let win = win_score(board);
+    match board.winner() {
+        Some(winner) if winner == mark => return win - depth,
+        Some(_) => return depth - win,
+        None if board.is_full() => return 0,
+        None => {}
+    }
+
+    let moves = board.legal_moves();
+    // Boards without legal moves left but not full are drawn
+    if moves.is_empty() {
+        return 0;
+    }
+
+    let mut best = -win;
+    for cell in moves {
+        let next = after_move(board, cell, mark);
+        let score = -negamax(&next, opponent, mark, depth + 1, -beta, -alpha);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+fn after_move<B: Board>(board: &B, (row, col): (usize, usize), mark: Mark) -> B {
+    let mut next = board.clone();
+    next.place(row, col, mark)
+        .expect("Legal moves can be placed");
+    next
+}
+
+/// Returns whether the game on `board` is over, won or without any move left.
+fn is_over(board: &impl Board) -> bool {
+    board.winner().is_some() || board.is_full() || board.legal_moves().is_empty()
+}
+
+/// How much the tree search favors moves it tried less over the best ones so far.
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// Position of the tree searched by [`mcts_move`].
+struct Node {
+    /// Move reaching the position, and the index in the turn order of the mark who played it
+    played: Option<((usize, usize), usize)>,
+    children: Vec<usize>,
+    /// Moves from the position without a child yet
+    untried: Vec<(usize, usize)>,
+    visits: f64,
+    /// Sum of the playout results for the mark who played the move: 1 for a win, half for a draw
+    reward: f64,
+}
+
+impl Node {
+    fn new(played: Option<((usize, usize), usize)>, board: &impl Board) -> Self {
+        let untried = if is_over(board) {
+            vec![]
+        } else {
+            board.legal_moves()
+        };
+        Self {
+            played,
+            children: vec![],
+            untried,
+            visits: 0.0,
+            reward: 0.0,
+        }
+    }
+}
+
+/// Estimates the best move with a Monte Carlo tree search over `iterations` random playouts.
+/// `turns` are the marks in the order they play, cycling, starting with the mark about to move.
+/// Returns `None` if the game is over or `turns` is empty.
+pub fn mcts_move<B: Board>(
+    board: &B,
+    turns: &[Mark],
+    iterations: usize,
+    rng: &mut dyn RngCore,
+) -> Option<(usize, usize)> {
+    if turns.is_empty() || is_over(board) {
+        return None;
+    }
+
+    let mut nodes = vec![Node::new(None, board)];
+    for _ in 0..iterations.max(1) {
+        let mut board = board.clone();
+        let mut path = vec![0];
+        let mut turn = 0;
+
+        // Follow the most promising moves down to a position with untried moves
+        let mut node = 0;
+        while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+            let parent_visits = nodes[node].visits;
+            node = *nodes[node]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    let a = upper_bound(&nodes[a], parent_visits);
+                    let b = upper_bound(&nodes[b], parent_visits);
+                    a.total_cmp(&b)
+                })
+                .unwrap();
+            let (cell, _) = nodes[node].played.unwrap();
+            board = after_move(&board, cell, turns[turn]);
+            turn = (turn + 1) % turns.len();
+            path.push(node);
+        }
+
+        // Try one of its moves
+        if !nodes[node].untried.is_empty() {
+            let untried = &mut nodes[node].untried;
+            let cell = untried.swap_remove(rng.next_u32() as usize % untried.len());
+            board = after_move(&board, cell, turns[turn]);
+            let child = nodes.len();
+            nodes.push(Node::new(Some((cell, turn)), &board));
+            nodes[node].children.push(child);
+            turn = (turn + 1) % turns.len();
+            path.push(child);
+        }
+
+        // Play randomly to the end, and credit the marks who made the moves on the way
+        while !is_over(&board) {
+            let &cell = board.legal_moves().choose(rng).unwrap();
+            board = after_move(&board, cell, turns[turn]);
+            turn = (turn + 1) % turns.len();
+        }
+        let winner = board.winner();
+        for &node in &path {
+            let node = &mut nodes[node];
+            node.visits += 1.0;
+            if let Some((_, mover)) = node.played {
+                node.reward += match winner {
+                    Some(winner) if winner == turns[mover] => 1.0,
+                    Some(_) => 0.0,
+                    None => 0.5,
+                };
+            }
+        }
+    }
+
+    // The most visited move is the one the search trusts the most
+    nodes[0]
+        .children
+        .iter()
+        .max_by(|&&a, &&b| nodes[a].visits.total_cmp(&nodes[b].visits))
+        .and_then(|&child| nodes[child].played)
+        .map(|(cell, _)| cell)
+}
+
+/// Upper confidence bound of the results of the move reaching `node`, whose parent was visited
+/// `parent_visits` times.
+fn upper_bound(node: &Node, parent_visits: f64) -> f64 {
+    node.reward / node.visits + EXPLORATION * (parent_visits.ln() / node.visits).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{board::SquareBoard, grid::Grid};
+
+    use super::*;
+
+    #[test]
+    fn solves_any_board_size() {
+        // The classic game is a draw
+        let grid = Grid::default();
+        assert_eq!(solve(&grid, Mark::X, Mark::O), 0);
+        assert_eq!(solve(&SquareBoard::new(3, 3), Mark::X, Mark::O), 0);
+
+        // On a 4x4 board with 3 in a row to win, X wins from a corner and the center
+        let mut board = SquareBoard::new(4, 3);
+        for (row, col, mark) in [(1, 1, Mark::X), (0, 0, Mark::O), (2, 2, Mark::X)] {
+            board.place(row, col, mark).unwrap();
+        }
+        board.place(3, 3, Mark::O).unwrap();
+        assert!(solve(&board, Mark::X, Mark::O) > 0);
+        let best = best_move(&board, Mark::X, Mark::O).unwrap();
+        assert!(best_moves(&board, Mark::X, Mark::O).contains(&best));
+
+        let mut won = SquareBoard::new(3, 3);
+        for col in 0..3 {
+            won.place(0, col, Mark::O).unwrap();
+        }
+        assert!(solve(&won, Mark::X, Mark::O) < 0);
+        assert_eq!(best_move(&won, Mark::X, Mark::O), None);
+    }
+
+    #[test]
+    fn mcts_finds_wins_and_blocks() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // X completes the top row of a 5x5 board, rather than blocking O
+        let mut board = SquareBoard::new(5, 4);
+        for col in 0..3 {
+            board.place(0, col, Mark::X).unwrap();
+            board.place(4, col, Mark::O).unwrap();
+        }
+        let turns = [Mark::X, Mark::O];
+        let cell = mcts_move(&board, &turns, 500, &mut rng).unwrap();
+        assert!(cell == (0, 3), "{:?}", cell);
+
+        // Y blocks X when it would otherwise win next
+        board.place(0, 3, Mark::O).unwrap();
+        board.place(4, 3, Mark::Y).unwrap();
+        board.place(4, 4, Mark::O).unwrap();
+        for row in 1..4 {
+            board.place(row, 4, Mark::X).unwrap();
+        }
+        let turns = [Mark::Y, Mark::X, Mark::O];
+        assert_eq!(mcts_move(&board, &turns, 2000, &mut rng), Some((0, 4)));
+        assert_eq!(mcts_move(&board, &[], 500, &mut rng), None);
+    }
+}
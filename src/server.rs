@@ -0,0 +1,1940 @@
+//! Dedicated server hosting games between remote clients, see [`DedicatedServer`].
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    mem,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    game::{self, GameResult, WinReason},
+    grid::{Grid, Mark},
+    protocol::{
+        self, BoardSync, ChatMessage, EndOfGame, Heartbeat, InvalidMove, LobbyPacket, Packet,
+        PlayerMove, Rematch, Resign, ServerHello, ServerShutdown,
+    },
+};
+
+pub use self::accounts::MAX_ACCOUNT_NAME_LEN;
+use self::{accounts::Accounts, leaderboard::Leaderboard, saved_games::SavedGame};
+
+mod accounts;
+mod leaderboard;
+mod saved_games;
+
+/// Client connection, buffered for reading. Packets are written directly to the inner stream.
+type Client = BufReader<Connection>;
+
+/// Open client sockets by address, for the admin console to reach them.
+type Connections = Mutex<BTreeMap<SocketAddr, Arc<ClientSocket>>>;
+
+/// Socket of a client, shared between its connection and the admin console.
+#[derive(Debug)]
+struct ClientSocket {
+    socket: TcpStream,
+    /// Held while writing a packet, so notices from the admin console aren't interleaved with the
+    /// game's packets
+    write_lock: Mutex<()>,
+}
+
+/// Client socket, counted in the server's open connections and registered in its [`Connections`]
+/// until it is dropped.
+#[derive(Debug)]
+struct Connection {
+    client: Arc<ClientSocket>,
+    peer: SocketAddr,
+    /// Account the client logged in to, if any
+    account: Option<String>,
+    metrics: Arc<Metrics>,
+    connections: Arc<Connections>,
+}
+
+impl Connection {
+    fn new(
+        socket: TcpStream,
+        metrics: Arc<Metrics>,
+        connections: Arc<Connections>,
+    ) -> io::Result<Self> {
+        let peer = socket.peer_addr()?;
+        let client = Arc::new(ClientSocket {
+            socket,
+            write_lock: Mutex::default(),
+        });
+        connections
+            .lock()
+            .unwrap()
+            .insert(peer, Arc::clone(&client));
+        metrics.connections.fetch_add(1, Ordering::Relaxed);
+        Ok(Self {
+            client,
+            peer,
+            account: None,
+            metrics,
+            connections,
+        })
+    }
+}
+
+impl Deref for Connection {
+    type Target = TcpStream;
+
+    fn deref(&self) -> &TcpStream {
+        &self.client.socket
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.client.socket).read(buf)
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let _guard = self.client.write_lock.lock().unwrap();
+        (&self.client.socket).write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let _guard = self.client.write_lock.lock().unwrap();
+        (&self.client.socket).write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.client.socket).flush()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.connections.lock().unwrap().remove(&self.peer);
+        self.metrics.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Counters exported by the status endpoint in Prometheus' format, see [`StatusServer`].
+#[derive(Debug, Default)]
+struct Metrics {
+    /// Games started, including rematches
+    games_started: AtomicU64,
+    /// Games played until their end, excluding the ones aborted by a client leaving
+    games_finished: AtomicU64,
+    moves: AtomicU64,
+    /// Clients whose first packet wasn't valid
+    handshake_failures: AtomicU64,
+    /// Open client connections
+    connections: AtomicU64,
+}
+
+/// Server hosting games between its clients, and relaying moves between them.
+///
+/// Clients connecting the same way they would to a [`ServerGame`](crate::game::ServerGame) are
+/// paired in the order they connect. Clients can instead enter the server's lobby with a
+/// [`LobbyClient`](crate::lobby::LobbyClient), to open a game or choose which one to join, or join
+/// a room with [`lobby::join_room`](crate::lobby::join_room) to play against the client joining
+/// with the same code. Since both clients connect out to the server, it can relay games between
+/// players who can't host one, such as players behind NAT.
+///
+/// Neither client plays as the host: the first client of each pair, or the one who opened the
+/// game, plays `X` and moves first. Every move is checked against the server's own copy of the
+/// grid: illegal moves are rejected with an INVALID_MOVE packet, and the client is asked for
+/// another move. Resignations are forwarded like moves, and the server sends the END_OF_GAME
+/// packet once the last mark is placed. Both clients can then agree to a rematch, played with the
+/// marks and first player swapped. Chat messages, heartbeats, board synchronizations and move
+/// acknowledgments are forwarded to the other client.
+///
+/// Each client is sent a token with its SERVER_HELLO packet. If its connection drops, or it stops
+/// sending heartbeats while choosing its move, the client has [`game::RECONNECT_GRACE_PERIOD`] to
+/// reconnect with a RECONNECT packet carrying that token, like it would to a
+/// [`ServerGame`](crate::game::ServerGame): it is then sent the game's state, and the game goes on
+/// over the new connection. A game is aborted if the client doesn't reconnect in time, or if both
+/// clients leave.
+///
+/// The server can be shut down without dropping its games, see [`ServerAdmin::shutdown`]: games
+/// still going are saved, and their players reconnect to them the same way once the server starts
+/// again, see [`DedicatedServer::with_state_file`].
+///
+/// Clients can create a named account and log in to it before anything else, see
+/// [`lobby::create_account`](crate::lobby::create_account). Their games are then tied to the
+/// account rather than to their connection, and the lobby shows its name. Accounts are optional,
+/// and only last until the server stops unless it saves them, see
+/// [`DedicatedServer::with_accounts_file`]. The results of their games make up the server's
+/// leaderboard, which lobby clients can request (see
+/// [`LobbyClient::leaderboard`](crate::lobby::LobbyClient::leaderboard)), and which is kept until
+/// the server stops.
+///
+/// Operators can monitor the server over HTTP, see [`DedicatedServer::status_server`], and manage
+/// it while it runs, see [`DedicatedServer::admin`].
+#[derive(Debug)]
+pub struct DedicatedServer {
+    listener: TcpListener,
+    lobby: Arc<Mutex<Lobby>>,
+    metrics: Arc<Metrics>,
+    connections: Arc<Connections>,
+    started: Instant,
+    max_connections: u64,
+}
+
+#[derive(Debug, Default)]
+struct Lobby {
+    /// Client waiting to be paired with the next client
+    waiting: Option<Client>,
+    /// Games opened from the lobby by id, with the name of the player who opened them
+    open_games: BTreeMap<u32, (String, Client)>,
+    next_id: u32,
+    /// Clients waiting in a room by code, for the other client to join it
+    rooms: BTreeMap<String, Client>,
+    /// Games being relayed by id
+    games: BTreeMap<u64, ActiveGame>,
+    next_game_id: u64,
+    /// Whether the server stopped starting games, see [`ServerAdmin::drain`]
+    draining: bool,
+    accounts: Accounts,
+    leaderboard: Leaderboard,
+    /// File the unfinished games are saved to when the server shuts down
+    state_file: Option<PathBuf>,
+    /// Games loaded from the state file by id, relayed once their players reconnect
+    resumed: Vec<(u64, SavedGame)>,
+    /// Games loaded from the state file by id that didn't end yet, which the file keeps until they
+    /// do so they aren't lost if the server crashes
+    saved: BTreeMap<u64, SavedGame>,
+}
+
+impl Lobby {
+    /// Returns the number of clients waiting for an opponent, whether paired in connection order,
+    /// in an open game or in a room.
+    fn waiting_count(&self) -> usize {
+        usize::from(self.waiting.is_some()) + self.open_games.len() + self.rooms.len()
+    }
+
+    /// Removes the clients waiting for an opponent for which `keep` returns `false`, closing their
+    /// connection.
+    fn retain_waiting(&mut self, mut keep: impl FnMut(&Client) -> bool) {
+        if self.waiting.as_ref().is_some_and(|client| !keep(client)) {
+            self.waiting = None;
+        }
+        self.open_games.retain(|_, (_, client)| keep(client));
+        self.rooms.retain(|_, client| keep(client));
+    }
+
+    /// Registers a game being relayed, and returns its id.
+    fn add_game(&mut self, game: ActiveGame) -> u64 {
+        let id = self.next_game_id;
+        self.next_game_id += 1;
+        self.games.insert(id, game);
+        id
+    }
+
+    /// Unregisters the game `id` once it ended, and removes it from the state file if it was loaded
+    /// from there.
+    fn remove_game(&mut self, id: u64) {
+        self.games.remove(&id);
+        if self.saved.remove(&id).is_none() {
+            return;
+        }
+        if let Some(path) = &self.state_file {
+            let saved: Vec<_> = self.saved.values().cloned().collect();
+            // Failing only leaves the game in the file, and the next server drops it once its
+            // players don't reconnect
+            let _ = saved_games::save(path, &saved);
+        }
+    }
+}
+
+/// Game relayed by the server, as reported by its status endpoint.
+#[derive(Debug)]
+struct ActiveGame {
+    players: [SocketAddr; 2],
+    /// Accounts the players logged in to, in the same order
+    accounts: [Option<String>; 2],
+    /// Tokens the players reconnect with, see [`reconnect`]. A kicked player's token is removed,
+    /// so it can't reconnect.
+    tokens: [Option<u64>; 2],
+    /// New connections of the players who reconnected, until the game picks them up
+    reconnected: [Option<Client>; 2],
+    started: Instant,
+    /// Moves played in the current game, reset on rematches
+    moves: usize,
+    /// Grid of the current game, until it ends
+    grid: Option<Grid>,
+}
+
+impl ActiveGame {
+    /// Game between the clients at `players`, playing from `grid`.
+    fn new(
+        players: [SocketAddr; 2],
+        accounts: [Option<String>; 2],
+        tokens: [u64; 2],
+        grid: Grid,
+    ) -> Self {
+        Self {
+            players,
+            accounts,
+            tokens: tokens.map(Some),
+            reconnected: [None, None],
+            started: Instant::now(),
+            moves: grid.cell_count(),
+            grid: Some(grid),
+        }
+    }
+}
+
+/// Entry of a relayed game in the lobby, removed once the game ends for any reason.
+struct GameEntry<'a> {
+    lobby: &'a Mutex<Lobby>,
+    metrics: &'a Metrics,
+    id: u64,
+}
+
+impl<'a> GameEntry<'a> {
+    fn register(lobby: &'a Mutex<Lobby>, metrics: &'a Metrics, game: ActiveGame) -> Self {
+        let id = lobby.lock().unwrap().add_game(game);
+        Self { lobby, metrics, id }
+    }
+
+    /// Records the move that resulted in `grid`.
+    fn record_move(&self, grid: &Grid) {
+        self.metrics.moves.fetch_add(1, Ordering::Relaxed);
+        self.update(|game| {
+            game.moves += 1;
+            game.grid = Some(*grid);
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ActiveGame)) {
+        if let Some(game) = self.lobby.lock().unwrap().games.get_mut(&self.id) {
+            f(game);
+        }
+    }
+}
+
+impl Drop for GameEntry<'_> {
+    fn drop(&mut self) {
+        self.lobby.lock().unwrap().remove_game(self.id);
+    }
+}
+
+/// Longest room code accepted by the server, in bytes.
+pub const MAX_ROOM_CODE_LEN: usize = 32;
+
+/// Most client connections a server keeps open by default, see
+/// [`DedicatedServer::with_max_connections`].
+pub const MAX_CONNECTIONS: u64 = 1024;
+
+/// Longest packet the server reads from a client, in bytes, terminator included. Client packets
+/// are far shorter, so this only stops a client from making the server buffer an endless packet.
+const MAX_PACKET_LEN: u64 = 4096;
+
+impl DedicatedServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            lobby: Arc::default(),
+            metrics: Arc::default(),
+            connections: Arc::default(),
+            started: Instant::now(),
+            max_connections: MAX_CONNECTIONS,
+        })
+    }
+
+    /// Keeps at most `max` client connections open, [`MAX_CONNECTIONS`] by default. Connections
+    /// beyond it are closed right away, until other clients leave.
+    pub fn with_max_connections(mut self, max: u64) -> Self {
+        self.max_connections = max;
+        self
+    }
+
+    /// Keeps the server's accounts in the file at `path`, loading the ones saved there, so they
+    /// outlive the server. Each account is a `<name> <token hash>` line, with the SHA-256 hash of
+    /// its token: the file is created readable by the server only.
+    pub fn with_accounts_file(self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let accounts = Accounts::load(path.as_ref())?;
+        self.lobby.lock().unwrap().accounts = accounts;
+        Ok(self)
+    }
+
+    /// Saves the games still going when the server shuts down to the file at `path` (see
+    /// [`ServerAdmin::shutdown`]), and resumes the games saved there. Their players have
+    /// [`game::RECONNECT_GRACE_PERIOD`] after the server starts running to reconnect to them, and
+    /// the games are relayed once both players are back.
+    ///
+    /// A resumed game stays in the file until it ends, or is dropped because its players didn't
+    /// reconnect: if the server crashes meanwhile, the next one resumes it from where it was saved.
+    pub fn with_state_file(self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let saved = saved_games::load(path)?;
+        {
+            let mut lobby = self.lobby.lock().unwrap();
+            for game in saved {
+                // The players' addresses are only known once they reconnect
+                let players = [SocketAddr::from(([0, 0, 0, 0], 0)); 2];
+                let active =
+                    ActiveGame::new(players, game.accounts.clone(), game.tokens, game.grid);
+                let id = lobby.add_game(active);
+                lobby.saved.insert(id, game.clone());
+                lobby.resumed.push((id, game));
+            }
+            lobby.state_file = Some(path.to_owned());
+        }
+        Ok(self)
+    }
+
+    /// Returns a handle to manage the server while it runs, which can be used from any thread.
+    pub fn admin(&self) -> ServerAdmin {
+        ServerAdmin {
+            lobby: Arc::clone(&self.lobby),
+            connections: Arc::clone(&self.connections),
+        }
+    }
+
+    /// Binds an HTTP endpoint reporting the server's state, served once
+    /// [`StatusServer::run`] is called.
+    pub fn status_server<A: ToSocketAddrs>(&self, addr: A) -> io::Result<StatusServer> {
+        Ok(StatusServer {
+            listener: TcpListener::bind(addr)?,
+            lobby: Arc::clone(&self.lobby),
+            metrics: Arc::clone(&self.metrics),
+            started: self.started,
+        })
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts clients until accepting a connection fails. Each client is handled by its own
+    /// thread, which then plays the game once an opponent is found. Clients have
+    /// [`game::HANDSHAKE_TIMEOUT`] to send each packet until their first one that isn't about
+    /// their account.
+    pub fn run(&self) -> io::Result<()> {
+        let resumed = mem::take(&mut self.lobby.lock().unwrap().resumed);
+        for (id, saved) in resumed {
+            let lobby = Arc::clone(&self.lobby);
+            let metrics = Arc::clone(&self.metrics);
+            thread::spawn(move || {
+                let entry = GameEntry {
+                    lobby: &lobby,
+                    metrics: &metrics,
+                    id,
+                };
+                // Errors only affect this game, which is dropped
+                let _ = resume_game(saved, entry);
+            });
+        }
+        loop {
+            let (socket, _) = self.listener.accept()?;
+            // Dropping the socket closes the connection
+            if self.metrics.connections.load(Ordering::Relaxed) >= self.max_connections {
+                continue;
+            }
+            // Counted before its thread starts, so a burst of connections can't exceed the cap
+            let connections = Arc::clone(&self.connections);
+            let Ok(socket) = Connection::new(socket, Arc::clone(&self.metrics), connections) else {
+                continue;
+            };
+            let lobby = Arc::clone(&self.lobby);
+            let metrics = Arc::clone(&self.metrics);
+            thread::spawn(move || {
+                // Errors only affect this client's game, which is dropped
+                let _ = handle_client(socket, &lobby, &metrics);
+            });
+        }
+    }
+}
+
+/// Waits for the client's first packet: CLIENT_HELLO or an introduction to be paired with the next
+/// client, a lobby registration, a room to join, or RECONNECT to get back into its game.
+/// Introductions are answered like CLIENT_HELLO, since the server doesn't introduce itself. The
+/// client may log in to an account or create one first.
+fn handle_client(socket: Connection, lobby: &Mutex<Lobby>, metrics: &Metrics) -> io::Result<()> {
+    socket.set_read_timeout(Some(game::HANDSHAKE_TIMEOUT))?;
+    let mut client = BufReader::new(socket);
+    let hello = receive_after_login(&mut client, lobby).and_then(|pkt| match pkt {
+        Packet::Lobby(LobbyPacket::Register(_) | LobbyPacket::JoinRoom(_))
+        | Packet::ClientHello
+        | Packet::Introduction(_)
+        | Packet::Reconnect(_) => Ok(pkt),
+        // Resuming is not supported: players of saved games reconnect to them instead
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Expected a CLIENT_HELLO or lobby packet",
+        )),
+    });
+    match hello {
+        // Names are listed one per line
+        Ok(Packet::Lobby(LobbyPacket::Register(name))) => {
+            let account = client.get_ref().account.clone();
+            if account.is_none() && lobby.lock().unwrap().accounts.contains(&name) {
+                let refusal = format!("{} is an account, log in to it to use its name", name);
+                return send(&mut client, &LobbyPacket::Error(refusal).to_bytes());
+            }
+            // Clients logged in to an account are listed under its name
+            let name = account.unwrap_or(name);
+            return lobby_session(client, name.replace('\n', " "), lobby, metrics);
+        }
+        Ok(Packet::Lobby(LobbyPacket::JoinRoom(code))) => {
+            return join_room(client, code, lobby, metrics)
+        }
+        Ok(Packet::Reconnect(pkt)) => {
+            let reconnected = reconnect(client, pkt.token, lobby);
+            if reconnected.is_err() {
+                metrics.handshake_failures.fetch_add(1, Ordering::Relaxed);
+            }
+            return reconnected;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            metrics.handshake_failures.fetch_add(1, Ordering::Relaxed);
+            return Err(e);
+        }
+    }
+    // Clients then wait for an opponent, which takes as long as it takes
+    client.get_ref().set_read_timeout(None)?;
+
+    let opponent = {
+        let mut lobby = lobby.lock().unwrap();
+        if lobby.draining {
+            return Ok(());
+        }
+        match lobby.waiting.take() {
+            Some(opponent) if !is_closed(&opponent) => opponent,
+            // A client who left while waiting is replaced
+            _ => {
+                lobby.waiting = Some(client);
+                return Ok(());
+            }
+        }
+    };
+
+    relay_game(opponent, client, lobby, metrics).map(|_| ())
+}
+
+/// Hands the connection of a client reconnecting with `token` over to its game, and closes the
+/// connection it replaces so the game notices it right away. The game then sends the client its
+/// state, see [`await_reconnection`].
+fn reconnect(client: Client, token: u64, lobby: &Mutex<Lobby>) -> io::Result<()> {
+    let mut lobby = lobby.lock().unwrap();
+    let seat = lobby.games.values_mut().find_map(|game| {
+        let seat = game.tokens.iter().position(|t| *t == Some(token))?;
+        Some((game, seat))
+    });
+    let Some((game, seat)) = seat else {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Unknown reconnection token",
+        ));
+    };
+
+    let replaced = client
+        .get_ref()
+        .connections
+        .lock()
+        .unwrap()
+        .get(&game.players[seat])
+        .cloned();
+    if let Some(replaced) = replaced {
+        let _ = replaced.socket.shutdown(Shutdown::Both);
+    }
+    game.reconnected[seat] = Some(client);
+    Ok(())
+}
+
+/// Answers the account packets the client starts with, logging it in, and returns its first other
+/// packet. A client failing to log in is told why, and may try again.
+fn receive_after_login(client: &mut Client, lobby: &Mutex<Lobby>) -> io::Result<Packet> {
+    loop {
+        let reply = match receive(client)? {
+            Packet::Lobby(LobbyPacket::CreateAccount(name)) => {
+                let created = lobby.lock().unwrap().accounts.create(&name);
+                match created {
+                    Ok(token) => {
+                        client.get_mut().account = Some(name);
+                        LobbyPacket::AccountCreated(token)
+                    }
+                    Err(reason) => LobbyPacket::Error(reason),
+                }
+            }
+            Packet::Lobby(LobbyPacket::Login { name, token }) => {
+                if lobby.lock().unwrap().accounts.authenticate(&name, &token) {
+                    client.get_mut().account = Some(name);
+                    LobbyPacket::LoggedIn
+                } else {
+                    LobbyPacket::Error("Unknown account name or token".to_owned())
+                }
+            }
+            pkt => return Ok(pkt),
+        };
+        send(client, &reply.to_bytes())?;
+    }
+}
+
+/// Waits in the room `code` for another client, or starts the game with the client already
+/// waiting in it. The client who entered the room first plays `X`.
+fn join_room(
+    mut client: Client,
+    code: String,
+    lobby: &Mutex<Lobby>,
+    metrics: &Metrics,
+) -> io::Result<()> {
+    if code.is_empty() || code.len() > MAX_ROOM_CODE_LEN {
+        let reply = LobbyPacket::Error(format!(
+            "Room codes must be 1 to {} bytes long",
+            MAX_ROOM_CODE_LEN
+        ));
+        return send(&mut client, &reply.to_bytes());
+    }
+
+    let opponent = {
+        let mut lobby = lobby.lock().unwrap();
+        if lobby.draining {
+            drop(lobby);
+            return send(&mut client, &shutting_down().to_bytes());
+        }
+        match lobby.rooms.remove(&code) {
+            Some(opponent) if !is_closed(&opponent) => opponent,
+            _ => {
+                lobby.rooms.insert(code, client);
+                return Ok(());
+            }
+        }
+    };
+    relay_game(opponent, client, lobby, metrics).map(|_| ())
+}
+
+/// Answers a lobby client's requests until it opens or joins a game.
+fn lobby_session(
+    mut client: Client,
+    name: String,
+    lobby: &Mutex<Lobby>,
+    metrics: &Metrics,
+) -> io::Result<()> {
+    loop {
+        let Packet::Lobby(pkt) = receive(&mut client)? else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "Expected a lobby packet",
+            ));
+        };
+
+        let reply = match pkt {
+            LobbyPacket::ListGames => {
+                let lobby = lobby.lock().unwrap();
+                let games = lobby
+                    .open_games
+                    .iter()
+                    .map(|(&id, (host, _))| (id, host.clone()))
+                    .collect();
+                LobbyPacket::GameList(games)
+            }
+            LobbyPacket::GetLeaderboard => {
+                LobbyPacket::Leaderboard(lobby.lock().unwrap().leaderboard.standings())
+            }
+            LobbyPacket::CreateGame if lobby.lock().unwrap().draining => shutting_down(),
+            LobbyPacket::CreateGame => {
+                let mut lobby = lobby.lock().unwrap();
+                let id = lobby.next_id;
+                lobby.next_id += 1;
+                send(&mut client, &LobbyPacket::GameCreated(id).to_bytes())?;
+                lobby.open_games.insert(id, (name, client));
+                return Ok(());
+            }
+            LobbyPacket::JoinGame(id) => {
+                let host = lobby.lock().unwrap().open_games.remove(&id);
+                match host {
+                    Some((_, host)) => return relay_game(host, client, lobby, metrics).map(|_| ()),
+                    None => LobbyPacket::Error(format!("No open game with id {}", id)),
+                }
+            }
+            pkt => LobbyPacket::Error(format!("Unexpected packet: {:?}", pkt)),
+        };
+        send(&mut client, &reply.to_bytes())?;
+    }
+}
+
+/// Plays games between `player_x`, moving first, and `player_o`, until one ends without both
+/// clients accepting a rematch. Marks and the first player are swapped for each rematch.
+fn relay_game(
+    player_x: Client,
+    player_o: Client,
+    lobby: &Mutex<Lobby>,
+    metrics: &Metrics,
+) -> io::Result<GameResult> {
+    let mut players = [player_x, player_o];
+    let peers = [players[0].get_ref().peer, players[1].get_ref().peer];
+    let accounts = players
+        .each_ref()
+        .map(|client| client.get_ref().account.clone());
+    let tokens = [rand::random(), rand::random()];
+    let game = ActiveGame::new(peers, accounts.clone(), tokens, Grid::default());
+    let entry = GameEntry::register(lobby, metrics, game);
+    for (seat, client) in players.iter_mut().enumerate() {
+        client
+            .get_ref()
+            .set_read_timeout(Some(game::HEARTBEAT_TIMEOUT))?;
+        let pkt = ServerHello {
+            client_first: seat == 0,
+            client_mark: SEAT_MARKS[seat],
+            token: Some(tokens[seat]),
+        };
+        send(client, &pkt.to_bytes())?;
+    }
+    relay_games(players, accounts, Grid::default(), &entry)
+}
+
+/// Relays the game of `entry` from `grid`, and its rematches, like [`relay_game`].
+fn relay_games(
+    mut players: [Client; 2],
+    mut accounts: [Option<String>; 2],
+    mut grid: Grid,
+    entry: &GameEntry,
+) -> io::Result<GameResult> {
+    loop {
+        entry.metrics.games_started.fetch_add(1, Ordering::Relaxed);
+        let result = relay_moves(&mut players, grid, entry)?;
+        entry.metrics.games_finished.fetch_add(1, Ordering::Relaxed);
+        let declined = {
+            let mut lobby = entry.lobby.lock().unwrap();
+            if let Some(game) = lobby.games.get_mut(&entry.id) {
+                game.grid = None;
+            }
+            lobby.leaderboard.record(&result, &accounts);
+            lobby.draining
+        };
+        let x_accepts = relay_rematch(&mut players, 0, declined)?;
+        let o_accepts = relay_rematch(&mut players, 1, declined)?;
+        if !(x_accepts && o_accepts) {
+            return Ok(result);
+        }
+        players.swap(0, 1);
+        accounts.swap(0, 1);
+        entry.update(|game| {
+            game.players.swap(0, 1);
+            game.accounts.swap(0, 1);
+            game.tokens.swap(0, 1);
+            game.reconnected.swap(0, 1);
+            game.moves = 0;
+            game.grid = Some(Grid::default());
+        });
+        grid = Grid::default();
+    }
+}
+
+/// Relays a game saved when the server last shut down, once both its players reconnected with
+/// their token (see [`reconnect`]). Both are then sent the game's state. The game is dropped if
+/// they don't reconnect within [`game::RECONNECT_GRACE_PERIOD`].
+fn resume_game(saved: SavedGame, entry: GameEntry) -> io::Result<GameResult> {
+    let deadline = Instant::now() + game::RECONNECT_GRACE_PERIOD;
+    let mut reconnected = [None, None];
+    // Reconnected clients wait for the game's state, so they don't play before the other is back
+    while reconnected.iter().any(Option::is_none) {
+        if Instant::now() >= deadline {
+            return Err(io::Error::new(
+                ErrorKind::TimedOut,
+                "Players didn't reconnect to the saved game",
+            ));
+        }
+        thread::sleep(game::RECONNECT_POLL_INTERVAL);
+        let mut lobby = entry.lobby.lock().unwrap();
+        let Some(game) = lobby.games.get_mut(&entry.id) else {
+            return Err(ErrorKind::ConnectionAborted.into());
+        };
+        if game.tokens.contains(&None) {
+            return Err(ErrorKind::ConnectionAborted.into());
+        }
+        for (client, new) in reconnected.iter_mut().zip(&mut game.reconnected) {
+            if new.is_some() {
+                *client = new.take();
+            }
+        }
+    }
+
+    let mut players = reconnected.map(|client| client.expect("Both players reconnected"));
+    let sync = BoardSync {
+        grid: saved.grid,
+        turn: SEAT_MARKS[saved.grid.cell_count() % 2],
+    }
+    .to_bytes();
+    for client in &mut players {
+        client
+            .get_ref()
+            .set_read_timeout(Some(game::HEARTBEAT_TIMEOUT))?;
+        send(client, &sync)?;
+    }
+    let peers = players.each_ref().map(|client| client.get_ref().peer);
+    entry.update(|game| game.players = peers);
+    relay_games(players, saved.accounts, saved.grid, &entry)
+}
+
+/// Marks of the clients of a relayed game by seat: the client at seat 0 plays `X` and moves first.
+const SEAT_MARKS: [Mark; 2] = [Mark::X, Mark::O];
+
+/// Error relaying a game, caused by the client at `seat`: its connection failed, or it broke the
+/// protocol.
+struct RelayError {
+    seat: usize,
+    error: io::Error,
+}
+
+impl RelayError {
+    fn at(seat: usize) -> impl FnOnce(io::Error) -> Self {
+        move |error| Self { seat, error }
+    }
+}
+
+impl From<RelayError> for io::Error {
+    fn from(value: RelayError) -> Self {
+        value.error
+    }
+}
+
+/// Relays the moves of a single game, until it ends. A client whose connection drops can reconnect
+/// to the game, see [`await_reconnection`].
+fn relay_moves(
+    players: &mut [Client; 2],
+    mut grid: Grid,
+    entry: &GameEntry,
+) -> io::Result<GameResult> {
+    loop {
+        match relay_move(players, &mut grid, entry) {
+            Ok(Some(result)) => return Ok(result),
+            Ok(None) => {}
+            Err(error) => {
+                await_reconnection(players, error, &grid, entry)?;
+                // The last move may have ended the game before a client received it
+                if let Some(result) = game::board_result(&grid) {
+                    return Ok(result);
+                }
+            }
+        }
+    }
+}
+
+/// Relays the next packet ending a turn: a move, which is checked against `grid`, a resignation or
+/// a timeout. Returns the result if it ended the game.
+fn relay_move(
+    players: &mut [Client; 2],
+    grid: &mut Grid,
+    entry: &GameEntry,
+) -> Result<Option<GameResult>, RelayError> {
+    // Rejected moves aren't placed, so the marks on the grid tell whose turn it is
+    let seat = grid.cell_count() % 2;
+    let other = 1 - seat;
+    let mark = SEAT_MARKS[seat];
+    let (row, col) = match relay_until_packet(players, seat)? {
+        Packet::PlayerMove(pkt) => {
+            // Moves sent again are forwarded as well, since the client's acknowledgment may be
+            // lost
+            if usize::from(pkt.seq) <= grid.cell_count() {
+                send_to(players, other, &pkt.to_bytes())?;
+                return Ok(None);
+            }
+            (pkt.row, pkt.col)
+        }
+        Packet::Resign => {
+            send_to(players, other, &Resign.to_bytes())?;
+            return Ok(Some(GameResult::Win {
+                winner: mark.opposite(),
+                reason: WinReason::Resignation,
+            }));
+        }
+        // The client ran out of time on a clock set by both clients
+        Packet::EndOfGame(
+            pkt @ EndOfGame(GameResult::Win {
+                reason: WinReason::Timeout,
+                ..
+            }),
+        ) => {
+            send_to(players, other, &pkt.to_bytes())?;
+            return Ok(Some(pkt.0));
+        }
+        pkt => return Err(RelayError::at(seat)(game::unexpected_remote_packet(&pkt))),
+    };
+    let seq = grid.cell_count() as u8 + 1;
+    if let Err(reason) = grid.try_set_cell(row, col, mark) {
+        // The server's grid is authoritative, so the client is asked for another move
+        let rejection = InvalidMove {
+            row,
+            col,
+            seq,
+            reason,
+        };
+        send_to(players, seat, &rejection.to_bytes())?;
+        return Ok(None);
+    }
+    entry.record_move(grid);
+    // A client missing this move gets the grid with it once reconnected
+    send_to(players, other, &PlayerMove { row, col, seq }.to_bytes())?;
+
+    let result = game::board_result(grid);
+    if let Some(result) = result {
+        // The client who played the last move sends its own END_OF_GAME packet, which isn't
+        // forwarded since the server's grid is authoritative
+        send_to(players, other, &EndOfGame(result).to_bytes())?;
+    }
+    Ok(result)
+}
+
+/// Waits for the client whose connection failed with `error` to reconnect with its token (see
+/// [`reconnect`]), for [`game::RECONNECT_GRACE_PERIOD`], and sends it the state of the game. The
+/// other client is sent heartbeats meanwhile, so it keeps waiting, and may reconnect as well.
+///
+/// The error is returned if the client broke the protocol, if it didn't reconnect in time, if
+/// either client was kicked, or if both clients left.
+fn await_reconnection(
+    players: &mut [Client; 2],
+    error: RelayError,
+    grid: &Grid,
+    entry: &GameEntry,
+) -> io::Result<()> {
+    let RelayError { seat, error } = error;
+    if error.kind() == ErrorKind::InvalidData {
+        return Err(error);
+    }
+    let sync = BoardSync {
+        grid: *grid,
+        turn: SEAT_MARKS[grid.cell_count() % 2],
+    }
+    .to_bytes();
+    let deadline = Instant::now() + game::RECONNECT_GRACE_PERIOD;
+    let mut next_heartbeat = Instant::now();
+    let mut missing = [false; 2];
+    missing[seat] = true;
+    loop {
+        let reconnected = {
+            let mut lobby = entry.lobby.lock().unwrap();
+            match lobby.games.get_mut(&entry.id) {
+                Some(game) if !game.tokens.contains(&None) => mem::take(&mut game.reconnected),
+                _ => return Err(error),
+            }
+        };
+        for (seat, client) in reconnected.into_iter().enumerate() {
+            let Some(mut client) = client else { continue };
+            let resumed = client
+                .get_ref()
+                .set_read_timeout(Some(game::HEARTBEAT_TIMEOUT))
+                .and_then(|_| send(&mut client, &sync));
+            if resumed.is_ok() {
+                let peer = client.get_ref().peer;
+                entry.update(|game| game.players[seat] = peer);
+                players[seat] = client;
+                missing[seat] = false;
+            }
+        }
+        if !missing.contains(&true) {
+            return Ok(());
+        }
+
+        for (client, missing) in players.iter_mut().zip(&mut missing) {
+            if !*missing && is_closed(client) {
+                *missing = true;
+            }
+        }
+        if missing == [true, true] || Instant::now() >= deadline {
+            return Err(error);
+        }
+        if Instant::now() >= next_heartbeat {
+            for (client, missing) in players.iter_mut().zip(&mut missing) {
+                if !*missing && send(client, &Heartbeat.to_bytes()).is_err() {
+                    *missing = true;
+                }
+            }
+            next_heartbeat += game::HEARTBEAT_INTERVAL;
+        }
+        thread::sleep(game::RECONNECT_POLL_INTERVAL);
+    }
+}
+
+/// Returns whether the client closed its connection, without waiting for it to send a packet.
+fn is_closed(client: &Client) -> bool {
+    // A short timeout rather than the non-blocking mode, which would also affect the admin
+    // console's writes
+    let socket = client.get_ref();
+    if socket.set_read_timeout(Some(CLOSED_CHECK_TIMEOUT)).is_err() {
+        return true;
+    }
+    let closed = match socket.peek(&mut [0]) {
+        Ok(read) => read == 0,
+        Err(e) => !matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut),
+    };
+    closed
+        || socket
+            .set_read_timeout(Some(game::HEARTBEAT_TIMEOUT))
+            .is_err()
+}
+
+/// How long [`is_closed`] waits for a client's connection to show it was closed.
+const CLOSED_CHECK_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// Forwards the answer of the client at `seat` to a rematch to the other client, and returns it.
+/// If `declined`, the rematch is declined on the client's behalf.
+fn relay_rematch(players: &mut [Client; 2], seat: usize, declined: bool) -> io::Result<bool> {
+    loop {
+        match relay_until_packet(players, seat)? {
+            Packet::EndOfGame(_) => {}
+            Packet::Rematch(pkt) => {
+                let accepts = pkt.0 && !declined;
+                send_to(players, 1 - seat, &Rematch(accepts).to_bytes())?;
+                return Ok(accepts);
+            }
+            pkt => return Err(game::unexpected_remote_packet(&pkt)),
+        }
+    }
+}
+
+/// Reads the packets of the client at `seat`, forwarding chat messages, heartbeats, board
+/// synchronizations and move acknowledgments to the other client, and returns the first other
+/// packet.
+fn relay_until_packet(players: &mut [Client; 2], seat: usize) -> Result<Packet, RelayError> {
+    loop {
+        match receive(&mut players[seat]).map_err(RelayError::at(seat))? {
+            pkt @ (Packet::ChatMessage(_)
+            | Packet::Heartbeat
+            | Packet::MoveAck(_)
+            | Packet::BoardSync(_)
+            | Packet::ClockUpdate(_)) => send_to(players, 1 - seat, &pkt.to_bytes())?,
+            pkt => return Ok(pkt),
+        }
+    }
+}
+
+fn send_to(players: &mut [Client; 2], seat: usize, pkt: &[u8]) -> Result<(), RelayError> {
+    send(&mut players[seat], pkt).map_err(RelayError::at(seat))
+}
+
+/// Reply to lobby requests that would start a game while the server is draining.
+fn shutting_down() -> LobbyPacket {
+    LobbyPacket::Error("The server is shutting down".to_owned())
+}
+
+/// Handle to manage a running [`DedicatedServer`], from an admin console for instance.
+#[derive(Debug, Clone)]
+pub struct ServerAdmin {
+    lobby: Arc<Mutex<Lobby>>,
+    connections: Arc<Connections>,
+}
+
+/// Game relayed by a [`DedicatedServer`], as listed by [`ServerAdmin::games`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameInfo {
+    pub id: u64,
+    pub player_x: SocketAddr,
+    pub player_o: SocketAddr,
+    /// Account the player of `X` logged in to, if any.
+    pub account_x: Option<String>,
+    pub account_o: Option<String>,
+    /// Moves played in the current game, reset on rematches.
+    pub moves: usize,
+    /// Time since the players were paired, including rematches.
+    pub duration: Duration,
+}
+
+/// Results of an account on a [`DedicatedServer`], as listed in its leaderboard, see
+/// [`LobbyClient::leaderboard`](crate::lobby::LobbyClient::leaderboard).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    /// Account name, which never contains spaces.
+    pub name: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// How often [`ServerAdmin::wait_drained`] and [`ServerAdmin::shutdown`] check whether games are
+/// still being relayed.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl ServerAdmin {
+    /// Returns the games being relayed, by id.
+    pub fn games(&self) -> Vec<GameInfo> {
+        let lobby = self.lobby.lock().unwrap();
+        lobby
+            .games
+            .iter()
+            .map(|(&id, game)| GameInfo {
+                id,
+                player_x: game.players[0],
+                player_o: game.players[1],
+                account_x: game.accounts[0].clone(),
+                account_o: game.accounts[1].clone(),
+                moves: game.moves,
+                duration: game.started.elapsed(),
+            })
+            .collect()
+    }
+
+    /// Returns the names of the server's accounts, in order.
+    pub fn accounts(&self) -> Vec<String> {
+        self.lobby.lock().unwrap().accounts.names()
+    }
+
+    /// Closes the connection of the client at `peer`. A game it was playing is aborted, which also
+    /// closes its opponent's connection. Returns `false` if no client is connected from `peer`.
+    pub fn kick(&self, peer: SocketAddr) -> bool {
+        let Some(client) = self.connections.lock().unwrap().get(&peer).cloned() else {
+            return false;
+        };
+        {
+            let mut lobby = self.lobby.lock().unwrap();
+            // Waiting clients are dropped from the lobby, so no game is started with them
+            lobby.retain_waiting(|client| client.get_ref().peer != peer);
+            // Players can't reconnect to their game, which is aborted
+            for game in lobby.games.values_mut() {
+                for (player, token) in game.players.iter().zip(&mut game.tokens) {
+                    if *player == peer {
+                        *token = None;
+                    }
+                }
+            }
+        }
+        let _ = client.socket.shutdown(Shutdown::Both);
+        true
+    }
+
+    /// Sends `message` to the players of every game being relayed, as a chat message from the
+    /// server. Returns the number of players it was sent to.
+    pub fn notice(&self, message: &str) -> io::Result<usize> {
+        game::check_chat_len(message)?;
+        Ok(self.broadcast(&ChatMessage(message.to_owned()).to_bytes()))
+    }
+
+    /// Sends `pkt` to the players of every game being relayed, and returns the number of players
+    /// it was sent to.
+    fn broadcast(&self, pkt: &[u8]) -> usize {
+        let players: Vec<_> = {
+            let lobby = self.lobby.lock().unwrap();
+            lobby.games.values().flat_map(|game| game.players).collect()
+        };
+        let clients: Vec<_> = {
+            let connections = self.connections.lock().unwrap();
+            players
+                .iter()
+                .filter_map(|peer| connections.get(peer).cloned())
+                .collect()
+        };
+
+        let mut sent = 0;
+        for client in clients {
+            let _guard = client.write_lock.lock().unwrap();
+            // A client that can't be written to is dropped by its game's thread
+            if (&client.socket).write_all(pkt).is_ok() {
+                sent += 1;
+            }
+        }
+        sent
+    }
+
+    /// Stops starting games, so the server can be stopped once the games being relayed end. Clients
+    /// waiting for an opponent are disconnected, new clients are refused, and rematches are declined
+    /// on behalf of the players.
+    pub fn drain(&self) {
+        let mut lobby = self.lobby.lock().unwrap();
+        lobby.draining = true;
+        lobby.retain_waiting(|_| false);
+    }
+
+    /// Returns `true` once [`drain`](Self::drain) was called and no game is being relayed anymore.
+    pub fn is_drained(&self) -> bool {
+        let lobby = self.lobby.lock().unwrap();
+        lobby.draining && lobby.games.is_empty()
+    }
+
+    /// Blocks until the server [is drained](Self::is_drained).
+    pub fn wait_drained(&self) {
+        while !self.is_drained() {
+            thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+    }
+
+    /// Prepares the server to stop: it [drains](Self::drain), and the players of the games being
+    /// relayed are sent a SERVER_SHUTDOWN packet, telling them the games still going after
+    /// `grace_period` are interrupted. Blocks until every game ended or the grace period is over.
+    ///
+    /// Unfinished games are then saved to the server's state file, if it has one (see
+    /// [`DedicatedServer::with_state_file`]), for their players to reconnect to them once the
+    /// server starts again, and their connections are closed. Returns the number of games saved.
+    ///
+    /// Clients only try to reconnect for [`game::RECONNECT_GRACE_PERIOD`] (30 seconds) after
+    /// losing their connection, so the server must be running again by then for the players to get
+    /// their games back.
+    pub fn shutdown(&self, grace_period: Duration) -> io::Result<usize> {
+        self.drain();
+        self.broadcast(&ServerShutdown { grace_period }.to_bytes());
+        let deadline = Instant::now() + grace_period;
+        while !self.is_drained() && Instant::now() < deadline {
+            thread::sleep(
+                DRAIN_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+            );
+        }
+
+        let (saved, players) = {
+            let mut lobby = self.lobby.lock().unwrap();
+            // The games loaded from the state file are saved again below if they are still going
+            lobby.saved.clear();
+            let mut saved = vec![];
+            let mut players = vec![];
+            for game in lobby.games.values_mut() {
+                // Players can't reconnect to the game on this server anymore, so it is aborted
+                let tokens = mem::take(&mut game.tokens);
+                if let (Some(grid), [Some(x), Some(o)]) = (game.grid, tokens) {
+                    saved.push(SavedGame {
+                        grid,
+                        tokens: [x, o],
+                        accounts: game.accounts.clone(),
+                    });
+                }
+                players.extend(game.players);
+            }
+            // Saved under the lock, so games ending meanwhile don't rewrite the file
+            let saved = match &lobby.state_file {
+                Some(path) => saved_games::save(path, &saved).map(|_| saved.len()),
+                None => Ok(0),
+            };
+            (saved, players)
+        };
+
+        let connections = self.connections.lock().unwrap();
+        for client in players.iter().filter_map(|peer| connections.get(peer)) {
+            let _ = client.socket.shutdown(Shutdown::Both);
+        }
+        saved
+    }
+}
+
+/// HTTP endpoint reporting the state of a [`DedicatedServer`], for operators to monitor it without
+/// connecting a game client.
+///
+/// `GET /metrics` is answered with the server's counters in Prometheus' text format: games started
+/// and finished, active games and connections, moves relayed (whose rate gives the moves per
+/// second) and handshake failures. `GET /leaderboard` lists the wins, draws and losses of the
+/// accounts that finished a game, best first:
+///
+/// ```text
+/// [{"name":"alice","wins":3,"draws":1,"losses":0},{"name":"bob","wins":0,"draws":1,"losses":3}]
+/// ```
+///
+/// Any other `GET` request is answered with the server's uptime, the number of players waiting for
+/// an opponent or playing a game, and every game being relayed, as JSON:
+///
+/// ```text
+/// {"uptime_secs":3600,"players":3,"waiting":1,"games":[{"id":0,"player_x":"10.0.0.2:51234",
+/// "player_o":"10.0.0.3:49876","account_x":"alice","account_o":null,"moves":4,"duration_secs":42}]}
+/// ```
+///
+/// Clients still choosing a game in the lobby aren't counted, and players who didn't log in to an
+/// account have a `null` account.
+#[derive(Debug)]
+pub struct StatusServer {
+    listener: TcpListener,
+    lobby: Arc<Mutex<Lobby>>,
+    metrics: Arc<Metrics>,
+    started: Instant,
+}
+
+/// How long a status request has to be received in full, before its connection is dropped.
+const STATUS_REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Longest status request read, headers included. Anything beyond it is ignored.
+const MAX_STATUS_REQUEST_LEN: u64 = 8192;
+
+/// Reads a status request from its socket until [`STATUS_REQUEST_TIMEOUT`] after it was accepted,
+/// so a client sending it slowly can't hold up the next requests.
+struct StatusRequest<'a> {
+    socket: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for StatusRequest<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                ErrorKind::TimedOut,
+                "Status request too slow",
+            ));
+        }
+        self.socket.set_read_timeout(Some(remaining))?;
+        self.socket.read(buf)
+    }
+}
+
+impl StatusServer {
+    /// Returns the address the endpoint is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answers requests until accepting a connection fails. Requests are answered one at a time,
+    /// since they are quick to serve: each has [`STATUS_REQUEST_TIMEOUT`] to be received.
+    pub fn run(&self) -> io::Result<()> {
+        loop {
+            let (socket, _) = self.listener.accept()?;
+            // Errors only affect this request
+            let _ = self.answer(socket);
+        }
+    }
+
+    fn answer(&self, socket: TcpStream) -> io::Result<()> {
+        socket.set_write_timeout(Some(STATUS_REQUEST_TIMEOUT))?;
+        let request = StatusRequest {
+            socket: &socket,
+            deadline: Instant::now() + STATUS_REQUEST_TIMEOUT,
+        };
+        let mut reader = BufReader::new(request.take(MAX_STATUS_REQUEST_LEN));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // The headers are read so the client doesn't see the connection reset
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let (status, content_type, body) = match request_line.split_whitespace().collect::<Vec<_>>()
+            [..]
+        {
+            ["GET", "/metrics", ..] => ("200 OK", "text/plain; version=0.0.4", self.metrics_text()),
+            ["GET", "/leaderboard", ..] => ("200 OK", "application/json", self.leaderboard_json()),
+            ["GET", ..] => ("200 OK", "application/json", self.status_json()),
+            _ => (
+                "405 Method Not Allowed",
+                "application/json",
+                r#"{"error":"Only GET requests are supported"}"#.to_owned(),
+            ),
+        };
+        let mut socket = &socket;
+        write!(socket, "HTTP/1.1 {}\r\n", status)?;
+        write!(socket, "Content-Type: {}\r\n", content_type)?;
+        write!(socket, "Content-Length: {}\r\n", body.len())?;
+        write!(socket, "Connection: close\r\n\r\n{}", body)?;
+        socket.flush()
+    }
+
+    /// Writes the server's metrics in Prometheus' text exposition format.
+    fn metrics_text(&self) -> String {
+        let active_games = self.lobby.lock().unwrap().games.len() as u64;
+        let metrics = &self.metrics;
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let mut text = String::new();
+        for (name, kind, help, value) in [
+            (
+                "uptime_seconds",
+                "gauge",
+                "Time since the server started.",
+                self.started.elapsed().as_secs(),
+            ),
+            (
+                "games_started_total",
+                "counter",
+                "Games started, including rematches.",
+                load(&metrics.games_started),
+            ),
+            (
+                "games_finished_total",
+                "counter",
+                "Games played until their end.",
+                load(&metrics.games_finished),
+            ),
+            (
+                "active_games",
+                "gauge",
+                "Games being relayed.",
+                active_games,
+            ),
+            (
+                "connections",
+                "gauge",
+                "Open client connections.",
+                load(&metrics.connections),
+            ),
+            (
+                "moves_total",
+                "counter",
+                "Moves relayed.",
+                load(&metrics.moves),
+            ),
+            (
+                "handshake_failures_total",
+                "counter",
+                "Clients whose first packet was invalid.",
+                load(&metrics.handshake_failures),
+            ),
+        ] {
+            let _ = writeln!(text, "# HELP tictactoe_{} {}", name, help);
+            let _ = writeln!(text, "# TYPE tictactoe_{} {}", name, kind);
+            let _ = writeln!(text, "tictactoe_{} {}", name, value);
+        }
+        text
+    }
+
+    /// Describes the server's state. Only numbers, socket addresses and account names are written,
+    /// which don't need escaping.
+    fn status_json(&self) -> String {
+        let lobby = self.lobby.lock().unwrap();
+        let account = |account: &Option<String>| match account {
+            Some(name) => format!(r#""{}""#, name),
+            None => "null".to_owned(),
+        };
+        let games = lobby
+            .games
+            .iter()
+            .map(|(id, game)| {
+                format!(
+                    concat!(
+                        r#"{{"id":{},"player_x":"{}","player_o":"{}","account_x":{},"#,
+                        r#""account_o":{},"moves":{},"duration_secs":{}}}"#
+                    ),
+                    id,
+                    game.players[0],
+                    game.players[1],
+                    account(&game.accounts[0]),
+                    account(&game.accounts[1]),
+                    game.moves,
+                    game.started.elapsed().as_secs()
+                )
+            })
+            .collect::<Vec<_>>();
+        let waiting = lobby.waiting_count();
+        format!(
+            r#"{{"uptime_secs":{},"players":{},"waiting":{},"games":[{}]}}"#,
+            self.started.elapsed().as_secs(),
+            waiting + 2 * lobby.games.len(),
+            waiting,
+            games.join(",")
+        )
+    }
+
+    /// Lists the results of the server's accounts, best first. Account names don't need escaping.
+    fn leaderboard_json(&self) -> String {
+        let standings = self.lobby.lock().unwrap().leaderboard.standings();
+        let entries = standings
+            .iter()
+            .map(|entry| {
+                format!(
+                    r#"{{"name":"{}","wins":{},"draws":{},"losses":{}}}"#,
+                    entry.name, entry.wins, entry.draws, entry.losses
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", entries.join(","))
+    }
+}
+
+/// Reads the client's next packet, failing if it is longer than [`MAX_PACKET_LEN`].
+fn receive(client: &mut impl BufRead) -> io::Result<Packet> {
+    let mut buf = vec![];
+    client
+        .take(MAX_PACKET_LEN)
+        .read_until(protocol::TERMINATOR, &mut buf)?;
+    if buf.len() as u64 == MAX_PACKET_LEN && buf.last() != Some(&protocol::TERMINATOR) {
+        return Err(io::Error::new(ErrorKind::InvalidData, "Packet too long"));
+    }
+    game::parse_packet(&buf)
+}
+
+fn send(client: &mut BufReader<impl Write>, pkt: &[u8]) -> io::Result<()> {
+    client.get_mut().write_all(pkt)?;
+    client.get_mut().flush()
+}
+
+/// Options creating files only the server can read, for the files giving access to its accounts
+/// or games.
+fn private_file_options() -> fs::OpenOptions {
+    let mut options = fs::OpenOptions::new();
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use crate::{
+        game::{DrawReason, NetworkedGame, RemoteGame},
+        grid::GridPlacementError,
+        player::{tests::shared, BotPlayer, Move, Player, ScriptedPlayer},
+        protocol::{ClientHello, Reconnect},
+    };
+
+    use super::*;
+
+    fn start_server() -> SocketAddr {
+        let server = DedicatedServer::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        thread::spawn(move || server.run());
+        addr
+    }
+
+    /// Plays a game hosted by the server until it ends, returning the final grid and result.
+    fn play(addr: SocketAddr, player: impl Player + 'static) -> (Grid, GameResult) {
+        let player = shared(player);
+        let mut game = RemoteGame::connect(addr).expect("Error connecting to server");
+        loop {
+            if let Some(result) = game.result() {
+                return (*game.grid(), result);
+            }
+            game.try_move(&player).expect("Error playing move");
+        }
+    }
+
+    #[test]
+    fn server_relays_moves_between_clients() {
+        let addr = start_server();
+        // Clients are paired in the order they connect, so wait for the first one to be waiting
+        let first = thread::spawn(move || {
+            let moves = [(0, 0), (0, 1), (0, 2)].map(|(row, col)| Move::Place(row, col));
+            play(addr, ScriptedPlayer::new(moves))
+        });
+        thread::sleep(Duration::from_millis(50));
+        let moves = [(1, 0), (1, 1)].map(|(row, col)| Move::Place(row, col));
+        let second = play(addr, ScriptedPlayer::new(moves));
+
+        let first = first.join().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.0.get_winning_mark(), Some(Mark::X));
+        assert_eq!(
+            first.1,
+            GameResult::Win {
+                winner: Mark::X,
+                reason: WinReason::Line
+            }
+        );
+    }
+
+    #[test]
+    fn status_endpoint_reports_games_and_metrics() {
+        let server = DedicatedServer::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let status = server
+            .status_server("127.0.0.1:0")
+            .expect("Error binding status endpoint");
+        let status_addr = status.local_addr().expect("Error getting status address");
+        thread::spawn(move || server.run());
+        thread::spawn(move || status.run());
+        let get = |method: &str, path: &str| {
+            let mut socket = TcpStream::connect(status_addr).unwrap();
+            write!(socket, "{} {} HTTP/1.1\r\n\r\n", method, path).unwrap();
+            let mut response = String::new();
+            socket.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let first = thread::spawn(move || RemoteGame::connect(addr).unwrap());
+        thread::sleep(Duration::from_millis(50));
+        assert!(get("GET", "/").contains(r#""players":1,"waiting":1,"games":[]"#));
+
+        let mut player_o = RemoteGame::connect(addr).unwrap();
+        let mut player_x = first.join().unwrap();
+        player_x
+            .try_move(&shared(ScriptedPlayer::new([Move::Place(1, 1)])))
+            .unwrap();
+        player_o.try_move(&shared(ScriptedPlayer::new([]))).unwrap();
+        let response = get("GET", "/status");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(r#""players":2,"waiting":0"#));
+        assert!(response.contains(r#""moves":1"#));
+
+        drop((player_x, player_o));
+        let mut spammer = TcpStream::connect(addr).unwrap();
+        spammer.write_all(&Resign.to_bytes()).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert!(get("GET", "/").contains(r#""players":0,"waiting":0,"games":[]"#));
+        assert!(get("POST", "/").starts_with("HTTP/1.1 405"));
+
+        let metrics = get("GET", "/metrics");
+        assert!(metrics.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+        for line in [
+            "tictactoe_games_started_total 1\n",
+            "tictactoe_games_finished_total 0\n",
+            "tictactoe_active_games 0\n",
+            "tictactoe_connections 0\n",
+            "tictactoe_moves_total 1\n",
+            "tictactoe_handshake_failures_total 1\n",
+        ] {
+            assert!(metrics.contains(line), "Missing {:?} in {}", line, metrics);
+        }
+        // Players without an account aren't ranked
+        assert!(get("GET", "/leaderboard").ends_with("\r\n\r\n[]"));
+
+        // A request sent a byte at a time is dropped, rather than holding up the next ones
+        let mut slow = TcpStream::connect(status_addr).unwrap();
+        thread::spawn(move || {
+            while slow.write_all(b"X").is_ok() {
+                thread::sleep(Duration::from_millis(100));
+            }
+        });
+        thread::sleep(Duration::from_millis(50));
+        let start = Instant::now();
+        assert!(get("GET", "/").starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(start.elapsed() < STATUS_REQUEST_TIMEOUT * 3);
+    }
+
+    #[test]
+    fn admin_manages_running_server() {
+        let server = DedicatedServer::bind("127.0.0.1:0").expect("Error binding to socket");
+        let addr = server.local_addr().expect("Error getting server address");
+        let admin = server.admin();
+        thread::spawn(move || server.run());
+        let connect = || {
+            let mut client = BufReader::new(TcpStream::connect(addr).unwrap());
+            send(&mut client, &ClientHello.to_bytes()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            client
+        };
+        let mut player_x = connect();
+        let mut player_o = connect();
+        receive(&mut player_x).unwrap();
+        receive(&mut player_o).unwrap();
+
+        let games = admin.games();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].player_x, player_x.get_ref().local_addr().unwrap());
+        assert_eq!(games[0].player_o, player_o.get_ref().local_addr().unwrap());
+        assert_eq!(admin.notice("Restarting soon").unwrap(), 2);
+        for client in [&mut player_x, &mut player_o] {
+            assert!(
+                matches!(receive(client).unwrap(), Packet::ChatMessage(ChatMessage(m)) if m == "Restarting soon")
+            );
+        }
+
+        let mut waiting = connect();
+        let waiting_addr = waiting.get_ref().local_addr().unwrap();
+        assert!(admin.kick(waiting_addr));
+        assert!(!admin.kick(waiting_addr));
+        assert!(receive(&mut waiting).is_err());
+
+        admin.drain();
+        assert!(receive(&mut connect()).is_err());
+        let err = crate::lobby::join_room(addr, "blue-otter").unwrap_err();
+        assert!(matches!(err, crate::lobby::LobbyError::Refused(_)));
+        assert!(!admin.is_drained());
+
+        // The game being relayed is played until its end, without a rematch
+        send(&mut player_x, &Resign.to_bytes()).unwrap();
+        assert!(matches!(receive(&mut player_o).unwrap(), Packet::Resign));
+        send(&mut player_x, &Rematch(true).to_bytes()).unwrap();
+        send(&mut player_o, &Rematch(true).to_bytes()).unwrap();
+        for client in [&mut player_x, &mut player_o] {
+            assert!(matches!(
+                receive(client).unwrap(),
+                Packet::Rematch(Rematch(false))
+            ));
+        }
+        admin.wait_drained();
+        assert!(admin.games().is_empty());
+    }
+
+    #[test]
+    fn server_rejects_illegal_moves() {
+        let addr = start_server();
+        let connect = || {
+            let mut client = BufReader::new(TcpStream::connect(addr).unwrap());
+            send(&mut client, &ClientHello.to_bytes()).unwrap();
+            client
+        };
+        let mut player_x = connect();
+        thread::sleep(Duration::from_millis(50));
+        let mut player_o = connect();
+        receive(&mut player_x).unwrap();
+        receive(&mut player_o).unwrap();
+
+        let first = PlayerMove {
+            row: 1,
+            col: 1,
+            seq: 1,
+        };
+        send(&mut player_x, &first.to_bytes()).unwrap();
+        assert!(matches!(receive(&mut player_o).unwrap(), Packet::PlayerMove(pkt) if pkt == first));
+
+        let illegal = PlayerMove { seq: 2, ..first };
+        send(&mut player_o, &illegal.to_bytes()).unwrap();
+        let Packet::InvalidMove(rejection) = receive(&mut player_o).unwrap() else {
+            panic!("Expected the illegal move to be rejected");
+        };
+        assert!(rejection.rejects(&illegal));
+        assert_eq!(
+            rejection.reason,
+            GridPlacementError::CellInUse { row: 1, col: 1 }
+        );
+
+        let legal = PlayerMove {
+            row: 0,
+            col: 0,
+            seq: 2,
+        };
+        send(&mut player_o, &legal.to_bytes()).unwrap();
+        assert!(matches!(receive(&mut player_x).unwrap(), Packet::PlayerMove(pkt) if pkt == legal));
+    }
+
+    #[test]
+    fn server_drops_misbehaving_clients() {
+        let server = DedicatedServer::bind("127.0.0.1:0")
+            .expect("Error binding to socket")
+            .with_max_connections(1);
+        let addr = server.local_addr().expect("Error getting server address");
+        thread::spawn(move || server.run());
+        let closed = |client: &mut TcpStream| matches!(client.read(&mut [0]), Ok(0));
+
+        // A client that never sends its first packet is dropped after the handshake timeout
+        let mut silent = TcpStream::connect(addr).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        // Beyond the cap, connections are closed right away
+        let mut refused = TcpStream::connect(addr).unwrap();
+        assert!(closed(&mut refused));
+        assert!(closed(&mut silent));
+
+        // A packet without a terminator isn't buffered forever
+        let mut flooding = TcpStream::connect(addr).unwrap();
+        let garbage = [0xAA; MAX_PACKET_LEN as usize + 1];
+        flooding.write_all(&garbage).unwrap();
+        assert!(closed(&mut flooding));
+    }
+
+    #[test]
+    fn clients_who_left_while_waiting_are_not_paired() {
+        let addr = start_server();
+        let connect = || {
+            let mut client = BufReader::new(TcpStream::connect(addr).unwrap());
+            send(&mut client, &ClientHello.to_bytes()).unwrap();
+            client
+        };
+        drop(connect());
+        thread::sleep(Duration::from_millis(50));
+        let mut player_x = connect();
+        thread::sleep(Duration::from_millis(50));
+        let mut player_o = connect();
+        let Packet::ServerHello(hello) = receive(&mut player_x).unwrap() else {
+            panic!("Expected SERVER_HELLO");
+        };
+        assert_eq!(hello.client_mark, Mark::X);
+        assert!(matches!(
+            receive(&mut player_o).unwrap(),
+            Packet::ServerHello(_)
+        ));
+    }
+
+    #[test]
+    fn clients_reconnect_to_their_game() {
+        let addr = start_server();
+        let connect = |hello: &[u8]| {
+            let mut client = BufReader::new(TcpStream::connect(addr).unwrap());
+            send(&mut client, hello).unwrap();
+            client
+        };
+        let mut player_x = connect(&ClientHello.to_bytes());
+        thread::sleep(Duration::from_millis(50));
+        let mut player_o = connect(&ClientHello.to_bytes());
+        let Packet::ServerHello(hello) = receive(&mut player_x).unwrap() else {
+            panic!("Expected a SERVER_HELLO packet");
+        };
+        let token = hello.token.expect("Missing reconnection token");
+        assert!(matches!(
+            receive(&mut player_o).unwrap(),
+            Packet::ServerHello(ServerHello { token: Some(t), .. }) if t != token
+        ));
+
+        // X's connection drops right after its move, and O's move is sent while it reconnects
+        send(
+            &mut player_x,
+            &PlayerMove {
+                row: 1,
+                col: 1,
+                seq: 1,
+            }
+            .to_bytes(),
+        )
+        .unwrap();
+        drop(player_x);
+        assert!(matches!(
+            receive(&mut player_o).unwrap(),
+            Packet::PlayerMove(PlayerMove { seq: 1, .. })
+        ));
+        let mut player_x = connect(&Reconnect { token }.to_bytes());
+        thread::sleep(Duration::from_millis(50));
+        send(
+            &mut player_o,
+            &PlayerMove {
+                row: 0,
+                col: 0,
+                seq: 2,
+            }
+            .to_bytes(),
+        )
+        .unwrap();
+        let Packet::BoardSync(sync) = receive(&mut player_x).unwrap() else {
+            panic!("Expected a BOARD_SYNC packet");
+        };
+        assert_eq!(sync.grid.cell_count(), 2);
+        assert_eq!(sync.turn, Mark::X);
+
+        // The game goes on over the new connection
+        send(
+            &mut player_x,
+            &PlayerMove {
+                row: 2,
+                col: 2,
+                seq: 3,
+            }
+            .to_bytes(),
+        )
+        .unwrap();
+        assert!(matches!(
+            receive(&mut player_o).unwrap(),
+            Packet::PlayerMove(PlayerMove { seq: 3, .. })
+        ));
+
+        let mut stranger = connect(&Reconnect { token: !token }.to_bytes());
+        assert!(receive(&mut stranger).is_err());
+    }
+
+    #[test]
+    fn shutdown_saves_games_for_the_next_server() {
+        let path = std::env::temp_dir().join(format!("tictactoe-state-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let start = || {
+            let server = DedicatedServer::bind("127.0.0.1:0")
+                .and_then(|server| server.with_state_file(&path))
+                .expect("Error starting server");
+            let addr = server.local_addr().expect("Error getting server address");
+            let admin = server.admin();
+            thread::spawn(move || server.run());
+            (addr, admin)
+        };
+        let connect = |addr, hello: &[u8]| {
+            let mut client = BufReader::new(TcpStream::connect(addr).unwrap());
+            send(&mut client, hello).unwrap();
+            client
+        };
+        let (addr, admin) = start();
+        let mut player_x = connect(addr, &ClientHello.to_bytes());
+        thread::sleep(Duration::from_millis(50));
+        let mut player_o = connect(addr, &ClientHello.to_bytes());
+        let tokens = [&mut player_x, &mut player_o].map(|client| match receive(client).unwrap() {
+            Packet::ServerHello(ServerHello {
+                token: Some(token), ..
+            }) => token,
+            pkt => panic!("Expected a SERVER_HELLO packet, got {:?}", pkt),
+        });
+        let first = PlayerMove {
+            row: 1,
+            col: 1,
+            seq: 1,
+        };
+        send(&mut player_x, &first.to_bytes()).unwrap();
+        assert!(matches!(receive(&mut player_o).unwrap(), Packet::PlayerMove(pkt) if pkt == first));
+
+        let grace_period = Duration::from_millis(100);
+        assert_eq!(admin.shutdown(grace_period).unwrap(), 1);
+        // The game is aborted once saved
+        admin.wait_drained();
+        for client in [&mut player_x, &mut player_o] {
+            assert!(matches!(
+                receive(client).unwrap(),
+                Packet::ServerShutdown(pkt) if pkt.grace_period == grace_period
+            ));
+            assert!(receive(client).is_err());
+        }
+
+        // The players reconnect to the next server, which sends them the game's state once both
+        // are back
+        let (addr, admin) = start();
+        assert_eq!(admin.games()[0].moves, 1);
+        let mut player_x = connect(addr, &Reconnect { token: tokens[0] }.to_bytes());
+        let mut player_o = connect(addr, &Reconnect { token: tokens[1] }.to_bytes());
+        for client in [&mut player_x, &mut player_o] {
+            let Packet::BoardSync(sync) = receive(client).unwrap() else {
+                panic!("Expected a BOARD_SYNC packet");
+            };
+            assert_eq!(sync.grid.cell_count(), 1);
+            assert_eq!(sync.turn, Mark::O);
+        }
+        let reply = PlayerMove {
+            row: 0,
+            col: 0,
+            seq: 2,
+        };
+        send(&mut player_o, &reply.to_bytes()).unwrap();
+        assert!(matches!(receive(&mut player_x).unwrap(), Packet::PlayerMove(pkt) if pkt == reply));
+
+        // The game stays saved until it ends, in case the server crashes
+        assert_eq!(saved_games::load(&path).unwrap().len(), 1);
+        send(&mut player_o, &Resign.to_bytes()).unwrap();
+        send(&mut player_x, &Rematch(false).to_bytes()).unwrap();
+        while !admin.games().is_empty() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(saved_games::load(&path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn server_forwards_resignation() {
+        let addr = start_server();
+        let first = thread::spawn(move || play(addr, ScriptedPlayer::new([Move::Place(1, 1)])));
+        thread::sleep(Duration::from_millis(50));
+        let (_, second) = play(addr, ScriptedPlayer::new([Move::Resign]));
+
+        let (grid, first) = first.join().unwrap();
+        let result = GameResult::Win {
+            winner: Mark::X,
+            reason: WinReason::Resignation,
+        };
+        assert_eq!((first, second), (result, result));
+        assert_eq!(grid.cell_count(), 1);
+    }
+
+    #[test]
+    fn server_relays_rematches() {
+        let addr = start_server();
+        let first = thread::spawn(move || {
+            let mut game = RemoteGame::connect(addr).expect("Error connecting to server");
+            game.try_move(&shared(ScriptedPlayer::new([Move::Resign])))
+                .unwrap();
+            assert!(game.rematch(true).unwrap());
+            // The second client now moves first
+            let player = shared(ScriptedPlayer::new([Move::Place(0, 0)]));
+            game.try_move(&player).unwrap();
+            game.try_move(&player).unwrap();
+            game
+        });
+        thread::sleep(Duration::from_millis(50));
+        let mut second = RemoteGame::connect(addr).expect("Error connecting to server");
+        second.try_move(&shared(ScriptedPlayer::new([]))).unwrap();
+        assert!(second.rematch(true).unwrap());
+        second
+            .try_move(&shared(ScriptedPlayer::new([Move::Place(1, 1)])))
+            .unwrap();
+        second.try_move(&shared(ScriptedPlayer::new([]))).unwrap();
+
+        let first = first.join().unwrap();
+        assert_eq!(first.local_mark(), Mark::O);
+        assert_eq!(second.local_mark(), Mark::X);
+        assert_eq!(first.grid(), second.grid());
+        assert_eq!(first.grid().cell_count(), 2);
+    }
+
+    #[test]
+    fn server_hosts_games_concurrently() {
+        let addr = start_server();
+        let games: Vec<_> = (0..6)
+            .map(|_| thread::spawn(move || play(addr, BotPlayer::impossible())))
+            .collect();
+
+        for game in games {
+            let (grid, result) = game.join().unwrap();
+            assert!(grid.is_full());
+            assert_eq!(result, GameResult::Draw(DrawReason::BoardFull));
+        }
+    }
+}
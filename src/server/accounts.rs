@@ -0,0 +1,160 @@
+//! Named accounts of a [`DedicatedServer`](super::DedicatedServer), which clients log in to with a
+//! token handed out when the account is created.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+/// Longest account name accepted by the server, in bytes.
+pub const MAX_ACCOUNT_NAME_LEN: usize = 24;
+
+/// SHA-256 hash of an account's token. Only hashes are kept, so the accounts file doesn't give
+/// access to the accounts.
+type TokenHash = [u8; 32];
+
+/// Accounts by name, saved to a file if the server was given one.
+#[derive(Debug, Default)]
+pub(super) struct Accounts {
+    /// Token hashes by account name
+    tokens: BTreeMap<String, TokenHash>,
+    path: Option<PathBuf>,
+}
+
+impl Accounts {
+    /// Loads the accounts saved at `path`, one `<name> <token hash>` line each with the SHA-256
+    /// hash of the token in hexadecimal, and saves the accounts created from now on there. A
+    /// missing file holds no accounts.
+    pub(super) fn load(path: &Path) -> io::Result<Self> {
+        let saved = match fs::read_to_string(path) {
+            Ok(saved) => saved,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let tokens = saved
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(
+                |line| match line.trim().split_once(' ').map(|(n, h)| (n, parse_hash(h))) {
+                    Some((name, Some(hash))) => Ok((name.to_owned(), hash)),
+                    _ => Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid account line: {}", line),
+                    )),
+                },
+            )
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            tokens,
+            path: Some(path.to_owned()),
+        })
+    }
+
+    /// Creates the account `name`, and returns its token. Fails with the reason sent to the client
+    /// if the name is invalid or taken, or the account can't be saved.
+    pub(super) fn create(&mut self, name: &str) -> Result<String, String> {
+        if name.is_empty()
+            || name.len() > MAX_ACCOUNT_NAME_LEN
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Account names are 1 to {} letters, digits, dashes or underscores",
+                MAX_ACCOUNT_NAME_LEN
+            ));
+        }
+        if self.tokens.contains_key(name) {
+            return Err(format!("The account name {} is taken", name));
+        }
+
+        let token = format!("{:032x}", rand::random::<u128>());
+        let hash = hash(&token);
+        if let Some(path) = &self.path {
+            let hex: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+            super::private_file_options()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{} {}", name, hex))
+                .map_err(|e| format!("The account couldn't be saved: {}", e))?;
+        }
+        self.tokens.insert(name.to_owned(), hash);
+        Ok(token)
+    }
+
+    /// Returns whether `token` is the token of the account `name`. The token's hash is compared in
+    /// constant time, so the comparison's duration doesn't reveal how much of it is right.
+    pub(super) fn authenticate(&self, name: &str, token: &str) -> bool {
+        let hash = hash(token);
+        self.tokens.get(name).is_some_and(|expected| {
+            expected
+                .iter()
+                .zip(hash)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+        })
+    }
+
+    /// Returns whether the account `name` exists.
+    pub(super) fn contains(&self, name: &str) -> bool {
+        self.tokens.contains_key(name)
+    }
+
+    /// Returns the account names, in order.
+    pub(super) fn names(&self) -> Vec<String> {
+        self.tokens.keys().cloned().collect()
+    }
+}
+
+fn hash(token: &str) -> TokenHash {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+fn parse_hash(hex: &str) -> Option<TokenHash> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = TokenHash::default();
+    for (byte, i) in hash.iter_mut().zip((0..hex.len()).step_by(2)) {
+        *byte = u8::from_str_radix(&hex[i..i + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accounts_are_saved_and_authenticated() {
+        let path = std::env::temp_dir().join(format!("tictactoe-accounts-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut accounts = Accounts::load(&path).unwrap();
+        let token = accounts.create("alice").unwrap();
+        assert!(accounts.create("alice").is_err());
+        assert!(accounts.create("bob smith").is_err());
+        assert!(accounts.create("").is_err());
+
+        let accounts = Accounts::load(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        #[cfg(unix)]
+        let mode =
+            std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&path).unwrap().permissions());
+        fs::remove_file(&path).unwrap();
+        // Only the token's hash is saved, and only the server can read it
+        assert!(!saved.contains(&token));
+        #[cfg(unix)]
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(accounts.names(), ["alice"]);
+        assert!(accounts.contains("alice"));
+        assert!(!accounts.contains("bob"));
+        assert!(accounts.authenticate("alice", &token));
+        assert!(!accounts.authenticate("alice", "wrong"));
+        assert!(!accounts.authenticate("bob", &token));
+    }
+}
@@ -0,0 +1,84 @@
+//! Results of the accounts of a [`DedicatedServer`](super::DedicatedServer), recorded as their
+//! games end.
+
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use crate::{game::GameResult, grid::Mark};
+
+use super::LeaderboardEntry;
+
+/// Results by account name, kept until the server stops.
+#[derive(Debug, Default)]
+pub(super) struct Leaderboard {
+    entries: BTreeMap<String, LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// Records the result of a game between the accounts playing `X` and `O`. Players who didn't
+    /// log in aren't recorded, and neither are games an account played against itself.
+    pub(super) fn record(&mut self, result: &GameResult, accounts: &[Option<String>; 2]) {
+        if accounts[0].is_some() && accounts[0] == accounts[1] {
+            return;
+        }
+        for (account, mark) in accounts.iter().zip([Mark::X, Mark::O]) {
+            let Some(name) = account else { continue };
+            let entry = self
+                .entries
+                .entry(name.clone())
+                .or_insert_with(|| LeaderboardEntry {
+                    name: name.clone(),
+                    wins: 0,
+                    draws: 0,
+                    losses: 0,
+                });
+            match result {
+                GameResult::Win { winner, .. } if *winner == mark => entry.wins += 1,
+                GameResult::Win { .. } => entry.losses += 1,
+                GameResult::Draw(_) => entry.draws += 1,
+            }
+        }
+    }
+
+    /// Returns the accounts that finished a game, by most wins, then most draws, then fewest
+    /// losses. Ties are listed by name.
+    pub(super) fn standings(&self) -> Vec<LeaderboardEntry> {
+        let mut standings: Vec<_> = self.entries.values().cloned().collect();
+        // Sorting is stable, so ties stay in name order
+        standings.sort_by_key(|entry| (Reverse(entry.wins), Reverse(entry.draws), entry.losses));
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{DrawReason, WinReason};
+
+    use super::*;
+
+    #[test]
+    fn results_are_ranked() {
+        let alice = Some("alice".to_owned());
+        let bob = Some("bob".to_owned());
+        let carol = Some("carol".to_owned());
+        let win = |winner| GameResult::Win {
+            winner,
+            reason: WinReason::Line,
+        };
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.record(&win(Mark::X), &[alice.clone(), bob.clone()]);
+        leaderboard.record(&win(Mark::O), &[None, carol.clone()]);
+        leaderboard.record(&GameResult::Draw(DrawReason::BoardFull), &[bob, carol]);
+        // Games against oneself aren't counted
+        leaderboard.record(&win(Mark::X), &[alice.clone(), alice]);
+
+        let standings = leaderboard.standings();
+        let results: Vec<_> = standings
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.wins, entry.draws, entry.losses))
+            .collect();
+        assert_eq!(
+            results,
+            [("carol", 1, 1, 0), ("alice", 1, 0, 0), ("bob", 0, 1, 1)]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Games a [`DedicatedServer`](super::DedicatedServer) was relaying when it shut down, saved so
+//! their players can reconnect to them once it starts again.
+
+use std::{
+    fs,
+    io::{self, ErrorKind, Write},
+    path::Path,
+};
+
+use crate::grid::Grid;
+
+/// Unfinished game, with what its players need to reconnect to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct SavedGame {
+    pub(super) grid: Grid,
+    /// Tokens the players reconnect with, the player of `X` first
+    pub(super) tokens: [u64; 2],
+    /// Accounts the players logged in to, in the same order
+    pub(super) accounts: [Option<String>; 2],
+}
+
+/// Marks a player who didn't log in to an account, which account names can't contain.
+const NO_ACCOUNT: &str = "*";
+
+/// Loads the games saved at `path`. A missing file holds no games.
+pub(super) fn load(path: &Path) -> io::Result<Vec<SavedGame>> {
+    let saved = match fs::read_to_string(path) {
+        Ok(saved) => saved,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    saved
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            parse(line.trim()).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid saved game line: {}", line),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Replaces the games saved at `path` with `games`, one
+/// `<grid> <token of X> <token of O> <account of X> <account of O>` line each. The grid is in the
+/// notation of [`Grid::notation`], and players who didn't log in have a `*` account. The file gives
+/// access to the games, so it is created readable by the server only.
+///
+/// The games are written to a temporary file next to `path` first, which then replaces it: a crash
+/// while saving leaves the previous games at `path`.
+pub(super) fn save(path: &Path, games: &[SavedGame]) -> io::Result<()> {
+    let lines: String = games
+        .iter()
+        .map(|game| {
+            let [account_x, account_o] = game
+                .accounts
+                .each_ref()
+                .map(|account| account.as_deref().unwrap_or(NO_ACCOUNT));
+            format!(
+                "{} {:016x} {:016x} {} {}\n",
+                game.grid.notation(),
+                game.tokens[0],
+                game.tokens[1],
+                account_x,
+                account_o
+            )
+        })
+        .collect();
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut temp = super::private_file_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp_path)?;
+    temp.write_all(lines.as_bytes())?;
+    temp.sync_all()?;
+    fs::rename(&temp_path, path)
+}
+
+fn parse(line: &str) -> Option<SavedGame> {
+    let fields: Vec<_> = line.split(' ').collect();
+    let &[grid, token_x, token_o, account_x, account_o] = fields.as_slice() else {
+        return None;
+    };
+    let token = |hex| u64::from_str_radix(hex, 16).ok();
+    let account = |name: &str| (name != NO_ACCOUNT).then(|| name.to_owned());
+    Some(SavedGame {
+        grid: grid.parse().ok()?,
+        tokens: [token(token_x)?, token(token_o)?],
+        accounts: [account(account_x), account(account_o)],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Mark;
+
+    use super::*;
+
+    #[test]
+    fn games_are_saved_and_loaded() {
+        let path = std::env::temp_dir().join(format!("tictactoe-games-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(load(&path).unwrap().is_empty());
+
+        let mut grid = Grid::default();
+        grid.set_cell(1, 1, Mark::X);
+        let games = [
+            SavedGame {
+                grid,
+                tokens: [1, u64::MAX],
+                accounts: [Some("alice".to_owned()), None],
+            },
+            SavedGame {
+                grid: Grid::default(),
+                tokens: [0xab, 0xcd],
+                accounts: [None, None],
+            },
+        ];
+        save(&path, &games).unwrap();
+        let loaded = load(&path).unwrap();
+        #[cfg(unix)]
+        assert_eq!(
+            std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&path).unwrap().permissions())
+                & 0o777,
+            0o600
+        );
+        save(&path, &[]).unwrap();
+        assert!(load(&path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, games);
+
+        assert!(parse("X../.../... 1 2 alice").is_none());
+        assert!(parse("X../.../... 1 zz alice *").is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! Read-only clients of a hosted game, see [`Spectator`].
+//!
+//! Spectators open their connection with a `SpectatorHello` packet. The host then sends them:
+//! - the state of the game (a `BoardSync` packet), when they join and when a rematch starts,
+//! - every move as it is played (a `MoveBroadcast` packet),
+//! - the result of the game once it ends (an `EndOfGame` packet).
+
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use crate::{
+    game::{self, GameResult, GameStream, PlayedMove},
+    grid::{Grid, GridPlacementError, Mark},
+    player::Move,
+    protocol::{self, EndOfGame, Packet, SpectatorHello},
+};
+
+/// Update of the watched game received from the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectatorEvent {
+    Move(PlayedMove),
+    GameOver(GameResult),
+}
+
+/// Client watching a game hosted by a [`ServerGame`](crate::game::ServerGame), without playing
+/// it.
+///
+/// The host first sends the state of the game when the spectator joins, and again when a rematch
+/// starts. Only the moves played afterwards are received one by one.
+#[derive(Debug)]
+pub struct Spectator<S: GameStream = TcpStream> {
+    stream: BufReader<S>,
+    grid: Grid,
+    turn: Option<Mark>,
+    result: Option<GameResult>,
+}
+
+impl Spectator {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Self::start(TcpStream::connect(addr)?)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Spectator<crate::game::TlsClientStream> {
+    /// Same as [`Spectator::connect`], over a TLS connection. The server's certificate must be
+    /// valid for `server_name`, according to `config`.
+    pub fn connect_tls<A: ToSocketAddrs>(
+        addr: A,
+        config: std::sync::Arc<rustls::ClientConfig>,
+        server_name: rustls::pki_types::ServerName<'static>,
+    ) -> io::Result<Self> {
+        Self::start(crate::game::tls_client_stream(addr, config, server_name)?)
+    }
+}
+
+#[cfg(feature = "noise")]
+impl Spectator<crate::noise::NoiseStream<TcpStream>> {
+    /// Same as [`Spectator::connect`], over a connection encrypted with Noise (see
+    /// [`noise`](crate::noise)).
+    pub fn connect_noise<A: ToSocketAddrs>(
+        addr: A,
+        config: &crate::noise::NoiseConfig,
+    ) -> io::Result<Self> {
+        Self::start(crate::game::noise_client_stream(addr, config)?)
+    }
+}
+
+impl<S: GameStream> Spectator<S> {
+    fn start(mut stream: S) -> io::Result<Self> {
+        stream.write_all(&SpectatorHello.to_bytes())?;
+        stream.flush()?;
+        Ok(Self {
+            stream: BufReader::new(stream),
+            grid: Grid::default(),
+            turn: None,
+            result: None,
+        })
+    }
+
+    /// Returns the grid with every move received so far.
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Returns the mark playing next, or `None` until the host sent the state of the game.
+    pub fn turn(&self) -> Option<Mark> {
+        self.turn
+    }
+
+    /// Returns the result of the game, once the host sent it.
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// Waits for the next move, and applies it to the grid. The end of the game is only recorded,
+    /// see [`Spectator::next_event`] to be notified of it. Returns `None` once the host closed the
+    /// connection, which happens when its game is dropped.
+    pub fn next_move(&mut self) -> io::Result<Option<PlayedMove>> {
+        loop {
+            match self.next_event()? {
+                Some(SpectatorEvent::Move(played)) => return Ok(Some(played)),
+                Some(SpectatorEvent::GameOver(_)) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Waits for the next move or the end of the game, and applies it. The state of the game sent
+    /// by the host before it replaces the grid, and clears the result of a previous game.
+    /// Returns `None` once the host closed the connection.
+    pub fn next_event(&mut self) -> io::Result<Option<SpectatorEvent>> {
+        let pkt = loop {
+            let mut buf = vec![];
+            if self.stream.read_until(protocol::TERMINATOR, &mut buf)? == 0 {
+                return Ok(None);
+            }
+            match game::parse_packet(&buf)? {
+                Packet::BoardSync(sync) => {
+                    self.grid = sync.grid;
+                    self.turn = Some(sync.turn);
+                    self.result = None;
+                }
+                Packet::MoveBroadcast(pkt) => break pkt,
+                Packet::EndOfGame(EndOfGame(result)) => {
+                    self.result = Some(result);
+                    return Ok(Some(SpectatorEvent::GameOver(result)));
+                }
+                pkt => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Unexpected packet from host: {:?}", pkt),
+                    ))
+                }
+            }
+        };
+        self.grid
+            .try_set_cell(pkt.row, pkt.col, pkt.mark)
+            .map_err(|e: GridPlacementError| io::Error::new(ErrorKind::InvalidData, e))?;
+        self.turn = Some(pkt.mark.opposite());
+
+        Ok(Some(SpectatorEvent::Move(PlayedMove {
+            mark: pkt.mark,
+            player_move: Move::Place(pkt.row, pkt.col),
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{
+        game::{NetworkedGame, RemoteGame, ServerGame, WinReason},
+        grid::Mark,
+        player::tests::{shared, MockPlayer, ResigningPlayer},
+    };
+
+    use super::*;
+
+    #[test]
+    fn spectators_receive_every_move() {
+        let server = ServerGame::bind("127.0.0.1:0", &Default::default()).unwrap();
+        let addr = server.local_addr().unwrap();
+        let early = Spectator::connect(addr).expect("Error connecting spectator");
+        let host = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut host = host
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        host.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        let late = Spectator::connect(addr).expect("Error connecting spectator");
+        client.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        host.try_move(&shared(MockPlayer(0, 0))).unwrap();
+        drop(host);
+
+        // The late spectator is only accepted after the next move, and receives it in the state of
+        // the game
+        for (mut spectator, expected_moves) in [(early, 2), (late, 0)] {
+            let mut moves = vec![];
+            while let Some(played) = spectator.next_move().expect("Error receiving move") {
+                moves.push(played);
+            }
+            assert_eq!(spectator.grid(), client.grid());
+            assert_eq!(spectator.turn(), Some(Mark::X));
+            assert_eq!(moves.len(), expected_moves);
+        }
+    }
+
+    #[test]
+    fn spectators_are_told_the_result() {
+        let server = ServerGame::bind("127.0.0.1:0", &Default::default()).unwrap();
+        let addr = server.local_addr().unwrap();
+        let mut spectator = Spectator::connect(addr).expect("Error connecting spectator");
+        let host = thread::spawn(move || server.listen());
+        let mut client = RemoteGame::connect(addr).expect("Error connecting to server");
+        let mut host = host
+            .join()
+            .unwrap()
+            .expect("Error listening to connections");
+
+        host.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(MockPlayer(1, 1))).unwrap();
+        client.try_move(&shared(ResigningPlayer)).unwrap();
+        host.try_move(&shared(ResigningPlayer)).unwrap();
+
+        let result = GameResult::Win {
+            winner: Mark::X,
+            reason: WinReason::Resignation,
+        };
+        assert_eq!(host.result(), Some(result));
+        assert!(matches!(
+            spectator.next_event().unwrap(),
+            Some(SpectatorEvent::Move(_))
+        ));
+        assert_eq!(
+            spectator.next_event().unwrap(),
+            Some(SpectatorEvent::GameOver(result))
+        );
+        assert_eq!(spectator.result(), Some(result));
+    }
+}
@@ -0,0 +1,133 @@
+//! Characters marks are drawn with, which can be replaced by other characters or emoji. Cells are
+//! widened to fit the widest symbol, so the grid lines still align.
+
+use tictactoe::{board::Board, grid::Mark};
+use unicode_width::UnicodeWidthStr;
+
+/// Symbols of each mark on the board.
+#[derive(Debug, Clone)]
+pub struct Symbols {
+    x: String,
+    o: String,
+    y: String,
+    /// Columns taken by the widest symbol
+    width: usize,
+}
+
+impl Symbols {
+    /// Uses `x` and `o` for the marks of X and O. Symbols must be 1 or 2 columns wide, and can't be
+    /// digits, spaces or characters of the grid lines.
+    pub fn new(x: impl Into<String>, o: impl Into<String>) -> Result<Self, String> {
+        let (x, o, y) = (x.into(), o.into(), Mark::Y.to_string());
+        for symbol in [&x, &o] {
+            validate(symbol)?;
+        }
+        if x == o || x == y || o == y {
+            return Err("Mark symbols must all be different".to_owned());
+        }
+
+        let width = [&x, &o, &y].iter().map(|s| s.width()).max().unwrap_or(1);
+        Ok(Self { x, o, y, width })
+    }
+
+    /// Returns the symbol of `mark`.
+    pub fn get(&self, mark: Mark) -> &str {
+        match mark {
+            Mark::X => &self.x,
+            Mark::O => &self.o,
+            Mark::Y => &self.y,
+        }
+    }
+
+    /// Returns the columns taken by a cell's symbol, the same for every mark.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the symbol of `mark`, or spaces if `None`, padded to [`Self::width`].
+    pub fn cell(&self, mark: Option<Mark>) -> String {
+        let symbol = mark.map_or("", |mark| self.get(mark));
+        format!("{}{}", symbol, " ".repeat(self.width() - symbol.width()))
+    }
+
+    /// Draws `board` like its `Display` implementation, with these symbols. Box drawing
+    /// characters are used if `unicode` is on, and marks are colored if `color` is on.
+    pub fn draw(&self, board: &impl Board, unicode: bool, color: bool) -> String {
+        let (rows, cols) = board.dimensions();
+        let dashes = if unicode { "\u{2500}" } else { "-" }.repeat(self.width + 2);
+        let line = |left: &str, middle: &str, right: &str| {
+            if unicode {
+                format!(
+                    " {left}{}{dashes}{right}\n",
+                    format!("{dashes}{middle}").repeat(cols - 1)
+                )
+            } else {
+                format!("-{}\n", "-".repeat((self.width + 3) * cols))
+            }
+        };
+        let separator = if unicode { "\u{2502}" } else { "|" };
+        let indent = if unicode { " " } else { "" };
+
+        let mut out = line("\u{250C}", "\u{252C}", "\u{2510}");
+        for row in 0..rows {
+            out.push_str(indent);
+            out.push_str(separator);
+            for col in 0..cols {
+                let mark = board.mark(row, col);
+                let cell = match mark {
+                    Some(mark) if color => paint(mark, &self.cell(Some(mark))),
+                    _ => self.cell(mark),
+                };
+                out.push_str(&format!(" {cell} {separator}"));
+            }
+            out.push('\n');
+            if row == rows - 1 {
+                out.push_str(&line("\u{2514}", "\u{2534}", "\u{2518}"));
+            } else {
+                out.push_str(&line("\u{251C}", "\u{253C}", "\u{2524}"));
+            }
+        }
+        out
+    }
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Self {
+            x: Mark::X.to_string(),
+            o: Mark::O.to_string(),
+            y: Mark::Y.to_string(),
+            width: 1,
+        }
+    }
+}
+
+/// Wraps `text` in the ANSI color of `mark`: red for X, blue for O and green for Y.
+pub fn paint(mark: Mark, text: &str) -> String {
+    let color = match mark {
+        Mark::X => 31,
+        Mark::O => 34,
+        Mark::Y => 32,
+    };
+    format!("\x1b[{color}m{text}\x1b[0m")
+}
+
+fn validate(symbol: &str) -> Result<(), String> {
+    if !(1..=2).contains(&symbol.width()) {
+        return Err(format!(
+            "Mark symbol \"{symbol}\" must be 1 or 2 columns wide"
+        ));
+    }
+    let invalid = |c: char| {
+        c.is_whitespace()
+            || c.is_control()
+            || c.is_ascii_digit()
+            || matches!(c, '-' | '|' | '\u{2500}'..='\u{257F}')
+    };
+    if symbol.chars().any(invalid) {
+        return Err(format!(
+            "Mark symbol \"{symbol}\" can't contain spaces, digits or grid lines"
+        ));
+    }
+    Ok(())
+}
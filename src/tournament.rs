@@ -0,0 +1,408 @@
+//! Tournaments between players on the classic grid, to compare strategies. See [`Tournament`].
+
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    game::{Finished, Game, GameResult, MoveError},
+    grid::Mark,
+    player::Player,
+};
+
+pub(crate) type PlayerConstructor = Box<dyn Fn() -> Box<dyn Player> + Send + Sync>;
+
+/// How entrants are paired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TournamentFormat {
+    /// Every entrant plays `games` games against every other one.
+    RoundRobin { games: usize },
+    /// Entrants play matches of `games` games in pairs, in the order they were added, and the
+    /// winner of each match advances to the next round until one is left. The entrant added first
+    /// advances from a tied match, and the last entrant of an odd round advances without playing.
+    Knockout { games: usize },
+}
+
+/// Wins, losses and draws of an entrant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl Score {
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Returns 1 point per win and half a point per draw.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    pub(crate) fn add(&mut self, result: GameResult, mark: Mark) {
+        match result.winner() {
+            Some(winner) if winner == mark => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.draws += 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TournamentError {
+    /// A tournament needs at least 2 entrants.
+    NotEnoughPlayers,
+    /// A game couldn't be played to the end.
+    Game {
+        player_x: String,
+        player_o: String,
+        error: Box<MoveError>,
+    },
+}
+
+impl Display for TournamentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotEnoughPlayers => write!(f, "A tournament needs at least 2 players"),
+            Self::Game {
+                player_x,
+                player_o,
+                error,
+            } => write!(f, "Game {} vs {} failed: {}", player_x, player_o, error),
+        }
+    }
+}
+
+impl Error for TournamentError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::NotEnoughPlayers => None,
+            Self::Game { error, .. } => Some(error.as_ref()),
+        }
+    }
+}
+
+/// Tournament between named players, each game being played by new players made by their
+/// constructor. Entrants alternate playing X, who always moves first, in each pairing.
+///
+/// ```
+/// # use tictactoe::{player::BotPlayer, tournament::{Tournament, TournamentFormat}};
+/// let results = Tournament::new(TournamentFormat::RoundRobin { games: 2 })
+///     .with_player("easy", || Box::new(BotPlayer::easy()))
+///     .with_player("impossible", || Box::new(BotPlayer::impossible()))
+///     .run()
+///     .unwrap();
+/// assert_eq!(results.head_to_head("easy", "impossible").unwrap().wins, 0);
+/// println!("{}", results);
+/// ```
+pub struct Tournament {
+    format: TournamentFormat,
+    entrants: Vec<(String, PlayerConstructor)>,
+}
+
+impl std::fmt::Debug for Tournament {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tournament")
+            .field("format", &self.format)
+            .field(
+                "entrants",
+                &self
+                    .entrants
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl Tournament {
+    /// Creates a tournament without any entrant.
+    pub fn new(format: TournamentFormat) -> Self {
+        Self {
+            format,
+            entrants: vec![],
+        }
+    }
+
+    /// Adds an entrant called `name`, playing with the players made by `constructor`.
+    pub fn add_player(
+        &mut self,
+        name: impl Into<String>,
+        constructor: impl Fn() -> Box<dyn Player> + Send + Sync + 'static,
+    ) {
+        self.entrants.push((name.into(), Box::new(constructor)));
+    }
+
+    /// Same as [`Tournament::add_player`], returning the tournament.
+    pub fn with_player(
+        mut self,
+        name: impl Into<String>,
+        constructor: impl Fn() -> Box<dyn Player> + Send + Sync + 'static,
+    ) -> Self {
+        self.add_player(name, constructor);
+        self
+    }
+
+    /// Returns the names of the entrants, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entrants.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Plays every game of the tournament.
+    pub fn run(&self) -> Result<TournamentResults, TournamentError> {
+        self.run_with(|_, _, _| {})
+    }
+
+    /// Same as [`Tournament::run`], calling `on_game` with the names of the X and O entrants after
+    /// each game, to look into how it was played.
+    pub fn run_with(
+        &self,
+        mut on_game: impl FnMut(&str, &str, &Game<Finished>),
+    ) -> Result<TournamentResults, TournamentError> {
+        let count = self.entrants.len();
+        if count < 2 {
+            return Err(TournamentError::NotEnoughPlayers);
+        }
+        let mut results = TournamentResults {
+            names: self.entrants.iter().map(|(name, _)| name.clone()).collect(),
+            head_to_head: vec![vec![Score::default(); count]; count],
+            champion: None,
+        };
+
+        match self.format {
+            TournamentFormat::RoundRobin { games } => {
+                for a in 0..count {
+                    for b in a + 1..count {
+                        self.play_match(a, b, games, &mut results, &mut on_game)?;
+                    }
+                }
+            }
+            TournamentFormat::Knockout { games } => {
+                let mut remaining: Vec<usize> = (0..count).collect();
+                while remaining.len() > 1 {
+                    let mut next = vec![];
+                    for pair in remaining.chunks(2) {
+                        match *pair {
+                            [a, b] => {
+                                let score =
+                                    self.play_match(a, b, games, &mut results, &mut on_game)?;
+                                next.push(if score.wins >= score.losses { a } else { b });
+                            }
+                            _ => next.push(pair[0]),
+                        }
+                    }
+                    remaining = next;
+                }
+                results.champion = Some(remaining[0]);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Plays `games` games between entrants `a` and `b`, `a` playing X first, and returns the
+    /// score of `a` in the match.
+    fn play_match(
+        &self,
+        a: usize,
+        b: usize,
+        games: usize,
+        results: &mut TournamentResults,
+        on_game: &mut impl FnMut(&str, &str, &Game<Finished>),
+    ) -> Result<Score, TournamentError> {
+        let mut score = Score::default();
+        for game in 0..games {
+            let (x, o) = if game % 2 == 0 { (a, b) } else { (b, a) };
+            let game = self.play_game(x, o)?;
+            on_game(&self.entrants[x].0, &self.entrants[o].0, &game);
+            let result = game.result();
+            let a_mark = if x == a { Mark::X } else { Mark::O };
+            score.add(result, a_mark);
+            results.head_to_head[a][b].add(result, a_mark);
+            results.head_to_head[b][a].add(result, a_mark.opposite());
+        }
+        Ok(score)
+    }
+
+    fn play_game(&self, x: usize, o: usize) -> Result<Game<Finished>, TournamentError> {
+        let (name_x, player_x) = &self.entrants[x];
+        let (name_o, player_o) = &self.entrants[o];
+        let game = Game::builder()
+            .player_x(player_x())
+            .player_o(player_o())
+            .first_mark(Mark::X)
+            .build()
+            .expect("Both players are set");
+        game.play_to_end().map_err(|error| TournamentError::Game {
+            player_x: name_x.clone(),
+            player_o: name_o.clone(),
+            error: Box::new(error),
+        })
+    }
+}
+
+/// Scores of a finished [`Tournament`]. Its `Display` implementation writes the standings followed
+/// by the head-to-head table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentResults {
+    names: Vec<String>,
+    /// Score of each entrant against each other one, `head_to_head[a][b]` being from `a`'s side
+    head_to_head: Vec<Vec<Score>>,
+    champion: Option<usize>,
+}
+
+impl TournamentResults {
+    /// Returns the total score of each entrant, sorted by points, then wins, then the order
+    /// entrants were added in.
+    pub fn standings(&self) -> Vec<(&str, Score)> {
+        let mut standings: Vec<_> = self
+            .names
+            .iter()
+            .zip(&self.head_to_head)
+            .map(|(name, scores)| {
+                let total = scores.iter().fold(Score::default(), |total, score| Score {
+                    wins: total.wins + score.wins,
+                    losses: total.losses + score.losses,
+                    draws: total.draws + score.draws,
+                });
+                (name.as_str(), total)
+            })
+            .collect();
+        standings
+            .sort_by(|(_, a), (_, b)| b.points().total_cmp(&a.points()).then(b.wins.cmp(&a.wins)));
+        standings
+    }
+
+    /// Returns the score of `a` in their games against `b`, or `None` if either isn't an entrant.
+    pub fn head_to_head(&self, a: &str, b: &str) -> Option<Score> {
+        let index = |name| self.names.iter().position(|n| n == name);
+        Some(self.head_to_head[index(a)?][index(b)?])
+    }
+
+    /// Returns the winner of a knockout tournament, `None` for a round-robin.
+    pub fn champion(&self) -> Option<&str> {
+        self.champion.map(|i| self.names[i].as_str())
+    }
+}
+
+impl Display for TournamentResults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.names.iter().map(String::len).max().unwrap_or(0).max(6);
+        writeln!(
+            f,
+            "{:<width$}  Games    Won   Lost  Drawn  Points",
+            "Player"
+        )?;
+        for (name, score) in self.standings() {
+            writeln!(
+                f,
+                "{:<width$}  {:>5}  {:>5}  {:>5}  {:>5}  {:>6.1}",
+                name,
+                score.games(),
+                score.wins,
+                score.losses,
+                score.draws,
+                score.points()
+            )?;
+        }
+        if let Some(champion) = self.champion() {
+            writeln!(f, "\nChampion: {}", champion)?;
+        }
+
+        // Each row's wins-losses-draws against each column, whose players are numbered
+        writeln!(f, "\nHead to head (wins-losses-draws):")?;
+        write!(f, "{:<w$}", "", w = width + 4)?;
+        for i in 0..self.names.len() {
+            write!(f, "  {:>8}", i + 1)?;
+        }
+        writeln!(f)?;
+        for (i, (name, scores)) in self.names.iter().zip(&self.head_to_head).enumerate() {
+            write!(f, "{:>2}. {:<width$}", i + 1, name)?;
+            for (j, score) in scores.iter().enumerate() {
+                let cell = if i == j || score.games() == 0 {
+                    "-".to_owned()
+                } else {
+                    format!("{}-{}-{}", score.wins, score.losses, score.draws)
+                };
+                write!(f, "  {:>8}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::player::{
+        tests::{MockPlayer, ResigningPlayer},
+        BotPlayer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn round_robin_plays_every_pairing() {
+        let results = Tournament::new(TournamentFormat::RoundRobin { games: 2 })
+            .with_player("resigns", || Box::new(ResigningPlayer))
+            .with_player("minimax", || Box::new(BotPlayer::impossible()))
+            .with_player("also minimax", || Box::new(BotPlayer::impossible()))
+            .run()
+            .unwrap();
+
+        let standings = results.standings();
+        assert_eq!(standings.len(), 3);
+        assert_eq!(standings[2].0, "resigns");
+        assert_eq!(
+            standings[2].1,
+            Score {
+                wins: 0,
+                losses: 4,
+                draws: 0
+            }
+        );
+        assert_eq!(
+            results.head_to_head("minimax", "also minimax"),
+            Some(Score {
+                wins: 0,
+                losses: 0,
+                draws: 2
+            })
+        );
+        assert_eq!(results.head_to_head("minimax", "nobody"), None);
+        assert_eq!(results.champion(), None);
+        assert!(results.to_string().contains("0-0-2"));
+    }
+
+    #[test]
+    fn knockout_advances_match_winners() {
+        let results = Tournament::new(TournamentFormat::Knockout { games: 1 })
+            .with_player("a", || Box::new(ResigningPlayer))
+            .with_player("b", || Box::new(BotPlayer::impossible()))
+            .with_player("c", || Box::new(BotPlayer::impossible()))
+            .run()
+            .unwrap();
+
+        // b beats a, c advances without playing, then b plays X first against c and draws
+        assert_eq!(results.champion(), Some("b"));
+        assert_eq!(results.head_to_head("b", "a").unwrap().wins, 1);
+        assert_eq!(results.head_to_head("b", "c").unwrap().draws, 1);
+        assert_eq!(results.head_to_head("a", "c").unwrap().games(), 0);
+    }
+
+    #[test]
+    fn failing_games_are_reported() {
+        let tournament = Tournament::new(TournamentFormat::RoundRobin { games: 1 });
+        assert!(matches!(
+            tournament.run(),
+            Err(TournamentError::NotEnoughPlayers)
+        ));
+
+        // Both mock players keep playing the same cell
+        let result = tournament
+            .with_player("a", || Box::new(MockPlayer(0, 0)))
+            .with_player("b", || Box::new(MockPlayer(0, 0)))
+            .run();
+        assert!(matches!(result, Err(TournamentError::Game { .. })));
+    }
+}
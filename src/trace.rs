@@ -0,0 +1,54 @@
+//! Diagnostics of the networking code, reported through the `tracing` crate when the `tracing`
+//! feature is enabled. Without it, spans and events compile to nothing.
+//!
+//! Every networked game runs in a `connection` span, recording the local side's role and the
+//! remote side's address. Packets sent and received are reported as `TRACE` events, connections
+//! and games starting or ending as `INFO` events, and dropped connections or invalid packets as
+//! `WARN` events.
+
+use std::{marker::PhantomData, net::SocketAddr};
+
+/// Reports an event at the given level (`trace`, `debug`, `info`, `warn` or `error`), taking the
+/// same arguments as the `tracing` macro of that name.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    }};
+}
+pub(crate) use event;
+
+/// Span of a networked game's connection, entered by every operation on the game.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl ConnectionSpan {
+    /// `role` is the side of the game the local player is on, such as `host` or `client`.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(role: &'static str, peer: Option<SocketAddr>) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("connection", role, peer = ?peer),
+        }
+    }
+
+    /// Enters the span until the returned guard is dropped.
+    pub(crate) fn enter(&self) -> Entered {
+        Entered {
+            #[cfg(feature = "tracing")]
+            _entered: self.span.clone().entered(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Guard of an entered [`ConnectionSpan`]. It isn't `Send`, so the span is exited on the thread
+/// that entered it.
+pub(crate) struct Entered {
+    #[cfg(feature = "tracing")]
+    _entered: tracing::span::EnteredSpan,
+    _marker: PhantomData<*const ()>,
+}
@@ -0,0 +1,263 @@
+//! Training data for external models, generated by self-play of the perfect engine. See
+//! [`SelfPlay`] to generate samples and [`write_samples`] to export them as CSV or JSON lines.
+//!
+//! # Formats
+//! Each sample is a position with the mark about to play, the engine's best move and the outcome of
+//! the game for that mark: 1 for a win, 0 for a draw and -1 for a loss. Positions are written the
+//! way [`Grid`] is parsed, like `X.O/.X./...`, and cells are counted from 0.
+//!
+//! ```text
+//! position,mark,best_row,best_col,outcome
+//! X.O/.X./...,O,2,2,0
+//! ```
+//!
+//! ```text
+//! {"position":"X.O/.X./...","mark":"O","best_move":[2,2],"outcome":0}
+//! ```
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    board::Board,
+    grid::{Grid, Mark},
+    hint::Evaluation,
+    player::BotPlayer,
+};
+
+/// Position reached during a self-play game, see [`SelfPlay`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrainingSample {
+    pub grid: Grid,
+    /// Mark about to play
+    pub mark: Mark,
+    /// Move of the perfect engine, which may not be the one played
+    pub best_move: (usize, usize),
+    /// How the game ended for `mark`
+    pub outcome: Evaluation,
+}
+
+impl TrainingSample {
+    /// Returns the outcome as written in the exports: 1 for a win, 0 for a draw and -1 for a loss.
+    pub fn outcome_value(&self) -> i8 {
+        match self.outcome {
+            Evaluation::Win => 1,
+            Evaluation::Draw => 0,
+            Evaluation::Loss => -1,
+        }
+    }
+}
+
+/// Generates training samples from self-play games, see [`SelfPlay::samples`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfPlay {
+    games: usize,
+    exploration: u8,
+    seed: u64,
+}
+
+impl SelfPlay {
+    /// Plays `games` games, only with best moves until [`SelfPlay::with_exploration`] is set.
+    pub fn new(games: usize) -> Self {
+        Self {
+            games,
+            exploration: 0,
+            seed: 0,
+        }
+    }
+
+    /// Sets the percentage of moves played randomly instead of the best move, capped at 100.
+    /// Perfect play always reaches the same outcome, so random moves are needed to see wins and
+    /// losses.
+    pub fn with_exploration(mut self, exploration: u8) -> Self {
+        self.exploration = exploration.min(100);
+        self
+    }
+
+    /// Seeds the random moves, 0 by default. The same settings always generate the same samples.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns a sample for every position of every game, game by game. Games are generated as the
+    /// samples are read, so large numbers of them can be written without keeping them in memory.
+    pub fn samples(&self) -> impl Iterator<Item = TrainingSample> {
+        let exploration = self.exploration;
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        (0..self.games).flat_map(move |_| play_game(exploration, &mut rng))
+    }
+}
+
+/// Plays a game from the empty grid, X first, and returns its samples.
+fn play_game(exploration: u8, rng: &mut StdRng) -> Vec<TrainingSample> {
+    let mut grid = Grid::default();
+    let mut mark = Mark::X;
+    let mut positions = vec![];
+    while grid.winner().is_none() && !grid.is_full() {
+        let best_move = BotPlayer::minimax_move(&grid, &mark);
+        positions.push((grid, mark, best_move));
+        let (row, col) = if rng.gen_range(0..100) < exploration {
+            let moves = grid.legal_moves();
+            moves[rng.gen_range(0..moves.len())]
+        } else {
+            best_move
+        };
+        grid.set_cell(row, col, mark);
+        mark = mark.opposite();
+    }
+
+    let winner = grid.winner();
+    positions
+        .into_iter()
+        .map(|(grid, mark, best_move)| TrainingSample {
+            grid,
+            mark,
+            best_move,
+            outcome: match winner {
+                Some(winner) if winner == mark => Evaluation::Win,
+                Some(_) => Evaluation::Loss,
+                None => Evaluation::Draw,
+            },
+        })
+        .collect()
+}
+
+/// Format of exported samples, see the [module documentation](self).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values, with a header line
+    #[default]
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csv => write!(f, "csv"),
+            Self::JsonLines => write!(f, "jsonl"),
+        }
+    }
+}
+
+/// Parsed from `csv` or `jsonl`, ignoring case.
+impl FromStr for ExportFormat {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" => Ok(Self::JsonLines),
+            _ => Err(UnknownFormat(s.to_owned())),
+        }
+    }
+}
+
+/// Error parsing an [`ExportFormat`], holding the unknown format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFormat(pub String);
+
+impl Display for UnknownFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown format '{}', expected csv or jsonl", self.0)
+    }
+}
+
+impl Error for UnknownFormat {}
+
+/// Writes `samples` to `writer` in `format`, one per line, and returns how many were written.
+pub fn write_samples(
+    writer: &mut impl Write,
+    samples: impl IntoIterator<Item = TrainingSample>,
+    format: ExportFormat,
+) -> io::Result<usize> {
+    if format == ExportFormat::Csv {
+        writeln!(writer, "position,mark,best_row,best_col,outcome")?;
+    }
+    let mut written = 0;
+    for sample in samples {
+        let (row, col) = sample.best_move;
+        let position = sample.grid.notation();
+        let outcome = sample.outcome_value();
+        match format {
+            ExportFormat::Csv => writeln!(
+                writer,
+                "{},{},{},{},{}",
+                position, sample.mark, row, col, outcome
+            )?,
+            ExportFormat::JsonLines => writeln!(
+                writer,
+                r#"{{"position":"{}","mark":"{}","best_move":[{},{}],"outcome":{}}}"#,
+                position, sample.mark, row, col, outcome
+            )?,
+        }
+        written += 1;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hint;
+
+    use super::*;
+
+    #[test]
+    fn self_play_records_every_position() {
+        // Perfect play always draws, over the full 9 moves
+        let samples: Vec<_> = SelfPlay::new(3).samples().collect();
+        assert_eq!(samples.len(), 27);
+        assert!(samples.iter().all(|s| s.outcome == Evaluation::Draw));
+        assert_eq!(samples[0].grid, Grid::default());
+        assert_eq!(samples[1].mark, Mark::O);
+
+        // Random moves lead to wins and losses, but best moves stay the engine's
+        let self_play = SelfPlay::new(50).with_exploration(50).with_seed(1);
+        let samples: Vec<_> = self_play.samples().collect();
+        assert_eq!(samples, self_play.samples().collect::<Vec<_>>());
+        assert!(samples.iter().any(|s| s.outcome == Evaluation::Win));
+        assert!(samples.iter().any(|s| s.outcome == Evaluation::Loss));
+        for sample in samples.iter().step_by(7) {
+            let analysis = hint::analyze(&sample.grid, &sample.mark).unwrap();
+            assert!(analysis.best_moves.contains(&sample.best_move));
+        }
+    }
+
+    #[test]
+    fn samples_are_written_as_csv_or_json_lines() {
+        let sample = TrainingSample {
+            grid: "X.O/.X./...".parse().unwrap(),
+            mark: Mark::O,
+            best_move: (2, 2),
+            outcome: Evaluation::Draw,
+        };
+        let mut csv = vec![];
+        assert_eq!(
+            write_samples(&mut csv, [sample], ExportFormat::Csv).unwrap(),
+            1
+        );
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "position,mark,best_row,best_col,outcome\nX.O/.X./...,O,2,2,0\n"
+        );
+
+        let mut jsonl = vec![];
+        write_samples(&mut jsonl, [sample, sample], ExportFormat::JsonLines).unwrap();
+        let line = r#"{"position":"X.O/.X./...","mark":"O","best_move":[2,2],"outcome":0}"#;
+        assert_eq!(
+            String::from_utf8(jsonl).unwrap(),
+            format!("{line}\n{line}\n")
+        );
+
+        assert_eq!("JSONL".parse(), Ok(ExportFormat::JsonLines));
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
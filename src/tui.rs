@@ -0,0 +1,645 @@
+//! Full-screen terminal front-end, drawn with ratatui. Only built with the `tui` feature.
+//!
+//! The board is drawn in its own pane, and moves are chosen by moving a cursor with the arrow keys
+//! and pressing Enter, or by clicking a cell. Other panes show whose turn it is, the clocks, and
+//! the chat of networked games.
+
+use std::{
+    fmt::Debug,
+    io::{self, ErrorKind},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use ratatui::{
+    crossterm::{
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+            KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+        },
+        execute,
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+use tictactoe::{
+    game::{
+        Clock, DrawReason, Finished, Game, GameResult, InProgress, NetworkedGame,
+        NetworkedGameError, Turn, WinReason,
+    },
+    grid::{Grid, Mark},
+    player::{CellNumbering, Move, Player, PlayerError},
+};
+
+use crate::{
+    local_history::{self, Opponent},
+    symbols::Symbols,
+};
+
+/// How often the screen is redrawn while waiting for a key, so running clocks tick.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Full-screen UI, restoring the terminal when dropped.
+pub struct Tui {
+    terminal: DefaultTerminal,
+    grid: Grid,
+    cursor: (usize, usize),
+    /// Whether the local player is choosing a cell, which shows the cursor
+    selecting: bool,
+    status: String,
+    /// Clocks of a timed game, with the mark whose clock is running and since when
+    clock: Option<(Clock, Option<Mark>, Instant)>,
+    /// Messages of a networked game's chat, `None` in local games
+    chat: Option<Arc<Mutex<Vec<String>>>>,
+    /// Chat message being typed
+    message: Option<String>,
+    /// Chat messages typed during the local player's turn, sent once their move is played
+    outgoing: Vec<String>,
+    /// Numbering of the cells, which can be played by typing their number
+    numbering: CellNumbering,
+    /// Symbols the marks are drawn with
+    symbols: Symbols,
+    /// Where the board was last drawn, to find the cell under the mouse
+    board_area: Rect,
+}
+
+/// Shares the UI between the game loop and the players reading their moves from it.
+pub type SharedTui = Arc<Mutex<Tui>>;
+
+impl Tui {
+    /// Switches the terminal to the full-screen UI. `chat` adds the chat pane of networked games.
+    pub fn new(chat: bool, numbering: CellNumbering, symbols: Symbols) -> io::Result<SharedTui> {
+        let terminal = ratatui::try_init()?;
+        execute!(io::stdout(), EnableMouseCapture)?;
+        Ok(Arc::new(Mutex::new(Self {
+            terminal,
+            grid: Grid::default(),
+            cursor: (1, 1),
+            selecting: false,
+            status: String::new(),
+            clock: None,
+            chat: chat.then(Default::default),
+            message: None,
+            outgoing: vec![],
+            numbering,
+            symbols,
+            board_area: Rect::default(),
+        })))
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let Self {
+            terminal,
+            grid,
+            cursor,
+            selecting,
+            status,
+            clock,
+            chat,
+            message,
+            symbols,
+            board_area,
+            ..
+        } = self;
+        // Border + left grid line + each cell's symbol, padding and right grid line + border
+        let board_width = 3 * (symbols.width() as u16 + 3) + 3;
+        let chat = chat.as_ref().map(|chat| chat.lock().unwrap().clone());
+        terminal.draw(|frame| {
+            let [top, chat_area, help] = Layout::vertical([
+                Constraint::Length(9),
+                Constraint::Min(if chat.is_some() { 3 } else { 0 }),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+            let [board, status_area] =
+                Layout::horizontal([Constraint::Length(board_width), Constraint::Min(20)])
+                    .areas(top);
+
+            *board_area = board;
+            let cursor = selecting.then_some(*cursor);
+            frame.render_widget(
+                Paragraph::new(grid_lines(grid, cursor, symbols))
+                    .block(Block::bordered().title("Board")),
+                board,
+            );
+            draw_status(frame, status_area, status, clock.as_ref());
+            if let Some(chat) = &chat {
+                draw_chat(frame, chat_area, chat, message.as_deref());
+            }
+
+            let keys = match (message.is_some(), chat.is_some()) {
+                (true, _) => "Enter: send  Esc: cancel",
+                (false, true) => {
+                    "Arrows: move  Enter/1-9/click: play  t: chat  r: resign  Ctrl-C: quit"
+                }
+                (false, false) => {
+                    "Arrows: move  Enter/1-9/click: play  d: offer a draw  r: resign  Ctrl-C: quit"
+                }
+            };
+            frame.render_widget(
+                Line::from(keys).style(Style::new().add_modifier(Modifier::DIM)),
+                help,
+            );
+        })?;
+        Ok(())
+    }
+
+    /// Shows `grid` and `status`, and redraws the screen.
+    pub fn show(&mut self, grid: &Grid, status: impl Into<String>) -> io::Result<()> {
+        self.grid = *grid;
+        self.status = status.into();
+        self.draw()
+    }
+
+    /// Shows the time left on `clock`, counting down the clock of `running`.
+    pub fn set_clock(&mut self, clock: Option<Clock>, running: Option<Mark>) {
+        self.clock = clock.map(|clock| (clock, running, Instant::now()));
+    }
+
+    /// Returns the chat log of a networked game, for the chat handler to add messages to.
+    pub fn chat_log(&self) -> Option<Arc<Mutex<Vec<String>>>> {
+        self.chat.clone()
+    }
+
+    /// Returns the chat messages typed during the local player's turn, to send after their move.
+    pub fn take_outgoing(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Waits for the local player to choose a move with the cursor. `offer_draw` lets them offer a
+    /// draw.
+    fn choose_move(&mut self, offer_draw: bool) -> io::Result<Move> {
+        self.selecting = true;
+        let result = self.read_move(offer_draw);
+        self.selecting = false;
+        result
+    }
+
+    fn read_move(&mut self, offer_draw: bool) -> io::Result<Move> {
+        loop {
+            self.draw()?;
+            let key = match next_event()? {
+                Some(Event::Key(key)) => key,
+                Some(Event::Mouse(mouse)) if self.message.is_none() => {
+                    let Some(cell) = self.cell_at(mouse) else {
+                        continue;
+                    };
+                    // The cursor follows the mouse, highlighting the cell it would play
+                    self.cursor = cell;
+                    if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+                        if let Some(played) = self.play_cursor() {
+                            return Ok(played);
+                        }
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            if let Some(message) = &mut self.message {
+                match key.code {
+                    KeyCode::Enter => {
+                        let message = self.message.take().unwrap_or_default();
+                        if !message.is_empty() {
+                            self.outgoing.push(message);
+                        }
+                    }
+                    KeyCode::Esc => self.message = None,
+                    KeyCode::Backspace => {
+                        message.pop();
+                    }
+                    KeyCode::Char(c) => message.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+
+            let (row, col) = &mut self.cursor;
+            match key.code {
+                KeyCode::Up => *row = row.saturating_sub(1),
+                KeyCode::Down => *row = (*row + 1).min(2),
+                KeyCode::Left => *col = col.saturating_sub(1),
+                KeyCode::Right => *col = (*col + 1).min(2),
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    if let Some(played) = self.play_cursor() {
+                        return Ok(played);
+                    }
+                }
+                KeyCode::Char(c @ '1'..='9') => {
+                    let n = c.to_digit(10).unwrap_or_default() as usize;
+                    if let Some(cell) = self.numbering.cell(n, 3) {
+                        self.cursor = cell;
+                        if let Some(played) = self.play_cursor() {
+                            return Ok(played);
+                        }
+                    }
+                }
+                KeyCode::Char('t') if self.chat.is_some() => self.message = Some(String::new()),
+                KeyCode::Char('d') if offer_draw => return Ok(Move::OfferDraw),
+                KeyCode::Char('r') => {
+                    let status = std::mem::take(&mut self.status);
+                    if self.confirm("Resign the game? (y/n)")? {
+                        return Ok(Move::Resign);
+                    }
+                    self.status = status;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the `(row, col)` of the cell under the mouse, if any.
+    fn cell_at(&self, mouse: MouseEvent) -> Option<(usize, usize)> {
+        // Cells are a padded symbol with a separator on each side, and separated by a line, all
+        // inside the pane's border
+        let width = self.symbols.width() + 3;
+        let x = usize::from(mouse.column.checked_sub(self.board_area.x + 2)?);
+        let y = usize::from(mouse.row.checked_sub(self.board_area.y + 2)?);
+        let (row, col) = (y / 2, x / width);
+        (y % 2 == 0 && x % width != width - 1 && row < 3 && col < 3).then_some((row, col))
+    }
+
+    /// Plays the cell under the cursor if it is free.
+    fn play_cursor(&mut self) -> Option<Move> {
+        let (row, col) = self.cursor;
+        if self.grid.get_cell(row, col).is_empty() {
+            return Some(Move::Place(row, col));
+        }
+        self.status = "That cell is already taken.".to_owned();
+        None
+    }
+
+    /// Shows `question`, and waits for the player to answer it with y or n.
+    pub fn confirm(&mut self, question: impl Into<String>) -> io::Result<bool> {
+        self.status = question.into();
+        loop {
+            self.draw()?;
+            match next_key()?.map(|key| key.code) {
+                Some(KeyCode::Char('y' | 'Y')) => return Ok(true),
+                Some(KeyCode::Char('n' | 'N') | KeyCode::Esc) => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    /// Shows `status`, and waits for any key.
+    pub fn wait_key(&mut self, status: impl Into<String>) -> io::Result<()> {
+        self.status = status.into();
+        loop {
+            self.draw()?;
+            if next_key()?.is_some() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        ratatui::restore();
+    }
+}
+
+/// Human player choosing their moves in the UI.
+pub struct TuiPlayer {
+    tui: SharedTui,
+    /// Whether the player can offer draws, which networked games don't support
+    offer_draw: bool,
+}
+
+impl TuiPlayer {
+    pub fn new(tui: SharedTui, offer_draw: bool) -> Self {
+        Self { tui, offer_draw }
+    }
+}
+
+impl Debug for TuiPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TuiPlayer").finish_non_exhaustive()
+    }
+}
+
+impl Player for TuiPlayer {
+    fn get_move(&self, board: &Grid, mark: &Mark) -> Result<Move, PlayerError> {
+        let mut tui = self.tui.lock().unwrap();
+        tui.show(board, format!("{}'s turn", mark))?;
+        Ok(tui.choose_move(self.offer_draw)?)
+    }
+
+    fn accept_draw(&self, board: &Grid, mark: &Mark) -> Result<bool, PlayerError> {
+        let mut tui = self.tui.lock().unwrap();
+        tui.show(board, "")?;
+        Ok(tui.confirm(format!(
+            "{}'s opponent offers a draw. Accept it, {}? (y/n)",
+            mark, mark
+        ))?)
+    }
+}
+
+/// Plays a local game in the UI until it ends, and returns it for the caller to show its result.
+pub fn local_game_loop(
+    game: Game<InProgress, Grid>,
+    tui: &SharedTui,
+) -> Result<Game<Finished, Grid>, String> {
+    tui.lock()
+        .unwrap()
+        .show(game.board(), format!("{}'s turn", game.current_player()))
+        .map_err(|e| e.to_string())?;
+    let game = game
+        .play_to_end_with(|turn| {
+            let Turn::InProgress(game) = turn else {
+                return;
+            };
+            let status = match game.history().last() {
+                Some(m) if m.player_move == Move::OfferDraw => {
+                    format!("Draw offer declined. {}'s turn", game.current_player())
+                }
+                _ => format!("{}'s turn", game.current_player()),
+            };
+            let _ = tui.lock().unwrap().show(game.board(), status);
+        })
+        .map_err(|e| e.to_string())?;
+
+    let status = match game.result() {
+        GameResult::Win {
+            winner,
+            reason: WinReason::Resignation,
+        } => {
+            let resigned = game.history().last().map(|m| m.mark).unwrap();
+            format!("{} resigned. {} won the game!", resigned, winner)
+        }
+        GameResult::Win { winner, .. } => format!("{} won the game!", winner),
+        GameResult::Draw(DrawReason::Agreement) => "Players agreed to a draw.".to_owned(),
+        GameResult::Draw(DrawReason::BoardFull) => "Draw!".to_owned(),
+    };
+    tui.lock()
+        .unwrap()
+        .show(game.board(), status)
+        .map_err(|e| e.to_string())?;
+    Ok(game)
+}
+
+/// Plays a networked game in the UI, including rematches, until a player leaves.
+pub fn networked_game_loop(
+    game: &mut impl NetworkedGame,
+    tui: &SharedTui,
+) -> Result<(), NetworkedGameError> {
+    let opponent = game.peer().nickname;
+    if let Some(log) = tui.lock().unwrap().chat_log() {
+        let tui = Arc::clone(tui);
+        let name = opponent.clone().unwrap_or_else(|| "Opponent".to_owned());
+        game.set_chat_handler(Box::new(move |message| {
+            log.lock().unwrap().push(format!("{}: {}", name, message));
+            // Messages only arrive while waiting for the opponent, when the UI isn't locked
+            if let Ok(mut tui) = tui.try_lock() {
+                let _ = tui.draw();
+            }
+        }));
+    }
+    let player: Arc<dyn Player> = Arc::new(TuiPlayer::new(Arc::clone(tui), false));
+
+    loop {
+        let started_at = SystemTime::now();
+        let result = loop {
+            if let Some(result) = game.result() {
+                break result;
+            }
+
+            let local = game.is_local_turn();
+            {
+                let mut tui = tui.lock().unwrap();
+                let running = if local {
+                    game.local_mark()
+                } else {
+                    game.local_mark().opposite()
+                };
+                tui.set_clock(game.clock(), Some(running));
+                let status = if local {
+                    format!("Your turn, you play {}", game.local_mark())
+                } else {
+                    match &opponent {
+                        Some(nickname) => format!("Waiting for {}'s move...", nickname),
+                        None => "Waiting for your opponent's move...".to_owned(),
+                    }
+                };
+                tui.show(game.grid(), status)?;
+            }
+            let played = if local {
+                game.try_move(&player)
+            } else {
+                wait_for_remote_move(game, &player)
+            };
+            match played {
+                Ok(()) => {}
+                Err(NetworkedGameError::Rejected(e)) => {
+                    let status = format!("Your opponent rejected your last move ({})", e);
+                    tui.lock().unwrap().show(game.grid(), status)?;
+                }
+                Err(e) => return Err(e),
+            }
+
+            let (outgoing, log) = {
+                let mut tui = tui.lock().unwrap();
+                (tui.take_outgoing(), tui.chat_log())
+            };
+            for message in outgoing {
+                game.send_chat(&message)?;
+                if let Some(log) = &log {
+                    log.lock().unwrap().push(format!("You: {}", message));
+                }
+            }
+        };
+
+        let local = game.local_mark();
+        let recorded = local_history::record(local, Opponent::Remote, result, &[], started_at);
+        let status = match result {
+            GameResult::Win {
+                winner,
+                reason: WinReason::Resignation,
+            } if winner == local => "Your opponent resigned. You won the game!",
+            GameResult::Win {
+                reason: WinReason::Resignation,
+                ..
+            } => "You resigned.",
+            GameResult::Win {
+                winner,
+                reason: WinReason::Timeout,
+            } if winner == local => "Your opponent ran out of time. You won the game!",
+            GameResult::Win {
+                reason: WinReason::Timeout,
+                ..
+            } => "You ran out of time, and lost the game.",
+            GameResult::Win { winner, .. } if winner == local => "You won the game!",
+            GameResult::Win { .. } => "Your opponent won the game.",
+            GameResult::Draw(_) => "Draw!",
+        };
+        let status = match recorded {
+            Ok(()) => status.to_owned(),
+            Err(e) => format!("{} Couldn't add the game to the history: {}.", status, e),
+        };
+        let accept = {
+            let mut tui = tui.lock().unwrap();
+            tui.set_clock(game.clock(), None);
+            tui.show(game.grid(), status.clone())?;
+            game.with_heartbeat(|| tui.confirm(format!("{} Play a rematch? (y/n)", status)))?
+        };
+        if accept {
+            tui.lock()
+                .unwrap()
+                .show(game.grid(), "Waiting for your opponent's answer...")?;
+        }
+        if !game.rematch(accept)? {
+            if accept {
+                tui.lock()
+                    .unwrap()
+                    .wait_key("Your opponent declined the rematch. Press any key to exit.")?;
+            }
+            return Ok(());
+        }
+    }
+}
+
+/// Plays the remote player's turn, cancelling it if Ctrl-C is pressed meanwhile so the game can be
+/// quit. Other keys are ignored, since nothing can be played until the move arrives.
+fn wait_for_remote_move(
+    game: &mut impl NetworkedGame,
+    player: &Arc<dyn Player>,
+) -> Result<(), NetworkedGameError> {
+    let canceller = game.canceller();
+    let waiting = AtomicBool::new(true);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            while waiting.load(Ordering::Relaxed) {
+                match next_key() {
+                    Err(e) if e.kind() == ErrorKind::Interrupted => return canceller.cancel(),
+                    Err(_) => return,
+                    Ok(_) => {}
+                }
+            }
+        });
+        let played = game.try_move(player);
+        waiting.store(false, Ordering::Relaxed);
+        played
+    })
+}
+
+/// Reads the next key press, mouse move or left click, or returns `None` once [`REDRAW_INTERVAL`]
+/// elapsed without one. Ctrl-C fails with [`ErrorKind::Interrupted`], since the terminal doesn't
+/// turn it into a signal while the UI is shown.
+fn next_event() -> io::Result<Option<Event>> {
+    if !event::poll(REDRAW_INTERVAL)? {
+        return Ok(None);
+    }
+    match event::read()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => {
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Err(io::Error::new(ErrorKind::Interrupted, "Game quit"));
+            }
+            Ok(Some(Event::Key(key)))
+        }
+        event @ Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved | MouseEventKind::Down(MouseButton::Left),
+            ..
+        }) => Ok(Some(event)),
+        _ => Ok(None),
+    }
+}
+
+/// Same as [`next_event`], ignoring the mouse.
+fn next_key() -> io::Result<Option<KeyEvent>> {
+    match next_event()? {
+        Some(Event::Key(key)) => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
+
+/// Draws the grid with box drawing characters, highlighting the cell under `cursor`.
+fn grid_lines(
+    grid: &Grid,
+    cursor: Option<(usize, usize)>,
+    symbols: &Symbols,
+) -> Vec<Line<'static>> {
+    let dashes = "─".repeat(symbols.width() + 2);
+    let border = |left: &str, middle: &str, right: &str| {
+        Line::from(format!(
+            "{left}{dashes}{middle}{dashes}{middle}{dashes}{right}"
+        ))
+    };
+    let mut lines = vec![border("┌", "┬", "┐")];
+    for row in 0..3 {
+        let mut spans = vec![Span::raw("│")];
+        for col in 0..3 {
+            let mark = grid.get_cell(row, col).try_get_mark().copied();
+            let cell = Span::raw(format!(" {} ", symbols.cell(mark)));
+            if cursor == Some((row, col)) {
+                spans.push(cell.style(Style::new().add_modifier(Modifier::REVERSED)));
+            } else {
+                spans.push(cell);
+            }
+            spans.push(Span::raw("│"));
+        }
+        lines.push(Line::from(spans));
+        if row < 2 {
+            lines.push(border("├", "┼", "┤"));
+        }
+    }
+    lines.push(border("└", "┴", "┘"));
+    lines
+}
+
+fn draw_status(
+    frame: &mut Frame,
+    area: Rect,
+    status: &str,
+    clock: Option<&(Clock, Option<Mark>, Instant)>,
+) {
+    let mut lines = vec![Line::from(status.to_owned())];
+    if let Some((clock, running, since)) = clock {
+        let remaining = |mark: Mark| {
+            let left = clock.remaining(mark);
+            if *running == Some(mark) {
+                left.saturating_sub(since.elapsed())
+            } else {
+                left
+            }
+        };
+        lines.push(Line::default());
+        for mark in [Mark::X, Mark::O] {
+            let left = remaining(mark).as_secs();
+            lines.push(Line::from(format!(
+                "{} {:02}:{:02}",
+                mark,
+                left / 60,
+                left % 60
+            )));
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title("Status")),
+        area,
+    );
+}
+
+fn draw_chat(frame: &mut Frame, area: Rect, chat: &[String], message: Option<&str>) {
+    let mut lines: Vec<Line> = chat.iter().map(|m| Line::from(m.as_str())).collect();
+    if let Some(message) = message {
+        lines.push(Line::from(format!("> {}", message)));
+    }
+    // Keep the last messages in view
+    let height = usize::from(area.height.saturating_sub(2));
+    let skip = lines.len().saturating_sub(height);
+    frame.render_widget(
+        Paragraph::new(lines.split_off(skip)).block(Block::bordered().title("Chat")),
+        area,
+    );
+}
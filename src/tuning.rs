@@ -0,0 +1,471 @@
+//! Bot choosing its moves with a weighted heuristic, and an offline tuner finding good weights by
+//! self-play, see [`TunedBot`] and [`Tuner`].
+//!
+//! Weights are saved as text, one `name = value` line per weight, so tuned weights can be kept in a
+//! file and loaded by later runs:
+//!
+//! ```
+//! # use tictactoe::tuning::HeuristicWeights;
+//! let weights = HeuristicWeights::default();
+//! let saved = weights.to_string();
+//! assert_eq!(saved.parse::<HeuristicWeights>().unwrap(), weights);
+//! ```
+
+use std::{
+    error::Error,
+    fmt::Display,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    board::Board,
+    grid::Mark,
+    player::{BotPlayer, Move, Player, PlayerError},
+    tournament::{Tournament, TournamentError, TournamentFormat},
+};
+
+/// Weights of the features of a move in the heuristic of a [`TunedBot`]. A move scores the sum of
+/// the weights of its features.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeuristicWeights {
+    /// Playing the center cell
+    pub center: f64,
+    /// Playing a corner
+    pub corner: f64,
+    /// Playing on an edge, away from the corners
+    pub edge: f64,
+    /// Each line the move leaves one mark away from completion
+    pub threat: f64,
+    /// Leaving at least two lines one mark away from completion, which can't all be blocked
+    pub fork: f64,
+    /// Taking a cell an opponent would fork on
+    pub block_fork: f64,
+}
+
+/// Hand-picked weights, playing the center, then forks and corners.
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        Self {
+            center: 3.0,
+            corner: 2.0,
+            edge: 0.0,
+            threat: 1.0,
+            fork: 5.0,
+            block_fork: 4.0,
+        }
+    }
+}
+
+impl HeuristicWeights {
+    /// Names of the weights, as written by the `Display` implementation.
+    pub const NAMES: [&'static str; 6] =
+        ["center", "corner", "edge", "threat", "fork", "block_fork"];
+
+    fn values(&self) -> [f64; 6] {
+        [
+            self.center,
+            self.corner,
+            self.edge,
+            self.threat,
+            self.fork,
+            self.block_fork,
+        ]
+    }
+
+    fn from_values([center, corner, edge, threat, fork, block_fork]: [f64; 6]) -> Self {
+        Self {
+            center,
+            corner,
+            edge,
+            threat,
+            fork,
+            block_fork,
+        }
+    }
+
+    /// Scores `mark` playing on `(row, col)` of `board`. Boards with an even number of rows or
+    /// columns don't have a center.
+    pub fn score<B: Board>(&self, board: &B, mark: &Mark, (row, col): (usize, usize)) -> f64 {
+        let (rows, cols) = board.dimensions();
+        let on_edge = |i: usize, len: usize| i == 0 || i == len - 1;
+        let threats = threats_after(board, *mark, (row, col));
+        let opponent_fork = opponents(mark).any(|m| threats_after(board, m, (row, col)) >= 2);
+
+        let mut score = threats as f64 * self.threat;
+        score += match (on_edge(row, rows), on_edge(col, cols)) {
+            (true, true) => self.corner,
+            (true, false) | (false, true) => self.edge,
+            _ if rows % 2 == 1 && cols % 2 == 1 && (row, col) == (rows / 2, cols / 2) => {
+                self.center
+            }
+            _ => 0.0,
+        };
+        if threats >= 2 {
+            score += self.fork;
+        }
+        if opponent_fork {
+            score += self.block_fork;
+        }
+        score
+    }
+}
+
+/// Marks `mark` can play against.
+fn opponents(mark: &Mark) -> impl Iterator<Item = Mark> + '_ {
+    Mark::TURN_ORDER.into_iter().filter(move |m| m != mark)
+}
+
+/// Returns the first cell completing a line of `mark`, if any.
+fn winning_move<B: Board>(board: &B, mark: Mark) -> Option<(usize, usize)> {
+    board.legal_moves().into_iter().find(|&(row, col)| {
+        let mut after = board.clone();
+        after.place(row, col, mark).is_ok() && after.winner() == Some(mark)
+    })
+}
+
+/// Number of cells where `mark` could complete a line after playing on `(row, col)`.
+fn threats_after<B: Board>(board: &B, mark: Mark, (row, col): (usize, usize)) -> usize {
+    let mut next = board.clone();
+    if next.place(row, col, mark).is_err() {
+        return 0;
+    }
+    next.legal_moves()
+        .into_iter()
+        .filter(|&(r, c)| {
+            let mut after = next.clone();
+            after.place(r, c, mark).is_ok() && after.winner() == Some(mark)
+        })
+        .count()
+}
+
+/// Written as one `name = value` line per weight.
+impl Display for HeuristicWeights {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in Self::NAMES.iter().zip(self.values()) {
+            writeln!(f, "{} = {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads weights written by the `Display` implementation. Empty lines and lines starting with `#`
+/// are ignored, and weights left out keep their default value.
+impl FromStr for HeuristicWeights {
+    type Err = WeightsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = Self::default().values();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| WeightsParseError::InvalidLine(line.to_owned()))?;
+            let index = Self::NAMES
+                .iter()
+                .position(|n| *n == name.trim())
+                .ok_or_else(|| WeightsParseError::UnknownWeight(name.trim().to_owned()))?;
+            values[index] = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|value: &f64| value.is_finite())
+                .ok_or_else(|| WeightsParseError::InvalidValue(line.to_owned()))?;
+        }
+        Ok(Self::from_values(values))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WeightsParseError {
+    /// A line isn't written as `name = value`
+    InvalidLine(String),
+    /// A weight isn't one of [`HeuristicWeights::NAMES`]
+    UnknownWeight(String),
+    /// A weight isn't a finite number
+    InvalidValue(String),
+}
+
+impl Display for WeightsParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLine(line) => write!(f, "Expected name = value, found \"{}\"", line),
+            Self::UnknownWeight(name) => write!(f, "Unknown weight \"{}\"", name),
+            Self::InvalidValue(line) => write!(f, "Invalid weight in \"{}\"", line),
+        }
+    }
+}
+
+impl Error for WeightsParseError {}
+
+/// Bot completing its lines and blocking its opponents' like every bot, and otherwise playing the
+/// move scoring the most with its [`HeuristicWeights`], breaking ties randomly. Plays on any
+/// board.
+#[derive(Debug)]
+pub struct TunedBot {
+    weights: HeuristicWeights,
+    rng: Mutex<StdRng>,
+}
+
+impl TunedBot {
+    pub fn new(weights: HeuristicWeights) -> Self {
+        Self {
+            weights,
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Breaks ties with a generator seeded with `seed`, like [`BotPlayer::with_seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    pub fn weights(&self) -> &HeuristicWeights {
+        &self.weights
+    }
+}
+
+impl<B: Board> Player<B> for TunedBot {
+    fn get_move(&self, board: &B, mark: &Mark) -> Result<Move, PlayerError> {
+        let forced = winning_move(board, *mark)
+            .or_else(|| opponents(mark).find_map(|m| winning_move(board, m)));
+        if let Some((row, col)) = forced {
+            return Ok(Move::Place(row, col));
+        }
+
+        let moves = board.legal_moves();
+        let scores: Vec<_> = moves
+            .iter()
+            .map(|&cell| self.weights.score(board, mark, cell))
+            .collect();
+        let best = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let favorites: Vec<_> = moves
+            .iter()
+            .zip(scores)
+            .filter(|&(_, score)| score == best)
+            .map(|(&cell, _)| cell)
+            .collect();
+        let (row, col) = *favorites
+            .choose(&mut *self.rng.lock().unwrap())
+            .ok_or(PlayerError::EndOfInput)?;
+        Ok(Move::Place(row, col))
+    }
+}
+
+/// Tunes [`HeuristicWeights`] by self-play, see [`Tuner::tune`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuner {
+    generations: usize,
+    candidates: usize,
+    games: usize,
+    step: f64,
+    seed: u64,
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Self {
+            generations: 20,
+            candidates: 4,
+            games: 10,
+            step: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+impl Tuner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many rounds of candidates are tried, 20 by default.
+    pub fn with_generations(mut self, generations: usize) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// Sets how many candidates are tried each generation, 4 by default.
+    pub fn with_candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Sets how many games each pair of entrants plays each generation, 10 by default.
+    pub fn with_games(mut self, games: usize) -> Self {
+        self.games = games;
+        self
+    }
+
+    /// Sets how far each weight of a candidate can be from the best weights so far, 1 by default.
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Seeds the candidates and the bots' random choices, 0 by default. The same tuner started
+    /// from the same weights always finds the same weights.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Tunes weights starting from `start`, see [`Tuner::tune_with`].
+    pub fn tune(&self, start: HeuristicWeights) -> Result<HeuristicWeights, TournamentError> {
+        self.tune_with(start, |_, _, _| {})
+    }
+
+    /// Tunes weights starting from `start`. Each generation, candidates are made by moving every
+    /// weight of the best weights so far by up to the step, and play a round-robin tournament
+    /// with them and with `Normal` and `Impossible` bots. The weights scoring the most points are
+    /// kept for the next generation.
+    ///
+    /// `on_generation` is called after each generation with its number, the weights kept and
+    /// their share of the points they could have made.
+    pub fn tune_with(
+        &self,
+        start: HeuristicWeights,
+        mut on_generation: impl FnMut(usize, &HeuristicWeights, f64),
+    ) -> Result<HeuristicWeights, TournamentError> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let seeds = Arc::new(AtomicU64::new(self.seed));
+        let mut best = start;
+        for generation in 1..=self.generations {
+            let mut entrants = vec![best];
+            for _ in 0..self.candidates {
+                let values = best
+                    .values()
+                    .map(|value| value + rng.gen_range(-self.step..=self.step));
+                entrants.push(HeuristicWeights::from_values(values));
+            }
+
+            let mut tournament =
+                Tournament::new(TournamentFormat::RoundRobin { games: self.games });
+            for (i, &weights) in entrants.iter().enumerate() {
+                let seeds = seeds.clone();
+                tournament.add_player(i.to_string(), move || {
+                    let seed = seeds.fetch_add(1, Ordering::Relaxed);
+                    Box::new(TunedBot::new(weights).with_seed(seed))
+                });
+            }
+            for (name, bot) in [
+                ("Normal", BotPlayer::normal()),
+                ("Impossible", BotPlayer::impossible()),
+            ] {
+                let seeds = seeds.clone();
+                tournament.add_player(name, move || {
+                    Box::new(bot.clone().with_seed(seeds.fetch_add(1, Ordering::Relaxed)))
+                });
+            }
+
+            let results = tournament.run()?;
+            // The best weights so far win ties, then the first candidates
+            let (index, score) = results
+                .standings()
+                .into_iter()
+                .filter_map(|(name, score)| Some((name.parse::<usize>().ok()?, score)))
+                .max_by(|(a, a_score), (b, b_score)| {
+                    a_score.points().total_cmp(&b_score.points()).then(b.cmp(a))
+                })
+                .expect("Candidates entered the tournament");
+            best = entrants[index];
+            on_generation(
+                generation,
+                &best,
+                score.points() / score.games().max(1) as f64,
+            );
+        }
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+
+    use super::*;
+
+    #[test]
+    fn weights_are_saved_as_text() {
+        let weights = HeuristicWeights {
+            edge: -1.5,
+            ..Default::default()
+        };
+        let saved = weights.to_string();
+        assert!(saved.contains("edge = -1.5\n"));
+        assert_eq!(saved.parse(), Ok(weights));
+        assert_eq!(
+            "# Only the center\ncenter = 1\n".parse::<HeuristicWeights>(),
+            Ok(HeuristicWeights {
+                center: 1.0,
+                ..Default::default()
+            })
+        );
+
+        assert!(matches!(
+            "middle = 2".parse::<HeuristicWeights>(),
+            Err(WeightsParseError::UnknownWeight(name)) if name == "middle"
+        ));
+        assert!(matches!(
+            "center = lots".parse::<HeuristicWeights>(),
+            Err(WeightsParseError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            "center".parse::<HeuristicWeights>(),
+            Err(WeightsParseError::InvalidLine(_))
+        ));
+    }
+
+    #[test]
+    fn tuned_bots_follow_their_weights() {
+        let grid = Grid::default();
+        let center = TunedBot::new(HeuristicWeights::default()).with_seed(0);
+        assert_eq!(center.get_move(&grid, &Mark::X).unwrap(), Move::Place(1, 1));
+        let edges = HeuristicWeights {
+            edge: 10.0,
+            ..Default::default()
+        };
+        let Move::Place(row, col) = TunedBot::new(edges).get_move(&grid, &Mark::X).unwrap() else {
+            panic!("Tuned bots only place marks");
+        };
+        assert!(row == 1 || col == 1);
+        assert_ne!((row, col), (1, 1));
+
+        // |X| |!|
+        // | |O| |
+        // | | |?|
+        // Both are corners, but only one threatens to complete the first row
+        let mut grid = Grid::default();
+        grid.set_cell(0, 0, Mark::X);
+        grid.set_cell(1, 1, Mark::O);
+        let weights = HeuristicWeights::default();
+        assert_eq!(weights.score(&grid, &Mark::X, (2, 2)), 2.0);
+        assert_eq!(weights.score(&grid, &Mark::X, (0, 2)), 3.0);
+    }
+
+    #[test]
+    fn tuning_is_reproducible() {
+        let tuner = Tuner::new()
+            .with_generations(2)
+            .with_candidates(2)
+            .with_games(2)
+            .with_seed(3);
+        let mut generations = vec![];
+        let weights = tuner
+            .tune_with(HeuristicWeights::default(), |generation, _, share| {
+                assert!((0.0..=1.0).contains(&share));
+                generations.push(generation);
+            })
+            .unwrap();
+        assert_eq!(generations, [1, 2]);
+        assert_eq!(tuner.tune(HeuristicWeights::default()).unwrap(), weights);
+    }
+}
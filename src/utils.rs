@@ -1,22 +1,44 @@
-use std::io::{self, BufRead, Write};
+use std::{
+    io::{self, BufRead, Write},
+    ops::RangeInclusive,
+};
 
 /// Reads one line from stdin, and returns the read value, or `default` if no value was entered.
 /// Prompt format: "{Prompt} ({Default}):
 pub fn read_string_default(prompt: impl AsRef<str>, default: impl ToString) -> String {
     let default = default.to_string();
+    let buf = read_string(format!("{} ({})", prompt.as_ref(), default));
+
+    if buf.is_empty() {
+        default
+    } else {
+        buf
+    }
+}
+
+/// Reads one line from stdin, and returns it without surrounding whitespace.
+/// Prompt format: "{Prompt}: "
+pub fn read_string(prompt: impl AsRef<str>) -> String {
     let mut stdin = io::stdin().lock();
     let mut buf = String::new();
 
-    print!("{} ({}): ", prompt.as_ref(), default);
+    print!("{}: ", prompt.as_ref());
     io::stdout().flush().unwrap();
     stdin.read_line(&mut buf).expect("Error reading from stdin");
-    let buf = buf.trim();
+    buf.trim().to_owned()
+}
 
-    if buf.is_empty() {
-        default
-    } else {
-        buf.to_owned()
-    }
+/// Prints `prompt` and waits for a line from stdin, usually an empty one from pressing Enter.
+/// Returns false if stdin ended instead.
+pub fn wait_for_enter(prompt: impl AsRef<str>) -> bool {
+    print!("{}", prompt.as_ref());
+    io::stdout().flush().unwrap();
+    let mut buf = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut buf)
+        .expect("Error reading from stdin")
+        > 0
 }
 
 /// Reads from stdin until we receive a boolean answer. Appends either `[Y/n]` or `[y/N]` to the
@@ -80,3 +102,37 @@ pub fn read_list(prompt: impl AsRef<str>, options: &[impl AsRef<str>]) -> usize
         }
     }
 }
+
+/// Reads from stdin until we receive a number within `range`, or nothing in which case `default`
+/// is returned. Prompt format: "{Prompt} ({min}-{max}, default {Default}): "
+pub fn read_number_default(
+    prompt: impl AsRef<str>,
+    range: RangeInclusive<usize>,
+    default: usize,
+) -> usize {
+    let mut stdin = io::stdin().lock();
+    let mut buffer = String::new();
+    loop {
+        print!(
+            "{} ({}-{}, default {}): ",
+            prompt.as_ref(),
+            range.start(),
+            range.end(),
+            default
+        );
+        io::stdout().flush().unwrap();
+        stdin
+            .read_line(&mut buffer)
+            .expect("Error reading from stdin");
+
+        match buffer.trim() {
+            "" => return default,
+            input => match input.parse::<usize>() {
+                Ok(i) if range.contains(&i) => return i,
+                Ok(_) => println!("Choice not within bounds."),
+                Err(_) => println!("Invalid value"),
+            },
+        }
+        buffer = String::new();
+    }
+}
@@ -0,0 +1,245 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    board::Board,
+    game::PlayedMove,
+    grid::{Grid, Mark},
+    player::{Move, Player, PlayerError},
+};
+
+/// Summary of a successful [`verify_never_loses`] run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Number of complete games played.
+    pub games: usize,
+    /// True if every opponent move sequence was tried before reaching the game limit.
+    pub exhaustive: bool,
+}
+
+/// Reason a player failed verification. Every variant but [`VerifyError::Player`] contains the
+/// mark the player had, and the moves played so far.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The player lost the game, by letting the opponent complete a line or by resigning.
+    Lost {
+        mark: Mark,
+        moves: Vec<PlayedMove>,
+    },
+    /// The player chose a move that can't be played, or offered a draw again after it was
+    /// declined.
+    InvalidMove {
+        mark: Mark,
+        moves: Vec<PlayedMove>,
+        player_move: Move,
+    },
+    Player(PlayerError),
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lost { mark, moves } => {
+                write!(f, "Player lost as {} after: {}", mark, format_moves(moves))
+            }
+            Self::InvalidMove {
+                mark,
+                moves,
+                player_move,
+            } => write!(
+                f,
+                "Player chose invalid move {:?} as {} after: {}",
+                player_move,
+                mark,
+                format_moves(moves)
+            ),
+            Self::Player(e) => write!(f, "Player failed to choose a move: {}", e),
+        }
+    }
+}
+
+impl Error for VerifyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Player(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<PlayerError> for VerifyError {
+    fn from(value: PlayerError) -> Self {
+        Self::Player(value)
+    }
+}
+
+fn format_moves(moves: &[PlayedMove]) -> String {
+    if moves.is_empty() {
+        return "no moves".to_owned();
+    }
+
+    moves
+        .iter()
+        .map(|played| match played.player_move {
+            Move::Place(row, col) => format!("{} ({}, {})", played.mark, row, col),
+            m => format!("{} {:?}", played.mark, m),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Plays `player` against every possible sequence of opponent moves, both as `X` and `O`, and
+/// fails with the first losing line found.
+///
+/// At most `games` games are played, in which case the check is only partial and the returned
+/// report isn't exhaustive. Players making random choices only get one sample per opponent
+/// sequence, so running the check several times gives more confidence.
+pub fn verify_never_loses(player: &dyn Player, games: usize) -> Result<VerifyReport, VerifyError> {
+    let mut verifier = Verifier {
+        player,
+        games_left: games,
+        moves: vec![],
+    };
+
+    let exhaustive = verifier.explore(Grid::default(), Mark::X, Mark::X)?
+        && verifier.explore(Grid::default(), Mark::X, Mark::O)?;
+    Ok(VerifyReport {
+        games: games - verifier.games_left,
+        exhaustive,
+    })
+}
+
+struct Verifier<'a> {
+    player: &'a dyn Player,
+    games_left: usize,
+    /// Moves leading to the position being explored.
+    moves: Vec<PlayedMove>,
+}
+
+impl Verifier<'_> {
+    /// Explores every game reachable from `grid`, where `to_move` plays next and the player plays
+    /// `mark`. Returns false if the game limit was reached before exploring everything.
+    fn explore(&mut self, grid: Grid, to_move: Mark, mark: Mark) -> Result<bool, VerifyError> {
+        if self.games_left == 0 {
+            return Ok(false);
+        }
+        if grid.winner() == Some(mark.opposite()) {
+            return Err(self.lost(mark));
+        }
+        if grid.winner().is_some() || grid.is_full() {
+            self.games_left -= 1;
+            return Ok(true);
+        }
+
+        if to_move == mark {
+            let (row, col) = self.player_move(&grid, mark)?;
+            return self.explore_move(grid, (row, col), to_move, mark);
+        }
+
+        for cell in grid.legal_moves() {
+            if !self.explore_move(grid, cell, to_move, mark)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn explore_move(
+        &mut self,
+        mut grid: Grid,
+        (row, col): (usize, usize),
+        to_move: Mark,
+        mark: Mark,
+    ) -> Result<bool, VerifyError> {
+        grid.set_cell(row, col, to_move);
+        self.moves.push(PlayedMove {
+            mark: to_move,
+            player_move: Move::Place(row, col),
+        });
+        let complete = self.explore(grid, to_move.opposite(), mark)?;
+        self.moves.pop();
+        Ok(complete)
+    }
+
+    /// Asks the player for their move, declining a single draw offer per turn like a game would.
+    fn player_move(&mut self, grid: &Grid, mark: Mark) -> Result<(usize, usize), VerifyError> {
+        let mut draw_offered = false;
+        loop {
+            let player_move = self.player.get_move(grid, &mark)?;
+            match player_move {
+                Move::Place(row, col) if grid.legal_moves().contains(&(row, col)) => {
+                    return Ok((row, col))
+                }
+                Move::OfferDraw if !draw_offered => draw_offered = true,
+                Move::Resign => {
+                    self.moves.push(PlayedMove { mark, player_move });
+                    return Err(self.lost(mark));
+                }
+                _ => {
+                    return Err(VerifyError::InvalidMove {
+                        mark,
+                        moves: self.moves.clone(),
+                        player_move,
+                    })
+                }
+            }
+        }
+    }
+
+    fn lost(&self, mark: Mark) -> VerifyError {
+        VerifyError::Lost {
+            mark,
+            moves: self.moves.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::player::{tests::MockPlayer, BotPlayer};
+
+    use super::*;
+
+    #[test]
+    fn impossible_bot_never_loses() {
+        let report = verify_never_loses(&BotPlayer::impossible(), usize::MAX).unwrap();
+        assert!(report.exhaustive);
+        assert!(report.games > 0);
+    }
+
+    #[test]
+    fn verify_reports_losing_line() {
+        let err = verify_never_loses(&BotPlayer::easy(), usize::MAX).unwrap_err();
+        let VerifyError::Lost { mark, moves } = err else {
+            panic!("Expected a losing line, got {:?}", err);
+        };
+
+        let mut grid = Grid::default();
+        for played in &moves {
+            let Move::Place(row, col) = played.player_move else {
+                panic!("Bot didn't place a mark");
+            };
+            grid.set_cell(row, col, played.mark);
+        }
+        assert_eq!(grid.winner(), Some(mark.opposite()));
+    }
+
+    #[test]
+    fn verify_stops_at_game_limit() {
+        let report = verify_never_loses(&BotPlayer::impossible(), 10).unwrap();
+        assert_eq!(report.games, 10);
+        assert!(!report.exhaustive);
+    }
+
+    #[test]
+    fn verify_reports_invalid_moves() {
+        let err = verify_never_loses(&MockPlayer(3, 3), usize::MAX).unwrap_err();
+        assert!(matches!(
+            err,
+            VerifyError::InvalidMove {
+                mark: Mark::X,
+                player_move: Move::Place(3, 3),
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+//! Bindings for browsers, built with `wasm-bindgen`. Only available with the `wasm` feature, see
+//! [`WasmGame`].
+
+use std::fmt::Display;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    game::{self, GameResult},
+    grid::{Grid, GridPlacementError, Mark},
+    player::{BotPlayer, BotPlayerDifficulty, Move, Player},
+};
+
+#[derive(Debug)]
+enum PlaceError {
+    GameOver,
+    IllegalMove(GridPlacementError),
+    UnknownDifficulty,
+}
+
+impl Display for PlaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GameOver => write!(f, "The game is already over"),
+            Self::IllegalMove(e) => write!(f, "{}", e),
+            Self::UnknownDifficulty => write!(f, "Unknown bot difficulty"),
+        }
+    }
+}
+
+impl From<GridPlacementError> for PlaceError {
+    fn from(value: GridPlacementError) -> Self {
+        Self::IllegalMove(value)
+    }
+}
+
+/// Local game on a [`Grid`], driven by the web page: moves are placed one at a time for the mark
+/// whose turn it is, and bots can be asked to play the current turn. `X` plays first.
+///
+/// Marks are exposed to JavaScript as the strings `"X"` and `"O"`.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct WasmGame {
+    grid: Grid,
+    turn: Mark,
+    result: Option<GameResult>,
+}
+
+impl Default for WasmGame {
+    fn default() -> Self {
+        Self {
+            grid: Grid::default(),
+            turn: Mark::X,
+            result: None,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Places the current mark on the cell, and hands the turn to the other mark.
+    pub fn place(&mut self, row: usize, col: usize) -> Result<(), JsError> {
+        self.try_place(row, col).map_err(to_js_error)
+    }
+
+    /// Lets a bot of the given difficulty (`"easy"`, `"normal"` or `"impossible"`) play the current
+    /// turn.
+    #[wasm_bindgen(js_name = playBot)]
+    pub fn play_bot(&mut self, difficulty: &str) -> Result<(), JsError> {
+        self.try_play_bot(difficulty).map_err(to_js_error)
+    }
+
+    /// Returns the mark on the cell, or `undefined` if it is empty.
+    pub fn cell(&self, row: usize, col: usize) -> Option<String> {
+        self.grid
+            .get_cell(row, col)
+            .try_get_mark()
+            .map(Mark::to_string)
+    }
+
+    /// Returns the mark playing the next move.
+    #[wasm_bindgen(js_name = currentMark)]
+    pub fn current_mark(&self) -> String {
+        self.turn.to_string()
+    }
+
+    #[wasm_bindgen(js_name = isOver)]
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Returns the winning mark, or `undefined` if the game is a draw or still in progress.
+    pub fn winner(&self) -> Option<String> {
+        self.result
+            .and_then(|result| result.winner())
+            .map(|mark| mark.to_string())
+    }
+
+    /// Returns the board drawn as text, like the CLI does.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_text(&self) -> String {
+        self.grid.to_string()
+    }
+}
+
+impl WasmGame {
+    fn try_place(&mut self, row: usize, col: usize) -> Result<(), PlaceError> {
+        if self.result.is_some() {
+            return Err(PlaceError::GameOver);
+        }
+
+        self.grid.try_set_cell(row, col, self.turn)?;
+        self.turn = self.turn.opposite();
+        self.result = game::board_result(&self.grid);
+        Ok(())
+    }
+
+    fn try_play_bot(&mut self, difficulty: &str) -> Result<(), PlaceError> {
+        let difficulty = match difficulty {
+            "easy" => BotPlayerDifficulty::Easy,
+            "normal" => BotPlayerDifficulty::Normal,
+            "impossible" => BotPlayerDifficulty::Impossible,
+            _ => return Err(PlaceError::UnknownDifficulty),
+        };
+        if self.result.is_some() {
+            return Err(PlaceError::GameOver);
+        }
+
+        let bot = BotPlayer::from_difficulty(difficulty);
+        match bot.get_move(&self.grid, &self.turn) {
+            Ok(Move::Place(row, col)) => self.try_place(row, col),
+            // Bots always place a mark on a game in progress
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn to_js_error(error: PlaceError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_game_alternates_marks() {
+        let mut game = WasmGame::new();
+        game.try_place(1, 1).unwrap();
+        assert_eq!(game.cell(1, 1).as_deref(), Some("X"));
+        assert_eq!(game.current_mark(), "O");
+        assert!(matches!(
+            game.try_place(1, 1),
+            Err(PlaceError::IllegalMove(GridPlacementError::CellInUse {
+                row: 1,
+                col: 1
+            }))
+        ));
+    }
+
+    #[test]
+    fn wasm_game_detects_end_of_game() {
+        let mut game = WasmGame::new();
+        for (row, col) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            game.try_place(row, col).unwrap();
+        }
+        game.try_play_bot("impossible").unwrap();
+
+        assert!(game.is_over());
+        assert_eq!(game.winner().as_deref(), Some("X"));
+        assert!(matches!(game.try_place(2, 2), Err(PlaceError::GameOver)));
+    }
+}
@@ -0,0 +1,160 @@
+//! Games over WebSocket connections, so browser front-ends and HTTP proxies can reach hosts. Only
+//! available with the `websocket` feature.
+//!
+//! Connections start with the WebSocket opening handshake, after which packets are sent as binary
+//! messages. Text messages are read as their UTF-8 bytes, and pings are answered automatically.
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use tungstenite::{
+    client::IntoClientRequest, handshake::HandshakeRole, protocol::WebSocket, HandshakeError,
+    Message,
+};
+
+use crate::game::GameStream;
+
+/// Connection speaking WebSocket, see the [module documentation](self).
+#[derive(Debug)]
+pub struct WebSocketStream<S> {
+    socket: WebSocket<S>,
+    /// Payload of the last message, not read yet
+    incoming: Vec<u8>,
+}
+
+impl<S: Read + Write> WebSocketStream<S> {
+    /// Performs the opening handshake as the client, requesting `url` (like `ws://host:port/`).
+    pub fn client(inner: S, url: &str) -> io::Result<Self> {
+        let request = url
+            .into_client_request()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+        let (socket, _) = tungstenite::client(request, inner).map_err(handshake_error)?;
+        Ok(Self::new(socket))
+    }
+
+    /// Performs the opening handshake as the host, accepting any requested path.
+    pub fn host(inner: S) -> io::Result<Self> {
+        let socket = tungstenite::accept(inner).map_err(handshake_error)?;
+        Ok(Self::new(socket))
+    }
+
+    fn new(socket: WebSocket<S>) -> Self {
+        Self {
+            socket,
+            incoming: vec![],
+        }
+    }
+}
+
+impl<S: Read + Write> Read for WebSocketStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.incoming.is_empty() {
+            match self.socket.read() {
+                Ok(Message::Binary(payload)) => self.incoming = payload.into(),
+                Ok(Message::Text(text)) => self.incoming = text.as_bytes().to_vec(),
+                Ok(Message::Close(_))
+                | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return Ok(0)
+                }
+                // Pings are answered by the socket
+                Ok(_) => {}
+                Err(e) => return Err(io_error(e)),
+            }
+        }
+
+        let len = buf.len().min(self.incoming.len());
+        buf[..len].copy_from_slice(&self.incoming[..len]);
+        self.incoming.drain(..len);
+        Ok(len)
+    }
+}
+
+impl<S: Read + Write> Write for WebSocketStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.socket
+            .send(Message::binary(buf.to_vec()))
+            .map_err(io_error)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.socket.flush().map_err(io_error)
+    }
+}
+
+impl<S: GameStream> GameStream for WebSocketStream<S> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.socket.get_ref().set_read_timeout(timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.socket.get_ref().set_write_timeout(timeout)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.get_ref().peer_addr()
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.socket.get_ref().shutdown()
+    }
+}
+
+fn io_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(e) => e,
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            ErrorKind::BrokenPipe.into()
+        }
+        e => io::Error::new(ErrorKind::InvalidData, e),
+    }
+}
+
+fn handshake_error<R: HandshakeRole>(error: HandshakeError<R>) -> io::Error {
+    match error {
+        HandshakeError::Failure(e) => io_error(e),
+        // Handshakes are only interrupted when a read timed out
+        HandshakeError::Interrupted(_) => ErrorKind::TimedOut.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        thread,
+    };
+
+    use crate::game::MemoryStream;
+
+    use super::*;
+
+    #[test]
+    fn packets_are_sent_as_messages() {
+        let (host, client) = MemoryStream::pair();
+        let host = thread::spawn(move || {
+            let mut stream = WebSocketStream::host(host).unwrap();
+            stream.write_all(b"hello\n").unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            line
+        });
+
+        let mut stream = WebSocketStream::client(client, "ws://localhost/").unwrap();
+        let mut buf = [0; 6];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello\n");
+        // Browsers may send text messages
+        stream.socket.send(Message::text("hi\n")).unwrap();
+        assert_eq!(host.join().unwrap(), "hi\n");
+        assert!(stream.peer_addr().is_err());
+
+        // The handshake fails if the other end is gone
+        let (host, client) = MemoryStream::pair();
+        drop(client);
+        assert!(WebSocketStream::host(host).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+"""Writes policy.onnx, the policy network of the ONNX bot tests, without any dependency.
+
+The network is a single Gemm layer from the 9 cells of a 3x3 board (1 for the bot's marks, -1 for
+its opponents' and 0 for empty cells) to a score for each cell. It prefers the center, then the
+corners, then the edges, and the cells next to its own marks.
+"""
+
+import struct
+
+
+def varint(n):
+    out = bytearray()
+    while True:
+        byte = n & 0x7F
+        n >>= 7
+        if n:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def field(number, wire_type, payload):
+    key = varint(number << 3 | wire_type)
+    if wire_type == 0:
+        return key + varint(payload)
+    return key + varint(len(payload)) + payload
+
+
+def string(number, s):
+    return field(number, 2, s.encode() if isinstance(s, str) else s)
+
+
+def tensor(name, dims, values):
+    floats = b"".join(struct.pack("<f", v) for v in values)
+    return (
+        b"".join(field(1, 0, d) for d in dims)
+        + field(2, 0, 1)  # FLOAT
+        + string(8, name)
+        + string(9, floats)  # raw_data
+    )
+
+
+def value_info(name, dims):
+    shape = b"".join(string(1, field(1, 0, d)) for d in dims)
+    tensor_type = field(1, 0, 1) + string(2, shape)
+    return string(1, name) + string(2, string(1, tensor_type))
+
+
+CELLS = 9
+bias = [0.5, 0.0, 0.5, 0.0, 1.0, 0.0, 0.5, 0.0, 0.5]
+# Each cell scores a little more for each of the bot's marks next to it
+weights = [0.0] * CELLS * CELLS
+for i in range(CELLS):
+    for j in range(CELLS):
+        (ri, ci), (rj, cj) = divmod(i, 3), divmod(j, 3)
+        if i != j and abs(ri - rj) <= 1 and abs(ci - cj) <= 1:
+            weights[i * CELLS + j] = 0.1
+
+node = (
+    string(1, "board")
+    + string(1, "weights")
+    + string(1, "bias")
+    + string(2, "policy")
+    + string(3, "dense")
+    + string(4, "Gemm")
+)
+graph = (
+    string(1, node)
+    + string(2, "policy")
+    + string(5, tensor("weights", [CELLS, CELLS], weights))
+    + string(5, tensor("bias", [CELLS], bias))
+    + string(11, value_info("board", [1, CELLS]))
+    + string(12, value_info("policy", [1, CELLS]))
+)
+model = (
+    field(1, 0, 7)  # IR version
+    + string(2, "tictactoe")
+    + string(7, graph)
+    + string(8, field(2, 0, 13))  # Opset 13
+)
+
+with open("policy.onnx", "wb") as f:
+    f.write(model)